@@ -1,15 +1,23 @@
 use std::{
     borrow::Cow,
     num::NonZero,
-    path::Path,
+    path::{
+        Path,
+        PathBuf,
+    },
     sync::Arc,
 };
 
 use cem_render::{
     RendererConfig,
     plugin::RenderPlugin,
-    texture::mipmap_cache::MipMapCache,
+    texture::{
+        mipmap_cache::MipMapCache,
+        table::TextureTable,
+    },
 };
+#[cfg(feature = "debug-server")]
+use cem_util::egui::EguiUtilContextExt;
 use cem_util::{
     egui::{
         RecentlyOpenedFiles,
@@ -32,9 +40,18 @@ use egui_wgpu::{
 };
 use image::RgbaImage;
 
+#[cfg(feature = "debug-server")]
+use crate::debug_server::{
+    DebugServer,
+    DebugTarget,
+};
 use crate::{
     args::Args,
     build_info::BUILD_INFO,
+    clipboard::{
+        CopyScreenRegion,
+        EguiClipboardPlugin,
+    },
     composer::{
         Composers,
         file_formats::FileFormat,
@@ -47,8 +64,23 @@ use crate::{
         show_error_dialog,
     },
     files::AppFiles,
+    jobs::Jobs,
     menubar::MenuBar,
-    solver::runner::SolverRunner,
+    recorder::{
+        Recorder,
+        Replay,
+    },
+    solver::{
+        convergence::ConvergenceStudy,
+        export::ExportScreenshot,
+        library::library_directory,
+        line_wizard::LineWizard,
+        runner::SolverRunner,
+        sequence::SequenceRun,
+        stream::RemoteMonitorWindow,
+        tuning::TuningAssistant,
+    },
+    status_bar::StatusBar,
 };
 
 #[derive(Clone, Debug)]
@@ -101,6 +133,7 @@ pub(super) fn run_app(args: Args) -> Result<(), Error> {
     else {
         app_files.read_config_or_create::<AppConfig>()?
     };
+    config.color_scheme.make_current();
 
     // these are more or less fixed
     let multisample_count = NonZero::new(4).unwrap(); // can really only be 1 or 4
@@ -191,6 +224,14 @@ pub(super) fn run_app(args: Args) -> Result<(), Error> {
                             required_features.insert(wgpu::Features::DEPTH32FLOAT_STENCIL8);
                         }
 
+                        // bindless textures, if available. otherwise the renderer falls back
+                        // to a bind group per textured object.
+                        TextureTable::request_features(
+                            adapter,
+                            &mut required_features,
+                            &mut required_limits,
+                        );
+
                         wgpu::DeviceDescriptor {
                             label: Some("egui wgpu device"),
                             required_limits,
@@ -244,7 +285,7 @@ pub(super) fn run_app(args: Args) -> Result<(), Error> {
             });
 
             // add our custom clipboard extension
-            //cc.egui_ctx.add_plugin(EguiClipboardPlugin);
+            cc.egui_ctx.add_plugin(EguiClipboardPlugin::default());
 
             // this is the egui-wgpu renderer, which we can use to create egui textures from
             // wgpu textures and vice versa. in case we ever need it
@@ -273,10 +314,22 @@ pub struct App {
     pub recently_opened_files: RecentlyOpenedFiles,
     pub file_dialog_state: FileDialogState,
     pub show_about: bool,
+    pub show_jobs: bool,
+    pub jobs: Jobs,
     pub solver_runner: SolverRunner,
     pub composers: Composers,
+    pub remote_monitor: RemoteMonitorWindow,
+    pub tuning_assistant: TuningAssistant,
+    pub sequence_run: SequenceRun,
+    pub line_wizard: LineWizard,
+    pub convergence_study: ConvergenceStudy,
+    pub status_bar: StatusBar,
+    pub recorder: Recorder,
+    pub replay: Option<Replay>,
     pub wgpu_context: WgpuContext,
     pub renderer_config: RendererConfig,
+    #[cfg(feature = "debug-server")]
+    pub debug_server: Option<DebugServer>,
 }
 
 impl App {
@@ -315,8 +368,14 @@ impl App {
             Err(error) => error_dialog.handle_error(error.into()),
         }
 
-        let mut composers = Composers::new(&context.egui_context, render_plugin);
-        let solver_runner = SolverRunner::from_app_context(&context);
+        let jobs = Jobs::new(&context.egui_context);
+        jobs.register_in_context(&context.egui_context);
+
+        let recorder = Recorder::default();
+        let mut composers =
+            Composers::new(&context.egui_context, render_plugin).with_recorder(recorder.clone());
+        let solver_runner =
+            SolverRunner::from_app_context(&context).with_recorder(recorder.clone());
 
         let recently_opened_files = RecentlyOpenedFiles::new(
             context.egui_context.clone(),
@@ -324,6 +383,18 @@ impl App {
             context.config.recently_opened_files_limit,
         );
 
+        // start recording before the file is opened, so that it's recorded
+        if let Some(path) = &context.args.record {
+            recorder
+                .start_recording(path)
+                .ok_or_handle(&mut error_dialog);
+        }
+        let replay = context
+            .args
+            .replay
+            .as_deref()
+            .and_then(|path| Replay::from_path(path).ok_or_handle(&mut error_dialog));
+
         if context.args.new_file {
             // command line telling us to directly go to a new file
             composers.new_file(&context.config);
@@ -338,6 +409,19 @@ impl App {
                 .ok_or_handle(&mut error_dialog);
         }
 
+        let mut remote_monitor = RemoteMonitorWindow::default();
+        if let Some(address) = context.args.monitor {
+            remote_monitor
+                .connect(&context.egui_context, address)
+                .ok_or_handle(&mut error_dialog);
+        }
+
+        #[cfg(feature = "debug-server")]
+        let debug_server = context.args.debug_server.and_then(|address| {
+            DebugServer::bind(address, context.egui_context.repaint_trigger())
+                .ok_or_handle(&mut error_dialog)
+        });
+
         error_dialog.register_in_context(&context.egui_context);
 
         Self {
@@ -346,17 +430,55 @@ impl App {
             recently_opened_files,
             file_dialog_state: Default::default(),
             show_about: false,
+            show_jobs: false,
+            jobs,
             solver_runner,
             composers,
+            remote_monitor,
+            tuning_assistant: Default::default(),
+            sequence_run: Default::default(),
+            line_wizard: Default::default(),
+            convergence_study: Default::default(),
+            status_bar: Default::default(),
+            recorder,
+            replay,
             wgpu_context: context.wgpu_context,
             renderer_config: context.renderer_config,
+            #[cfg(feature = "debug-server")]
+            debug_server,
         }
     }
 
-    fn save_screenshot(&self, image: &egui::ColorImage) -> Result<(), Error> {
-        let filename = format!("{}.png", Local::now().format("%Y-%m-%d_%H:%M:%S"));
+    /// Replays the actions of a recording that are due.
+    fn replay_actions(&mut self, ctx: &egui::Context) {
+        let Some(replay) = &mut self.replay
+        else {
+            return;
+        };
+
+        let actions = replay.take_due();
+        match replay.next_due_in() {
+            Some(next_due_in) => ctx.request_repaint_after(next_due_in),
+            None => {
+                tracing::info!("replay finished");
+                self.replay = None;
+            }
+        }
+
+        for action in actions {
+            action.replay(self).ok_or_handle(ctx);
+        }
+    }
 
-        let screenshot_path = self.app_files.screenshots_dir().join(&filename);
+    fn save_screenshot(
+        &self,
+        image: &egui::ColorImage,
+        path: Option<PathBuf>,
+    ) -> Result<(), Error> {
+        let screenshot_path = path.unwrap_or_else(|| {
+            let filename = format!("{}.png", Local::now().format("%Y-%m-%d_%H:%M:%S"));
+            self.app_files.screenshots_dir().join(&filename)
+        });
 
         let image = RgbaImage::from_raw(
             image.width() as u32,
@@ -390,10 +512,22 @@ impl eframe::App for App {
                         }
                         egui::Event::Screenshot {
                             viewport_id: _,
-                            user_data: _,
+                            user_data,
                             image,
                         } => {
-                            self.save_screenshot(image).ok_or_handle(ctx);
+                            // screenshots requested by a results export go into the export
+                            // directory. screenshots for the clipboard are handled by the
+                            // clipboard plugin.
+                            let data = user_data.data.as_ref();
+                            let is_for_clipboard = data.is_some_and(|data| {
+                                data.downcast_ref::<CopyScreenRegion>().is_some()
+                            });
+                            if !is_for_clipboard {
+                                let path = data
+                                    .and_then(|data| data.downcast_ref::<ExportScreenshot>())
+                                    .map(|export| export.path.clone());
+                                self.save_screenshot(image, path).ok_or_handle(ctx);
+                            }
                         }
                         _ => {}
                     }
@@ -405,6 +539,10 @@ impl eframe::App for App {
             }
         }
 
+        // runs started from now on are saved to the active project's library
+        self.solver_runner
+            .set_library_directory(self.composers.save_path().map(library_directory));
+
         egui::Panel::top("top_panel")
             .frame(
                 egui::Frame::new()
@@ -426,13 +564,47 @@ impl eframe::App for App {
                 });
             });
 
+        // requests from the debug server are handled before the composers update, so
+        // their changes show up in this frame.
+        #[cfg(feature = "debug-server")]
+        if let Some(debug_server) = &self.debug_server {
+            debug_server.handle_requests(DebugTarget {
+                composer: self.composers.active_scene_mut(),
+                solver_runner: &mut self.solver_runner,
+            });
+        }
+
+        self.replay_actions(ctx);
+
         // show solver ui window
+        self.solver_runner.update();
+        if let Some((scene, solver_configs)) = self.composers.active_scene_mut() {
+            self.solver_runner
+                .check_stale_results(&mut scene.world, solver_configs);
+        }
         self.solver_runner.show_active_solver_ui(ctx);
 
+        // the status bar has to be added before the composer's central panel
+        self.status_bar
+            .show(ctx, &mut self.composers, &self.solver_runner);
+
         self.composers.show(ctx);
 
+        self.tuning_assistant
+            .show(ctx, &mut self.composers, &mut self.solver_runner);
+        self.sequence_run
+            .show(ctx, &mut self.composers, &mut self.solver_runner);
+        self.line_wizard
+            .show(ctx, &mut self.composers, &mut self.solver_runner);
+        self.convergence_study
+            .show(ctx, &mut self.composers, &mut self.solver_runner);
+
+        self.remote_monitor.show(ctx).ok_or_handle(ctx);
+
         show_about_window(ctx, &mut self.show_about);
 
+        self.jobs.show_window(ctx, &mut self.show_jobs);
+
         self.show_debug_window(ctx);
 
         self.file_dialog_state.update(
@@ -1,4 +1,7 @@
-use std::path::PathBuf;
+use std::{
+    net::SocketAddr,
+    path::PathBuf,
+};
 
 #[derive(Clone, Debug, clap::Parser)]
 pub struct Args {
@@ -9,4 +12,45 @@ pub struct Args {
 
     #[clap(long)]
     pub ignore_config: bool,
+
+    /// Connect to a headless solver run at this address and show what it's
+    /// doing.
+    #[clap(long)]
+    pub monitor: Option<SocketAddr>,
+
+    /// Record what you do into this file, to reproduce bugs.
+    #[clap(long)]
+    pub record: Option<PathBuf>,
+
+    /// Replay actions that were recorded with `--record`.
+    #[clap(long)]
+    pub replay: Option<PathBuf>,
+
+    /// Listen for scripting requests on this address (e.g.
+    /// `127.0.0.1:7878`).
+    #[cfg(feature = "debug-server")]
+    #[clap(long)]
+    pub debug_server: Option<SocketAddr>,
+}
+
+#[derive(Clone, Debug, clap::Parser)]
+pub struct HeadlessArgs {
+    /// File to run. If omitted, the example scene is run.
+    pub file: Option<PathBuf>,
+
+    /// Index of the solver configuration in the file.
+    #[clap(long, default_value_t = 0)]
+    pub solver: usize,
+
+    /// Address on which monitors can connect to watch the run.
+    #[clap(long, default_value = "127.0.0.1:7879")]
+    pub stream: SocketAddr,
+
+    #[clap(long)]
+    pub ignore_config: bool,
+
+    /// TOML file with probes, observers and NF2FF boxes to add to the scene
+    /// for this run.
+    #[clap(long)]
+    pub extraction: Option<PathBuf>,
 }
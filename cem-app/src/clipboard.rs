@@ -9,13 +9,17 @@ use parking_lot::RwLock;
 ///
 /// Manages copying to and pasting from the clipboard. This contains a local
 /// buffer for objects that can't be copied outside the app (e.g. entities).
+/// Text and images are also copied to the OS clipboard.
 ///
 /// # TODO
 ///
-/// - Wire up with the OS clipboard
+/// - Paste from the OS clipboard
 #[derive(Clone, Debug, Default)]
 pub struct Clipboard {
     local_buffer: LocalBuffer,
+
+    /// Screen region to copy once the next frame was rendered
+    pending_screen_region: Option<CopyScreenRegion>,
 }
 
 impl Clipboard {
@@ -62,6 +66,10 @@ pub enum ClipboardItem {
     Text {
         text: String,
     },
+    Image {
+        #[debug(skip)]
+        image: Arc<egui::ColorImage>,
+    },
     Entities {
         // todo: bevy-migrate: clipboard
         //#[debug(skip)]
@@ -81,9 +89,35 @@ impl From<&str> for ClipboardItem {
     }
 }
 
+impl From<egui::ColorImage> for ClipboardItem {
+    fn from(value: egui::ColorImage) -> Self {
+        Self::Image {
+            image: Arc::new(value),
+        }
+    }
+}
+
+/// Attached to a screenshot request to mark it as a copy of a screen region.
+#[derive(Clone, Copy, Debug)]
+pub struct CopyScreenRegion {
+    pub rect: egui::Rect,
+    pub pixels_per_point: f32,
+}
+
 pub trait EguiClipboardExt {
     fn clipboard<R>(&self, f: impl FnOnce(&Clipboard) -> R) -> R;
     fn clipboard_mut<R>(&self, f: impl FnOnce(&mut Clipboard) -> R) -> R;
+
+    /// Copies an item to the local buffer, and text and images also to the OS
+    /// clipboard.
+    fn copy_item(&self, item: impl Into<ClipboardItem>);
+
+    /// Copies what is shown in `rect` as an image.
+    ///
+    /// This happens with a delay of a frame or two: We wait for the next frame
+    /// (so that e.g. the context menu that triggered this is closed), request a
+    /// screenshot and crop it once it arrives.
+    fn copy_screen_region(&self, rect: egui::Rect);
 }
 
 impl EguiClipboardExt for egui::Context {
@@ -94,6 +128,27 @@ impl EguiClipboardExt for egui::Context {
     fn clipboard_mut<R>(&self, f: impl FnOnce(&mut Clipboard) -> R) -> R {
         EguiClipboardData::write(self, f)
     }
+
+    fn copy_item(&self, item: impl Into<ClipboardItem>) {
+        let item = item.into();
+
+        match &item {
+            ClipboardItem::Text { text } => self.copy_text(text.clone()),
+            ClipboardItem::Image { image } => self.copy_image((**image).clone()),
+            ClipboardItem::Entities {} => {}
+        }
+
+        self.clipboard_mut(|clipboard| clipboard.push(item));
+    }
+
+    fn copy_screen_region(&self, rect: egui::Rect) {
+        let region = CopyScreenRegion {
+            rect,
+            pixels_per_point: self.pixels_per_point(),
+        };
+        self.clipboard_mut(|clipboard| clipboard.pending_screen_region = Some(region));
+        self.request_repaint();
+    }
 }
 
 impl EguiClipboardExt for egui::Ui {
@@ -104,6 +159,28 @@ impl EguiClipboardExt for egui::Ui {
     fn clipboard_mut<R>(&self, f: impl FnOnce(&mut Clipboard) -> R) -> R {
         EguiClipboardData::write(self.ctx(), f)
     }
+
+    fn copy_item(&self, item: impl Into<ClipboardItem>) {
+        self.ctx().copy_item(item);
+    }
+
+    fn copy_screen_region(&self, rect: egui::Rect) {
+        self.ctx().copy_screen_region(rect);
+    }
+}
+
+/// Shows a context menu on `response` to copy the widget as image, or the
+/// data shown in it as CSV.
+pub fn copy_image_or_csv_context_menu(response: &egui::Response, to_csv: impl FnOnce() -> String) {
+    response.context_menu(|ui| {
+        if ui.button("Copy Image").clicked() {
+            ui.copy_screen_region(response.rect);
+        }
+
+        if ui.button("Copy Data as CSV").clicked() {
+            ui.copy_item(to_csv());
+        }
+    });
 }
 
 #[derive(Clone)]
@@ -130,8 +207,12 @@ impl EguiClipboardData {
     }
 }
 
-#[derive(Debug)]
-pub struct EguiClipboardPlugin;
+#[derive(Debug, Default)]
+pub struct EguiClipboardPlugin {
+    /// Cropped screenshots that will be copied to the OS clipboard at the end
+    /// of the pass.
+    copied_images: Vec<egui::ColorImage>,
+}
 
 impl egui::Plugin for EguiClipboardPlugin {
     fn debug_name(&self) -> &'static str {
@@ -147,18 +228,47 @@ impl egui::Plugin for EguiClipboardPlugin {
         });
     }
 
+    fn on_begin_pass(&mut self, ctx: &egui::Context) {
+        if let Some(region) = ctx.clipboard_mut(|clipboard| clipboard.pending_screen_region.take())
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot(egui::UserData::new(
+                region,
+            )));
+        }
+    }
+
     fn input_hook(&mut self, input: &mut egui::RawInput) {
         for event in &input.events {
             match event {
                 egui::Event::Copy | egui::Event::Cut | egui::Event::Paste(_) => {
                     tracing::debug!(?event, "clipboard-ext: input event");
                 }
+                egui::Event::Screenshot {
+                    user_data, image, ..
+                } => {
+                    if let Some(region) = user_data
+                        .data
+                        .as_ref()
+                        .and_then(|data| data.downcast_ref::<CopyScreenRegion>())
+                    {
+                        tracing::debug!(?region, "clipboard-ext: copy screen region");
+                        self.copied_images
+                            .push(image.region(&region.rect, Some(region.pixels_per_point)));
+                    }
+                }
                 _ => {}
             }
         }
     }
 
     fn output_hook(&mut self, output: &mut egui::FullOutput) {
+        for image in self.copied_images.drain(..) {
+            output
+                .platform_output
+                .commands
+                .push(egui::OutputCommand::CopyImage(image));
+        }
+
         for command in &output.platform_output.commands {
             #[allow(clippy::single_match)]
             match command {
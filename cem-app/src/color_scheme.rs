@@ -0,0 +1,196 @@
+//! Colors the app draws things in that aren't part of the scene.
+//!
+//! A [`ColorScheme`] is chosen in the config file or in the *View* menu, and
+//! applies to selection outlines, wireframes of helper objects, the field
+//! color map of observers and the colors of plots. Besides the classic scheme
+//! there are presets that stay distinguishable with color vision deficiencies,
+//! based on the palettes by Okabe & Ito and by Paul Tol.
+//!
+//! The scheme is global, so that code that doesn't have access to the config
+//! (e.g. [`crate::solver::port::port_color`]) can use it too.
+
+use std::sync::atomic::{
+    AtomicU8,
+    Ordering,
+};
+
+use cem_render::material::Outline;
+use palette::{
+    Srgb,
+    Srgba,
+    WithAlpha,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+static CURRENT: AtomicU8 = AtomicU8::new(ColorScheme::Classic as u8);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum ColorScheme {
+    #[default]
+    Classic,
+
+    /// Safe for all common forms of color blindness, using the palette by
+    /// Okabe & Ito.
+    OkabeIto,
+
+    /// Safe for all common forms of color blindness, using the palettes by
+    /// Paul Tol.
+    Tol,
+}
+
+impl ColorScheme {
+    pub const ALL: [Self; 3] = [Self::Classic, Self::OkabeIto, Self::Tol];
+
+    /// The color scheme currently in use.
+    pub fn current() -> Self {
+        match CURRENT.load(Ordering::Relaxed) {
+            1 => Self::OkabeIto,
+            2 => Self::Tol,
+            _ => Self::Classic,
+        }
+    }
+
+    /// Uses this color scheme from now on.
+    ///
+    /// Objects that were already created with colors from the previous scheme
+    /// keep their colors.
+    pub fn make_current(self) {
+        tracing::debug!(color_scheme = ?self, "switching color scheme");
+        CURRENT.store(self as u8, Ordering::Relaxed);
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Classic => "Classic",
+            Self::OkabeIto => "Okabe-Ito (color blind safe)",
+            Self::Tol => "Tol (color blind safe)",
+        }
+    }
+
+    pub fn palette(&self) -> &'static Palette {
+        match self {
+            Self::Classic => &CLASSIC,
+            Self::OkabeIto => &OKABE_ITO,
+            Self::Tol => &TOL,
+        }
+    }
+}
+
+/// Shorthand for the palette of the [current](ColorScheme::current) color
+/// scheme.
+pub fn palette() -> &'static Palette {
+    ColorScheme::current().palette()
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Palette {
+    /// Colors for things that are told apart by color, e.g. ports and traces
+    /// in plots.
+    pub categorical: &'static [egui::Color32],
+
+    /// Color of the selection outline, or `None` to use the color from the
+    /// config.
+    pub selection: Option<Srgba<u8>>,
+
+    /// Color of wireframes of helper objects, e.g. far-field probes.
+    pub wireframe: Srgb<u8>,
+
+    /// Colors of positive and negative field values in observers.
+    pub field_positive: Srgb<u8>,
+    pub field_negative: Srgb<u8>,
+
+    sequential: SequentialGradient,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum SequentialGradient {
+    Viridis,
+    Cividis,
+}
+
+impl Palette {
+    /// Color of the `index`-th thing that is told apart by color.
+    pub fn categorical(&self, index: usize) -> egui::Color32 {
+        self.categorical[index % self.categorical.len()]
+    }
+
+    /// Gradient for sequential data, e.g. gain patterns and spectrograms.
+    pub fn sequential(&self) -> colorgrad::BasisGradient {
+        match self.sequential {
+            SequentialGradient::Viridis => colorgrad::preset::viridis(),
+            SequentialGradient::Cividis => colorgrad::preset::cividis(),
+        }
+    }
+
+    pub fn wireframe(&self) -> Srgba {
+        self.wireframe.into_format().with_alpha(1.0)
+    }
+
+    /// The selection outline from the config, in this palette's selection
+    /// color.
+    pub fn selection_outline(&self, mut outline: Outline) -> Outline {
+        if let Some(color) = self.selection {
+            outline.color = color.into_format();
+        }
+        outline
+    }
+}
+
+const CLASSIC: Palette = Palette {
+    categorical: &[
+        egui::Color32::from_rgb(0xd6, 0x27, 0x28),
+        egui::Color32::from_rgb(0x1f, 0x77, 0xb4),
+        egui::Color32::from_rgb(0x2c, 0xa0, 0x2c),
+        egui::Color32::from_rgb(0xff, 0x7f, 0x0e),
+        egui::Color32::from_rgb(0x94, 0x67, 0xbd),
+        egui::Color32::from_rgb(0x8c, 0x56, 0x4b),
+        egui::Color32::from_rgb(0xe3, 0x77, 0xc2),
+        egui::Color32::from_rgb(0x17, 0xbe, 0xcf),
+    ],
+    selection: None,
+    wireframe: palette::named::ORANGE,
+    field_positive: Srgb::new(0xff, 0x00, 0x00),
+    field_negative: Srgb::new(0x00, 0xff, 0x00),
+    sequential: SequentialGradient::Viridis,
+};
+
+// https://jfly.uni-koeln.de/color/
+const OKABE_ITO: Palette = Palette {
+    categorical: &[
+        egui::Color32::from_rgb(0xe6, 0x9f, 0x00),
+        egui::Color32::from_rgb(0x56, 0xb4, 0xe9),
+        egui::Color32::from_rgb(0x00, 0x9e, 0x73),
+        egui::Color32::from_rgb(0xf0, 0xe4, 0x42),
+        egui::Color32::from_rgb(0x00, 0x72, 0xb2),
+        egui::Color32::from_rgb(0xd5, 0x5e, 0x00),
+        egui::Color32::from_rgb(0xcc, 0x79, 0xa7),
+    ],
+    selection: Some(Srgba::new(0xf0, 0xe4, 0x42, 0xc0)),
+    wireframe: Srgb::new(0xe6, 0x9f, 0x00),
+    field_positive: Srgb::new(0xe6, 0x9f, 0x00),
+    field_negative: Srgb::new(0x56, 0xb4, 0xe9),
+    sequential: SequentialGradient::Cividis,
+};
+
+// https://personal.sron.nl/~pault/
+const TOL: Palette = Palette {
+    categorical: &[
+        egui::Color32::from_rgb(0x44, 0x77, 0xaa),
+        egui::Color32::from_rgb(0xee, 0x66, 0x77),
+        egui::Color32::from_rgb(0x22, 0x88, 0x33),
+        egui::Color32::from_rgb(0xcc, 0xbb, 0x44),
+        egui::Color32::from_rgb(0x66, 0xcc, 0xee),
+        egui::Color32::from_rgb(0xaa, 0x33, 0x77),
+        egui::Color32::from_rgb(0xbb, 0xbb, 0xbb),
+    ],
+    selection: Some(Srgba::new(0xdd, 0xaa, 0x33, 0xc0)),
+    wireframe: Srgb::new(0xdd, 0xaa, 0x33),
+    field_positive: Srgb::new(0xbb, 0x55, 0x66),
+    field_negative: Srgb::new(0x00, 0x44, 0x88),
+    sequential: SequentialGradient::Viridis,
+};
@@ -0,0 +1,283 @@
+//! Aligning and distributing the selected objects.
+//!
+//! Objects are arranged by their bounding boxes in world space. Objects
+//! without geometry (e.g. probes) are treated like a point at their origin.
+
+use bevy_ecs::{
+    entity::Entity,
+    hierarchy::ChildOf,
+    world::World,
+};
+use cem_scene::{
+    spatial::{
+        Aabb,
+        Collider,
+        traits::ComputeAabb,
+    },
+    transform::{
+        GlobalTransform,
+        LocalTransform,
+        propagate_transforms,
+    },
+};
+use nalgebra::{
+    Translation3,
+    Vector3,
+};
+
+pub const AXIS_LABELS: [&str; 3] = ["X", "Y", "Z"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alignment {
+    Min,
+    Center,
+    Max,
+}
+
+impl Alignment {
+    pub const ALL: [Self; 3] = [Self::Min, Self::Center, Self::Max];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Min => "Min",
+            Self::Center => "Center",
+            Self::Max => "Max",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arrangement {
+    /// Moves the objects along `axis`, so their bounding boxes line up with the
+    /// minimum, center or maximum of the bounding box around all of them.
+    Align { axis: usize, alignment: Alignment },
+
+    /// Spaces the centers of the objects equally along `axis`. The outermost
+    /// objects stay where they are.
+    Distribute { axis: usize },
+}
+
+impl Arrangement {
+    /// Short description of the edit shown in the UI.
+    pub fn label(&self) -> String {
+        match self {
+            Self::Align { axis, alignment } => {
+                format!("Align {} ({})", AXIS_LABELS[*axis], alignment.label())
+            }
+            Self::Distribute { axis } => format!("Distribute {}", AXIS_LABELS[*axis]),
+        }
+    }
+
+    /// Minimum number of objects this does anything for.
+    pub fn min_objects(&self) -> usize {
+        match self {
+            Self::Align { .. } => 2,
+            Self::Distribute { .. } => 3,
+        }
+    }
+
+    /// Computes how far each object has to be moved, in world space.
+    ///
+    /// Objects that are children of other objects in `entities` are left out,
+    /// as they're moved with their parent anyway.
+    fn translations(&self, world: &mut World, entities: &[Entity]) -> Vec<(Entity, f32)> {
+        propagate_transforms(world);
+
+        let objects = entities
+            .iter()
+            .filter(|entity| {
+                world.get::<LocalTransform>(**entity).is_some()
+                    && !has_ancestor_in(world, **entity, entities)
+            })
+            .filter_map(|entity| Some((*entity, world_aabb(world, *entity)?)))
+            .collect::<Vec<_>>();
+        if objects.len() < self.min_objects() {
+            return vec![];
+        }
+
+        match *self {
+            Self::Align { axis, alignment } => {
+                let min = objects
+                    .iter()
+                    .map(|(_, aabb)| aabb.mins[axis])
+                    .fold(f32::INFINITY, f32::min);
+                let max = objects
+                    .iter()
+                    .map(|(_, aabb)| aabb.maxs[axis])
+                    .fold(f32::NEG_INFINITY, f32::max);
+
+                objects
+                    .iter()
+                    .map(|(entity, aabb)| {
+                        let distance = match alignment {
+                            Alignment::Min => min - aabb.mins[axis],
+                            Alignment::Center => 0.5 * (min + max) - aabb.center()[axis],
+                            Alignment::Max => max - aabb.maxs[axis],
+                        };
+                        (*entity, distance)
+                    })
+                    .collect()
+            }
+            Self::Distribute { axis } => {
+                let mut centers = objects
+                    .iter()
+                    .map(|(entity, aabb)| (*entity, aabb.center()[axis]))
+                    .collect::<Vec<_>>();
+                centers.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+
+                let first = centers[0].1;
+                let spacing = (centers[centers.len() - 1].1 - first) / (centers.len() - 1) as f32;
+
+                centers
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (entity, center))| (*entity, first + i as f32 * spacing - center))
+                    .collect()
+            }
+        }
+    }
+
+    /// Arranges the objects.
+    ///
+    /// Returns the objects that were moved together with their transforms from
+    /// before.
+    pub fn apply(&self, world: &mut World, entities: &[Entity]) -> Vec<(Entity, LocalTransform)> {
+        let axis = match self {
+            Self::Align { axis, .. } | Self::Distribute { axis } => *axis,
+        };
+
+        self.translations(world, entities)
+            .into_iter()
+            .filter(|(_, distance)| *distance != 0.0)
+            .filter_map(|(entity, distance)| {
+                let mut translation = Vector3::zeros();
+                translation[axis] = distance;
+                let previous = translate_world(world, entity, &translation)?;
+                Some((entity, previous))
+            })
+            .collect()
+    }
+}
+
+/// Bounding box of the object in world space.
+fn world_aabb(world: &World, entity: Entity) -> Option<Aabb> {
+    let transform = world.get::<GlobalTransform>(entity)?;
+    world
+        .get::<Collider>(entity)
+        .and_then(|collider| collider.compute_aabb(transform.isometry()))
+        .filter(|aabb| aabb.mins.iter().chain(&aabb.maxs).all(|x| x.is_finite()))
+        .or_else(|| {
+            let position = transform.position();
+            Some(Aabb::new(position, position))
+        })
+}
+
+fn has_ancestor_in(world: &World, entity: Entity, entities: &[Entity]) -> bool {
+    let mut current = entity;
+    while let Some(child_of) = world.get::<ChildOf>(current) {
+        current = child_of.parent();
+        if entities.contains(&current) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Moves the object by `translation` in world space.
+///
+/// Returns its transform from before.
+fn translate_world(
+    world: &mut World,
+    entity: Entity,
+    translation: &Vector3<f32>,
+) -> Option<LocalTransform> {
+    // the local transform is relative to the parent
+    let translation = world
+        .get::<ChildOf>(entity)
+        .and_then(|child_of| world.get::<GlobalTransform>(child_of.parent()))
+        .map_or(*translation, |parent| {
+            parent
+                .isometry()
+                .rotation
+                .inverse_transform_vector(translation)
+        });
+
+    let mut transform = world.get_mut::<LocalTransform>(entity)?;
+    let previous = *transform;
+    transform.translate_global(&Translation3::from(translation));
+    Some(previous)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        entity::Entity,
+        world::World,
+    };
+    use cem_scene::{
+        spatial::Collider,
+        transform::{
+            GlobalTransform,
+            LocalTransform,
+            propagate_transforms,
+        },
+    };
+    use nalgebra::{
+        Translation3,
+        UnitQuaternion,
+        Vector3,
+    };
+    use parry3d::shape::Cuboid;
+
+    use crate::composer::arrange::{
+        Alignment,
+        Arrangement,
+    };
+
+    fn spawn_box(world: &mut World, x: f32, half_width: f32) -> Entity {
+        world
+            .spawn((
+                LocalTransform::new(Translation3::new(x, 0.0, 0.0), UnitQuaternion::identity()),
+                Collider::from(Cuboid::new(Vector3::new(half_width, 1.0, 1.0))),
+            ))
+            .id()
+    }
+
+    fn x(world: &World, entity: Entity) -> f32 {
+        world.get::<GlobalTransform>(entity).unwrap().position().x
+    }
+
+    #[test]
+    fn align_to_min() {
+        let mut world = World::default();
+        let a = spawn_box(&mut world, 0.0, 1.0);
+        let b = spawn_box(&mut world, 5.0, 2.0);
+
+        let moved = Arrangement::Align {
+            axis: 0,
+            alignment: Alignment::Min,
+        }
+        .apply(&mut world, &[a, b]);
+        propagate_transforms(&mut world);
+
+        assert_eq!(moved.len(), 1);
+        assert_eq!(moved[0].0, b);
+        assert_eq!(x(&world, a), 0.0);
+        assert_eq!(x(&world, b), 1.0);
+    }
+
+    #[test]
+    fn distribute_keeps_outermost() {
+        let mut world = World::default();
+        let a = spawn_box(&mut world, 0.0, 1.0);
+        let b = spawn_box(&mut world, 10.0, 1.0);
+        let c = spawn_box(&mut world, 2.0, 3.0);
+
+        Arrangement::Distribute { axis: 0 }.apply(&mut world, &[a, b, c]);
+        propagate_transforms(&mut world);
+
+        assert_eq!(x(&world, a), 0.0);
+        assert_eq!(x(&world, b), 10.0);
+        assert_eq!(x(&world, c), 5.0);
+    }
+}
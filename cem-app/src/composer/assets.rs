@@ -0,0 +1,206 @@
+//! Assets (e.g. textures) used by a project.
+//!
+//! Textures inside the project directory are referenced relative to it (see
+//! [`AssetPath`]), so the directory can be moved as a whole. "Collect Assets"
+//! pulls in everything else: small files are embedded into the project file,
+//! larger ones are copied into an `assets` directory next to it.
+
+use std::{
+    collections::{
+        BTreeSet,
+        HashMap,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use bevy_ecs::{
+    change_detection::Mut,
+    world::World,
+};
+use cem_render::{
+    material::{
+        AlbedoTexture,
+        LoadAlbedoTexture,
+        LoadMaterialTexture,
+        MaterialTexture,
+    },
+    texture::TextureSource,
+};
+use cem_scene::assets::{
+    AssetPath,
+    AssetStore,
+    ResolvedAsset,
+};
+use color_eyre::eyre::{
+    OptionExt,
+    bail,
+};
+
+use crate::Error;
+
+/// Directory next to the project file that collected assets are copied to.
+pub const ASSETS_DIRECTORY: &str = "assets";
+
+/// Directory the project file is in.
+pub fn project_directory(project_file: &Path) -> Option<PathBuf> {
+    std::path::absolute(project_file)
+        .ok()?
+        .parent()
+        .map(ToOwned::to_owned)
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CollectedAssets {
+    pub embedded: usize,
+    pub copied: usize,
+}
+
+/// Replaces paths of textures inside the project directory with
+/// project-relative ones.
+pub fn make_asset_paths_relative(world: &mut World) {
+    world.resource_scope(|world, asset_store: Mut<AssetStore>| {
+        for_each_texture_source(world, |source| {
+            if let Some(path) = source.file_path()
+                && let Ok(path) = std::path::absolute(path)
+                && let Some(asset_path) = asset_store.relative_path(&path)
+            {
+                *source = TextureSource::Asset {
+                    path: asset_path,
+                    mip_levels: source.mip_levels(),
+                };
+            }
+        });
+    });
+}
+
+/// Replaces project-relative texture paths with absolute ones.
+///
+/// This is needed before the project directory changes (e.g. on "Save As"),
+/// since the files stay where they are.
+pub fn make_asset_paths_absolute(world: &mut World) {
+    world.resource_scope(|world, asset_store: Mut<AssetStore>| {
+        for_each_texture_source(world, |source| {
+            if let TextureSource::Asset {
+                path: path @ AssetPath::Project(_),
+                mip_levels,
+            } = source
+                && let Ok(ResolvedAsset::File(path)) = asset_store.resolve(path)
+            {
+                let mip_levels = *mip_levels;
+                *source = TextureSource::File { path, mip_levels };
+            }
+        });
+    });
+}
+
+/// Embeds or copies all textures that are not part of the project yet.
+///
+/// Files up to `embed_limit` bytes are embedded, others are copied into
+/// [`ASSETS_DIRECTORY`]. The project needs to have been saved, so we know where
+/// that is.
+pub fn collect_assets(world: &mut World, embed_limit: u64) -> Result<CollectedAssets, Error> {
+    world.resource_scope(|world, mut asset_store: Mut<AssetStore>| {
+        let Some(project_directory) = asset_store.project_directory().map(ToOwned::to_owned)
+        else {
+            bail!("The project needs to be saved before its assets can be collected");
+        };
+
+        let mut paths = BTreeSet::new();
+        for_each_texture_source(world, |source| {
+            if let Some(path) = source.file_path() {
+                paths.insert(path.to_owned());
+            }
+        });
+
+        let mut collected = CollectedAssets::default();
+        let mut asset_paths = HashMap::with_capacity(paths.len());
+
+        for path in paths {
+            let absolute_path = std::path::absolute(&path)?;
+
+            let asset_path = if let Some(asset_path) = asset_store.relative_path(&absolute_path) {
+                // already part of the project
+                asset_path
+            }
+            else {
+                let file_name = absolute_path
+                    .file_name()
+                    .ok_or_eyre("Asset path has no file name")?
+                    .to_string_lossy()
+                    .into_owned();
+
+                if std::fs::metadata(&absolute_path)?.len() <= embed_limit {
+                    collected.embedded += 1;
+                    asset_store.embed(&file_name, std::fs::read(&absolute_path)?)
+                }
+                else {
+                    let directory = project_directory.join(ASSETS_DIRECTORY);
+                    std::fs::create_dir_all(&directory)?;
+                    let file_name = unique_file_name(&directory, &file_name);
+                    std::fs::copy(&absolute_path, directory.join(&file_name))?;
+                    collected.copied += 1;
+                    AssetPath::Project(Path::new(ASSETS_DIRECTORY).join(file_name))
+                }
+            };
+
+            tracing::debug!(path = %path.display(), ?asset_path, "collected asset");
+            asset_paths.insert(path, asset_path);
+        }
+
+        for_each_texture_source(world, |source| {
+            if let Some(asset_path) = source.file_path().and_then(|path| asset_paths.get(path)) {
+                *source = TextureSource::Asset {
+                    path: asset_path.clone(),
+                    mip_levels: source.mip_levels(),
+                };
+            }
+        });
+
+        Ok(collected)
+    })
+}
+
+/// Calls `f` with the source of every texture, both loaded and still waiting
+/// to be loaded.
+fn for_each_texture_source(world: &mut World, mut f: impl FnMut(&mut TextureSource)) {
+    for mut texture in world.query::<&mut AlbedoTexture>().iter_mut(world) {
+        f(&mut texture.source);
+    }
+    for mut texture in world.query::<&mut MaterialTexture>().iter_mut(world) {
+        f(&mut texture.source);
+    }
+    for mut loader in world.query::<&mut LoadAlbedoTexture>().iter_mut(world) {
+        f(&mut loader.source);
+    }
+    for mut loader in world.query::<&mut LoadMaterialTexture>().iter_mut(world) {
+        f(&mut loader.source);
+    }
+}
+
+/// Appends a counter to the file stem until there's no file with that name in
+/// `directory`.
+fn unique_file_name(directory: &Path, file_name: &str) -> String {
+    let path = Path::new(file_name);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy());
+
+    let mut candidate = file_name.to_owned();
+    let mut counter = 1;
+    while directory.join(&candidate).exists() {
+        counter += 1;
+        candidate = match &extension {
+            Some(extension) => format!("{stem}-{counter}.{extension}"),
+            None => format!("{stem}-{counter}"),
+        };
+    }
+
+    candidate
+}
@@ -1,4 +1,5 @@
 use bevy_ecs::{
+    component::Component,
     entity::Entity,
     query::QueryData,
     system::{
@@ -14,9 +15,14 @@ use bevy_ecs::{
 use cem_render::{
     DrawCommand,
     camera::{
+        CameraConfig,
         CameraProjection,
         Viewport,
     },
+    components::{
+        OverlayFlags,
+        ViewOverlays,
+    },
     grab_draw_list_for_camera,
 };
 use cem_scene::{
@@ -33,6 +39,7 @@ use cem_scene::{
 use nalgebra::{
     Isometry3,
     Point2,
+    Point3,
     Translation3,
     UnitQuaternion,
     Vector2,
@@ -40,6 +47,21 @@ use nalgebra::{
 };
 use parry3d::query::Ray;
 
+/// Animation of a camera moving to another transform.
+///
+/// See [`CameraWorldMut::animate_to`].
+#[derive(Clone, Copy, Debug, Component)]
+pub struct CameraAnimation {
+    from: Isometry3<f64>,
+    to: Isometry3<f64>,
+    start: f64,
+}
+
+impl CameraAnimation {
+    /// Duration of the animation in seconds.
+    pub const DURATION: f64 = 0.4;
+}
+
 /// A proxy to control a camera in a world.
 #[derive(Debug)]
 pub struct CameraWorldMut<'a> {
@@ -77,6 +99,33 @@ impl<'a> CameraWorldMut<'a> {
             .unwrap();
     }
 
+    /// Whether the camera draws dielectrics see-through (see
+    /// [`crate::composer::simulation_view`]).
+    pub fn simulation_view(&self) -> bool {
+        self.world
+            .get::<CameraConfig>(self.camera_entity)
+            .is_some_and(|camera_config| camera_config.see_through)
+    }
+
+    pub fn set_simulation_view(&mut self, enabled: bool) {
+        if let Some(mut camera_config) = self.world.get_mut::<CameraConfig>(self.camera_entity) {
+            camera_config.see_through = enabled;
+        }
+    }
+
+    /// Overlays the camera shows.
+    pub fn view_overlays(&self) -> OverlayFlags {
+        self.world
+            .get::<ViewOverlays>(self.camera_entity)
+            .map_or_else(OverlayFlags::default, |view_overlays| view_overlays.flags)
+    }
+
+    pub fn set_view_overlays(&mut self, flags: OverlayFlags) {
+        self.world
+            .entity_mut(self.camera_entity)
+            .insert(ViewOverlays { flags });
+    }
+
     pub fn with<Q, F, R>(&mut self, f: F) -> R
     where
         for<'w, 's> F: FnMut(Q::Item<'w, 's>) -> R + 'static,
@@ -120,6 +169,120 @@ impl<'a> CameraWorldMut<'a> {
             .unwrap()
     }
 
+    /// Ray from the camera through `pointer_position` (in normalized screen
+    /// coordinates), in world space.
+    pub fn screen_ray(&mut self, pointer_position: Point2<f32>) -> Option<Ray> {
+        self.world
+            .run_system_cached_with(
+                |In((camera_entity, pointer_position)): In<(Entity, Point2<f32>)>,
+                 cameras: Query<(&GlobalTransform, &CameraProjection)>| {
+                    let (camera_transform, camera_projection) = cameras.get(camera_entity).ok()?;
+                    Some(
+                        camera_projection
+                            .shoot_screen_ray(&pointer_position)
+                            .transform_by(camera_transform.isometry()),
+                    )
+                },
+                (self.camera_entity, pointer_position),
+            )
+            .unwrap()
+    }
+
+    /// Computes the screen rectangle that contains all `points` (in world
+    /// coordinates).
+    ///
+    /// Returns `None` if the camera has no viewport yet, or if any point is
+    /// behind the camera.
+    pub fn screen_rect(&mut self, points: Vec<Point3<f32>>) -> Option<egui::Rect> {
+        let viewport = self.viewport()?;
+        let mut rect = egui::Rect::NOTHING;
+        for point in self.project_to_screen(points)? {
+            rect.extend_with(point);
+        }
+        Some(rect.intersect(viewport))
+    }
+
+    /// Projects points in world space onto the screen.
+    ///
+    /// Returns `None` if any of the points is behind the camera.
+    pub fn project_to_screen(&mut self, points: Vec<Point3<f32>>) -> Option<Vec<egui::Pos2>> {
+        self.world
+            .run_system_cached_with(
+                |In((camera_entity, points)): In<(Entity, Vec<Point3<f32>>)>,
+                 cameras: Query<(&GlobalTransform, &CameraProjection, &Viewport)>| {
+                    let (camera_transform, camera_projection, viewport) =
+                        cameras.get(camera_entity).ok()?;
+
+                    points
+                        .iter()
+                        .map(|point| {
+                            let point = camera_transform.isometry().inverse_transform_point(point);
+                            if point.z <= 0.0 {
+                                return None;
+                            }
+                            Some(camera_to_screen(camera_projection, viewport, &point))
+                        })
+                        .collect()
+                },
+                (self.camera_entity, points),
+            )
+            .unwrap()
+    }
+
+    /// Projects line segments in world space onto the screen.
+    ///
+    /// Segments are clipped at the camera's near plane. Segments that are
+    /// completely behind it are left out.
+    pub fn project_segments_to_screen(
+        &mut self,
+        segments: Vec<[Point3<f32>; 2]>,
+    ) -> Vec<[egui::Pos2; 2]> {
+        self.world
+            .run_system_cached_with(
+                |In((camera_entity, segments)): In<(Entity, Vec<[Point3<f32>; 2]>)>,
+                 cameras: Query<(&GlobalTransform, &CameraProjection, &Viewport)>| {
+                    let Ok((camera_transform, camera_projection, viewport)) =
+                        cameras.get(camera_entity)
+                    else {
+                        return vec![];
+                    };
+                    let znear = camera_projection.znear();
+
+                    segments
+                        .iter()
+                        .filter_map(|segment| {
+                            let [mut a, mut b] = segment.map(|point| {
+                                camera_transform.isometry().inverse_transform_point(&point)
+                            });
+                            if a.z < znear && b.z < znear {
+                                return None;
+                            }
+                            if a.z < znear {
+                                a = b + (a - b) * (b.z - znear) / (b.z - a.z);
+                            }
+                            else if b.z < znear {
+                                b = a + (b - a) * (a.z - znear) / (a.z - b.z);
+                            }
+                            Some(
+                                [a, b].map(|point| {
+                                    camera_to_screen(camera_projection, viewport, &point)
+                                }),
+                            )
+                        })
+                        .collect()
+                },
+                (self.camera_entity, segments),
+            )
+            .unwrap()
+    }
+
+    /// The area of the screen the camera renders to.
+    pub fn viewport(&self) -> Option<egui::Rect> {
+        self.world
+            .get::<Viewport>(self.camera_entity)
+            .map(|viewport| viewport.viewport)
+    }
+
     /// Moves the camera such that it fits the whole scene.
     ///
     /// Specifically this only translates the camera. It will be translated (by
@@ -175,6 +338,24 @@ impl<'a> CameraWorldMut<'a> {
         up: &Vector3<f32>,
         margin: &Vector2<f32>,
     ) {
+        if let Some(transform) = self.transform_fitting_scene_along_axis(axis, up, margin) {
+            // FIXME: this doesn't work anymore if the camera has a parent
+            if let Some(mut camera_transform) =
+                self.world.get_mut::<LocalTransform>(self.camera_entity)
+            {
+                *camera_transform = transform;
+            }
+        }
+    }
+
+    /// Computes the transform [`Self::fit_to_scene_looking_along_axis`] moves
+    /// the camera to, without moving it.
+    pub fn transform_fitting_scene_along_axis(
+        &mut self,
+        axis: &Vector3<f32>,
+        up: &Vector3<f32>,
+        margin: &Vector2<f32>,
+    ) -> Option<LocalTransform> {
         #[allow(clippy::type_complexity)]
         self.world
             .run_system_cached_with(
@@ -184,15 +365,10 @@ impl<'a> CameraWorldMut<'a> {
                     Vector3<f32>,
                     Vector2<f32>,
                 )>,
-                 mut cameras: Query<(&mut LocalTransform, &CameraProjection)>,
+                 cameras: Query<&CameraProjection>,
                  world_aabb: WorldAabb| {
                     let scene_aabb = world_aabb.root_aabb();
-
-                    let Ok((mut camera_local_transform, camera_projection)) =
-                        cameras.get_mut(camera_entity)
-                    else {
-                        return;
-                    };
+                    let camera_projection = cameras.get(camera_entity).ok()?;
 
                     let rotation = UnitQuaternion::face_towards(&axis, &up);
 
@@ -209,13 +385,59 @@ impl<'a> CameraWorldMut<'a> {
                         rotation,
                     ));
                     new_local.translate_local(&Translation3::from(-Vector3::z() * distance));
-
-                    // FIXME: this doesn't work anymore if the camera has a parent
-                    *camera_local_transform = new_local;
+                    Some(new_local)
                 },
                 (self.camera_entity, *axis, *up, *margin),
             )
-            .unwrap();
+            .unwrap()
+    }
+
+    /// Moves the camera smoothly to `target`.
+    ///
+    /// `time` is the current time in seconds (e.g. egui's input time). The
+    /// animation is advanced by [`Self::update_animation`].
+    pub fn animate_to(&mut self, target: LocalTransform, time: f64) {
+        let Some(transform) = self.world.get::<LocalTransform>(self.camera_entity)
+        else {
+            return;
+        };
+        let animation = CameraAnimation {
+            from: transform.isometry,
+            to: target.isometry,
+            start: time,
+        };
+        self.world.entity_mut(self.camera_entity).insert(animation);
+    }
+
+    /// Advances the camera's animation, if it has one.
+    ///
+    /// Returns whether the camera is still moving, i.e. whether another frame
+    /// should be drawn.
+    pub fn update_animation(&mut self, time: f64) -> bool {
+        let Some(animation) = self
+            .world
+            .get::<CameraAnimation>(self.camera_entity)
+            .copied()
+        else {
+            return false;
+        };
+
+        let t = ((time - animation.start) / CameraAnimation::DURATION).clamp(0.0, 1.0);
+        // ease in and out
+        let t = t * t * (3.0 - 2.0 * t);
+
+        let mut entity = self.world.entity_mut(self.camera_entity);
+        if let Some(mut transform) = entity.get_mut::<LocalTransform>() {
+            transform.isometry = animation.from.lerp_slerp(&animation.to, t);
+        }
+
+        if t >= 1.0 {
+            entity.remove::<CameraAnimation>();
+            false
+        }
+        else {
+            true
+        }
     }
 
     pub fn point_to_scene_center(&mut self) {
@@ -241,7 +463,7 @@ impl<'a> CameraWorldMut<'a> {
                     if (eye - scene_center).cross(&up).norm_squared() < COLLINEAR_THRESHOLD {
                         // we would be looking straight up or down, so keep the up vector from the
                         // camera
-                        up = camera_transform.isometry.rotation.transform_vector(&up);
+                        up = camera_transform.isometry.rotation.cast::<f32>() * up;
                         tracing::debug!(?eye, ?scene_center, ?up, "looking straight up or down");
                     }
 
@@ -252,3 +474,17 @@ impl<'a> CameraWorldMut<'a> {
             .unwrap();
     }
 }
+
+/// Maps a point in the camera's frame onto the screen.
+fn camera_to_screen(
+    camera_projection: &CameraProjection,
+    viewport: &Viewport,
+    point: &Point3<f32>,
+) -> egui::Pos2 {
+    let viewport = viewport.viewport;
+    let point = camera_projection.project(point);
+    egui::pos2(
+        viewport.left() + 0.5 * (point.x + 1.0) * viewport.width(),
+        viewport.top() + 0.5 * (1.0 - point.y) * viewport.height(),
+    )
+}
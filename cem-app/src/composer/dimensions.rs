@@ -0,0 +1,155 @@
+//! Dimensions of the selection.
+//!
+//! The bounding box of the selected objects is drawn into the viewport, and
+//! the status bar shows its size in the scene's lengths and in wavelengths at
+//! the excitation frequency entered there.
+
+use bevy_ecs::{
+    entity::Entity,
+    hierarchy::Children,
+    query::With,
+    system::Query,
+    world::World,
+};
+use cem_scene::spatial::{
+    Aabb,
+    queries::WorldAabb,
+};
+use cem_solver::material::UnitSystem;
+use nalgebra::{
+    Point3,
+    Vector3,
+};
+
+use crate::composer::{
+    camera::CameraWorldMut,
+    selection::Selected,
+};
+
+/// Bounding box of the selected objects and their descendants.
+pub fn selection_aabb(world: &mut World) -> Option<Aabb> {
+    world
+        .run_system_cached(
+            |selected: Query<Entity, With<Selected>>,
+             children: Query<&Children>,
+             world_aabb: WorldAabb| {
+                world_aabb.of_entities(selected.iter().flat_map(|entity| {
+                    std::iter::once(entity).chain(children.iter_descendants(entity))
+                }))
+            },
+        )
+        .unwrap()
+}
+
+/// Wavelength in vacuum at `frequency`.
+pub fn vacuum_wavelength(units: UnitSystem, frequency: f64) -> f64 {
+    units.physical_constants().speed_of_light() / frequency
+}
+
+/// Size of a bounding box, e.g. `12.000 mm × 3.500 mm × 1.000 mm`.
+pub fn format_size(aabb: &Aabb) -> String {
+    format_vector(&aabb.extents(), |length| format_length(length.into()))
+}
+
+/// Size of a bounding box in wavelengths, e.g. `0.40 λ × 0.12 λ × 0.03 λ`.
+pub fn format_size_in_wavelengths(aabb: &Aabb, wavelength: f64) -> String {
+    format_vector(&aabb.extents(), |length| {
+        format!("{:.2} λ", f64::from(length) / wavelength)
+    })
+}
+
+fn format_vector(vector: &Vector3<f32>, format: impl Fn(f32) -> String) -> String {
+    vector
+        .iter()
+        .map(|length| format(*length))
+        .collect::<Vec<_>>()
+        .join(" × ")
+}
+
+/// Formats a length in meters with a unit prefix, e.g. `3.500 mm`.
+pub fn format_length(length: f64) -> String {
+    let (scale, unit) = match length.abs() {
+        l if l >= 1.0 || l == 0.0 => (1.0, "m"),
+        l if l >= 1e-3 => (1e3, "mm"),
+        l if l >= 1e-6 => (1e6, "µm"),
+        _ => (1e9, "nm"),
+    };
+    format!("{:.3} {unit}", length * scale)
+}
+
+/// Draws the bounding box of the selection with its size into the viewport.
+pub fn dimensions_overlay(ui: &egui::Ui, camera: &mut CameraWorldMut, aabb: &Aabb) {
+    let Some(viewport) = camera.viewport()
+    else {
+        return;
+    };
+
+    let corners = (0..8)
+        .map(|i| {
+            Point3::new(
+                if i & 1 == 0 { aabb.mins.x } else { aabb.maxs.x },
+                if i & 2 == 0 { aabb.mins.y } else { aabb.maxs.y },
+                if i & 4 == 0 { aabb.mins.z } else { aabb.maxs.z },
+            )
+        })
+        .collect();
+    let Some(corners) = camera.project_to_screen(corners)
+    else {
+        return;
+    };
+
+    let painter = ui.painter_at(viewport);
+    let stroke = egui::Stroke::new(1.0, ui.visuals().weak_text_color());
+
+    // corners that differ in exactly one bit share an edge
+    for i in 0..8 {
+        for bit in [1, 2, 4] {
+            if i & bit == 0 {
+                painter.line_segment([corners[i], corners[i | bit]], stroke);
+            }
+        }
+    }
+
+    let top = corners
+        .iter()
+        .copied()
+        .min_by(|a, b| a.y.total_cmp(&b.y))
+        .unwrap();
+    painter.text(
+        top - egui::vec2(0.0, 4.0),
+        egui::Align2::CENTER_BOTTOM,
+        format_size(aabb),
+        egui::FontId::default(),
+        ui.visuals().text_color(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use cem_scene::spatial::Aabb;
+    use nalgebra::Point3;
+
+    use crate::composer::dimensions::{
+        format_length,
+        format_size,
+        format_size_in_wavelengths,
+    };
+
+    #[test]
+    fn lengths_are_formatted_with_prefix() {
+        assert_eq!(format_length(2.0), "2.000 m");
+        assert_eq!(format_length(0.0125), "12.500 mm");
+        assert_eq!(format_length(3e-6), "3.000 µm");
+        assert_eq!(format_length(0.0), "0.000 m");
+    }
+
+    #[test]
+    fn size_of_aabb() {
+        let aabb = Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(0.5, 0.25, 2.0));
+        assert_eq!(format_size(&aabb), "500.000 mm × 250.000 mm × 2.000 m");
+        assert_eq!(
+            format_size_in_wavelengths(&aabb, 0.5),
+            "1.00 λ × 0.50 λ × 4.00 λ"
+        );
+    }
+}
@@ -1,22 +1,48 @@
 pub use std::any::type_name;
+use std::{
+    any::TypeId,
+    collections::BTreeSet,
+};
 
 use bevy_ecs::{
     component::Component,
     entity::Entity,
+    hierarchy::ChildOf,
     name::Name,
+    query::With,
     reflect::{
         AppTypeRegistry,
         ReflectComponent,
     },
+    resource::Resource,
     world::World,
 };
 use bevy_reflect::{
+    ApplyError,
+    PartialReflect,
+    ReflectMut,
+    ReflectRef,
+    TypeInfo,
     TypeRegistry,
     prelude::ReflectDefault,
 };
-use cem_scene::probe::{
-    ReflectComponentUi,
-    component_name,
+use cem_scene::{
+    probe::{
+        ReflectComponentUi,
+        component_name,
+    },
+    transform::{
+        GlobalTransform,
+        LocalTransform,
+    },
+};
+
+use crate::{
+    composer::{
+        selection::Selected,
+        transform_entry::TransformEntry,
+    },
+    recorder,
 };
 
 /// Component for entities that have an entity window open
@@ -51,6 +77,14 @@ pub fn show_entity_windows(ctx: &egui::Context, world: &mut World) {
             .components_deletable(window.component_delete_buttons)
             .show(ctx);
     }
+
+    if world.contains_resource::<SelectionWindow>() {
+        let mut query = world.query_filtered::<Entity, With<Selected>>();
+        let entities = query.iter(world).collect::<Vec<_>>();
+        if !SelectionWindowRenderer::new(world, entities, &type_registry).show(ctx) {
+            world.remove_resource::<SelectionWindow>();
+        }
+    }
 }
 
 #[derive(derive_more::Debug)]
@@ -92,6 +126,12 @@ impl<'a> EntityWindowRenderer<'a> {
 
         let mut is_open = true;
         let mut delete_entity = false;
+        let mut changed_components = vec![];
+
+        let parent_transform = entity
+            .get::<ChildOf>()
+            .and_then(|child_of| entity.world().get::<GlobalTransform>(child_of.parent()))
+            .map(|transform| *transform.precise_isometry());
 
         let title = entity.get::<Name>().map_or_else(
             || egui::WidgetText::from(entity.id().to_string()).monospace(),
@@ -168,6 +208,7 @@ impl<'a> EntityWindowRenderer<'a> {
                                 {
                                     let default = reflect_default.default();
                                     entity.insert_reflect(default);
+                                    changed_components.push(reflect_component);
                                 }
                             }
                         });
@@ -175,6 +216,28 @@ impl<'a> EntityWindowRenderer<'a> {
                 });
                 ui.separator();
 
+                if let Some(mut transform) = entity.get_mut::<LocalTransform>() {
+                    egui::CollapsingHeader::new("Transform")
+                        .id_salt(self.id.with("transform"))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            if ui
+                                .add(TransformEntry::new(
+                                    self.id.with("transform_entry"),
+                                    &mut transform.isometry,
+                                    parent_transform,
+                                ))
+                                .changed()
+                                && let Some(reflect_component) =
+                                    self.type_registry.get_type_data::<ReflectComponent>(
+                                        TypeId::of::<LocalTransform>(),
+                                    )
+                            {
+                                changed_components.push(reflect_component);
+                            }
+                        });
+                }
+
                 for (type_registration, reflect_component_ui) in
                     self.type_registry.iter_with_data::<ReflectComponentUi>()
                 {
@@ -198,7 +261,9 @@ impl<'a> EntityWindowRenderer<'a> {
                             .id_salt(self.id.with("component").with(type_info.type_id()))
                             .default_open(true)
                             .show(ui, |ui| {
-                                component_ui.properties_ui(ui, &());
+                                if component_ui.properties_ui(ui, &()).changed() {
+                                    changed_components.push(reflect_component);
+                                }
 
                                 if self.components_deletable && ui.small_button("Delete").clicked()
                                 {
@@ -216,10 +281,271 @@ impl<'a> EntityWindowRenderer<'a> {
         if delete_entity {
             entity.despawn();
         }
-        else if !is_open {
-            entity.remove::<EntityWindow>();
+        else {
+            if !is_open {
+                entity.remove::<EntityWindow>();
+            }
+
+            for reflect_component in changed_components {
+                recorder::record_component_change(self.world, self.entity, reflect_component);
+            }
         }
 
         response.map(|response| response.response)
     }
 }
+
+/// Resource that is present while the properties window for the selection is
+/// open.
+#[derive(Clone, Copy, Debug, Default, Resource)]
+pub struct SelectionWindow;
+
+/// Shows the components that all selected entities have, and applies edits to
+/// all of them.
+///
+/// Only the fields that were edited are applied, so fields that differ
+/// between the entities keep their values.
+#[derive(derive_more::Debug)]
+pub struct SelectionWindowRenderer<'a> {
+    world: &'a mut World,
+    entities: Vec<Entity>,
+    #[debug(skip)]
+    type_registry: &'a TypeRegistry,
+}
+
+impl<'a> SelectionWindowRenderer<'a> {
+    pub fn new(
+        world: &'a mut World,
+        entities: Vec<Entity>,
+        type_registry: &'a TypeRegistry,
+    ) -> Self {
+        Self {
+            world,
+            entities,
+            type_registry,
+        }
+    }
+
+    /// Returns whether the window is still open.
+    pub fn show(&mut self, ctx: &egui::Context) -> bool {
+        let id = egui::Id::new("selection_window");
+        let mut is_open = true;
+
+        egui::Window::new(format!("{} selected", self.entities.len()))
+            .id(id)
+            .movable(true)
+            .collapsible(true)
+            .open(&mut is_open)
+            .show(ctx, |ui| {
+                if self.entities.is_empty() {
+                    ui.label("Nothing selected");
+                    return;
+                }
+
+                let type_registry = self.type_registry;
+                let mut empty = true;
+                for (type_registration, reflect_component_ui) in
+                    type_registry.iter_with_data::<ReflectComponentUi>()
+                {
+                    let type_info = type_registration.type_info();
+                    if !is_editable_in_bulk(type_info) {
+                        continue;
+                    }
+                    let Some(reflect_component) = type_registration.data::<ReflectComponent>()
+                    else {
+                        continue;
+                    };
+
+                    // the components of all entities, or none if any of them doesn't have it
+                    let Some(components) = self
+                        .entities
+                        .iter()
+                        .map(|entity| reflect_component.reflect(self.world.entity(*entity)))
+                        .collect::<Option<Vec<_>>>()
+                    else {
+                        continue;
+                    };
+
+                    let first = components[0].as_partial_reflect();
+                    let mixed_fields = components[1..]
+                        .iter()
+                        .flat_map(|other| {
+                            changed_fields(first, other.as_partial_reflect()).unwrap_or_default()
+                        })
+                        .collect::<BTreeSet<_>>();
+                    let mixed = components[1..].iter().any(|other| {
+                        first.reflect_partial_eq(other.as_partial_reflect()) != Some(true)
+                    });
+
+                    let Ok(before) = first.reflect_clone()
+                    else {
+                        continue;
+                    };
+                    let Ok(mut edited) = first.reflect_clone()
+                    else {
+                        continue;
+                    };
+                    let Some(component_ui) = reflect_component_ui.get_mut(&mut *edited)
+                    else {
+                        continue;
+                    };
+                    empty = false;
+
+                    let mut changed = false;
+                    egui::CollapsingHeader::new(component_name(type_info))
+                        .id_salt(id.with("component").with(type_info.type_id()))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            if mixed {
+                                ui.weak("Mixed values")
+                                    .on_hover_text(mixed_hover_text(first, &mixed_fields));
+                            }
+                            changed = component_ui.properties_ui(ui, &()).changed();
+                        });
+
+                    if changed {
+                        self.apply_edit(
+                            reflect_component,
+                            before.as_partial_reflect(),
+                            edited.as_partial_reflect(),
+                            mixed,
+                        );
+                    }
+                }
+
+                if empty {
+                    ui.label("The selected objects have no components in common");
+                }
+            });
+
+        is_open
+    }
+
+    /// Applies the fields that changed from `before` to `edited` to all
+    /// entities.
+    fn apply_edit(
+        &mut self,
+        reflect_component: &ReflectComponent,
+        before: &dyn PartialReflect,
+        edited: &dyn PartialReflect,
+        mixed: bool,
+    ) {
+        let fields = changed_fields(before, edited);
+
+        // the edit might only have changed state that isn't reflected, so we can only
+        // copy the whole value. but that would overwrite the differing values.
+        if fields.as_ref().is_none_or(|fields| fields.is_empty()) && mixed {
+            tracing::debug!("edit can't be applied to entities with mixed values");
+            return;
+        }
+
+        for entity in &self.entities {
+            let mut entity = self.world.entity_mut(*entity);
+            if let Some(mut component) = reflect_component.reflect_mut(&mut entity) {
+                let component = component.as_partial_reflect_mut();
+                let result = match &fields {
+                    Some(fields) if !fields.is_empty() => apply_fields(component, edited, fields),
+                    _ => component.try_apply(edited),
+                };
+                if let Err(error) = result {
+                    tracing::warn!(?error, "failed to apply edit");
+                }
+            }
+        }
+
+        for entity in &self.entities {
+            recorder::record_component_change(self.world, *entity, reflect_component);
+        }
+    }
+}
+
+/// Whether edits to a component can be applied field by field.
+///
+/// Components without reflected fields (e.g. tags, or values that are opaque to
+/// reflection) can't be compared, so they're not shown for multiple entities.
+fn is_editable_in_bulk(type_info: &TypeInfo) -> bool {
+    match type_info {
+        TypeInfo::Struct(info) => info.field_len() > 0,
+        TypeInfo::TupleStruct(info) => info.field_len() > 0,
+        TypeInfo::Enum(_) => true,
+        _ => false,
+    }
+}
+
+/// Indices of the fields that differ between two values of the same type.
+///
+/// Returns `None` if the values don't have fields, e.g. enums.
+fn changed_fields(a: &dyn PartialReflect, b: &dyn PartialReflect) -> Option<Vec<usize>> {
+    let differs = |a: Option<&dyn PartialReflect>, b: Option<&dyn PartialReflect>| {
+        match (a, b) {
+            (Some(a), Some(b)) => a.reflect_partial_eq(b) != Some(true),
+            _ => false,
+        }
+    };
+
+    match (a.reflect_ref(), b.reflect_ref()) {
+        (ReflectRef::Struct(a), ReflectRef::Struct(b)) => {
+            Some(
+                (0..a.field_len())
+                    .filter(|i| differs(a.field_at(*i), b.field_at(*i)))
+                    .collect(),
+            )
+        }
+        (ReflectRef::TupleStruct(a), ReflectRef::TupleStruct(b)) => {
+            Some(
+                (0..a.field_len())
+                    .filter(|i| differs(a.field(*i), b.field(*i)))
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+fn apply_fields(
+    target: &mut dyn PartialReflect,
+    source: &dyn PartialReflect,
+    fields: &[usize],
+) -> Result<(), ApplyError> {
+    match (target.reflect_mut(), source.reflect_ref()) {
+        (ReflectMut::Struct(target), ReflectRef::Struct(source)) => {
+            for i in fields {
+                if let (Some(target), Some(source)) = (target.field_at_mut(*i), source.field_at(*i))
+                {
+                    target.try_apply(source)?;
+                }
+            }
+            Ok(())
+        }
+        (ReflectMut::TupleStruct(target), ReflectRef::TupleStruct(source)) => {
+            for i in fields {
+                if let (Some(target), Some(source)) = (target.field_mut(*i), source.field(*i)) {
+                    target.try_apply(source)?;
+                }
+            }
+            Ok(())
+        }
+        // only structs and tuple structs have fields
+        _ => Ok(()),
+    }
+}
+
+fn mixed_hover_text(value: &dyn PartialReflect, mixed_fields: &BTreeSet<usize>) -> String {
+    let names = match value.reflect_ref() {
+        ReflectRef::Struct(value) => {
+            mixed_fields
+                .iter()
+                .filter_map(|i| value.name_at(*i))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+        _ => String::new(),
+    };
+
+    if names.is_empty() {
+        "The selected objects have different values".to_owned()
+    }
+    else {
+        format!("The selected objects have different values for: {names}")
+    }
+}
@@ -24,6 +24,7 @@ pub fn guess_file_format_from_path(path: impl AsRef<Path>) -> Option<FileFormat>
 pub enum FileFormat {
     Cem,
     Nec,
+    Obj,
 }
 
 impl FileFormat {
@@ -35,6 +36,7 @@ impl FileFormat {
         match self {
             Self::Cem => &["cem"],
             Self::Nec => &["nec"],
+            Self::Obj => &["obj"],
         }
     }
 
@@ -42,6 +44,7 @@ impl FileFormat {
         match self {
             Self::Cem => "CEM Project File",
             Self::Nec => "NEC File",
+            Self::Obj => "Wavefront OBJ",
         }
     }
 
@@ -49,6 +52,7 @@ impl FileFormat {
         match self {
             Self::Cem => true,
             Self::Nec => true,
+            Self::Obj => true,
         }
     }
 
@@ -9,34 +9,74 @@ use cem_scene::{
     transform::LocalTransform,
 };
 use nalgebra::{
+    Point3,
     Translation3,
     UnitQuaternion,
     UnitVector3,
+    Vector2,
+    Vector3,
     Vector4,
 };
 use nec_file::{
     NecFile,
-    card::WireSegmentDimensions,
+    card::{
+        GroundPlaneFlag,
+        WireSegmentDimensions,
+    },
     interpreter::GeometrySpecification,
 };
 use parry3d::shape::Cylinder;
 
-use crate::util::scene::{
-    EntityBuilderExt,
-    SceneExt,
+use crate::{
+    composer::import::ImportOptions,
+    solver::ground_plane::{
+        GroundPlane,
+        add_ground_plane_with_normal,
+    },
+    util::scene::{
+        EntityBuilderExt,
+        SceneExt,
+    },
 };
 
 #[derive(Clone, Copy, Debug)]
 pub struct PopulateWithNec<'a> {
     pub nec_file: &'a NecFile,
     pub material: Material,
+    pub import_options: ImportOptions,
+}
+
+/// End points of all wires, in file coordinates.
+pub fn wire_end_points(nec_file: &NecFile) -> impl Iterator<Item = Point3<f32>> + '_ {
+    nec_file
+        .geometry
+        .iter()
+        .filter_map(|(_tag, geometry)| {
+            match geometry.specification {
+                GeometrySpecification::Wire { length, .. } => {
+                    Some(
+                        [Vector4::w(), Vector4::w() + length * Vector4::y()].map(|point| {
+                            Point3::from_homogeneous(geometry.transform * point)
+                                .expect("wire transform is affine")
+                        }),
+                    )
+                }
+                _ => None,
+            }
+        })
+        .flatten()
 }
 
 impl<'a> PopulateScene for PopulateWithNec<'a> {
     type Error = Infallible;
 
     fn populate_scene(&self, scene: &mut Scene) -> Result<(), Self::Error> {
+        let import_transform = self.import_options.linear_map().to_homogeneous();
+        let scale = self.import_options.scale_factor();
+
         for (_tag, geometry) in &self.nec_file.geometry {
+            let transform = import_transform * geometry.transform;
+
             match geometry.specification {
                 GeometrySpecification::WireArc { .. } => todo!("populate scene: wire-arc"),
                 GeometrySpecification::Wire {
@@ -47,13 +87,13 @@ impl<'a> PopulateScene for PopulateWithNec<'a> {
                     for (i, wire_segment) in segments.dimensions(num_segments, length).enumerate() {
                         match wire_segment {
                             WireSegmentDimensions::Flat { length, radius } => {
-                                let shape = Cylinder::new(0.5 * length, radius);
+                                let shape = Cylinder::new(0.5 * length * scale, radius * scale);
 
                                 let transform = LocalTransform::new(
                                     // get the translation by applying the origin point + length
                                     // along the wire to the transform
                                     Translation3::from(
-                                        (geometry.transform
+                                        (transform
                                             * (Vector4::w() + i as f32 * length * Vector4::y()))
                                         .xyz(),
                                     ),
@@ -61,7 +101,7 @@ impl<'a> PopulateScene for PopulateWithNec<'a> {
                                     // aligned along the y axis)
                                     UnitQuaternion::from_axis_angle(
                                         &UnitVector3::new_normalize(
-                                            (geometry.transform * Vector4::y()).xyz(),
+                                            (transform * Vector4::y()).xyz(),
                                         ),
                                         0.0,
                                     ),
@@ -77,6 +117,17 @@ impl<'a> PopulateScene for PopulateWithNec<'a> {
             }
         }
 
+        // NEC's ground is the plane z = 0, with the structure above it.
+        if let GroundPlaneFlag::Present { .. } = self.nec_file.ground_plane_flag {
+            add_ground_plane_with_normal(
+                scene,
+                GroundPlane::Infinite,
+                Point3::origin(),
+                &(self.import_options.linear_map() * Vector3::z()),
+                Vector2::zeros(),
+            );
+        }
+
         Ok(())
     }
 }
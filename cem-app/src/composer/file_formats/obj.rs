@@ -1,19 +1,31 @@
 use std::{
-    convert::Infallible,
+    collections::HashMap,
     path::Path,
 };
 
 use bevy_ecs::name::Name;
-use cem_render::material::Material;
+use cem_render::{
+    material::Material,
+    mesh::LoadMesh,
+};
 use cem_scene::{
     PopulateScene,
     Scene,
+    material_groups::MaterialGroups,
+    spatial::Collider,
     transform::LocalTransform,
 };
+use cem_solver::material::Material as EmMaterial;
 use nalgebra::Point3;
-use parry3d::shape::TriMesh;
+use palette::Srgba;
+use parry3d::shape::{
+    TriMesh,
+    TriMeshBuilderError,
+};
 use tobj::LoadOptions;
 
+use crate::composer::import::ImportOptions;
+
 pub type Error = tobj::LoadError;
 
 #[derive(Clone, Debug)]
@@ -23,6 +35,17 @@ pub struct ObjFile {
 }
 
 impl ObjFile {
+    /// All vertex positions, in file coordinates.
+    pub fn points(&self) -> impl Iterator<Item = Point3<f32>> + '_ {
+        self.models.iter().flat_map(|model| {
+            model
+                .mesh
+                .positions
+                .chunks_exact(3)
+                .map(|point| Point3::new(point[0], point[1], point[2]))
+        })
+    }
+
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
         let options = LoadOptions {
             // we are only interested in vertex positions, so we don't need a single index. face
@@ -43,19 +66,92 @@ impl ObjFile {
     }
 }
 
+/// Adds the models of an OBJ file to a scene as one object.
+///
+/// The models are merged into one mesh. Each model becomes a material group
+/// with the render material from the file. EM materials can be assigned to
+/// the groups by material name with [`Self::with_em_material`].
 pub struct PopulateSceneWithObjFile<'a> {
     obj_file: &'a ObjFile,
+    name: String,
     transform: LocalTransform,
+    import_options: ImportOptions,
     material: Material,
+    em_materials: HashMap<String, EmMaterial>,
+}
+
+impl<'a> PopulateSceneWithObjFile<'a> {
+    pub fn new(obj_file: &'a ObjFile, name: impl Into<String>) -> Self {
+        Self {
+            obj_file,
+            name: name.into(),
+            transform: LocalTransform::identity(),
+            import_options: ImportOptions::default(),
+            material: Material::default(),
+            em_materials: HashMap::new(),
+        }
+    }
+
+    pub fn with_transform(mut self, transform: LocalTransform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// How file coordinates are mapped into the scene.
+    pub fn with_import_options(mut self, import_options: ImportOptions) -> Self {
+        self.import_options = import_options;
+        self
+    }
+
+    /// Material for faces of models that don't reference a material.
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    /// EM material for models with the OBJ material `name`.
+    pub fn with_em_material(mut self, name: impl Into<String>, material: EmMaterial) -> Self {
+        self.em_materials.insert(name.into(), material);
+        self
+    }
+
+    fn render_material(&self, material_id: Option<usize>) -> Material {
+        let Some(material) = material_id.and_then(|id| self.obj_file.materials.get(id))
+        else {
+            return self.material;
+        };
+
+        let mut render_material = self.material;
+        if let Some([red, green, blue]) = material.diffuse {
+            render_material.albedo = Srgba::new(red, green, blue, 1.0);
+        }
+        if let Some(dissolve) = material.dissolve {
+            render_material.albedo.alpha = dissolve;
+            render_material.transparent = dissolve < 1.0;
+        }
+        render_material
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PopulateObjError {
+    #[error("OBJ file contains no faces")]
+    Empty,
+    #[error("Invalid triangle mesh")]
+    TriMesh(#[from] TriMeshBuilderError),
 }
 
 impl<'a> PopulateScene for PopulateSceneWithObjFile<'a> {
-    type Error = Infallible;
+    type Error = PopulateObjError;
 
     fn populate_scene(&self, scene: &mut Scene) -> Result<(), Self::Error> {
-        // todo: does parry expect a windong order? we don't know the winding order of
-        // the model. parry uses counter-clockwise (relative to their coordinate system
-        // with z axis inverted).
+        let linear_map = self.import_options.linear_map();
+        let flip_winding = self.import_options.flips_winding();
+
+        let mut vertices = vec![];
+        let mut indices = vec![];
+        let mut render_groups = MaterialGroups::default();
+        let mut em_groups = MaterialGroups::default();
 
         for model in &self.obj_file.models {
             assert!(model.mesh.face_arities.is_empty(), "non-triangular mesh");
@@ -68,30 +164,74 @@ impl<'a> PopulateScene for PopulateSceneWithObjFile<'a> {
                 "number of indices not a multiple of 3"
             );
 
+            let base_vertex = vertices.len() as u32;
+            let first_face = indices.len() as u32;
+
             // could use bytemuck here, but we need ownership anyway.
-            let vertices = model
-                .mesh
-                .positions
-                .chunks_exact(3)
-                .map(|point| Point3::new(point[0], point[1], point[2]))
-                .collect();
+            vertices.extend(
+                model
+                    .mesh
+                    .positions
+                    .chunks_exact(3)
+                    .map(|point| linear_map * Point3::new(point[0], point[1], point[2])),
+            );
+
+            indices.extend(model.mesh.indices.chunks_exact(3).map(|face| {
+                let mut face = [
+                    base_vertex + face[0],
+                    base_vertex + face[1],
+                    base_vertex + face[2],
+                ];
+                // mirroring the mesh turns the faces inside out
+                if flip_winding {
+                    face.reverse();
+                }
+                face
+            }));
+
+            let faces = first_face..indices.len() as u32;
+            if faces.is_empty() {
+                continue;
+            }
 
-            let indices = model
+            // the faces of each model form a group, named after its material if it has one.
+            let material_name = model
                 .mesh
-                .indices
-                .chunks_exact(3)
-                .map(|face| [face[0], face[1], face[2]])
-                .collect();
-
-            // fixme: this should be integrated with the loader API
-            let _tri_mesh = TriMesh::new(vertices, indices).expect("invalid triangle mesh");
-
-            scene.world.spawn((
-                self.transform,
-                self.material,
-                //MeshFromShape::from(tri_mesh),
-                Name::new(model.name.clone()),
-            ));
+                .material_id
+                .and_then(|id| self.obj_file.materials.get(id))
+                .map(|material| material.name.as_str());
+            let group_name = material_name.unwrap_or(&model.name);
+
+            render_groups.push(
+                group_name,
+                faces.clone(),
+                self.render_material(model.mesh.material_id),
+            );
+            if let Some(em_material) = material_name.and_then(|name| self.em_materials.get(name)) {
+                em_groups.push(group_name, faces, *em_material);
+            }
+        }
+
+        if indices.is_empty() {
+            return Err(PopulateObjError::Empty);
+        }
+
+        // OBJ faces are wound counter-clockwise (in a right-handed coordinate system), same
+        // as parry expects.
+        let tri_mesh = TriMesh::new(vertices, indices)?;
+
+        let mut entity = scene.world.spawn((
+            self.transform,
+            self.material,
+            render_groups,
+            Collider::from(tri_mesh.clone()),
+            LoadMesh::from_shape(tri_mesh, ()),
+            Name::new(self.name.clone()),
+        ));
+        if !em_groups.is_empty() {
+            // the solver only looks at entities with a material. faces without a group are
+            // vacuum.
+            entity.insert((EmMaterial::VACUUM, em_groups));
         }
 
         Ok(())
@@ -1,33 +1,67 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+};
 
+use base64::Engine;
 use bevy_ecs::{
-    component::Component,
     query::With,
-    reflect::ReflectComponent,
     world::World,
 };
-use bevy_reflect::{
-    Reflect,
-    prelude::ReflectDefault,
+use cem_scene::{
+    assets::AssetStore,
+    serde::{
+        SavedIds,
+        WorldDeserialize,
+        WorldSerialize,
+    },
 };
-use cem_scene::serde::WorldSerialize;
+pub use cem_scene_builder::SaveToFile;
+use cem_solver::material::UnitSystem;
 use chrono::{
     DateTime,
     Local,
 };
+use color_eyre::eyre::bail;
+use ron::value::RawValue;
 use serde::{
     Deserialize,
     Serialize,
+    de::DeserializeSeed,
+};
+
+use crate::{
+    Error,
+    solver::{
+        parameters::{
+            ParameterValues,
+            SceneParameters,
+        },
+        units::SceneUnits,
+    },
 };
 
 pub const MAGIC: &str = "cem-project";
-pub const VERSION: u64 = 0;
+
+/// Version of the project file format.
+///
+/// - 0: Entities are identified by their entity ID. They get new
+///   [`StableId`](cem_scene::stable_id::StableId)s when they're loaded.
+/// - 1: Entities are identified by their
+///   [`StableId`](cem_scene::stable_id::StableId) instead of their entity ID.
+pub const VERSION: u64 = 1;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProjectFileData<S> {
     pub magic: Cow<'static, str>,
     pub version: u64,
     pub save_timestamp: DateTime<Local>,
+    #[serde(default)]
+    pub units: UnitSystem,
+    #[serde(default)]
+    pub parameters: ParameterValues,
+    #[serde(default)]
+    pub embedded_assets: EmbeddedAssets,
     pub scene: S,
 }
 
@@ -37,11 +71,176 @@ impl<'world> ProjectFileData<WorldSerialize<'world, With<SaveToFile>>> {
             magic: MAGIC.into(),
             version: VERSION,
             save_timestamp: Local::now(),
+            units: SceneUnits::get(world),
+            parameters: SceneParameters::get(world),
+            embedded_assets: EmbeddedAssets::from_asset_store(world.resource::<AssetStore>()),
             scene: WorldSerialize::<With<SaveToFile>>::new(world),
         }
     }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize, Component, Reflect)]
-#[reflect(Component, Default)]
-pub struct SaveToFile;
+impl ProjectFileData<Box<RawValue>> {
+    /// Parses a project file. The scene is only read by
+    /// [`populate_world`](Self::populate_world).
+    pub fn from_ron(ron: &str) -> Result<Self, Error> {
+        let data: Self = ron::from_str(ron)?;
+        if data.magic != MAGIC {
+            bail!("Not a project file");
+        }
+        if data.version > VERSION {
+            bail!("Unsupported project file version: {}", data.version);
+        }
+        Ok(data)
+    }
+
+    /// Spawns the saved entities into `world`, and restores its units,
+    /// parameters and embedded assets.
+    pub fn populate_world(&self, world: &mut World) -> Result<(), Error> {
+        let mut deserializer = ron::Deserializer::from_str(self.scene.get_ron())?;
+        let ids = if self.version == 0 {
+            SavedIds::Entity
+        }
+        else {
+            SavedIds::Keep
+        };
+        let entities = WorldDeserialize::new(world)
+            .with_ids(ids)
+            .deserialize(&mut deserializer)?;
+        for entity in entities {
+            world.entity_mut(entity).insert(SaveToFile);
+        }
+
+        world.insert_resource(SceneUnits { system: self.units });
+        world.insert_resource(SceneParameters {
+            values: self.parameters.clone(),
+        });
+        self.embedded_assets
+            .insert_into(&mut world.resource_mut::<AssetStore>())?;
+
+        Ok(())
+    }
+}
+
+/// Assets embedded into the project file, by name.
+///
+/// The data is base64-encoded, which is a lot more compact in RON than a list
+/// of bytes.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EmbeddedAssets(pub BTreeMap<String, String>);
+
+impl EmbeddedAssets {
+    pub fn from_asset_store(asset_store: &AssetStore) -> Self {
+        Self(
+            asset_store
+                .embedded()
+                .map(|(name, data)| (name.to_owned(), BASE64.encode(data)))
+                .collect(),
+        )
+    }
+
+    pub fn insert_into(&self, asset_store: &mut AssetStore) -> Result<(), base64::DecodeError> {
+        for (name, data) in &self.0 {
+            asset_store.embed(name, BASE64.decode(data)?);
+        }
+        Ok(())
+    }
+}
+
+const BASE64: base64::engine::GeneralPurpose = base64::engine::general_purpose::STANDARD;
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        component::Component,
+        hierarchy::ChildOf,
+        query::With,
+        reflect::{
+            AppTypeRegistry,
+            ReflectComponent,
+        },
+        world::World,
+    };
+    use bevy_reflect::{
+        Reflect,
+        ReflectDeserialize,
+        ReflectSerialize,
+    };
+    use cem_scene::{
+        assets::AssetStore,
+        stable_id::{
+            StableId,
+            StableIds,
+        },
+    };
+    use serde::{
+        Deserialize,
+        Serialize,
+    };
+
+    use crate::composer::file_formats::project_file::{
+        ProjectFileData,
+        SaveToFile,
+    };
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Component, Reflect)]
+    #[reflect(Component, Serialize, Deserialize)]
+    struct Label(String);
+
+    /// A project saved before entities had stable IDs. The child comes before
+    /// its parent.
+    const PROJECT_V0: &str = r#"(
+    magic: "cem-project",
+    version: 0,
+    save_timestamp: "2025-11-02T14:21:07.532915+01:00",
+    scene: [
+        {
+            "id": 6,
+            "cem_app::composer::file_formats::project_file::tests::Label": ("child"),
+            "bevy_ecs::hierarchy::ChildOf": (5),
+        },
+        {
+            "id": 5,
+            "cem_app::composer::file_formats::project_file::tests::Label": ("parent"),
+        },
+    ],
+)"#;
+
+    fn empty_world() -> World {
+        let mut world = World::new();
+        world.insert_resource(StableIds::default());
+        world.insert_resource(AssetStore::default());
+        let type_registry = AppTypeRegistry::default();
+        type_registry.write().register::<Label>();
+        world.insert_resource(type_registry);
+        world
+    }
+
+    #[test]
+    fn it_loads_version_0() {
+        let mut world = empty_world();
+        ProjectFileData::from_ron(PROJECT_V0)
+            .unwrap()
+            .populate_world(&mut world)
+            .unwrap();
+
+        let mut entities =
+            world.query_filtered::<(&Label, &StableId, Option<&ChildOf>), With<SaveToFile>>();
+        let loaded = entities.iter(&world).collect::<Vec<_>>();
+        assert_eq!(loaded.len(), 2);
+
+        let (_, parent_id, _) = loaded
+            .iter()
+            .find(|(label, _, _)| label.0 == "parent")
+            .unwrap();
+        let (_, child_id, child_of) = loaded
+            .iter()
+            .find(|(label, _, _)| label.0 == "child")
+            .unwrap();
+        assert_ne!(parent_id, child_id);
+
+        let stable_ids = world.resource::<StableIds>();
+        assert_eq!(child_of.map(ChildOf::parent), stable_ids.get(parent_id));
+        assert_eq!(stable_ids.len(), 2);
+    }
+}
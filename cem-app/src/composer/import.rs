@@ -0,0 +1,371 @@
+//! Options for importing geometry from other tools.
+//!
+//! The scene is Y-up and left-handed (see [`cem_render`]), with lengths in
+//! meters. CAD tools and antenna modelling software are often Z-up and
+//! right-handed, and use other units. [`ImportOptions`] maps file coordinates
+//! into the scene. The defaults come from the
+//! [`ComposerConfig`](crate::config::ComposerConfig), and can be changed per
+//! import in the [`ImportDialog`].
+
+use std::path::PathBuf;
+
+use nalgebra::{
+    Matrix3,
+    Point3,
+    Vector3,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::config::ComposerConfig;
+
+/// Which axis points up in the imported file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UpAxis {
+    #[default]
+    Y,
+    Z,
+}
+
+impl UpAxis {
+    pub const ALL: [Self; 2] = [Self::Y, Self::Z];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Y => "Y up",
+            Self::Z => "Z up",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Handedness {
+    #[default]
+    Left,
+    Right,
+}
+
+impl Handedness {
+    pub const ALL: [Self; 2] = [Self::Left, Self::Right];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Left => "Left-handed",
+            Self::Right => "Right-handed",
+        }
+    }
+}
+
+/// Length unit used in the imported file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LengthUnit {
+    #[default]
+    Meter,
+    Centimeter,
+    Millimeter,
+    Inch,
+    Foot,
+}
+
+impl LengthUnit {
+    pub const ALL: [Self; 5] = [
+        Self::Meter,
+        Self::Centimeter,
+        Self::Millimeter,
+        Self::Inch,
+        Self::Foot,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Meter => "m",
+            Self::Centimeter => "cm",
+            Self::Millimeter => "mm",
+            Self::Inch => "in",
+            Self::Foot => "ft",
+        }
+    }
+
+    pub fn in_meters(&self) -> f32 {
+        match self {
+            Self::Meter => 1.0,
+            Self::Centimeter => 0.01,
+            Self::Millimeter => 0.001,
+            Self::Inch => 0.0254,
+            Self::Foot => 0.3048,
+        }
+    }
+}
+
+/// How coordinates in an imported file are mapped into the scene.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ImportOptions {
+    #[serde(default)]
+    pub up_axis: UpAxis,
+
+    #[serde(default)]
+    pub handedness: Handedness,
+
+    #[serde(default)]
+    pub unit: LengthUnit,
+
+    /// Additional scale factor applied after converting the unit.
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            up_axis: Default::default(),
+            handedness: Default::default(),
+            unit: Default::default(),
+            scale: default_scale(),
+        }
+    }
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+impl ImportOptions {
+    /// Factor from file units to scene units.
+    pub fn scale_factor(&self) -> f32 {
+        self.unit.in_meters() * self.scale
+    }
+
+    /// Linear map from file coordinates to scene coordinates.
+    pub fn linear_map(&self) -> Matrix3<f32> {
+        // rows are the scene axes, columns the file axes.
+        #[rustfmt::skip]
+        let axes = match (self.up_axis, self.handedness) {
+            (UpAxis::Y, Handedness::Left) => Matrix3::identity(),
+            (UpAxis::Y, Handedness::Right) => Matrix3::new(
+                1.0, 0.0, 0.0,
+                0.0, 1.0, 0.0,
+                0.0, 0.0, -1.0,
+            ),
+            (UpAxis::Z, Handedness::Left) => Matrix3::new(
+                1.0, 0.0, 0.0,
+                0.0, 0.0, 1.0,
+                0.0, -1.0, 0.0,
+            ),
+            (UpAxis::Z, Handedness::Right) => Matrix3::new(
+                1.0, 0.0, 0.0,
+                0.0, 0.0, 1.0,
+                0.0, 1.0, 0.0,
+            ),
+        };
+
+        axes * self.scale_factor()
+    }
+
+    pub fn transform_point(&self, point: &Point3<f32>) -> Point3<f32> {
+        self.linear_map() * point
+    }
+
+    pub fn transform_vector(&self, vector: &Vector3<f32>) -> Vector3<f32> {
+        self.linear_map() * vector
+    }
+
+    /// Whether the mapping mirrors the geometry, so that faces need to be
+    /// wound the other way round.
+    pub fn flips_winding(&self) -> bool {
+        self.handedness == Handedness::Right
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        let mut response = ui
+            .horizontal(|ui| {
+                let mut response = combo_box(
+                    ui,
+                    "up_axis",
+                    &mut self.up_axis,
+                    &UpAxis::ALL,
+                    UpAxis::label,
+                );
+                response |= combo_box(
+                    ui,
+                    "handedness",
+                    &mut self.handedness,
+                    &Handedness::ALL,
+                    Handedness::label,
+                );
+                response
+            })
+            .inner;
+
+        ui.horizontal(|ui| {
+            ui.label("Unit");
+            response |= combo_box(
+                ui,
+                "unit",
+                &mut self.unit,
+                &LengthUnit::ALL,
+                LengthUnit::label,
+            );
+            response |= ui.add(
+                egui::DragValue::new(&mut self.scale)
+                    .range(1e-6..=1e6)
+                    .speed(0.01)
+                    .prefix("× "),
+            );
+        });
+
+        response
+    }
+}
+
+fn combo_box<T: Copy + PartialEq>(
+    ui: &mut egui::Ui,
+    id_salt: &str,
+    value: &mut T,
+    options: &[T],
+    label: impl Fn(&T) -> &'static str,
+) -> egui::Response {
+    let mut changed = false;
+    let mut response = egui::ComboBox::from_id_salt(ui.id().with(id_salt))
+        .selected_text(label(value))
+        .show_ui(ui, |ui| {
+            for option in options {
+                changed |= ui.selectable_value(value, *option, label(option)).changed();
+            }
+        })
+        .response;
+    if changed {
+        response.mark_changed();
+    }
+    response
+}
+
+/// Dialog shown after a file was parsed, before it's added to a scene.
+#[derive(Debug)]
+pub struct ImportDialog {
+    pub path: PathBuf,
+    pub options: ImportOptions,
+
+    /// Axis-aligned bounds of the file's geometry in file coordinates, for the
+    /// preview.
+    bounds: Option<[Point3<f32>; 2]>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum ImportDialogResult {
+    Import,
+    Cancel,
+}
+
+impl ImportDialog {
+    pub fn new(
+        path: PathBuf,
+        config: &ComposerConfig,
+        points: impl IntoIterator<Item = Point3<f32>>,
+    ) -> Self {
+        let bounds = points.into_iter().fold(None, |bounds, point| {
+            let [min, max] = bounds.unwrap_or([point, point]);
+            Some([min.inf(&point), max.sup(&point)])
+        });
+
+        Self {
+            path,
+            options: config.import,
+            bounds,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<ImportDialogResult> {
+        let mut result = None;
+
+        let file_name = self
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .display()
+            .to_string();
+
+        egui::Window::new(format!("Import {file_name}"))
+            .id(egui::Id::new("import_dialog"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                self.options.ui(ui);
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    axes_preview(ui, &self.options);
+
+                    ui.vertical(|ui| {
+                        let linear_map = self.options.linear_map();
+                        for (index, label) in ["X", "Y", "Z"].into_iter().enumerate() {
+                            let scene_axis = linear_map.column(index).into_owned();
+                            ui.label(format!("File {label} → Scene {}", axis_label(&scene_axis)));
+                        }
+
+                        if let Some([min, max]) = self.bounds {
+                            let size = (linear_map * (max - min)).abs();
+                            ui.label(format!(
+                                "Size: {:.3} × {:.3} × {:.3} m",
+                                size.x, size.y, size.z
+                            ));
+                        }
+                    });
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Import").clicked() {
+                        result = Some(ImportDialogResult::Import);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        result = Some(ImportDialogResult::Cancel);
+                    }
+                });
+            });
+
+        result
+    }
+}
+
+/// Names the scene axis a (scaled) file axis is mapped to, e.g. `-Z`.
+fn axis_label(vector: &Vector3<f32>) -> String {
+    let index = vector.iamax();
+    let sign = if vector[index] < 0.0 { "-" } else { "+" };
+    format!("{sign}{}", ["X", "Y", "Z"][index])
+}
+
+/// Draws the file's axes as they will appear in the scene, seen from the
+/// default camera direction.
+fn axes_preview(ui: &mut egui::Ui, options: &ImportOptions) {
+    let size = 80.0;
+    let (response, painter) = ui.allocate_painter(egui::Vec2::splat(size), egui::Sense::hover());
+    let center = response.rect.center();
+    let length = 0.35 * size;
+
+    // oblique projection: scene x to the right, y up, z (into the screen) to the
+    // upper right.
+    let project = |vector: Vector3<f32>| {
+        center + length * egui::vec2(vector.x + 0.4 * vector.z, -vector.y - 0.3 * vector.z)
+    };
+
+    let axes = options.linear_map();
+    let colors = [
+        egui::Color32::from_rgb(230, 60, 60),
+        egui::Color32::from_rgb(60, 200, 60),
+        egui::Color32::from_rgb(70, 110, 240),
+    ];
+    for (index, (label, color)) in ["X", "Y", "Z"].into_iter().zip(colors).enumerate() {
+        let direction = axes.column(index).normalize();
+        let tip = project(direction);
+        painter.line_segment([center, tip], egui::Stroke::new(2.0, color));
+        painter.text(
+            tip,
+            egui::Align2::CENTER_CENTER,
+            label,
+            egui::FontId::monospace(10.0),
+            color,
+        );
+    }
+}
@@ -1,3 +1,6 @@
+use bevy_ecs::hierarchy::ChildOf;
+use cem_scene::transform::LocalTransform;
+use cem_solver::material::UnitSystem;
 use nalgebra::{
     Vector2,
     Vector3,
@@ -7,13 +10,45 @@ use crate::{
     composer::{
         ComposerState,
         Composers,
-        entity_window::EntityWindow,
+        arrange::{
+            AXIS_LABELS,
+            Alignment,
+            Arrangement,
+        },
+        entity_window::{
+            EntityWindow,
+            SelectionWindow,
+        },
     },
     error::ResultExt,
     menubar::setup_menu,
+    recorder::{
+        self,
+        Recorder,
+    },
     solver::{
-        config::SolverConfig,
+        attach::{
+            attach_target,
+            attach_to,
+            detach,
+            is_attachable,
+        },
+        config::{
+            SolverConfig,
+            SolverConfigSpecifics,
+        },
+        far_field::add_far_field_probe,
+        feed_wizard::FeedWizard,
+        ground_plane::{
+            GroundPlane,
+            add_ground_plane,
+        },
+        refinement::{
+            add_refinement_regions,
+            propose_refinements,
+        },
         runner::SolverRunner,
+        units::SceneUnits,
     },
 };
 
@@ -27,13 +62,15 @@ pub struct ComposerMenuElements<'a> {
 impl<'a> ComposerMenuElements<'a> {
     /// TODO: We might want to split the edit menu into several methods.
     pub fn edit_menu_buttons(&mut self, ui: &mut egui::Ui) {
-        let (has_file_open, can_undo, can_redo, has_selected) = self
+        let (has_file_open, undo_label, redo_label, can_undo, can_redo, has_selected) = self
             .composers
             .with_active_mut(|composer| {
                 (
                     true,
-                    composer.has_undos(),
-                    composer.has_redos(),
+                    composer.undo_label(),
+                    composer.redo_label(),
+                    composer.can_undo(),
+                    composer.can_redo(),
                     !composer.selection().is_empty(),
                 )
             })
@@ -41,12 +78,14 @@ impl<'a> ComposerMenuElements<'a> {
 
         if ui
             .add_enabled(can_undo, egui::Button::new("Undo"))
+            .on_hover_text(undo_label.unwrap_or_default())
             .clicked()
         {
             self.composers.with_active_mut(|composer| composer.undo());
         }
         if ui
             .add_enabled(can_redo, egui::Button::new("Redo"))
+            .on_hover_text(redo_label.unwrap_or_default())
             .clicked()
         {
             self.composers.with_active_mut(|composer| composer.redo());
@@ -98,13 +137,56 @@ impl<'a> ComposerMenuElements<'a> {
             .clicked()
         {
             self.composers.with_selected(|state, entities| {
-                entities.into_iter().for_each(|entity| {
+                if let [entity] = entities[..] {
                     state
                         .scene
                         .world
                         .entity_mut(entity)
                         .insert(EntityWindow::default());
-                });
+                }
+                else {
+                    state.scene.world.insert_resource(SelectionWindow);
+                }
+            });
+        }
+
+        ui.separator();
+
+        let (can_attach, can_detach) = self
+            .composers
+            .with_selected(|state, entities| {
+                let world = &state.scene.world;
+                (
+                    attach_target(world, &entities).is_some(),
+                    entities.iter().any(|entity| {
+                        is_attachable(world, *entity) && world.get::<ChildOf>(*entity).is_some()
+                    }),
+                )
+            })
+            .unwrap_or_default();
+
+        if ui
+            .add_enabled(can_attach, egui::Button::new("Attach to Object"))
+            .on_hover_text("Attach the selected probes, observers and NF2FF boxes to the selected object, so they move with it.")
+            .clicked()
+        {
+            self.composers.with_selected(|state, entities| {
+                if let Some((target, attachable)) = attach_target(&state.scene.world, &entities) {
+                    attach_to(&mut state.scene.world, target, &attachable);
+                }
+            });
+        }
+
+        if ui
+            .add_enabled(can_detach, egui::Button::new("Detach from Object"))
+            .clicked()
+        {
+            self.composers.with_selected(|state, entities| {
+                let attached = entities
+                    .into_iter()
+                    .filter(|entity| is_attachable(&state.scene.world, *entity))
+                    .collect::<Vec<_>>();
+                detach(&mut state.scene.world, &attached);
             });
         }
     }
@@ -136,6 +218,70 @@ impl<'a> ComposerMenuElements<'a> {
         {
             selection.as_mut().unwrap().select_all();
         }
+
+        let mut select_group_roots = self
+            .composers
+            .with_active(|composer| composer.config.views.select_group_roots)
+            .unwrap_or_default();
+        if ui
+            .add_enabled(
+                has_file_open,
+                egui::Checkbox::new(&mut select_group_roots, "Select Groups"),
+            )
+            .on_hover_text(
+                "Clicking on an object in a group selects the whole group. Double-click to select \
+                 the object itself.",
+            )
+            .changed()
+        {
+            self.composers.with_active_mut(|composer| {
+                composer.config.views.select_group_roots = select_group_roots;
+            });
+        }
+    }
+
+    pub fn arrange_submenu_button(&mut self, ui: &mut egui::Ui) {
+        let num_selected = self
+            .composers
+            .with_active_mut(|composer| composer.selection().count())
+            .unwrap_or_default();
+
+        ui.add_enabled_ui(num_selected >= 2, |ui| {
+            ui.menu_button("Arrange", |ui| {
+                let mut arrangement = None;
+
+                for (axis, axis_label) in AXIS_LABELS.into_iter().enumerate() {
+                    ui.menu_button(format!("Align {axis_label}"), |ui| {
+                        for alignment in Alignment::ALL {
+                            if ui.button(alignment.label()).clicked() {
+                                arrangement = Some(Arrangement::Align { axis, alignment });
+                            }
+                        }
+                    });
+                }
+
+                ui.separator();
+
+                for (axis, axis_label) in AXIS_LABELS.into_iter().enumerate() {
+                    let distribute = Arrangement::Distribute { axis };
+                    if ui
+                        .add_enabled(
+                            num_selected >= distribute.min_objects(),
+                            egui::Button::new(format!("Distribute {axis_label}")),
+                        )
+                        .on_hover_text("Space the centers of the selected objects equally")
+                        .clicked()
+                    {
+                        arrangement = Some(distribute);
+                    }
+                }
+
+                if let Some(arrangement) = arrangement {
+                    self.composers
+                        .with_active_mut(|composer| composer.arrange(arrangement));
+                }
+            });
+        });
     }
 
     pub fn camera_submenu_button(&mut self, ui: &mut egui::Ui) {
@@ -221,6 +367,26 @@ impl<'a> ComposerMenuElements<'a> {
 
             ui.separator();
 
+            let mut simulation_view = camera
+                .as_ref()
+                .is_some_and(|camera| camera.simulation_view());
+            if ui
+                .add_enabled(
+                    has_file_open,
+                    egui::Checkbox::new(&mut simulation_view, "Simulation View"),
+                )
+                .on_hover_text(
+                    "Draw dielectric objects see-through, so fields inside and behind them are \
+                     visible. Conductors stay opaque.",
+                )
+                .changed()
+            {
+                camera
+                    .as_mut()
+                    .unwrap()
+                    .set_simulation_view(simulation_view);
+            }
+
             if ui
                 .add_enabled(has_file_open, egui::Button::new("Configure"))
                 .clicked()
@@ -231,6 +397,163 @@ impl<'a> ComposerMenuElements<'a> {
         });
     }
 
+    pub fn material_inspector_button(&mut self, ui: &mut egui::Ui) {
+        let mut active = self
+            .composers
+            .with_active(|composer| composer.material_inspector)
+            .unwrap_or_default();
+
+        if ui
+            .add_enabled(
+                self.composers.has_file_open(),
+                egui::Checkbox::new(&mut active, "Material Inspector"),
+            )
+            .on_hover_text("Show the material the solver assigns to the cell under the pointer.")
+            .changed()
+        {
+            self.composers
+                .with_active_mut(|composer| composer.material_inspector = active);
+        }
+    }
+
+    pub fn dimensions_button(&mut self, ui: &mut egui::Ui) {
+        let mut active = self
+            .composers
+            .with_active(|composer| composer.config.views.show_dimensions)
+            .unwrap_or_default();
+
+        if ui
+            .add_enabled(
+                self.composers.has_file_open(),
+                egui::Checkbox::new(&mut active, "Dimensions"),
+            )
+            .on_hover_text("Show the bounding box of the selection and its size in the viewport.")
+            .changed()
+        {
+            self.composers
+                .with_active_mut(|composer| composer.config.views.show_dimensions = active);
+        }
+    }
+
+    pub fn problems_button(&mut self, ui: &mut egui::Ui) {
+        let (mut open, num_problems) = self
+            .composers
+            .with_active(|composer| {
+                (
+                    composer.problems_window.open,
+                    composer.problems_window.num_problems(),
+                )
+            })
+            .unwrap_or_default();
+
+        if ui
+            .add_enabled(
+                self.composers.has_file_open(),
+                egui::Checkbox::new(&mut open, format!("Problems ({num_problems})")),
+            )
+            .on_hover_text("Show assets that failed to load.")
+            .changed()
+        {
+            self.composers
+                .with_active_mut(|composer| composer.problems_window.open = open);
+        }
+    }
+
+    pub fn measurements_button(&mut self, ui: &mut egui::Ui) {
+        let mut open = self
+            .composers
+            .with_active(|composer| composer.measurements_window.open)
+            .unwrap_or_default();
+
+        if ui
+            .add_enabled(
+                self.composers.has_file_open(),
+                egui::Checkbox::new(&mut open, "Measured Data"),
+            )
+            .on_hover_text("Compare measured S-parameters and patterns with simulations.")
+            .changed()
+        {
+            self.composers
+                .with_active_mut(|composer| composer.measurements_window.open = open);
+        }
+    }
+
+    pub fn results_library_button(&mut self, ui: &mut egui::Ui) {
+        let mut open = self
+            .composers
+            .with_active(|composer| composer.results_library_window.open)
+            .unwrap_or_default();
+
+        if ui
+            .add_enabled(
+                self.composers.has_file_open(),
+                egui::Checkbox::new(&mut open, "Results Library"),
+            )
+            .on_hover_text("Browse past runs of the project.")
+            .changed()
+        {
+            self.composers
+                .with_active_mut(|composer| composer.results_library_window.open = open);
+        }
+    }
+
+    pub fn parameters_button(&mut self, ui: &mut egui::Ui) {
+        let mut open = self
+            .composers
+            .with_active(|composer| composer.parameters_window.open)
+            .unwrap_or_default();
+
+        if ui
+            .add_enabled(
+                self.composers.has_file_open(),
+                egui::Checkbox::new(&mut open, "Parameters"),
+            )
+            .on_hover_text("Edit the parameters materials can depend on.")
+            .changed()
+        {
+            self.composers
+                .with_active_mut(|composer| composer.parameters_window.open = open);
+        }
+    }
+
+    pub fn ports_button(&mut self, ui: &mut egui::Ui) {
+        let mut open = self
+            .composers
+            .with_active(|composer| composer.ports_window.open)
+            .unwrap_or_default();
+
+        if ui
+            .add_enabled(
+                self.composers.has_file_open(),
+                egui::Checkbox::new(&mut open, "Ports"),
+            )
+            .on_hover_text("Number the ports and choose which ones a solver run uses.")
+            .changed()
+        {
+            self.composers
+                .with_active_mut(|composer| composer.ports_window.open = open);
+        }
+    }
+
+    pub fn array_button(&mut self, ui: &mut egui::Ui) {
+        let mut open = self
+            .composers
+            .with_active(|composer| composer.array_window.open)
+            .unwrap_or_default();
+
+        if ui
+            .add_enabled(
+                self.composers.has_file_open(),
+                egui::Checkbox::new(&mut open, "Array Excitation"),
+            )
+            .on_hover_text("Set the amplitudes and phases of sources, and steer the beam.")
+            .changed()
+        {
+            self.composers
+                .with_active_mut(|composer| composer.array_window.open = open);
+        }
+    }
+
     pub fn configure_solver_button(&mut self, ui: &mut egui::Ui) {
         if ui
             .add_enabled(
@@ -244,6 +567,142 @@ impl<'a> ComposerMenuElements<'a> {
         }
     }
 
+    pub fn units_submenu_button(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Units", |ui| {
+            setup_menu(ui);
+
+            let current = self
+                .composers
+                .with_active_mut(|composer| SceneUnits::get(&composer.scene.world));
+
+            for system in UnitSystem::ALL {
+                if ui
+                    .add_enabled(
+                        current.is_some(),
+                        egui::RadioButton::new(current == Some(system), system.label()),
+                    )
+                    .on_hover_text("Converts materials, sources and solver configs.")
+                    .clicked()
+                {
+                    self.composers
+                        .with_active_mut(|composer| composer.set_unit_system(system));
+                }
+            }
+        });
+    }
+
+    pub fn propose_refinement_button(&mut self, ui: &mut egui::Ui) {
+        // the base cell size is taken from the first FDTD solver
+        let base_cell_size = self
+            .composers
+            .with_active_mut(|composer| {
+                composer
+                    .solver_configs
+                    .iter()
+                    .find_map(|solver_config| {
+                        match &solver_config.specifics {
+                            SolverConfigSpecifics::Fdtd(fdtd_config) => {
+                                Some(fdtd_config.resolution.spatial.min() as f32)
+                            }
+                            _ => None,
+                        }
+                    })
+            })
+            .flatten();
+
+        if ui
+            .add_enabled(
+                base_cell_size.is_some(),
+                egui::Button::new("Propose Mesh Refinement"),
+            )
+            .on_hover_text("Add refinement regions around sources, thin features and gaps.")
+            .clicked()
+        {
+            let base_cell_size = base_cell_size.unwrap();
+            self.composers.with_active_mut(|composer| {
+                let proposals = propose_refinements(&mut composer.scene.world, base_cell_size);
+                tracing::debug!(?proposals, "proposed mesh refinement");
+                add_refinement_regions(&mut composer.scene, &proposals);
+            });
+        }
+    }
+
+    pub fn ground_plane_submenu_button(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Add Ground Plane", |ui| {
+            setup_menu(ui);
+
+            for ground_plane in GroundPlane::ALL {
+                if ui
+                    .add_enabled(
+                        self.composers.has_file_open(),
+                        egui::Button::new(ground_plane.label()),
+                    )
+                    .clicked()
+                {
+                    self.composers.with_active_mut(|composer| {
+                        add_ground_plane(&mut composer.scene, ground_plane, Vector2::repeat(0.5));
+                    });
+                }
+            }
+        });
+    }
+
+    pub fn feed_wizard_submenu_button(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Add Feed", |ui| {
+            setup_menu(ui);
+
+            for wizard in FeedWizard::ALL {
+                if ui
+                    .add_enabled(
+                        self.composers.has_file_open(),
+                        egui::Button::new(format!("{}...", wizard.label())),
+                    )
+                    .clicked()
+                {
+                    self.composers.with_active_mut(|composer| {
+                        composer.feed_wizard_window.open = Some(wizard);
+                    });
+                }
+            }
+        });
+    }
+
+    pub fn far_field_probe_button(&mut self, ui: &mut egui::Ui) {
+        if ui
+            .add_enabled(
+                self.composers.has_file_open(),
+                egui::Button::new("Add Far-Field Probe"),
+            )
+            .on_hover_text(
+                "Add an arrow that reports gain and phase over frequency in its direction, \
+                 computed from the nearest NF2FF box.",
+            )
+            .clicked()
+        {
+            self.composers.with_active_mut(|composer| {
+                add_far_field_probe(
+                    &mut composer.scene,
+                    "Far-Field Probe",
+                    LocalTransform::identity(),
+                );
+            });
+        }
+    }
+
+    pub fn batch_run_button(&mut self, ui: &mut egui::Ui) {
+        if ui
+            .add_enabled(
+                self.composers.has_file_open(),
+                egui::Button::new("Cluster Batch Run"),
+            )
+            .on_hover_text("Package the project for a job scheduler, or import its results.")
+            .clicked()
+        {
+            self.composers
+                .with_active_mut(|composer| composer.batch_window.open = true);
+        }
+    }
+
     pub fn solver_run_buttons(&mut self, ui: &mut egui::Ui) {
         let solver_button =
             |solver: &SolverConfig| egui::Button::new(("Run ", &solver.label, " Solver"));
@@ -259,6 +718,12 @@ impl<'a> ComposerMenuElements<'a> {
                         ty = ?solver_config.solver_type(),
                         "run solver"
                     );
+                    composer.scene.world.resource::<Recorder>().record(
+                        recorder::Action::RunSolver {
+                            solver: i,
+                            label: solver_config.label.clone(),
+                        },
+                    );
                     // for now we'll just send the config and scene to the runner to run it. but
                     // we'll need an intermediate step to rasterize/tesselate the scene
                     self.solver_runner
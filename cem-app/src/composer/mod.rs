@@ -1,10 +1,20 @@
+pub mod arrange;
+pub mod assets;
 pub mod camera;
+pub mod dimensions;
 pub mod entity_window;
 pub mod file_formats;
+pub mod import;
 pub mod menubar;
+pub mod navigation_cube;
+pub mod origin;
+pub mod overlays;
 pub mod presets;
+pub mod problems;
 pub mod selection;
 pub mod shape;
+pub mod simulation_view;
+pub mod transform_entry;
 pub mod tree;
 pub mod undo;
 pub mod view;
@@ -23,13 +33,16 @@ use std::{
     },
 };
 
+use base64::Engine;
 use bevy_ecs::{
     entity::Entity,
+    hierarchy::Children,
     name::{
         Name,
         NameOrEntity,
     },
     query::With,
+    reflect::AppTypeRegistry,
     system::{
         In,
         InMut,
@@ -39,27 +52,52 @@ use bevy_ecs::{
 use cem_render::{
     DrawCommandInfo,
     camera::{
+        ActiveCamera,
         CameraConfig,
         CameraProjection,
         ClearColor,
     },
+    components::{
+        OverlayFlags,
+        ViewOverlays,
+    },
     plugin::RenderPlugin,
 };
 use cem_scene::{
     PopulateScene,
     Scene,
     SceneBuilder,
+    assets::AssetStore,
     async_commands::AsyncUpdateTrigger,
     builtin_plugins,
     plugin::Plugin,
-    transform::LocalTransform,
+    schedule,
+    serde::{
+        EntitySerialize,
+        SavedIds,
+        WorldDeserialize,
+    },
+    spatial::Collider,
+    stable_id::{
+        StableId,
+        StableIds,
+    },
+    transform::{
+        GlobalTransform,
+        LocalTransform,
+    },
+};
+use cem_scene_builder::{
+    SaveToFile,
+    SceneBuilderPlugin,
 };
 use cem_solver::{
     fdtd,
     material::{
         Material,
-        PhysicalConstants,
+        UnitSystem,
     },
+    source::Source,
 };
 use cem_util::egui::{
     EguiUtilContextExt,
@@ -76,12 +114,27 @@ use nec_file::NecFile;
 use serde::{
     Deserialize,
     Serialize,
+    de::DeserializeSeed,
 };
 
 use crate::{
     Error,
+    clipboard::EguiClipboardExt,
+    color_scheme,
     composer::{
+        arrange::Arrangement,
+        assets::{
+            CollectedAssets,
+            collect_assets,
+            make_asset_paths_absolute,
+            make_asset_paths_relative,
+            project_directory,
+        },
         camera::CameraWorldMut,
+        dimensions::{
+            dimensions_overlay,
+            selection_aabb,
+        },
         entity_window::{
             EntityWindow,
             show_entity_windows,
@@ -89,22 +142,42 @@ use crate::{
         file_formats::{
             FileFormat,
             guess_file_format_from_path,
-            nec::PopulateWithNec,
-            project_file::{
-                ProjectFileData,
-                SaveToFile,
+            nec::{
+                PopulateWithNec,
+                wire_end_points,
+            },
+            obj::{
+                ObjFile,
+                PopulateSceneWithObjFile,
             },
+            project_file::ProjectFileData,
+        },
+        import::{
+            ImportDialog,
+            ImportDialogResult,
+            ImportOptions,
         },
         menubar::ComposerMenuElements,
+        navigation_cube::NavigationCube,
+        origin::{
+            OriginTarget,
+            move_origin_to,
+        },
+        overlays::view_overlays_ui,
         presets::ExampleScene,
+        problems::ProblemsWindow,
         selection::{
             Selected,
             SelectionWorldMut,
+            update_inherited_outlines,
         },
         tree::ObjectTreeState,
         undo::{
             HadesId,
+            RedoAction,
+            UndoAction,
             UndoBuffer,
+            swap_transforms,
         },
         view::{
             EntityUnderPointer,
@@ -119,9 +192,25 @@ use crate::{
     debug::{
         DebugUi,
         RendererDebugUi,
+        show_schedules_debug,
+    },
+    error::{
+        ErrorHandler,
+        ResultExt,
+    },
+    jobs::{
+        Cancelled,
+        JobHandle,
+        Jobs,
     },
     lipsum,
+    recorder::{
+        self,
+        Recorder,
+    },
     solver::{
+        array::ArrayWindow,
+        batch::BatchWindow,
         config::{
             FixedVolume,
             Parallelization,
@@ -132,9 +221,25 @@ use crate::{
             StopCondition,
             Volume,
         },
+        feed_wizard::FeedWizardWindow,
+        inspector::CellMaterial,
+        library::ResultsLibraryWindow,
+        measured::MeasurementsWindow,
+        observer::{
+            Observer,
+            update_observer_depth_compositing,
+        },
+        parameters::ParametersWindow,
+        port::PortsWindow,
         runner::SolverRunner,
         ui::SolverConfigUiWindow,
+        units::{
+            SceneUnits,
+            switch_unit_system,
+        },
+        waveform::waveform_preview_ui,
     },
+    status_bar::ComposerStatus,
 };
 
 #[derive(Debug)]
@@ -142,6 +247,51 @@ pub struct Composers {
     composers: Vec<ComposerState>,
     active: Option<usize>,
     composer_plugin: ComposerPlugin,
+    jobs: Jobs,
+
+    /// Files that are being parsed in the background
+    pending_imports: Vec<PendingImport>,
+
+    /// Parsed files waiting for the user to confirm the import options. Only
+    /// the first one is shown at a time.
+    import_dialogs: Vec<PendingImportDialog>,
+}
+
+/// A file being parsed by a job. Populating the scene needs the world, so
+/// that's done on the UI thread once parsing finished.
+#[derive(Debug)]
+struct PendingImport {
+    path: PathBuf,
+    config: ComposerConfig,
+    job: JobHandle<ImportedFile>,
+}
+
+/// A parsed file and the dialog that asks how to import it.
+#[derive(Debug)]
+struct PendingImportDialog {
+    dialog: ImportDialog,
+    config: ComposerConfig,
+    imported_file: ImportedFile,
+}
+
+#[derive(Debug)]
+enum ImportedFile {
+    Nec(NecFile),
+    Obj(ObjFile),
+    /// Project files are only parsed once they're opened, since the scene is
+    /// read into the composer's world.
+    Project(String),
+}
+
+impl ImportedFile {
+    /// Points of the geometry in the file, to preview its size.
+    fn points(&self) -> Vec<Point3<f32>> {
+        match self {
+            Self::Nec(nec_file) => wire_end_points(nec_file).collect(),
+            Self::Obj(obj_file) => obj_file.points().collect(),
+            Self::Project(_) => vec![],
+        }
+    }
 }
 
 impl Composers {
@@ -152,12 +302,36 @@ impl Composers {
             composer_plugin: ComposerPlugin {
                 render_plugin,
                 repaint_trigger: ctx.repaint_trigger(),
+                recorder: Default::default(),
             },
+            jobs: Jobs::from_ctx(ctx),
+            pending_imports: vec![],
+            import_dialogs: vec![],
         }
     }
 
+    /// Records the actions in all files opened from now on.
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.composer_plugin.recorder = recorder;
+        self
+    }
+
     pub fn show(&mut self, ctx: &egui::Context) {
-        if self.composers.is_empty() {
+        self.finish_imports(ctx);
+        self.show_import_dialog(ctx);
+
+        if self.composers.is_empty() && !self.pending_imports.is_empty() {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.add_space(100.0);
+                ui.vertical_centered(|ui| {
+                    ui.spinner();
+                    for import in &self.pending_imports {
+                        ui.label(format!("Opening {}", import.path.display()));
+                    }
+                });
+            });
+        }
+        else if self.composers.is_empty() {
             // what is being shown when no file is open
             egui::CentralPanel::default().show(ctx, |ui| {
                 ui.add_space(100.0);
@@ -229,12 +403,17 @@ impl Composers {
 
     /// Creates a new file with an example scene
     pub fn new_file(&mut self, app_config: &AppConfig) {
+        self.composer_plugin
+            .recorder
+            .record(recorder::Action::NewFile);
+
         let mut state =
             ComposerState::new(app_config.composer.clone(), self.composer_plugin.clone());
 
         ExampleScene
             .populate_scene(&mut state.scene)
             .expect("populating example scene failed");
+        recorder::assign_file_ids(&mut state.scene.world, false);
 
         //PresetScene.populate_scene(&mut state.scene).expect("populating example scene
         // failed");
@@ -243,6 +422,9 @@ impl Composers {
     }
 
     /// Opens a file and populate the scene with it.
+    ///
+    /// The file is parsed in a background job. The composer is opened once
+    /// it's done.
     pub fn open_file(
         &mut self,
         app_config: &AppConfig,
@@ -250,39 +432,208 @@ impl Composers {
     ) -> Result<(), Error> {
         let path = path.as_ref();
         tracing::debug!(path = %path.display(), "open file");
+        self.composer_plugin
+            .recorder
+            .record(recorder::Action::OpenFile {
+                path: path.to_owned(),
+            });
 
-        if let Some(file_format) = guess_file_format_from_path(path) {
-            #[allow(unreachable_patterns)]
-            match file_format {
-                FileFormat::Nec => {
-                    let reader = BufReader::new(File::open(path)?);
-                    let nec_file = NecFile::from_reader(reader)?;
-                    tracing::debug!("{nec_file:#?}");
-
-                    let mut state = ComposerState::new(
-                        app_config.composer.clone(),
-                        self.composer_plugin.clone(),
-                    );
-
-                    state.set_path(path);
+        let Some(file_format) = guess_file_format_from_path(path)
+        else {
+            bail!("Unknown file format: {}", path.display());
+        };
 
-                    PopulateWithNec {
-                        nec_file: &nec_file,
-                        material: palette::named::ORANGERED.into(),
+        #[allow(unreachable_patterns)]
+        let job = match file_format {
+            FileFormat::Cem => {
+                let file_name = path.file_name().unwrap_or_default().display().to_string();
+                self.jobs.spawn(format!("Open {file_name}"), {
+                    let path = path.to_owned();
+                    move |job| {
+                        job.set_message("Reading");
+                        Ok(ImportedFile::Project(std::fs::read_to_string(&path)?))
                     }
-                    .populate_scene(&mut state.scene)?;
+                })
+            }
+            FileFormat::Nec => {
+                let file_name = path.file_name().unwrap_or_default().display().to_string();
+                self.jobs.spawn(format!("Import {file_name}"), {
+                    let path = path.to_owned();
+                    move |job| {
+                        job.set_message("Parsing");
+                        let reader = BufReader::new(File::open(&path)?);
+                        let nec_file = NecFile::from_reader(reader)?;
+                        tracing::debug!("{nec_file:#?}");
+                        Ok(ImportedFile::Nec(nec_file))
+                    }
+                })
+            }
+            FileFormat::Obj => {
+                let file_name = path.file_name().unwrap_or_default().display().to_string();
+                self.jobs.spawn(format!("Import {file_name}"), {
+                    let path = path.to_owned();
+                    move |job| {
+                        job.set_message("Parsing");
+                        Ok(ImportedFile::Obj(ObjFile::from_file(&path)?))
+                    }
+                })
+            }
+            _ => bail!("Unsupported file format: {file_format:?}"),
+        };
+
+        self.pending_imports.push(PendingImport {
+            path: path.to_owned(),
+            config: app_config.composer.clone(),
+            job,
+        });
 
-                    state.camera().fit_to_scene(&Default::default());
+        Ok(())
+    }
 
-                    self.open_composer(state);
+    /// Opens composers for all imports whose jobs are done.
+    fn finish_imports(&mut self, ctx: &egui::Context) {
+        let mut index = 0;
+        while index < self.pending_imports.len() {
+            let Some(result) = self.pending_imports[index].job.try_take_result()
+            else {
+                index += 1;
+                continue;
+            };
+
+            let import = self.pending_imports.remove(index);
+            match result {
+                // there's nothing to ask about opening a project
+                Ok(imported_file @ ImportedFile::Project(_)) => {
+                    self.populate_import(
+                        &import.path,
+                        import.config.clone(),
+                        &import.config.import,
+                        imported_file,
+                    )
+                    .ok_or_handle(ctx);
                 }
-                _ => bail!("Unsupported file format: {file_format:?}"),
+                Ok(imported_file) => {
+                    self.import_dialogs.push(PendingImportDialog {
+                        dialog: ImportDialog::new(
+                            import.path,
+                            &import.config,
+                            imported_file.points(),
+                        ),
+                        config: import.config,
+                        imported_file,
+                    });
+                }
+                Err(error) if error.is::<Cancelled>() => {
+                    tracing::debug!(path = %import.path.display(), "import cancelled");
+                }
+                Err(error) => ctx.handle_error(error),
+            }
+        }
+    }
+
+    /// Shows the dialog for the next parsed file, and imports it once the user
+    /// confirms.
+    fn show_import_dialog(&mut self, ctx: &egui::Context) {
+        let Some(pending) = self.import_dialogs.first_mut()
+        else {
+            return;
+        };
+
+        match pending.dialog.show(ctx) {
+            Some(ImportDialogResult::Import) => {
+                let pending = self.import_dialogs.remove(0);
+                self.populate_import(
+                    &pending.dialog.path,
+                    pending.config,
+                    &pending.dialog.options,
+                    pending.imported_file,
+                )
+                .ok_or_handle(ctx);
+            }
+            Some(ImportDialogResult::Cancel) => {
+                let pending = self.import_dialogs.remove(0);
+                tracing::debug!(path = %pending.dialog.path.display(), "import cancelled");
             }
+            None => {}
         }
+    }
+
+    /// Opens a file without a background job, or creates a new one if there's
+    /// no path.
+    ///
+    /// This is for when there's no UI to show progress, e.g. in headless mode.
+    /// Imports use the default options from the config.
+    pub(crate) fn open_file_blocking(
+        &mut self,
+        app_config: &AppConfig,
+        path: Option<&Path>,
+    ) -> Result<(), Error> {
+        let Some(path) = path
+        else {
+            self.new_file(app_config);
+            return Ok(());
+        };
+
+        let Some(file_format) = guess_file_format_from_path(path)
         else {
             bail!("Unknown file format: {}", path.display());
+        };
+
+        #[allow(unreachable_patterns)]
+        let imported_file = match file_format {
+            FileFormat::Cem => ImportedFile::Project(std::fs::read_to_string(path)?),
+            FileFormat::Nec => {
+                let reader = BufReader::new(File::open(path)?);
+                ImportedFile::Nec(NecFile::from_reader(reader)?)
+            }
+            FileFormat::Obj => ImportedFile::Obj(ObjFile::from_file(path)?),
+            _ => bail!("Unsupported file format: {file_format:?}"),
+        };
+
+        self.populate_import(
+            path,
+            app_config.composer.clone(),
+            &app_config.composer.import,
+            imported_file,
+        )
+    }
+
+    fn populate_import(
+        &mut self,
+        path: &Path,
+        config: ComposerConfig,
+        import_options: &ImportOptions,
+        imported_file: ImportedFile,
+    ) -> Result<(), Error> {
+        let mut state = ComposerState::new(config, self.composer_plugin.clone());
+        state.set_path(path);
+
+        let is_project = matches!(imported_file, ImportedFile::Project(_));
+        match imported_file {
+            ImportedFile::Nec(nec_file) => {
+                PopulateWithNec {
+                    nec_file: &nec_file,
+                    material: palette::named::ORANGERED.into(),
+                    import_options: *import_options,
+                }
+                .populate_scene(&mut state.scene)?;
+            }
+            ImportedFile::Obj(obj_file) => {
+                let name = path.file_stem().unwrap_or_default().display().to_string();
+                PopulateSceneWithObjFile::new(&obj_file, name)
+                    .with_import_options(*import_options)
+                    .populate_scene(&mut state.scene)?;
+            }
+            ImportedFile::Project(ron) => {
+                ProjectFileData::from_ron(&ron)?.populate_world(&mut state.scene.world)?;
+            }
         }
 
+        recorder::assign_file_ids(&mut state.scene.world, is_project);
+        state.camera().fit_to_scene(&Default::default());
+
+        self.open_composer(state);
+
         Ok(())
     }
 
@@ -317,13 +668,22 @@ impl Composers {
         self.active().map(f)
     }
 
-    fn with_active_mut<'a, R>(&'a mut self, f: impl FnOnce(&'a mut ComposerState) -> R) -> Option<R>
+    pub(crate) fn with_active_mut<'a, R>(
+        &'a mut self,
+        f: impl FnOnce(&'a mut ComposerState) -> R,
+    ) -> Option<R>
     where
         R: 'a,
     {
         self.active_mut().map(f)
     }
 
+    /// Scene and solver configs of the active composer.
+    pub(crate) fn active_scene_mut(&mut self) -> Option<(&mut Scene, &[SolverConfig])> {
+        self.active_mut()
+            .map(|composer| (&mut composer.scene, composer.solver_configs.as_slice()))
+    }
+
     /// todo: do we want to move this into ComposerMenuElements? It's only used
     /// there at the moment
     fn with_selected<R>(
@@ -347,15 +707,40 @@ impl Composers {
     }
 
     pub fn save_file(&mut self, path: Option<&Path>) -> Result<(), Error> {
+        self.composer_plugin
+            .recorder
+            .record(recorder::Action::SaveFile {
+                path: path.map(ToOwned::to_owned),
+            });
         self.with_active_mut(|state| state.save_file(path))
             .unwrap_or(Ok(()))
     }
+
+    /// Embeds or copies all assets of the active file into the project.
+    ///
+    /// See [`assets::collect_assets`].
+    pub fn collect_assets(&mut self, app_config: &AppConfig) -> Result<(), Error> {
+        let embed_limit = app_config.composer.embed_assets_limit;
+        self.with_active_mut(|state| {
+            let collected = collect_assets(&mut state.scene.world, embed_limit)?;
+            tracing::info!(?collected, "collected assets");
+
+            let CollectedAssets { embedded, copied } = collected;
+            if embedded > 0 || copied > 0 {
+                state.modified = true;
+            }
+
+            Ok(())
+        })
+        .unwrap_or(Ok(()))
+    }
 }
 
 #[derive(Clone, Debug)]
 struct ComposerPlugin {
     pub render_plugin: RenderPlugin,
     pub repaint_trigger: RepaintTrigger,
+    pub recorder: Recorder,
 }
 
 impl Plugin for ComposerPlugin {
@@ -363,12 +748,27 @@ impl Plugin for ComposerPlugin {
         builder.register_plugins(builtin_plugins());
         builder.register_plugin(self.render_plugin.clone());
 
-        // serialize_world relies on this being registered
-        // todo: make serialization a plugin?
-        builder.world.register_component::<SaveToFile>();
+        // serialize_world relies on `SaveToFile` being registered
+        builder.register_plugin(SceneBuilderPlugin);
 
         let repaint_trigger = self.repaint_trigger.clone();
         builder.insert_resource(AsyncUpdateTrigger::new(move || repaint_trigger.repaint()));
+        builder.insert_resource(self.recorder.clone());
+
+        builder.add_systems(
+            schedule::PostUpdate,
+            (
+                simulation_view::update_see_through,
+                update_observer_depth_compositing,
+                update_inherited_outlines,
+                overlays::tag_overlays,
+            ),
+        );
+
+        // the test solver configs are tuned for normalized units
+        builder.insert_resource(SceneUnits {
+            system: UnitSystem::Normalized,
+        });
     }
 }
 
@@ -393,7 +793,10 @@ struct ComposerState {
     /// The camera used to render the scene.
     ///
     /// There will be one per view eventually
-    camera_entity: Entity,
+    camera: StableId,
+
+    /// Navigation cube in the corner of the view
+    navigation_cube: NavigationCube,
 
     /// Stores where in the scene our mouse is pointing.
     ///
@@ -404,25 +807,58 @@ struct ComposerState {
     object_tree: ObjectTreeState,
 
     /// If an context menu is open, which entity is it about
-    context_menu_object: Option<Entity>,
+    context_menu_object: Option<EntityUnderPointer>,
 
     /// Buffer storing undo and redo commands
     undo_buffer: UndoBuffer,
 
     solver_configs: Vec<SolverConfig>,
     solver_config_window: SolverConfigUiWindow,
+
+    /// Show the material the solver would assign to the cell under the
+    /// pointer.
+    material_inspector: bool,
+
+    /// Missing assets and such
+    problems_window: ProblemsWindow,
+
+    /// Measured data to compare simulation results with
+    measurements_window: MeasurementsWindow,
+
+    /// Past runs of the project
+    results_library_window: ResultsLibraryWindow,
+
+    /// Packages the project for a cluster, and imports the results
+    batch_window: BatchWindow,
+
+    /// Scene parameters, e.g. for materials that depend on temperature
+    parameters_window: ParametersWindow,
+
+    /// Builds feed structures with a port
+    feed_wizard_window: FeedWizardWindow,
+
+    ports_window: PortsWindow,
+
+    /// Amplitudes and phases of the sources, and the array factor they produce
+    array_window: ArrayWindow,
 }
 
 impl ComposerState {
     fn new(config: ComposerConfig, composer_plugin: ComposerPlugin) -> Self {
+        let view_config = &config.views.view_3d;
+        let navigation_cube = NavigationCube::new(
+            &composer_plugin.render_plugin,
+            &composer_plugin.repaint_trigger,
+            view_config,
+        );
+
         let mut scene_builder = SceneBuilder::default();
         scene_builder.register_plugin(composer_plugin);
 
         // the only view we have right now
         // todo: don't create camera here. for a proper project file it will be
         // populated by it.
-        let view_config = &config.views.view_3d;
-        let camera_entity = scene_builder
+        let camera = *scene_builder
             .world
             .spawn((
                 LocalTransform::look_at(
@@ -437,23 +873,30 @@ impl ComposerState {
                     gamma: view_config.gamma,
                     ..Default::default()
                 },
+                ViewOverlays::default(),
                 view_config.ambient_light,
                 view_config.point_light,
                 Name::new("camera"),
+                ActiveCamera,
             ))
-            .id();
+            .get::<StableId>()
+            .unwrap();
 
         let undo_buffer = UndoBuffer::new(config.undo_limit, config.redo_limit);
 
         // some test solver configs
         let solver_configs = {
             vec![
-                make_config("CPU (single-threaded)", None),
+                make_config(
+                    "CPU (single-threaded)",
+                    Some(Parallelization::SingleThreaded),
+                ),
                 make_config(
                     "CPU (multi-threaded)",
                     Some(Parallelization::MultiThreaded { num_threads: None }),
                 ),
                 make_config("GPU", Some(Parallelization::Wgpu)),
+                make_config("Automatic", None),
             ]
         };
 
@@ -465,23 +908,42 @@ impl ComposerState {
             title: Default::default(),
             modified: false,
             scene,
-            camera_entity,
+            camera,
+            navigation_cube,
             scene_pointer: Default::default(),
             object_tree: Default::default(),
             context_menu_object: None,
             undo_buffer,
             solver_configs,
+            material_inspector: false,
+            problems_window: ProblemsWindow::default(),
+            measurements_window: MeasurementsWindow::default(),
+            results_library_window: ResultsLibraryWindow::default(),
+            batch_window: BatchWindow::default(),
+            parameters_window: ParametersWindow::default(),
+            feed_wizard_window: FeedWizardWindow::default(),
+            ports_window: PortsWindow::default(),
+            array_window: ArrayWindow::default(),
             solver_config_window: SolverConfigUiWindow::default(),
         }
     }
 
     pub fn show(&mut self, ctx: &egui::Context) {
+        if self
+            .camera()
+            .update_animation(ctx.input(|input| input.time))
+        {
+            ctx.request_repaint();
+        }
+
         // update world
         self.scene.update();
 
         // render world
         self.scene.render();
 
+        SceneUnits::get(&self.scene.world).set_display_units(ctx);
+
         {
             // some input events are rather tricky to get
 
@@ -519,7 +981,7 @@ impl ComposerState {
             }
 
             if let Some(text) = paste {
-                self.paste(&text);
+                self.paste(&text).ok_or_handle(ctx);
             }
 
             if escape {
@@ -543,9 +1005,10 @@ impl ComposerState {
         egui::CentralPanel::default().show(ctx, |ui| {
             {
                 // actually render the scene
+                let camera_entity = self.camera_entity();
                 let view_response = ui.add(
                     SceneView::new(&mut self.scene)
-                        .with_camera(self.camera_entity)
+                        .with_camera(camera_entity)
                         .with_scene_pointer(&mut self.scene_pointer),
                 );
 
@@ -553,6 +1016,8 @@ impl ComposerState {
                     // todo: shift should also remove from selection
 
                     let shift_key = ui.input(|input| input.modifiers.shift);
+                    let enter_group =
+                        !self.config.views.select_group_roots || view_response.double_clicked();
                     let entity = self
                         .scene_pointer
                         .entity_under_pointer
@@ -561,6 +1026,16 @@ impl ComposerState {
 
                     let mut selection = self.selection();
 
+                    // clicks select the whole group, a double-click selects the object itself
+                    let entity = entity.map(|entity| {
+                        if enter_group {
+                            entity
+                        }
+                        else {
+                            selection.group_root(entity)
+                        }
+                    });
+
                     match (entity, shift_key) {
                         (Some(entity), false) => {
                             selection.clear();
@@ -576,14 +1051,143 @@ impl ComposerState {
                     }
                 }
 
+                // the inspector uses the grid of the first FDTD solver
+                if self.material_inspector
+                    && let Some(entity_under_pointer) = &self.scene_pointer.entity_under_pointer
+                    && let Some(solver_config) = self.solver_configs.iter().find(|solver_config| {
+                        matches!(solver_config.specifics, SolverConfigSpecifics::Fdtd(_))
+                    })
+                    && let Some(cell_material) = CellMaterial::at_point(
+                        &mut self.scene,
+                        solver_config,
+                        &entity_under_pointer.point_hovered,
+                    )
+                {
+                    view_response
+                        .clone()
+                        .on_hover_ui_at_pointer(|ui| cell_material.show(ui));
+                }
+
+                view_overlays_ui(ui, &mut self.camera());
+                if self
+                    .camera()
+                    .view_overlays()
+                    .contains(OverlayFlags::NAVIGATION_CUBE)
+                {
+                    self.navigation_cube.show(
+                        ui,
+                        &mut self.scene,
+                        camera_entity,
+                        view_response.rect,
+                    );
+                }
+
+                if self.config.views.show_dimensions
+                    && let Some(aabb) = selection_aabb(&mut self.scene.world)
+                {
+                    dimensions_overlay(ui, &mut self.camera(), &aabb);
+                }
+
                 self.context_menu(&view_response);
             }
         });
 
-        self.solver_config_window
-            .show(ctx, &mut self.solver_configs);
+        self.source_preview(ctx);
+
+        if let Some(solver_configs) = self
+            .solver_config_window
+            .show(ctx, &mut self.solver_configs)
+        {
+            self.undo_buffer
+                .push_undo(UndoAction::EditSolverConfigs { solver_configs });
+        }
 
         show_entity_windows(ctx, &mut self.scene.world);
+        recorder::track_entities(&mut self.scene.world);
+        self.problems_window.show(ctx, &mut self.scene.world);
+        self.results_library_window.show(
+            ctx,
+            self.path.as_deref(),
+            &mut self.scene.world,
+            &self.solver_configs,
+            &mut self.measurements_window,
+        );
+        self.batch_window.show(
+            ctx,
+            self.path.as_deref(),
+            self.modified,
+            &self.solver_configs,
+            &mut self.results_library_window,
+        );
+        self.measurements_window.show(ctx);
+        self.parameters_window.show(ctx, &mut self.scene.world);
+        self.feed_wizard_window.show(ctx, &mut self.scene);
+        self.array_window.show(ctx, &mut self.scene.world);
+
+        if let Some(solver_configs) =
+            self.ports_window
+                .show(ctx, &mut self.scene.world, &mut self.solver_configs)
+        {
+            self.undo_buffer
+                .push_undo(UndoAction::EditSolverConfigs { solver_configs });
+        }
+    }
+
+    /// Shows the waveform of the selected source in the corner of the viewport.
+    fn source_preview(&mut self, ctx: &egui::Context) {
+        let [entity] = self.selection().entities()[..]
+        else {
+            return;
+        };
+
+        let entity_ref = self.scene.world.entity(entity);
+        let Some(source) = entity_ref.get::<Source>().cloned()
+        else {
+            return;
+        };
+        let name = entity_ref
+            .get::<Name>()
+            .map_or_else(|| entity.to_string(), |name| name.to_string());
+        let units = SceneUnits::get(&self.scene.world);
+
+        egui::Window::new(name)
+            .id(egui::Id::new("source_preview"))
+            .anchor(egui::Align2::LEFT_BOTTOM, [8.0, -8.0])
+            .resizable(false)
+            .collapsible(true)
+            .show(ctx, |ui| {
+                waveform_preview_ui(ui, "source_preview", &source, units);
+            });
+    }
+
+    /// What the status bar shows about this composer.
+    pub fn status(&mut self) -> ComposerStatus {
+        let pointer = self
+            .scene_pointer
+            .entity_under_pointer
+            .map(|entity_under_pointer| entity_under_pointer.point_hovered);
+
+        // like the material inspector, this uses the grid of the first FDTD solver
+        let cell = pointer.and_then(|pointer| {
+            let solver_config = self.solver_configs.iter().find(|solver_config| {
+                matches!(solver_config.specifics, SolverConfigSpecifics::Fdtd(_))
+            })?;
+            CellMaterial::at_point(&mut self.scene, solver_config, &pointer)
+        });
+
+        ComposerStatus {
+            units: SceneUnits::get(&self.scene.world),
+            tool: if self.material_inspector {
+                "Material Inspector"
+            }
+            else {
+                "Select"
+            },
+            num_selected: self.selection().count(),
+            selection_aabb: selection_aabb(&mut self.scene.world),
+            pointer,
+            cell,
+        }
     }
 
     pub fn context_menu(&mut self, response: &egui::Response) {
@@ -593,13 +1197,14 @@ impl ComposerState {
             // todo: if the clicked entity is in the selection, we might want to have the
             // context menu be about the whole selection
 
-            self.context_menu_object = self
-                .scene_pointer
-                .entity_under_pointer
-                .map(|entity_under_pointer| entity_under_pointer.entity);
+            self.context_menu_object = self.scene_pointer.entity_under_pointer;
         }
 
-        let Some(entity) = self.context_menu_object
+        let Some(EntityUnderPointer {
+            entity,
+            point_hovered,
+            ..
+        }) = self.context_menu_object
         else {
             return;
         };
@@ -623,7 +1228,8 @@ impl ComposerState {
             }
 
             if ui.button("Paste").clicked() {
-                tracing::debug!("todo: cut");
+                ui.ctx()
+                    .send_viewport_cmd(egui::ViewportCommand::RequestPaste);
             }
 
             ui.separator();
@@ -634,6 +1240,61 @@ impl ComposerState {
 
             ui.separator();
 
+            let observer_corners = {
+                let entity = self.scene.world.entity(entity);
+                entity
+                    .get::<Observer>()
+                    .zip(entity.get::<GlobalTransform>())
+                    .map(|(observer, transform)| {
+                        let half_extents = observer.half_extents;
+                        [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                            .into_iter()
+                            .map(|(x, y)| {
+                                transform.isometry()
+                                    * Point3::new(x * half_extents.x, y * half_extents.y, 0.0)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+            };
+            if let Some(observer_corners) = observer_corners {
+                // the observer is only rendered as texture in the viewport, so we copy it
+                // from the screen.
+                if ui.button("Copy Observer Image").clicked()
+                    && let Some(rect) = self.camera().screen_rect(observer_corners)
+                {
+                    ui.copy_screen_region(rect);
+                }
+
+                ui.separator();
+            }
+
+            if self.scene.world.entity(entity).contains::<Collider>() {
+                ui.menu_button("Move Origin", |ui| {
+                    let mut target = None;
+                    if ui.button("To Center").clicked() {
+                        target = Some(OriginTarget::Center);
+                    }
+                    ui.menu_button("To Bounding Box Corner", |ui| {
+                        for corner in OriginTarget::corners() {
+                            if ui.button(corner.label()).clicked() {
+                                target = Some(corner);
+                            }
+                        }
+                    });
+                    if ui.button("To Picked Point").clicked() {
+                        target = Some(OriginTarget::Point(point_hovered));
+                    }
+
+                    if let Some(target) = target
+                        && !move_origin_to(&mut self.scene.world, entity, target)
+                    {
+                        tracing::warn!(?entity, ?target, "can't move origin");
+                    }
+                });
+
+                ui.separator();
+            }
+
             if ui.button("Properties").clicked() {
                 self.scene
                     .world
@@ -677,6 +1338,9 @@ impl ComposerState {
             }
         };
 
+        // paths are written relative to where the file is saved to
+        make_asset_paths_relative(&mut self.scene.world);
+
         let mut writer = BufWriter::new(File::create(path)?);
         /*ron::Options::default().to_io_writer_pretty(
             writer,
@@ -696,6 +1360,13 @@ impl ComposerState {
     fn set_path(&mut self, path: impl Into<PathBuf>) {
         let path = path.into();
         self.title.set_from_path(&path);
+
+        // the project might move to another directory
+        make_asset_paths_absolute(&mut self.scene.world);
+        self.scene
+            .world
+            .resource_mut::<AssetStore>()
+            .set_project_directory(project_directory(&path));
         self.path = Some(path);
     }
 
@@ -707,32 +1378,160 @@ impl ComposerState {
         self.undo_buffer.has_redos()
     }
 
+    pub fn undo_label(&self) -> Option<String> {
+        self.undo_buffer.iter_undo().next().map(UndoAction::label)
+    }
+
+    pub fn redo_label(&self) -> Option<String> {
+        self.undo_buffer.iter_redo().next().map(RedoAction::label)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.undo_buffer
+            .iter_undo()
+            .next()
+            .is_some_and(UndoAction::can_undo)
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.undo_buffer
+            .iter_redo()
+            .next()
+            .is_some_and(RedoAction::can_redo)
+    }
+
     pub fn undo(&mut self) {
-        //self.undo_buffer.undo_most_recent(&mut self.scene);
+        let Some(undo_action) = self.undo_buffer.pop_undo()
+        else {
+            return;
+        };
+        tracing::debug!(action = undo_action.label(), "undo");
+        self.scene
+            .world
+            .resource::<Recorder>()
+            .record(recorder::Action::Undo);
+
+        match undo_action {
+            UndoAction::EditSolverConfigs { solver_configs } => {
+                let solver_configs = std::mem::replace(&mut self.solver_configs, solver_configs);
+                self.undo_buffer
+                    .push_redo(RedoAction::EditSolverConfigs { solver_configs });
+            }
+            UndoAction::SwitchUnitSystem { previous } => {
+                let system = SceneUnits::get(&self.scene.world);
+                switch_unit_system(&mut self.scene, &mut self.solver_configs, previous);
+                self.undo_buffer
+                    .push_redo(RedoAction::SwitchUnitSystem { system });
+            }
+            UndoAction::MoveEntities { label, transforms } => {
+                let transforms = swap_transforms(&mut self.scene.world, transforms);
+                self.undo_buffer
+                    .push_redo(RedoAction::MoveEntities { label, transforms });
+            }
+            UndoAction::DeleteEntity { .. } | UndoAction::CreateEntity { .. } => {
+                unreachable!("pop_undo doesn't return edits that can't be undone")
+            }
+        }
     }
 
     pub fn redo(&mut self) {
-        tracing::debug!("todo: redo");
+        let Some(redo_action) = self.undo_buffer.pop_redo()
+        else {
+            return;
+        };
+        tracing::debug!(action = redo_action.label(), "redo");
+        self.scene
+            .world
+            .resource::<Recorder>()
+            .record(recorder::Action::Redo);
+
+        match redo_action {
+            RedoAction::EditSolverConfigs { solver_configs } => {
+                let solver_configs = std::mem::replace(&mut self.solver_configs, solver_configs);
+                self.undo_buffer
+                    .push_undo_keep_redo(UndoAction::EditSolverConfigs { solver_configs });
+            }
+            RedoAction::SwitchUnitSystem { system } => {
+                let previous = SceneUnits::get(&self.scene.world);
+                switch_unit_system(&mut self.scene, &mut self.solver_configs, system);
+                self.undo_buffer
+                    .push_undo_keep_redo(UndoAction::SwitchUnitSystem { previous });
+            }
+            RedoAction::MoveEntities { label, transforms } => {
+                let transforms = swap_transforms(&mut self.scene.world, transforms);
+                self.undo_buffer
+                    .push_undo_keep_redo(UndoAction::MoveEntities { label, transforms });
+            }
+            RedoAction::DeleteEntity { .. } => {
+                unreachable!("pop_redo doesn't return edits that can't be redone")
+            }
+        }
+    }
+
+    /// Aligns or distributes the selected objects.
+    ///
+    /// This can be undone.
+    pub fn arrange(&mut self, arrangement: Arrangement) {
+        let entities = self.selection().entities();
+        let moved = arrangement.apply(&mut self.scene.world, &entities);
+        if moved.is_empty() {
+            return;
+        }
+
+        let transforms = moved
+            .into_iter()
+            .map(|(entity, transform)| {
+                (
+                    recorder::stable_id(&mut self.scene.world, entity),
+                    transform,
+                )
+            })
+            .collect();
+        self.undo_buffer.push_undo(UndoAction::MoveEntities {
+            label: arrangement.label(),
+            transforms,
+        });
+    }
+
+    /// Switches the scene to another unit system, see [`switch_unit_system`].
+    ///
+    /// This can be undone.
+    pub fn set_unit_system(&mut self, system: UnitSystem) {
+        let previous = SceneUnits::get(&self.scene.world);
+        if previous != system {
+            switch_unit_system(&mut self.scene, &mut self.solver_configs, system);
+            self.undo_buffer
+                .push_undo(UndoAction::SwitchUnitSystem { previous });
+        }
     }
 
     pub fn selection(&mut self) -> SelectionWorldMut<'_> {
         SelectionWorldMut {
             world: &mut self.scene.world,
-            outline: &self.config.views.selection_outline,
+            outline: color_scheme::palette().selection_outline(self.config.views.selection_outline),
         }
     }
 
+    fn camera_entity(&self) -> Entity {
+        self.scene
+            .world
+            .resource::<StableIds>()
+            .get(&self.camera)
+            .expect("camera was despawned")
+    }
+
     pub fn camera(&mut self) -> CameraWorldMut<'_> {
         CameraWorldMut {
+            camera_entity: self.camera_entity(),
             world: &mut self.scene.world,
-            camera_entity: self.camera_entity,
         }
     }
 
     pub fn open_camera_window(&mut self) {
+        let camera_entity = self.camera_entity();
         self.scene
             .world
-            .entity_mut(self.camera_entity)
+            .entity_mut(camera_entity)
             .insert(EntityWindow::default());
     }
 
@@ -778,61 +1577,94 @@ impl ComposerState {
         });
     }
 
-    pub fn copy(&mut self, _ctx: &egui::Context, _entities: impl IntoIterator<Item = Entity>) {
-        /*
-        // this is rather hacky, doesn't use our local buffer/clipboard extension and
-        // pollutes the OS clipboard.
-        // todo: error handling
+    /// Copies the objects and their children to the clipboard.
+    ///
+    /// They're written like they are in project files, with their
+    /// [`StableId`]s, and sent to the OS clipboard as a data URL with
+    /// [`CLIPBOARD_PREFIX`].
+    pub fn copy(&mut self, ctx: &egui::Context, entities: impl IntoIterator<Item = Entity>) {
+        if let Some(encoded) = self.clipboard_text(entities).ok_or_handle(ctx) {
+            tracing::debug!("copying entities to clipboard: {} bytes", encoded.len());
+            ctx.copy_text(encoded);
+        }
+    }
 
-        let entities = entities
-            .into_iter()
-            .filter_map(|entity| self.scene.serialize(entity))
-            .collect();
+    fn clipboard_text(&self, entities: impl IntoIterator<Item = Entity>) -> Result<String, Error> {
+        let world = &self.scene.world;
 
-        let clipboard = SceneClipboard::Entities { entities };
+        // children are copied with their parents. only objects that would be saved
+        // are copied.
+        let mut stack = entities.into_iter().collect::<Vec<_>>();
+        let mut copied = vec![];
+        while let Some(entity) = stack.pop() {
+            if copied.contains(&entity) || !world.entity(entity).contains::<SaveToFile>() {
+                continue;
+            }
+            copied.push(entity);
+            if let Some(children) = world.get::<Children>(entity) {
+                stack.extend_from_slice(children);
+            }
+        }
 
-        // serialize to json
-        let json = serde_json::to_vec_pretty(&clipboard).unwrap();
+        let type_registry = world.resource::<AppTypeRegistry>().read();
+        let clipboard = SceneClipboard::Entities {
+            entities: copied
+                .into_iter()
+                .map(|entity| {
+                    EntitySerialize {
+                        world,
+                        entity,
+                        type_registry: &type_registry,
+                    }
+                })
+                .collect(),
+        };
 
-        // compress (lz4)
+        let json = serde_json::to_vec(&clipboard)?;
         let compressed = lz4_flex::compress_prepend_size(&json);
 
-        // base64 encode into data url
         let mut encoded = CLIPBOARD_PREFIX.to_owned();
         base64::engine::general_purpose::URL_SAFE.encode_string(&compressed, &mut encoded);
-
-        // send to OS clipboard
-        tracing::debug!("copying entities to clipboard: {} bytes", encoded.len());
-        ctx.copy_text(encoded);
-         */
-        // todo: bevy-migrate: copy/paste
-        tracing::debug!("todo: copy/paste");
+        Ok(encoded)
     }
 
-    pub fn paste(&mut self, _text: &str) {
-        /*if let Some(encoded) = text.strip_prefix(CLIPBOARD_PREFIX) {
-            let mut compressed = Vec::with_capacity(encoded.len());
-            base64::engine::general_purpose::URL_SAFE
-                .decode_vec(encoded, &mut compressed)
-                .unwrap();
+    /// Pastes objects that were copied with [`copy`](Self::copy), and selects
+    /// them.
+    ///
+    /// The pasted objects get new [`StableId`]s. Other text is ignored.
+    pub fn paste(&mut self, text: &str) -> Result<(), Error> {
+        let Some(encoded) = text.strip_prefix(CLIPBOARD_PREFIX)
+        else {
+            return Ok(());
+        };
 
-            let decompressed = lz4_flex::decompress_size_prepended(&compressed).unwrap();
+        let compressed = base64::engine::general_purpose::URL_SAFE.decode(encoded)?;
+        let json = lz4_flex::decompress_size_prepended(&compressed)?;
+        let SceneClipboard::Entities { entities } =
+            serde_json::from_slice::<SceneClipboard<serde_json::Value>>(&json)?;
+
+        // todo: might want to move them, so they don't cover the originals
+        let entities = WorldDeserialize::new(&mut self.scene.world)
+            .with_ids(SavedIds::Renew)
+            .deserialize(serde_json::Value::Array(entities))?;
+
+        // the copies of selected objects are selected instead of them
+        let selected = entities
+            .iter()
+            .copied()
+            .filter(|entity| self.scene.world.entity(*entity).contains::<Selected>())
+            .collect::<Vec<_>>();
+        for entity in &entities {
+            self.scene.world.entity_mut(*entity).insert(SaveToFile);
+        }
 
-            let clipboard: SceneClipboard<DeserializeEntity> =
-                serde_json::from_slice(&decompressed).unwrap();
+        let mut selection = self.selection();
+        selection.clear();
+        for entity in selected {
+            selection.select(entity);
+        }
 
-            match clipboard {
-                SceneClipboard::Entities { entities } => {
-                    // todo: might want to modify them (positions?)
-                    // todo: might want to spawn them in a temporary world to query them
-                    for mut entity in entities {
-                        self.scene.entities.spawn(entity.entity_builder.build());
-                    }
-                }
-            }
-        }*/
-        // todo: bevy-migrate: copy/paste
-        tracing::debug!("todo: copy/paste");
+        Ok(())
     }
 }
 
@@ -849,7 +1681,6 @@ fn make_config(name: &str, parallelization: Option<Parallelization>) -> SolverCo
                 ),
                 half_extents: Vector3::new(0.5, 0.5, 0.0),
             }),
-            physical_constants: PhysicalConstants::REDUCED,
             default_material: Material {
                 // intoduce dissipation
                 eletrical_conductivity: 10.0,
@@ -857,6 +1688,9 @@ fn make_config(name: &str, parallelization: Option<Parallelization>) -> SolverCo
             },
             parallelization,
             memory_limit: Some(200_000_000),
+            parameter_overrides: Default::default(),
+            disabled_ports: Default::default(),
+            events: vec![],
         },
         specifics: SolverConfigSpecifics::Fdtd(SolverConfigFdtd {
             resolution: fdtd::Resolution {
@@ -864,6 +1698,11 @@ fn make_config(name: &str, parallelization: Option<Parallelization>) -> SolverCo
                 temporal: 0.001,
             },
             stop_condition: StopCondition::Never,
+            spatial_order: Default::default(),
+            mesh: Default::default(),
+            watchdog: Default::default(),
+            sparse: None,
+            run_log: Default::default(),
         }),
     }
 }
@@ -898,6 +1737,10 @@ impl DebugUi for &mut Composers {
                     ui.collapsing("Undo Buffer", |ui| {
                         composer.undo_buffer.show_debug(ui);
                     });
+
+                    ui.collapsing("Schedules", |ui| {
+                        show_schedules_debug(&composer.scene.world, ui);
+                    });
                 });
             }
         }
@@ -956,6 +1799,8 @@ fn draw_composer_debug_ui_system(
             ui.label(format!("Total: {:?}", info.total));
             ui.label(format!("Opaque: {:?}", info.num_opaque));
             ui.label(format!("Transparent: {:?}", info.num_transparent));
+            ui.label(format!("See-through: {:?}", info.num_see_through));
+            ui.label(format!("X-ray: {:?}", info.num_x_ray));
             ui.label(format!("Outlines: {:?}", info.num_outlines));
         });
     });
@@ -0,0 +1,340 @@
+//! Navigation cube in the corner of a view.
+//!
+//! The cube lives in its own scene, so it never shows up in the composer's
+//! scene. It's rendered by a camera that is rotated like the view's camera.
+//! Clicking a face, edge or corner of the cube moves the view's camera to look
+//! at the scene from that direction.
+
+use bevy_ecs::{
+    entity::Entity,
+    name::Name,
+    world::World,
+};
+use cem_render::{
+    camera::{
+        CameraConfig,
+        CameraProjection,
+    },
+    material as render_material,
+    mesh::LoadMesh,
+    plugin::RenderPlugin,
+};
+use cem_scene::{
+    Scene,
+    SceneBuilder,
+    async_commands::AsyncUpdateTrigger,
+    builtin_plugins,
+    transform::{
+        GlobalTransform,
+        LocalTransform,
+    },
+};
+use cem_util::egui::RepaintTrigger;
+use nalgebra::{
+    Isometry3,
+    Point2,
+    Point3,
+    Translation3,
+    Vector2,
+    Vector3,
+};
+use palette::WithAlpha;
+use parry3d::{
+    query::RayCast,
+    shape::Cuboid,
+};
+
+use crate::{
+    composer::{
+        camera::CameraWorldMut,
+        view::SceneView,
+    },
+    config::View3dConfig,
+};
+
+const CUBE_HALF_SIZE: f32 = 0.5;
+
+/// Width of the bands along the cube's edges that select an edge or corner
+/// instead of a face.
+const EDGE_WIDTH: f32 = 0.15;
+
+/// Distance of the cube's camera from the cube.
+const CAMERA_DISTANCE: f32 = 3.0;
+
+/// Vertical field of view of the cube's camera, in degrees.
+const CAMERA_FOVY: f32 = 35.0;
+
+/// Size of the cube's view in points.
+const VIEW_SIZE: f32 = 96.0;
+
+/// Distance of the cube's view from the corner of the view.
+const VIEW_MARGIN: f32 = 8.0;
+
+/// The cube's own scene and camera.
+#[derive(Debug)]
+pub struct NavigationCube {
+    scene: Scene,
+    camera_entity: Entity,
+}
+
+impl NavigationCube {
+    /// Creates the cube's scene with the cube and the camera rendering it.
+    pub fn new(
+        render_plugin: &RenderPlugin,
+        repaint_trigger: &RepaintTrigger,
+        view_config: &View3dConfig,
+    ) -> Self {
+        let mut scene_builder = SceneBuilder::default();
+        scene_builder.register_plugins(builtin_plugins());
+        scene_builder.register_plugin(render_plugin.clone());
+        let repaint_trigger = repaint_trigger.clone();
+        scene_builder.insert_resource(AsyncUpdateTrigger::new(move || repaint_trigger.repaint()));
+
+        let cube = Cuboid::new(Vector3::repeat(CUBE_HALF_SIZE));
+        scene_builder.world.spawn((
+            LocalTransform::identity(),
+            LoadMesh::from_shape(cube, ()),
+            render_material::Material::from_albedo(
+                palette::named::LIGHTGRAY.into_format().with_alpha(1.0),
+            ),
+            Name::new("navigation cube"),
+        ));
+
+        let camera_entity = scene_builder
+            .world
+            .spawn((
+                LocalTransform::look_at(
+                    &Point3::from(-CAMERA_DISTANCE * Vector3::z()),
+                    &Point3::origin(),
+                    &Vector3::y_axis(),
+                ),
+                CameraProjection::new(CAMERA_FOVY.to_radians()),
+                CameraConfig::default(),
+                view_config.ambient_light,
+                view_config.point_light,
+                Name::new("navigation cube camera"),
+            ))
+            .id();
+
+        Self {
+            scene: scene_builder.build(),
+            camera_entity,
+        }
+    }
+
+    /// Shows the cube in the bottom-right corner of `viewport`, and moves the
+    /// view's camera when it's clicked.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        scene: &mut Scene,
+        view_camera: Entity,
+        viewport: egui::Rect,
+    ) {
+        self.follow_camera(&scene.world, view_camera);
+        self.scene.update();
+        self.scene.render();
+
+        let rect = egui::Rect::from_min_size(
+            viewport.right_bottom() - egui::Vec2::splat(VIEW_SIZE + VIEW_MARGIN),
+            egui::Vec2::splat(VIEW_SIZE),
+        );
+        let response = ui.put(
+            rect,
+            SceneView::new(&mut self.scene)
+                .with_camera(self.camera_entity)
+                .without_input(),
+        );
+
+        let mut camera = CameraWorldMut {
+            world: &mut self.scene.world,
+            camera_entity: self.camera_entity,
+        };
+        face_labels(ui, &mut camera, rect);
+
+        let Some(region) = response
+            .hover_pos()
+            .and_then(|pointer| region_at(&mut camera, rect, pointer))
+        else {
+            return;
+        };
+
+        if response.on_hover_text(region_label(&region)).clicked() {
+            let (axis, up) = view_along(&region);
+            let mut view_camera = CameraWorldMut {
+                world: &mut scene.world,
+                camera_entity: view_camera,
+            };
+            if let Some(target) =
+                view_camera.transform_fitting_scene_along_axis(&axis, &up, &Vector2::zeros())
+            {
+                view_camera.animate_to(target, ui.input(|input| input.time));
+            }
+        }
+    }
+
+    /// Rotates the cube's camera like the view's camera.
+    fn follow_camera(&mut self, world: &World, view_camera: Entity) {
+        let Some(rotation) = world
+            .get::<GlobalTransform>(view_camera)
+            .map(|transform| transform.isometry().rotation)
+        else {
+            return;
+        };
+        let isometry = Isometry3::from_parts(
+            Translation3::from(-CAMERA_DISTANCE * (rotation * Vector3::z())),
+            rotation,
+        )
+        .cast::<f64>();
+
+        if let Some(mut transform) = self
+            .scene
+            .world
+            .get_mut::<LocalTransform>(self.camera_entity)
+            && transform.isometry != isometry
+        {
+            transform.isometry = isometry;
+        }
+    }
+}
+
+/// Labels the faces of the cube that face its camera.
+fn face_labels(ui: &egui::Ui, camera: &mut CameraWorldMut, rect: egui::Rect) {
+    let Some(camera_position) = camera
+        .world
+        .get::<GlobalTransform>(camera.camera_entity)
+        .map(|transform| transform.position())
+    else {
+        return;
+    };
+    let painter = ui.painter_at(rect);
+
+    for axis in 0..3 {
+        for sign in [-1.0, 1.0] {
+            let mut normal = Vector3::zeros();
+            normal[axis] = sign;
+            let center = Point3::from(CUBE_HALF_SIZE * normal);
+            if (camera_position - center).dot(&normal) <= 0.0 {
+                continue;
+            }
+
+            if let Some(position) = camera
+                .project_to_screen(vec![center])
+                .and_then(|positions| positions.first().copied())
+            {
+                painter.text(
+                    position,
+                    egui::Align2::CENTER_CENTER,
+                    region_label(&normal),
+                    egui::FontId::proportional(11.0),
+                    egui::Color32::from_gray(40),
+                );
+            }
+        }
+    }
+}
+
+/// The part of the cube under the pointer.
+fn region_at(
+    camera: &mut CameraWorldMut,
+    rect: egui::Rect,
+    pointer: egui::Pos2,
+) -> Option<Vector3<f32>> {
+    let pointer = Point2::new(
+        2.0 * (pointer.x - rect.left()) / rect.width() - 1.0,
+        1.0 - 2.0 * (pointer.y - rect.top()) / rect.height(),
+    );
+    let ray = camera.screen_ray(pointer)?;
+
+    let time_of_impact =
+        Cuboid::new(Vector3::repeat(CUBE_HALF_SIZE)).cast_local_ray(&ray, f32::MAX, true)?;
+    Some(cube_region(&ray.point_at(time_of_impact)))
+}
+
+/// Part of the cube a point on its surface is on, as the direction from the
+/// cube's center. Faces have one non-zero component, edges two and corners
+/// three.
+fn cube_region(point: &Point3<f32>) -> Vector3<f32> {
+    point.coords.map(|x| {
+        if x.abs() > CUBE_HALF_SIZE - EDGE_WIDTH {
+            x.signum()
+        }
+        else {
+            0.0
+        }
+    })
+}
+
+/// Direction to look along, and the up vector, to see the scene from `region`.
+///
+/// These are the same as the "Fit Camera to" entries in the camera menu.
+fn view_along(region: &Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+    let axis = -region.normalize();
+    // looking straight down or up, the up vector can't be Y
+    let up = if region.x == 0.0 && region.z == 0.0 {
+        region.y * Vector3::z()
+    }
+    else {
+        Vector3::y()
+    };
+    (axis, up)
+}
+
+/// Name of the view from `region`, e.g. `Top Front Left`.
+fn region_label(region: &Vector3<f32>) -> String {
+    [
+        (region.y, "Top", "Bottom"),
+        (region.z, "Back", "Front"),
+        (region.x, "Right", "Left"),
+    ]
+    .into_iter()
+    .filter(|(component, ..)| *component != 0.0)
+    .map(
+        |(component, positive, negative)| {
+            if component > 0.0 { positive } else { negative }
+        },
+    )
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{
+        Point3,
+        Vector3,
+    };
+
+    use crate::composer::navigation_cube::{
+        cube_region,
+        region_label,
+        view_along,
+    };
+
+    #[test]
+    fn regions_on_the_cube() {
+        assert_eq!(
+            cube_region(&Point3::new(0.5, 0.1, -0.2)),
+            Vector3::new(1.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            cube_region(&Point3::new(0.5, 0.45, 0.0)),
+            Vector3::new(1.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            cube_region(&Point3::new(-0.4, 0.5, -0.45)),
+            Vector3::new(-1.0, 1.0, -1.0)
+        );
+        assert_eq!(
+            region_label(&Vector3::new(-1.0, 1.0, -1.0)),
+            "Top Front Left"
+        );
+    }
+
+    #[test]
+    fn top_and_bottom_views_match_camera_menu() {
+        assert_eq!(view_along(&Vector3::y()), (-Vector3::y(), Vector3::z()));
+        assert_eq!(view_along(&-Vector3::y()), (Vector3::y(), -Vector3::z()));
+        assert_eq!(view_along(&-Vector3::z()), (Vector3::z(), Vector3::y()));
+    }
+}
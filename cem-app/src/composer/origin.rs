@@ -0,0 +1,259 @@
+//! Moving the origin of an object without moving its geometry.
+//!
+//! The geometry itself can't be regenerated (meshes only live on the GPU once
+//! they're loaded), so the [`Collider`] and the mesh get an offset that
+//! cancels out the change of the object's [`LocalTransform`]. The children are
+//! moved the other way, so they stay where they are too.
+
+use bevy_ecs::{
+    entity::Entity,
+    hierarchy::Children,
+    world::World,
+};
+use cem_render::mesh::{
+    LoadMesh,
+    Mesh,
+    MeshOffset,
+};
+use cem_scene::{
+    spatial::{
+        Collider,
+        traits::ComputeAabb,
+    },
+    transform::{
+        GlobalTransform,
+        LocalTransform,
+        propagate_transforms,
+    },
+};
+use nalgebra::{
+    Isometry3,
+    Point3,
+    Translation3,
+    Vector3,
+};
+
+/// Where to move an object's origin to.
+#[derive(Clone, Copy, Debug)]
+pub enum OriginTarget {
+    /// Center of the object's bounding box.
+    Center,
+
+    /// Corner of the object's bounding box. `true` selects the maximum along
+    /// that axis.
+    Corner([bool; 3]),
+
+    /// A point in world space, e.g. the one picked in the view.
+    Point(Point3<f32>),
+}
+
+impl OriginTarget {
+    /// All corners of the bounding box.
+    pub fn corners() -> impl Iterator<Item = Self> {
+        (0..8).map(|i| Self::Corner([i & 1 != 0, i & 2 != 0, i & 4 != 0]))
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::Center => "Center".to_owned(),
+            Self::Corner(corner) => {
+                let sign = |max: bool| if max { '+' } else { '-' };
+                format!(
+                    "{}X {}Y {}Z",
+                    sign(corner[0]),
+                    sign(corner[1]),
+                    sign(corner[2])
+                )
+            }
+            Self::Point(_) => "Picked Point".to_owned(),
+        }
+    }
+
+    /// The target in the object's local frame.
+    ///
+    /// Returns `None` if the object has no geometry to take the bounding box
+    /// from, or the bounding box is unbounded.
+    pub fn local_point(&self, world: &World, entity: Entity) -> Option<Point3<f32>> {
+        match self {
+            Self::Center | Self::Corner(_) => {
+                let aabb = world
+                    .get::<Collider>(entity)?
+                    .compute_aabb(&Isometry3::identity())?;
+                if !aabb.mins.iter().chain(&aabb.maxs).all(|x| x.is_finite()) {
+                    return None;
+                }
+                match self {
+                    Self::Corner(corner) => {
+                        Some(Point3::from(Vector3::from_fn(|i, _| {
+                            if corner[i] {
+                                aabb.maxs[i]
+                            }
+                            else {
+                                aabb.mins[i]
+                            }
+                        })))
+                    }
+                    _ => Some(aabb.center()),
+                }
+            }
+            Self::Point(point) => {
+                let transform = world.get::<GlobalTransform>(entity)?;
+                Some(transform.isometry().inverse_transform_point(point))
+            }
+        }
+    }
+}
+
+/// Moves the origin of `entity` to `target`.
+///
+/// Returns `false` if the target can't be determined for this object.
+pub fn move_origin_to(world: &mut World, entity: Entity, target: OriginTarget) -> bool {
+    propagate_transforms(world);
+
+    if let Some(origin) = target.local_point(world, entity) {
+        move_origin(world, entity, &origin);
+        true
+    }
+    else {
+        false
+    }
+}
+
+/// Moves the origin of `entity` to `origin`, given in its local frame.
+///
+/// The geometry and children of the object stay where they are.
+pub fn move_origin(world: &mut World, entity: Entity, origin: &Point3<f32>) {
+    if *origin == Point3::origin() {
+        return;
+    }
+    let translation = Translation3::from(origin.coords);
+    let inverse = translation.inverse();
+
+    let mut entity_mut = world.entity_mut(entity);
+
+    if let Some(mut transform) = entity_mut.get_mut::<LocalTransform>() {
+        transform.translate_local(&translation);
+    }
+
+    if let Some(mut collider) = entity_mut.get_mut::<Collider>() {
+        let offset = inverse * *collider.offset();
+        *collider = collider.clone().with_offset(offset);
+    }
+
+    if entity_mut.contains::<Mesh>() || entity_mut.contains::<LoadMesh>() {
+        let offset = entity_mut
+            .get::<MeshOffset>()
+            .map_or_else(Isometry3::identity, |offset| offset.isometry);
+        entity_mut.insert(MeshOffset {
+            isometry: inverse * offset,
+        });
+    }
+
+    let children = entity_mut
+        .get::<Children>()
+        .map(|children| children.to_vec())
+        .unwrap_or_default();
+    for child in children {
+        if let Some(mut transform) = world.get_mut::<LocalTransform>(child) {
+            transform.isometry = inverse.cast() * transform.isometry;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        hierarchy::ChildOf,
+        world::World,
+    };
+    use cem_scene::{
+        spatial::{
+            Collider,
+            traits::ComputeAabb,
+        },
+        transform::{
+            GlobalTransform,
+            LocalTransform,
+            propagate_transforms,
+        },
+    };
+    use nalgebra::{
+        Isometry3,
+        Point3,
+        Translation3,
+        UnitQuaternion,
+        Vector3,
+    };
+    use parry3d::shape::Cuboid;
+
+    use crate::composer::origin::{
+        OriginTarget,
+        move_origin_to,
+    };
+
+    fn assert_close(a: &Point3<f32>, b: &Point3<f32>) {
+        assert!((a - b).norm() < 1e-5, "{a} != {b}");
+    }
+
+    #[test]
+    fn geometry_and_children_stay_in_place() {
+        let mut world = World::default();
+
+        let parent = world
+            .spawn((
+                LocalTransform::new(
+                    Translation3::new(1.0, 2.0, 3.0),
+                    UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.5),
+                ),
+                Collider::from(Cuboid::new(Vector3::new(1.0, 2.0, 3.0)))
+                    .with_offset(Isometry3::translation(0.5, 0.0, 0.0)),
+            ))
+            .id();
+        let child = world
+            .spawn((
+                LocalTransform::new(Translation3::new(0.0, 1.0, 0.0), UnitQuaternion::identity()),
+                ChildOf(parent),
+            ))
+            .id();
+        propagate_transforms(&mut world);
+
+        let world_aabb = |world: &World| {
+            let transform = world.get::<GlobalTransform>(parent).unwrap();
+            world
+                .get::<Collider>(parent)
+                .unwrap()
+                .compute_aabb(transform.isometry())
+                .unwrap()
+        };
+        let before = world_aabb(&world);
+        let child_before = world.get::<GlobalTransform>(child).unwrap().position();
+
+        assert!(move_origin_to(
+            &mut world,
+            parent,
+            OriginTarget::Corner([true, false, true])
+        ));
+        propagate_transforms(&mut world);
+
+        let after = world_aabb(&world);
+        assert_close(&before.mins, &after.mins);
+        assert_close(&before.maxs, &after.maxs);
+        assert_close(
+            &child_before,
+            &world.get::<GlobalTransform>(child).unwrap().position(),
+        );
+
+        // the corner is now the origin
+        let local = OriginTarget::Corner([true, false, true])
+            .local_point(&world, parent)
+            .unwrap();
+        assert_close(&local, &Point3::origin());
+
+        assert!(move_origin_to(&mut world, parent, OriginTarget::Center));
+        propagate_transforms(&mut world);
+        assert_close(
+            &world.get::<GlobalTransform>(parent).unwrap().position(),
+            &before.center(),
+        );
+    }
+}
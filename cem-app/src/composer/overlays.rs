@@ -0,0 +1,320 @@
+//! Overlays on top of the scene views.
+//!
+//! Every camera has its own [`ViewOverlays`]. Observer planes and PML regions
+//! are tagged with an [`Overlay`], so the renderer hides them for cameras that
+//! don't show them. The grid, axes, bounding boxes and statistics are painted
+//! on top of the view.
+//!
+//! [`ViewOverlays`]: cem_render::components::ViewOverlays
+
+use bevy_ecs::{
+    entity::Entity,
+    query::{
+        Added,
+        Without,
+    },
+    system::{
+        Commands,
+        Query,
+    },
+    world::World,
+};
+use cem_render::{
+    DrawCommandInfo,
+    components::{
+        Hidden,
+        Overlay,
+        OverlayFlags,
+    },
+};
+use cem_scene::{
+    spatial::{
+        Aabb,
+        Collider,
+        traits::ComputeAabb,
+    },
+    transform::GlobalTransform,
+};
+use cem_solver::fdtd::pml::GradedPml;
+use nalgebra::{
+    Point3,
+    Vector3,
+};
+
+use crate::{
+    composer::camera::CameraWorldMut,
+    solver::observer::Observer,
+};
+
+/// Number of grid lines on each side of the point below the camera.
+const GRID_HALF_LINES: i32 = 20;
+
+/// Every this many grid lines a major line is drawn.
+const GRID_MAJOR_LINES: i32 = 10;
+
+/// Tags observer planes and PML regions with their [`Overlay`].
+pub fn tag_overlays(
+    observers: Query<Entity, Added<Observer>>,
+    pmls: Query<Entity, Added<GradedPml>>,
+    mut commands: Commands,
+) {
+    for entity in &observers {
+        commands.entity(entity).insert(Overlay {
+            flags: OverlayFlags::OBSERVERS,
+        });
+    }
+    for entity in &pmls {
+        commands.entity(entity).insert(Overlay {
+            flags: OverlayFlags::PML,
+        });
+    }
+}
+
+/// Paints the overlays of a view, and the menu to toggle them in its top-right
+/// corner.
+pub fn view_overlays_ui(ui: &mut egui::Ui, camera: &mut CameraWorldMut) {
+    let Some(viewport) = camera.viewport()
+    else {
+        return;
+    };
+    let flags = camera.view_overlays();
+    let painter = ui.painter_at(viewport);
+
+    let camera_position = camera
+        .world
+        .get::<GlobalTransform>(camera.camera_entity)
+        .map(|transform| transform.position());
+    if let Some(camera_position) = camera_position {
+        let spacing = grid_spacing(camera_position.coords.norm());
+
+        if flags.contains(OverlayFlags::GRID) {
+            let segments = grid_segments(&camera_position, spacing);
+            let (major, minor): (Vec<_>, Vec<_>) =
+                segments.into_iter().partition(|(_, major)| *major);
+            let color = ui.visuals().weak_text_color();
+            for (segments, alpha) in [(minor, 0.15), (major, 0.4)] {
+                let stroke = egui::Stroke::new(1.0, color.gamma_multiply(alpha));
+                let segments = segments.into_iter().map(|(segment, _)| segment).collect();
+                for segment in camera.project_segments_to_screen(segments) {
+                    painter.line_segment(segment, stroke);
+                }
+            }
+        }
+
+        if flags.contains(OverlayFlags::AXES) {
+            let length = GRID_MAJOR_LINES as f32 * spacing;
+            let axes = [
+                ("X", Vector3::x(), egui::Color32::from_rgb(230, 70, 70)),
+                ("Y", Vector3::y(), egui::Color32::from_rgb(80, 200, 80)),
+                ("Z", Vector3::z(), egui::Color32::from_rgb(80, 120, 240)),
+            ];
+            for (label, axis, color) in axes {
+                let end = Point3::from(axis * length);
+                let Some([start, end]) = camera
+                    .project_segments_to_screen(vec![[Point3::origin(), end]])
+                    .pop()
+                else {
+                    continue;
+                };
+                painter.line_segment([start, end], egui::Stroke::new(2.0, color));
+                painter.text(
+                    end,
+                    egui::Align2::LEFT_BOTTOM,
+                    label,
+                    egui::FontId::default(),
+                    color,
+                );
+            }
+        }
+    }
+
+    if flags.contains(OverlayFlags::BOUNDING_BOXES) {
+        let segments = object_aabbs(camera.world)
+            .iter()
+            .flat_map(aabb_edges)
+            .collect();
+        let stroke = egui::Stroke::new(1.0, ui.visuals().warn_fg_color.gamma_multiply(0.6));
+        for segment in camera.project_segments_to_screen(segments) {
+            painter.line_segment(segment, stroke);
+        }
+    }
+
+    if flags.contains(OverlayFlags::STATISTICS) {
+        let text = statistics(ui, camera);
+        let galley = painter.layout_no_wrap(
+            text,
+            egui::FontId::monospace(12.0),
+            ui.visuals().text_color(),
+        );
+        let margin = egui::vec2(4.0, 4.0);
+        let rect = egui::Rect::from_min_size(
+            viewport.left_top() + 2.0 * margin,
+            galley.size() + 2.0 * margin,
+        );
+        painter.rect_filled(rect, 4.0, ui.visuals().extreme_bg_color.gamma_multiply(0.8));
+        painter.galley(rect.min + margin, galley, ui.visuals().text_color());
+    }
+
+    ui.scope_builder(
+        egui::UiBuilder::new()
+            .max_rect(viewport.shrink(8.0))
+            .layout(egui::Layout::right_to_left(egui::Align::Min)),
+        |ui| overlays_menu_button(ui, camera),
+    );
+}
+
+fn overlays_menu_button(ui: &mut egui::Ui, camera: &mut CameraWorldMut) {
+    let mut flags = camera.view_overlays();
+    let mut changed = false;
+
+    ui.menu_button("Overlays", |ui| {
+        for overlay in OverlayFlags::ALL {
+            let mut enabled = flags.contains(overlay);
+            if ui.checkbox(&mut enabled, overlay.label()).changed() {
+                flags.set(overlay, enabled);
+                changed = true;
+            }
+        }
+    });
+
+    if changed {
+        camera.set_view_overlays(flags);
+    }
+}
+
+/// Spacing of the grid lines for a camera at `distance` from the origin.
+///
+/// This is a power of 10, such that the grid spans a few times the distance.
+pub fn grid_spacing(distance: f32) -> f32 {
+    10.0f32.powf(distance.max(1e-6).log10().floor() - 1.0)
+}
+
+/// Lines of the grid in the XZ plane around the point below `center`, and
+/// whether they're major lines.
+fn grid_segments(center: &Point3<f32>, spacing: f32) -> Vec<([Point3<f32>; 2], bool)> {
+    let index = [
+        (center.x / spacing).round() as i32,
+        (center.z / spacing).round() as i32,
+    ];
+    let line = |index: i32| index as f32 * spacing;
+    let center = [line(index[0]), line(index[1])];
+    let extent = GRID_HALF_LINES as f32 * spacing;
+
+    (-GRID_HALF_LINES..=GRID_HALF_LINES)
+        .flat_map(|i| {
+            let x = index[0] + i;
+            let z = index[1] + i;
+            [
+                (
+                    [
+                        Point3::new(line(x), 0.0, center[1] - extent),
+                        Point3::new(line(x), 0.0, center[1] + extent),
+                    ],
+                    x % GRID_MAJOR_LINES == 0,
+                ),
+                (
+                    [
+                        Point3::new(center[0] - extent, 0.0, line(z)),
+                        Point3::new(center[0] + extent, 0.0, line(z)),
+                    ],
+                    z % GRID_MAJOR_LINES == 0,
+                ),
+            ]
+        })
+        .collect()
+}
+
+/// Bounding boxes of all visible objects with a bounded collider.
+fn object_aabbs(world: &mut World) -> Vec<Aabb> {
+    world
+        .run_system_cached(
+            |objects: Query<(&Collider, &GlobalTransform), Without<Hidden>>| {
+                objects
+                    .iter()
+                    .filter_map(|(collider, transform)| collider.compute_aabb(transform.isometry()))
+                    .filter(|aabb| aabb.mins.iter().chain(&aabb.maxs).all(|x| x.is_finite()))
+                    .collect()
+            },
+        )
+        .unwrap()
+}
+
+/// The 12 edges of a bounding box.
+pub fn aabb_edges(aabb: &Aabb) -> Vec<[Point3<f32>; 2]> {
+    let corner = |i: usize| {
+        Point3::new(
+            if i & 1 == 0 { aabb.mins.x } else { aabb.maxs.x },
+            if i & 2 == 0 { aabb.mins.y } else { aabb.maxs.y },
+            if i & 4 == 0 { aabb.mins.z } else { aabb.maxs.z },
+        )
+    };
+
+    // corners that differ in exactly one bit share an edge
+    (0..8)
+        .flat_map(|i| {
+            [1, 2, 4]
+                .into_iter()
+                .filter(move |bit| i & bit == 0)
+                .map(move |bit| [corner(i), corner(i | bit)])
+        })
+        .collect()
+}
+
+fn statistics(ui: &egui::Ui, camera: &mut CameraWorldMut) -> String {
+    let frame_time = ui.input(|input| input.stable_dt);
+    let num_objects = camera
+        .world
+        .query::<&GlobalTransform>()
+        .iter(camera.world)
+        .count();
+
+    let mut text = format!(
+        "{:.1} fps ({:.1} ms)\nObjects: {num_objects}",
+        1.0 / frame_time.max(f32::EPSILON),
+        1e3 * frame_time
+    );
+
+    if let Some(info) = camera.world.get::<DrawCommandInfo>(camera.camera_entity) {
+        text.push_str(&format!(
+            "\nDraw: {:.2?}\nOpaque: {}\nTransparent: {}\nSee-through: {}\nX-ray: {}\nOutlines: {}",
+            info.total,
+            info.num_opaque,
+            info.num_transparent,
+            info.num_see_through,
+            info.num_x_ray,
+            info.num_outlines
+        ));
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use cem_scene::spatial::Aabb;
+    use nalgebra::Point3;
+
+    use crate::composer::overlays::{
+        aabb_edges,
+        grid_spacing,
+    };
+
+    #[test]
+    fn grid_spacing_follows_distance() {
+        assert!((grid_spacing(1.5) - 0.1).abs() < 1e-6);
+        assert!((grid_spacing(15.0) - 1.0).abs() < 1e-6);
+        assert!((grid_spacing(0.5) - 0.01).abs() < 1e-6);
+    }
+
+    #[test]
+    fn aabb_has_twelve_axis_aligned_edges() {
+        let aabb = Aabb::new(Point3::new(-1.0, 0.0, 2.0), Point3::new(1.0, 3.0, 4.0));
+        let edges = aabb_edges(&aabb);
+        assert_eq!(edges.len(), 12);
+
+        for [a, b] in edges {
+            let different = (0..3).filter(|i| a[*i] != b[*i]).count();
+            assert_eq!(different, 1);
+        }
+    }
+}
@@ -3,10 +3,7 @@ use std::convert::Infallible;
 use bevy_ecs::name::Name;
 use cem_render::{
     material as render_material,
-    mesh::{
-        IntoGenerateMesh,
-        LoadMesh,
-    },
+    mesh::LoadMesh,
     texture::TextureSource,
 };
 use cem_scene::{
@@ -48,9 +45,16 @@ use crate::{
         },
         tree::ShowInTree,
     },
-    solver::observer::{
-        Observer,
-        test_color_map,
+    solver::{
+        nf2ff::Nf2ffBox,
+        observer::{
+            Observer,
+            test_color_map,
+        },
+        probe::{
+            LineProbe,
+            PointProbe,
+        },
     },
     util::scene::{
         EntityBuilderExt,
@@ -109,7 +113,7 @@ impl PopulateScene for ExampleScene {
                     filter: image::imageops::FilterType::CatmullRom,
                 },
             )),
-            LoadMesh::from_generator(HalfSpace.into_generate_mesh(()).unwrap()),
+            LoadMesh::from_shape(HalfSpace, ()),
             Collider::from(HalfSpace),
             Name::new("Ground"),
         ));
@@ -155,6 +159,10 @@ impl PopulateScene for ExampleScene {
                     field: FieldComponent::E,
                     color_map: test_color_map(1.0, Vector3::z_axis()),
                     half_extents,
+                    post_process: None,
+                    slice: None,
+                    depth_compositing: Default::default(),
+                    history_budget: None,
                 },
                 render_material::LoadAlbedoTexture::new("assets/test_pattern.png"),
                 render_material::Material::from(render_material::presets::OFFICE_PAPER),
@@ -166,6 +174,30 @@ impl PopulateScene for ExampleScene {
             ));
         }
 
+        // probes
+
+        scene.world.spawn((
+            Name::new("Line Probe"),
+            LineProbe::default(),
+            LocalTransform::from(Point3::new(0.0, 0.5, 0.0)),
+        ));
+
+        scene.world.spawn((
+            Name::new("Point Probe"),
+            PointProbe::default(),
+            LocalTransform::from(Point3::new(0.25, 0.5, 0.0)),
+        ));
+
+        scene.world.spawn((
+            Name::new("NF2FF Box"),
+            Nf2ffBox {
+                half_extents: Vector3::repeat(0.2),
+                frequency: 5.0,
+                ..Default::default()
+            },
+            LocalTransform::from(Point3::new(0.0, 0.5, 0.0)),
+        ));
+
         // source
 
         {
@@ -0,0 +1,231 @@
+//! Problems with the scene that the user can fix.
+//!
+//! At the moment these are textures that failed to load. They're shown with a
+//! placeholder (see [`MissingTexture`]), and can be relinked to another file
+//! from here.
+
+use std::path::PathBuf;
+
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    name::NameOrEntity,
+    world::World,
+};
+use cem_render::{
+    material::{
+        LoadAlbedoTexture,
+        LoadMaterialTexture,
+        MissingTexture,
+    },
+    texture::TextureSource,
+};
+use cem_util::egui::{
+    EguiUtilUiExt,
+    FilePickerConfig,
+};
+
+#[derive(Debug, Default)]
+pub struct ProblemsWindow {
+    pub open: bool,
+
+    /// Number of problems when we last looked, so the window can open when
+    /// new ones show up.
+    num_problems: usize,
+}
+
+impl ProblemsWindow {
+    pub fn num_problems(&self) -> usize {
+        self.num_problems
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, world: &mut World) {
+        let problems = [
+            missing_textures::<LoadAlbedoTexture>(world),
+            missing_textures::<LoadMaterialTexture>(world),
+        ]
+        .concat();
+
+        if problems.len() > self.num_problems {
+            self.open = true;
+        }
+        self.num_problems = problems.len();
+
+        let mut action = None;
+
+        egui::Window::new("Problems")
+            .movable(true)
+            .default_size([400.0, 200.0])
+            .open(&mut self.open)
+            .show(ctx, |ui| {
+                if problems.is_empty() {
+                    ui.label("No problems");
+                    return;
+                }
+
+                for problem in &problems {
+                    ui.push_id((problem.entity, problem.kind), |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(&problem.entity_name);
+                            ui.label(format!("{} texture missing", problem.kind.label()))
+                                .on_hover_text(&problem.error);
+
+                            let mut path = None;
+                            if ui
+                                .file_picker_button(&mut path, &FilePickerConfig::Open)
+                                .on_hover_text(format!("Replace {}", problem.source))
+                                .changed()
+                                && let Some(path) = path
+                            {
+                                action = Some(ProblemAction::Relink {
+                                    entity: problem.entity,
+                                    kind: problem.kind,
+                                    path,
+                                });
+                            }
+
+                            if ui
+                                .button("Ignore")
+                                .on_hover_text("Keep the placeholder")
+                                .clicked()
+                            {
+                                action = Some(ProblemAction::Ignore {
+                                    entity: problem.entity,
+                                    kind: problem.kind,
+                                });
+                            }
+                        });
+                    });
+                }
+            });
+
+        match action {
+            Some(ProblemAction::Relink { entity, kind, path }) => {
+                match kind {
+                    TextureKind::Albedo => relink::<LoadAlbedoTexture>(world, entity, path),
+                    TextureKind::Material => relink::<LoadMaterialTexture>(world, entity, path),
+                }
+            }
+            Some(ProblemAction::Ignore { entity, kind }) => {
+                let mut entity = world.entity_mut(entity);
+                match kind {
+                    TextureKind::Albedo => {
+                        entity.remove::<MissingTexture<LoadAlbedoTexture>>();
+                    }
+                    TextureKind::Material => {
+                        entity.remove::<MissingTexture<LoadMaterialTexture>>();
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Problem {
+    entity: Entity,
+    entity_name: String,
+    kind: TextureKind,
+    source: String,
+    error: String,
+}
+
+#[derive(Debug)]
+enum ProblemAction {
+    Relink {
+        entity: Entity,
+        kind: TextureKind,
+        path: PathBuf,
+    },
+    Ignore {
+        entity: Entity,
+        kind: TextureKind,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum TextureKind {
+    Albedo,
+    Material,
+}
+
+impl TextureKind {
+    fn label(&self) -> &'static str {
+        match self {
+            TextureKind::Albedo => "Albedo",
+            TextureKind::Material => "Material",
+        }
+    }
+}
+
+trait TextureLoader: Component + Clone {
+    const KIND: TextureKind;
+
+    fn source(&self) -> &TextureSource;
+
+    fn source_mut(&mut self) -> &mut TextureSource;
+}
+
+impl TextureLoader for LoadAlbedoTexture {
+    const KIND: TextureKind = TextureKind::Albedo;
+
+    fn source(&self) -> &TextureSource {
+        &self.source
+    }
+
+    fn source_mut(&mut self) -> &mut TextureSource {
+        &mut self.source
+    }
+}
+
+impl TextureLoader for LoadMaterialTexture {
+    const KIND: TextureKind = TextureKind::Material;
+
+    fn source(&self) -> &TextureSource {
+        &self.source
+    }
+
+    fn source_mut(&mut self) -> &mut TextureSource {
+        &mut self.source
+    }
+}
+
+fn missing_textures<L: TextureLoader>(world: &mut World) -> Vec<Problem> {
+    let mut query = world.query::<(Entity, NameOrEntity, &MissingTexture<L>)>();
+    query
+        .iter(world)
+        .map(|(entity, name, missing)| {
+            Problem {
+                entity,
+                entity_name: name.to_string(),
+                kind: L::KIND,
+                source: format_source(missing.loader.source()),
+                error: missing.error.clone(),
+            }
+        })
+        .collect()
+}
+
+fn format_source(source: &TextureSource) -> String {
+    match source {
+        TextureSource::File { path, .. } => path.display().to_string(),
+        TextureSource::Asset { path, .. } => path.name(),
+        TextureSource::Memory { label, .. } => label.clone(),
+        TextureSource::Channel { .. } => "channel".to_owned(),
+    }
+}
+
+/// Loads the texture again from another file.
+fn relink<L: TextureLoader>(world: &mut World, entity: Entity, path: PathBuf) {
+    let Some(missing) = world.entity_mut(entity).take::<MissingTexture<L>>()
+    else {
+        return;
+    };
+    tracing::debug!(?entity, path = %path.display(), "relinking texture");
+
+    let mut loader = missing.loader;
+    let source = loader.source_mut();
+    *source = TextureSource::from_path_with_mip_levels(path, source.mip_levels());
+    world.entity_mut(entity).insert(loader);
+}
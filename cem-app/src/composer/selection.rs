@@ -1,8 +1,18 @@
 use bevy_ecs::{
     bundle::Bundle,
     component::Component,
-    entity::Entity,
-    query::With,
+    entity::{
+        Entity,
+        EntityHashMap,
+    },
+    hierarchy::{
+        ChildOf,
+        Children,
+    },
+    query::{
+        Has,
+        With,
+    },
     reflect::ReflectComponent,
     system::{
         Commands,
@@ -58,6 +68,13 @@ impl PropertiesUi for Selectable {
     }
 }
 
+/// Tag component for entities that are outlined, because one of their
+/// ancestors is selected.
+///
+/// See [`update_inherited_outlines`].
+#[derive(Clone, Copy, Debug, Default, Component)]
+pub struct InheritedOutline;
+
 /// System parameter to query and modify the selection.
 ///
 /// All modification are deferred via [`Commands`]
@@ -122,7 +139,7 @@ impl<'w, 's> Selection<'w, 's> {
 #[derive(Debug)]
 pub struct SelectionWorldMut<'a> {
     pub world: &'a mut World,
-    pub outline: &'a Outline,
+    pub outline: Outline,
 }
 
 impl<'a> SelectionWorldMut<'a> {
@@ -136,7 +153,7 @@ impl<'a> SelectionWorldMut<'a> {
                 |(In(f), InRef(outline)): (In<F>, InRef<Outline>), selection: Selection| {
                     f(selection, outline)
                 },
-                (f, self.outline),
+                (f, &self.outline),
             )
             .unwrap()
     }
@@ -176,4 +193,141 @@ impl<'a> SelectionWorldMut<'a> {
             })
             .unwrap()
     }
+
+    /// The outermost selectable ancestor of `entity`, or `entity` itself if it
+    /// has none.
+    ///
+    /// Clicking on an object in a group selects this, so that the group is
+    /// selected as a whole.
+    pub fn group_root(&mut self, entity: Entity) -> Entity {
+        self.world
+            .run_system_cached_with(
+                |In(entity): In<Entity>,
+                 parents: Query<&ChildOf>,
+                 selectable: Query<(), With<Selectable>>| {
+                    parents
+                        .iter_ancestors(entity)
+                        .filter(|ancestor| selectable.contains(*ancestor))
+                        .last()
+                        .unwrap_or(entity)
+                },
+                entity,
+            )
+            .unwrap()
+    }
+}
+
+/// Outlines the descendants of selected entities with their ancestor's
+/// outline, and removes these outlines again when the ancestor isn't selected
+/// anymore.
+pub fn update_inherited_outlines(
+    selected: Query<(Entity, &Outline), With<Selected>>,
+    children: Query<&Children>,
+    is_selected: Query<(), With<Selected>>,
+    inherited: Query<(Entity, Has<Selected>), With<InheritedOutline>>,
+    mut commands: Commands,
+) {
+    let mut outlined = EntityHashMap::default();
+    for (entity, outline) in &selected {
+        for descendant in children.iter_descendants(entity) {
+            if !is_selected.contains(descendant) {
+                outlined.insert(descendant, *outline);
+            }
+        }
+    }
+
+    for (entity, selected) in &inherited {
+        if selected {
+            // the selection owns the outline now
+            commands.entity(entity).remove::<InheritedOutline>();
+        }
+        else if outlined.remove(&entity).is_none() {
+            commands
+                .entity(entity)
+                .remove::<(InheritedOutline, Outline)>();
+        }
+    }
+
+    for (entity, outline) in outlined {
+        commands.entity(entity).insert((InheritedOutline, outline));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        hierarchy::ChildOf,
+        schedule::Schedule,
+        world::World,
+    };
+    use cem_render::material::Outline;
+
+    use crate::composer::selection::{
+        InheritedOutline,
+        Selectable,
+        SelectionWorldMut,
+        update_inherited_outlines,
+    };
+
+    #[test]
+    fn children_are_outlined_with_their_parent() {
+        let mut world = World::default();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_inherited_outlines);
+
+        let parent = world.spawn(Selectable).id();
+        let child = world.spawn((Selectable, ChildOf(parent))).id();
+        let grandchild = world.spawn((Selectable, ChildOf(child))).id();
+        let other = world.spawn(Selectable).id();
+
+        let mut selection = SelectionWorldMut {
+            world: &mut world,
+            outline: Outline::default(),
+        };
+        selection.select(parent);
+        schedule.run(&mut world);
+
+        assert!(world.entity(child).contains::<InheritedOutline>());
+        assert!(world.entity(grandchild).contains::<Outline>());
+        assert!(!world.entity(other).contains::<Outline>());
+
+        // selecting a child directly keeps its outline
+        let mut selection = SelectionWorldMut {
+            world: &mut world,
+            outline: Outline::default(),
+        };
+        selection.select(child);
+        schedule.run(&mut world);
+
+        assert!(!world.entity(child).contains::<InheritedOutline>());
+        assert!(world.entity(child).contains::<Outline>());
+
+        let mut selection = SelectionWorldMut {
+            world: &mut world,
+            outline: Outline::default(),
+        };
+        selection.clear();
+        schedule.run(&mut world);
+
+        assert!(!world.entity(child).contains::<Outline>());
+        assert!(!world.entity(grandchild).contains::<Outline>());
+        assert!(!world.entity(grandchild).contains::<InheritedOutline>());
+    }
+
+    #[test]
+    fn clicks_escalate_to_the_group_root() {
+        let mut world = World::default();
+        let root = world.spawn(Selectable).id();
+        let group = world.spawn(ChildOf(root)).id();
+        let child = world.spawn((Selectable, ChildOf(group))).id();
+        let loose = world.spawn(Selectable).id();
+
+        let mut selection = SelectionWorldMut {
+            world: &mut world,
+            outline: Outline::default(),
+        };
+        assert_eq!(selection.group_root(child), root);
+        assert_eq!(selection.group_root(root), root);
+        assert_eq!(selection.group_root(loose), loose);
+    }
 }
@@ -119,6 +119,12 @@ impl Quad {
     }
 }
 
+impl ShapeName for Quad {
+    fn shape_name(&self) -> &str {
+        "Quad"
+    }
+}
+
 impl ComputeAabb for Quad {
     fn compute_aabb(&self, transform: &Isometry3<f32>) -> Option<Aabb> {
         Some(self.aabb_impl(transform))
@@ -0,0 +1,125 @@
+//! Simulation view: dielectrics are drawn see-through, so the fields inside and
+//! behind them are visible.
+//!
+//! Every object with a physical material that isn't a conductor is tagged with
+//! [`SeeThrough`]. Cameras only draw them semi-transparent if they have
+//! [`CameraConfig::see_through`] enabled, so the materials themselves don't
+//! have to be changed.
+//!
+//! [`CameraConfig::see_through`]: cem_render::camera::CameraConfig::see_through
+
+use bevy_ecs::{
+    entity::Entity,
+    lifecycle::RemovedComponents,
+    query::{
+        Changed,
+        Has,
+        With,
+        Without,
+    },
+    system::{
+        Commands,
+        Query,
+    },
+};
+use cem_render::components::SeeThrough;
+use cem_solver::material::Material as PhysicsMaterial;
+
+/// Conductivity (in S/m) above which a material is drawn like a conductor.
+///
+/// Metals are in the range of 1e6 to 1e8 S/m, while even lossy dielectrics
+/// stay far below this.
+pub const CONDUCTOR_CONDUCTIVITY: f64 = 1e5;
+
+/// Whether objects with this material stay opaque in the simulation view.
+pub fn is_conductor(material: &PhysicsMaterial) -> bool {
+    material.is_perfect_electric_conductor()
+        || material.eletrical_conductivity >= CONDUCTOR_CONDUCTIVITY
+}
+
+/// Tags objects with [`SeeThrough`] if their material is a dielectric.
+pub fn update_see_through(
+    materials: Query<(Entity, &PhysicsMaterial, Has<SeeThrough>), Changed<PhysicsMaterial>>,
+    without_material: Query<(), (With<SeeThrough>, Without<PhysicsMaterial>)>,
+    mut removed: RemovedComponents<PhysicsMaterial>,
+    mut commands: Commands,
+) {
+    for (entity, material, see_through) in &materials {
+        if is_conductor(material) {
+            if see_through {
+                commands.entity(entity).remove::<SeeThrough>();
+            }
+        }
+        else if !see_through {
+            commands.entity(entity).insert(SeeThrough);
+        }
+    }
+
+    for entity in removed.read() {
+        if without_material.contains(entity) {
+            commands.entity(entity).try_remove::<SeeThrough>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        schedule::Schedule,
+        world::World,
+    };
+    use cem_render::components::SeeThrough;
+    use cem_solver::material::Material as PhysicsMaterial;
+
+    use crate::composer::simulation_view::{
+        is_conductor,
+        update_see_through,
+    };
+
+    #[test]
+    fn only_conductors_are_opaque() {
+        assert!(is_conductor(&PhysicsMaterial::PEC));
+        assert!(is_conductor(&PhysicsMaterial {
+            eletrical_conductivity: 5.8e7,
+            ..PhysicsMaterial::VACUUM
+        }));
+        assert!(!is_conductor(&PhysicsMaterial::VACUUM));
+        assert!(!is_conductor(&PhysicsMaterial {
+            relative_permittivity: 4.4,
+            eletrical_conductivity: 1e-3,
+            ..PhysicsMaterial::VACUUM
+        }));
+    }
+
+    #[test]
+    fn dielectrics_are_tagged() {
+        let mut world = World::default();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_see_through);
+
+        let dielectric = world
+            .spawn(PhysicsMaterial {
+                relative_permittivity: 4.4,
+                ..PhysicsMaterial::VACUUM
+            })
+            .id();
+        let conductor = world.spawn(PhysicsMaterial::PEC).id();
+        schedule.run(&mut world);
+
+        assert!(world.entity(dielectric).contains::<SeeThrough>());
+        assert!(!world.entity(conductor).contains::<SeeThrough>());
+
+        *world.get_mut::<PhysicsMaterial>(dielectric).unwrap() = PhysicsMaterial::PEC;
+        world.entity_mut(conductor).remove::<PhysicsMaterial>();
+        schedule.run(&mut world);
+
+        assert!(!world.entity(dielectric).contains::<SeeThrough>());
+
+        world.entity_mut(dielectric).remove::<PhysicsMaterial>();
+        world.entity_mut(conductor).insert(PhysicsMaterial::VACUUM);
+        schedule.run(&mut world);
+
+        assert!(world.entity(conductor).contains::<SeeThrough>());
+        assert!(!world.entity(dielectric).contains::<SeeThrough>());
+    }
+}
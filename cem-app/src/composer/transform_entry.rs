@@ -0,0 +1,368 @@
+//! Numeric entry of an object's transform.
+//!
+//! Unlike the generic UI of [`LocalTransform`], this can show and edit the
+//! transform in world coordinates, move and rotate objects by a typed delta,
+//! and enter rotations as axis and angle.
+//!
+//! [`LocalTransform`]: cem_scene::transform::LocalTransform
+
+use cem_probe::{
+    TrackChanges,
+    nalgebra::EulerAngles,
+    std::DragAngle,
+};
+use nalgebra::{
+    Isometry3,
+    Translation3,
+    Unit,
+    UnitQuaternion,
+    Vector3,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TransformFrame {
+    /// Relative to the parent, or the world if the object has no parent.
+    #[default]
+    Local,
+    World,
+}
+
+impl TransformFrame {
+    pub const ALL: [Self; 2] = [Self::Local, Self::World];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Local => "Local",
+            Self::World => "World",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RotationEntry {
+    #[default]
+    EulerAngles,
+    AxisAngle,
+}
+
+impl RotationEntry {
+    pub const ALL: [Self; 2] = [Self::EulerAngles, Self::AxisAngle];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::EulerAngles => "Euler Angles",
+            Self::AxisAngle => "Axis-Angle",
+        }
+    }
+}
+
+/// Rotation as it's entered in the UI.
+///
+/// This is kept around, so the axis doesn't get lost when the angle is 0, and
+/// the Euler angles don't jump around near the poles while they're edited.
+#[derive(Clone, Copy, Debug)]
+struct RotationInput {
+    euler: EulerAngles,
+    axis: Vector3<f32>,
+    angle: f32,
+}
+
+impl Default for RotationInput {
+    fn default() -> Self {
+        Self {
+            euler: Default::default(),
+            axis: Vector3::z(),
+            angle: 0.0,
+        }
+    }
+}
+
+impl RotationInput {
+    fn set(&mut self, rotation: &UnitQuaternion<f32>) {
+        self.euler = EulerAngles::from(*rotation);
+        if let Some((axis, angle)) = rotation.axis_angle() {
+            self.axis = axis.into_inner();
+            self.angle = angle;
+        }
+        else {
+            self.angle = 0.0;
+        }
+    }
+
+    fn get(&self, entry: RotationEntry) -> UnitQuaternion<f32> {
+        match entry {
+            RotationEntry::EulerAngles => self.euler.into(),
+            RotationEntry::AxisAngle => {
+                Unit::try_new(self.axis, f32::EPSILON)
+                    .map_or_else(UnitQuaternion::identity, |axis| {
+                        UnitQuaternion::from_axis_angle(&axis, self.angle)
+                    })
+            }
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, entry: RotationEntry) -> egui::Response {
+        let mut changed = TrackChanges::default();
+
+        let response = ui
+            .horizontal(|ui| {
+                match entry {
+                    RotationEntry::EulerAngles => {
+                        ui.label("Roll");
+                        changed.track(ui.add(DragAngle::new(&mut self.euler.roll).speed(1.0)));
+                        ui.label("Pitch");
+                        changed.track(ui.add(DragAngle::new(&mut self.euler.pitch).speed(1.0)));
+                        ui.label("Yaw");
+                        changed.track(ui.add(DragAngle::new(&mut self.euler.yaw).speed(1.0)));
+                    }
+                    RotationEntry::AxisAngle => {
+                        ui.label("Axis");
+                        for value in self.axis.iter_mut() {
+                            changed.track(ui.add(egui::DragValue::new(value).speed(0.01)));
+                        }
+                        ui.label("Angle");
+                        changed.track(ui.add(DragAngle::new(&mut self.angle).speed(1.0)));
+                    }
+                }
+            })
+            .response;
+
+        changed.propagated(response)
+    }
+}
+
+/// State of the transform entry, stored in egui's memory.
+#[derive(Clone, Copy, Debug, Default)]
+struct TransformEntryState {
+    frame: TransformFrame,
+    rotation_entry: RotationEntry,
+
+    /// Whether the typed values are applied as a delta.
+    delta: bool,
+    delta_translation: Vector3<f64>,
+    delta_rotation: RotationInput,
+
+    /// Rotation of the transform, as it was entered.
+    rotation: RotationInput,
+}
+
+/// Widget to enter an object's [`LocalTransform`] numerically.
+///
+/// [`LocalTransform`]: cem_scene::transform::LocalTransform
+#[derive(Debug)]
+pub struct TransformEntry<'a> {
+    id: egui::Id,
+    local: &'a mut Isometry3<f64>,
+    parent: Isometry3<f64>,
+}
+
+impl<'a> TransformEntry<'a> {
+    /// `parent` is the global transform of the object's parent, if it has one.
+    pub fn new(
+        id: egui::Id,
+        local: &'a mut Isometry3<f64>,
+        parent: Option<Isometry3<f64>>,
+    ) -> Self {
+        Self {
+            id,
+            local,
+            parent: parent.unwrap_or_else(Isometry3::identity),
+        }
+    }
+}
+
+impl<'a> egui::Widget for TransformEntry<'a> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let mut state = ui
+            .data(|data| data.get_temp::<TransformEntryState>(self.id))
+            .unwrap_or_default();
+        let mut changed = TrackChanges::default();
+
+        let response = egui::Frame::new()
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    for frame in TransformFrame::ALL {
+                        ui.selectable_value(&mut state.frame, frame, frame.label());
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut state.delta, "Delta")
+                        .on_hover_text("Move and rotate by the entered values");
+                    ui.separator();
+                    egui::ComboBox::from_id_salt(self.id.with("rotation_entry"))
+                        .selected_text(state.rotation_entry.label())
+                        .show_ui(ui, |ui| {
+                            for entry in RotationEntry::ALL {
+                                ui.selectable_value(
+                                    &mut state.rotation_entry,
+                                    entry,
+                                    entry.label(),
+                                );
+                            }
+                        });
+                });
+
+                if state.delta {
+                    ui.horizontal(|ui| {
+                        ui.label("Move");
+                        vector_ui(ui, &mut state.delta_translation);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Rotate");
+                        state.delta_rotation.ui(ui, state.rotation_entry);
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Apply").clicked() {
+                            *self.local = apply_delta(
+                                self.local,
+                                &self.parent,
+                                state.frame,
+                                &state.delta_translation,
+                                &state.delta_rotation.get(state.rotation_entry).cast(),
+                            );
+                            changed.changed = true;
+                        }
+                        if ui.button("Reset").clicked() {
+                            state.delta_translation = Vector3::zeros();
+                            state.delta_rotation = Default::default();
+                        }
+                    });
+                }
+                else {
+                    let mut shown = match state.frame {
+                        TransformFrame::Local => *self.local,
+                        TransformFrame::World => self.parent * *self.local,
+                    };
+
+                    let shown_rotation = shown.rotation.cast();
+                    if state
+                        .rotation
+                        .get(state.rotation_entry)
+                        .angle_to(&shown_rotation)
+                        > 1e-5
+                    {
+                        state.rotation.set(&shown_rotation);
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.label("Position");
+                        changed.track(vector_ui(ui, &mut shown.translation.vector));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Rotation");
+                        if changed
+                            .track(state.rotation.ui(ui, state.rotation_entry))
+                            .changed()
+                        {
+                            shown.rotation = state.rotation.get(state.rotation_entry).cast();
+                        }
+                    });
+
+                    if changed.changed {
+                        *self.local = match state.frame {
+                            TransformFrame::Local => shown,
+                            TransformFrame::World => self.parent.inverse() * shown,
+                        };
+                    }
+                }
+            })
+            .response;
+
+        ui.data_mut(|data| data.insert_temp(self.id, state));
+
+        changed.propagated(response)
+    }
+}
+
+fn vector_ui(ui: &mut egui::Ui, vector: &mut Vector3<f64>) -> egui::Response {
+    let mut changed = TrackChanges::default();
+    let response = ui
+        .horizontal(|ui| {
+            for (label, value) in ["X", "Y", "Z"].into_iter().zip(vector.iter_mut()) {
+                ui.label(label);
+                changed.track(ui.add(egui::DragValue::new(value).speed(0.01).suffix(" m")));
+            }
+        })
+        .response;
+    changed.propagated(response)
+}
+
+/// Moves and rotates the local transform `local` by a delta given in `frame`.
+///
+/// In the local frame the object is moved along its own axes. In the world
+/// frame it's moved along the world axes. In both cases it's rotated around
+/// its origin.
+pub fn apply_delta(
+    local: &Isometry3<f64>,
+    parent: &Isometry3<f64>,
+    frame: TransformFrame,
+    translation: &Vector3<f64>,
+    rotation: &UnitQuaternion<f64>,
+) -> Isometry3<f64> {
+    match frame {
+        TransformFrame::Local => {
+            local * Isometry3::from_parts(Translation3::from(*translation), *rotation)
+        }
+        TransformFrame::World => {
+            let world = parent * local;
+            let world = Isometry3::from_parts(
+                Translation3::from(world.translation.vector + translation),
+                rotation * world.rotation,
+            );
+            parent.inverse() * world
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use nalgebra::{
+        Isometry3,
+        Point3,
+        UnitQuaternion,
+        Vector3,
+    };
+
+    use crate::composer::transform_entry::{
+        TransformFrame,
+        apply_delta,
+    };
+
+    #[test]
+    fn delta_in_local_and_world_frame() {
+        let parent = Isometry3::new(Vector3::new(1.0, 0.0, 0.0), Vector3::z() * FRAC_PI_2);
+        let local = Isometry3::translation(0.0, 2.0, 0.0);
+        let world_position = |local: &Isometry3<f64>| (parent * local) * Point3::origin();
+        let before = world_position(&local);
+
+        // the parent is rotated by 90° around Z, so local X is world Y
+        let moved = apply_delta(
+            &local,
+            &parent,
+            TransformFrame::Local,
+            &Vector3::x(),
+            &UnitQuaternion::identity(),
+        );
+        assert!((world_position(&moved) - (before + Vector3::y())).norm() < 1e-5);
+
+        let moved = apply_delta(
+            &local,
+            &parent,
+            TransformFrame::World,
+            &Vector3::x(),
+            &UnitQuaternion::identity(),
+        );
+        assert!((world_position(&moved) - (before + Vector3::x())).norm() < 1e-5);
+
+        // rotating keeps the position
+        let rotated = apply_delta(
+            &local,
+            &parent,
+            TransformFrame::World,
+            &Vector3::zeros(),
+            &UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 1.0),
+        );
+        assert!((world_position(&rotated) - before).norm() < 1e-5);
+    }
+}
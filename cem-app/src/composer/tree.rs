@@ -42,9 +42,12 @@ use serde::{
     Serialize,
 };
 
-use crate::composer::{
-    ComposerState,
-    selection::Selection,
+use crate::{
+    color_scheme,
+    composer::{
+        ComposerState,
+        selection::Selection,
+    },
 };
 
 #[derive(Debug, Default)]
@@ -54,7 +57,8 @@ pub struct ObjectTreeState {
 
 impl ComposerState {
     pub(super) fn object_tree(&mut self, ui: &mut egui::Ui) -> egui::Response {
-        let selection_outline = self.config.views.selection_outline;
+        let selection_outline =
+            color_scheme::palette().selection_outline(self.config.views.selection_outline);
         self.scene
             .world
             .run_system_cached_with(
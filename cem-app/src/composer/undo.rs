@@ -1,9 +1,23 @@
 use std::collections::VecDeque;
 
-use bevy_ecs::entity::Entity;
-use cem_scene::Scene;
+use bevy_ecs::{
+    entity::Entity,
+    world::World,
+};
+use cem_scene::{
+    Scene,
+    stable_id::{
+        StableId,
+        StableIds,
+    },
+    transform::LocalTransform,
+};
+use cem_solver::material::UnitSystem;
 
-use crate::debug::DebugUi;
+use crate::{
+    debug::DebugUi,
+    solver::config::SolverConfig,
+};
 
 // todo: bevy-migrate: undo
 
@@ -41,11 +55,43 @@ impl UndoBuffer {
         }
     }*/
 
+    /// Records a new edit.
+    ///
+    /// This clears the redo buffer.
     pub fn push_undo(&mut self, undo: UndoAction) {
+        self.redo_actions.clear();
+        self.push_undo_keep_redo(undo);
+    }
+
+    /// Records an edit that was made by redoing it.
+    pub fn push_undo_keep_redo(&mut self, undo: UndoAction) {
         self.undo_actions.push_front(undo);
         self.limit_undo_buffer();
     }
 
+    pub fn push_redo(&mut self, redo: RedoAction) {
+        self.redo_actions.push_front(redo);
+        if let Some(redo_limit) = self.redo_limit {
+            self.redo_actions.truncate(redo_limit);
+        }
+    }
+
+    /// Removes the most recent edit, unless it can't be undone yet.
+    pub fn pop_undo(&mut self) -> Option<UndoAction> {
+        if !self.undo_actions.front()?.can_undo() {
+            return None;
+        }
+        self.undo_actions.pop_front()
+    }
+
+    /// Removes the most recently undone edit, unless it can't be redone yet.
+    pub fn pop_redo(&mut self) -> Option<RedoAction> {
+        if !self.redo_actions.front()?.can_redo() {
+            return None;
+        }
+        self.redo_actions.pop_front()
+    }
+
     fn limit_undo_buffer(&mut self) {
         if let Some(undo_limit) = self.undo_limit {
             while self.undo_actions.len() > undo_limit {
@@ -104,13 +150,103 @@ impl UndoBuffer {
 
 #[derive(Debug)]
 pub enum UndoAction {
-    DeleteEntity { hades_ids: Vec<HadesId> },
-    CreateEntity { entity: Entity },
+    DeleteEntity {
+        hades_ids: Vec<HadesId>,
+    },
+    /// Entities are respawned by undo and redo, so they're referred to by their
+    /// stable ID.
+    CreateEntity {
+        entity: StableId,
+    },
+    /// Restores the solver configs as they were before the edit.
+    EditSolverConfigs {
+        solver_configs: Vec<SolverConfig>,
+    },
+    SwitchUnitSystem {
+        previous: UnitSystem,
+    },
+    /// Restores the transforms of objects that were moved, e.g. by aligning
+    /// them.
+    MoveEntities {
+        label: String,
+        transforms: Vec<(StableId, LocalTransform)>,
+    },
+}
+
+impl UndoAction {
+    /// Short description of the edit shown in the UI.
+    pub fn label(&self) -> String {
+        match self {
+            Self::DeleteEntity { hades_ids } => format!("Delete {} object(s)", hades_ids.len()),
+            Self::CreateEntity { .. } => "Create object".to_owned(),
+            Self::EditSolverConfigs { .. } => "Edit solver config".to_owned(),
+            Self::SwitchUnitSystem { previous } => {
+                format!("Switch units (from {})", previous.label())
+            }
+            Self::MoveEntities { label, .. } => label.clone(),
+        }
+    }
+
+    /// Whether this edit can be undone.
+    ///
+    /// Edits that can't be undone yet stay in the buffer, so that they aren't
+    /// lost.
+    pub fn can_undo(&self) -> bool {
+        // todo: bevy-migrate: undo
+        !matches!(self, Self::DeleteEntity { .. } | Self::CreateEntity { .. })
+    }
 }
 
 #[derive(Debug)]
 pub enum RedoAction {
-    DeleteEntity { entity: Entity },
+    DeleteEntity {
+        entity: StableId,
+    },
+    EditSolverConfigs {
+        solver_configs: Vec<SolverConfig>,
+    },
+    SwitchUnitSystem {
+        system: UnitSystem,
+    },
+    MoveEntities {
+        label: String,
+        transforms: Vec<(StableId, LocalTransform)>,
+    },
+}
+
+impl RedoAction {
+    /// Short description of the edit shown in the UI.
+    pub fn label(&self) -> String {
+        match self {
+            Self::DeleteEntity { .. } => "Delete object".to_owned(),
+            Self::EditSolverConfigs { .. } => "Edit solver config".to_owned(),
+            Self::SwitchUnitSystem { system } => format!("Switch units (to {})", system.label()),
+            Self::MoveEntities { label, .. } => label.clone(),
+        }
+    }
+
+    /// Whether this edit can be redone. See [`UndoAction::can_undo`].
+    pub fn can_redo(&self) -> bool {
+        // todo: bevy-migrate: undo
+        !matches!(self, Self::DeleteEntity { .. })
+    }
+}
+
+/// Sets the transforms of the objects and returns the ones they had before.
+///
+/// Objects that were deleted in the meantime are skipped.
+pub fn swap_transforms(
+    world: &mut World,
+    transforms: Vec<(StableId, LocalTransform)>,
+) -> Vec<(StableId, LocalTransform)> {
+    transforms
+        .into_iter()
+        .filter_map(|(id, transform)| {
+            let entity = world.resource::<StableIds>().get(&id)?;
+            let mut current = world.get_mut::<LocalTransform>(entity)?;
+            Some((id, std::mem::replace(&mut *current, transform)))
+        })
+        .collect()
 }
 
 /// It's just an [`hecs::Entity`], but wrapped to avoid mixups.
@@ -125,7 +261,7 @@ impl DebugUi for &UndoBuffer {
         let mut empty = true;
         for undo_action in self.undo_actions.iter().take(10) {
             empty = false;
-            ui.code(format!("{undo_action:?}"));
+            ui.label(undo_action.label());
         }
         if empty {
             ui.label("No undo actions");
@@ -136,10 +272,36 @@ impl DebugUi for &UndoBuffer {
         let mut empty = true;
         for redo_action in self.redo_actions.iter().take(10) {
             empty = false;
-            ui.code(format!("{redo_action:?}"));
+            ui.label(redo_action.label());
         }
         if empty {
             ui.label("No redo actions");
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cem_scene::stable_id::StableId;
+    use cem_solver::material::UnitSystem;
+
+    use crate::composer::undo::{
+        UndoAction,
+        UndoBuffer,
+    };
+
+    #[test]
+    fn edits_that_cant_be_undone_stay_in_buffer() {
+        let mut undo_buffer = UndoBuffer::default();
+        undo_buffer.push_undo(UndoAction::SwitchUnitSystem {
+            previous: UnitSystem::Si,
+        });
+        undo_buffer.push_undo(UndoAction::CreateEntity {
+            entity: StableId::new(),
+        });
+
+        assert!(undo_buffer.pop_undo().is_none());
+        assert!(undo_buffer.pop_undo().is_none());
+        assert_eq!(undo_buffer.iter_undo().count(), 2);
+    }
+}
@@ -29,6 +29,9 @@ pub struct SceneView<'a> {
     scene: &'a mut Scene,
     camera_entity: Option<Entity>,
     scene_pointer: Option<&'a mut ScenePointer>,
+
+    /// Whether the camera is moved by dragging in the view.
+    interactive: bool,
 }
 
 impl<'a> SceneView<'a> {
@@ -37,6 +40,7 @@ impl<'a> SceneView<'a> {
             scene,
             camera_entity: None,
             scene_pointer: None,
+            interactive: true,
         }
     }
 
@@ -49,6 +53,12 @@ impl<'a> SceneView<'a> {
         self.scene_pointer = Some(scene_pointer);
         self
     }
+
+    /// Doesn't move the camera on input. The view still senses clicks.
+    pub fn without_input(mut self) -> Self {
+        self.interactive = false;
+        self
+    }
 }
 
 impl<'a> egui::Widget for SceneView<'a> {
@@ -64,8 +74,14 @@ impl<'a> egui::Widget for SceneView<'a> {
                 camera_entity,
             };
 
-            // handle inputs (and resizing)
-            handle_input(&mut camera_proxy, self.scene_pointer, &response);
+            // update camera's viewport
+            camera_proxy.update_viewport(Viewport {
+                viewport: response.rect,
+            });
+
+            if self.interactive {
+                handle_input(&mut camera_proxy, self.scene_pointer, &response);
+            }
 
             if !ui.is_sizing_pass()
                 && ui.is_rect_visible(response.rect)
@@ -93,11 +109,6 @@ fn handle_input(
     let camera_pan_tilt_speed = Vector2::repeat(1.0);
     let camera_translation_speed = Vector3::new(0.5, 0.5, 0.1);
 
-    // update camera's viewport
-    camera_proxy.update_viewport(Viewport {
-        viewport: response.rect,
-    });
-
     // some events (i.e. mouse wheel) we have to read manually, but we only want to
     // do this when the mouse cursor is on top of the view.
     if response.contains_pointer() {
@@ -13,6 +13,11 @@ use serde::{
     Serialize,
 };
 
+use crate::{
+    color_scheme::ColorScheme,
+    composer::import::ImportOptions,
+};
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AppConfig {
     #[serde(default = "default_recently_opened_files_limit")]
@@ -21,6 +26,10 @@ pub struct AppConfig {
     #[serde(default)]
     pub composer: ComposerConfig,
 
+    /// Colors of selection outlines, wireframes, field color maps and plots.
+    #[serde(default)]
+    pub color_scheme: ColorScheme,
+
     pub graphics: GraphicsConfig,
 }
 
@@ -29,6 +38,7 @@ impl Default for AppConfig {
         Self {
             recently_opened_files_limit: default_recently_opened_files_limit(),
             composer: Default::default(),
+            color_scheme: Default::default(),
             graphics: Default::default(),
         }
     }
@@ -38,7 +48,7 @@ fn default_recently_opened_files_limit() -> usize {
     10
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ComposerConfig {
     #[serde(default)]
     pub undo_limit: Option<usize>,
@@ -48,15 +58,60 @@ pub struct ComposerConfig {
 
     #[serde(default)]
     pub views: ViewsConfig,
+
+    /// When collecting assets, files up to this size (in bytes) are embedded
+    /// into the project file instead of being copied next to it.
+    #[serde(default = "default_embed_assets_limit")]
+    pub embed_assets_limit: u64,
+
+    /// Default coordinate mapping for imported files.
+    #[serde(default)]
+    pub import: ImportOptions,
+}
+
+impl Default for ComposerConfig {
+    fn default() -> Self {
+        Self {
+            undo_limit: None,
+            redo_limit: None,
+            views: Default::default(),
+            embed_assets_limit: default_embed_assets_limit(),
+            import: Default::default(),
+        }
+    }
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+fn default_embed_assets_limit() -> u64 {
+    64 << 10
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ViewsConfig {
     #[serde(rename = "3d", default)]
     pub view_3d: View3dConfig,
 
     #[serde(default)]
     pub selection_outline: Outline,
+
+    /// Clicking on an object in a group selects the whole group. A
+    /// double-click selects the object itself.
+    #[serde(default = "default_to_true")]
+    pub select_group_roots: bool,
+
+    /// Draw the bounding box of the selection and its size into the viewport.
+    #[serde(default = "default_to_true")]
+    pub show_dimensions: bool,
+}
+
+impl Default for ViewsConfig {
+    fn default() -> Self {
+        Self {
+            view_3d: Default::default(),
+            selection_outline: Default::default(),
+            select_group_roots: true,
+            show_dimensions: true,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -134,6 +189,8 @@ pub struct GraphicsConfig {
     #[serde(default)]
     pub memory_hints: wgpu::MemoryHints,
 
+    /// Minimum size of staging buffer chunks. Chunks grow when frames upload
+    /// more than this.
     #[serde(default = "default_staging_chunk_size")]
     pub staging_chunk_size: wgpu::BufferSize,
     // this is really limited and hard to tell what works
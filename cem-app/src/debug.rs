@@ -1,17 +1,45 @@
 use std::{
-    sync::Arc,
+    collections::HashMap,
+    sync::{
+        Arc,
+        LazyLock,
+    },
     time::{
         Duration,
         Instant,
     },
 };
 
-use bevy_ecs::system::{
-    Res,
-    SystemParam,
+use bevy_ecs::{
+    schedule::ScheduleLabel,
+    system::{
+        Res,
+        SystemParam,
+    },
+    world::World,
 };
 use cem_render::RendererInfo;
+use cem_scene::schedule::{
+    self,
+    ScheduleTimings,
+    schedule_systems,
+};
 use cem_util::format_size;
+use parking_lot::Mutex;
+use tracing::{
+    Subscriber,
+    field::{
+        Field,
+        Visit,
+    },
+    span,
+};
+use tracing_subscriber::{
+    Layer,
+    filter::FilterFn,
+    layer::Context,
+    registry::LookupSpan,
+};
 
 use crate::{
     app::{
@@ -146,6 +174,18 @@ impl App {
                                         "Cumulative staged: {}",
                                         format_size(staging_belt_info.total_staged_bytes)
                                     ));
+                                    ui.label(format!(
+                                        "Reused chunks: {}",
+                                        staging_belt_info.total_reuse_count
+                                    ));
+                                    ui.label(format!(
+                                        "Stalls: {}",
+                                        staging_belt_info.total_stall_count
+                                    ));
+                                    ui.label(format!(
+                                        "Chunk size: {}",
+                                        format_size(staging_belt_info.chunk_size)
+                                    ));
                                 });
                             });
 
@@ -251,6 +291,281 @@ impl<'w> DebugUi for &RendererDebugUi<'w> {
                     format_size(info.prepare_world_staged.camera_buffers),
                 ));
             });
+
+            egui::CollapsingHeader::new(format!(
+                "Staging belt: {}",
+                format_size(info.staging_belt.staged_bytes),
+            ))
+            .id_salt(ui.id().with("staging_belt"))
+            .show(ui, |ui| {
+                ui.label(format!(
+                    "Chunks: {} reused, {} allocated",
+                    info.staging_belt.reused_chunks, info.staging_belt.allocated_chunks
+                ));
+                if let Some(reuse_rate) = info.staging_belt.reuse_rate() {
+                    ui.label(format!("Reuse rate: {:.0}%", 100.0 * reuse_rate));
+                }
+                ui.label(format!("Stalls: {}", info.staging_belt.stalls))
+                    .on_hover_text("Chunks allocated while others were still in use by the GPU");
+                ui.label(format!(
+                    "Chunk size: {}",
+                    format_size(info.staging_pool.chunk_size)
+                ));
+            });
+
+            ui.label(format!(
+                "Instances updated: {}/{}",
+                info.num_instances_updated, info.num_instances
+            ));
+            ui.label(format!(
+                "Draw list: {}",
+                if info.draw_list_reused {
+                    "reused"
+                }
+                else {
+                    "rebuilt"
+                }
+            ));
+        }
+    }
+}
+
+/// Execution times of ECS systems.
+///
+/// These are measured from the `system` spans that `bevy_ecs` enters whenever
+/// it runs a system, so the [`SystemTimings::layer`] needs to be installed in
+/// the tracing subscriber.
+///
+/// Systems are identified by their names, so the timings of a system are
+/// shared by all scenes.
+#[derive(Clone, Debug, Default)]
+pub struct SystemTimings {
+    timings: Arc<Mutex<HashMap<String, SystemTiming>>>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemTiming {
+    /// Duration of the last run.
+    pub last: Duration,
+
+    /// Exponential moving average of the duration.
+    pub average: Duration,
+
+    pub runs: u64,
+}
+
+impl SystemTiming {
+    /// Weight of the last run in the moving average.
+    const SMOOTHING: f64 = 0.05;
+
+    fn record(&mut self, duration: Duration) {
+        self.last = duration;
+        self.average = if self.runs == 0 {
+            duration
+        }
+        else {
+            self.average.mul_f64(1.0 - Self::SMOOTHING) + duration.mul_f64(Self::SMOOTHING)
+        };
+        self.runs += 1;
+    }
+}
+
+impl SystemTimings {
+    pub fn global() -> &'static Self {
+        static GLOBAL: LazyLock<SystemTimings> = LazyLock::new(Default::default);
+        &GLOBAL
+    }
+
+    pub fn get(&self, system: &str) -> Option<SystemTiming> {
+        self.timings.lock().get(system).copied()
+    }
+
+    pub fn layer<S>(&self) -> impl Layer<S>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        SystemTimingLayer {
+            timings: self.clone(),
+        }
+        .with_filter(FilterFn::new(|metadata| {
+            metadata.is_span() && metadata.name() == "system"
+        }))
+    }
+}
+
+struct SystemTimingLayer {
+    timings: SystemTimings,
+}
+
+/// Stored in the extensions of a system span.
+struct SystemSpan {
+    name: String,
+    entered: Option<Instant>,
+}
+
+impl<S> Layer<S> for SystemTimingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attributes: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut visitor = SystemNameVisitor::default();
+        attributes.record(&mut visitor);
+
+        if let Some(name) = visitor.name
+            && let Some(span) = ctx.span(id)
+        {
+            span.extensions_mut().insert(SystemSpan {
+                name,
+                entered: None,
+            });
+        }
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id)
+            && let Some(system_span) = span.extensions_mut().get_mut::<SystemSpan>()
+        {
+            system_span.entered = Some(Instant::now());
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id)
+            && let Some(system_span) = span.extensions_mut().get_mut::<SystemSpan>()
+            && let Some(entered) = system_span.entered.take()
+        {
+            self.timings
+                .timings
+                .lock()
+                .entry(system_span.name.clone())
+                .or_default()
+                .record(entered.elapsed());
+        }
+    }
+}
+
+#[derive(Default)]
+struct SystemNameVisitor {
+    name: Option<String>,
+}
+
+impl Visit for SystemNameVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "name" {
+            self.name = Some(value.to_owned());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "name" && self.name.is_none() {
+            self.name = Some(format!("{value:?}").trim_matches('"').to_owned());
+        }
+    }
+}
+
+/// Shows the schedules of a scene with the execution times of their systems.
+pub fn show_schedules_debug(world: &World, ui: &mut egui::Ui) {
+    let Some(schedule_timings) = world.get_resource::<ScheduleTimings>()
+    else {
+        ui.label("No schedules run yet");
+        return;
+    };
+    let system_timings = SystemTimings::global();
+
+    for (label, duration) in schedule_timings.iter() {
+        egui::CollapsingHeader::new(format!("{label:?}: {duration:.2?}"))
+            .id_salt(ui.id().with(label))
+            .show(ui, |ui| {
+                let Some(systems) = schedule_systems(world, label)
+                else {
+                    return;
+                };
+
+                let mut systems = systems
+                    .into_iter()
+                    .map(|name| {
+                        let timing = system_timings.get(&name);
+                        (name, timing)
+                    })
+                    .collect::<Vec<_>>();
+                // slowest first
+                systems.sort_by_key(|(_, timing)| {
+                    std::cmp::Reverse(timing.map(|timing| timing.average))
+                });
+
+                egui::Grid::new(ui.id().with("systems"))
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.small("System");
+                        ui.small("Last");
+                        ui.small("Average");
+                        ui.end_row();
+
+                        for (name, timing) in &systems {
+                            ui.monospace(short_system_name(name))
+                                .on_hover_text(name.as_str());
+                            if let Some(timing) = timing {
+                                ui.label(format!("{:.1?}", timing.last));
+                                ui.label(format!("{:.1?}", timing.average));
+                            }
+                            else {
+                                ui.label("-");
+                                ui.label("-");
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    ui.small(format!(
+        "Update: {:.2?}, Render: {:.2?}",
+        [
+            schedule::PreUpdate.intern(),
+            schedule::Update.intern(),
+            schedule::PostUpdate.intern()
+        ]
+        .into_iter()
+        .filter_map(|label| schedule_timings.get(label))
+        .sum::<Duration>(),
+        schedule_timings.get(schedule::Render).unwrap_or_default(),
+    ));
+}
+
+/// Strips the module paths from a system name, e.g.
+/// `cem_render::systems::begin_frame` becomes `begin_frame`.
+fn short_system_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    let mut segment_start = 0;
+    for (i, c) in name.char_indices() {
+        match c {
+            ':' => segment_start = i + 1,
+            '<' | '>' | ',' | ' ' | '(' | ')' => {
+                short.push_str(&name[segment_start..i]);
+                short.push(c);
+                segment_start = i + 1;
+            }
+            _ => {}
         }
     }
+    short.push_str(&name[segment_start..]);
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::debug::short_system_name;
+
+    #[test]
+    fn system_names_are_shortened() {
+        assert_eq!(
+            short_system_name("cem_render::systems::begin_frame"),
+            "begin_frame"
+        );
+        assert_eq!(
+            short_system_name("cem_scene::assets::load<cem_render::mesh::LoadMesh>"),
+            "load<LoadMesh>"
+        );
+        assert_eq!(short_system_name("(a::b, c::d)"), "(b, d)");
+    }
 }
@@ -0,0 +1,370 @@
+//! Debug server for scripting the app from outside.
+//!
+//! When enabled with `--debug-server <address>`, the app listens on a TCP
+//! socket for requests. The protocol is line-based: every line is a JSON
+//! object with a `method` and its parameters, and an optional `id` that is
+//! echoed back in the response. Every request gets one response line with
+//! either a `result` or an `error`:
+//!
+//! ```text
+//! > {"id": 1, "method": "list_entities"}
+//! < {"id": 1, "result": [{"entity": 4294967296, "name": "camera"}, ...]}
+//! > {"id": 2, "method": "set_transform", "entity": 4294967296, "translation": [0, 1, 0]}
+//! < {"id": 2, "result": null}
+//! > {"id": 3, "method": "run_solver", "solver": 0}
+//! < {"id": 3, "result": "preparing"}
+//! > {"id": 4, "method": "solver_state"}
+//! < {"id": 4, "result": {"preparing": true, "progress": 0.25}}
+//! ```
+//!
+//! `run_solver` replies right away, while the solver is set up in a job. Poll
+//! `solver_state` until it's no longer preparing. If the setup fails, the
+//! error is reported in the app like for any other job, and `solver_state` is
+//! `null`.
+//!
+//! Requests operate on the active composer. They are handled on the UI thread
+//! between frames, so they see the same state as the user does.
+//!
+//! There is no authentication, so only bind this to a loopback address.
+
+use std::{
+    io::{
+        BufRead,
+        BufReader,
+        BufWriter,
+        Write,
+    },
+    net::{
+        SocketAddr,
+        TcpListener,
+        TcpStream,
+    },
+    sync::mpsc,
+};
+
+use bevy_ecs::{
+    entity::Entity,
+    name::Name,
+};
+use cem_scene::{
+    Scene,
+    transform::LocalTransform,
+};
+use cem_util::egui::RepaintTrigger;
+use color_eyre::eyre::{
+    OptionExt,
+    bail,
+};
+use nalgebra::{
+    Quaternion,
+    Translation3,
+    UnitQuaternion,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::{
+    Value,
+    json,
+};
+
+use crate::{
+    Error,
+    solver::{
+        config::SolverConfig,
+        runner::SolverRunner,
+    },
+    util::spawn_thread,
+};
+
+#[derive(Debug)]
+pub struct DebugServer {
+    address: SocketAddr,
+    requests: mpsc::Receiver<PendingRequest>,
+}
+
+impl DebugServer {
+    /// Starts listening on `address`.
+    ///
+    /// Connections are served by their own threads. `repaint_trigger` is used
+    /// to wake up the UI when a request arrives.
+    pub fn bind(address: SocketAddr, repaint_trigger: RepaintTrigger) -> Result<Self, Error> {
+        if !address.ip().is_loopback() {
+            tracing::warn!(%address, "debug server is not bound to a loopback address");
+        }
+
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        tracing::info!(%address, "debug server listening");
+
+        let (sender, requests) = mpsc::channel();
+
+        spawn_thread("debug-server", move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let sender = sender.clone();
+                        let repaint_trigger = repaint_trigger.clone();
+                        spawn_thread("debug-server-connection", move || {
+                            let peer = stream.peer_addr().ok();
+                            tracing::debug!(?peer, "debug server connection");
+                            if let Err(error) = serve_connection(stream, &sender, &repaint_trigger)
+                            {
+                                tracing::debug!(?peer, %error, "debug server connection closed");
+                            }
+                        });
+                    }
+                    Err(error) => tracing::warn!(%error, "debug server failed to accept"),
+                }
+            }
+        });
+
+        Ok(Self { address, requests })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Handles all requests that arrived since the last call.
+    pub fn handle_requests(&self, mut target: DebugTarget) {
+        while let Ok(pending) = self.requests.try_recv() {
+            tracing::debug!(request = ?pending.request, "debug server request");
+
+            let response = match target.handle(pending.request) {
+                Ok(result) => Response::Result(result),
+                Err(error) => Response::Error(format!("{error:#}")),
+            };
+
+            // the connection might be gone already
+            let _ = pending.reply.send(response);
+        }
+    }
+}
+
+/// What requests operate on.
+#[derive(Debug)]
+pub struct DebugTarget<'a> {
+    /// Scene and solver configs of the active composer.
+    pub composer: Option<(&'a mut Scene, &'a [SolverConfig])>,
+    pub solver_runner: &'a mut SolverRunner,
+}
+
+impl DebugTarget<'_> {
+    fn handle(&mut self, request: Request) -> Result<Value, Error> {
+        match request {
+            Request::ListEntities => {
+                let scene = self.scene()?;
+                let mut query = scene.world.query::<(Entity, Option<&Name>)>();
+                let entities = query
+                    .iter(&scene.world)
+                    .map(|(entity, name)| {
+                        json!({
+                            "entity": entity.to_bits(),
+                            "name": name.map(|name| name.as_str()),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(entities.into())
+            }
+            Request::GetTransform { entity } => {
+                let scene = self.scene()?;
+                let entity = lookup_entity(scene, entity)?;
+                let transform = scene
+                    .world
+                    .get::<LocalTransform>(entity)
+                    .ok_or_eyre("Entity has no transform")?;
+                let translation = transform.isometry.translation.vector;
+                let rotation = transform.isometry.rotation.coords;
+                Ok(json!({
+                    "translation": [translation.x, translation.y, translation.z],
+                    "rotation": [rotation.x, rotation.y, rotation.z, rotation.w],
+                }))
+            }
+            Request::SetTransform {
+                entity,
+                translation,
+                rotation,
+            } => {
+                let scene = self.scene()?;
+                let entity = lookup_entity(scene, entity)?;
+                let mut transform = scene
+                    .world
+                    .get_mut::<LocalTransform>(entity)
+                    .ok_or_eyre("Entity has no transform")?;
+                if let Some([x, y, z]) = translation {
+                    transform.isometry.translation = Translation3::new(x, y, z);
+                }
+                if let Some([i, j, k, w]) = rotation {
+                    transform.isometry.rotation =
+                        UnitQuaternion::from_quaternion(Quaternion::new(w, i, j, k));
+                }
+                Ok(Value::Null)
+            }
+            Request::Despawn { entity } => {
+                let scene = self.scene()?;
+                let entity = lookup_entity(scene, entity)?;
+                scene.world.despawn(entity);
+                Ok(Value::Null)
+            }
+            Request::ListSolvers => {
+                let (_, solver_configs) = self.composer()?;
+                let solvers = solver_configs
+                    .iter()
+                    .enumerate()
+                    .map(|(index, solver_config)| {
+                        json!({
+                            "solver": index,
+                            "label": solver_config.label,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(solvers.into())
+            }
+            Request::RunSolver { solver } => {
+                let (scene, solver_configs) = self.composer.as_mut().ok_or_eyre("No file open")?;
+                let Some(solver_config) = solver_configs.get(solver)
+                else {
+                    bail!("No solver with index {solver}");
+                };
+                self.solver_runner.run(solver_config, scene)?;
+                Ok("preparing".into())
+            }
+            Request::StopSolver => {
+                self.solver_runner.stop();
+                Ok(Value::Null)
+            }
+            Request::SolverState => {
+                if self.solver_runner.is_preparing() {
+                    return Ok(json!({
+                        "preparing": true,
+                        "progress": self.solver_runner.preparation_progress(),
+                    }));
+                }
+                let Some(solver) = self.solver_runner.active_solver()
+                else {
+                    return Ok(Value::Null);
+                };
+                let state = solver.state();
+                Ok(json!({
+                    "finished": state.finished,
+                    "paused": state.paused,
+                    "sim_time": state.sim_time,
+                    "sim_tick": state.sim_tick,
+                }))
+            }
+        }
+    }
+
+    fn composer(&mut self) -> Result<(&mut Scene, &[SolverConfig]), Error> {
+        let (scene, solver_configs) = self.composer.as_mut().ok_or_eyre("No file open")?;
+        Ok((&mut **scene, *solver_configs))
+    }
+
+    fn scene(&mut self) -> Result<&mut Scene, Error> {
+        Ok(self.composer()?.0)
+    }
+}
+
+fn lookup_entity(scene: &Scene, bits: u64) -> Result<Entity, Error> {
+    Entity::try_from_bits(bits)
+        .filter(|entity| scene.world.get_entity(*entity).is_ok())
+        .ok_or_eyre("No such entity")
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum Request {
+    ListEntities,
+    GetTransform {
+        entity: u64,
+    },
+    SetTransform {
+        entity: u64,
+        translation: Option<[f64; 3]>,
+        /// Quaternion as `[i, j, k, w]`
+        rotation: Option<[f64; 4]>,
+    },
+    Despawn {
+        entity: u64,
+    },
+    ListSolvers,
+    RunSolver {
+        solver: usize,
+    },
+    StopSolver,
+    SolverState,
+}
+
+#[derive(Debug, Deserialize)]
+struct RequestLine {
+    #[serde(default)]
+    id: Value,
+    #[serde(flatten)]
+    request: Request,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Response {
+    Result(Value),
+    Error(String),
+}
+
+#[derive(Debug, Serialize)]
+struct ResponseLine {
+    id: Value,
+    #[serde(flatten)]
+    response: Response,
+}
+
+#[derive(Debug)]
+struct PendingRequest {
+    request: Request,
+    reply: mpsc::Sender<Response>,
+}
+
+fn serve_connection(
+    stream: TcpStream,
+    sender: &mpsc::Sender<PendingRequest>,
+    repaint_trigger: &RepaintTrigger,
+) -> Result<(), Error> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response_line = match serde_json::from_str::<RequestLine>(&line) {
+            Ok(RequestLine { id, request }) => {
+                let (reply, reply_receiver) = mpsc::channel();
+                if sender.send(PendingRequest { request, reply }).is_err() {
+                    bail!("App closed");
+                }
+                repaint_trigger.repaint();
+
+                let Ok(response) = reply_receiver.recv()
+                else {
+                    bail!("App closed");
+                };
+                ResponseLine { id, response }
+            }
+            Err(error) => {
+                ResponseLine {
+                    id: Value::Null,
+                    response: Response::Error(format!("Invalid request: {error}")),
+                }
+            }
+        };
+
+        serde_json::to_writer(&mut writer, &response_line)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+    }
+
+    Ok(())
+}
@@ -54,6 +54,18 @@ impl AppFiles {
         self.project_dirs.data_local_dir().join("screenshots")
     }
 
+    /// Directory for recordings started from the *Help* menu, see
+    /// [`crate::recorder`].
+    pub fn recordings_dir(&self) -> PathBuf {
+        self.project_dirs.data_local_dir().join("recordings")
+    }
+
+    /// Directory for the logs of long solver runs, see
+    /// [`crate::solver::run_log`].
+    pub fn run_logs_dir(&self) -> PathBuf {
+        self.project_dirs.data_local_dir().join("run-logs")
+    }
+
     /// Returns path to file for egui's persistence.
     pub fn egui_persist_path(&self) -> PathBuf {
         self.state_dir_with_fallback().join("ui_state")
@@ -91,6 +103,11 @@ impl AppFiles {
         Ok(config)
     }
 
+    /// Throughput of past solver runs, see [`crate::solver::benchmark`].
+    pub fn benchmarks_path(&self) -> PathBuf {
+        self.state_dir_with_fallback().join("benchmarks.json")
+    }
+
     pub fn mipmap_cache_path(&self) -> PathBuf {
         self.project_dirs.cache_dir().join("mipmaps")
     }
@@ -0,0 +1,223 @@
+//! Running a solver without a window.
+//!
+//! This is meant for long runs on machines without a display. The scene is
+//! loaded the same way the app does it and the solver runs right away. Status
+//! and observer images are streamed to monitors (see
+//! [`crate::solver::stream`]). Probes and observers for the run can be given
+//! in a separate file (see [`crate::solver::extraction`]).
+
+use std::{
+    num::NonZero,
+    time::Duration,
+};
+
+use cem_render::{
+    RendererConfig,
+    plugin::RenderPlugin,
+    texture::table::TextureTable,
+};
+use color_eyre::eyre::OptionExt;
+
+use crate::{
+    Error,
+    app::WgpuContext,
+    args::HeadlessArgs,
+    composer::Composers,
+    config::AppConfig,
+    error::ErrorDialog,
+    files::AppFiles,
+    jobs::Jobs,
+    solver::{
+        benchmark::BenchmarkDatabase,
+        export::export_results,
+        extraction::Extraction,
+        hashes::RunHashes,
+        library::RunRecord,
+        report::ReportOptions,
+        runner::SolverRunner,
+        stream::{
+            RemoteStatus,
+            StreamPublisher,
+        },
+    },
+};
+
+/// How often the status and observer images are sent to monitors.
+const STATUS_INTERVAL: Duration = Duration::from_millis(500);
+
+pub fn run_headless(args: HeadlessArgs) -> Result<(), Error> {
+    let app_files = AppFiles::open()?;
+    let config = if args.ignore_config {
+        AppConfig::default()
+    }
+    else {
+        app_files.read_config_or_create::<AppConfig>()?
+    };
+    config.color_scheme.make_current();
+
+    let wgpu_context = create_wgpu_context(&config)?;
+
+    // nothing is rendered, but the renderer still sets up its pipelines and
+    // observers need it for their textures.
+    let render_plugin = RenderPlugin::new(
+        wgpu_context.device.clone(),
+        wgpu_context.queue.clone(),
+        wgpu_context.staging_pool.clone(),
+        RendererConfig {
+            target_texture_format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            depth_texture_format: Some(wgpu::TextureFormat::Depth24PlusStencil8),
+            multisample_count: NonZero::new(1).unwrap(),
+        },
+    );
+
+    // without a window the egui context is only used for repaint triggers (which do
+    // nothing), jobs and error reporting (which is logged).
+    let egui_context = egui::Context::default();
+    Jobs::new(&egui_context).register_in_context(&egui_context);
+    ErrorDialog::default().register_in_context(&egui_context);
+
+    let mut composers = Composers::new(&egui_context, render_plugin);
+    composers.open_file_blocking(&config, args.file.as_deref())?;
+
+    let extraction = args
+        .extraction
+        .as_deref()
+        .map(Extraction::from_path)
+        .transpose()?
+        .unwrap_or_default();
+
+    let publisher = StreamPublisher::bind(args.stream)?;
+    let mut solver_runner = SolverRunner::new(&wgpu_context, &egui_context)
+        .with_benchmarks(BenchmarkDatabase::open(app_files.benchmarks_path()))
+        .with_run_logs_directory(app_files.run_logs_dir());
+    solver_runner.set_stream(Some(publisher.clone()));
+
+    let (label, hashes) = {
+        let (scene, solver_configs) = composers
+            .active_scene_mut()
+            .expect("composer was just opened");
+        extraction.apply(&mut scene.world);
+
+        let Some(solver_config) = solver_configs.get(args.solver)
+        else {
+            color_eyre::eyre::bail!(
+                "No solver with index {} (there are {})",
+                args.solver,
+                solver_configs.len()
+            );
+        };
+        let hashes = RunHashes::new(solver_config, &mut scene.world)?;
+
+        tracing::info!(label = solver_config.label, "running solver");
+        solver_runner.run(solver_config, scene)?;
+        solver_runner.wait_until_started()?;
+
+        (solver_config.label.clone(), hashes)
+    };
+
+    let solver = solver_runner
+        .active_solver()
+        .ok_or_eyre("Solver didn't start")?;
+
+    // solvers start paused, and are rate-limited for the UI. there's no point in
+    // observing more often than we send status updates.
+    {
+        let mut state = solver.state_mut();
+        state.step_delay = None;
+        state.observation_delay = Some(STATUS_INTERVAL);
+    }
+    solver.resume();
+
+    loop {
+        let state = solver.state();
+        publisher.publish_status(RemoteStatus::from(&state));
+
+        if state.finished {
+            tracing::info!(
+                sim_tick = state.sim_tick,
+                sim_time = state.sim_time,
+                running_time = ?state.total_running_time,
+                "solver finished"
+            );
+            break;
+        }
+
+        std::thread::sleep(STATUS_INTERVAL);
+    }
+
+    if let Some(directory) = extraction.export {
+        // there is no viewport to take an image of
+        let report = extraction.report.map(|report| {
+            ReportOptions {
+                viewport: false,
+                ..report
+            }
+        });
+        let state = solver.state();
+        let probe_outputs = solver.probe_outputs().clone();
+        let handle = Jobs::from_ctx(&egui_context).spawn("Export results", move |job| {
+            let files = export_results(&directory, &state, &probe_outputs, report.as_ref(), job)?;
+
+            // so the results can be imported into the project's library
+            RunRecord::new(label, hashes, &state, &probe_outputs).write(&directory)?;
+
+            Ok(files)
+        });
+
+        let files = loop {
+            if let Some(result) = handle.try_take_result() {
+                break result?;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        };
+        tracing::info!(num_files = files.len(), "exported results");
+    }
+
+    solver_runner.stop();
+
+    Ok(())
+}
+
+fn create_wgpu_context(config: &AppConfig) -> Result<WgpuContext, Error> {
+    let instance = wgpu::Instance::new(
+        &wgpu::InstanceDescriptor {
+            backends: config.graphics.backends,
+            ..Default::default()
+        }
+        .with_env(),
+    );
+
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference:
+            wgpu::PowerPreference::from_env().unwrap_or(config.graphics.power_preference),
+        force_fallback_adapter: false,
+        compatible_surface: None,
+    }))?;
+
+    let adapter_info = adapter.get_info();
+    tracing::debug!(
+        backend = ?adapter_info.backend,
+        name = adapter_info.name,
+        "using adapter"
+    );
+
+    let mut required_features = wgpu::Features::default();
+    let mut required_limits = wgpu::Limits::downlevel_defaults();
+    TextureTable::request_features(&adapter, &mut required_features, &mut required_limits);
+
+    let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+        label: Some("headless wgpu device"),
+        required_features,
+        required_limits,
+        experimental_features: wgpu::ExperimentalFeatures::disabled(),
+        memory_hints: config.graphics.memory_hints.clone(),
+        trace: wgpu::Trace::Off,
+    }))?;
+
+    Ok(WgpuContext::new(
+        adapter,
+        device,
+        queue,
+        config.graphics.staging_chunk_size,
+    ))
+}
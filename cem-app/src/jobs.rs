@@ -0,0 +1,450 @@
+//! Background jobs with progress reporting and cancellation.
+//!
+//! Anything that might take longer than a frame (importing files, exporting
+//! results, etc.) should be spawned as a job instead of running on the UI
+//! thread. Jobs run on a small pool of worker threads and are listed in the
+//! jobs window, from where they can be cancelled.
+//!
+//! The [`Jobs`] handle is registered in the egui context, so it's reachable
+//! from anywhere in the UI with [`Jobs::from_ctx`].
+
+use std::{
+    collections::VecDeque,
+    panic::AssertUnwindSafe,
+    sync::{
+        Arc,
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+    },
+    time::Instant,
+};
+
+use egui::Id;
+use parking_lot::{
+    Condvar,
+    Mutex,
+};
+
+use crate::{
+    Error,
+    util::spawn_thread,
+};
+
+/// Maximum number of worker threads.
+///
+/// The solver runs on its own threads, so we don't want to take all cores.
+const MAX_WORKERS: usize = 4;
+
+type Task = Box<dyn FnOnce() + Send>;
+
+/// Handle to the job system.
+///
+/// This is cheap to clone.
+#[derive(Clone)]
+pub struct Jobs {
+    shared: Arc<Shared>,
+}
+
+impl std::fmt::Debug for Jobs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Jobs")
+            .field("num_jobs", &self.shared.jobs.lock().len())
+            .finish_non_exhaustive()
+    }
+}
+
+struct Shared {
+    queue: Mutex<VecDeque<Task>>,
+    queue_condition: Condvar,
+
+    /// All jobs that haven't been cleared from the jobs window yet.
+    jobs: Mutex<Vec<Arc<JobStatus>>>,
+
+    /// Used to request repaints when a job makes progress.
+    egui_context: egui::Context,
+}
+
+impl Jobs {
+    /// Creates the job system and starts its worker threads.
+    ///
+    /// The workers live as long as the application.
+    pub fn new(egui_context: &egui::Context) -> Self {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(VecDeque::new()),
+            queue_condition: Condvar::new(),
+            jobs: Mutex::new(vec![]),
+            egui_context: egui_context.clone(),
+        });
+
+        let num_workers = std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(MAX_WORKERS);
+        for index in 0..num_workers {
+            spawn_thread(format!("job-worker-{index}"), {
+                let shared = shared.clone();
+                move || worker(&shared)
+            });
+        }
+
+        Self { shared }
+    }
+
+    pub fn register_in_context(&self, ctx: &egui::Context) {
+        ctx.data_mut(|data| {
+            data.insert_temp(Self::id(), self.clone());
+        });
+    }
+
+    pub fn from_ctx(ctx: &egui::Context) -> Self {
+        ctx.data(|data| data.get_temp::<Self>(Self::id()))
+            .expect("jobs not registered in egui context")
+    }
+
+    fn id() -> Id {
+        Id::new("jobs")
+    }
+
+    /// Queues a job.
+    ///
+    /// The job should regularly report its progress and check for
+    /// cancellation through the [`JobContext`] it's passed.
+    pub fn spawn<T, F>(&self, label: impl Into<String>, f: F) -> JobHandle<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&JobContext) -> Result<T, Error> + Send + 'static,
+    {
+        let label = label.into();
+        tracing::debug!(%label, "spawn job");
+
+        let status = Arc::new(JobStatus {
+            label,
+            cancelled: CancellationToken::default(),
+            inner: Mutex::new(JobStatusInner {
+                state: JobState::Queued,
+                progress: None,
+                message: None,
+                started: None,
+                finished: None,
+            }),
+        });
+        let result = Arc::new(Mutex::new(None));
+
+        let task: Task = Box::new({
+            let context = JobContext {
+                status: status.clone(),
+                egui_context: self.shared.egui_context.clone(),
+            };
+            let result = result.clone();
+
+            move || {
+                if context.is_cancelled() {
+                    context.finish(JobState::Cancelled);
+                    *result.lock() = Some(Err(Cancelled.into()));
+                    return;
+                }
+
+                context.update(|inner| {
+                    inner.state = JobState::Running;
+                    inner.started = Some(Instant::now());
+                });
+
+                let output = std::panic::catch_unwind(AssertUnwindSafe(|| f(&context)))
+                    .unwrap_or_else(|_| Err(color_eyre::eyre::eyre!("Job panicked")));
+
+                let state = match &output {
+                    Ok(_) => JobState::Finished,
+                    Err(error) if error.is::<Cancelled>() => JobState::Cancelled,
+                    Err(error) => JobState::Failed(format!("{error:#}")),
+                };
+                *result.lock() = Some(output);
+                context.finish(state);
+            }
+        });
+
+        self.shared.jobs.lock().push(status.clone());
+        self.shared.queue.lock().push_back(task);
+        self.shared.queue_condition.notify_one();
+
+        JobHandle { status, result }
+    }
+
+    /// Removes all jobs that are done from the list shown in the jobs window.
+    pub fn clear_finished(&self) {
+        self.shared
+            .jobs
+            .lock()
+            .retain(|job| !job.inner.lock().state.is_done());
+    }
+
+    pub fn num_running(&self) -> usize {
+        self.shared
+            .jobs
+            .lock()
+            .iter()
+            .filter(|job| !job.inner.lock().state.is_done())
+            .count()
+    }
+
+    pub fn show_window(&self, ctx: &egui::Context, is_open: &mut bool) {
+        egui::Window::new("Jobs")
+            .movable(true)
+            .collapsible(true)
+            .open(is_open)
+            .show(ctx, |ui| {
+                let jobs = self.shared.jobs.lock().clone();
+
+                if jobs.is_empty() {
+                    ui.weak("No jobs");
+                }
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for job in jobs.iter().rev() {
+                        job.show(ui);
+                        ui.separator();
+                    }
+                });
+
+                if ui.button("Clear Finished").clicked() {
+                    self.clear_finished();
+                }
+            });
+    }
+}
+
+fn worker(shared: &Shared) {
+    loop {
+        let task = {
+            let mut queue = shared.queue.lock();
+            loop {
+                if let Some(task) = queue.pop_front() {
+                    break task;
+                }
+                shared.queue_condition.wait(&mut queue);
+            }
+        };
+
+        task();
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JobState {
+    Queued,
+    Running,
+    Finished,
+    Cancelled,
+    Failed(String),
+}
+
+impl JobState {
+    pub fn is_done(&self) -> bool {
+        matches!(self, Self::Finished | Self::Cancelled | Self::Failed(_))
+    }
+}
+
+struct JobStatus {
+    label: String,
+    cancelled: CancellationToken,
+    inner: Mutex<JobStatusInner>,
+}
+
+struct JobStatusInner {
+    state: JobState,
+
+    /// Progress from 0 to 1, if the job knows how far along it is.
+    progress: Option<f32>,
+
+    message: Option<String>,
+    started: Option<Instant>,
+    finished: Option<Instant>,
+}
+
+impl JobStatus {
+    fn show(&self, ui: &mut egui::Ui) {
+        let (state, progress, message, elapsed) = {
+            let inner = self.inner.lock();
+            let elapsed = inner.started.map(|started| {
+                inner
+                    .finished
+                    .unwrap_or_else(Instant::now)
+                    .duration_since(started)
+            });
+            (
+                inner.state.clone(),
+                inner.progress,
+                inner.message.clone(),
+                elapsed,
+            )
+        };
+
+        ui.horizontal(|ui| {
+            ui.strong(&self.label);
+
+            match &state {
+                JobState::Queued => {
+                    ui.weak("Queued");
+                }
+                JobState::Running => {
+                    ui.spinner();
+                }
+                JobState::Finished => {
+                    ui.label("Finished");
+                }
+                JobState::Cancelled => {
+                    ui.weak("Cancelled");
+                }
+                JobState::Failed(_) => {
+                    ui.colored_label(ui.visuals().error_fg_color, "Failed");
+                }
+            }
+
+            if let Some(elapsed) = elapsed {
+                ui.weak(format!("{:.1} s", elapsed.as_secs_f32()));
+            }
+
+            if !state.is_done() {
+                let cancel_button =
+                    ui.add_enabled(!self.cancelled.is_cancelled(), egui::Button::new("Cancel"));
+                if cancel_button.clicked() {
+                    tracing::debug!(label = %self.label, "cancel job");
+                    self.cancelled.cancel();
+                }
+            }
+        });
+
+        if state == JobState::Running
+            && let Some(progress) = progress
+        {
+            ui.add(egui::ProgressBar::new(progress).show_percentage());
+        }
+
+        if let JobState::Failed(error) = &state {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        else if let Some(message) = &message {
+            ui.weak(message);
+        }
+    }
+}
+
+/// Passed to a running job to report progress and check for cancellation.
+pub struct JobContext {
+    status: Arc<JobStatus>,
+    egui_context: egui::Context,
+}
+
+impl JobContext {
+    /// Sets the progress of the job, from 0 to 1.
+    pub fn set_progress(&self, progress: f32) {
+        self.update(|inner| inner.progress = Some(progress.clamp(0.0, 1.0)));
+    }
+
+    /// Sets a short message describing what the job is currently doing.
+    pub fn set_message(&self, message: impl Into<String>) {
+        let message = message.into();
+        self.update(|inner| inner.message = Some(message));
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.status.cancelled.is_cancelled()
+    }
+
+    /// Returns an error if the job was cancelled.
+    ///
+    /// Jobs should call this regularly and return the error with `?`.
+    pub fn check_cancelled(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        }
+        else {
+            Ok(())
+        }
+    }
+
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.status.cancelled.clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut JobStatusInner)) {
+        f(&mut self.status.inner.lock());
+        self.egui_context.request_repaint();
+    }
+
+    fn finish(&self, state: JobState) {
+        tracing::debug!(label = %self.status.label, ?state, "job done");
+        self.update(|inner| {
+            inner.state = state;
+            inner.finished = Some(Instant::now());
+        });
+    }
+}
+
+/// Handle to a spawned job, used to get its result.
+pub struct JobHandle<T> {
+    status: Arc<JobStatus>,
+    result: Arc<Mutex<Option<Result<T, Error>>>>,
+}
+
+impl<T> Clone for JobHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            status: self.status.clone(),
+            result: self.result.clone(),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for JobHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobHandle")
+            .field("label", &self.status.label)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> JobHandle<T> {
+    pub fn label(&self) -> &str {
+        &self.status.label
+    }
+
+    pub fn cancel(&self) {
+        self.status.cancelled.cancel();
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.status.inner.lock().state.is_done()
+    }
+
+    pub fn progress(&self) -> Option<f32> {
+        self.status.inner.lock().progress
+    }
+
+    /// Takes the result of the job, if it's done.
+    ///
+    /// This returns the result only once.
+    pub fn try_take_result(&self) -> Option<Result<T, Error>> {
+        self.result.lock().take()
+    }
+}
+
+/// Shared flag used to cancel a job.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Clone, Copy, Debug, thiserror::Error)]
+#[error("Job was cancelled")]
+pub struct Cancelled;
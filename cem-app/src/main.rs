@@ -5,13 +5,20 @@ pub mod app;
 pub mod args;
 pub mod build_info;
 pub mod clipboard;
+pub mod color_scheme;
 pub mod composer;
 pub mod config;
 pub mod debug;
+#[cfg(feature = "debug-server")]
+pub mod debug_server;
 pub mod error;
 pub mod files;
+pub mod headless;
+pub mod jobs;
 pub mod menubar;
+pub mod recorder;
 pub mod solver;
+pub mod status_bar;
 pub mod util;
 
 use std::path::PathBuf;
@@ -25,21 +32,35 @@ use color_eyre::eyre::{
     bail,
 };
 use dotenvy::dotenv;
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::{
+    EnvFilter,
+    Layer,
+    layer::SubscriberExt,
+    util::SubscriberInitExt,
+};
 
-use crate::config::AppConfig;
+use crate::{
+    config::AppConfig,
+    debug::SystemTimings,
+};
 
 fn main() -> Result<(), Error> {
     let _ = dotenv();
     color_eyre::install()?;
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .pretty()
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .pretty()
+                .with_filter(EnvFilter::from_default_env()),
+        )
+        // measures how long systems take for the debug panel
+        .with(SystemTimings::global().layer())
         .init();
 
     let args = Args::parse();
     match args.command {
         Command::Main(args) => app::run_app(args)?,
+        Command::Headless(args) => headless::run_headless(args)?,
         Command::DumpDefaultConfig { output, format } => {
             let config = AppConfig::default();
             let config = match format.as_str() {
@@ -69,6 +90,8 @@ struct Args {
 enum Command {
     // the main app, the other's are just temporary for testing purposes
     Main(args::Args),
+    /// Run a solver without a window, streaming to monitors.
+    Headless(args::HeadlessArgs),
     DumpDefaultConfig {
         #[clap(short, long)]
         output: Option<PathBuf>,
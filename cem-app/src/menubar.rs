@@ -5,6 +5,7 @@ use crate::{
         App,
         GithubUrls,
     },
+    color_scheme::ColorScheme,
     composer::menubar::ComposerMenuElements,
     error::ResultExt,
 };
@@ -96,6 +97,21 @@ impl<'a> MenuBar<'a> {
                     .save_file(self.app.composers.save_path());
             }
 
+            if ui
+                .add_enabled(
+                    self.app.composers.save_path().is_some(),
+                    egui::Button::new("Collect Assets"),
+                )
+                .on_hover_text("Embed or copy all files the project uses into the project")
+                .on_disabled_hover_text("Save the project first")
+                .clicked()
+            {
+                self.app
+                    .composers
+                    .collect_assets(&self.app.config)
+                    .ok_or_handle(&*ui);
+            }
+
             ui.separator();
 
             if ui.button("Preferences").clicked() {
@@ -133,14 +149,56 @@ impl<'a> MenuBar<'a> {
     fn selection_menu(&mut self, ui: &mut egui::Ui) {
         ui.menu_button("Selection", |ui| {
             setup_menu(ui);
-            self.composer_menu_elements().selection_menu_buttons(ui);
+            let mut composer_menu_elements = self.composer_menu_elements();
+            composer_menu_elements.selection_menu_buttons(ui);
+            ui.separator();
+            composer_menu_elements.arrange_submenu_button(ui);
         });
     }
 
     fn view_menu(&mut self, ui: &mut egui::Ui) {
         ui.menu_button("View", |ui| {
             setup_menu(ui);
-            self.composer_menu_elements().camera_submenu_button(ui);
+            let mut composer_menu_elements = self.composer_menu_elements();
+
+            composer_menu_elements.camera_submenu_button(ui);
+            composer_menu_elements.material_inspector_button(ui);
+            composer_menu_elements.dimensions_button(ui);
+            composer_menu_elements.problems_button(ui);
+            composer_menu_elements.measurements_button(ui);
+            composer_menu_elements.results_library_button(ui);
+            composer_menu_elements.parameters_button(ui);
+            composer_menu_elements.ports_button(ui);
+            composer_menu_elements.array_button(ui);
+
+            ui.separator();
+
+            ui.menu_button("Color Scheme", |ui| {
+                for color_scheme in ColorScheme::ALL {
+                    if ui
+                        .radio_value(
+                            &mut self.app.config.color_scheme,
+                            color_scheme,
+                            color_scheme.label(),
+                        )
+                        .on_hover_text("Set color_scheme in the config file to keep it")
+                        .clicked()
+                    {
+                        color_scheme.make_current();
+                    }
+                }
+            });
+
+            ui.separator();
+
+            let num_running = self.app.jobs.num_running();
+            let label = if num_running > 0 {
+                format!("Jobs ({num_running})")
+            }
+            else {
+                "Jobs".to_owned()
+            };
+            ui.checkbox(&mut self.app.show_jobs, label);
         });
     }
 
@@ -150,8 +208,43 @@ impl<'a> MenuBar<'a> {
             let mut composer_menu_elements = self.composer_menu_elements();
 
             composer_menu_elements.configure_solver_button(ui);
+            composer_menu_elements.units_submenu_button(ui);
+            composer_menu_elements.propose_refinement_button(ui);
+            composer_menu_elements.ground_plane_submenu_button(ui);
+            composer_menu_elements.feed_wizard_submenu_button(ui);
+            composer_menu_elements.far_field_probe_button(ui);
             ui.separator();
             composer_menu_elements.solver_run_buttons(ui);
+            composer_menu_elements.batch_run_button(ui);
+            ui.separator();
+
+            if ui.button("Tuning Assistant").clicked() {
+                self.app.tuning_assistant.open = true;
+            }
+            if ui
+                .button("Sequence Run")
+                .on_hover_text("Run the solver for a series of parameter values")
+                .clicked()
+            {
+                self.app.sequence_run.open = true;
+            }
+            if ui
+                .button("Convergence Study")
+                .on_hover_text("Run the solver at increasingly fine resolutions")
+                .clicked()
+            {
+                self.app.convergence_study.open = true;
+            }
+            if ui
+                .button("Line Characterization")
+                .on_hover_text("Extract the parameters of a transmission line")
+                .clicked()
+            {
+                self.app.line_wizard.open = true;
+            }
+            if ui.button("Monitor Remote Run").clicked() {
+                self.app.remote_monitor.open = true;
+            }
         });
     }
 
@@ -174,6 +267,35 @@ impl<'a> MenuBar<'a> {
                 ui.ctx()
                     .open_url(egui::OpenUrl::new_tab(GithubUrls::PACKAGE.issues()));
             }
+
+            let mut is_recording = self.app.recorder.is_recording();
+            let hover_text = match self.app.recorder.recording_path() {
+                Some(path) => format!("Recording to {}", format_path(&path)),
+                None => {
+                    "Records what you do, so it can be attached to an issue and replayed with \
+                     --replay"
+                        .to_owned()
+                }
+            };
+            if ui
+                .checkbox(&mut is_recording, "Record Actions")
+                .on_hover_text(hover_text)
+                .changed()
+            {
+                if is_recording {
+                    let file_name = chrono::Local::now()
+                        .format("%Y-%m-%d_%H-%M-%S.jsonl")
+                        .to_string();
+                    self.app
+                        .recorder
+                        .start_recording(self.app.app_files.recordings_dir().join(file_name))
+                        .ok_or_handle(&*ui);
+                }
+                else {
+                    self.app.recorder.stop_recording();
+                }
+            }
+
             if ui.button("View License").clicked() {
                 ui.ctx()
                     .open_url(egui::OpenUrl::new_tab(GithubUrls::PACKAGE.license()));
@@ -0,0 +1,692 @@
+//! Opt-in recording of what the user does, for reproducing bugs.
+//!
+//! While recording, high-level actions (opening a file, creating or deleting
+//! an object, changing a property, running a solver, ...) are appended to a
+//! script with one JSON object per line. Each line has the time in seconds
+//! since the recording started:
+//!
+//! ```text
+//! {"time":0.0,"action":"new_file"}
+//! {"time":4.2,"action":"set_component","entity":"6c0f1f4e-...","component":"cem_app::solver::port::Port","value":{...}}
+//! {"time":9.8,"action":"run_solver","solver":0,"label":"FDTD"}
+//! ```
+//!
+//! Recording is started with `--record <path>` or from the *Help* menu. A
+//! script is replayed with `--replay <path>`, at the pace it was recorded.
+//!
+//! Objects are referred to by their [`StableId`], so a replay should start
+//! from the same file as the recording did. Opening a file gives its objects
+//! IDs that only depend on the file (see [`assign_file_ids`]), so the replay
+//! finds objects that existed before the recording started. Objects that
+//! don't have a stable ID get one when they're first recorded. Created objects
+//! are recorded with their serializable components, so a replay recreates
+//! them without e.g. their meshes.
+
+use std::{
+    collections::{
+        HashMap,
+        HashSet,
+        VecDeque,
+    },
+    fs::File,
+    io::{
+        BufRead,
+        BufReader,
+        BufWriter,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::Arc,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use bevy_ecs::{
+    entity::Entity,
+    hierarchy::ChildOf,
+    name::Name,
+    query::With,
+    reflect::{
+        AppTypeRegistry,
+        ReflectComponent,
+    },
+    resource::Resource,
+    world::World,
+};
+use bevy_reflect::serde::{
+    TypedReflectDeserializer,
+    TypedReflectSerializer,
+};
+use cem_scene::{
+    serde::EntitySerialize,
+    stable_id::{
+        StableId,
+        StableIds,
+    },
+};
+use color_eyre::eyre::{
+    OptionExt,
+    eyre,
+};
+use parking_lot::Mutex;
+use serde::{
+    Deserialize,
+    Serialize,
+    de::DeserializeSeed,
+};
+
+use crate::{
+    Error,
+    app::App,
+    composer::{
+        ComposerState,
+        tree::ShowInTree,
+    },
+};
+
+/// Handle to the recording.
+///
+/// This is cheap to clone. The app owns it, and every composer's world has a
+/// clone as a resource, so that edits made to the world can be recorded.
+#[derive(Clone, Debug, Default, Resource)]
+pub struct Recorder {
+    shared: Arc<Mutex<Shared>>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    recording: Option<Recording>,
+
+    /// Incremented whenever a recording starts, so objects that already
+    /// existed aren't recorded as created.
+    generation: usize,
+}
+
+#[derive(Debug)]
+struct Recording {
+    path: PathBuf,
+    start: Instant,
+    writer: BufWriter<File>,
+}
+
+impl Recorder {
+    /// Starts recording into a new script at `path`.
+    ///
+    /// A recording that is already running is stopped.
+    pub fn start_recording(&self, path: impl Into<PathBuf>) -> Result<(), Error> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let writer = BufWriter::new(File::create(&path)?);
+
+        tracing::info!(path = %path.display(), "recording actions");
+        let mut shared = self.shared.lock();
+        shared.generation += 1;
+        shared.recording = Some(Recording {
+            path,
+            start: Instant::now(),
+            writer,
+        });
+
+        Ok(())
+    }
+
+    pub fn stop_recording(&self) {
+        if let Some(recording) = self.shared.lock().recording.take() {
+            tracing::info!(path = %recording.path.display(), "stopped recording actions");
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.shared.lock().recording.is_some()
+    }
+
+    /// Path of the script that is being recorded into.
+    pub fn recording_path(&self) -> Option<PathBuf> {
+        self.shared
+            .lock()
+            .recording
+            .as_ref()
+            .map(|recording| recording.path.clone())
+    }
+
+    /// Appends an action to the script, if recording.
+    pub fn record(&self, action: Action) {
+        let mut shared = self.shared.lock();
+        let Some(recording) = &mut shared.recording
+        else {
+            return;
+        };
+
+        let line = RecordedAction {
+            time: recording.start.elapsed().as_secs_f64(),
+            action,
+        };
+
+        // every line is flushed, so the script is complete even if the app crashes
+        let result = serde_json::to_writer(&mut recording.writer, &line)
+            .map_err(Error::from)
+            .and_then(|()| {
+                writeln!(recording.writer)?;
+                recording.writer.flush()?;
+                Ok(())
+            });
+        if let Err(error) = result {
+            tracing::error!(?error, "failed to record action");
+        }
+    }
+
+    fn generation(&self) -> usize {
+        self.shared.lock().generation
+    }
+
+    /// The recorder of a composer's world, if it's recording.
+    fn recording_in(world: &World) -> Option<Self> {
+        world
+            .get_resource::<Self>()
+            .filter(|recorder| recorder.is_recording())
+            .cloned()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedAction {
+    /// Seconds since the recording started
+    pub time: f64,
+
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    NewFile,
+    OpenFile {
+        path: PathBuf,
+    },
+    SaveFile {
+        path: Option<PathBuf>,
+    },
+    CreateEntity {
+        entity: StableId,
+        name: Option<String>,
+        /// The entity as it's written to project files
+        components: serde_json::Value,
+    },
+    DeleteEntity {
+        entity: StableId,
+        name: Option<String>,
+    },
+    SetComponent {
+        entity: StableId,
+        /// Type path of the component
+        component: String,
+        value: serde_json::Value,
+    },
+    Undo,
+    Redo,
+    RunSolver {
+        /// Index of the solver configuration
+        solver: usize,
+        label: String,
+    },
+    StopSolver,
+}
+
+impl Action {
+    /// Does what the action recorded the user doing.
+    pub fn replay(self, app: &mut App) -> Result<(), Error> {
+        tracing::debug!(action = ?self, "replaying");
+
+        match self {
+            Action::NewFile => app.composers.new_file(&app.config),
+            Action::OpenFile { path } => app.composers.open_file(&app.config, path)?,
+            Action::SaveFile { path } => app.composers.save_file(path.as_deref())?,
+            Action::CreateEntity { .. } | Action::SetComponent { .. } => {
+                app.composers
+                    .with_active_mut(|composer| self.replay_in_world(&mut composer.scene.world))
+                    .ok_or_eyre("No file open")??;
+            }
+            Action::DeleteEntity { entity, .. } => {
+                app.composers
+                    .with_active_mut(|composer| {
+                        let entity = lookup_entity(&composer.scene.world, entity)?;
+                        composer.delete([entity]);
+                        Ok::<_, Error>(())
+                    })
+                    .ok_or_eyre("No file open")??;
+            }
+            Action::Undo => {
+                app.composers.with_active_mut(ComposerState::undo);
+            }
+            Action::Redo => {
+                app.composers.with_active_mut(ComposerState::redo);
+            }
+            Action::RunSolver { solver, .. } => {
+                let solver_runner = &mut app.solver_runner;
+                app.composers
+                    .with_active_mut(|composer| {
+                        let solver_config = composer
+                            .solver_configs
+                            .get(solver)
+                            .ok_or_eyre("No such solver")?;
+                        solver_runner.run(solver_config, &mut composer.scene)
+                    })
+                    .ok_or_eyre("No file open")??;
+            }
+            Action::StopSolver => app.solver_runner.stop(),
+        }
+
+        Ok(())
+    }
+
+    /// Replays the actions that only edit the objects in a world.
+    fn replay_in_world(self, world: &mut World) -> Result<(), Error> {
+        match self {
+            Action::CreateEntity {
+                entity, components, ..
+            } => spawn_recorded(world, entity, components),
+            Action::SetComponent {
+                entity,
+                component,
+                value,
+            } => {
+                let entity = lookup_entity(world, entity)?;
+                insert_recorded_component(world, entity, &component, value)
+            }
+            _ => Err(eyre!("Not an action on objects: {self:?}")),
+        }
+    }
+}
+
+fn lookup_entity(world: &World, id: StableId) -> Result<Entity, Error> {
+    world
+        .resource::<StableIds>()
+        .get(&id)
+        .ok_or_else(|| eyre!("No object with ID {id}"))
+}
+
+/// Gives the objects of a newly opened file IDs that only depend on the file.
+///
+/// Objects spawned when a file is opened get random IDs, so a replay, which
+/// opens the file again, couldn't find them by their IDs. IDs that were loaded
+/// from a project file are kept, if `keep_loaded` is set.
+pub fn assign_file_ids(world: &mut World, keep_loaded: bool) {
+    // the file is populated the same way every time, so the objects are spawned in
+    // the same order.
+    let mut entities = world
+        .query_filtered::<Entity, With<ShowInTree>>()
+        .iter(world)
+        .collect::<Vec<_>>();
+    entities.sort();
+
+    for (index, entity) in entities.into_iter().enumerate() {
+        let mut entity = world.entity_mut(entity);
+        if !(keep_loaded && entity.contains::<StableId>()) {
+            entity.insert(StableId::from_index(index));
+        }
+    }
+}
+
+/// The entity's stable ID. If it doesn't have one, it gets one.
+///
+/// Objects in a scene get one when they're spawned, since it's required by
+/// [`LocalTransform`](cem_scene::transform::LocalTransform).
+pub fn stable_id(world: &mut World, entity: Entity) -> StableId {
+    let mut entity = world.entity_mut(entity);
+    if let Some(id) = entity.get::<StableId>() {
+        *id
+    }
+    else {
+        let id = StableId::new();
+        entity.insert(id);
+        id
+    }
+}
+
+/// Records a component that was edited in the UI.
+pub fn record_component_change(
+    world: &mut World,
+    entity: Entity,
+    reflect_component: &ReflectComponent,
+) {
+    let Some(recorder) = Recorder::recording_in(world)
+    else {
+        return;
+    };
+
+    let id = stable_id(world, entity);
+    let type_registry = world.resource::<AppTypeRegistry>().read();
+    let Some(component) = reflect_component.reflect(world.entity(entity))
+    else {
+        return;
+    };
+    let type_path = component.reflect_type_path();
+
+    match serde_json::to_value(TypedReflectSerializer::new(
+        component.as_partial_reflect(),
+        &type_registry,
+    )) {
+        Ok(value) => {
+            recorder.record(Action::SetComponent {
+                entity: id,
+                component: type_path.to_owned(),
+                value,
+            });
+        }
+        Err(error) => {
+            tracing::debug!(?error, component = type_path, "can't record component");
+        }
+    }
+}
+
+/// Objects in a world that were already recorded.
+#[derive(Debug, Default, Resource)]
+struct TrackedEntities {
+    generation: usize,
+    entities: HashMap<Entity, (StableId, Option<String>)>,
+}
+
+/// Records objects that were created or deleted since the last call.
+///
+/// This is called once per frame, so objects are recorded once they're set
+/// up. Only objects that are shown in the object tree are recorded.
+pub fn track_entities(world: &mut World) {
+    let Some(recorder) = Recorder::recording_in(world)
+    else {
+        return;
+    };
+
+    let generation = recorder.generation();
+    let current = world
+        .query_filtered::<Entity, With<ShowInTree>>()
+        .iter(world)
+        .collect::<HashSet<_>>();
+
+    let mut tracked = world
+        .remove_resource::<TrackedEntities>()
+        .unwrap_or_default();
+
+    // objects that existed when the recording started, or that came with an
+    // opened file, weren't created by the user.
+    let initial = tracked.generation != generation;
+    if initial {
+        tracked = TrackedEntities {
+            generation,
+            entities: HashMap::new(),
+        };
+    }
+
+    for entity in &current {
+        if tracked.entities.contains_key(entity) {
+            continue;
+        }
+
+        let id = stable_id(world, *entity);
+        let name = world
+            .get::<Name>(*entity)
+            .map(|name| name.as_str().to_owned());
+
+        if !initial {
+            let type_registry = world.resource::<AppTypeRegistry>().read();
+            match serde_json::to_value(EntitySerialize {
+                world: &*world,
+                entity: *entity,
+                type_registry: &type_registry,
+            }) {
+                Ok(components) => {
+                    recorder.record(Action::CreateEntity {
+                        entity: id,
+                        name: name.clone(),
+                        components,
+                    });
+                }
+                Err(error) => tracing::error!(?error, "can't record created entity"),
+            }
+        }
+
+        tracked.entities.insert(*entity, (id, name));
+    }
+
+    tracked.entities.retain(|entity, (id, name)| {
+        let exists = current.contains(entity);
+        if !exists {
+            recorder.record(Action::DeleteEntity {
+                entity: *id,
+                name: name.clone(),
+            });
+        }
+        exists
+    });
+
+    world.insert_resource(tracked);
+}
+
+/// Spawns an entity from the components recorded with
+/// [`Action::CreateEntity`].
+fn spawn_recorded(
+    world: &mut World,
+    id: StableId,
+    components: serde_json::Value,
+) -> Result<(), Error> {
+    let serde_json::Value::Object(components) = components
+    else {
+        return Err(eyre!("Recorded entity is not a map"));
+    };
+
+    let entity = world.spawn(id).id();
+
+    for (key, value) in components {
+        match key.as_str() {
+            // already inserted
+            "id" => {}
+            "parent" => {
+                let parent = serde_json::from_value::<StableId>(value)?;
+                let parent = lookup_entity(world, parent)?;
+                world.entity_mut(entity).insert(ChildOf(parent));
+            }
+            type_path => {
+                if let Err(error) = insert_recorded_component(world, entity, type_path, value) {
+                    tracing::warn!(?error, type_path, "can't replay component");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn insert_recorded_component(
+    world: &mut World,
+    entity: Entity,
+    type_path: &str,
+    value: serde_json::Value,
+) -> Result<(), Error> {
+    let type_registry = world.resource::<AppTypeRegistry>().clone();
+    let type_registry = type_registry.read();
+
+    let registration = type_registry
+        .get_with_type_path(type_path)
+        .ok_or_else(|| eyre!("Unknown component: {type_path}"))?;
+    let reflect_component = registration
+        .data::<ReflectComponent>()
+        .ok_or_else(|| eyre!("Not a component: {type_path}"))?;
+
+    let component =
+        TypedReflectDeserializer::new(registration, &type_registry).deserialize(value)?;
+    reflect_component.insert(&mut world.entity_mut(entity), &*component, &type_registry);
+
+    Ok(())
+}
+
+/// Replays a recorded script, see the [module documentation](self).
+#[derive(Debug)]
+pub struct Replay {
+    actions: VecDeque<RecordedAction>,
+    start: Instant,
+}
+
+impl Replay {
+    pub fn from_path(path: &Path) -> Result<Self, Error> {
+        let mut actions = VecDeque::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                actions.push_back(serde_json::from_str(&line)?);
+            }
+        }
+
+        tracing::info!(path = %path.display(), num_actions = actions.len(), "replaying actions");
+
+        Ok(Self {
+            actions,
+            start: Instant::now(),
+        })
+    }
+
+    /// Removes the actions that are due, in the order they were recorded.
+    pub fn take_due(&mut self) -> Vec<Action> {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let mut due = vec![];
+        while self
+            .actions
+            .front()
+            .is_some_and(|recorded| recorded.time <= elapsed)
+        {
+            due.extend(self.actions.pop_front().map(|recorded| recorded.action));
+        }
+        due
+    }
+
+    /// Time until the next action is due, or `None` if the replay is done.
+    pub fn next_due_in(&self) -> Option<Duration> {
+        let next = self.actions.front()?;
+        Some(Duration::from_secs_f64(next.time).saturating_sub(self.start.elapsed()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        reflect::{
+            AppTypeRegistry,
+            ReflectComponent,
+        },
+        world::World,
+    };
+    use bevy_reflect::{
+        Reflect,
+        ReflectDeserialize,
+        ReflectSerialize,
+    };
+    use cem_scene::stable_id::{
+        StableId,
+        StableIds,
+    };
+    use serde::{
+        Deserialize,
+        Serialize,
+    };
+
+    use crate::{
+        composer::tree::ShowInTree,
+        recorder::{
+            Recorder,
+            Replay,
+            assign_file_ids,
+            record_component_change,
+            track_entities,
+        },
+    };
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Component, Reflect)]
+    #[reflect(Component, Serialize, Deserialize)]
+    struct Label(String);
+
+    /// A world as it is after opening the same file.
+    fn opened_file() -> (World, [Entity; 2]) {
+        let mut world = World::new();
+        world.insert_resource(StableIds::default());
+        let type_registry = AppTypeRegistry::default();
+        type_registry.write().register::<Label>();
+        type_registry.write().register::<ShowInTree>();
+        world.insert_resource(type_registry);
+
+        let entities = [
+            world.spawn((ShowInTree, Label("a".to_owned()))).id(),
+            world.spawn((ShowInTree, Label("b".to_owned()))).id(),
+        ];
+        assign_file_ids(&mut world, false);
+
+        (world, entities)
+    }
+
+    #[test]
+    fn replay_finds_objects_of_opened_file() {
+        let path =
+            std::env::temp_dir().join(format!("cem-recorder-test-{}.jsonl", std::process::id()));
+
+        let (mut world, [a, _]) = opened_file();
+        let recorder = Recorder::default();
+        world.insert_resource(recorder.clone());
+        recorder.start_recording(&path).unwrap();
+
+        // the objects of the file aren't recorded as created
+        track_entities(&mut world);
+
+        world.entity_mut(a).insert(Label("edited".to_owned()));
+        let type_registry = world.resource::<AppTypeRegistry>().clone();
+        let reflect_component = type_registry
+            .read()
+            .get(std::any::TypeId::of::<Label>())
+            .unwrap()
+            .data::<ReflectComponent>()
+            .unwrap()
+            .clone();
+        record_component_change(&mut world, a, &reflect_component);
+
+        let created = world
+            .spawn((ShowInTree, Label("c".to_owned()), ChildOf(a)))
+            .id();
+        track_entities(&mut world);
+        recorder.stop_recording();
+
+        let replay = Replay::from_path(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replay.actions.len(), 2);
+
+        let (mut replayed, [replayed_a, _]) = opened_file();
+        for recorded in replay.actions {
+            recorded.action.replay_in_world(&mut replayed).unwrap();
+        }
+
+        assert_eq!(
+            replayed.get::<Label>(replayed_a),
+            Some(&Label("edited".to_owned()))
+        );
+        let replayed_created = replayed
+            .resource::<StableIds>()
+            .get(world.get::<StableId>(created).unwrap())
+            .unwrap();
+        assert_eq!(
+            replayed.get::<Label>(replayed_created),
+            Some(&Label("c".to_owned()))
+        );
+        assert_eq!(
+            replayed
+                .get::<ChildOf>(replayed_created)
+                .map(ChildOf::parent),
+            Some(replayed_a)
+        );
+    }
+}
@@ -0,0 +1,538 @@
+//! Phased arrays.
+//!
+//! Every feed or source can be given an [`Excitation`], which scales and
+//! delays its waveform. The [`ArrayWindow`] edits the excitations of all
+//! sources in a scene, steers the main beam by computing their delays, and
+//! previews the resulting array factor before anything is simulated.
+//!
+//! Delays are true time delays, so a steered array keeps its beam direction
+//! over the whole bandwidth of a pulse. Phases are only shown for the
+//! frequency the window is set to.
+
+use std::f64::consts::TAU;
+
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    name::NameOrEntity,
+    query::{
+        Or,
+        With,
+    },
+    reflect::ReflectComponent,
+    world::World,
+};
+use bevy_reflect::{
+    Reflect,
+    ReflectSerialize,
+    prelude::ReflectDefault,
+};
+use cem_probe::{
+    PropertiesUi,
+    PropertiesUiExt,
+    TrackChanges,
+};
+use cem_scene::{
+    probe::{
+        ComponentName,
+        ReflectComponentUi,
+    },
+    transform::{
+        GlobalTransform,
+        propagate_transforms,
+    },
+};
+use cem_solver::{
+    material::{
+        PhysicalConstants,
+        UnitConversion,
+        UnitSystem,
+    },
+    source::{
+        Delayed,
+        Scaled,
+        ScaledAmplitudes,
+        Source,
+        feed::Feed,
+    },
+};
+use nalgebra::{
+    Point3,
+    Vector3,
+};
+use num::complex::Complex64;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::solver::{
+    pattern::{
+        PolarPlotConfig,
+        RadiationPattern,
+        polar_pattern_plot,
+    },
+    units::SceneUnits,
+};
+
+/// Number of theta samples of the array factor preview (2° steps).
+const PREVIEW_THETA_SAMPLES: usize = 91;
+
+/// Number of phi samples of the array factor preview (2° steps).
+const PREVIEW_PHI_SAMPLES: usize = 180;
+
+/// Amplitude and delay of a feed or source.
+///
+/// Entities without this component are excited with amplitude 1 and no
+/// delay.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Component, Reflect)]
+#[reflect(Component, ComponentUi, @ComponentName::new("Excitation"), Default, Serialize)]
+pub struct Excitation {
+    /// Factor the waveform is scaled with
+    pub amplitude: f64,
+
+    /// Time the waveform is delayed by. For a time-harmonic excitation at
+    /// frequency `f` this is a phase lag of `2π f delay`.
+    pub delay: f64,
+}
+
+impl Default for Excitation {
+    fn default() -> Self {
+        Self {
+            amplitude: 1.0,
+            delay: 0.0,
+        }
+    }
+}
+
+impl Excitation {
+    /// Phase at `frequency` in degrees, wrapped to [-180°, 180°).
+    pub fn phase(&self, frequency: f64) -> f64 {
+        (-360.0 * frequency * self.delay + 180.0).rem_euclid(360.0) - 180.0
+    }
+
+    /// Sets the delay, so that the excitation has the given phase (in degrees)
+    /// at `frequency`.
+    ///
+    /// This does nothing if `frequency` isn't positive.
+    pub fn set_phase(&mut self, frequency: f64, phase: f64) {
+        if frequency > 0.0 {
+            self.delay = -phase / (360.0 * frequency);
+        }
+    }
+
+    pub fn apply_to_feed(&self, feed: &Feed) -> Feed {
+        Feed::new(
+            feed.kind,
+            Scaled {
+                scale: self.amplitude,
+                inner: Delayed {
+                    delay: self.delay,
+                    inner: feed.waveform.clone(),
+                },
+            },
+        )
+    }
+
+    pub fn apply_to_source(&self, source: &Source) -> Source {
+        Source::from(ScaledAmplitudes {
+            j: self.amplitude,
+            m: self.amplitude,
+            inner: Delayed {
+                delay: self.delay,
+                inner: source.0.clone(),
+            },
+        })
+    }
+
+    pub fn convert_units(&self, conversion: &UnitConversion) -> Self {
+        Self {
+            amplitude: self.amplitude,
+            delay: self.delay * conversion.time,
+        }
+    }
+}
+
+impl PropertiesUi for Excitation {
+    type Config = ();
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
+        let _ = config;
+        let mut changes = TrackChanges::default();
+        let units = UnitSystem::display_units(ui.ctx());
+
+        let response = egui::Frame::new()
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Amplitude");
+                    changes.track(
+                        ui.add(
+                            egui::DragValue::new(&mut self.amplitude)
+                                .range(0.0..=f64::INFINITY)
+                                .speed(0.01),
+                        ),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Delay");
+                    changes.track(
+                        ui.add(
+                            egui::DragValue::new(&mut self.delay)
+                                .speed(1e-12)
+                                .suffix(format!(" {}", units.time_unit())),
+                        ),
+                    );
+                });
+            })
+            .response;
+
+        changes.propagated(response)
+    }
+}
+
+/// A single radiating element of an array.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArrayElement {
+    pub position: Point3<f64>,
+    pub excitation: Excitation,
+}
+
+/// Unit vector pointing towards theta, phi (in degrees).
+///
+/// Theta is measured from +Z, and phi from +X towards +Y, like in
+/// [`RadiationPattern`]s.
+pub fn direction(theta: f64, phi: f64) -> Vector3<f64> {
+    let (sin_theta, cos_theta) = theta.to_radians().sin_cos();
+    let (sin_phi, cos_phi) = phi.to_radians().sin_cos();
+    Vector3::new(sin_theta * cos_phi, sin_theta * sin_phi, cos_theta)
+}
+
+/// Delays that make the waves of elements at `positions` add up in
+/// `direction`.
+///
+/// The delays are shifted so that the smallest one is 0.
+pub fn steering_delays(
+    positions: impl IntoIterator<Item = Point3<f64>>,
+    direction: &Vector3<f64>,
+    speed_of_light: f64,
+) -> Vec<f64> {
+    let delays = positions
+        .into_iter()
+        .map(|position| position.coords.dot(direction) / speed_of_light)
+        .collect::<Vec<_>>();
+    let min = delays.iter().copied().fold(f64::INFINITY, f64::min);
+    delays.into_iter().map(|delay| delay - min).collect()
+}
+
+/// Array factor of isotropic elements in `direction`.
+pub fn array_factor(
+    elements: &[ArrayElement],
+    direction: &Vector3<f64>,
+    frequency: f64,
+    speed_of_light: f64,
+) -> Complex64 {
+    let omega = TAU * frequency;
+    elements
+        .iter()
+        .map(|element| {
+            let path_delay = element.position.coords.dot(direction) / speed_of_light;
+            element.excitation.amplitude
+                * Complex64::cis(omega * (path_delay - element.excitation.delay))
+        })
+        .sum()
+}
+
+/// Samples the array factor over the full sphere.
+///
+/// The array factor is stored as the theta component of the pattern's field,
+/// so the pattern's gain is the directivity of an array of isotropic
+/// elements.
+pub fn array_factor_pattern(
+    elements: &[ArrayElement],
+    frequency: f64,
+    physical_constants: &PhysicalConstants,
+) -> RadiationPattern {
+    let speed_of_light = physical_constants.speed_of_light();
+    let mut pattern = RadiationPattern::new(
+        "Array Factor",
+        frequency,
+        PREVIEW_THETA_SAMPLES,
+        PREVIEW_PHI_SAMPLES,
+        physical_constants.vacuum_impedance(),
+    );
+
+    for phi_index in 0..pattern.num_phi() {
+        for theta_index in 0..pattern.num_theta() {
+            let direction = direction(pattern.theta(theta_index), pattern.phi(phi_index));
+            let value = array_factor(elements, &direction, frequency, speed_of_light);
+            pattern.set(theta_index, phi_index, value, Complex64::ZERO);
+        }
+    }
+
+    pattern
+}
+
+/// Edits the excitations of all feeds and sources of a scene, and shows the
+/// array factor they produce.
+#[derive(Debug, Default)]
+pub struct ArrayWindow {
+    pub open: bool,
+
+    /// Frequency phases are shown for and the array factor is computed at.
+    /// This is set to a default for the scene's units when the window is shown
+    /// first.
+    frequency: Option<f64>,
+
+    /// Direction of the main beam in degrees
+    theta: f64,
+    phi: f64,
+
+    /// Whether the delays follow the steering direction
+    steer: bool,
+
+    plot: PolarPlotConfig,
+
+    preview: ArrayFactorPreview,
+}
+
+impl ArrayWindow {
+    pub fn show(&mut self, ctx: &egui::Context, world: &mut World) {
+        if !self.open {
+            return;
+        }
+
+        propagate_transforms(world);
+
+        let units = SceneUnits::get(world);
+        let physical_constants = units.physical_constants();
+        let frequency = *self.frequency.get_or_insert(match units {
+            UnitSystem::Si => 1e9,
+            UnitSystem::Normalized => 1.0,
+        });
+
+        let mut elements = world
+            .query_filtered::<
+                (Entity, NameOrEntity, &GlobalTransform, Option<&Excitation>),
+                Or<(With<Feed>, With<Source>)>,
+            >()
+            .iter(world)
+            .map(|(entity, name, transform, excitation)| {
+                let element = ArrayElement {
+                    position: transform.position().cast(),
+                    excitation: excitation.copied().unwrap_or_default(),
+                };
+                (entity, name.to_string(), element)
+            })
+            .collect::<Vec<_>>();
+        elements.sort_by(|(_, a, _), (_, b, _)| a.cmp(b));
+        let previous = elements
+            .iter()
+            .map(|(_, _, element)| element.excitation)
+            .collect::<Vec<_>>();
+
+        let mut open = true;
+        egui::Window::new("Array Excitation")
+            .movable(true)
+            .default_size([650.0, 400.0])
+            .open(&mut open)
+            .show(ctx, |ui| {
+                if elements.is_empty() {
+                    ui.weak("No sources. Add one with Run > Add Feed.");
+                    return;
+                }
+
+                let mut frequency = frequency;
+                let steering_changed = ui
+                    .horizontal(|ui| {
+                        ui.label("Frequency");
+                        let mut response = ui.add(
+                            egui::DragValue::new(&mut frequency)
+                                .range(0.0..=f64::INFINITY)
+                                .speed(frequency * 1e-3)
+                                .suffix(format!(" {}", units.frequency_unit())),
+                        );
+
+                        ui.separator();
+
+                        response |= ui.checkbox(&mut self.steer, "Steer").on_hover_text(
+                            "Compute the delays of all elements, so that the main beam points \
+                             towards θ, φ.",
+                        );
+                        response |= ui.add(
+                            egui::DragValue::new(&mut self.theta)
+                                .range(0.0..=180.0)
+                                .suffix("°")
+                                .prefix("θ "),
+                        );
+                        response |= ui.add(
+                            egui::DragValue::new(&mut self.phi)
+                                .range(0.0..=360.0)
+                                .suffix("°")
+                                .prefix("φ "),
+                        );
+                        response.changed()
+                    })
+                    .inner;
+                self.frequency = Some(frequency);
+
+                if self.steer && steering_changed {
+                    let delays = steering_delays(
+                        elements.iter().map(|(_, _, element)| element.position),
+                        &direction(self.theta, self.phi),
+                        physical_constants.speed_of_light(),
+                    );
+                    for ((_, _, element), delay) in elements.iter_mut().zip(delays) {
+                        element.excitation.delay = delay;
+                    }
+                }
+
+                ui.separator();
+
+                ui.columns(2, |columns| {
+                    egui::ScrollArea::vertical().show(&mut columns[0], |ui| {
+                        egui::Grid::new("array_elements")
+                            .num_columns(3)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.strong("Element");
+                                ui.strong("Amplitude");
+                                ui.strong("Phase");
+                                ui.end_row();
+
+                                for (_, name, element) in &mut elements {
+                                    ui.label(&*name);
+                                    ui.add(
+                                        egui::DragValue::new(&mut element.excitation.amplitude)
+                                            .range(0.0..=f64::INFINITY)
+                                            .speed(0.01),
+                                    );
+                                    let mut phase = element.excitation.phase(frequency);
+                                    if ui
+                                        .add(
+                                            egui::DragValue::new(&mut phase).speed(1.0).suffix("°"),
+                                        )
+                                        .changed()
+                                    {
+                                        element.excitation.set_phase(frequency, phase);
+                                        // the phases don't follow the steering anymore
+                                        self.steer = false;
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                    let ui = &mut columns[1];
+                    let array_elements = elements
+                        .iter()
+                        .map(|(_, _, element)| *element)
+                        .collect::<Vec<_>>();
+                    let pattern =
+                        self.preview
+                            .pattern(array_elements, frequency, &physical_constants);
+
+                    let (theta, phi, gain) = pattern.max_gain();
+                    ui.label(format!(
+                        "Directivity: {:.2} dBi at θ = {theta:.0}°, φ = {phi:.0}°",
+                        10.0 * gain.log10()
+                    ))
+                    .on_hover_text("Of the array factor, i.e. with isotropic elements");
+
+                    ui.properties(&mut self.plot);
+                    polar_pattern_plot(ui, pattern, &self.plot);
+                });
+            });
+        self.open = open;
+
+        for ((entity, _, element), previous) in elements.iter().zip(previous) {
+            if element.excitation != previous {
+                world.entity_mut(*entity).insert(element.excitation);
+            }
+        }
+    }
+}
+
+/// Array factor of the elements the window showed last.
+///
+/// The array factor is only recomputed when the elements or the frequency
+/// change.
+#[derive(Debug, Default)]
+struct ArrayFactorPreview {
+    cached: Option<(Vec<ArrayElement>, f64, RadiationPattern)>,
+}
+
+impl ArrayFactorPreview {
+    fn pattern(
+        &mut self,
+        elements: Vec<ArrayElement>,
+        frequency: f64,
+        physical_constants: &PhysicalConstants,
+    ) -> &RadiationPattern {
+        let outdated =
+            self.cached
+                .as_ref()
+                .is_none_or(|(previous_elements, previous_frequency, _)| {
+                    *previous_elements != elements || *previous_frequency != frequency
+                });
+        if outdated {
+            let pattern = array_factor_pattern(&elements, frequency, physical_constants);
+            self.cached = Some((elements, frequency, pattern));
+        }
+
+        &self.cached.as_ref().unwrap().2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cem_solver::material::PhysicalConstants;
+    use nalgebra::Point3;
+
+    use crate::solver::array::{
+        ArrayElement,
+        Excitation,
+        array_factor,
+        direction,
+        steering_delays,
+    };
+
+    #[test]
+    fn phase_round_trips_through_delay() {
+        let mut excitation = Excitation::default();
+        excitation.set_phase(2.0, -45.0);
+        let phase = excitation.phase(2.0);
+        assert!((phase + 45.0).abs() < 1e-9, "{phase}");
+        assert!(excitation.delay > 0.0);
+    }
+
+    #[test]
+    fn steered_array_factor_peaks_in_steering_direction() {
+        let speed_of_light = PhysicalConstants::REDUCED.speed_of_light();
+        let frequency = 1.0;
+        let spacing = 0.5 * speed_of_light / frequency;
+        let positions = (0..4)
+            .map(|i| Point3::new(i as f64 * spacing, 0.0, 0.0))
+            .collect::<Vec<_>>();
+
+        let steering = direction(30.0, 0.0);
+        let elements = steering_delays(positions.iter().copied(), &steering, speed_of_light)
+            .into_iter()
+            .zip(&positions)
+            .map(|(delay, position)| {
+                ArrayElement {
+                    position: *position,
+                    excitation: Excitation {
+                        amplitude: 1.0,
+                        delay,
+                    },
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let peak = array_factor(&elements, &steering, frequency, speed_of_light).norm();
+        assert!((peak - 4.0).abs() < 1e-9, "{peak}");
+
+        let broadside = array_factor(&elements, &direction(0.0, 0.0), frequency, speed_of_light);
+        assert!(broadside.norm() < peak);
+    }
+}
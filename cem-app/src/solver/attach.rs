@@ -0,0 +1,102 @@
+//! Attaching probes, observers and NF2FF boxes to scene objects.
+//!
+//! Attached entities are children in the transform hierarchy, so they follow
+//! the object when it's moved. The solver resolves their positions through
+//! their [`GlobalTransform`] when a run starts.
+
+use bevy_ecs::{
+    entity::Entity,
+    hierarchy::ChildOf,
+    world::World,
+};
+use cem_scene::transform::{
+    GlobalTransform,
+    LocalTransform,
+    propagate_transforms,
+};
+
+use crate::solver::{
+    far_field::FarFieldProbe,
+    nf2ff::Nf2ffBox,
+    observer::Observer,
+    probe::{
+        LineProbe,
+        PointProbe,
+    },
+};
+
+/// Whether the entity is a probe, observer or NF2FF box.
+pub fn is_attachable(world: &World, entity: Entity) -> bool {
+    world.get_entity(entity).is_ok_and(|entity| {
+        entity.contains::<PointProbe>()
+            || entity.contains::<LineProbe>()
+            || entity.contains::<Observer>()
+            || entity.contains::<Nf2ffBox>()
+            || entity.contains::<FarFieldProbe>()
+    })
+}
+
+/// Splits the selection into the object to attach to and the entities to
+/// attach.
+///
+/// Returns `None` unless exactly one of the entities isn't attachable.
+pub fn attach_target(world: &World, entities: &[Entity]) -> Option<(Entity, Vec<Entity>)> {
+    let (attachable, targets): (Vec<Entity>, Vec<Entity>) = entities
+        .iter()
+        .copied()
+        .partition(|entity| is_attachable(world, *entity));
+
+    match (targets.as_slice(), attachable.is_empty()) {
+        ([target], false) => Some((*target, attachable)),
+        _ => None,
+    }
+}
+
+/// Attaches the entities to `parent`, keeping their current position in the
+/// world.
+pub fn attach_to(world: &mut World, parent: Entity, entities: &[Entity]) {
+    propagate_transforms(world);
+
+    let Some(parent_transform) = world.get::<GlobalTransform>(parent).copied()
+    else {
+        return;
+    };
+
+    for entity in entities.iter().copied().filter(|entity| *entity != parent) {
+        let Some(global_transform) = world.get::<GlobalTransform>(entity).copied()
+        else {
+            continue;
+        };
+
+        world.entity_mut(entity).insert((
+            LocalTransform::from_precise(
+                parent_transform
+                    .precise_isometry()
+                    .inv_mul(global_transform.precise_isometry()),
+            ),
+            ChildOf(parent),
+        ));
+    }
+}
+
+/// Detaches the entities from their parents, keeping their current position in
+/// the world.
+pub fn detach(world: &mut World, entities: &[Entity]) {
+    propagate_transforms(world);
+
+    for entity in entities.iter().copied() {
+        if world.get::<ChildOf>(entity).is_none() {
+            continue;
+        }
+        let Some(global_transform) = world.get::<GlobalTransform>(entity).copied()
+        else {
+            continue;
+        };
+
+        let mut entity = world.entity_mut(entity);
+        entity.remove::<ChildOf>();
+        entity.insert(LocalTransform::from_precise(
+            *global_transform.precise_isometry(),
+        ));
+    }
+}
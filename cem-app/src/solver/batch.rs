@@ -0,0 +1,524 @@
+//! Batch runs on compute clusters.
+//!
+//! A batch package is a directory with a copy of the project, its
+//! [assets](crate::composer::assets), an [`Extraction`] that exports the
+//! results, and submission scripts for SLURM and PBS. The package is copied to
+//! the cluster and submitted there, where the scripts run the project
+//! [headless](crate::headless).
+//!
+//! Headless runs write a [`RunRecord`] next to their results. Once the results
+//! are copied back, they can be imported into the project's
+//! [library](crate::solver::library).
+
+use std::{
+    fmt::Write,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use cem_util::egui::file_dialog::FileDialog;
+use color_eyre::eyre::{
+    bail,
+    eyre,
+};
+
+use crate::{
+    Error,
+    composer::assets::{
+        ASSETS_DIRECTORY,
+        project_directory,
+    },
+    error::ResultExt,
+    solver::{
+        config::SolverConfig,
+        extraction::Extraction,
+        library::{
+            RUN_RECORD_FILE_NAME,
+            ResultsLibraryWindow,
+            RunRecord,
+            library_directory,
+        },
+    },
+};
+
+/// Directory in the package that the results are exported into.
+pub const RESULTS_DIRECTORY: &str = "results";
+
+/// File name of the [`Extraction`] in the package.
+pub const EXTRACTION_FILE_NAME: &str = "extraction.toml";
+
+/// How deep results are searched for in the imported directory.
+const MAX_IMPORT_DEPTH: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheduler {
+    Slurm,
+    Pbs,
+}
+
+impl Scheduler {
+    pub const ALL: [Self; 2] = [Self::Slurm, Self::Pbs];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Slurm => "SLURM",
+            Self::Pbs => "PBS",
+        }
+    }
+
+    pub fn script_file_name(&self) -> &'static str {
+        match self {
+            Self::Slurm => "submit.slurm",
+            Self::Pbs => "submit.pbs",
+        }
+    }
+
+    /// Command that submits the script on the cluster.
+    pub fn submit_command(&self) -> &'static str {
+        match self {
+            Self::Slurm => "sbatch",
+            Self::Pbs => "qsub",
+        }
+    }
+}
+
+/// Resources that a batch run requests from the scheduler.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchJob {
+    pub name: String,
+
+    /// Index of the solver config that's run
+    pub solver: usize,
+
+    /// Wall time limit
+    pub hours: u32,
+
+    pub cpus: u32,
+    pub memory_gib: u32,
+    pub gpus: u32,
+
+    /// Partition or queue. The cluster's default is used if empty.
+    pub partition: String,
+}
+
+impl Default for BatchJob {
+    fn default() -> Self {
+        Self {
+            name: "cem-run".to_owned(),
+            solver: 0,
+            hours: 24,
+            cpus: 8,
+            memory_gib: 16,
+            gpus: 0,
+            partition: String::new(),
+        }
+    }
+}
+
+/// Generates the script that submits `job` to the `scheduler`.
+///
+/// The script runs in the package's directory, and expects the `cem-app`
+/// binary in the `PATH`, or in the `CEM_APP` environment variable.
+pub fn submission_script(scheduler: Scheduler, job: &BatchJob, project_file_name: &str) -> String {
+    let mut script = "#!/bin/bash\n".to_owned();
+
+    // writing into a string doesn't fail
+    match scheduler {
+        Scheduler::Slurm => {
+            writeln!(script, "#SBATCH --job-name={}", job.name).unwrap();
+            writeln!(script, "#SBATCH --time={:02}:00:00", job.hours).unwrap();
+            writeln!(script, "#SBATCH --cpus-per-task={}", job.cpus).unwrap();
+            writeln!(script, "#SBATCH --mem={}G", job.memory_gib).unwrap();
+            if !job.partition.is_empty() {
+                writeln!(script, "#SBATCH --partition={}", job.partition).unwrap();
+            }
+            if job.gpus > 0 {
+                writeln!(script, "#SBATCH --gres=gpu:{}", job.gpus).unwrap();
+            }
+            writeln!(script, "#SBATCH --output={}.%j.log", job.name).unwrap();
+            writeln!(script, "\ncd \"$SLURM_SUBMIT_DIR\"").unwrap();
+        }
+        Scheduler::Pbs => {
+            writeln!(script, "#PBS -N {}", job.name).unwrap();
+            writeln!(script, "#PBS -l walltime={:02}:00:00", job.hours).unwrap();
+            write!(
+                script,
+                "#PBS -l select=1:ncpus={}:mem={}GB",
+                job.cpus, job.memory_gib
+            )
+            .unwrap();
+            if job.gpus > 0 {
+                write!(script, ":ngpus={}", job.gpus).unwrap();
+            }
+            script.push('\n');
+            if !job.partition.is_empty() {
+                writeln!(script, "#PBS -q {}", job.partition).unwrap();
+            }
+            writeln!(script, "#PBS -j oe").unwrap();
+            writeln!(script, "\ncd \"$PBS_O_WORKDIR\"").unwrap();
+        }
+    }
+
+    // nobody watches the stream, so it's bound to any free port
+    writeln!(
+        script,
+        "\"${{CEM_APP:-cem-app}}\" headless \"{project_file_name}\" --solver {} --ignore-config --extraction {EXTRACTION_FILE_NAME} --stream 127.0.0.1:0",
+        job.solver
+    )
+    .unwrap();
+
+    script
+}
+
+/// Writes a batch package for the project at `project_path` into `directory`.
+///
+/// Returns the path of the project file in the package.
+pub fn package_batch_run(
+    project_path: &Path,
+    job: &BatchJob,
+    directory: &Path,
+) -> Result<PathBuf, Error> {
+    let project_file_name = project_path
+        .file_name()
+        .and_then(|file_name| file_name.to_str())
+        .ok_or_else(|| eyre!("Invalid project file name: {}", project_path.display()))?;
+
+    std::fs::create_dir_all(directory)?;
+
+    let packaged_project = directory.join(project_file_name);
+    std::fs::copy(project_path, &packaged_project)?;
+
+    if let Some(assets) =
+        project_directory(project_path).map(|directory| directory.join(ASSETS_DIRECTORY))
+        && assets.is_dir()
+    {
+        copy_directory(&assets, &directory.join(ASSETS_DIRECTORY))?;
+    }
+
+    let extraction = Extraction {
+        export: Some(RESULTS_DIRECTORY.into()),
+        ..Default::default()
+    };
+    std::fs::write(
+        directory.join(EXTRACTION_FILE_NAME),
+        toml::to_string_pretty(&extraction)?,
+    )?;
+
+    for scheduler in Scheduler::ALL {
+        std::fs::write(
+            directory.join(scheduler.script_file_name()),
+            submission_script(scheduler, job, project_file_name),
+        )?;
+    }
+
+    Ok(packaged_project)
+}
+
+/// Copies the results of finished batch runs found in `source` into the
+/// `library`.
+///
+/// Runs that are already in the library are skipped. Returns how many runs
+/// were imported.
+pub fn import_batch_results(source: &Path, library: &Path) -> Result<usize, Error> {
+    let mut run_directories = vec![];
+    find_runs(source, MAX_IMPORT_DEPTH, &mut run_directories)?;
+    if run_directories.is_empty() {
+        bail!("No results found in {}", source.display());
+    }
+
+    let mut num_imported = 0;
+    for run_directory in run_directories {
+        let record: RunRecord =
+            serde_json::from_slice(&std::fs::read(run_directory.join(RUN_RECORD_FILE_NAME))?)?;
+
+        let destination = library.join(record.directory_name());
+        if destination.exists() {
+            tracing::info!(directory = %run_directory.display(), "run is already in the library");
+            continue;
+        }
+
+        copy_directory(&run_directory, &destination)?;
+        num_imported += 1;
+    }
+
+    Ok(num_imported)
+}
+
+/// Collects the directories below `directory` that contain a [`RunRecord`].
+fn find_runs(directory: &Path, depth: usize, runs: &mut Vec<PathBuf>) -> Result<(), Error> {
+    if directory.join(RUN_RECORD_FILE_NAME).exists() {
+        runs.push(directory.to_owned());
+        return Ok(());
+    }
+
+    if depth > 0 {
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                find_runs(&path, depth - 1, runs)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn copy_directory(source: &Path, destination: &Path) -> Result<(), Error> {
+    std::fs::create_dir_all(destination)?;
+
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let path = entry.path();
+        let destination = destination.join(entry.file_name());
+        if path.is_dir() {
+            copy_directory(&path, &destination)?;
+        }
+        else {
+            std::fs::copy(&path, &destination)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+enum PickingDirectory {
+    #[default]
+    None,
+    Package,
+    Import,
+}
+
+/// Window to package the project for a cluster, and to import the results.
+#[derive(Debug)]
+pub struct BatchWindow {
+    pub open: bool,
+
+    job: BatchJob,
+    scheduler: Scheduler,
+
+    file_dialog: FileDialog,
+    picking: PickingDirectory,
+
+    /// Outcome of the last export or import
+    status: Option<String>,
+}
+
+impl Default for BatchWindow {
+    fn default() -> Self {
+        Self {
+            open: false,
+            job: BatchJob::default(),
+            scheduler: Scheduler::Slurm,
+            file_dialog: FileDialog::new().anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0]),
+            picking: PickingDirectory::None,
+            status: None,
+        }
+    }
+}
+
+impl BatchWindow {
+    /// Shows the window for the project at `project_path`. Imported results
+    /// are shown in the `results_library_window`.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        project_path: Option<&Path>,
+        modified: bool,
+        solver_configs: &[SolverConfig],
+        results_library_window: &mut ResultsLibraryWindow,
+    ) {
+        if !self.open {
+            return;
+        }
+
+        let mut open = self.open;
+
+        egui::Window::new("Cluster Batch Run")
+            .movable(true)
+            .default_width(400.0)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let Some(project_path) = project_path
+                else {
+                    ui.label("Save the project to run it on a cluster.");
+                    return;
+                };
+
+                self.job_ui(ui, solver_configs);
+
+                ui.separator();
+
+                if modified {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "The project has unsaved changes. The package contains the saved file.",
+                    );
+                }
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Export Package")
+                        .on_hover_text(
+                            "Copy the project and its assets into a directory, with submission \
+                             scripts.",
+                        )
+                        .clicked()
+                    {
+                        self.picking = PickingDirectory::Package;
+                        self.file_dialog.pick_directory();
+                    }
+
+                    if ui
+                        .button("Import Results")
+                        .on_hover_text(
+                            "Copy the results of finished runs into the project's library.",
+                        )
+                        .clicked()
+                    {
+                        self.picking = PickingDirectory::Import;
+                        self.file_dialog.pick_directory();
+                    }
+                });
+
+                ui.label(format!(
+                    "Submit with `{} {}` in the package's directory.",
+                    self.scheduler.submit_command(),
+                    self.scheduler.script_file_name()
+                ));
+
+                if let Some(status) = &self.status {
+                    ui.label(status);
+                }
+
+                self.file_dialog.update(ui.ctx());
+
+                if let Some(directory) = self.file_dialog.take_picked() {
+                    match std::mem::take(&mut self.picking) {
+                        PickingDirectory::None => {}
+                        PickingDirectory::Package => {
+                            if package_batch_run(project_path, &self.job, &directory)
+                                .ok_or_handle(ui.ctx())
+                                .is_some()
+                            {
+                                self.status =
+                                    Some(format!("Exported package to {}", directory.display()));
+                            }
+                        }
+                        PickingDirectory::Import => {
+                            if let Some(num_imported) =
+                                import_batch_results(&directory, &library_directory(project_path))
+                                    .ok_or_handle(ui.ctx())
+                            {
+                                self.status = Some(format!("Imported {num_imported} runs"));
+                                results_library_window.refresh(ui.ctx());
+                                results_library_window.open = true;
+                            }
+                        }
+                    }
+                }
+            });
+
+        self.open = open;
+    }
+
+    fn job_ui(&mut self, ui: &mut egui::Ui, solver_configs: &[SolverConfig]) {
+        egui::Grid::new("batch_job").num_columns(2).show(ui, |ui| {
+            ui.label("Scheduler");
+            ui.horizontal(|ui| {
+                for scheduler in Scheduler::ALL {
+                    ui.selectable_value(&mut self.scheduler, scheduler, scheduler.label());
+                }
+            });
+            ui.end_row();
+
+            ui.label("Job Name");
+            ui.text_edit_singleline(&mut self.job.name);
+            ui.end_row();
+
+            ui.label("Solver");
+            egui::ComboBox::from_id_salt("solver")
+                .selected_text(
+                    solver_configs
+                        .get(self.job.solver)
+                        .map_or("", |config| config.label.as_str()),
+                )
+                .show_ui(ui, |ui| {
+                    for (index, config) in solver_configs.iter().enumerate() {
+                        ui.selectable_value(&mut self.job.solver, index, &config.label);
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Wall Time");
+            ui.add(
+                egui::DragValue::new(&mut self.job.hours)
+                    .range(1..=u32::MAX)
+                    .suffix(" h"),
+            );
+            ui.end_row();
+
+            ui.label("CPUs");
+            ui.add(egui::DragValue::new(&mut self.job.cpus).range(1..=u32::MAX));
+            ui.end_row();
+
+            ui.label("Memory");
+            ui.add(
+                egui::DragValue::new(&mut self.job.memory_gib)
+                    .range(1..=u32::MAX)
+                    .suffix(" GiB"),
+            );
+            ui.end_row();
+
+            ui.label("GPUs");
+            ui.add(egui::DragValue::new(&mut self.job.gpus));
+            ui.end_row();
+
+            ui.label("Partition");
+            ui.text_edit_singleline(&mut self.job.partition)
+                .on_hover_text("Partition or queue. Leave empty for the cluster's default.");
+            ui.end_row();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::batch::{
+        BatchJob,
+        Scheduler,
+        submission_script,
+    };
+
+    #[test]
+    fn slurm_script_requests_resources() {
+        let job = BatchJob {
+            gpus: 1,
+            partition: "gpu".to_owned(),
+            ..Default::default()
+        };
+        let script = submission_script(Scheduler::Slurm, &job, "antenna.cem");
+
+        assert!(script.starts_with("#!/bin/bash\n"));
+        assert!(script.contains("#SBATCH --time=24:00:00\n"));
+        assert!(script.contains("#SBATCH --mem=16G\n"));
+        assert!(script.contains("#SBATCH --partition=gpu\n"));
+        assert!(script.contains("#SBATCH --gres=gpu:1\n"));
+        assert!(script.contains(
+            "\"${CEM_APP:-cem-app}\" headless \"antenna.cem\" --solver 0 --ignore-config \
+             --extraction extraction.toml"
+        ));
+    }
+
+    #[test]
+    fn pbs_script_selects_one_node() {
+        let script = submission_script(Scheduler::Pbs, &BatchJob::default(), "antenna.cem");
+
+        assert!(script.contains("#PBS -N cem-run\n"));
+        assert!(script.contains("#PBS -l select=1:ncpus=8:mem=16GB\n"));
+        assert!(!script.contains("#PBS -q"));
+        assert!(script.contains("cd \"$PBS_O_WORKDIR\""));
+    }
+}
@@ -0,0 +1,386 @@
+//! Automatic choice of the FDTD backend.
+//!
+//! Solver configs that don't set a backend (see
+//! [`SolverConfigCommon::parallelization`](crate::solver::config::SolverConfigCommon::parallelization))
+//! are run on the backend that is expected to be the fastest for their grid.
+//! The expectation comes from the [`BenchmarkDatabase`]: after every FDTD run
+//! the measured throughput (cell updates per second) is stored, and the
+//! backend that was fastest for grids of similar size is chosen. Until there
+//! are measurements the choice is made by the grid size alone. Backends that
+//! would exceed the memory limit or the GPU's buffer size limits are never
+//! chosen.
+//!
+//! The choice and the reason for it are logged and shown in the solver window.
+//! To override it, choose a backend in the solver config.
+
+use std::{
+    path::PathBuf,
+    time::Duration,
+};
+
+use cem_util::format_size;
+use color_eyre::eyre::bail;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    Error,
+    solver::config::Parallelization,
+};
+
+/// Grids with at least this many cells run on the GPU if there are no
+/// measurements yet. Below, the overhead of dispatching to the GPU dominates.
+pub const GPU_MIN_CELLS: usize = 500_000;
+
+/// Grids with at least this many cells run multi-threaded if there are no
+/// measurements yet.
+pub const MULTI_THREADED_MIN_CELLS: usize = 20_000;
+
+/// Runs that stepped for less time than this aren't recorded, because their
+/// throughput is dominated by noise.
+pub const MIN_MEASURED_TIME: Duration = Duration::from_secs(1);
+
+/// Measurements are used for grids that are at most this factor smaller or
+/// larger.
+const SIMILAR_SIZE_FACTOR: f64 = 4.0;
+
+/// Older measurements are dropped, so the database follows hardware and
+/// driver changes.
+const MAX_SAMPLES_PER_BACKEND: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FdtdBackendKind {
+    SingleThreaded,
+    MultiThreaded,
+    Wgpu,
+}
+
+impl FdtdBackendKind {
+    pub const ALL: [Self; 3] = [Self::SingleThreaded, Self::MultiThreaded, Self::Wgpu];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::SingleThreaded => "CPU (single-threaded)",
+            Self::MultiThreaded => "CPU (multi-threaded)",
+            Self::Wgpu => "GPU",
+        }
+    }
+
+    pub fn from_parallelization(parallelization: &Parallelization) -> Self {
+        match parallelization {
+            Parallelization::SingleThreaded => Self::SingleThreaded,
+            Parallelization::MultiThreaded { .. } => Self::MultiThreaded,
+            Parallelization::Wgpu => Self::Wgpu,
+        }
+    }
+
+    /// The parallelization that runs on this backend, with default settings.
+    pub fn parallelization(&self) -> Parallelization {
+        match self {
+            Self::SingleThreaded => Parallelization::SingleThreaded,
+            Self::MultiThreaded => Parallelization::MultiThreaded { num_threads: None },
+            Self::Wgpu => Parallelization::Wgpu,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkSample {
+    pub backend: FdtdBackendKind,
+    pub num_cells: usize,
+    pub cells_per_second: f64,
+}
+
+/// Throughput of past runs, stored in the app's state directory.
+#[derive(Clone, Debug, Default)]
+pub struct BenchmarkDatabase {
+    /// Where the database is saved to. If `None`, it's only kept in memory.
+    path: Option<PathBuf>,
+    samples: Vec<BenchmarkSample>,
+}
+
+impl BenchmarkDatabase {
+    /// Opens the database at `path`.
+    ///
+    /// If it doesn't exist or can't be read, an empty database is used, which
+    /// is then saved to `path`.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+
+        let samples = if path.exists() {
+            std::fs::read(&path)
+                .map_err(Error::from)
+                .and_then(|json| Ok(serde_json::from_slice(&json)?))
+                .unwrap_or_else(|error| {
+                    tracing::warn!(path = %path.display(), ?error, "can't read benchmark database");
+                    vec![]
+                })
+        }
+        else {
+            vec![]
+        };
+
+        Self {
+            path: Some(path),
+            samples,
+        }
+    }
+
+    pub fn samples(&self) -> &[BenchmarkSample] {
+        &self.samples
+    }
+
+    /// Adds a measurement and saves the database.
+    pub fn record(&mut self, sample: BenchmarkSample) {
+        tracing::debug!(?sample, "recording benchmark");
+
+        self.samples.push(sample);
+
+        let num_samples = self
+            .samples
+            .iter()
+            .filter(|other| other.backend == sample.backend)
+            .count();
+        if num_samples > MAX_SAMPLES_PER_BACKEND
+            && let Some(oldest) = self
+                .samples
+                .iter()
+                .position(|other| other.backend == sample.backend)
+        {
+            self.samples.remove(oldest);
+        }
+
+        if let Err(error) = self.save() {
+            tracing::warn!(?error, "can't save benchmark database");
+        }
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        if let Some(path) = &self.path {
+            std::fs::write(path, serde_json::to_vec_pretty(&self.samples)?)?;
+        }
+        Ok(())
+    }
+
+    /// Mean throughput of a backend for grids of similar size, if it was
+    /// measured.
+    pub fn cells_per_second(&self, backend: FdtdBackendKind, num_cells: usize) -> Option<f64> {
+        let (sum, count) = self
+            .samples
+            .iter()
+            .filter(|sample| {
+                let ratio = sample.num_cells as f64 / num_cells as f64;
+                sample.backend == backend
+                    && (1.0 / SIMILAR_SIZE_FACTOR..=SIMILAR_SIZE_FACTOR).contains(&ratio)
+            })
+            .fold((0.0, 0), |(sum, count), sample| {
+                (sum + sample.cells_per_second, count + 1)
+            });
+        (count > 0).then(|| sum / count as f64)
+    }
+}
+
+/// A backend that could run a grid.
+#[derive(Clone, Copy, Debug)]
+pub struct Candidate {
+    pub backend: FdtdBackendKind,
+    pub memory_required: Option<usize>,
+
+    /// Why the backend can't run the grid, if it can't.
+    pub unavailable: Option<&'static str>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BackendChoice {
+    pub backend: FdtdBackendKind,
+
+    /// Why the backend was chosen, for the user.
+    pub reason: String,
+}
+
+/// Chooses the backend for a grid with `num_cells` cells, see the [module
+/// documentation](self).
+pub fn choose_backend(
+    num_cells: usize,
+    memory_limit: Option<usize>,
+    candidates: &[Candidate],
+    benchmarks: &BenchmarkDatabase,
+) -> Result<BackendChoice, Error> {
+    let mut excluded = vec![];
+    let usable = candidates
+        .iter()
+        .filter(|candidate| {
+            if let Some(reason) = candidate.unavailable {
+                excluded.push(format!("{}: {reason}", candidate.backend.label()));
+                false
+            }
+            else if let (Some(memory_required), Some(memory_limit)) =
+                (candidate.memory_required, memory_limit)
+                && memory_required > memory_limit
+            {
+                excluded.push(format!(
+                    "{}: needs {} (limit {})",
+                    candidate.backend.label(),
+                    format_size(memory_required),
+                    format_size(memory_limit)
+                ));
+                false
+            }
+            else {
+                true
+            }
+        })
+        .map(|candidate| candidate.backend)
+        .collect::<Vec<_>>();
+
+    if usable.is_empty() {
+        bail!(
+            "No backend can run a grid with {num_cells} cells: {}",
+            excluded.join(", ")
+        );
+    }
+
+    let fastest_measured = usable
+        .iter()
+        .filter_map(|backend| Some((*backend, benchmarks.cells_per_second(*backend, num_cells)?)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    let (backend, mut reason) = if let Some((backend, cells_per_second)) = fastest_measured {
+        (
+            backend,
+            format!(
+                "{num_cells} cells, fastest for similar grids with {cells_per_second:.3e} cells/s"
+            ),
+        )
+    }
+    else {
+        let preferred = if num_cells >= GPU_MIN_CELLS {
+            [
+                FdtdBackendKind::Wgpu,
+                FdtdBackendKind::MultiThreaded,
+                FdtdBackendKind::SingleThreaded,
+            ]
+        }
+        else if num_cells >= MULTI_THREADED_MIN_CELLS {
+            [
+                FdtdBackendKind::MultiThreaded,
+                FdtdBackendKind::SingleThreaded,
+                FdtdBackendKind::Wgpu,
+            ]
+        }
+        else {
+            [
+                FdtdBackendKind::SingleThreaded,
+                FdtdBackendKind::MultiThreaded,
+                FdtdBackendKind::Wgpu,
+            ]
+        };
+        let backend = preferred
+            .into_iter()
+            .find(|backend| usable.contains(backend))
+            .unwrap_or(usable[0]);
+        (
+            backend,
+            format!("{num_cells} cells, no benchmarks for similar grids yet"),
+        )
+    };
+
+    if !excluded.is_empty() {
+        reason = format!("{reason}; not usable: {}", excluded.join(", "));
+    }
+
+    Ok(BackendChoice { backend, reason })
+}
+
+/// The FDTD backend a solver runs on.
+#[derive(Clone, Debug)]
+pub struct ActiveBackend {
+    pub backend: FdtdBackendKind,
+    pub num_cells: usize,
+
+    /// If the backend was chosen automatically, why.
+    pub choice_reason: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::benchmark::{
+        BenchmarkDatabase,
+        BenchmarkSample,
+        Candidate,
+        FdtdBackendKind,
+        choose_backend,
+    };
+
+    fn candidates() -> Vec<Candidate> {
+        FdtdBackendKind::ALL
+            .into_iter()
+            .map(|backend| {
+                Candidate {
+                    backend,
+                    memory_required: Some(1000),
+                    unavailable: None,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chooses_by_grid_size_without_benchmarks() {
+        let benchmarks = BenchmarkDatabase::default();
+
+        let choice = choose_backend(1000, None, &candidates(), &benchmarks).unwrap();
+        assert_eq!(choice.backend, FdtdBackendKind::SingleThreaded);
+
+        let choice = choose_backend(10_000_000, None, &candidates(), &benchmarks).unwrap();
+        assert_eq!(choice.backend, FdtdBackendKind::Wgpu);
+    }
+
+    #[test]
+    fn chooses_fastest_measured_backend() {
+        let mut benchmarks = BenchmarkDatabase::default();
+        benchmarks.record(BenchmarkSample {
+            backend: FdtdBackendKind::Wgpu,
+            num_cells: 2000,
+            cells_per_second: 1e6,
+        });
+        benchmarks.record(BenchmarkSample {
+            backend: FdtdBackendKind::MultiThreaded,
+            num_cells: 1000,
+            cells_per_second: 1e7,
+        });
+        // too different in size to be considered
+        benchmarks.record(BenchmarkSample {
+            backend: FdtdBackendKind::SingleThreaded,
+            num_cells: 1_000_000,
+            cells_per_second: 1e9,
+        });
+
+        let choice = choose_backend(1000, None, &candidates(), &benchmarks).unwrap();
+        assert_eq!(choice.backend, FdtdBackendKind::MultiThreaded);
+    }
+
+    #[test]
+    fn excludes_backends_over_memory_limit() {
+        let mut candidates = candidates();
+        candidates[2].memory_required = Some(2000);
+        candidates[1].unavailable = Some("compiled without multi-threading");
+
+        let choice = choose_backend(
+            10_000_000,
+            Some(1500),
+            &candidates,
+            &BenchmarkDatabase::default(),
+        )
+        .unwrap();
+        assert_eq!(choice.backend, FdtdBackendKind::SingleThreaded);
+        assert!(choice.reason.contains("not usable"), "{}", choice.reason);
+
+        assert!(
+            choose_backend(1000, Some(500), &candidates, &BenchmarkDatabase::default()).is_err()
+        );
+    }
+}
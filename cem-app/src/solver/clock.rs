@@ -0,0 +1,246 @@
+//! Simulation clock shared by the solver and everything showing its results.
+//!
+//! The solver writes the time it has reached into the clock. Observers, probe
+//! plots and the waveform cursor show the clock's displayed time instead. The
+//! displayed time usually follows the solver, but the visualization can be
+//! paused while the solver continues, or advance at a real-time factor. With a
+//! real-time factor the solver waits for the displayed time, so that fields
+//! and probes are shown at the time they were computed.
+
+use std::{
+    sync::Arc,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use cem_solver::material::UnitSystem;
+use parking_lot::Mutex;
+
+/// How long the solver sleeps when it's ahead of the displayed time.
+pub const WAIT_INTERVAL: Duration = Duration::from_millis(1);
+
+/// How far the displayed time may run ahead of the solver, in wall-clock time.
+///
+/// Without this limit a solver that was paused, or is slower than the
+/// real-time factor, would race through the lead once it catches up.
+const MAX_LEAD: Duration = Duration::from_millis(100);
+
+/// Handle to the clock of a running solver.
+#[derive(Clone, Debug, Default)]
+pub struct SimulationClock {
+    state: Arc<Mutex<ClockState>>,
+}
+
+impl SimulationClock {
+    /// Called by the solver after every step.
+    pub fn set_solver_time(&self, tick: usize, time: f64) {
+        let mut state = self.state.lock();
+        state.solver_tick = tick;
+        state.solver_time = time;
+    }
+
+    pub fn solver_tick(&self) -> usize {
+        self.state.lock().solver_tick
+    }
+
+    pub fn solver_time(&self) -> f64 {
+        self.state.lock().solver_time
+    }
+
+    /// Simulated time that visualizations show.
+    pub fn displayed_time(&self) -> f64 {
+        self.state.lock().displayed_time(Instant::now())
+    }
+
+    /// Whether a solver at `time` should wait for the displayed time to catch
+    /// up, before it does its next step.
+    pub fn is_ahead(&self, time: f64) -> bool {
+        let mut state = self.state.lock();
+        state.paused_at.is_none()
+            && state.real_time_factor.is_some()
+            && time > state.displayed_time(Instant::now())
+    }
+
+    pub fn is_visualization_paused(&self) -> bool {
+        self.state.lock().paused_at.is_some()
+    }
+
+    /// Freezes the displayed time, while the solver continues. When resumed,
+    /// the displayed time jumps to the solver's time.
+    pub fn set_visualization_paused(&self, paused: bool) {
+        let mut state = self.state.lock();
+        if paused {
+            if state.paused_at.is_none() {
+                state.paused_at = Some(state.displayed_time(Instant::now()));
+            }
+        }
+        else {
+            state.paused_at = None;
+            state.anchor = None;
+        }
+    }
+
+    /// Pauses the visualization at an earlier `time`, e.g. when replaying an
+    /// observer's history.
+    pub fn pause_visualization_at(&self, time: f64) {
+        self.state.lock().paused_at = Some(time);
+    }
+
+    /// Simulated time per second of wall-clock time, if the displayed time is
+    /// limited to it.
+    pub fn real_time_factor(&self) -> Option<f64> {
+        self.state.lock().real_time_factor
+    }
+
+    pub fn set_real_time_factor(&self, real_time_factor: Option<f64>) {
+        let mut state = self.state.lock();
+        let now = Instant::now();
+        let displayed_time = state.displayed_time(now);
+        state.real_time_factor = real_time_factor;
+        state.anchor = Some((now, displayed_time));
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct ClockState {
+    solver_tick: usize,
+    solver_time: f64,
+
+    /// Displayed time while the visualization is paused.
+    paused_at: Option<f64>,
+
+    real_time_factor: Option<f64>,
+
+    /// Wall-clock time and displayed time that the real-time factor counts
+    /// from.
+    anchor: Option<(Instant, f64)>,
+}
+
+impl ClockState {
+    fn displayed_time(&mut self, now: Instant) -> f64 {
+        if let Some(time) = self.paused_at {
+            return time;
+        }
+        let Some(factor) = self.real_time_factor
+        else {
+            return self.solver_time;
+        };
+
+        let (start, start_time) = *self.anchor.get_or_insert((now, self.solver_time));
+        let time = start_time + factor * now.saturating_duration_since(start).as_secs_f64();
+
+        let max_lead = factor * MAX_LEAD.as_secs_f64();
+        if time > self.solver_time + max_lead {
+            self.anchor = Some((now, self.solver_time + max_lead));
+        }
+
+        time.min(self.solver_time)
+    }
+}
+
+/// Real-time factor that's suggested when it's enabled.
+fn default_real_time_factor(units: UnitSystem) -> f64 {
+    match units {
+        UnitSystem::Si => 1e-9,
+        UnitSystem::Normalized => 1.0,
+    }
+}
+
+/// Controls for the displayed time of a running solver.
+pub fn clock_ui(ui: &mut egui::Ui, clock: &SimulationClock) {
+    let units = UnitSystem::display_units(ui.ctx());
+
+    let displayed_time = clock.displayed_time();
+    let solver_time = clock.solver_time();
+    if displayed_time < solver_time {
+        ui.label(format!(
+            "Displayed Time: {displayed_time:.3} ({:.3} behind)",
+            solver_time - displayed_time
+        ));
+    }
+    else {
+        ui.label(format!("Displayed Time: {displayed_time:.3}"));
+    }
+
+    let mut paused = clock.is_visualization_paused();
+    if ui
+        .checkbox(&mut paused, "Pause Visualization")
+        .on_hover_text(
+            "Keep showing fields and probes at the current time, while the solver continues",
+        )
+        .changed()
+    {
+        clock.set_visualization_paused(paused);
+    }
+
+    ui.horizontal(|ui| {
+        let real_time_factor = clock.real_time_factor();
+        let mut enabled = real_time_factor.is_some();
+        let mut factor = real_time_factor.unwrap_or_else(|| default_real_time_factor(units));
+
+        let toggled = ui
+            .checkbox(&mut enabled, "Real-Time Factor")
+            .on_hover_text("Simulated time per second. The solver waits, so it doesn't run ahead.")
+            .changed();
+        let dragged = ui
+            .add_enabled(
+                enabled,
+                egui::DragValue::new(&mut factor)
+                    .speed(0.01 * factor)
+                    .range(f64::MIN_POSITIVE..=f64::INFINITY)
+                    .suffix(format!(" {}/s", units.time_unit())),
+            )
+            .changed();
+
+        if toggled || dragged {
+            clock.set_real_time_factor(enabled.then_some(factor));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{
+        Duration,
+        Instant,
+    };
+
+    use crate::solver::clock::ClockState;
+
+    #[test]
+    fn displayed_time_follows_real_time_factor() {
+        let start = Instant::now();
+        let mut state = ClockState {
+            solver_time: 10.0,
+            real_time_factor: Some(2.0),
+            anchor: Some((start, 1.0)),
+            ..Default::default()
+        };
+
+        assert_eq!(state.displayed_time(start), 1.0);
+        assert_eq!(state.displayed_time(start + Duration::from_secs(2)), 5.0);
+
+        // the displayed time never runs ahead of the solver
+        assert_eq!(state.displayed_time(start + Duration::from_secs(10)), 10.0);
+
+        // and only a short lead is kept, for when the solver continues
+        state.solver_time = 20.0;
+        let time = state.displayed_time(start + Duration::from_secs(10));
+        assert!(time < 10.5, "{time}");
+    }
+
+    #[test]
+    fn paused_visualization_keeps_its_time() {
+        let mut state = ClockState {
+            solver_time: 3.0,
+            paused_at: Some(2.0),
+            ..Default::default()
+        };
+        assert_eq!(state.displayed_time(Instant::now()), 2.0);
+
+        state.paused_at = None;
+        assert_eq!(state.displayed_time(Instant::now()), 3.0);
+    }
+}
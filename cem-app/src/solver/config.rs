@@ -1,4 +1,7 @@
-use std::time::Duration;
+use std::{
+    collections::BTreeSet,
+    time::Duration,
+};
 
 use bevy_ecs::system::In;
 use cem_scene::{
@@ -6,11 +9,16 @@ use cem_scene::{
     spatial::queries::WorldAabb,
 };
 use cem_solver::{
-    fdtd::Resolution,
+    fdtd::{
+        Resolution,
+        SpatialOrder,
+        cpu::SparseConfig,
+    },
     material::{
         Material,
-        PhysicalConstants,
+        UnitConversion,
     },
+    registry::SolverConfigCustom,
 };
 use nalgebra::{
     Isometry3,
@@ -23,6 +31,14 @@ use serde::{
     Serialize,
 };
 
+use crate::solver::{
+    events::ScheduledEvent,
+    ground_plane::fit_volume_to_ground_planes,
+    parameters::ParameterValues,
+    run_log::RunLogConfig,
+    watchdog::WatchdogConfig,
+};
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SolverConfig {
     pub label: String,
@@ -36,25 +52,63 @@ impl SolverConfig {
     pub fn solver_type(&self) -> SolverType {
         self.specifics.solver_type()
     }
+
+    /// Converts the config into another unit system.
+    ///
+    /// See [`switch_unit_system`][crate::solver::units::switch_unit_system].
+    pub fn convert_units(&mut self, conversion: &UnitConversion) {
+        self.common.default_material = self.common.default_material.convert_units(conversion);
+
+        match &mut self.specifics {
+            SolverConfigSpecifics::Fdtd(fdtd_config) => {
+                fdtd_config.resolution.temporal *= conversion.time;
+                if let StopCondition::SimulatedTimeLimit { limit } = &mut fdtd_config.stop_condition
+                {
+                    *limit *= conversion.time as f32;
+                }
+            }
+            SolverConfigSpecifics::Feec(_) => {}
+            // custom backends read the unit system from the scene when they're run
+            SolverConfigSpecifics::Custom(_) => {}
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SolverConfigCommon {
     pub volume: Volume,
 
-    pub physical_constants: PhysicalConstants,
-
     pub default_material: Material,
 
+    /// Backend to run on. If `None`, it's chosen when the solver is run, see
+    /// [`crate::solver::benchmark`].
     pub parallelization: Option<Parallelization>,
 
     pub memory_limit: Option<usize>,
+
+    /// Scene parameters with other values for this run, see
+    /// [`SceneParameters`](crate::solver::parameters::SceneParameters).
+    #[serde(default)]
+    pub parameter_overrides: ParameterValues,
+
+    /// Numbers of the ports that are neither excited nor recorded in this run,
+    /// see [`Port`](crate::solver::port::Port).
+    #[serde(default)]
+    pub disabled_ports: BTreeSet<usize>,
+
+    /// Actions the solver runs at certain simulation times, see
+    /// [`crate::solver::events`].
+    #[serde(default)]
+    pub events: Vec<ScheduledEvent>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum SolverConfigSpecifics {
     Fdtd(SolverConfigFdtd),
     Feec(SolverConfigFeec),
+
+    /// A backend registered at runtime, see [`cem_solver::registry`].
+    Custom(SolverConfigCustom),
 }
 
 impl SolverConfigSpecifics {
@@ -62,6 +116,7 @@ impl SolverConfigSpecifics {
         match self {
             Self::Fdtd(_) => SolverType::Fdtd,
             Self::Feec(_) => SolverType::Feec,
+            Self::Custom(_) => SolverType::Custom,
         }
     }
 }
@@ -70,6 +125,33 @@ impl SolverConfigSpecifics {
 pub struct SolverConfigFdtd {
     pub resolution: Resolution,
     pub stop_condition: StopCondition,
+
+    #[serde(default)]
+    pub spatial_order: SpatialOrder,
+
+    #[serde(default)]
+    pub mesh: MeshGrading,
+
+    #[serde(default)]
+    pub watchdog: WatchdogConfig,
+
+    /// Skip quiet regions of the lattice. Only the CPU backends support this.
+    #[serde(default)]
+    pub sparse: Option<SparseConfig>,
+
+    /// Periodically log metrics and observer images during the run.
+    #[serde(default)]
+    pub run_log: RunLogConfig,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum MeshGrading {
+    /// Use the configured spatial resolution everywhere.
+    #[default]
+    Uniform,
+
+    /// Refine the mesh inside of refinement regions.
+    Graded { max_grading_ratio: f64 },
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -82,6 +164,7 @@ pub enum StopCondition {
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Parallelization {
+    SingleThreaded,
     MultiThreaded { num_threads: Option<usize> },
     Wgpu,
 }
@@ -93,6 +176,7 @@ pub struct SolverConfigFeec {}
 pub enum SolverType {
     Fdtd,
     Feec,
+    Custom,
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -117,7 +201,7 @@ impl Volume {
                 )
             }
             Volume::SceneAabb(scene_aabb_volume) => {
-                scene
+                let aabb = scene
                     .world
                     .run_system_cached_with(
                         |In(scene_aabb_volume): In<SceneAabbVolume>, mut world_aabb: WorldAabb| {
@@ -136,7 +220,9 @@ impl Volume {
                         },
                         *scene_aabb_volume,
                     )
-                    .unwrap()
+                    .unwrap();
+
+                fit_volume_to_ground_planes(&mut scene.world, &aabb, &scene_aabb_volume.rotation)
             }
         }
     }
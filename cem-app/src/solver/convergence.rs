@@ -0,0 +1,640 @@
+//! Convergence studies.
+//!
+//! A convergence study runs a solver config at a few increasingly fine
+//! resolutions, one after another like the steps of a
+//! [sequence run](crate::solver::sequence), and evaluates a metric of a port's
+//! reflection for each. Richardson extrapolation over the cell size then
+//! estimates the metric for an infinitely fine mesh, and the order the results
+//! converge with.
+//!
+//! Every level divides the spatial and temporal resolution by the same ratio,
+//! so the Courant number stays the same. Step limits are scaled up, so that
+//! every level simulates the same time.
+
+use std::collections::VecDeque;
+
+use crate::{
+    Error,
+    clipboard::copy_image_or_csv_context_menu,
+    composer::Composers,
+    error::ResultExt,
+    solver::{
+        config::{
+            SolverConfig,
+            SolverConfigSpecifics,
+            StopCondition,
+        },
+        network::{
+            Reflection,
+            format_frequency,
+            to_db,
+        },
+        probe::ProbeOutputs,
+        runner::SolverRunner,
+        sequence::{
+            StepStatus,
+            runs_forever,
+            start_step,
+            step_status,
+        },
+        tuning::frequency_drag_value,
+    },
+};
+
+/// Number of frequencies the resonance is searched at across the band.
+const NUM_FREQUENCIES: usize = 201;
+
+/// Order of convergence that's assumed, if it can't be observed. The Yee
+/// scheme is of second order.
+const ASSUMED_ORDER: f64 = 2.0;
+
+/// What a convergence study compares between the levels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConvergenceMetric {
+    /// Frequency at which |S11| is minimal within `band`, in Hz.
+    Resonance { band: (f64, f64) },
+
+    /// |S11| at `frequency` (in Hz), in dB.
+    Reflection { frequency: f64 },
+}
+
+impl ConvergenceMetric {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Resonance { .. } => "Resonance",
+            Self::Reflection { .. } => "S11 at f0",
+        }
+    }
+
+    /// Evaluates the metric for the port with the given number.
+    pub fn evaluate(&self, outputs: &ProbeOutputs, port: usize) -> Option<f64> {
+        let (name, output) = outputs
+            .ports
+            .iter()
+            .find(|(_, output)| output.number() == port)?;
+
+        match self {
+            Self::Resonance { band: (start, end) } => {
+                let frequencies = (0..NUM_FREQUENCIES)
+                    .map(|i| start + (end - start) * i as f64 / (NUM_FREQUENCIES - 1) as f64)
+                    .collect();
+                resonance(&output.reflection(name.clone(), frequencies)?)
+            }
+            Self::Reflection { frequency } => {
+                let reflection = output.reflection(name.clone(), vec![*frequency])?;
+                Some(to_db(reflection.values.first()?.norm()))
+            }
+        }
+    }
+
+    fn format(&self, value: f64) -> String {
+        match self {
+            Self::Resonance { .. } => format_frequency(value),
+            Self::Reflection { .. } => format!("{value:.2} dB"),
+        }
+    }
+
+    /// Converts a value for plotting.
+    fn plot_value(&self, value: f64) -> f64 {
+        match self {
+            Self::Resonance { .. } => value * 1e-9,
+            Self::Reflection { .. } => value,
+        }
+    }
+
+    fn plot_label(&self) -> &'static str {
+        match self {
+            Self::Resonance { .. } => "Resonance [GHz]",
+            Self::Reflection { .. } => "|S11| [dB]",
+        }
+    }
+}
+
+/// Frequency at which |S11| is minimal, refined with a parabola through the
+/// lowest sample and its neighbours.
+///
+/// The frequencies have to be spaced uniformly.
+fn resonance(reflection: &Reflection) -> Option<f64> {
+    let magnitudes = reflection
+        .values
+        .iter()
+        .map(|value| value.norm())
+        .collect::<Vec<_>>();
+    let (index, _) = magnitudes
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+    let frequency = reflection.frequencies[index];
+
+    let (Some(previous), Some(next)) = (
+        index.checked_sub(1).map(|i| magnitudes[i]),
+        magnitudes.get(index + 1),
+    )
+    else {
+        return Some(frequency);
+    };
+    let curvature = previous - 2.0 * magnitudes[index] + next;
+    if curvature <= 0.0 {
+        return Some(frequency);
+    }
+
+    let offset = 0.5 * (previous - next) / curvature;
+    let spacing = reflection.frequencies[index + 1] - frequency;
+    Some(frequency + offset * spacing)
+}
+
+/// Estimate of the metric for an infinitely fine mesh.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Extrapolation {
+    pub value: f64,
+
+    /// Order of convergence observed over the 3 finest levels. This is `None`
+    /// with less than 3 levels, or if the results don't converge
+    /// monotonically, and [`ASSUMED_ORDER`] is used instead.
+    pub observed_order: Option<f64>,
+}
+
+/// Richardson extrapolation of `values`, ordered from the coarsest to the
+/// finest level, whose cell sizes shrink by `ratio` from level to level.
+///
+/// Returns `None` with less than 2 values.
+pub fn richardson(values: &[f64], ratio: f64) -> Option<Extrapolation> {
+    let observed_order = match values {
+        [.., coarse, medium, fine] => {
+            let order = ((coarse - medium) / (medium - fine)).ln() / ratio.ln();
+            (order.is_finite() && order > 0.0).then_some(order)
+        }
+        _ => None,
+    };
+
+    let [.., coarse, fine] = values
+    else {
+        return None;
+    };
+    let order = observed_order.unwrap_or(ASSUMED_ORDER);
+    Some(Extrapolation {
+        value: fine + (fine - coarse) / (ratio.powf(order) - 1.0),
+        observed_order,
+    })
+}
+
+/// `solver_config` with its cells divided by `refinement`.
+fn refined_config(solver_config: &SolverConfig, refinement: f64) -> SolverConfig {
+    let mut solver_config = solver_config.clone();
+
+    if let SolverConfigSpecifics::Fdtd(fdtd_config) = &mut solver_config.specifics {
+        fdtd_config.resolution.spatial /= refinement;
+        fdtd_config.resolution.temporal /= refinement;
+
+        if let StopCondition::StepLimit { limit } = &mut fdtd_config.stop_condition {
+            *limit = (*limit as f64 * refinement).ceil() as usize;
+        }
+    }
+
+    solver_config
+}
+
+/// Results of one level of a convergence study.
+#[derive(Clone, Debug)]
+pub struct ConvergenceLevel {
+    /// Largest cell size of the level, in m
+    pub cell_size: f64,
+
+    pub value: Option<f64>,
+}
+
+#[derive(Debug)]
+pub struct ConvergenceStudy {
+    pub open: bool,
+
+    /// Index of the solver config the coarsest level is run with
+    solver_config: usize,
+
+    num_levels: usize,
+
+    /// Ratio of the cell sizes of two consecutive levels
+    ratio: f64,
+
+    metric: ConvergenceMetric,
+
+    /// Number of the port the metric is evaluated for
+    port: usize,
+
+    /// Levels that are still to be run, with their refinement and cell size
+    pending: VecDeque<(f64, f64)>,
+
+    /// Cell size of the level that is running
+    running: Option<f64>,
+
+    levels: Vec<ConvergenceLevel>,
+
+    /// Metric and ratio of the finished levels
+    results_for: Option<(ConvergenceMetric, f64)>,
+}
+
+impl Default for ConvergenceStudy {
+    fn default() -> Self {
+        Self {
+            open: false,
+            solver_config: 0,
+            num_levels: 3,
+            ratio: 1.5,
+            metric: ConvergenceMetric::Resonance { band: (2e9, 3e9) },
+            port: 1,
+            pending: VecDeque::new(),
+            running: None,
+            levels: vec![],
+            results_for: None,
+        }
+    }
+}
+
+impl ConvergenceStudy {
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        composers: &mut Composers,
+        solver_runner: &mut SolverRunner,
+    ) {
+        // the study continues while the window is closed
+        self.advance(composers, solver_runner).ok_or_handle(ctx);
+
+        let mut open = self.open;
+
+        egui::Window::new("Convergence Study")
+            .movable(true)
+            .default_size([400.0, 450.0])
+            .open(&mut open)
+            .show(ctx, |ui| {
+                self.setup_ui(ui, composers, solver_runner);
+
+                ui.separator();
+
+                if self.levels.is_empty() {
+                    ui.weak("No results yet");
+                }
+                else {
+                    self.results_ui(ui);
+                }
+            });
+
+        self.open = open;
+    }
+
+    fn setup_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        composers: &mut Composers,
+        solver_runner: &mut SolverRunner,
+    ) {
+        let Some((_, solver_configs)) = composers.active_scene_mut()
+        else {
+            ui.weak("No scene open");
+            return;
+        };
+
+        if solver_configs.is_empty() {
+            ui.weak("Configure a solver first");
+            return;
+        }
+        self.solver_config = self.solver_config.min(solver_configs.len() - 1);
+
+        ui.add_enabled_ui(!self.is_running(), |ui| {
+            egui::Grid::new("convergence_setup")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Solver");
+                    egui::ComboBox::from_id_salt("solver_config")
+                        .selected_text(&solver_configs[self.solver_config].label)
+                        .show_ui(ui, |ui| {
+                            for (i, solver_config) in solver_configs.iter().enumerate() {
+                                ui.selectable_value(
+                                    &mut self.solver_config,
+                                    i,
+                                    &solver_config.label,
+                                );
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Levels");
+                    ui.add(egui::DragValue::new(&mut self.num_levels).range(2..=4));
+                    ui.end_row();
+
+                    ui.label("Refinement");
+                    ui.add(
+                        egui::DragValue::new(&mut self.ratio)
+                            .range(1.1..=4.0)
+                            .speed(0.01)
+                            .prefix("× "),
+                    )
+                    .on_hover_text("Ratio of the cell sizes of two consecutive levels");
+                    ui.end_row();
+
+                    ui.label("Port");
+                    ui.add(egui::DragValue::new(&mut self.port).range(1..=usize::MAX));
+                    ui.end_row();
+
+                    ui.label("Metric");
+                    egui::ComboBox::from_id_salt("metric")
+                        .selected_text(self.metric.label())
+                        .show_ui(ui, |ui| {
+                            let frequency = match self.metric {
+                                ConvergenceMetric::Resonance { band } => 0.5 * (band.0 + band.1),
+                                ConvergenceMetric::Reflection { frequency } => frequency,
+                            };
+                            for metric in [
+                                ConvergenceMetric::Resonance {
+                                    band: (0.8 * frequency, 1.2 * frequency),
+                                },
+                                ConvergenceMetric::Reflection { frequency },
+                            ] {
+                                let selected = std::mem::discriminant(&metric)
+                                    == std::mem::discriminant(&self.metric);
+                                if ui.selectable_label(selected, metric.label()).clicked()
+                                    && !selected
+                                {
+                                    self.metric = metric;
+                                }
+                            }
+                        });
+                    ui.end_row();
+
+                    match &mut self.metric {
+                        ConvergenceMetric::Resonance { band } => {
+                            ui.label("Band");
+                            ui.horizontal(|ui| {
+                                frequency_drag_value(ui, &mut band.0);
+                                ui.label("to");
+                                frequency_drag_value(ui, &mut band.1);
+                            });
+                        }
+                        ConvergenceMetric::Reflection { frequency } => {
+                            ui.label("f0");
+                            frequency_drag_value(ui, frequency);
+                        }
+                    }
+                    ui.end_row();
+                });
+        });
+
+        let solver_config = &solver_configs[self.solver_config];
+        let is_fdtd = matches!(solver_config.specifics, SolverConfigSpecifics::Fdtd(_));
+        if !is_fdtd {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "Only FDTD solvers have a resolution to refine.",
+            );
+        }
+
+        // every level has to finish on its own
+        let runs_forever = runs_forever(solver_config);
+        if runs_forever {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "The solver has no stop condition, so the levels would never finish.",
+            );
+        }
+
+        let mut start = false;
+        ui.horizontal(|ui| {
+            if let Some(cell_size) = self.running {
+                let level = self.levels.len() + 1;
+                let num_levels = level + self.pending.len();
+                ui.spinner();
+                ui.label(format!(
+                    "Level {level} of {num_levels} ({:.3} mm cells)",
+                    cell_size * 1e3
+                ));
+                if ui.button("Cancel").clicked() {
+                    self.cancel(solver_runner);
+                }
+            }
+            else if ui
+                .add_enabled(is_fdtd && !runs_forever, egui::Button::new("Start"))
+                .on_hover_text("Run the solver at increasingly fine resolutions")
+                .clicked()
+            {
+                start = true;
+            }
+        });
+
+        if start {
+            let cell_size = match &solver_config.specifics {
+                SolverConfigSpecifics::Fdtd(fdtd_config) => fdtd_config.resolution.spatial.max(),
+                _ => 0.0,
+            };
+            self.levels.clear();
+            self.results_for = Some((self.metric, self.ratio));
+            self.pending = (0..self.num_levels)
+                .map(|level| {
+                    let refinement = self.ratio.powi(level as i32);
+                    (refinement, cell_size / refinement)
+                })
+                .collect();
+            self.start_next(composers, solver_runner)
+                .ok_or_handle(ui.ctx());
+        }
+    }
+
+    /// Evaluates the running level once it finished, and starts the next one.
+    fn advance(
+        &mut self,
+        composers: &mut Composers,
+        solver_runner: &mut SolverRunner,
+    ) -> Result<(), Error> {
+        let Some(cell_size) = self.running
+        else {
+            return Ok(());
+        };
+
+        let outputs = match step_status(solver_runner) {
+            StepStatus::Running => return Ok(()),
+            StepStatus::Finished(outputs) => outputs,
+            StepStatus::Closed => {
+                self.pending.clear();
+                self.running = None;
+                return Ok(());
+            }
+        };
+
+        let value = self
+            .results_for
+            .and_then(|(metric, _)| metric.evaluate(&outputs, self.port));
+        self.levels.push(ConvergenceLevel { cell_size, value });
+        self.running = None;
+
+        self.start_next(composers, solver_runner)
+    }
+
+    fn start_next(
+        &mut self,
+        composers: &mut Composers,
+        solver_runner: &mut SolverRunner,
+    ) -> Result<(), Error> {
+        let Some((refinement, cell_size)) = self.pending.pop_front()
+        else {
+            return Ok(());
+        };
+
+        let Some((scene, solver_configs)) = composers.active_scene_mut()
+        else {
+            self.pending.clear();
+            return Ok(());
+        };
+        let Some(solver_config) = solver_configs.get(self.solver_config)
+        else {
+            self.pending.clear();
+            return Ok(());
+        };
+
+        let solver_config = refined_config(solver_config, refinement);
+
+        tracing::debug!(refinement, cell_size, "starting convergence level");
+
+        if let Err(error) = start_step(solver_runner, scene, &solver_config) {
+            self.pending.clear();
+            return Err(error);
+        }
+        self.running = Some(cell_size);
+
+        Ok(())
+    }
+
+    fn cancel(&mut self, solver_runner: &mut SolverRunner) {
+        self.pending.clear();
+        if self.running.take().is_some() {
+            solver_runner.stop();
+        }
+    }
+
+    fn results_ui(&self, ui: &mut egui::Ui) {
+        let Some((metric, ratio)) = self.results_for
+        else {
+            return;
+        };
+
+        egui::Grid::new("convergence_levels")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Level");
+                ui.strong("Cell Size");
+                ui.strong(metric.label());
+                ui.end_row();
+
+                for (i, level) in self.levels.iter().enumerate() {
+                    ui.label((i + 1).to_string());
+                    ui.label(format!("{:.3} mm", level.cell_size * 1e3));
+                    match level.value {
+                        Some(value) => ui.label(metric.format(value)),
+                        None => ui.weak(format!("No port {}", self.port)),
+                    };
+                    ui.end_row();
+                }
+            });
+
+        // a level without a value would break the constant ratio
+        let values = self
+            .levels
+            .iter()
+            .map(|level| level.value)
+            .collect::<Option<Vec<_>>>();
+        let extrapolation = values
+            .as_deref()
+            .and_then(|values| richardson(values, ratio));
+
+        if let Some(extrapolation) = &extrapolation {
+            ui.label(format!(
+                "Extrapolated: {}",
+                metric.format(extrapolation.value)
+            ));
+            match extrapolation.observed_order {
+                Some(order) => ui.label(format!("Observed order: {order:.2}")),
+                None => {
+                    ui.weak(format!(
+                        "Order {ASSUMED_ORDER} assumed. Run 3 or more levels that converge monotonically to observe it."
+                    ))
+                }
+            };
+        }
+
+        let points = self
+            .levels
+            .iter()
+            .filter_map(|level| Some([level.cell_size * 1e3, metric.plot_value(level.value?)]))
+            .collect::<Vec<_>>();
+
+        let response = egui_plot::Plot::new("convergence_plot")
+            .height(150.0)
+            .x_axis_label("Cell Size [mm]")
+            .y_axis_label(metric.plot_label())
+            .legend(egui_plot::Legend::default())
+            .include_x(0.0)
+            .show(ui, |plot_ui| {
+                plot_ui.line(egui_plot::Line::new("Levels", points.clone()));
+                plot_ui.points(egui_plot::Points::new("Levels", points.clone()).radius(3.0));
+                if let Some(extrapolation) = &extrapolation {
+                    plot_ui.points(
+                        egui_plot::Points::new(
+                            "Extrapolated",
+                            vec![[0.0, metric.plot_value(extrapolation.value)]],
+                        )
+                        .radius(4.0),
+                    );
+                }
+            })
+            .response;
+
+        copy_image_or_csv_context_menu(&response, || {
+            let mut csv = "cell_size,value\n".to_owned();
+            for level in &self.levels {
+                csv.push_str(&format!("{},", level.cell_size));
+                if let Some(value) = level.value {
+                    csv.push_str(&value.to_string());
+                }
+                csv.push('\n');
+            }
+            if let Some(extrapolation) = &extrapolation {
+                csv.push_str(&format!("0,{}\n", extrapolation.value));
+            }
+            csv
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::convergence::richardson;
+
+    #[test]
+    fn richardson_observes_order() {
+        // f(h) = 1 + 0.5 h², with h halved from level to level
+        let values = [1.5, 1.125, 1.03125];
+        let extrapolation = richardson(&values, 2.0).unwrap();
+        assert!((extrapolation.value - 1.0).abs() < 1e-12);
+        assert!((extrapolation.observed_order.unwrap() - 2.0).abs() < 1e-12);
+
+        // f(h) = 2 + h
+        let extrapolation = richardson(&[3.0, 2.5, 2.25], 2.0).unwrap();
+        assert!((extrapolation.value - 2.0).abs() < 1e-12);
+        assert!((extrapolation.observed_order.unwrap() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn richardson_assumes_second_order() {
+        let extrapolation = richardson(&[1.5, 1.125], 2.0).unwrap();
+        assert!((extrapolation.value - 1.0).abs() < 1e-12);
+        assert_eq!(extrapolation.observed_order, None);
+
+        // oscillating results don't give an order
+        let extrapolation = richardson(&[1.0, 2.0, 1.5], 2.0).unwrap();
+        assert_eq!(extrapolation.observed_order, None);
+
+        assert_eq!(richardson(&[1.0], 2.0), None);
+    }
+}
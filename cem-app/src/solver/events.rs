@@ -0,0 +1,250 @@
+//! Events scheduled at simulation times.
+//!
+//! A solver config lists [`ScheduledEvent`]s, e.g. to switch a source off after
+//! its pulse, or to save the fields at a certain time. The solver runs them
+//! between two ticks, as soon as the simulation time reaches the event's time,
+//! so they work the same on all FDTD backends.
+//!
+//! Custom backends bring their own sources and fields, so they only support
+//! [`EventAction::Pause`].
+
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{
+        BufWriter,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use cem_probe::{
+    TrackChanges,
+    label_and_value_with_config,
+};
+use cem_solver::{
+    Field,
+    FieldComponent,
+    FieldView,
+};
+use cem_util::egui::FilePickerConfig;
+use color_eyre::eyre::eyre;
+use nalgebra::Point3;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::Error;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledEvent {
+    /// Simulation time the event runs at.
+    pub time: f64,
+
+    pub action: EventAction,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum EventAction {
+    /// Scales the sources and feeds with this name. An amplitude of 0 switches
+    /// them off.
+    SetSourceAmplitude { source: String, amplitude: f64 },
+
+    /// Writes the E and H fields of the whole lattice into a CSV file. Without
+    /// a path, the file is saved into the run log's directory.
+    SaveFields { path: Option<PathBuf> },
+
+    /// Pauses the solver.
+    Pause,
+}
+
+impl EventAction {
+    /// One action of each kind, to pick the kind from.
+    fn kinds() -> [Self; 3] {
+        [
+            Self::SetSourceAmplitude {
+                source: String::new(),
+                amplitude: 0.0,
+            },
+            Self::SaveFields { path: None },
+            Self::Pause,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::SetSourceAmplitude { .. } => "Set Source Amplitude",
+            Self::SaveFields { .. } => "Save Fields",
+            Self::Pause => "Pause",
+        }
+    }
+}
+
+/// The events of a run that haven't run yet, ordered by their times.
+#[derive(Clone, Debug, Default)]
+pub struct EventSchedule {
+    events: VecDeque<ScheduledEvent>,
+}
+
+impl EventSchedule {
+    pub fn new(events: &[ScheduledEvent]) -> Self {
+        let mut events = events.to_vec();
+        events.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self {
+            events: events.into(),
+        }
+    }
+
+    /// Removes the events that are due at `time` and returns them.
+    pub fn take_due(&mut self, time: f64) -> Vec<ScheduledEvent> {
+        let num_due = self
+            .events
+            .iter()
+            .take_while(|event| event.time <= time)
+            .count();
+        self.events.drain(..num_due).collect()
+    }
+}
+
+/// File that [`EventAction::SaveFields`] writes to at `tick`.
+pub fn fields_path(
+    path: Option<&Path>,
+    run_log_directory: Option<&Path>,
+    tick: usize,
+) -> Result<PathBuf, Error> {
+    path.map(ToOwned::to_owned)
+        .or_else(|| {
+            run_log_directory.map(|directory| directory.join(format!("fields_{tick:08}.csv")))
+        })
+        .ok_or_else(|| eyre!("Saving the fields needs a file or a run log"))
+}
+
+/// Writes the E and H fields at every lattice point into a CSV file.
+pub fn save_fields<I>(instance: &I, state: &I::State, path: &Path) -> Result<(), Error>
+where
+    I: Field<Point3<usize>>,
+{
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "x,y,z,ex,ey,ez,hx,hy,hz")?;
+
+    let e = instance.field(state, .., FieldComponent::E);
+    let h = instance.field(state, .., FieldComponent::H);
+    for ((point, e), (_, h)) in e.iter().zip(h.iter()) {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{}",
+            point.x, point.y, point.z, e.x, e.y, e.z, h.x, h.y, h.z
+        )?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Edits the events of a solver config.
+pub fn events_ui(ui: &mut egui::Ui, changes: &mut TrackChanges, events: &mut Vec<ScheduledEvent>) {
+    let mut delete = None;
+
+    for (i, event) in events.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("At");
+                changes.track(ui.add(egui::DragValue::new(&mut event.time).speed(0.1)));
+
+                egui::ComboBox::from_id_salt("action")
+                    .selected_text(event.action.label())
+                    .show_ui(ui, |ui| {
+                        for kind in EventAction::kinds() {
+                            let selected = std::mem::discriminant(&kind)
+                                == std::mem::discriminant(&event.action);
+                            if ui.selectable_label(selected, kind.label()).clicked() && !selected {
+                                event.action = kind;
+                                changes.mark_changed();
+                            }
+                        }
+                    });
+
+                if ui.small_button("Delete").clicked() {
+                    delete = Some(i);
+                }
+            });
+
+            match &mut event.action {
+                EventAction::SetSourceAmplitude { source, amplitude } => {
+                    ui.horizontal(|ui| {
+                        ui.label("Source");
+                        changes
+                            .track(ui.add(egui::TextEdit::singleline(source).desired_width(100.0)));
+                        ui.label("Amplitude");
+                        changes.track(ui.add(egui::DragValue::new(amplitude).speed(0.01)));
+                    });
+                }
+                EventAction::SaveFields { path } => {
+                    label_and_value_with_config(ui, "File", changes, path, &FilePickerConfig::Save);
+                }
+                EventAction::Pause => {}
+            }
+        });
+    }
+
+    if let Some(i) = delete {
+        events.remove(i);
+        changes.mark_changed();
+    }
+
+    if ui.button("Add Event").clicked() {
+        events.push(ScheduledEvent {
+            time: events.last().map_or(0.0, |event| event.time),
+            action: EventAction::Pause,
+        });
+        changes.mark_changed();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::solver::events::{
+        EventAction,
+        EventSchedule,
+        ScheduledEvent,
+        fields_path,
+    };
+
+    fn event(time: f64) -> ScheduledEvent {
+        ScheduledEvent {
+            time,
+            action: EventAction::Pause,
+        }
+    }
+
+    #[test]
+    fn events_are_due_in_order_of_time() {
+        let mut schedule = EventSchedule::new(&[event(2.0), event(0.5), event(1.0)]);
+
+        assert!(schedule.take_due(0.0).is_empty());
+        assert_eq!(schedule.take_due(1.0), vec![event(0.5), event(1.0)]);
+        assert!(schedule.take_due(1.5).is_empty());
+        assert_eq!(schedule.take_due(10.0), vec![event(2.0)]);
+        assert!(schedule.take_due(20.0).is_empty());
+    }
+
+    #[test]
+    fn fields_are_saved_into_run_log_without_path() {
+        let run_log = Path::new("logs/run");
+        assert_eq!(
+            fields_path(None, Some(run_log), 42).unwrap(),
+            run_log.join("fields_00000042.csv")
+        );
+        assert_eq!(
+            fields_path(Some(Path::new("fields.csv")), Some(run_log), 42).unwrap(),
+            Path::new("fields.csv")
+        );
+        assert!(fields_path(None, None, 42).is_err());
+    }
+}
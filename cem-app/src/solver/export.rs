@@ -0,0 +1,516 @@
+//! Export of all results of a solver run into a directory.
+//!
+//! Files are named `<kind>_<index>_<label>.<ext>`, with the label reduced to
+//! characters that are safe in file names, e.g. `point_probe_00_Feed.csv`.
+//! Ports are written as Touchstone files and use their port number as index.
+//! Optionally an HTML report is written too (see [`crate::solver::report`]).
+
+use std::{
+    fmt::Write,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use nalgebra::Vector3;
+
+use crate::{
+    Error,
+    jobs::JobContext,
+    solver::{
+        far_field::far_field_samples_to_csv,
+        pattern::PatternCut,
+        probe::{
+            ProbeOutputs,
+            point_samples_to_csv,
+        },
+        report::{
+            ReportOptions,
+            write_report,
+        },
+        runner::SolverState,
+    },
+};
+
+/// File name of the viewport screenshot, which shows the observers.
+pub const VIEWPORT_IMAGE_FILE_NAME: &str = "viewport.png";
+
+/// Attached to a screenshot request, so the screenshot is saved into the
+/// export directory instead of the screenshots directory.
+#[derive(Clone, Debug)]
+pub struct ExportScreenshot {
+    pub path: PathBuf,
+}
+
+/// Gain below the peak at the center of exported polar plots, in dB.
+const POLAR_PLOT_DYNAMIC_RANGE: f64 = 40.0;
+
+/// Number of frequencies exported port reflections are evaluated at.
+const NUM_PORT_FREQUENCIES: usize = 200;
+
+/// Writes CSV files and SVG plots for all probes, a summary, and if `report`
+/// is set, an HTML report.
+///
+/// This is meant to run as a job. Returns the paths of all files written.
+pub fn export_results(
+    directory: &Path,
+    state: &SolverState,
+    probe_outputs: &ProbeOutputs,
+    report: Option<&ReportOptions>,
+    job: &JobContext,
+) -> Result<Vec<PathBuf>, Error> {
+    std::fs::create_dir_all(directory)?;
+
+    let mut files = vec![];
+
+    // one step per probe, the report and the summary
+    let num_steps = probe_outputs.line_probes.len()
+        + probe_outputs.point_probes.len()
+        + probe_outputs.nf2ff_boxes.len()
+        + probe_outputs.far_field_probes.len()
+        + probe_outputs.ports.len()
+        + usize::from(report.is_some())
+        + 1;
+    let mut step = 0;
+    let mut next_step = |label: &str| {
+        job.set_progress(step as f32 / num_steps as f32);
+        job.set_message(label);
+        step += 1;
+        job.check_cancelled()
+    };
+
+    for (index, (label, output)) in probe_outputs.line_probes.iter().enumerate() {
+        next_step(label)?;
+
+        let Some(line_cut) = output.latest()
+        else {
+            continue;
+        };
+
+        let stem = file_stem("line_probe", index, label);
+        files.push(write_csv(directory, &stem, &line_cut.to_csv())?);
+        files.push(write_svg_plot(
+            &directory.join(format!("{stem}.svg")),
+            &format!("{label} (t = {:.3})", line_cut.time),
+            &vector_series(line_cut.distances.iter().copied().zip(&line_cut.values)),
+        )?);
+    }
+
+    for (index, (label, output)) in probe_outputs.point_probes.iter().enumerate() {
+        next_step(label)?;
+
+        let samples = output.samples();
+        if samples.is_empty() {
+            continue;
+        }
+
+        let stem = file_stem("point_probe", index, label);
+        files.push(write_csv(
+            directory,
+            &stem,
+            &point_samples_to_csv(&samples),
+        )?);
+        files.push(write_svg_plot(
+            &directory.join(format!("{stem}.svg")),
+            label,
+            &vector_series(samples.iter().map(|sample| (sample.time, &sample.value))),
+        )?);
+    }
+
+    for (index, (label, output)) in probe_outputs.nf2ff_boxes.iter().enumerate() {
+        next_step(label)?;
+
+        let Some(pattern) = output.pattern(label)
+        else {
+            continue;
+        };
+
+        let stem = file_stem("nf2ff_box", index, label);
+        files.push(write_csv(directory, &stem, &pattern.to_csv())?);
+        files.push(write_svg_polar_plot(
+            &directory.join(format!("{stem}.svg")),
+            &format!(
+                "{label} (f = {} {})",
+                pattern.frequency,
+                probe_outputs.units.frequency_unit()
+            ),
+            &[PatternCut::EPlane, PatternCut::HPlane].map(|cut| (cut.label(), cut.gains(&pattern))),
+        )?);
+    }
+
+    for (index, (label, output)) in probe_outputs.far_field_probes.iter().enumerate() {
+        next_step(label)?;
+
+        let Some(samples) = output.samples()
+        else {
+            continue;
+        };
+
+        let (theta, phi) = output.direction();
+        let stem = file_stem("far_field_probe", index, label);
+        files.push(write_csv(
+            directory,
+            &stem,
+            &far_field_samples_to_csv(&samples),
+        )?);
+        files.push(write_svg_plot(
+            &directory.join(format!("{stem}.svg")),
+            &format!("{label} (θ = {theta:.1}°, φ = {phi:.1}°)"),
+            &[(
+                "gain [dBi]",
+                samples
+                    .iter()
+                    .map(|sample| [sample.frequency, sample.gain_dbi()])
+                    .collect(),
+            )],
+        )?);
+    }
+
+    for (label, output) in &probe_outputs.ports {
+        next_step(label)?;
+
+        let Some(max_frequency) = output.max_frequency()
+        else {
+            continue;
+        };
+        let frequencies = (1..=NUM_PORT_FREQUENCIES)
+            .map(|i| max_frequency * i as f64 / NUM_PORT_FREQUENCIES as f64)
+            .collect();
+        let Some(reflection) = output.reflection(format!("S{0}{0}", output.number()), frequencies)
+        else {
+            continue;
+        };
+
+        // the color is noted, so plots made from the file can match the app
+        let color = output.color();
+        let comments = [
+            format!("Port {}: {label}", output.number()),
+            format!(
+                "Color: #{:02x}{:02x}{:02x}",
+                color.r(),
+                color.g(),
+                color.b()
+            ),
+        ];
+
+        let path = directory.join(format!("{}.s1p", file_stem("port", output.number(), label)));
+        std::fs::write(&path, reflection.to_touchstone(&comments))?;
+        files.push(path);
+    }
+
+    if let Some(options) = report {
+        next_step("Report")?;
+        files.push(write_report(
+            directory,
+            state,
+            probe_outputs,
+            &files,
+            options,
+        )?);
+    }
+
+    next_step("Summary")?;
+    files.push(write_summary(directory, state, probe_outputs, &files)?);
+
+    Ok(files)
+}
+
+pub(super) fn file_stem(kind: &str, index: usize, label: &str) -> String {
+    format!("{kind}_{index:02}_{}", safe_file_name(label))
+}
+
+/// Replaces all characters that might not be safe in file names.
+pub(super) fn safe_file_name(label: &str) -> String {
+    label
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            }
+            else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn write_csv(directory: &Path, stem: &str, csv: &str) -> Result<PathBuf, Error> {
+    let path = directory.join(format!("{stem}.csv"));
+    std::fs::write(&path, csv)?;
+    Ok(path)
+}
+
+fn write_summary(
+    directory: &Path,
+    state: &SolverState,
+    probe_outputs: &ProbeOutputs,
+    files: &[PathBuf],
+) -> Result<PathBuf, Error> {
+    let mut report = String::new();
+
+    writeln!(report, "# Solver Run")?;
+    writeln!(report)?;
+    for (label, value) in run_metadata(state, probe_outputs) {
+        writeln!(report, "- {label}: {value}")?;
+    }
+    writeln!(report)?;
+
+    writeln!(report, "## Files")?;
+    writeln!(report)?;
+    writeln!(report, "- {VIEWPORT_IMAGE_FILE_NAME}")?;
+    for file in files {
+        if let Some(file_name) = file.file_name() {
+            writeln!(report, "- {}", file_name.display())?;
+        }
+    }
+
+    // todo: observers don't record their frames yet (see `Observer::write_to_gif`),
+    // so we can only export what's currently shown in the viewport.
+
+    let path = directory.join("summary.md");
+    std::fs::write(&path, report)?;
+
+    Ok(path)
+}
+
+/// Metadata of a run as labels and values, for the summary and reports.
+pub(super) fn run_metadata(
+    state: &SolverState,
+    probe_outputs: &ProbeOutputs,
+) -> Vec<(&'static str, String)> {
+    let units = probe_outputs.units;
+    vec![
+        (
+            "Exported",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        ),
+        ("Finished", state.finished.to_string()),
+        ("Units", units.label().to_string()),
+        (
+            "Simulation time",
+            format!("{} {}", state.sim_time, units.time_unit()),
+        ),
+        ("Ticks", state.sim_tick.to_string()),
+        ("Running time", format!("{:.3?}", state.total_running_time)),
+        ("Line probes", probe_outputs.line_probes.len().to_string()),
+        ("Point probes", probe_outputs.point_probes.len().to_string()),
+        ("NF2FF boxes", probe_outputs.nf2ff_boxes.len().to_string()),
+        (
+            "Far-field probes",
+            probe_outputs.far_field_probes.len().to_string(),
+        ),
+        ("Ports", probe_outputs.ports.len().to_string()),
+    ]
+}
+
+fn vector_series<'a>(
+    points: impl Iterator<Item = (f64, &'a Vector3<f64>)>,
+) -> [(&'static str, Vec<[f64; 2]>); 3] {
+    let mut series = [("x", vec![]), ("y", vec![]), ("z", vec![])];
+    for (x, value) in points {
+        for (axis, (_, points)) in series.iter_mut().enumerate() {
+            points.push([x, value[axis]]);
+        }
+    }
+    series
+}
+
+/// Writes a minimal line plot as SVG.
+fn write_svg_plot(
+    path: &Path,
+    title: &str,
+    series: &[(&str, Vec<[f64; 2]>)],
+) -> Result<PathBuf, Error> {
+    const WIDTH: f64 = 800.0;
+    const HEIGHT: f64 = 450.0;
+    const MARGIN: f64 = 50.0;
+    const COLORS: [&str; 3] = ["#d62728", "#2ca02c", "#1f77b4"];
+
+    let (mut min, mut max) = ([f64::INFINITY; 2], [f64::NEG_INFINITY; 2]);
+    for [x, y] in series.iter().flat_map(|(_, points)| points) {
+        min = [min[0].min(*x), min[1].min(*y)];
+        max = [max[0].max(*x), max[1].max(*y)];
+    }
+    let range = [0, 1].map(|i| {
+        let range = max[i] - min[i];
+        if range > 0.0 { range } else { 1.0 }
+    });
+
+    let to_svg = |[x, y]: [f64; 2]| {
+        [
+            MARGIN + (x - min[0]) / range[0] * (WIDTH - 2.0 * MARGIN),
+            HEIGHT - MARGIN - (y - min[1]) / range[1] * (HEIGHT - 2.0 * MARGIN),
+        ]
+    };
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" font-family="sans-serif" font-size="12">"#
+    )?;
+    writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+    writeln!(
+        svg,
+        r#"<rect x="{MARGIN}" y="{MARGIN}" width="{}" height="{}" fill="none" stroke="black"/>"#,
+        WIDTH - 2.0 * MARGIN,
+        HEIGHT - 2.0 * MARGIN
+    )?;
+    writeln!(
+        svg,
+        r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+        0.5 * WIDTH,
+        0.5 * MARGIN,
+        escape_xml(title)
+    )?;
+
+    // axis limits
+    if min[0].is_finite() {
+        writeln!(
+            svg,
+            r#"<text x="{MARGIN}" y="{}">{:.3e}</text><text x="{}" y="{}" text-anchor="end">{:.3e}</text>"#,
+            HEIGHT - 0.5 * MARGIN,
+            min[0],
+            WIDTH - MARGIN,
+            HEIGHT - 0.5 * MARGIN,
+            max[0]
+        )?;
+        writeln!(
+            svg,
+            r#"<text x="5" y="{}">{:.2e}</text><text x="5" y="{}">{:.2e}</text>"#,
+            HEIGHT - MARGIN,
+            min[1],
+            MARGIN + 10.0,
+            max[1]
+        )?;
+    }
+
+    for (i, (name, points)) in series.iter().enumerate() {
+        let color = COLORS[i % COLORS.len()];
+        let points = points
+            .iter()
+            .map(|point| {
+                let [x, y] = to_svg(*point);
+                format!("{x:.2},{y:.2}")
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(
+            svg,
+            r#"<polyline fill="none" stroke="{color}" stroke-width="1.5" points="{points}"/>"#
+        )?;
+        writeln!(
+            svg,
+            r#"<text x="{}" y="{}" fill="{color}">{}</text>"#,
+            WIDTH - MARGIN + 5.0,
+            MARGIN + 15.0 * (i + 1) as f64,
+            escape_xml(name)
+        )?;
+    }
+
+    writeln!(svg, "</svg>")?;
+
+    std::fs::write(path, svg)?;
+
+    Ok(path.to_owned())
+}
+
+/// Writes cuts through a pattern (angle and gain in dB) as a polar plot in SVG,
+/// scaled to the highest gain of all cuts.
+fn write_svg_polar_plot(
+    path: &Path,
+    title: &str,
+    series: &[(&str, Vec<[f64; 2]>)],
+) -> Result<PathBuf, Error> {
+    const SIZE: f64 = 500.0;
+    const MARGIN: f64 = 50.0;
+    const RING_STEP: f64 = 10.0;
+    const COLORS: [&str; 3] = ["#d62728", "#2ca02c", "#1f77b4"];
+
+    let peak = series
+        .iter()
+        .flat_map(|(_, points)| points)
+        .map(|[_, gain]| *gain)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let center = 0.5 * SIZE;
+    let outer_radius = 0.5 * SIZE - MARGIN;
+
+    let to_svg = |angle: f64, radius: f64| {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        [center + radius * sin, center - radius * cos]
+    };
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{SIZE}" height="{SIZE}" font-family="sans-serif" font-size="12">"#
+    )?;
+    writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+    writeln!(
+        svg,
+        r#"<text x="{center}" y="{}" text-anchor="middle">{}</text>"#,
+        0.4 * MARGIN,
+        escape_xml(title)
+    )?;
+
+    // rings, labelled with their gain
+    let mut ring = 0.0;
+    while ring < POLAR_PLOT_DYNAMIC_RANGE {
+        let radius = outer_radius * (1.0 - ring / POLAR_PLOT_DYNAMIC_RANGE);
+        writeln!(
+            svg,
+            r##"<circle cx="{center}" cy="{center}" r="{radius:.2}" fill="none" stroke="#cccccc"/><text x="{}" y="{:.2}" fill="#888888">{:.0} dBi</text>"##,
+            center + 2.0,
+            center - radius - 2.0,
+            peak - ring
+        )?;
+        ring += RING_STEP;
+    }
+
+    // spokes
+    for angle in (0..360).step_by(30) {
+        let [x, y] = to_svg(angle as f64, outer_radius);
+        let [label_x, label_y] = to_svg(angle as f64, outer_radius + 20.0);
+        writeln!(
+            svg,
+            r##"<line x1="{center}" y1="{center}" x2="{x:.2}" y2="{y:.2}" stroke="#cccccc"/><text x="{label_x:.2}" y="{label_y:.2}" text-anchor="middle" dominant-baseline="middle">{angle}°</text>"##
+        )?;
+    }
+
+    for (i, (name, points)) in series.iter().enumerate() {
+        let color = COLORS[i % COLORS.len()];
+        let points = points
+            .iter()
+            .map(|[angle, gain]| {
+                let radius = ((gain - peak + POLAR_PLOT_DYNAMIC_RANGE) / POLAR_PLOT_DYNAMIC_RANGE)
+                    .clamp(0.0, 1.0);
+                let [x, y] = to_svg(*angle, outer_radius * radius);
+                format!("{x:.2},{y:.2}")
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(
+            svg,
+            r#"<polyline fill="none" stroke="{color}" stroke-width="1.5" points="{points}"/>"#
+        )?;
+        writeln!(
+            svg,
+            r#"<text x="5" y="{}" fill="{color}">{}</text>"#,
+            SIZE - 5.0 - 15.0 * i as f64,
+            escape_xml(name)
+        )?;
+    }
+
+    writeln!(svg, "</svg>")?;
+
+    std::fs::write(path, svg)?;
+
+    Ok(path.to_owned())
+}
+
+pub(super) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
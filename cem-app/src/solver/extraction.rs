@@ -0,0 +1,355 @@
+//! Probes, observers and NF2FF boxes defined outside of a project.
+//!
+//! Headless runs read these from a TOML file (`--extraction`), so what is
+//! extracted from a batch of runs can be changed without editing the project.
+//! A file looks like this:
+//!
+//! ```toml
+//! # remove the probes defined in the project
+//! replace = true
+//! # write results here when the run finished
+//! export = "results/run-1"
+//!
+//! # and a report of them
+//! [report]
+//! title = "Run 1"
+//! layout = "grid"
+//!
+//! [[point_probes]]
+//! name = "Feed"
+//! position = [0.0, 0.5, 0.0]
+//!
+//! [[line_probes]]
+//! name = "Cut"
+//! start = [-0.5, 0.5, 0.0]
+//! end = [0.5, 0.5, 0.0]
+//! field = "H"
+//!
+//! [[observers]]
+//! name = "Slice"
+//! slice = { axis = "Y", position = 0.5 }
+//!
+//! [[nf2ff_boxes]]
+//! name = "Far Field"
+//! half_extents = [0.2, 0.2, 0.2]
+//! frequency = 5.0
+//! ```
+//!
+//! Positions are in scene units. Omitted fields take the same defaults as
+//! probes added in the app.
+
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use bevy_ecs::{
+    entity::Entity,
+    name::Name,
+    query::{
+        Or,
+        With,
+    },
+    world::World,
+};
+use cem_render::mesh::LoadMesh;
+use cem_scene::{
+    spatial::Collider,
+    transform::LocalTransform,
+};
+use cem_solver::FieldComponent;
+use nalgebra::{
+    Point3,
+    Vector2,
+    Vector3,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    Error,
+    composer::{
+        selection::Selectable,
+        shape::flat::{
+            Quad,
+            QuadMeshConfig,
+        },
+        tree::ShowInTree,
+    },
+    solver::{
+        far_field::FarFieldProbe,
+        nf2ff::Nf2ffBox,
+        observer::{
+            Observer,
+            VolumeSlice,
+            test_color_map,
+        },
+        probe::{
+            LineProbe,
+            PointProbe,
+        },
+        report::ReportOptions,
+    },
+};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Extraction {
+    /// Remove the probes, observers and NF2FF boxes defined in the project
+    pub replace: bool,
+
+    /// Export the results into this directory when the run finished
+    pub export: Option<PathBuf>,
+
+    /// Also write a report with the exported results
+    pub report: Option<ReportOptions>,
+
+    pub point_probes: Vec<PointProbeDefinition>,
+    pub line_probes: Vec<LineProbeDefinition>,
+    pub observers: Vec<ObserverDefinition>,
+    pub nf2ff_boxes: Vec<Nf2ffBoxDefinition>,
+}
+
+impl Extraction {
+    pub fn from_path(path: &Path) -> Result<Self, Error> {
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Adds the probes, observers and NF2FF boxes to the scene.
+    pub fn apply(&self, world: &mut World) {
+        if self.replace {
+            let entities = world
+                .query_filtered::<Entity, Or<(
+                    With<PointProbe>,
+                    With<LineProbe>,
+                    With<Observer>,
+                    With<Nf2ffBox>,
+                    With<FarFieldProbe>,
+                )>>()
+                .iter(world)
+                .collect::<Vec<_>>();
+
+            // entities like ports have other uses, so only the components are removed
+            for entity in entities {
+                world
+                    .entity_mut(entity)
+                    .remove::<(PointProbe, LineProbe, Observer, Nf2ffBox, FarFieldProbe)>();
+            }
+        }
+
+        for definition in &self.point_probes {
+            world.spawn((
+                Name::new(definition.name.clone()),
+                PointProbe {
+                    field: definition.field,
+                    interval: definition.interval,
+                    max_samples: definition.max_samples,
+                },
+                LocalTransform::from(definition.position),
+            ));
+        }
+
+        for definition in &self.line_probes {
+            world.spawn((
+                Name::new(definition.name.clone()),
+                LineProbe {
+                    start: definition.start,
+                    end: definition.end,
+                    num_samples: definition.num_samples,
+                    field: definition.field,
+                    interval: definition.interval,
+                    write_to_csv: definition.write_to_csv.clone(),
+                },
+                LocalTransform::from(Point3::origin()),
+            ));
+        }
+
+        for definition in &self.observers {
+            let quad = Quad::new(definition.half_extents);
+            world.spawn((
+                Name::new(definition.name.clone()),
+                Observer {
+                    write_to_gif: definition.write_to_gif.clone(),
+                    display_as_texture: true,
+                    field: definition.field,
+                    color_map: test_color_map(definition.scale, Vector3::z_axis()),
+                    half_extents: definition.half_extents,
+                    post_process: definition.post_process.clone(),
+                    slice: definition.slice,
+                    depth_compositing: Default::default(),
+                    history_budget: None,
+                },
+                LocalTransform::from(definition.position),
+                Collider::from(quad),
+                LoadMesh::from_shape(quad, QuadMeshConfig { back_face: true }),
+                Selectable,
+                ShowInTree,
+            ));
+        }
+
+        for definition in &self.nf2ff_boxes {
+            world.spawn((
+                Name::new(definition.name.clone()),
+                Nf2ffBox {
+                    half_extents: definition.half_extents,
+                    frequency: definition.frequency,
+                    num_theta: definition.num_theta,
+                    num_phi: definition.num_phi,
+                    interval: definition.interval,
+                },
+                LocalTransform::from(definition.position),
+            ));
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PointProbeDefinition {
+    pub name: String,
+    pub position: Point3<f32>,
+    pub field: FieldComponent,
+    pub interval: usize,
+    pub max_samples: usize,
+}
+
+impl Default for PointProbeDefinition {
+    fn default() -> Self {
+        let point_probe = PointProbe::default();
+        Self {
+            name: "Point Probe".to_owned(),
+            position: Point3::origin(),
+            field: point_probe.field,
+            interval: point_probe.interval,
+            max_samples: point_probe.max_samples,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LineProbeDefinition {
+    pub name: String,
+    pub start: Point3<f32>,
+    pub end: Point3<f32>,
+    pub num_samples: usize,
+    pub field: FieldComponent,
+    pub interval: usize,
+    pub write_to_csv: Option<PathBuf>,
+}
+
+impl Default for LineProbeDefinition {
+    fn default() -> Self {
+        let line_probe = LineProbe::default();
+        Self {
+            name: "Line Probe".to_owned(),
+            start: line_probe.start,
+            end: line_probe.end,
+            num_samples: line_probe.num_samples,
+            field: line_probe.field,
+            interval: line_probe.interval,
+            write_to_csv: line_probe.write_to_csv,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObserverDefinition {
+    pub name: String,
+    pub position: Point3<f32>,
+    pub half_extents: Vector2<f32>,
+    pub field: FieldComponent,
+
+    /// Scale of the color map
+    pub scale: f32,
+
+    /// See [`Observer::post_process`]
+    pub post_process: Option<String>,
+
+    pub slice: Option<VolumeSlice>,
+    pub write_to_gif: Option<PathBuf>,
+}
+
+impl Default for ObserverDefinition {
+    fn default() -> Self {
+        Self {
+            name: "Observer".to_owned(),
+            position: Point3::origin(),
+            half_extents: Vector2::repeat(0.5),
+            field: FieldComponent::E,
+            scale: 1.0,
+            post_process: None,
+            slice: None,
+            write_to_gif: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Nf2ffBoxDefinition {
+    pub name: String,
+    pub position: Point3<f32>,
+    pub half_extents: Vector3<f32>,
+    pub frequency: f64,
+    pub num_theta: usize,
+    pub num_phi: usize,
+    pub interval: usize,
+}
+
+impl Default for Nf2ffBoxDefinition {
+    fn default() -> Self {
+        let nf2ff_box = Nf2ffBox::default();
+        Self {
+            name: "NF2FF Box".to_owned(),
+            position: Point3::origin(),
+            half_extents: nf2ff_box.half_extents,
+            frequency: nf2ff_box.frequency,
+            num_theta: nf2ff_box.num_theta,
+            num_phi: nf2ff_box.num_phi,
+            interval: nf2ff_box.interval,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cem_solver::FieldComponent;
+
+    use crate::solver::{
+        extraction::Extraction,
+        observer::SliceAxis,
+    };
+
+    #[test]
+    fn omitted_fields_take_defaults() {
+        let extraction: Extraction = toml::from_str(
+            r#"
+            export = "results"
+
+            [[line_probes]]
+            start = [0.0, 0.0, 0.0]
+            end = [1.0, 0.0, 0.0]
+            field = "H"
+
+            [[observers]]
+            slice = { axis = "X", position = 0.25 }
+            "#,
+        )
+        .unwrap();
+
+        assert!(!extraction.replace);
+        assert!(extraction.point_probes.is_empty());
+
+        let line_probe = &extraction.line_probes[0];
+        assert_eq!(line_probe.field, FieldComponent::H);
+        assert_eq!(line_probe.num_samples, 100);
+
+        let slice = extraction.observers[0].slice.unwrap();
+        assert_eq!(slice.axis, SliceAxis::X);
+        assert_eq!(extraction.observers[0].scale, 1.0);
+    }
+}
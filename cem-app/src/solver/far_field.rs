@@ -0,0 +1,587 @@
+//! Far-field probes.
+//!
+//! A [`FarFieldProbe`] is an arrow pointing in a direction of interest, e.g. an
+//! antenna's boresight. While the solver runs, it Fourier transforms the
+//! fields on the faces of the nearest [`Nf2ffBox`] at a range of frequencies,
+//! and reports gain and phase of the far field in the arrow's direction over
+//! frequency.
+//!
+//! Only the arrow's direction matters for the far field, its position just
+//! picks the NF2FF box. The direction is resolved in the box's frame when a run
+//! starts, and phases are relative to the box's center.
+
+use std::{
+    f64::consts::PI,
+    sync::Arc,
+};
+
+use bevy_ecs::{
+    component::Component,
+    reflect::ReflectComponent,
+};
+use bevy_reflect::{
+    Reflect,
+    ReflectSerialize,
+    prelude::ReflectDefault,
+};
+use cem_probe::{
+    PropertiesUi,
+    TrackChanges,
+};
+use cem_render::{
+    material::Wireframe,
+    mesh::{
+        LoadMesh,
+        polyline::Polyline,
+    },
+};
+use cem_scene::{
+    Scene,
+    probe::{
+        ComponentName,
+        ReflectComponentUi,
+    },
+    spatial::Collider,
+    transform::{
+        GlobalTransform,
+        LocalTransform,
+    },
+};
+use cem_solver::{
+    Field,
+    material::UnitSystem,
+};
+use nalgebra::{
+    Point3,
+    Vector3,
+};
+use num::complex::Complex64;
+use parking_lot::Mutex;
+use parry3d::shape::Cuboid;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    clipboard::copy_image_or_csv_context_menu,
+    color_scheme,
+    composer::{
+        selection::Selectable,
+        tree::ShowInTree,
+    },
+    error::ErrorHandler,
+    jobs::{
+        Cancelled,
+        JobHandle,
+        Jobs,
+    },
+    solver::{
+        nf2ff::{
+            BoxSurface,
+            Nf2ffBox,
+            far_field,
+            vacuum_impedance_and_wavenumber,
+        },
+        runner::CoordinateTransformations,
+    },
+    util::scene::EntityBuilderExt,
+};
+
+/// Length of the arrow marking a far-field probe in the viewport.
+const ARROW_LENGTH: f32 = 0.2;
+
+/// Reports the far field in the direction of the entity's local z axis.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Component, Reflect)]
+#[reflect(Component, ComponentUi, @ComponentName::new("Far-Field Probe"), Default, Serialize)]
+pub struct FarFieldProbe {
+    pub start_frequency: f64,
+    pub end_frequency: f64,
+    pub num_frequencies: usize,
+
+    /// Sample every N ticks
+    pub interval: usize,
+}
+
+impl Default for FarFieldProbe {
+    fn default() -> Self {
+        Self {
+            start_frequency: 0.5,
+            end_frequency: 1.5,
+            num_frequencies: 21,
+            interval: 1,
+        }
+    }
+}
+
+impl FarFieldProbe {
+    /// Evenly spaced frequencies from start to end (inclusive).
+    pub fn frequencies(&self) -> Vec<f64> {
+        let num_frequencies = self.num_frequencies.max(1);
+        if num_frequencies == 1 {
+            return vec![self.start_frequency];
+        }
+
+        (0..num_frequencies)
+            .map(|i| {
+                self.start_frequency
+                    + (self.end_frequency - self.start_frequency) * i as f64
+                        / (num_frequencies - 1) as f64
+            })
+            .collect()
+    }
+}
+
+impl PropertiesUi for FarFieldProbe {
+    type Config = ();
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
+        let _ = config;
+        let mut changes = TrackChanges::default();
+
+        let response = egui::Frame::new()
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Frequencies");
+                    changes.track(
+                        ui.add(
+                            egui::DragValue::new(&mut self.start_frequency)
+                                .range(0.0..=self.end_frequency)
+                                .speed(0.01),
+                        ),
+                    );
+                    ui.label("to");
+                    changes.track(
+                        ui.add(
+                            egui::DragValue::new(&mut self.end_frequency)
+                                .range(self.start_frequency..=f64::INFINITY)
+                                .speed(0.01),
+                        ),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Samples");
+                    changes.track(
+                        ui.add(egui::DragValue::new(&mut self.num_frequencies).range(1..=1000)),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Every N Ticks");
+                    changes.track(
+                        ui.add(egui::DragValue::new(&mut self.interval).range(1..=usize::MAX)),
+                    );
+                });
+            })
+            .response;
+
+        changes.propagated(response)
+    }
+}
+
+/// Adds a far-field probe pointing along the local z axis of `transform`.
+pub fn add_far_field_probe(
+    scene: &mut Scene,
+    name: impl std::fmt::Display,
+    transform: LocalTransform,
+) {
+    // an arrow from -z to +z, with a head made of 4 strokes back from the tip
+    let half_length = 0.5 * ARROW_LENGTH;
+    let tip = Point3::new(0.0, 0.0, half_length);
+    let head = 0.2 * ARROW_LENGTH;
+    let mut points = vec![Point3::new(0.0, 0.0, -half_length), tip];
+    for (x, y) in [(1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0)] {
+        points.push(Point3::new(
+            0.5 * head * x,
+            0.5 * head * y,
+            half_length - head,
+        ));
+        points.push(tip);
+    }
+
+    let marker = Cuboid::new(Vector3::new(0.25 * head, 0.25 * head, half_length));
+
+    scene
+        .world
+        .spawn((
+            FarFieldProbe::default(),
+            Collider::from(marker),
+            LoadMesh::from_generator(Polyline::new(points)),
+            Wireframe::new(color_scheme::palette().wireframe()),
+            Selectable,
+            ShowInTree,
+        ))
+        .name(name)
+        .transform(transform);
+}
+
+/// Far field in the probe's direction at a single frequency.
+#[derive(Clone, Copy, Debug)]
+pub struct FarFieldSample {
+    pub frequency: f64,
+
+    /// Far-field components `r * E_theta` and `r * E_phi`
+    pub e_theta: Complex64,
+    pub e_phi: Complex64,
+
+    /// Total gain relative to the power radiated through the NF2FF box
+    /// (linear, not in dB).
+    pub gain: f64,
+}
+
+impl FarFieldSample {
+    pub fn gain_dbi(&self) -> f64 {
+        10.0 * self.gain.log10()
+    }
+}
+
+pub fn far_field_samples_to_csv(samples: &[FarFieldSample]) -> String {
+    let mut csv = "frequency,gain_dbi,phase_theta,phase_phi\n".to_owned();
+    for sample in samples {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            sample.frequency,
+            sample.gain_dbi(),
+            sample.e_theta.arg().to_degrees(),
+            sample.e_phi.arg().to_degrees()
+        ));
+    }
+    csv
+}
+
+#[derive(Debug)]
+struct FarFieldData {
+    frequencies: Vec<f64>,
+    units: UnitSystem,
+
+    /// Label of the NF2FF box whose surface is sampled
+    nf2ff_box: String,
+
+    /// Direction (theta, phi in degrees) in the NF2FF box's frame
+    direction: (f64, f64),
+
+    surface: Arc<BoxSurface>,
+
+    /// Fourier transformed E and H fields of every sampled cell, with the cells
+    /// varying fastest.
+    e: Vec<Vector3<Complex64>>,
+    h: Vec<Vector3<Complex64>>,
+
+    num_samples: usize,
+}
+
+/// Handle to the transformed fields of a running far-field probe.
+#[derive(Clone, Debug)]
+pub struct FarFieldProbeOutput {
+    data: Arc<Mutex<FarFieldData>>,
+}
+
+impl FarFieldProbeOutput {
+    /// Number of time samples taken so far.
+    pub fn num_samples(&self) -> usize {
+        self.data.lock().num_samples
+    }
+
+    /// Direction (theta, phi in degrees) in the frame of the NF2FF box.
+    pub fn direction(&self) -> (f64, f64) {
+        self.data.lock().direction
+    }
+
+    /// Label of the NF2FF box the probe samples.
+    pub fn nf2ff_box(&self) -> String {
+        self.data.lock().nf2ff_box.clone()
+    }
+
+    /// Computes the far field at every frequency from the fields sampled so
+    /// far.
+    ///
+    /// Returns `None` if nothing was sampled yet.
+    pub fn samples(&self) -> Option<Vec<FarFieldSample>> {
+        // copy the fields, so the solver isn't blocked while we integrate
+        let (frequencies, units, (theta, phi), surface, e, h) = {
+            let data = self.data.lock();
+            if data.num_samples == 0 {
+                return None;
+            }
+            (
+                data.frequencies.clone(),
+                data.units,
+                data.direction,
+                data.surface.clone(),
+                data.e.clone(),
+                data.h.clone(),
+            )
+        };
+
+        let num_cells = surface.num_cells();
+        let samples = frequencies
+            .iter()
+            .enumerate()
+            .map(|(index, &frequency)| {
+                let cells = index * num_cells..(index + 1) * num_cells;
+                let (e, h) = (&e[cells.clone()], &h[cells]);
+
+                let (vacuum_impedance, wavenumber) =
+                    vacuum_impedance_and_wavenumber(units, frequency);
+                let [e_theta, e_phi] = far_field(
+                    &surface.currents(e, h),
+                    wavenumber,
+                    vacuum_impedance,
+                    theta.to_radians(),
+                    phi.to_radians(),
+                );
+
+                let intensity = (e_theta.norm_sqr() + e_phi.norm_sqr()) / (2.0 * vacuum_impedance);
+                let power = surface.outgoing_power(e, h).max(f64::MIN_POSITIVE);
+
+                FarFieldSample {
+                    frequency,
+                    e_theta,
+                    e_phi,
+                    gain: 4.0 * PI * intensity / power,
+                }
+            })
+            .collect();
+
+        Some(samples)
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct FarFieldProbeInstance {
+    pub label: String,
+    surface: Arc<BoxSurface>,
+    angular_frequencies: Vec<f64>,
+    interval: usize,
+    output: FarFieldProbeOutput,
+}
+
+impl FarFieldProbeInstance {
+    /// Creates a probe sampling the surface of `nf2ff_box`.
+    ///
+    /// Returns `None` if the box is outside of the solver volume.
+    pub fn new(
+        label: String,
+        probe: &FarFieldProbe,
+        transform: &GlobalTransform,
+        (box_label, nf2ff_box, box_transform): (String, &Nf2ffBox, &GlobalTransform),
+        coordinate_transformations: &CoordinateTransformations,
+        units: UnitSystem,
+    ) -> Option<Self> {
+        let surface = Arc::new(BoxSurface::new(
+            &nf2ff_box.half_extents,
+            box_transform,
+            coordinate_transformations,
+        )?);
+
+        let direction = box_transform.precise_isometry().rotation.inverse()
+            * transform.precise_isometry().rotation
+            * Vector3::z();
+
+        let frequencies = probe.frequencies();
+        let num_values = frequencies.len() * surface.num_cells();
+
+        let output = FarFieldProbeOutput {
+            data: Arc::new(Mutex::new(FarFieldData {
+                units,
+                nf2ff_box: box_label,
+                direction: direction_angles(&direction),
+                surface: surface.clone(),
+                e: vec![Vector3::zeros(); num_values],
+                h: vec![Vector3::zeros(); num_values],
+                num_samples: 0,
+                frequencies: frequencies.clone(),
+            })),
+        };
+
+        Some(Self {
+            label,
+            surface,
+            angular_frequencies: frequencies
+                .iter()
+                .map(|frequency| 2.0 * PI * frequency)
+                .collect(),
+            interval: probe.interval.max(1),
+            output,
+        })
+    }
+
+    pub fn output(&self) -> FarFieldProbeOutput {
+        self.output.clone()
+    }
+
+    pub fn interval(&self) -> usize {
+        self.interval
+    }
+
+    /// Adds the current fields to the running Fourier transforms.
+    pub fn run<I>(&mut self, instance: &I, state: &I::State, time: f64)
+    where
+        I: Field<Point3<usize>>,
+    {
+        let kernels = self
+            .angular_frequencies
+            .iter()
+            .map(|angular_frequency| Complex64::from_polar(1.0, -angular_frequency * time))
+            .collect::<Vec<_>>();
+        let num_cells = self.surface.num_cells();
+
+        let mut data = self.output.data.lock();
+        let data = &mut *data;
+
+        self.surface.sample(instance, state, |index, e, h| {
+            for (frequency_index, kernel) in kernels.iter().enumerate() {
+                let index = frequency_index * num_cells + index;
+                data.e[index] += e.map(|c| kernel * c);
+                data.h[index] += h.map(|c| kernel * c);
+            }
+        });
+
+        data.num_samples += 1;
+    }
+}
+
+/// Theta and phi (in degrees) of a unit vector.
+fn direction_angles(direction: &Vector3<f64>) -> (f64, f64) {
+    let theta = direction.z.clamp(-1.0, 1.0).acos().to_degrees();
+    let phi = direction
+        .y
+        .atan2(direction.x)
+        .to_degrees()
+        .rem_euclid(360.0);
+    (theta, phi)
+}
+
+/// Per-probe view settings and the last computed samples, kept in egui's
+/// memory.
+#[derive(Clone, Debug, Default)]
+struct FarFieldView {
+    samples: Option<Arc<Vec<FarFieldSample>>>,
+
+    /// Job computing the next samples
+    job: Option<JobHandle<Option<Vec<FarFieldSample>>>>,
+}
+
+/// Computes the far field of a running far-field probe on request, and plots
+/// gain and phase over frequency.
+pub fn far_field_probe_ui(
+    ui: &mut egui::Ui,
+    id_salt: impl std::hash::Hash,
+    output: &FarFieldProbeOutput,
+    units: UnitSystem,
+) {
+    let id = ui.id().with(&id_salt);
+    let mut view = ui.data_mut(|data| data.get_temp_mut_or_default::<FarFieldView>(id).clone());
+
+    if let Some(result) = view.job.as_ref().and_then(JobHandle::try_take_result) {
+        view.job = None;
+        match result {
+            Ok(samples) => view.samples = samples.map(Arc::new),
+            Err(error) if error.is::<Cancelled>() => {
+                tracing::debug!("far-field computation cancelled");
+            }
+            Err(error) => ui.handle_error(error),
+        }
+    }
+
+    let (theta, phi) = output.direction();
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "θ = {theta:.1}°, φ = {phi:.1}° of {}, {} samples",
+            output.nf2ff_box(),
+            output.num_samples()
+        ));
+        if ui
+            .add_enabled(view.job.is_none(), egui::Button::new("Compute"))
+            .on_hover_text("Transform the fields sampled so far into the far field.")
+            .clicked()
+        {
+            let output = output.clone();
+            view.job = Some(
+                Jobs::from_ctx(ui.ctx())
+                    .spawn("Compute far field", move |_job| Ok(output.samples())),
+            );
+        }
+        if view.job.is_some() {
+            ui.spinner();
+        }
+    });
+
+    if let Some(samples) = &view.samples {
+        let series = |f: fn(&FarFieldSample) -> f64| {
+            samples
+                .iter()
+                .map(|sample| [sample.frequency, f(sample)])
+                .collect::<Vec<_>>()
+        };
+
+        let response = egui_plot::Plot::new(id.with("gain"))
+            .height(150.0)
+            .x_axis_label(format!("Frequency [{}]", units.frequency_unit()))
+            .y_axis_label("Gain [dBi]")
+            .show(ui, |plot_ui| {
+                plot_ui.line(egui_plot::Line::new(
+                    "Gain",
+                    series(|sample| sample.gain_dbi()),
+                ));
+            })
+            .response;
+        copy_image_or_csv_context_menu(&response, || far_field_samples_to_csv(samples));
+
+        let response = egui_plot::Plot::new(id.with("phase"))
+            .legend(egui_plot::Legend::default())
+            .height(150.0)
+            .x_axis_label(format!("Frequency [{}]", units.frequency_unit()))
+            .y_axis_label("Phase [°]")
+            .show(ui, |plot_ui| {
+                plot_ui.line(egui_plot::Line::new(
+                    "θ",
+                    series(|sample| sample.e_theta.arg().to_degrees()),
+                ));
+                plot_ui.line(egui_plot::Line::new(
+                    "φ",
+                    series(|sample| sample.e_phi.arg().to_degrees()),
+                ));
+            })
+            .response;
+        copy_image_or_csv_context_menu(&response, || far_field_samples_to_csv(samples));
+    }
+    else {
+        ui.label("Nothing computed yet");
+    }
+
+    ui.data_mut(|data| data.insert_temp(id, view));
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::solver::far_field::{
+        FarFieldProbe,
+        direction_angles,
+    };
+
+    #[test]
+    fn frequencies_include_both_ends() {
+        let probe = FarFieldProbe {
+            start_frequency: 1.0,
+            end_frequency: 2.0,
+            num_frequencies: 5,
+            interval: 1,
+        };
+        assert_eq!(probe.frequencies(), vec![1.0, 1.25, 1.5, 1.75, 2.0]);
+    }
+
+    #[test]
+    fn direction_angles_of_axes() {
+        let (theta, _) = direction_angles(&Vector3::z());
+        assert!(theta.abs() < 1e-9, "{theta}");
+
+        let (theta, phi) = direction_angles(&Vector3::y());
+        assert!((theta - 90.0).abs() < 1e-9, "{theta}");
+        assert!((phi - 90.0).abs() < 1e-9, "{phi}");
+
+        let (_, phi) = direction_angles(&-Vector3::y());
+        assert!((phi - 270.0).abs() < 1e-9, "{phi}");
+    }
+}
@@ -0,0 +1,551 @@
+//! Wizards that build common feed structures with a port.
+//!
+//! The geometry is placed around the origin with the scene's Y axis up. The
+//! port's reference impedance is set to the characteristic impedance of the
+//! line, as given by the usual closed-form approximations.
+
+use std::f64::consts::TAU;
+
+use cem_render::material as render_material;
+use cem_scene::{
+    Scene,
+    transform::LocalTransform,
+};
+use cem_solver::material::{
+    Material,
+    MaterialPriority,
+    PhysicalConstants,
+    UnitSystem,
+};
+use nalgebra::{
+    Point3,
+    UnitQuaternion,
+    Vector2,
+    Vector3,
+};
+use palette::WithAlpha;
+use parry3d::shape::{
+    Cuboid,
+    Cylinder,
+};
+
+use crate::{
+    solver::{
+        ground_plane::{
+            GroundPlane,
+            add_ground_plane_with_normal,
+        },
+        port::{
+            Port,
+            add_port,
+        },
+        units::SceneUnits,
+    },
+    util::scene::{
+        EntityBuilderExt,
+        SceneExt,
+    },
+};
+
+/// Characteristic impedance in Ω of a coaxial line.
+pub fn coax_impedance(inner_radius: f64, outer_radius: f64, relative_permittivity: f64) -> f64 {
+    let impedance = PhysicalConstants::SI.vacuum_impedance();
+    impedance / (TAU * relative_permittivity.sqrt()) * (outer_radius / inner_radius).ln()
+}
+
+/// Effective permittivity of a microstrip line (Hammerstad).
+pub fn microstrip_effective_permittivity(
+    width: f64,
+    height: f64,
+    relative_permittivity: f64,
+) -> f64 {
+    let ratio = width / height;
+    let mut effective = (relative_permittivity + 1.0) / 2.0
+        + (relative_permittivity - 1.0) / 2.0 / (1.0 + 12.0 / ratio).sqrt();
+    if ratio < 1.0 {
+        effective += (relative_permittivity - 1.0) / 2.0 * 0.04 * (1.0 - ratio).powi(2);
+    }
+    effective
+}
+
+/// Characteristic impedance in Ω of a microstrip line (Hammerstad), neglecting
+/// the thickness of the strip.
+pub fn microstrip_impedance(width: f64, height: f64, relative_permittivity: f64) -> f64 {
+    let impedance = PhysicalConstants::SI.vacuum_impedance();
+    let ratio = width / height;
+    let effective = microstrip_effective_permittivity(width, height, relative_permittivity);
+
+    if ratio <= 1.0 {
+        impedance / (TAU * effective.sqrt()) * (8.0 / ratio + ratio / 4.0).ln()
+    }
+    else {
+        impedance / (effective.sqrt() * (ratio + 1.393 + 0.667 * (ratio + 1.444).ln()))
+    }
+}
+
+/// Width of a microstrip line with the given characteristic impedance in Ω.
+///
+/// Returns `None` if the impedance can't be reached with a width between 1/100
+/// and 100 times the substrate height.
+pub fn microstrip_width(impedance: f64, height: f64, relative_permittivity: f64) -> Option<f64> {
+    // the impedance decreases monotonically with the width, so we bisect (on a log
+    // scale)
+    let mut low = 0.01 * height;
+    let mut high = 100.0 * height;
+
+    let impedance_at = |width| microstrip_impedance(width, height, relative_permittivity);
+    if impedance > impedance_at(low) || impedance < impedance_at(high) {
+        return None;
+    }
+
+    for _ in 0..60 {
+        let middle = (low * high).sqrt();
+        if impedance_at(middle) > impedance {
+            low = middle;
+        }
+        else {
+            high = middle;
+        }
+    }
+
+    Some((low * high).sqrt())
+}
+
+/// A coaxial line coming up through a ground plane, with the inner conductor
+/// continuing as a probe above it.
+///
+/// The line is shorted at its lower end, and the port sits in a gap between
+/// the short and the inner conductor.
+#[derive(Clone, Copy, Debug)]
+pub struct CoaxFeed {
+    pub inner_radius: f64,
+    pub outer_radius: f64,
+    pub relative_permittivity: f64,
+
+    /// Length of the line below the ground plane
+    pub line_length: f64,
+
+    /// Length of the inner conductor above the ground plane
+    pub probe_length: f64,
+
+    /// Thickness of the ground plane, the shield and the short
+    pub metal_thickness: f64,
+
+    pub ground_half_extents: f64,
+}
+
+impl Default for CoaxFeed {
+    fn default() -> Self {
+        // a PTFE filled 50 Ω line
+        Self {
+            inner_radius: 0.00065,
+            outer_radius: 0.0021,
+            relative_permittivity: 2.1,
+            line_length: 0.01,
+            probe_length: 0.015,
+            metal_thickness: 0.0005,
+            ground_half_extents: 0.03,
+        }
+    }
+}
+
+impl CoaxFeed {
+    pub fn impedance(&self) -> f64 {
+        coax_impedance(
+            self.inner_radius,
+            self.outer_radius,
+            self.relative_permittivity,
+        )
+    }
+
+    /// Adds the feed to the scene. The port's gap is as long as the metal is
+    /// thick.
+    pub fn add_to_scene(&self, scene: &mut Scene, max_frequency: f64) {
+        let t = self.metal_thickness;
+        let bottom = -t - self.line_length;
+
+        // the parts are nested. priorities make sure inner parts win over the ones
+        // they're cut out of.
+        let mut add_cylinder =
+            |name: &str, from: f64, to: f64, radius: f64, material: Material, priority: i32| {
+                let shape = Cylinder::new((0.5 * (to - from)) as f32, radius as f32);
+                let render_material = if material.is_perfect_electric_conductor() {
+                    render_material::Material::from(render_material::presets::COPPER)
+                }
+                else {
+                    render_material::Material::from_albedo(
+                        palette::named::WHITESMOKE.into_format().with_alpha(1.0),
+                    )
+                };
+
+                scene
+                    .add_object(Point3::new(0.0, (0.5 * (from + to)) as f32, 0.0), shape)
+                    .name(name)
+                    .material(render_material)
+                    .insert((material, MaterialPriority(priority)));
+            };
+
+        add_cylinder(
+            "Coax Shield",
+            bottom,
+            -t,
+            self.outer_radius + t,
+            Material::PEC,
+            0,
+        );
+        add_cylinder(
+            "Coax Short",
+            bottom - t,
+            bottom,
+            self.outer_radius + t,
+            Material::PEC,
+            0,
+        );
+        add_cylinder(
+            "Coax Dielectric",
+            bottom,
+            0.0,
+            self.outer_radius,
+            Material {
+                relative_permittivity: self.relative_permittivity,
+                ..Material::VACUUM
+            },
+            1,
+        );
+        add_cylinder(
+            "Coax Inner Conductor",
+            bottom + t,
+            self.probe_length,
+            self.inner_radius,
+            Material::PEC,
+            2,
+        );
+
+        // not a ground plane component, since those would close the aperture
+        let ground = Cuboid::new(Vector3::new(
+            self.ground_half_extents as f32,
+            (0.5 * t) as f32,
+            self.ground_half_extents as f32,
+        ));
+        scene
+            .add_object(Point3::new(0.0, (-0.5 * t) as f32, 0.0), ground)
+            .name("Ground")
+            .material(render_material::presets::BRASS)
+            .insert((Material::PEC, MaterialPriority(0)));
+
+        add_port(
+            scene,
+            "Coax Port",
+            vertical_transform(Point3::new(0.0, (bottom + 0.5 * t) as f32, 0.0)),
+            Port {
+                reference_impedance: self.impedance(),
+                length: t as f32,
+                ..Default::default()
+            },
+            max_frequency,
+        );
+    }
+}
+
+/// A microstrip line on a substrate over a finite ground plane, along the
+/// scene's Z axis. The port is at the -Z end, between ground and strip.
+#[derive(Clone, Copy, Debug)]
+pub struct MicrostripLine {
+    pub width: f64,
+    pub length: f64,
+    pub substrate_height: f64,
+    pub substrate_width: f64,
+    pub relative_permittivity: f64,
+    pub strip_thickness: f64,
+}
+
+impl Default for MicrostripLine {
+    fn default() -> Self {
+        // a 50 Ω line on 1.6 mm FR-4
+        Self {
+            width: 0.003,
+            length: 0.04,
+            substrate_height: 0.0016,
+            substrate_width: 0.03,
+            relative_permittivity: 4.4,
+            strip_thickness: 0.000035,
+        }
+    }
+}
+
+impl MicrostripLine {
+    pub fn impedance(&self) -> f64 {
+        microstrip_impedance(
+            self.width,
+            self.substrate_height,
+            self.relative_permittivity,
+        )
+    }
+
+    pub fn add_to_scene(&self, scene: &mut Scene, max_frequency: f64) {
+        let height = self.substrate_height;
+
+        add_ground_plane_with_normal(
+            scene,
+            GroundPlane::Finite,
+            Point3::origin(),
+            &Vector3::y(),
+            Vector2::new(0.5 * self.substrate_width, 0.5 * self.length).cast(),
+        );
+
+        let substrate = Cuboid::new(
+            Vector3::new(0.5 * self.substrate_width, 0.5 * height, 0.5 * self.length).cast(),
+        );
+        scene
+            .add_object(Point3::new(0.0, (0.5 * height) as f32, 0.0), substrate)
+            .name("Substrate")
+            .material(render_material::Material::from_albedo(
+                palette::named::DARKGREEN.into_format().with_alpha(1.0),
+            ))
+            .insert(Material {
+                relative_permittivity: self.relative_permittivity,
+                ..Material::VACUUM
+            });
+
+        let strip = Cuboid::new(
+            Vector3::new(
+                0.5 * self.width,
+                0.5 * self.strip_thickness,
+                0.5 * self.length,
+            )
+            .cast(),
+        );
+        scene
+            .add_object(
+                Point3::new(0.0, (height + 0.5 * self.strip_thickness) as f32, 0.0),
+                strip,
+            )
+            .name("Microstrip")
+            .material(render_material::presets::COPPER)
+            .insert((Material::PEC, MaterialPriority(1)));
+
+        add_port(
+            scene,
+            "Microstrip Port",
+            vertical_transform(Point3::new(
+                0.0,
+                (0.5 * height) as f32,
+                (-0.5 * self.length) as f32,
+            )),
+            Port {
+                reference_impedance: self.impedance(),
+                length: height as f32,
+                ..Default::default()
+            },
+            max_frequency,
+        );
+    }
+}
+
+/// Ports have their gap along the local Z axis. This turns it upright.
+fn vertical_transform(position: Point3<f32>) -> LocalTransform {
+    LocalTransform::new(
+        position,
+        UnitQuaternion::rotation_between(&Vector3::z(), &Vector3::y()).unwrap(),
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeedWizard {
+    Coax,
+    Microstrip,
+}
+
+impl FeedWizard {
+    pub const ALL: [Self; 2] = [Self::Coax, Self::Microstrip];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Coax => "Coaxial Probe Feed",
+            Self::Microstrip => "Microstrip Line",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FeedWizardWindow {
+    pub open: Option<FeedWizard>,
+    coax: CoaxFeed,
+    microstrip: MicrostripLine,
+
+    /// Highest frequency the port's pulse should excite, in Hz
+    max_frequency: f64,
+
+    /// Impedance to compute the microstrip width for, in Ω
+    target_impedance: f64,
+}
+
+impl Default for FeedWizardWindow {
+    fn default() -> Self {
+        Self {
+            open: None,
+            coax: Default::default(),
+            microstrip: Default::default(),
+            max_frequency: 10e9,
+            target_impedance: 50.0,
+        }
+    }
+}
+
+impl FeedWizardWindow {
+    pub fn show(&mut self, ctx: &egui::Context, scene: &mut Scene) {
+        let Some(wizard) = self.open
+        else {
+            return;
+        };
+
+        let mut open = true;
+        let mut create = false;
+
+        egui::Window::new(wizard.label())
+            .id(egui::Id::new("feed_wizard"))
+            .movable(true)
+            .resizable(false)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                egui::Grid::new("feed_wizard_grid")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        let impedance = match wizard {
+                            FeedWizard::Coax => {
+                                let coax = &mut self.coax;
+                                length_row(ui, "Inner Radius", &mut coax.inner_radius);
+                                length_row(ui, "Outer Radius", &mut coax.outer_radius);
+                                permittivity_row(ui, &mut coax.relative_permittivity);
+                                length_row(ui, "Line Length", &mut coax.line_length);
+                                length_row(ui, "Probe Length", &mut coax.probe_length);
+                                length_row(ui, "Metal Thickness", &mut coax.metal_thickness);
+                                length_row(ui, "Ground Half Size", &mut coax.ground_half_extents);
+                                coax.outer_radius = coax.outer_radius.max(coax.inner_radius);
+                                coax.impedance()
+                            }
+                            FeedWizard::Microstrip => {
+                                let line = &mut self.microstrip;
+                                length_row(ui, "Substrate Height", &mut line.substrate_height);
+                                length_row(ui, "Substrate Width", &mut line.substrate_width);
+                                permittivity_row(ui, &mut line.relative_permittivity);
+                                length_row(ui, "Strip Thickness", &mut line.strip_thickness);
+                                length_row(ui, "Length", &mut line.length);
+                                length_row(ui, "Width", &mut line.width);
+
+                                ui.label("Width for");
+                                ui.horizontal(|ui| {
+                                    ui.add(
+                                        egui::DragValue::new(&mut self.target_impedance)
+                                            .range(1.0..=500.0)
+                                            .speed(0.1)
+                                            .suffix(" Ω"),
+                                    );
+                                    let width = microstrip_width(
+                                        self.target_impedance,
+                                        line.substrate_height,
+                                        line.relative_permittivity,
+                                    );
+                                    if ui
+                                        .add_enabled(width.is_some(), egui::Button::new("Apply"))
+                                        .clicked()
+                                    {
+                                        line.width = width.unwrap();
+                                    }
+                                });
+                                ui.end_row();
+
+                                line.impedance()
+                            }
+                        };
+
+                        ui.label("Max. Frequency");
+                        let mut giga_hertz = self.max_frequency * 1e-9;
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut giga_hertz)
+                                    .range(1e-3..=1e6)
+                                    .speed(0.01)
+                                    .suffix(" GHz"),
+                            )
+                            .changed()
+                        {
+                            self.max_frequency = giga_hertz * 1e9;
+                        }
+                        ui.end_row();
+
+                        ui.label("Impedance");
+                        ui.label(format!("{impedance:.1} Ω"))
+                            .on_hover_text("Used as the port's reference impedance");
+                        ui.end_row();
+                    });
+
+                ui.separator();
+                create = ui.button("Create").clicked();
+            });
+
+        if create {
+            // the wizard takes the frequency in Hz, but the scene may use other units
+            let units = SceneUnits::get(&scene.world);
+            let max_frequency = self.max_frequency / UnitSystem::Si.conversion_to(&units).time;
+
+            match wizard {
+                FeedWizard::Coax => self.coax.add_to_scene(scene, max_frequency),
+                FeedWizard::Microstrip => self.microstrip.add_to_scene(scene, max_frequency),
+            }
+            open = false;
+        }
+
+        if !open {
+            self.open = None;
+        }
+    }
+}
+
+fn length_row(ui: &mut egui::Ui, label: &str, value: &mut f64) {
+    ui.label(label);
+    let speed = *value * 0.01;
+    ui.add(
+        egui::DragValue::new(value)
+            .range(1e-9..=f64::INFINITY)
+            .speed(speed)
+            .max_decimals(6)
+            .suffix(" m"),
+    );
+    ui.end_row();
+}
+
+fn permittivity_row(ui: &mut egui::Ui, value: &mut f64) {
+    ui.label("eps_r");
+    ui.add(
+        egui::DragValue::new(value)
+            .range(1.0..=f64::INFINITY)
+            .speed(0.01),
+    );
+    ui.end_row();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::feed_wizard::{
+        coax_impedance,
+        microstrip_impedance,
+        microstrip_width,
+    };
+
+    #[test]
+    fn coax_impedance_of_rg58() {
+        // RG-58: 0.81 mm / 2.95 mm diameters, solid PE
+        let impedance = coax_impedance(0.405e-3, 1.475e-3, 2.25);
+        assert!((impedance - 51.7).abs() < 0.5, "{impedance}");
+    }
+
+    #[test]
+    fn microstrip_width_inverts_impedance() {
+        let width = microstrip_width(50.0, 1.6e-3, 4.4).unwrap();
+
+        // about 3 mm on 1.6 mm FR-4
+        assert!((width - 3.06e-3).abs() < 0.1e-3, "{width}");
+        assert!((microstrip_impedance(width, 1.6e-3, 4.4) - 50.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,294 @@
+//! Ground planes.
+//!
+//! A ground plane is a perfectly conducting sheet in the local XY plane of its
+//! entity, with the structure on the +Z side. A [`GroundPlane::Finite`] plane
+//! is a sheet with the shape of the entity's collider (e.g. a
+//! [`Quad`](crate::composer::shape::flat::Quad)). A [`GroundPlane::Infinite`]
+//! plane models perfect ground by image theory: everything on the -Z side is
+//! filled with PEC, and the solver volume ends at the plane. This is what NEC
+//! calls a ground plane (the flag on the `GE` card, with a `GN 1` card).
+
+use bevy_ecs::{
+    component::Component,
+    reflect::ReflectComponent,
+    system::{
+        In,
+        Query,
+    },
+    world::World,
+};
+use bevy_reflect::{
+    Reflect,
+    ReflectSerialize,
+    prelude::ReflectDefault,
+};
+use cem_probe::PropertiesUi;
+use cem_render::material::presets::BRASS;
+use cem_scene::{
+    Scene,
+    probe::{
+        ComponentName,
+        ReflectComponentUi,
+    },
+    spatial::Collider,
+    transform::{
+        GlobalTransform,
+        LocalTransform,
+    },
+};
+use cem_solver::{
+    fdtd::Resolution,
+    material::Material,
+};
+use nalgebra::{
+    Isometry3,
+    Point3,
+    UnitQuaternion,
+    Vector2,
+    Vector3,
+};
+use parry3d::{
+    bounding_volume::Aabb,
+    query::Ray,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    composer::shape::flat::{
+        Plane,
+        Quad,
+    },
+    solver::runner::CoordinateTransformations,
+    util::scene::{
+        EntityBuilderExt,
+        SceneExt,
+    },
+};
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Component, Reflect,
+)]
+#[reflect(Component, ComponentUi, @ComponentName::new("Ground Plane"), Default, Serialize)]
+pub enum GroundPlane {
+    /// A PEC sheet with the shape of the entity's collider.
+    #[default]
+    Finite,
+
+    /// Perfect ground extending infinitely.
+    Infinite,
+}
+
+impl GroundPlane {
+    pub const ALL: [Self; 2] = [Self::Finite, Self::Infinite];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Finite => "Finite",
+            Self::Infinite => "Infinite",
+        }
+    }
+
+    /// Whether the cell at `point` (in world space) is part of the ground
+    /// plane.
+    ///
+    /// The sheet is rasterized one cell thick. For infinite planes all cells
+    /// below the plane are part of the ground too.
+    fn contains_cell(
+        &self,
+        collider: &Collider,
+        transform: &Isometry3<f64>,
+        point: &Point3<f64>,
+        half_cell_thickness: f32,
+    ) -> bool {
+        // rounded in the plane's frame, see `GlobalTransform::precise_point_to_local`
+        let local_point = transform.inverse_transform_point(point).cast::<f32>();
+        match self {
+            Self::Finite => {
+                if local_point.z.abs() > half_cell_thickness {
+                    return false;
+                }
+                // project the cell onto the sheet to check if it's inside its outline.
+                let ray = Ray::new(local_point, -local_point.z.signum() * Vector3::z());
+                collider
+                    .cast_ray(&Isometry3::identity(), &ray, half_cell_thickness, true)
+                    .is_some()
+            }
+            Self::Infinite => local_point.z <= half_cell_thickness,
+        }
+    }
+}
+
+impl PropertiesUi for GroundPlane {
+    type Config = ();
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
+        let _ = config;
+        let mut changed = false;
+
+        let mut response = egui::ComboBox::from_id_salt(ui.id().with("ground_plane"))
+            .selected_text(self.label())
+            .show_ui(ui, |ui| {
+                for ground_plane in Self::ALL {
+                    changed |= ui
+                        .selectable_value(self, ground_plane, ground_plane.label())
+                        .changed();
+                }
+            })
+            .response
+            .on_hover_text("Infinite ground planes fill everything below them with PEC.");
+
+        if changed {
+            response.mark_changed();
+        }
+        response
+    }
+}
+
+/// Adds a ground plane at the origin, with the scene's Y axis as normal.
+pub fn add_ground_plane(scene: &mut Scene, ground_plane: GroundPlane, half_extents: Vector2<f32>) {
+    add_ground_plane_with_normal(
+        scene,
+        ground_plane,
+        Point3::origin(),
+        &Vector3::y(),
+        half_extents,
+    );
+}
+
+/// Adds a ground plane through `origin` with the structure on the side `normal`
+/// points to.
+///
+/// `half_extents` is only used for finite ground planes.
+pub fn add_ground_plane_with_normal(
+    scene: &mut Scene,
+    ground_plane: GroundPlane,
+    origin: Point3<f32>,
+    normal: &Vector3<f32>,
+    half_extents: Vector2<f32>,
+) {
+    let rotation = UnitQuaternion::rotation_between(&Vector3::z(), normal).unwrap_or_else(|| {
+        UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f32::consts::PI)
+    });
+    let transform = LocalTransform::new(origin, rotation);
+
+    let entity = match ground_plane {
+        GroundPlane::Finite => scene.add_object(transform, Quad::new(half_extents)),
+        GroundPlane::Infinite => scene.add_object(transform, Plane),
+    };
+    entity
+        .name(format!("Ground Plane ({})", ground_plane.label()))
+        .material(BRASS)
+        .insert(ground_plane);
+}
+
+/// Ground planes resolved for rasterizing the scene into the solver lattice.
+#[derive(Clone, Debug, Default)]
+pub struct GroundPlanes {
+    ground_planes: Vec<(GroundPlane, Collider, Isometry3<f64>, f32)>,
+}
+
+impl GroundPlanes {
+    pub fn from_world(
+        world: &mut World,
+        resolution: &Resolution,
+        coordinate_transformations: &CoordinateTransformations,
+    ) -> Self {
+        let rotation_to_solver = coordinate_transformations
+            .rotation_from_solver_to_world
+            .inverse()
+            .cast::<f32>();
+        let spatial_resolution = resolution.spatial.cast::<f32>();
+
+        world
+            .run_system_cached_with(
+                |In((rotation_to_solver, spatial_resolution)): In<(
+                    UnitQuaternion<f32>,
+                    Vector3<f32>,
+                )>,
+                 ground_planes: Query<(&GroundPlane, &Collider, &GlobalTransform)>| {
+                    let ground_planes = ground_planes
+                        .iter()
+                        .map(|(ground_plane, collider, transform)| {
+                            // half the size of a cell along the plane's normal
+                            let normal = rotation_to_solver * (transform.isometry() * Vector3::z());
+                            let half_cell_thickness = 0.5 * normal.abs().dot(&spatial_resolution);
+
+                            (
+                                *ground_plane,
+                                collider.clone(),
+                                *transform.precise_isometry(),
+                                half_cell_thickness,
+                            )
+                        })
+                        .collect();
+                    Self { ground_planes }
+                },
+                (rotation_to_solver, spatial_resolution),
+            )
+            .unwrap()
+    }
+
+    /// The material of the cell at `point`, if it's part of a ground plane.
+    pub fn material(&self, point: &Point3<f64>) -> Option<Material> {
+        self.ground_planes
+            .iter()
+            .any(|(ground_plane, collider, transform, half_cell_thickness)| {
+                ground_plane.contains_cell(collider, transform, point, *half_cell_thickness)
+            })
+            .then_some(Material::PEC)
+    }
+}
+
+/// Makes the solver volume end at infinite ground planes.
+///
+/// The scene AABB doesn't include infinite shapes, so without this the volume
+/// might not reach down to the ground, or waste cells below it. `aabb` is in
+/// the frame of the solver volume, which is rotated by `rotation`. Only ground
+/// planes parallel to a face of the volume are considered.
+pub fn fit_volume_to_ground_planes(
+    world: &mut World,
+    aabb: &Aabb,
+    rotation: &UnitQuaternion<f32>,
+) -> Aabb {
+    world
+        .run_system_cached_with(
+            |In((mut aabb, rotation)): In<(Aabb, UnitQuaternion<f32>)>,
+             ground_planes: Query<(&GroundPlane, &GlobalTransform)>| {
+                let to_volume = rotation.inverse();
+
+                for (ground_plane, transform) in &ground_planes {
+                    if *ground_plane != GroundPlane::Infinite {
+                        continue;
+                    }
+
+                    let origin = to_volume * transform.position();
+                    let normal = to_volume * (transform.isometry() * Vector3::z());
+                    let axis = normal.iamax();
+                    if normal[axis].abs() < 1.0 - 1e-4 {
+                        tracing::warn!(
+                            ?normal,
+                            "infinite ground plane is not aligned with the solver volume"
+                        );
+                        continue;
+                    }
+
+                    let plane = origin[axis];
+                    if normal[axis] > 0.0 {
+                        aabb.mins[axis] = plane;
+                        aabb.maxs[axis] = aabb.maxs[axis].max(plane);
+                    }
+                    else {
+                        aabb.maxs[axis] = plane;
+                        aabb.mins[axis] = aabb.mins[axis].min(plane);
+                    }
+                }
+
+                aabb
+            },
+            (*aabb, *rotation),
+        )
+        .unwrap()
+}
@@ -0,0 +1,238 @@
+//! Hashes that tag a solver run with what it simulated.
+//!
+//! When a solver is launched, the solver config and the simulation-relevant
+//! geometry (everything that is rasterized into the lattice: objects with a
+//! material, PMLs and ground planes) are hashed. The hashes are stored with
+//! the run's results (see [`crate::solver::library`]). When results are shown,
+//! the hashes are compared with the current scene, and the results are marked
+//! as stale if they don't match anymore.
+//!
+//! The hashes are stable: they don't depend on entity IDs or the order of
+//! entities, so they stay the same when a project is saved and loaded again.
+
+use std::{
+    fmt::Write,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use bevy_ecs::world::World;
+use cem_scene::{
+    spatial::Collider,
+    transform::GlobalTransform,
+};
+use cem_solver::fdtd::pml::GradedPml;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    Error,
+    solver::{
+        config::SolverConfig,
+        ground_plane::GroundPlane,
+        runner::MaterialQueryData,
+    },
+};
+
+/// How often the hashes of the current scene are recomputed to check for
+/// stale results.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Hashes of what a run simulated, as hex strings.
+///
+/// Runs recorded before geometry hashes were introduced have an empty
+/// `geometry_hash`, which is never considered stale.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunHashes {
+    /// See [`config_hash`]
+    pub config_hash: String,
+
+    /// See [`geometry_hash`]
+    #[serde(default)]
+    pub geometry_hash: String,
+}
+
+impl RunHashes {
+    pub fn new(config: &SolverConfig, world: &mut World) -> Result<Self, Error> {
+        Ok(Self {
+            config_hash: config_hash(config)?,
+            geometry_hash: geometry_hash(world),
+        })
+    }
+}
+
+/// Hash of a solver config, to tell which runs used the same config.
+///
+/// This is the 64 bit FNV-1a hash of the config's JSON, so it's stable across
+/// builds.
+pub fn config_hash(config: &SolverConfig) -> Result<String, Error> {
+    let mut hasher = Fnv1a::default();
+    hasher.write_bytes(&serde_json::to_vec(config)?);
+    Ok(format!("{:016x}", hasher.0))
+}
+
+/// Hash of the simulation-relevant geometry in the world.
+///
+/// Every entity is hashed on its own, and the hashes are summed, so the order
+/// of entities doesn't matter.
+pub fn geometry_hash(world: &mut World) -> String {
+    fn hash_entity(item: impl std::fmt::Debug) -> u64 {
+        // the debug representation includes the shape's parameters, and floats are
+        // printed so that they round-trip.
+        let mut hasher = Fnv1a::default();
+        write!(hasher, "{item:?}").expect("hashing never fails");
+        hasher.0
+    }
+
+    let mut hash = 0u64;
+
+    let mut materials = world.query::<MaterialQueryData>();
+    for item in materials.iter(world) {
+        hash = hash.wrapping_add(hash_entity(item));
+    }
+
+    let mut pmls = world.query::<(&GradedPml, &Collider, &GlobalTransform)>();
+    for item in pmls.iter(world) {
+        hash = hash.wrapping_add(hash_entity(item));
+    }
+
+    let mut ground_planes = world.query::<(&GroundPlane, &Collider, &GlobalTransform)>();
+    for item in ground_planes.iter(world) {
+        hash = hash.wrapping_add(hash_entity(item));
+    }
+
+    format!("{hash:016x}")
+}
+
+/// Hashes of the current scene, to check results against.
+///
+/// Computing the geometry hash visits every object, so it's only recomputed
+/// every [`RECHECK_INTERVAL`].
+#[derive(Debug, Default)]
+pub struct CurrentHashes {
+    updated_at: Option<Instant>,
+    geometry_hash: String,
+
+    /// Hashes of the solver configs by label
+    config_hashes: Vec<(String, String)>,
+}
+
+impl CurrentHashes {
+    pub fn update(&mut self, world: &mut World, solver_configs: &[SolverConfig]) {
+        if self
+            .updated_at
+            .is_some_and(|updated_at| updated_at.elapsed() < RECHECK_INTERVAL)
+        {
+            return;
+        }
+
+        self.updated_at = Some(Instant::now());
+        self.geometry_hash = geometry_hash(world);
+        self.config_hashes = solver_configs
+            .iter()
+            .filter_map(|config| Some((config.label.clone(), config_hash(config).ok()?)))
+            .collect();
+    }
+
+    /// Why the results of a run of the solver config `label` are stale, if they
+    /// are.
+    pub fn stale_reason(&self, label: &str, hashes: &RunHashes) -> Option<&'static str> {
+        self.updated_at?;
+
+        let config_changed = !hashes.config_hash.is_empty()
+            && !self
+                .config_hashes
+                .iter()
+                .any(|(other_label, hash)| other_label == label && *hash == hashes.config_hash);
+        let geometry_changed =
+            !hashes.geometry_hash.is_empty() && hashes.geometry_hash != self.geometry_hash;
+
+        match (config_changed, geometry_changed) {
+            (false, false) => None,
+            (true, false) => Some("the solver config changed"),
+            (false, true) => Some("the geometry changed"),
+            (true, true) => Some("the solver config and geometry changed"),
+        }
+    }
+}
+
+/// Shows a warning that results are stale.
+pub fn stale_results_label(ui: &mut egui::Ui, reason: &str) -> egui::Response {
+    ui.colored_label(ui.visuals().warn_fg_color, "⚠ Stale results")
+        .on_hover_text(format!(
+            "These results don't match the current scene anymore: {reason}."
+        ))
+}
+
+/// 64 bit FNV-1a hash.
+#[derive(Clone, Copy, Debug)]
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Fnv1a {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+impl Write for Fnv1a {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.write_bytes(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::hashes::{
+        CurrentHashes,
+        Fnv1a,
+        RunHashes,
+    };
+
+    #[test]
+    fn fnv1a_matches_reference() {
+        let mut hasher = Fnv1a::default();
+        hasher.write_bytes(b"a");
+        assert_eq!(hasher.0, 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn stale_when_hashes_differ() {
+        let current = CurrentHashes {
+            updated_at: Some(std::time::Instant::now()),
+            geometry_hash: "g1".to_owned(),
+            config_hashes: vec![("GPU".to_owned(), "c1".to_owned())],
+        };
+        let hashes = |config: &str, geometry: &str| {
+            RunHashes {
+                config_hash: config.to_owned(),
+                geometry_hash: geometry.to_owned(),
+            }
+        };
+
+        assert_eq!(current.stale_reason("GPU", &hashes("c1", "g1")), None);
+        assert_eq!(
+            current.stale_reason("GPU", &hashes("c1", "g2")),
+            Some("the geometry changed")
+        );
+        assert_eq!(
+            current.stale_reason("CPU", &hashes("c1", "g1")),
+            Some("the solver config changed")
+        );
+
+        // recorded without a geometry hash
+        assert_eq!(current.stale_reason("GPU", &hashes("c1", "")), None);
+    }
+}
@@ -0,0 +1,230 @@
+//! Recent frames of the observers, for instant replay.
+//!
+//! Observers with a [`Observer::history_budget`] keep their latest images in a
+//! ring buffer while the solver runs. Scrubbing through it in the solver window
+//! pauses the visualization at the frame's time (see [`SimulationClock`]) and
+//! shows the frame on the observer plane, without recording the whole run.
+//!
+//! Only backends that project into host memory keep a history so far. The
+//! wgpu backend renders into the texture directly, and we'd need to copy its
+//! frames on the GPU.
+//!
+//! [`Observer::history_budget`]: crate::solver::observer::Observer::history_budget
+
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+};
+
+use cem_render::texture::channel::ImageSender;
+use parking_lot::Mutex;
+
+use crate::solver::clock::SimulationClock;
+
+/// History budget that's suggested when it's enabled, in bytes.
+pub const DEFAULT_HISTORY_BUDGET: usize = 64 << 20;
+
+/// Handle to the history of a running observer.
+#[derive(Clone, Debug)]
+pub struct ObserverHistory {
+    inner: Arc<Mutex<HistoryInner>>,
+}
+
+#[derive(Debug)]
+struct HistoryInner {
+    label: String,
+
+    /// Memory the frames may use, in bytes.
+    budget: usize,
+
+    frames: VecDeque<HistoryFrame>,
+
+    /// Tick and time of the observation that's being projected.
+    observed: (usize, f64),
+
+    /// Writes replayed frames onto the observer plane. Backends that don't
+    /// project into host memory don't attach one.
+    image_sender: Option<ImageSender>,
+
+    /// Index of the frame that's replayed.
+    replaying: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+struct HistoryFrame {
+    tick: usize,
+    time: f64,
+    image: Arc<image::RgbaImage>,
+}
+
+impl ObserverHistory {
+    pub fn new(label: impl Into<String>, budget: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(HistoryInner {
+                label: label.into(),
+                budget,
+                frames: VecDeque::new(),
+                observed: (0, 0.0),
+                image_sender: None,
+                replaying: None,
+            })),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        self.inner.lock().label.clone()
+    }
+
+    /// Called by the backend's projection, so replayed frames can be shown.
+    pub fn attach(&self, image_sender: ImageSender) {
+        self.inner.lock().image_sender = Some(image_sender);
+    }
+
+    /// Whether the observer's backend supports replaying frames.
+    pub fn is_supported(&self) -> bool {
+        self.inner.lock().image_sender.is_some()
+    }
+
+    /// Sets the tick and time that the next recorded frame is from.
+    pub fn begin_observation(&self, tick: usize, time: f64) {
+        self.inner.lock().observed = (tick, time);
+    }
+
+    /// Adds a frame, dropping the oldest ones that don't fit into the budget.
+    pub fn record(&self, image: &image::RgbaImage) {
+        let mut inner = self.inner.lock();
+
+        let max_frames = max_frames(inner.budget, image.as_raw().len());
+        if max_frames == 0 {
+            return;
+        }
+        while inner.frames.len() >= max_frames {
+            inner.frames.pop_front();
+        }
+
+        let (tick, time) = inner.observed;
+        inner.frames.push_back(HistoryFrame {
+            tick,
+            time,
+            image: Arc::new(image.clone()),
+        });
+
+        // a live frame replaces the one that was replayed
+        inner.replaying = None;
+    }
+
+    /// Ticks and times of the recorded frames, oldest first.
+    pub fn frames(&self) -> Vec<(usize, f64)> {
+        self.inner
+            .lock()
+            .frames
+            .iter()
+            .map(|frame| (frame.tick, frame.time))
+            .collect()
+    }
+
+    /// Index of the frame that's replayed, if any.
+    pub fn replaying(&self) -> Option<usize> {
+        self.inner.lock().replaying
+    }
+
+    /// Shows the frame at `index` on the observer plane, and returns its time.
+    pub fn replay(&self, index: usize) -> Option<f64> {
+        let mut inner = self.inner.lock();
+        let frame = inner.frames.get(index)?.clone();
+        let image_sender = inner.image_sender.as_mut()?;
+        image_sender
+            .update_image()
+            .copy_from_slice(frame.image.as_raw());
+        inner.replaying = Some(index);
+        Some(frame.time)
+    }
+
+    /// Shows the latest frame again.
+    pub fn stop_replay(&self) {
+        let latest = self.inner.lock().frames.len().checked_sub(1);
+        if let Some(latest) = latest {
+            self.replay(latest);
+        }
+        self.inner.lock().replaying = None;
+    }
+}
+
+/// Number of frames of `frame_size` bytes that fit into `budget`.
+fn max_frames(budget: usize, frame_size: usize) -> usize {
+    budget.checked_div(frame_size).unwrap_or_default()
+}
+
+/// Slider to scrub through an observer's history.
+///
+/// Scrubbing pauses the visualization at the time of the replayed frame, and
+/// going back to live resumes it.
+pub fn observer_history_ui(ui: &mut egui::Ui, history: &ObserverHistory, clock: &SimulationClock) {
+    ui.horizontal(|ui| {
+        ui.label(history.label());
+
+        if !history.is_supported() {
+            ui.weak("No history with this backend");
+            return;
+        }
+        let frames = history.frames();
+        let Some(latest) = frames.len().checked_sub(1)
+        else {
+            ui.weak("No frames yet");
+            return;
+        };
+
+        let replaying = history.replaying();
+        let mut index = replaying.unwrap_or(latest);
+        if ui
+            .add(egui::Slider::new(&mut index, 0..=latest).show_value(false))
+            .changed()
+            && let Some(time) = history.replay(index)
+        {
+            clock.pause_visualization_at(time);
+        }
+
+        let (tick, time) = frames[index];
+        ui.label(format!("Tick {tick} (t = {time:.3})"));
+
+        if ui
+            .add_enabled(replaying.is_some(), egui::Button::new("Live"))
+            .clicked()
+        {
+            history.stop_replay();
+            clock.set_visualization_paused(false);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::history::{
+        ObserverHistory,
+        max_frames,
+    };
+
+    #[test]
+    fn budget_limits_frames() {
+        assert_eq!(max_frames(1000, 64), 15);
+        assert_eq!(max_frames(10, 64), 0);
+        assert_eq!(max_frames(10, 0), 0);
+    }
+
+    #[test]
+    fn oldest_frames_are_dropped() {
+        let image = image::RgbaImage::new(4, 4);
+        let history = ObserverHistory::new("observer", 3 * image.as_raw().len());
+
+        for tick in 0..5 {
+            history.begin_observation(tick, tick as f64 * 0.5);
+            history.record(&image);
+        }
+
+        assert_eq!(history.frames(), vec![(2, 1.0), (3, 1.5), (4, 2.0)]);
+
+        // without an image sender frames can't be replayed
+        assert!(!history.is_supported());
+        assert_eq!(history.replay(0), None);
+    }
+}
@@ -0,0 +1,228 @@
+//! Shows which material the solver would assign to the cell under the mouse
+//! pointer.
+//!
+//! This uses the same lookup as the solver (see [`materials_at`]), so it helps
+//! to find out which of several overlapping objects wins, and why.
+
+use bevy_ecs::{
+    entity::Entity,
+    name::NameOrEntity,
+    system::{
+        In,
+        Query,
+    },
+};
+use cem_scene::{
+    Scene,
+    spatial::queries::PointQuery,
+};
+use cem_solver::{
+    fdtd::FdtdSolverConfig,
+    material::{
+        Material,
+        MaterialPriority,
+        UnitSystem,
+    },
+};
+use nalgebra::Point3;
+
+use crate::solver::{
+    config::{
+        MeshGrading,
+        SolverConfig,
+        SolverConfigSpecifics,
+    },
+    parameters::{
+        ParameterValues,
+        SceneParameters,
+    },
+    runner::{
+        CoordinateTransformations,
+        MaterialQueryData,
+        materials_at,
+    },
+    units::SceneUnits,
+};
+
+/// An object with a material at an inspected cell.
+#[derive(Clone, Debug)]
+pub struct Contributor {
+    pub label: String,
+    pub material: Material,
+    pub priority: MaterialPriority,
+    pub bounding_volume: f32,
+}
+
+/// Material of a single cell of the solver lattice.
+#[derive(Clone, Debug)]
+pub struct CellMaterial {
+    pub cell: Point3<usize>,
+
+    /// Center of the cell in world space
+    pub position: Point3<f64>,
+
+    /// The material the solver will use
+    pub material: Material,
+
+    /// All objects with a material at this cell, in order of precedence. The
+    /// first one contributes the material. If this is empty, the solver's
+    /// default material is used.
+    pub contributors: Vec<Contributor>,
+
+    /// The solver uses a graded mesh, but we only show the base grid.
+    pub graded: bool,
+}
+
+impl CellMaterial {
+    /// Looks up the cell containing `point` in the lattice the FDTD solver
+    /// would create for `solver_config`.
+    ///
+    /// Returns `None` if `point` is outside the solver volume, or the solver
+    /// isn't an FDTD solver.
+    pub fn at_point(
+        scene: &mut Scene,
+        solver_config: &SolverConfig,
+        point: &Point3<f32>,
+    ) -> Option<Self> {
+        let SolverConfigSpecifics::Fdtd(fdtd_config) = &solver_config.specifics
+        else {
+            return None;
+        };
+
+        let aabb = solver_config.common.volume.aabb(scene);
+        let lattice_size = FdtdSolverConfig {
+            resolution: fdtd_config.resolution,
+            physical_constants: SceneUnits::get(&scene.world).physical_constants(),
+            size: aabb.extents().cast(),
+            spatial_order: fdtd_config.spatial_order,
+        }
+        .size();
+
+        // todo: the solver uses the finest cell size of a graded mesh everywhere. we
+        // don't want to generate the mesh on every frame, so we show the base grid.
+        let coordinate_transformations = CoordinateTransformations::for_fdtd(
+            &fdtd_config.resolution,
+            &lattice_size,
+            &solver_config.common.volume.rotation(),
+            &aabb,
+        );
+
+        let cell =
+            coordinate_transformations.transform_point_from_world_to_solver(&point.cast())?;
+        let position = coordinate_transformations.transform_point_from_solver_to_world(&cell);
+
+        let parameters =
+            SceneParameters::for_run(&scene.world, &solver_config.common.parameter_overrides);
+        let contributors = scene
+            .world
+            .run_system_cached_with(cell_materials_system, (position, parameters))
+            .unwrap();
+
+        Some(Self {
+            cell,
+            position,
+            material: contributors
+                .first()
+                .map_or(solver_config.common.default_material, |contributor| {
+                    contributor.material
+                }),
+            contributors,
+            graded: matches!(fdtd_config.mesh, MeshGrading::Graded { .. }),
+        })
+    }
+
+    pub fn show(&self, ui: &mut egui::Ui) {
+        let units = UnitSystem::display_units(ui.ctx());
+
+        ui.label(format!(
+            "Cell ({}, {}, {}) at ({:.3}, {:.3}, {:.3})",
+            self.cell.x,
+            self.cell.y,
+            self.cell.z,
+            self.position.x,
+            self.position.y,
+            self.position.z,
+        ));
+        if self.graded {
+            ui.weak("Graded mesh: showing the base grid");
+        }
+
+        ui.separator();
+
+        ui.label(format!(
+            "eps_r = {}, sigma = {} {}",
+            self.material.relative_permittivity,
+            self.material.eletrical_conductivity,
+            units.electrical_conductivity_unit()
+        ));
+        ui.label(format!(
+            "mu_r = {}, sigma_m = {} {}",
+            self.material.relative_permeability,
+            self.material.magnetic_conductivity,
+            units.magnetic_conductivity_unit()
+        ));
+
+        ui.separator();
+
+        match self.contributors.as_slice() {
+            [] => {
+                ui.label("Default material");
+            }
+            [contributor] => {
+                ui.label(format!("From: {}", contributor.label));
+            }
+            [winner, overridden @ ..] => {
+                ui.label(format!(
+                    "From: {} (priority {})",
+                    winner.label, winner.priority.0
+                ));
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("Overlaps {} other object(s):", overridden.len()),
+                );
+                for contributor in overridden {
+                    let reason = if contributor.priority != winner.priority {
+                        "lower priority"
+                    }
+                    else if contributor.bounding_volume != winner.bounding_volume {
+                        "larger"
+                    }
+                    else {
+                        "later entity"
+                    };
+                    ui.weak(format!(
+                        "{} ({reason}): eps_r = {}, mu_r = {}",
+                        contributor.label,
+                        contributor.material.relative_permittivity,
+                        contributor.material.relative_permeability
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn cell_materials_system(
+    In((position, parameters)): In<(Point3<f64>, ParameterValues)>,
+    point_query: PointQuery,
+    materials: Query<MaterialQueryData>,
+    names: Query<NameOrEntity>,
+) -> Vec<Contributor> {
+    materials_at(&point_query, &materials, &parameters, position)
+        .into_iter()
+        .map(|overlapping| {
+            Contributor {
+                label: entity_label(&names, overlapping.entity),
+                material: overlapping.material,
+                priority: overlapping.priority,
+                bounding_volume: overlapping.bounding_volume,
+            }
+        })
+        .collect()
+}
+
+fn entity_label(names: &Query<NameOrEntity>, entity: Entity) -> String {
+    names
+        .get(entity)
+        .map_or_else(|_| entity.to_string(), |name| name.to_string())
+}
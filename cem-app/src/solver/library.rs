@@ -0,0 +1,551 @@
+//! Library of past solver runs of a project.
+//!
+//! Runs of a saved project are stored next to the project file, in a directory
+//! with the extension `.runs` (see [`library_directory`]). Every run gets its
+//! own directory with the exported results (see [`crate::solver::export`]),
+//! the image of the viewport, which is used as thumbnail, and a [`RunRecord`]
+//! with the run's metadata.
+//!
+//! A run is saved when it finishes, or when the user saves it from the solver
+//! window. Headless runs write a record next to the results they export, so
+//! they can be imported into the library later (see [`crate::solver::batch`]).
+//! The [`ResultsLibraryWindow`] lists the runs, and loads their port
+//! reflections and patterns into the [`MeasurementsWindow`] to compare them.
+
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use bevy_ecs::world::World;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    Error,
+    error::{
+        ErrorHandler,
+        ResultExt,
+    },
+    jobs::{
+        Cancelled,
+        JobContext,
+        JobHandle,
+        Jobs,
+    },
+    solver::{
+        config::SolverConfig,
+        export::{
+            VIEWPORT_IMAGE_FILE_NAME,
+            export_results,
+            run_metadata,
+            safe_file_name,
+        },
+        hashes::{
+            CurrentHashes,
+            RunHashes,
+            stale_results_label,
+        },
+        measured::{
+            Dataset,
+            MeasurementsWindow,
+        },
+        probe::ProbeOutputs,
+        runner::SolverState,
+    },
+};
+
+/// File name of the [`RunRecord`] in a run's directory.
+pub const RUN_RECORD_FILE_NAME: &str = "run.json";
+
+/// Size of the thumbnails in the library window.
+const THUMBNAIL_SIZE: egui::Vec2 = egui::vec2(96.0, 64.0);
+
+/// Where the runs of the project at `project_path` are stored.
+pub fn library_directory(project_path: &Path) -> PathBuf {
+    project_path.with_extension("runs")
+}
+
+/// Metadata of a run in the library.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Label of the solver config
+    pub label: String,
+
+    /// When the run was saved, as `YYYY-MM-DD HH:MM:SS` in local time.
+    pub date: String,
+
+    /// What the run simulated
+    #[serde(flatten)]
+    pub hashes: RunHashes,
+
+    pub finished: bool,
+
+    /// Labels and values, e.g. the simulated time.
+    pub metrics: Vec<(String, String)>,
+}
+
+impl RunRecord {
+    /// Record of a run that is saved now.
+    pub fn new(
+        label: impl Into<String>,
+        hashes: RunHashes,
+        state: &SolverState,
+        probe_outputs: &ProbeOutputs,
+    ) -> Self {
+        Self {
+            label: label.into(),
+            date: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            hashes,
+            finished: state.finished,
+            metrics: run_metadata(state, probe_outputs)
+                .into_iter()
+                // the date is recorded already
+                .filter(|(label, _)| *label != "Exported")
+                .map(|(label, value)| (label.to_owned(), value))
+                .collect(),
+        }
+    }
+
+    /// Name of the run's directory in a library, e.g.
+    /// `2025-03-01_12-00-00_GPU`.
+    pub fn directory_name(&self) -> String {
+        format!(
+            "{}_{}",
+            self.date.replace(' ', "_").replace(':', "-"),
+            safe_file_name(&self.label)
+        )
+    }
+
+    /// Writes the record into the directory the run's results were exported
+    /// to.
+    pub fn write(&self, directory: &Path) -> Result<(), Error> {
+        std::fs::write(
+            directory.join(RUN_RECORD_FILE_NAME),
+            serde_json::to_vec_pretty(self)?,
+        )?;
+        Ok(())
+    }
+
+    /// Whether all words of `query` appear in the label, date, hashes or
+    /// metrics. Case is ignored.
+    pub fn matches(&self, query: &str) -> bool {
+        let haystack = [
+            self.label.as_str(),
+            self.date.as_str(),
+            self.hashes.config_hash.as_str(),
+            self.hashes.geometry_hash.as_str(),
+        ]
+        .into_iter()
+        .chain(
+            self.metrics
+                .iter()
+                .flat_map(|(label, value)| [label.as_str(), value.as_str()]),
+        )
+        .collect::<Vec<_>>()
+        .join("\n")
+        .to_lowercase();
+
+        query
+            .split_whitespace()
+            .all(|word| haystack.contains(&word.to_lowercase()))
+    }
+}
+
+/// A run that is saved to the library when it finishes.
+#[derive(Clone, Debug)]
+pub struct LibraryRun {
+    /// The library the run is saved to
+    pub directory: PathBuf,
+
+    /// Label of the solver config
+    pub label: String,
+
+    pub hashes: RunHashes,
+
+    /// Whether the run was saved already.
+    pub saved: bool,
+}
+
+/// Exports the results of a run into a new directory in the library, and
+/// writes its [`RunRecord`].
+///
+/// This is meant to run as a job. Returns the run's directory. The viewport
+/// image has to be saved into it afterwards.
+pub fn save_run(
+    run: &LibraryRun,
+    state: &SolverState,
+    probe_outputs: &ProbeOutputs,
+    job: &JobContext,
+) -> Result<PathBuf, Error> {
+    let record = RunRecord::new(run.label.clone(), run.hashes.clone(), state, probe_outputs);
+    let directory = run.directory.join(record.directory_name());
+
+    export_results(&directory, state, probe_outputs, None, job)?;
+    record.write(&directory)?;
+
+    Ok(directory)
+}
+
+/// A run read from the library.
+#[derive(Clone, Debug)]
+pub struct LibraryEntry {
+    pub directory: PathBuf,
+    pub record: RunRecord,
+}
+
+impl LibraryEntry {
+    pub fn thumbnail_path(&self) -> PathBuf {
+        self.directory.join(VIEWPORT_IMAGE_FILE_NAME)
+    }
+
+    /// Port reflections and patterns of the run, labelled with the run.
+    pub fn datasets(&self) -> Result<Vec<Dataset>, Error> {
+        let mut paths = std::fs::read_dir(&self.directory)?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<_>, Error>>()?;
+        paths.sort();
+
+        let mut datasets = vec![];
+        for path in paths {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            // see `export_results` for the file names
+            let is_port = file_name.starts_with("port_") && file_name.ends_with(".s1p");
+            let is_pattern = file_name.starts_with("nf2ff_box_") && file_name.ends_with(".csv");
+            if is_port || is_pattern {
+                let mut dataset = Dataset::from_path(&path)?;
+                dataset.label = format!(
+                    "{} ({}) {}",
+                    self.record.label, self.record.date, dataset.label
+                );
+                datasets.push(dataset);
+            }
+        }
+
+        Ok(datasets)
+    }
+}
+
+/// Reads all runs in the library, newest first.
+///
+/// Directories without a readable [`RunRecord`] are skipped.
+pub fn scan_library(directory: &Path) -> Result<Vec<LibraryEntry>, Error> {
+    if !directory.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut entries = vec![];
+    for entry in std::fs::read_dir(directory)? {
+        let run_directory = entry?.path();
+        let record_path = run_directory.join(RUN_RECORD_FILE_NAME);
+        if !record_path.exists() {
+            continue;
+        }
+
+        match std::fs::read(&record_path)
+            .map_err(Error::from)
+            .and_then(|json| Ok(serde_json::from_slice::<RunRecord>(&json)?))
+        {
+            Ok(record) => {
+                entries.push(LibraryEntry {
+                    directory: run_directory,
+                    record,
+                });
+            }
+            Err(error) => {
+                tracing::warn!(path = %record_path.display(), ?error, "can't read run record");
+            }
+        }
+    }
+
+    entries.sort_by(|a, b| b.record.date.cmp(&a.record.date));
+
+    Ok(entries)
+}
+
+/// Window that lists the runs in a project's library.
+#[derive(Debug, Default)]
+pub struct ResultsLibraryWindow {
+    pub open: bool,
+
+    /// The library that `entries` were read from
+    directory: Option<PathBuf>,
+    entries: Vec<LibraryEntry>,
+
+    search: String,
+    finished_only: bool,
+
+    /// Run whose details are shown
+    selected: Option<usize>,
+
+    /// To tell which runs are stale
+    current_hashes: CurrentHashes,
+
+    /// Job loading the datasets of a run to compare, and the run's index
+    compare_job: Option<(usize, JobHandle<Vec<Dataset>>)>,
+}
+
+impl ResultsLibraryWindow {
+    /// Reads the runs in the library again.
+    pub fn refresh(&mut self, ctx: &egui::Context) {
+        let Some(directory) = &self.directory
+        else {
+            self.entries.clear();
+            return;
+        };
+
+        // new screenshots might have been written since the thumbnails were loaded
+        for entry in &self.entries {
+            ctx.forget_image(&thumbnail_uri(entry));
+        }
+
+        self.entries = scan_library(directory)
+            .ok_or_handle(ctx)
+            .unwrap_or_default();
+        self.selected = None;
+    }
+
+    /// Shows the runs in the library of the project at `project_path`.
+    ///
+    /// Runs are checked against the project's `world` and `solver_configs`, to
+    /// tell if they are stale.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        project_path: Option<&Path>,
+        world: &mut World,
+        solver_configs: &[SolverConfig],
+        measurements_window: &mut MeasurementsWindow,
+    ) {
+        if !self.open {
+            return;
+        }
+
+        self.current_hashes.update(world, solver_configs);
+
+        let directory = project_path.map(library_directory);
+        if directory != self.directory {
+            self.directory = directory;
+            self.refresh(ctx);
+        }
+
+        let mut open = self.open;
+
+        egui::Window::new("Results Library")
+            .movable(true)
+            .default_size([500.0, 400.0])
+            .open(&mut open)
+            .show(ctx, |ui| {
+                if self.directory.is_none() {
+                    ui.label("Save the project to keep its runs in a library.");
+                    return;
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Search");
+                    ui.text_edit_singleline(&mut self.search)
+                        .on_hover_text("Label, date, config hash or metrics");
+                    ui.checkbox(&mut self.finished_only, "Finished only");
+                    if ui.button("Refresh").clicked() {
+                        self.refresh(ui.ctx());
+                    }
+                });
+
+                ui.separator();
+
+                self.runs_ui(ui, measurements_window);
+
+                if let Some(entry) = self.selected.and_then(|index| self.entries.get(index)) {
+                    ui.separator();
+                    details_ui(ui, entry);
+                }
+            });
+
+        self.open = open;
+    }
+
+    fn runs_ui(&mut self, ui: &mut egui::Ui, measurements_window: &mut MeasurementsWindow) {
+        if let Some(result) = self
+            .compare_job
+            .as_ref()
+            .and_then(|(_, job)| job.try_take_result())
+        {
+            self.compare_job = None;
+            match result {
+                Ok(datasets) => {
+                    for dataset in datasets {
+                        measurements_window.add_dataset(dataset);
+                    }
+                }
+                Err(error) if error.is::<Cancelled>() => {
+                    tracing::debug!("loading datasets cancelled");
+                }
+                Err(error) => ui.handle_error(error),
+            }
+        }
+
+        let mut num_shown = 0;
+
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .show(ui, |ui| {
+                for (index, entry) in self.entries.iter().enumerate() {
+                    if (self.finished_only && !entry.record.finished)
+                        || !entry.record.matches(&self.search)
+                    {
+                        continue;
+                    }
+                    num_shown += 1;
+
+                    ui.push_id(index, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::Image::new(thumbnail_uri(entry))
+                                    .fit_to_exact_size(THUMBNAIL_SIZE)
+                                    .maintain_aspect_ratio(true),
+                            );
+
+                            ui.vertical(|ui| {
+                                ui.horizontal(|ui| {
+                                    ui.strong(&entry.record.label);
+                                    if let Some(reason) = self
+                                        .current_hashes
+                                        .stale_reason(&entry.record.label, &entry.record.hashes)
+                                    {
+                                        stale_results_label(ui, reason);
+                                    }
+                                });
+                                ui.label(&entry.record.date);
+                                ui.label(key_metrics(&entry.record));
+                                ui.monospace(format!(
+                                    "{} {}",
+                                    entry.record.hashes.config_hash,
+                                    entry.record.hashes.geometry_hash
+                                ))
+                                .on_hover_text("Hashes of the solver config and the geometry");
+
+                                ui.horizontal(|ui| {
+                                    if ui
+                                        .selectable_label(self.selected == Some(index), "Details")
+                                        .clicked()
+                                    {
+                                        self.selected =
+                                            (self.selected != Some(index)).then_some(index);
+                                    }
+
+                                    if ui
+                                        .add_enabled(
+                                            self.compare_job.is_none(),
+                                            egui::Button::new("Compare"),
+                                        )
+                                        .on_hover_text(
+                                            "Load the port reflections and patterns into the \
+                                             measured data window.",
+                                        )
+                                        .clicked()
+                                    {
+                                        let entry = entry.clone();
+                                        let job = Jobs::from_ctx(ui.ctx()).spawn(
+                                            "Load run results",
+                                            move |_job| {
+                                                let datasets = entry.datasets()?;
+                                                if datasets.is_empty() {
+                                                    tracing::info!(directory = %entry.directory.display(), "run has no ports or patterns");
+                                                }
+                                                Ok(datasets)
+                                            },
+                                        );
+                                        self.compare_job = Some((index, job));
+                                    }
+                                    if self
+                                        .compare_job
+                                        .as_ref()
+                                        .is_some_and(|(job_index, _)| *job_index == index)
+                                    {
+                                        ui.spinner();
+                                    }
+                                });
+                            });
+                        });
+                    });
+                }
+            });
+
+        if num_shown == 0 {
+            ui.label(if self.entries.is_empty() {
+                "No runs yet"
+            }
+            else {
+                "No runs match"
+            });
+        }
+    }
+}
+
+/// Simulated time and ticks of a run, in one line.
+fn key_metrics(record: &RunRecord) -> String {
+    record
+        .metrics
+        .iter()
+        .filter(|(label, _)| ["Simulation time", "Ticks", "Running time"].contains(&label.as_str()))
+        .map(|(label, value)| format!("{label}: {value}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn details_ui(ui: &mut egui::Ui, entry: &LibraryEntry) {
+    ui.horizontal(|ui| {
+        ui.add(egui::Image::new(thumbnail_uri(entry)).max_width(240.0));
+
+        egui::Grid::new("run_metrics").striped(true).show(ui, |ui| {
+            ui.label("Finished");
+            ui.label(entry.record.finished.to_string());
+            ui.end_row();
+
+            for (label, value) in &entry.record.metrics {
+                ui.label(label);
+                ui.label(value);
+                ui.end_row();
+            }
+        });
+    });
+
+    ui.label(format!("Directory: {}", entry.directory.display()));
+}
+
+fn thumbnail_uri(entry: &LibraryEntry) -> String {
+    format!("file://{}", entry.thumbnail_path().display())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::{
+        hashes::RunHashes,
+        library::RunRecord,
+    };
+
+    #[test]
+    fn search_matches_all_words() {
+        let record = RunRecord {
+            label: "GPU".to_owned(),
+            date: "2025-03-01 12:00:00".to_owned(),
+            hashes: RunHashes {
+                config_hash: "00ff00ff00ff00ff".to_owned(),
+                geometry_hash: "0123456789abcdef".to_owned(),
+            },
+            finished: true,
+            metrics: vec![("Ticks".to_owned(), "1000".to_owned())],
+        };
+
+        assert!(record.matches(""));
+        assert!(record.matches("gpu 2025-03"));
+        assert!(record.matches("ticks 1000"));
+        assert!(record.matches("00FF00"));
+        assert!(record.matches("456789"));
+        assert!(!record.matches("gpu cpu"));
+
+        assert_eq!(record.directory_name(), "2025-03-01_12-00-00_GPU");
+    }
+}
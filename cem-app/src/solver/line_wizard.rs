@@ -0,0 +1,840 @@
+//! Characterization of transmission lines with thru and line standards.
+//!
+//! The user draws a line between two ports, and controls its length with a
+//! scene parameter. The wizard runs the solver twice, once with the short thru
+//! length and once with the longer line length. Each run gives the chain
+//! (ABCD) matrix of its standard, including the transitions at the ports. The
+//! transitions cancel out of `T_line T_thru⁻¹`, which is similar to a piece of
+//! line as long as the difference of the lengths. Its eigenvalues give the
+//! propagation constant, and with it the effective permittivity.
+//!
+//! One run determines a chain matrix only because the standards are symmetric
+//! and reciprocal, and only if the ports aren't driven alike. Usually only the
+//! input port is driven, and the output port gets an
+//! [`Excitation`](crate::solver::array::Excitation) with amplitude 0. The
+//! characteristic impedance is read from the same matrix, which assumes that
+//! the transitions are short compared to the wavelength.
+
+use std::{
+    f64::consts::TAU,
+    ops::Mul,
+};
+
+use cem_solver::material::PhysicalConstants;
+use color_eyre::eyre::eyre;
+use num::complex::Complex64;
+
+use crate::{
+    Error,
+    clipboard::copy_image_or_csv_context_menu,
+    composer::Composers,
+    error::ResultExt,
+    solver::{
+        parameters::SceneParameters,
+        probe::ProbeOutputs,
+        runner::SolverRunner,
+        sequence::{
+            StepStatus,
+            runs_forever,
+            start_step,
+            step_status,
+        },
+        tuning::frequency_drag_value,
+    },
+};
+
+/// Chain (ABCD) matrix of a two-port at one frequency.
+///
+/// It maps the voltage and current at the output port to those at the input
+/// port, with the output current flowing out of the two-port.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChainMatrix {
+    pub a: Complex64,
+    pub b: Complex64,
+    pub c: Complex64,
+    pub d: Complex64,
+}
+
+impl ChainMatrix {
+    /// Matrix of a uniform line with propagation constant in 1/m,
+    /// characteristic impedance in Ω and length in m.
+    pub fn line(propagation_constant: Complex64, impedance: Complex64, length: f64) -> Self {
+        let x = propagation_constant * length;
+        Self {
+            a: x.cosh(),
+            b: impedance * x.sinh(),
+            c: x.sinh() / impedance,
+            d: x.cosh(),
+        }
+    }
+
+    /// Matrix of a symmetric, reciprocal two-port, from the voltage and current
+    /// phasors at its ports for one excitation. Both currents flow into the
+    /// two-port.
+    ///
+    /// With `A = D` and `AD - BC = 1` the two equations of one excitation are
+    /// enough. Returns `None` if they're degenerate, e.g. if both ports are
+    /// driven alike.
+    pub fn from_symmetric_port_phasors(
+        input: (Complex64, Complex64),
+        output: (Complex64, Complex64),
+    ) -> Option<Self> {
+        let (v1, i1) = input;
+        let (v2, i2) = (output.0, -output.1);
+
+        let a = (v1 * i1 + v2 * i2) / (v1 * i2 + v2 * i1);
+        let c = (i1 - a * i2) / v2;
+        let b = (a * a - 1.0) / c;
+
+        let matrix = Self { a, b, c, d: a };
+        [a, b, c].iter().all(|x| x.is_finite()).then_some(matrix)
+    }
+
+    pub fn determinant(&self) -> Complex64 {
+        self.a * self.d - self.b * self.c
+    }
+
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.determinant();
+        (determinant.norm() > 0.0).then(|| {
+            Self {
+                a: self.d / determinant,
+                b: -self.b / determinant,
+                c: -self.c / determinant,
+                d: self.a / determinant,
+            }
+        })
+    }
+
+    /// The two eigenvalues.
+    fn eigenvalues(&self) -> [Complex64; 2] {
+        let half_trace = (self.a + self.d) / 2.0;
+        let root = (half_trace * half_trace - self.determinant()).sqrt();
+        [half_trace - root, half_trace + root]
+    }
+}
+
+impl Mul for ChainMatrix {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            a: self.a * rhs.a + self.b * rhs.c,
+            b: self.a * rhs.b + self.b * rhs.d,
+            c: self.c * rhs.a + self.d * rhs.c,
+            d: self.c * rhs.b + self.d * rhs.d,
+        }
+    }
+}
+
+/// Parameters of a line at one frequency.
+#[derive(Clone, Copy, Debug)]
+pub struct LineSample {
+    /// Frequency in Hz
+    pub frequency: f64,
+
+    /// Propagation constant `α + jβ` in 1/m
+    pub propagation_constant: Complex64,
+
+    /// Characteristic impedance in Ω
+    pub impedance: Complex64,
+}
+
+impl LineSample {
+    /// `(β/k₀)²`, where `k₀` is the wavenumber in vacuum.
+    pub fn effective_permittivity(&self) -> f64 {
+        let wavenumber = TAU * self.frequency / PhysicalConstants::SI.speed_of_light();
+        (self.propagation_constant.im / wavenumber).powi(2)
+    }
+}
+
+/// Propagation constant and characteristic impedance of a line over frequency.
+#[derive(Clone, Debug, Default)]
+pub struct LineCharacteristics {
+    pub samples: Vec<LineSample>,
+}
+
+impl LineCharacteristics {
+    /// Extracts the line from the chain matrices of the thru and line
+    /// standards at each frequency. `length` is how much longer the line
+    /// standard is, in m.
+    ///
+    /// The phase of the eigenvalues is only known up to whole turns, so it's
+    /// followed from the lowest frequency, where it has to be less than half a
+    /// turn. The frequencies must be sorted, and close enough that the phase
+    /// changes by less than half a turn between them.
+    pub fn extract(
+        standards: impl IntoIterator<Item = (f64, ChainMatrix, ChainMatrix)>,
+        length: f64,
+    ) -> Self {
+        let mut samples = vec![];
+        let mut previous: Vec<Complex64> = vec![];
+        let mut phase_offset = 0.0;
+
+        for (frequency, thru, line) in standards {
+            let Some(inverse) = thru.inverse()
+            else {
+                continue;
+            };
+            let difference = line * inverse;
+
+            // the eigenvalues are e^(∓γl), for waves travelling forward and backward
+            let [first, second] = difference.eigenvalues();
+            let predicted = match previous.as_slice() {
+                [] => None,
+                [last] => Some(*last),
+                [.., before, last] => Some(last * (last / before)),
+            };
+            let eigenvalue = match predicted {
+                Some(predicted) => {
+                    if (first - predicted).norm() <= (second - predicted).norm() {
+                        first
+                    }
+                    else {
+                        second
+                    }
+                }
+                // at the lowest frequency the forward wave's phase lags by less than half a turn
+                None => {
+                    if first.arg() <= second.arg() {
+                        first
+                    }
+                    else {
+                        second
+                    }
+                }
+            };
+
+            if let Some(last) = previous.last() {
+                // whole turns, so that the step from the previous frequency is at most half a
+                // turn
+                phase_offset -= TAU * ((eigenvalue.arg() - last.arg()) / TAU).round();
+            }
+            previous.push(eigenvalue);
+
+            let propagation_constant =
+                -Complex64::new(eigenvalue.norm().ln(), eigenvalue.arg() + phase_offset) / length;
+
+            samples.push(LineSample {
+                frequency,
+                propagation_constant,
+                impedance: (difference.b / difference.c).sqrt(),
+            });
+        }
+
+        Self { samples }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = "frequency,re_z0,im_z0,alpha,beta,eps_eff\n".to_owned();
+        for sample in &self.samples {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                sample.frequency,
+                sample.impedance.re,
+                sample.impedance.im,
+                sample.propagation_constant.re,
+                sample.propagation_constant.im,
+                sample.effective_permittivity()
+            ));
+        }
+        csv
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Standard {
+    Thru,
+    Line,
+}
+
+impl Standard {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Thru => "Thru",
+            Self::Line => "Line",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LineWizard {
+    pub open: bool,
+
+    /// Index of the solver config the standards are run with
+    solver_config: usize,
+
+    /// Parameter that sets the length of the line
+    parameter: Option<String>,
+
+    /// Lengths of the thru and line standards, in m
+    thru_length: f64,
+    line_length: f64,
+
+    input_port: usize,
+    output_port: usize,
+
+    /// Frequencies the line is extracted at, in Hz
+    start_frequency: f64,
+    end_frequency: f64,
+    num_frequencies: usize,
+
+    /// Standard that is running
+    running: Option<Standard>,
+
+    thru: Option<ProbeOutputs>,
+    line: Option<ProbeOutputs>,
+
+    characteristics: Option<LineCharacteristics>,
+}
+
+impl Default for LineWizard {
+    fn default() -> Self {
+        Self {
+            open: false,
+            solver_config: 0,
+            parameter: None,
+            thru_length: 0.01,
+            line_length: 0.02,
+            input_port: 1,
+            output_port: 2,
+            start_frequency: 0.5e9,
+            end_frequency: 10e9,
+            num_frequencies: 100,
+            running: None,
+            thru: None,
+            line: None,
+            characteristics: None,
+        }
+    }
+}
+
+impl LineWizard {
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Frequencies the line is extracted at. Both ends of the band are
+    /// included.
+    pub fn frequencies(&self) -> Vec<f64> {
+        match self.num_frequencies {
+            0 => vec![],
+            1 => vec![self.start_frequency],
+            n => {
+                (0..n)
+                    .map(|i| {
+                        self.start_frequency
+                            + (self.end_frequency - self.start_frequency) * i as f64
+                                / (n - 1) as f64
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        composers: &mut Composers,
+        solver_runner: &mut SolverRunner,
+    ) {
+        // the standards continue while the window is closed
+        self.advance(composers, solver_runner).ok_or_handle(ctx);
+
+        let mut open = self.open;
+
+        egui::Window::new("Line Characterization")
+            .movable(true)
+            .default_size([400.0, 550.0])
+            .open(&mut open)
+            .show(ctx, |ui| {
+                self.setup_ui(ui, composers, solver_runner);
+
+                ui.separator();
+
+                match &self.characteristics {
+                    Some(characteristics) if !characteristics.samples.is_empty() => {
+                        results_ui(ui, characteristics);
+                    }
+                    Some(_) => {
+                        ui.weak("The line couldn't be extracted at any frequency.");
+                    }
+                    None => {
+                        ui.weak("No results yet");
+                    }
+                }
+            });
+
+        self.open = open;
+    }
+
+    fn setup_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        composers: &mut Composers,
+        solver_runner: &mut SolverRunner,
+    ) {
+        let Some((scene, solver_configs)) = composers.active_scene_mut()
+        else {
+            ui.weak("No scene open");
+            return;
+        };
+
+        if solver_configs.is_empty() {
+            ui.weak("Configure a solver first");
+            return;
+        }
+        self.solver_config = self.solver_config.min(solver_configs.len() - 1);
+
+        let mut band_changed = false;
+
+        ui.add_enabled_ui(!self.is_running(), |ui| {
+            egui::Grid::new("line_wizard_setup")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Solver");
+                    egui::ComboBox::from_id_salt("solver_config")
+                        .selected_text(&solver_configs[self.solver_config].label)
+                        .show_ui(ui, |ui| {
+                            for (i, solver_config) in solver_configs.iter().enumerate() {
+                                ui.selectable_value(
+                                    &mut self.solver_config,
+                                    i,
+                                    &solver_config.label,
+                                );
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Length Parameter");
+                    let parameters = SceneParameters::get(&scene.world);
+                    egui::ComboBox::from_id_salt("parameter")
+                        .selected_text(self.parameter.as_deref().unwrap_or("None"))
+                        .show_ui(ui, |ui| {
+                            for name in parameters.keys() {
+                                if ui
+                                    .selectable_label(self.parameter.as_ref() == Some(name), name)
+                                    .clicked()
+                                {
+                                    self.parameter = Some(name.clone());
+                                }
+                            }
+                        });
+                    ui.end_row();
+
+                    for (label, length) in [
+                        ("Thru Length", &mut self.thru_length),
+                        ("Line Length", &mut self.line_length),
+                    ] {
+                        ui.label(label);
+                        let speed = *length * 0.01;
+                        ui.add(
+                            egui::DragValue::new(length)
+                                .range(0.0..=f64::INFINITY)
+                                .speed(speed)
+                                .max_decimals(6)
+                                .suffix(" m"),
+                        );
+                        ui.end_row();
+                    }
+
+                    ui.label("Ports");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut self.input_port).range(1..=usize::MAX));
+                        ui.label("to");
+                        ui.add(egui::DragValue::new(&mut self.output_port).range(1..=usize::MAX));
+                    });
+                    ui.end_row();
+
+                    ui.label("Band");
+                    ui.horizontal(|ui| {
+                        band_changed |=
+                            frequency_drag_value(ui, &mut self.start_frequency).changed();
+                        ui.label("to");
+                        band_changed |= frequency_drag_value(ui, &mut self.end_frequency).changed();
+                    });
+                    ui.end_row();
+
+                    ui.label("Frequencies");
+                    band_changed |= ui
+                        .add(egui::DragValue::new(&mut self.num_frequencies).range(2..=10000))
+                        .changed();
+                    ui.end_row();
+                });
+        });
+
+        if self.parameter.is_none() {
+            ui.weak(
+                "Add a parameter for the line's length in View > Parameters and select it here.",
+            );
+        }
+        let difference = self.line_length - self.thru_length;
+        if difference <= 0.0 {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "The line standard has to be longer than the thru.",
+            );
+        }
+
+        // both standards have to finish on their own
+        let runs_forever = runs_forever(&solver_configs[self.solver_config]);
+        if runs_forever {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "The solver has no stop condition, so the standards would never finish.",
+            );
+        }
+
+        let mut start = false;
+        ui.horizontal(|ui| {
+            if let Some(standard) = self.running {
+                ui.spinner();
+                ui.label(format!("Running the {} standard", standard.label()));
+                if ui.button("Cancel").clicked() {
+                    self.cancel(solver_runner);
+                }
+            }
+            else if ui
+                .add_enabled(
+                    self.parameter.is_some() && difference > 0.0 && !runs_forever,
+                    egui::Button::new("Start"),
+                )
+                .on_hover_text("Run the thru and line standards")
+                .clicked()
+            {
+                start = true;
+            }
+        });
+
+        if start {
+            self.thru = None;
+            self.line = None;
+            self.characteristics = None;
+            self.start(Standard::Thru, composers, solver_runner)
+                .ok_or_handle(ui.ctx());
+        }
+        else if band_changed && !self.is_running() {
+            self.extract().ok_or_handle(ui.ctx());
+        }
+    }
+
+    /// Collects the results of the running standard once it finished, and
+    /// starts the next one.
+    fn advance(
+        &mut self,
+        composers: &mut Composers,
+        solver_runner: &mut SolverRunner,
+    ) -> Result<(), Error> {
+        let Some(standard) = self.running
+        else {
+            return Ok(());
+        };
+
+        let outputs = match step_status(solver_runner) {
+            StepStatus::Running => return Ok(()),
+            StepStatus::Finished(outputs) => outputs,
+            StepStatus::Closed => {
+                self.running = None;
+                return Ok(());
+            }
+        };
+        self.running = None;
+
+        match standard {
+            Standard::Thru => {
+                self.thru = Some(outputs);
+                self.start(Standard::Line, composers, solver_runner)
+            }
+            Standard::Line => {
+                self.line = Some(outputs);
+                self.extract()
+            }
+        }
+    }
+
+    fn start(
+        &mut self,
+        standard: Standard,
+        composers: &mut Composers,
+        solver_runner: &mut SolverRunner,
+    ) -> Result<(), Error> {
+        let Some(parameter) = &self.parameter
+        else {
+            return Ok(());
+        };
+        let Some((scene, solver_configs)) = composers.active_scene_mut()
+        else {
+            return Ok(());
+        };
+        let Some(solver_config) = solver_configs.get(self.solver_config)
+        else {
+            return Ok(());
+        };
+
+        let length = match standard {
+            Standard::Thru => self.thru_length,
+            Standard::Line => self.line_length,
+        };
+        let mut solver_config = solver_config.clone();
+        solver_config
+            .common
+            .parameter_overrides
+            .insert(parameter.clone(), length);
+
+        tracing::debug!(
+            standard = standard.label(),
+            length,
+            "starting line standard"
+        );
+
+        start_step(solver_runner, scene, &solver_config)?;
+        self.running = Some(standard);
+
+        Ok(())
+    }
+
+    fn cancel(&mut self, solver_runner: &mut SolverRunner) {
+        if self.running.take().is_some() {
+            solver_runner.stop();
+        }
+    }
+
+    /// Extracts the line from the outputs of both standards, if they have
+    /// finished.
+    fn extract(&mut self) -> Result<(), Error> {
+        let (Some(thru), Some(line)) = (&self.thru, &self.line)
+        else {
+            return Ok(());
+        };
+
+        let frequencies = self.frequencies();
+        let thru = self.chain_matrices(thru, Standard::Thru, &frequencies)?;
+        let line = self.chain_matrices(line, Standard::Line, &frequencies)?;
+
+        let standards = frequencies
+            .iter()
+            .zip(thru.into_iter().zip(line))
+            .filter_map(|(frequency, standards)| {
+                match standards {
+                    (Some(thru), Some(line)) => Some((*frequency, thru, line)),
+                    _ => None,
+                }
+            });
+
+        self.characteristics = Some(LineCharacteristics::extract(
+            standards,
+            self.line_length - self.thru_length,
+        ));
+
+        Ok(())
+    }
+
+    fn chain_matrices(
+        &self,
+        outputs: &ProbeOutputs,
+        standard: Standard,
+        frequencies: &[f64],
+    ) -> Result<Vec<Option<ChainMatrix>>, Error> {
+        let phasors = |number: usize| {
+            outputs
+                .ports
+                .iter()
+                .find(|(_, output)| output.number() == number)
+                .and_then(|(_, output)| output.phasors(frequencies))
+                .ok_or_else(|| {
+                    eyre!(
+                        "Port {number} wasn't recorded in the {} run",
+                        standard.label()
+                    )
+                })
+        };
+
+        let input = phasors(self.input_port)?;
+        let output = phasors(self.output_port)?;
+
+        Ok(input
+            .into_iter()
+            .zip(output)
+            .map(|(input, output)| ChainMatrix::from_symmetric_port_phasors(input, output))
+            .collect())
+    }
+}
+
+fn results_ui(ui: &mut egui::Ui, characteristics: &LineCharacteristics) {
+    let points = |f: &dyn Fn(&LineSample) -> f64| {
+        characteristics
+            .samples
+            .iter()
+            .map(|sample| [sample.frequency * 1e-9, f(sample)])
+            .collect::<Vec<_>>()
+    };
+
+    ui.label("Characteristic impedance");
+    plot(
+        ui,
+        "line_impedance",
+        "Z0 [Ω]",
+        vec![
+            ("Re", points(&|sample| sample.impedance.re)),
+            ("Im", points(&|sample| sample.impedance.im)),
+        ],
+        characteristics,
+    );
+
+    ui.label("Propagation constant");
+    plot(
+        ui,
+        "line_propagation_constant",
+        "γ [1/m]",
+        vec![
+            ("α [Np/m]", points(&|sample| sample.propagation_constant.re)),
+            (
+                "β [rad/m]",
+                points(&|sample| sample.propagation_constant.im),
+            ),
+        ],
+        characteristics,
+    );
+
+    ui.label("Effective permittivity");
+    plot(
+        ui,
+        "line_effective_permittivity",
+        "eps_eff",
+        vec![("eps_eff", points(&LineSample::effective_permittivity))],
+        characteristics,
+    );
+}
+
+fn plot(
+    ui: &mut egui::Ui,
+    id: &str,
+    y_label: &str,
+    lines: Vec<(&str, Vec<[f64; 2]>)>,
+    characteristics: &LineCharacteristics,
+) {
+    let response = egui_plot::Plot::new(id)
+        .height(150.0)
+        .x_axis_label("Frequency [GHz]")
+        .y_axis_label(y_label)
+        .legend(egui_plot::Legend::default())
+        .show(ui, |plot_ui| {
+            for (name, points) in lines {
+                plot_ui.line(egui_plot::Line::new(name, points));
+            }
+        })
+        .response;
+
+    copy_image_or_csv_context_menu(&response, || characteristics.to_csv());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::TAU;
+
+    use cem_solver::material::PhysicalConstants;
+    use num::complex::Complex64;
+
+    use crate::solver::line_wizard::{
+        ChainMatrix,
+        LineCharacteristics,
+    };
+
+    /// Transition at the ports, which the extraction has to remove.
+    fn transition() -> ChainMatrix {
+        ChainMatrix {
+            a: Complex64::ONE,
+            b: Complex64::new(2.0, 5.0),
+            c: Complex64::ZERO,
+            d: Complex64::ONE,
+        }
+    }
+
+    /// Voltages and currents at the ports of a standard whose output port isn't
+    /// driven.
+    fn measure(standard: &ChainMatrix) -> ((Complex64, Complex64), (Complex64, Complex64)) {
+        // the output is open, so the input sees A and C for a unit voltage
+        ((standard.a, standard.c), (Complex64::ONE, Complex64::ZERO))
+    }
+
+    #[test]
+    fn chain_matrix_from_one_excitation() {
+        let propagation_constant = Complex64::new(0.5, 100.0);
+        let standard = transition()
+            * ChainMatrix::line(propagation_constant, Complex64::new(50.0, 0.0), 0.03)
+            * transition();
+
+        let (input, output) = measure(&standard);
+        let extracted = ChainMatrix::from_symmetric_port_phasors(input, output).unwrap();
+        for (x, y) in [
+            (extracted.a, standard.a),
+            (extracted.b, standard.b),
+            (extracted.c, standard.c),
+            (extracted.d, standard.d),
+        ] {
+            assert!((x - y).norm() < 1e-9 * y.norm().max(1.0), "{x} != {y}");
+        }
+
+        // both ports driven alike can't be told apart from a thru
+        assert!(
+            ChainMatrix::from_symmetric_port_phasors(
+                (Complex64::ONE, Complex64::ONE),
+                (Complex64::ONE, Complex64::ONE)
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn line_is_extracted_over_several_turns() {
+        let effective_permittivity = 3.0_f64;
+        let impedance = Complex64::new(50.0, 0.0);
+        let speed_of_light = PhysicalConstants::SI.speed_of_light();
+
+        let standards = (1..=200).map(|i| {
+            let frequency = i as f64 * 50e6;
+            let beta = TAU * frequency * effective_permittivity.sqrt() / speed_of_light;
+            let propagation_constant = Complex64::new(0.1, beta);
+            let standard = |length| {
+                let line = ChainMatrix::line(propagation_constant, impedance, length);
+                let (input, output) = measure(&line);
+                ChainMatrix::from_symmetric_port_phasors(input, output).unwrap()
+            };
+            (frequency, standard(0.01), standard(0.06))
+        });
+
+        let characteristics = LineCharacteristics::extract(standards, 0.05);
+        assert_eq!(characteristics.samples.len(), 200);
+
+        // at 10 GHz the line standard is almost 3 wavelengths longer
+        for sample in &characteristics.samples {
+            let effective_permittivity = sample.effective_permittivity();
+            assert!(
+                (effective_permittivity - 3.0).abs() < 1e-6,
+                "{effective_permittivity}"
+            );
+            assert!((sample.propagation_constant.re - 0.1).abs() < 1e-6);
+            assert!((sample.impedance - impedance).norm() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn transitions_cancel_out_of_propagation_constant() {
+        let propagation_constant = Complex64::new(0.2, 80.0);
+        let standard = |length| {
+            let standard = transition()
+                * ChainMatrix::line(propagation_constant, Complex64::new(35.0, 0.0), length)
+                * transition();
+            let (input, output) = measure(&standard);
+            ChainMatrix::from_symmetric_port_phasors(input, output).unwrap()
+        };
+
+        let characteristics =
+            LineCharacteristics::extract([(1e9, standard(0.01), standard(0.02))], 0.01);
+        let extracted = characteristics.samples[0].propagation_constant;
+        assert!(
+            (extracted - propagation_constant).norm() < 1e-6,
+            "{extracted}"
+        );
+    }
+}
@@ -0,0 +1,825 @@
+//! Measured data to validate simulations against.
+//!
+//! S-parameters from VNAs are imported from Touchstone (`.sNp`) files, and
+//! antenna patterns from chamber measurements as CSV. Both are turned into
+//! [`Dataset`]s of curves, which are plotted in the [`MeasurementsWindow`]
+//! together with simulated results. One dataset can be chosen as reference,
+//! and the others are compared against it.
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use cem_util::egui::{
+    EguiUtilUiExt,
+    FilePickerConfig,
+};
+use color_eyre::eyre::{
+    OptionExt,
+    bail,
+    eyre,
+};
+use num::complex::Complex64;
+
+use crate::{
+    Error,
+    clipboard::EguiClipboardExt,
+    error::ResultExt,
+    solver::{
+        network::{
+            MATCHED_THRESHOLD,
+            NetworkQuantity,
+            Reflection,
+            format_frequency,
+            smith_chart,
+        },
+        pattern::RadiationPattern,
+    },
+};
+
+/// Network parameters from a Touchstone file.
+///
+/// Only S-parameters in the version 1 format are supported.
+#[derive(Clone, Debug)]
+pub struct Touchstone {
+    pub num_ports: usize,
+    pub reference_impedance: f64,
+
+    /// Frequencies in Hz.
+    pub frequencies: Vec<f64>,
+
+    /// `num_ports * num_ports` S-parameters per frequency, row-major.
+    parameters: Vec<Complex64>,
+}
+
+impl Touchstone {
+    /// Reads a Touchstone file. The number of ports is taken from the file
+    /// extension, e.g. `.s2p`.
+    pub fn from_path(path: &Path) -> Result<Self, Error> {
+        let num_ports = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| {
+                extension
+                    .to_ascii_lowercase()
+                    .strip_prefix('s')?
+                    .strip_suffix('p')?
+                    .parse::<usize>()
+                    .ok()
+            })
+            .filter(|num_ports| *num_ports > 0)
+            .ok_or_eyre("Touchstone file extension must be .sNp")?;
+
+        Self::parse(&std::fs::read_to_string(path)?, num_ports)
+    }
+
+    pub fn parse(text: &str, num_ports: usize) -> Result<Self, Error> {
+        let mut frequency_unit = 1e9;
+        let mut format = "MA";
+        let mut reference_impedance = 50.0;
+        let mut numbers = vec![];
+
+        for line in text.lines() {
+            // everything after a `!` is a comment
+            let line = line.split('!').next().unwrap_or_default().trim();
+
+            if let Some(options) = line.strip_prefix('#') {
+                let mut options = options.split_whitespace();
+                while let Some(option) = options.next() {
+                    match option.to_ascii_uppercase().as_str() {
+                        "HZ" => frequency_unit = 1.0,
+                        "KHZ" => frequency_unit = 1e3,
+                        "MHZ" => frequency_unit = 1e6,
+                        "GHZ" => frequency_unit = 1e9,
+                        "S" => {}
+                        "Y" | "Z" | "G" | "H" => bail!("Only S-parameters are supported"),
+                        "DB" => format = "DB",
+                        "MA" => format = "MA",
+                        "RI" => format = "RI",
+                        "R" => {
+                            reference_impedance = options
+                                .next()
+                                .and_then(|value| value.parse().ok())
+                                .ok_or_eyre("Invalid reference impedance")?;
+                        }
+                        _ => bail!("Invalid Touchstone option: {option}"),
+                    }
+                }
+            }
+            else if line.starts_with('[') {
+                // version 2 keywords. the network data is read like in version 1 files.
+                let keyword = line.to_ascii_lowercase();
+                if keyword.starts_with("[noise data]") || keyword.starts_with("[end]") {
+                    break;
+                }
+            }
+            else {
+                for number in line.split_whitespace() {
+                    numbers.push(
+                        number
+                            .parse::<f64>()
+                            .map_err(|_| eyre!("Invalid number in Touchstone file: {number}"))?,
+                    );
+                }
+            }
+        }
+
+        let num_parameters = num_ports * num_ports;
+        let mut frequencies = vec![];
+        let mut parameters = vec![];
+
+        for chunk in numbers.chunks(1 + 2 * num_parameters) {
+            let frequency = chunk[0] * frequency_unit;
+
+            // noise parameters of 2-ports follow the network data, starting at a lower
+            // frequency again.
+            if frequencies.last().is_some_and(|last| frequency <= *last) {
+                break;
+            }
+            if chunk.len() < 1 + 2 * num_parameters {
+                bail!("Incomplete network data at {frequency} Hz");
+            }
+
+            let mut matrix = chunk[1..]
+                .chunks_exact(2)
+                .map(|pair| {
+                    match format {
+                        "RI" => Complex64::new(pair[0], pair[1]),
+                        "DB" => {
+                            Complex64::from_polar(10f64.powf(pair[0] / 20.0), pair[1].to_radians())
+                        }
+                        _ => Complex64::from_polar(pair[0], pair[1].to_radians()),
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            // 2-ports are stored in the order S11, S21, S12, S22.
+            if num_ports == 2 {
+                matrix.swap(1, 2);
+            }
+
+            frequencies.push(frequency);
+            parameters.extend(matrix);
+        }
+
+        if frequencies.is_empty() {
+            bail!("Touchstone file contains no network data");
+        }
+
+        Ok(Self {
+            num_ports,
+            reference_impedance,
+            frequencies,
+            parameters,
+        })
+    }
+
+    /// `S_ij` at the `index`th frequency. Ports are numbered from 0.
+    pub fn parameter(&self, index: usize, i: usize, j: usize) -> Complex64 {
+        self.parameters[(index * self.num_ports + i) * self.num_ports + j]
+    }
+
+    /// One curve per S-parameter.
+    pub fn curves(&self, quantity: NetworkQuantity) -> Vec<Curve> {
+        let mut curves = vec![];
+        for i in 0..self.num_ports {
+            for j in 0..self.num_ports {
+                let values = (0..self.frequencies.len())
+                    .map(|index| self.parameter(index, i, j))
+                    .collect::<Vec<_>>();
+                curves.push(Curve {
+                    name: format!("S{}{}", i + 1, j + 1),
+                    points: self
+                        .frequencies
+                        .iter()
+                        .zip(quantity.evaluate(&self.frequencies, &values))
+                        .map(|(frequency, value)| [*frequency, value])
+                        .collect(),
+                });
+            }
+        }
+        curves
+    }
+}
+
+/// A measured antenna pattern.
+#[derive(Clone, Debug, Default)]
+pub struct MeasuredPattern {
+    /// Theta and phi in degrees, and the gain in dBi.
+    pub samples: Vec<[f64; 3]>,
+}
+
+impl MeasuredPattern {
+    /// Column names that are accepted for the gain, in order of preference.
+    const GAIN_COLUMNS: [&str; 5] = ["gain_total", "gain", "gain_db", "dbi", "directivity"];
+
+    /// Reads a CSV file with a header. The columns `theta`, `phi` and a gain
+    /// column (e.g. `gain`) are used, and others ignored. This also reads
+    /// patterns exported with [`RadiationPattern::to_csv`].
+    pub fn parse_csv(text: &str) -> Result<Self, Error> {
+        let split = |line: &str| {
+            line.split([',', ';', '\t'])
+                .map(|cell| cell.trim().trim_matches('"').to_ascii_lowercase())
+                .collect::<Vec<_>>()
+        };
+
+        let mut lines = text
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'));
+        let header = split(lines.next().ok_or_eyre("Pattern file is empty")?);
+        let column = |name: &str| header.iter().position(|column| column == name);
+
+        let theta = column("theta").ok_or_eyre("Pattern file has no theta column")?;
+        let phi = column("phi").ok_or_eyre("Pattern file has no phi column")?;
+        let gain = Self::GAIN_COLUMNS
+            .iter()
+            .find_map(|name| column(name))
+            .ok_or_eyre("Pattern file has no gain column")?;
+
+        let samples = lines
+            .enumerate()
+            .map(|(line_number, line)| {
+                let cells = split(line);
+                let value = |index: usize| {
+                    cells
+                        .get(index)
+                        .and_then(|cell| cell.parse::<f64>().ok())
+                        .ok_or_else(|| eyre!("Invalid value in line {}", line_number + 2))
+                };
+                Ok([value(theta)?, value(phi)?, value(gain)?])
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(Self { samples })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DatasetKind {
+    SParameters,
+    Pattern,
+}
+
+impl DatasetKind {
+    pub const ALL: [Self; 2] = [Self::SParameters, Self::Pattern];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::SParameters => "S-Parameters",
+            Self::Pattern => "Patterns",
+        }
+    }
+
+    fn axis_labels(&self, quantity: NetworkQuantity) -> (&'static str, &'static str) {
+        match self {
+            Self::SParameters => ("Frequency [Hz]", quantity.axis_label()),
+            Self::Pattern => ("Theta [°]", "Gain [dBi]"),
+        }
+    }
+
+    fn unit(&self, quantity: NetworkQuantity) -> &'static str {
+        match self {
+            Self::SParameters => quantity.unit(),
+            Self::Pattern => "dB",
+        }
+    }
+}
+
+/// A named series of points, sorted by x.
+#[derive(Clone, Debug)]
+pub struct Curve {
+    pub name: String,
+    pub points: Vec<[f64; 2]>,
+}
+
+impl Curve {
+    /// Linearly interpolated value at `x`, if it's inside the curve's range.
+    pub fn value_at(&self, x: f64) -> Option<f64> {
+        let index = self.points.partition_point(|point| point[0] < x);
+        let upper = self.points.get(index)?;
+        if upper[0] == x {
+            return Some(upper[1]);
+        }
+        let lower = self.points.get(index.checked_sub(1)?)?;
+        let t = (x - lower[0]) / (upper[0] - lower[0]);
+        Some(lower[1] + t * (upper[1] - lower[1]))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Dataset {
+    pub label: String,
+    pub kind: DatasetKind,
+    pub curves: Vec<Curve>,
+    pub visible: bool,
+
+    /// Reflection coefficients of all ports, for S-parameter datasets.
+    pub reflections: Vec<Reflection>,
+
+    /// Complex parameters of S-parameter datasets, to derive phase and group
+    /// delay from.
+    pub network: Option<Touchstone>,
+}
+
+impl Dataset {
+    /// Loads a Touchstone file (`.sNp`) or a pattern CSV file.
+    pub fn from_path(path: &Path) -> Result<Self, Error> {
+        let label = path.file_name().unwrap_or_default().display().to_string();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        if extension == "csv" {
+            let pattern = MeasuredPattern::parse_csv(&std::fs::read_to_string(path)?)?;
+            Ok(Self::from_measured_pattern(label, &pattern))
+        }
+        else {
+            Ok(Self::from_touchstone(label, &Touchstone::from_path(path)?))
+        }
+    }
+
+    /// One curve in dB per S-parameter.
+    pub fn from_touchstone(label: impl Into<String>, touchstone: &Touchstone) -> Self {
+        Self {
+            label: label.into(),
+            kind: DatasetKind::SParameters,
+            curves: touchstone.curves(NetworkQuantity::Magnitude),
+            visible: true,
+            reflections: (0..touchstone.num_ports)
+                .map(|port| Reflection::from_touchstone(touchstone, port))
+                .collect(),
+            network: Some(touchstone.clone()),
+        }
+    }
+
+    /// One curve over theta per phi cut.
+    pub fn from_measured_pattern(label: impl Into<String>, pattern: &MeasuredPattern) -> Self {
+        // keyed by phi in hundredths of a degree, so cuts sort and compare exactly
+        let mut cuts = BTreeMap::<i64, Vec<[f64; 2]>>::new();
+        for [theta, phi, gain] in &pattern.samples {
+            cuts.entry((phi * 100.0).round() as i64)
+                .or_default()
+                .push([*theta, *gain]);
+        }
+
+        Self::from_cuts(label, cuts)
+    }
+
+    /// Simulated pattern, as total gain in dBi.
+    pub fn from_radiation_pattern(pattern: &RadiationPattern) -> Self {
+        let gains = pattern.gains();
+        let mut cuts = BTreeMap::<i64, Vec<[f64; 2]>>::new();
+        for phi_index in 0..pattern.num_phi() {
+            let phi = pattern.phi(phi_index);
+            let cut = cuts.entry((phi * 100.0).round() as i64).or_default();
+            for theta_index in 0..pattern.num_theta() {
+                let gain = gains[phi_index * pattern.num_theta() + theta_index][2];
+                cut.push([pattern.theta(theta_index), 10.0 * gain.max(1e-20).log10()]);
+            }
+        }
+
+        Self::from_cuts(&pattern.label, cuts)
+    }
+
+    /// The curves showing `quantity`. Only datasets with network parameters
+    /// can show other quantities than their curves.
+    pub fn curves(&self, quantity: NetworkQuantity) -> Cow<'_, [Curve]> {
+        match (&self.network, quantity) {
+            (Some(network), NetworkQuantity::Phase | NetworkQuantity::GroupDelay) => {
+                Cow::Owned(network.curves(quantity))
+            }
+            _ => Cow::Borrowed(&self.curves),
+        }
+    }
+
+    fn from_cuts(label: impl Into<String>, cuts: BTreeMap<i64, Vec<[f64; 2]>>) -> Self {
+        let curves = cuts
+            .into_iter()
+            .map(|(phi, mut points)| {
+                points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+                Curve {
+                    name: format!("φ = {}°", phi as f64 / 100.0),
+                    points,
+                }
+            })
+            .collect();
+
+        Self {
+            label: label.into(),
+            kind: DatasetKind::Pattern,
+            curves,
+            visible: true,
+            reflections: vec![],
+            network: None,
+        }
+    }
+}
+
+/// How much a curve deviates from a reference curve, in the curve's unit.
+#[derive(Clone, Copy, Debug)]
+pub struct DifferenceMetrics {
+    pub rms: f64,
+    pub max: f64,
+    pub num_points: usize,
+}
+
+impl DifferenceMetrics {
+    /// Compares the points of `curve` with the interpolated reference. Points
+    /// outside the reference's range are skipped.
+    pub fn between(reference: &Curve, curve: &Curve) -> Option<Self> {
+        let differences = curve
+            .points
+            .iter()
+            .filter_map(|[x, y]| Some((y - reference.value_at(*x)?).abs()))
+            .collect::<Vec<_>>();
+
+        if differences.is_empty() {
+            return None;
+        }
+
+        let sum_of_squares = differences.iter().map(|d| d * d).sum::<f64>();
+        Some(Self {
+            rms: (sum_of_squares / differences.len() as f64).sqrt(),
+            max: differences.iter().copied().fold(0.0, f64::max),
+            num_points: differences.len(),
+        })
+    }
+}
+
+/// Window that plots measured and simulated datasets.
+#[derive(Debug)]
+pub struct MeasurementsWindow {
+    pub open: bool,
+    datasets: Vec<Dataset>,
+    kind: DatasetKind,
+
+    /// Index of the dataset others are compared against.
+    reference: Option<usize>,
+
+    show_smith_chart: bool,
+
+    /// What is plotted of S-parameters
+    quantity: NetworkQuantity,
+}
+
+impl Default for MeasurementsWindow {
+    fn default() -> Self {
+        Self {
+            open: false,
+            datasets: vec![],
+            kind: DatasetKind::SParameters,
+            reference: None,
+            show_smith_chart: false,
+            quantity: NetworkQuantity::Magnitude,
+        }
+    }
+}
+
+impl MeasurementsWindow {
+    /// Adds a dataset, e.g. a simulated result to compare with measurements.
+    pub fn add_dataset(&mut self, dataset: Dataset) {
+        self.kind = dataset.kind;
+        self.datasets.push(dataset);
+        self.open = true;
+    }
+
+    pub fn import(&mut self, path: &Path) -> Result<(), Error> {
+        self.add_dataset(Dataset::from_path(path)?);
+        Ok(())
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
+
+        egui::Window::new("Measured Data")
+            .movable(true)
+            .default_size([600.0, 400.0])
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let mut path: Option<PathBuf> = None;
+                    ui.label("Import");
+                    if ui
+                        .file_picker_button(&mut path, &FilePickerConfig::Open)
+                        .on_hover_text("Touchstone (.sNp) or pattern CSV file")
+                        .changed()
+                        && let Some(path) = path
+                    {
+                        self.import(&path).ok_or_handle(&*ui);
+                    }
+
+                    ui.separator();
+
+                    for kind in DatasetKind::ALL {
+                        ui.selectable_value(&mut self.kind, kind, kind.label());
+                    }
+
+                    if self.kind == DatasetKind::SParameters {
+                        ui.separator();
+
+                        egui::ComboBox::from_id_salt(ui.id().with("quantity"))
+                            .selected_text(self.quantity.label())
+                            .show_ui(ui, |ui| {
+                                for quantity in NetworkQuantity::ALL {
+                                    ui.selectable_value(
+                                        &mut self.quantity,
+                                        quantity,
+                                        quantity.label(),
+                                    );
+                                }
+                            });
+                    }
+                });
+
+                self.datasets_ui(ui);
+                ui.separator();
+                self.plot_ui(ui);
+                self.metrics_ui(ui);
+
+                if self.kind == DatasetKind::SParameters {
+                    ui.separator();
+                    self.readouts_ui(ui);
+                }
+            });
+
+        self.open = open;
+    }
+
+    fn datasets_ui(&mut self, ui: &mut egui::Ui) {
+        let mut remove = None;
+
+        for (index, dataset) in self.datasets.iter_mut().enumerate() {
+            if dataset.kind != self.kind {
+                continue;
+            }
+
+            ui.push_id(index, |ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut dataset.visible, &dataset.label);
+
+                    let mut is_reference = self.reference == Some(index);
+                    if ui
+                        .checkbox(&mut is_reference, "Reference")
+                        .on_hover_text("Compare the other datasets against this one.")
+                        .changed()
+                    {
+                        self.reference = is_reference.then_some(index);
+                    }
+
+                    if ui.button("Remove").clicked() {
+                        remove = Some(index);
+                    }
+                });
+            });
+        }
+
+        if let Some(index) = remove {
+            self.datasets.remove(index);
+            self.reference = match self.reference {
+                Some(reference) if reference == index => None,
+                Some(reference) if reference > index => Some(reference - 1),
+                reference => reference,
+            };
+        }
+    }
+
+    fn plot_ui(&self, ui: &mut egui::Ui) {
+        let (x_label, y_label) = self.kind.axis_labels(self.quantity);
+
+        egui_plot::Plot::new(ui.id().with("plot"))
+            .legend(egui_plot::Legend::default())
+            .height(250.0)
+            .x_axis_label(x_label)
+            .y_axis_label(y_label)
+            .show(ui, |plot_ui| {
+                for dataset in self.visible_datasets() {
+                    for curve in dataset.curves(self.quantity).iter() {
+                        plot_ui.line(egui_plot::Line::new(
+                            format!("{} {}", dataset.label, curve.name),
+                            curve.points.clone(),
+                        ));
+                    }
+
+                    if self.quantity != NetworkQuantity::Magnitude {
+                        continue;
+                    }
+
+                    // mark resonance and matched band on the reflection curves
+                    for reflection in &dataset.reflections {
+                        let Some(readouts) = reflection.readouts()
+                        else {
+                            continue;
+                        };
+                        let name = format!("{} {}", dataset.label, reflection.name);
+
+                        plot_ui.vline(egui_plot::VLine::new(&name, readouts.resonance));
+                        if let Some((low, high)) = readouts.matched_band {
+                            plot_ui.points(
+                                egui_plot::Points::new(
+                                    &name,
+                                    vec![[low, MATCHED_THRESHOLD], [high, MATCHED_THRESHOLD]],
+                                )
+                                .radius(4.0),
+                            );
+                        }
+                        if let Some(loaded_q) = readouts.loaded_q {
+                            plot_ui.text(
+                                egui_plot::Text::new(
+                                    &name,
+                                    egui_plot::PlotPoint::new(readouts.resonance, readouts.minimum),
+                                    format!("Q = {loaded_q:.1}"),
+                                )
+                                .anchor(egui::Align2::LEFT_TOP),
+                            );
+                        }
+                    }
+                }
+            });
+    }
+
+    /// Resonance, bandwidth and Q of every port, and the Smith chart.
+    fn readouts_ui(&mut self, ui: &mut egui::Ui) {
+        // not using `visible_datasets`, so `show_smith_chart` can still be borrowed
+        let reflections = self
+            .datasets
+            .iter()
+            .filter(|dataset| dataset.kind == self.kind && dataset.visible)
+            .flat_map(|dataset| {
+                dataset.reflections.iter().map(|reflection| {
+                    (format!("{} {}", dataset.label, reflection.name), reflection)
+                })
+            })
+            .collect::<Vec<_>>();
+        if reflections.is_empty() {
+            return;
+        }
+
+        egui::Grid::new(ui.id().with("readouts"))
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Port");
+                ui.strong("Resonance");
+                ui.strong(format!("Matched ({MATCHED_THRESHOLD} dB)"));
+                ui.strong("Bandwidth");
+                ui.strong("Loaded Q");
+                ui.end_row();
+
+                for (name, reflection) in &reflections {
+                    let Some(readouts) = reflection.readouts()
+                    else {
+                        continue;
+                    };
+
+                    ui.label(name);
+                    ui.label(format!(
+                        "{} ({:.2} dB)",
+                        format_frequency(readouts.resonance),
+                        readouts.minimum
+                    ));
+                    if let (Some((low, high)), Some(fractional_bandwidth)) =
+                        (readouts.matched_band, readouts.fractional_bandwidth())
+                    {
+                        ui.label(format!(
+                            "{} - {}",
+                            format_frequency(low),
+                            format_frequency(high)
+                        ));
+                        ui.label(format!("{:.2} %", 100.0 * fractional_bandwidth));
+                    }
+                    else {
+                        ui.label("-");
+                        ui.label("-");
+                    }
+                    ui.label(
+                        readouts
+                            .loaded_q
+                            .map_or_else(|| "-".to_owned(), |q| format!("{q:.1}")),
+                    );
+                    ui.end_row();
+                }
+            });
+
+        ui.horizontal(|ui| {
+            if ui
+                .button("Copy Summary")
+                .on_hover_text("Copy the readouts as Markdown.")
+                .clicked()
+            {
+                let summary = reflections
+                    .iter()
+                    .filter_map(|(name, reflection)| reflection.readouts()?.to_markdown(name).ok())
+                    .collect::<String>();
+                ui.copy_item(summary);
+            }
+            ui.checkbox(&mut self.show_smith_chart, "Smith Chart");
+        });
+
+        if self.show_smith_chart {
+            smith_chart(ui, &reflections);
+        }
+    }
+
+    fn metrics_ui(&self, ui: &mut egui::Ui) {
+        let Some(reference) = self
+            .reference
+            .and_then(|index| self.datasets.get(index))
+            .filter(|reference| reference.kind == self.kind)
+        else {
+            return;
+        };
+
+        egui::Grid::new(ui.id().with("metrics"))
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Dataset");
+                ui.strong("Curve");
+                ui.strong("RMS difference");
+                ui.strong("Max difference");
+                ui.end_row();
+
+                let unit = self.kind.unit(self.quantity);
+                let reference_curves = reference.curves(self.quantity);
+
+                for dataset in self.visible_datasets() {
+                    if std::ptr::eq(dataset, reference) {
+                        continue;
+                    }
+
+                    for curve in dataset.curves(self.quantity).iter() {
+                        let Some(metrics) = reference_curves
+                            .iter()
+                            .find(|reference| reference.name == curve.name)
+                            .and_then(|reference| DifferenceMetrics::between(reference, curve))
+                        else {
+                            continue;
+                        };
+
+                        ui.label(&dataset.label);
+                        ui.label(&curve.name);
+                        ui.label(format!("{:.2} {unit}", metrics.rms))
+                            .on_hover_text(format!("{} points compared", metrics.num_points));
+                        ui.label(format!("{:.2} {unit}", metrics.max));
+                        ui.end_row();
+                    }
+                }
+            });
+    }
+
+    fn visible_datasets(&self) -> impl Iterator<Item = &Dataset> {
+        self.datasets
+            .iter()
+            .filter(|dataset| dataset.kind == self.kind && dataset.visible)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::measured::{
+        Curve,
+        DifferenceMetrics,
+        Touchstone,
+    };
+
+    #[test]
+    fn parses_two_port_touchstone() {
+        let text = "! measured with a VNA
+# MHz S RI R 50
+100 0.5 0 0.1 0 0.2 0 0.3 0
+200 0.4 0
+    0.1 0.1 0.2 0.2 0.3 0.3
+! noise parameters
+50 1.0 0.5 30 0.2
+";
+        let touchstone = Touchstone::parse(text, 2).unwrap();
+
+        assert_eq!(touchstone.frequencies, vec![100e6, 200e6]);
+        assert_eq!(touchstone.parameter(0, 0, 0).re, 0.5);
+        // S21 comes second in the file
+        assert_eq!(touchstone.parameter(0, 1, 0).re, 0.1);
+        assert_eq!(touchstone.parameter(0, 0, 1).re, 0.2);
+        assert_eq!(touchstone.parameter(1, 1, 1).im, 0.3);
+    }
+
+    #[test]
+    fn compares_against_interpolated_reference() {
+        let reference = Curve {
+            name: "S11".to_owned(),
+            points: vec![[0.0, 0.0], [2.0, -2.0]],
+        };
+        let curve = Curve {
+            name: "S11".to_owned(),
+            points: vec![[1.0, -1.5], [2.0, -2.0], [3.0, 0.0]],
+        };
+
+        let metrics = DifferenceMetrics::between(&reference, &curve).unwrap();
+        assert_eq!(metrics.num_points, 2);
+        assert_eq!(metrics.max, 0.5);
+    }
+}
@@ -1,4 +1,39 @@
+pub mod array;
+pub mod attach;
+pub mod batch;
+pub mod benchmark;
+pub mod clock;
 pub mod config;
+pub mod convergence;
+pub mod events;
+pub mod export;
+pub mod extraction;
+pub mod far_field;
+pub mod feed_wizard;
+pub mod ground_plane;
+pub mod hashes;
+pub mod history;
+pub mod inspector;
+pub mod library;
+pub mod line_wizard;
+pub mod measured;
+pub mod motion;
+pub mod network;
+pub mod nf2ff;
 pub mod observer;
+pub mod parameters;
+pub mod pattern;
+pub mod port;
+pub mod probe;
+pub mod refinement;
+pub mod report;
+pub mod run_log;
 pub mod runner;
+pub mod sequence;
+pub mod spectrogram;
+pub mod stream;
+pub mod tuning;
 pub mod ui;
+pub mod units;
+pub mod watchdog;
+pub mod waveform;
@@ -0,0 +1,337 @@
+//! Geometry that moves with a scene parameter.
+//!
+//! A [`ParametricMotion`] rotates or shifts an entity by an amount that
+//! follows a scene parameter, e.g. a rotor angle. The scene itself keeps the
+//! entity at its reference position. The motion is only applied while a solver
+//! run is set up, with the parameter values of that run (see
+//! [`SceneParameters::for_run`]).
+//!
+//! Stepping the parameter through a sequence of runs (see
+//! [`crate::solver::sequence`]) approximates structures that move slowly
+//! compared to the simulated time, like rotating reflectors.
+
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    reflect::ReflectComponent,
+};
+use bevy_reflect::{
+    Reflect,
+    ReflectSerialize,
+    prelude::ReflectDefault,
+};
+use cem_probe::{
+    PropertiesUi,
+    TrackChanges,
+};
+use cem_scene::{
+    Scene,
+    probe::{
+        ComponentName,
+        ReflectComponentUi,
+    },
+    transform::LocalTransform,
+};
+use nalgebra::{
+    Isometry3,
+    Translation3,
+    UnitQuaternion,
+    Vector3,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::solver::parameters::{
+    ParameterValues,
+    SceneParameters,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum MotionKind {
+    /// Rotation about the axis through the entity's origin, in degrees per
+    /// unit of the parameter.
+    #[default]
+    Rotation,
+
+    /// Translation along the axis, in meters per unit of the parameter.
+    Translation,
+}
+
+impl MotionKind {
+    pub const ALL: [Self; 2] = [Self::Rotation, Self::Translation];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Rotation => "Rotation",
+            Self::Translation => "Translation",
+        }
+    }
+
+    fn rate_suffix(&self) -> &'static str {
+        match self {
+            Self::Rotation => " °",
+            Self::Translation => " m",
+        }
+    }
+}
+
+/// Axis of the entity's local frame a motion is about or along.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum MotionAxis {
+    X,
+    Y,
+    #[default]
+    Z,
+}
+
+impl MotionAxis {
+    pub const ALL: [Self; 3] = [Self::X, Self::Y, Self::Z];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::X => "X",
+            Self::Y => "Y",
+            Self::Z => "Z",
+        }
+    }
+
+    pub fn unit_vector(&self) -> Vector3<f32> {
+        match self {
+            Self::X => Vector3::x(),
+            Self::Y => Vector3::y(),
+            Self::Z => Vector3::z(),
+        }
+    }
+}
+
+/// Moves an entity with a scene parameter.
+///
+/// The entity is moved by `rate * (x - reference)`, where `x` is the
+/// parameter's value, in its own frame. Children (e.g. attached probes) move
+/// with it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Component, Reflect)]
+#[reflect(Component, ComponentUi, @ComponentName::new("Parametric Motion"), Default, Serialize)]
+pub struct ParametricMotion {
+    pub kind: MotionKind,
+    pub axis: MotionAxis,
+    pub parameter: String,
+
+    /// Parameter value at which the entity is at its position in the scene.
+    pub reference: f64,
+
+    pub rate: f64,
+}
+
+impl Default for ParametricMotion {
+    fn default() -> Self {
+        Self {
+            kind: MotionKind::Rotation,
+            axis: MotionAxis::Z,
+            parameter: "angle".to_owned(),
+            reference: 0.0,
+            rate: 1.0,
+        }
+    }
+}
+
+impl ParametricMotion {
+    /// Motion relative to the entity's reference position, in its own frame.
+    ///
+    /// Returns `None` if the parameter isn't defined, in which case the entity
+    /// stays at its reference position.
+    pub fn displacement(&self, parameters: &ParameterValues) -> Option<Isometry3<f32>> {
+        let value = parameters.get(&self.parameter)?;
+        let amount = (self.rate * (value - self.reference)) as f32;
+        let axis = self.axis.unit_vector();
+
+        Some(match self.kind {
+            MotionKind::Rotation => {
+                Isometry3::from_parts(
+                    Translation3::identity(),
+                    UnitQuaternion::from_scaled_axis(axis * amount.to_radians()),
+                )
+            }
+            MotionKind::Translation => {
+                Isometry3::from_parts((axis * amount).into(), UnitQuaternion::identity())
+            }
+        })
+    }
+}
+
+impl PropertiesUi for ParametricMotion {
+    type Config = ();
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
+        let _ = config;
+        let mut changes = TrackChanges::default();
+
+        let response = ui
+            .vertical(|ui| {
+                ui.horizontal(|ui| {
+                    let mut changed = false;
+                    egui::ComboBox::from_id_salt("kind")
+                        .selected_text(self.kind.label())
+                        .width(90.0)
+                        .show_ui(ui, |ui| {
+                            for kind in MotionKind::ALL {
+                                changed |= ui
+                                    .selectable_value(&mut self.kind, kind, kind.label())
+                                    .changed();
+                            }
+                        });
+                    egui::ComboBox::from_id_salt("axis")
+                        .selected_text(self.axis.label())
+                        .width(40.0)
+                        .show_ui(ui, |ui| {
+                            for axis in MotionAxis::ALL {
+                                changed |= ui
+                                    .selectable_value(&mut self.axis, axis, axis.label())
+                                    .changed();
+                            }
+                        });
+                    if changed {
+                        changes.mark_changed();
+                    }
+
+                    ui.label("with");
+                    changes.track(
+                        ui.add(egui::TextEdit::singleline(&mut self.parameter).desired_width(80.0)),
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("x0");
+                    changes
+                        .track(ui.add(egui::DragValue::new(&mut self.reference).speed(0.1)))
+                        .on_hover_text("Parameter value at which the entity is where it is now");
+
+                    ui.label("rate");
+                    changes
+                        .track(
+                            ui.add(
+                                egui::DragValue::new(&mut self.rate)
+                                    .speed(0.01)
+                                    .suffix(self.kind.rate_suffix()),
+                            ),
+                        )
+                        .on_hover_text("Movement per unit of the parameter");
+                });
+            })
+            .response;
+
+        changes.propagated(response)
+    }
+}
+
+/// Transforms of moved entities at their reference positions, see
+/// [`apply_motions`].
+#[derive(Debug, Default)]
+#[must_use = "the moved entities stay moved unless restored"]
+pub struct ReferencePositions {
+    transforms: Vec<(Entity, LocalTransform)>,
+}
+
+impl ReferencePositions {
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    /// Moves the entities back to their reference positions.
+    pub fn restore(self, scene: &mut Scene) {
+        if self.is_empty() {
+            return;
+        }
+
+        for (entity, transform) in self.transforms {
+            if let Ok(mut entity) = scene.world.get_entity_mut(entity) {
+                entity.insert(transform);
+            }
+        }
+
+        scene.update();
+    }
+}
+
+/// Moves all entities with a [`ParametricMotion`] to where `overrides` (and
+/// the scene's parameter values) put them.
+///
+/// The scene is updated, so that transforms and spatial queries reflect the
+/// new positions.
+pub fn apply_motions(scene: &mut Scene, overrides: &ParameterValues) -> ReferencePositions {
+    let parameters = SceneParameters::for_run(&scene.world, overrides);
+
+    let transforms = scene
+        .world
+        .query::<(Entity, &ParametricMotion, &mut LocalTransform)>()
+        .iter_mut(&mut scene.world)
+        .filter_map(|(entity, motion, mut transform)| {
+            let displacement = motion.displacement(&parameters)?;
+            let reference = *transform;
+            transform.isometry *= displacement.cast();
+            tracing::debug!(?entity, ?motion, "moving entity for solver run");
+            Some((entity, reference))
+        })
+        .collect::<Vec<_>>();
+
+    let reference_positions = ReferencePositions { transforms };
+    if !reference_positions.is_empty() {
+        scene.update();
+    }
+    reference_positions
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{
+        Point3,
+        Vector3,
+    };
+
+    use crate::solver::{
+        motion::{
+            MotionAxis,
+            MotionKind,
+            ParametricMotion,
+        },
+        parameters::ParameterValues,
+    };
+
+    #[test]
+    fn rotation_follows_parameter() {
+        let motion = ParametricMotion {
+            kind: MotionKind::Rotation,
+            axis: MotionAxis::Z,
+            parameter: "angle".to_owned(),
+            reference: 10.0,
+            rate: 1.0,
+        };
+
+        let parameters = ParameterValues::from([("angle".to_owned(), 100.0)]);
+        let displacement = motion.displacement(&parameters).unwrap();
+        let point = displacement * Point3::new(1.0, 0.0, 0.0);
+        assert!(
+            (point - Point3::new(0.0, 1.0, 0.0)).norm() < 1e-6,
+            "{point}"
+        );
+
+        // without the parameter the entity isn't moved
+        assert!(motion.displacement(&ParameterValues::new()).is_none());
+    }
+
+    #[test]
+    fn translation_follows_parameter() {
+        let motion = ParametricMotion {
+            kind: MotionKind::Translation,
+            axis: MotionAxis::X,
+            parameter: "offset".to_owned(),
+            reference: 0.0,
+            rate: 0.5,
+        };
+
+        let parameters = ParameterValues::from([("offset".to_owned(), 2.0)]);
+        let displacement = motion.displacement(&parameters).unwrap();
+        assert_eq!(displacement.translation.vector, Vector3::new(1.0, 0.0, 0.0));
+    }
+}
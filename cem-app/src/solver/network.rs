@@ -0,0 +1,529 @@
+//! Post-processing of network parameters.
+//!
+//! A [`Reflection`] is the reflection coefficient of one port over frequency,
+//! e.g. the S11 of an antenna. The resonance, matched bandwidth and loaded Q
+//! are derived from it as [`Readouts`], and it can be shown on a
+//! [`smith_chart`].
+//!
+//! Any parameter can be plotted as one of the [`NetworkQuantity`]s. Phase and
+//! group delay are mostly of interest for transmission through filters.
+
+use std::{
+    f64::consts::PI,
+    fmt::Write,
+};
+
+use num::complex::Complex64;
+
+use crate::{
+    Error,
+    color_scheme,
+    solver::measured::Touchstone,
+};
+
+/// Upper bound of |S11| in dB for a port to count as matched.
+pub const MATCHED_THRESHOLD: f64 = -10.0;
+
+#[derive(Clone, Debug)]
+pub struct Reflection {
+    pub name: String,
+    pub reference_impedance: f64,
+
+    /// Frequencies in Hz, ascending
+    pub frequencies: Vec<f64>,
+
+    pub values: Vec<Complex64>,
+}
+
+impl Reflection {
+    /// Reflection coefficient of `port` (numbered from 0).
+    pub fn from_touchstone(touchstone: &Touchstone, port: usize) -> Self {
+        Self {
+            name: format!("S{0}{0}", port + 1),
+            reference_impedance: touchstone.reference_impedance,
+            frequencies: touchstone.frequencies.clone(),
+            values: (0..touchstone.frequencies.len())
+                .map(|index| touchstone.parameter(index, port, port))
+                .collect(),
+        }
+    }
+
+    /// Writes the reflection as a 1-port Touchstone file, with `comments` in
+    /// the header.
+    pub fn to_touchstone(&self, comments: &[String]) -> String {
+        let mut text = String::new();
+        for comment in comments {
+            text.push_str(&format!("! {comment}\n"));
+        }
+        text.push_str(&format!("# Hz S RI R {}\n", self.reference_impedance));
+        for (frequency, value) in self.frequencies.iter().zip(&self.values) {
+            text.push_str(&format!("{frequency} {} {}\n", value.re, value.im));
+        }
+        text
+    }
+
+    /// Impedance seen into the port at the `index`th frequency.
+    pub fn impedance(&self, index: usize) -> Complex64 {
+        let value = self.values[index];
+        self.reference_impedance * (1.0 + value) / (1.0 - value)
+    }
+
+    /// Reflection coefficient at `frequency`, linearly interpolated.
+    pub fn value_at(&self, frequency: f64) -> Option<Complex64> {
+        let index = self.frequencies.partition_point(|f| *f < frequency);
+        let upper = *self.frequencies.get(index)?;
+        if upper == frequency {
+            return Some(self.values[index]);
+        }
+        let lower = *self.frequencies.get(index.checked_sub(1)?)?;
+        let t = (frequency - lower) / (upper - lower);
+        Some(self.values[index - 1] * (1.0 - t) + self.values[index] * t)
+    }
+
+    /// Finds the resonance as the minimum of |S11|, and the bandwidths around
+    /// it.
+    ///
+    /// Returns `None` if there are no samples.
+    pub fn readouts(&self) -> Option<Readouts> {
+        let (index, minimum) = self
+            .values
+            .iter()
+            .map(|value| value.norm())
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+        let resonance = self.frequencies[index];
+
+        let return_loss = self
+            .values
+            .iter()
+            .map(|value| to_db(value.norm()))
+            .collect::<Vec<_>>();
+        let matched_band = self.band(&return_loss, index, MATCHED_THRESHOLD);
+
+        // the loaded Q is the resonance frequency over the bandwidth in which
+        // the port accepts at least half the power it accepts at resonance.
+        let accepted_power = self
+            .values
+            .iter()
+            .map(|value| -(1.0 - value.norm_sqr()))
+            .collect::<Vec<_>>();
+        let loaded_q = self
+            .band(&accepted_power, index, 0.5 * accepted_power[index])
+            .filter(|(low, high)| high > low)
+            .map(|(low, high)| resonance / (high - low));
+
+        Some(Readouts {
+            resonance,
+            minimum: to_db(minimum),
+            matched_band,
+            loaded_q,
+        })
+    }
+
+    /// Frequencies around `index` at which `values` rises above `threshold`,
+    /// interpolated between samples.
+    ///
+    /// Returns `None` if `values[index]` is above the threshold, or the band
+    /// extends beyond the sweep.
+    fn band(&self, values: &[f64], index: usize, threshold: f64) -> Option<(f64, f64)> {
+        if values[index] > threshold {
+            return None;
+        }
+
+        let crossing = |indices: &mut dyn Iterator<Item = usize>| {
+            let mut previous = index;
+            for current in indices {
+                if values[current] > threshold {
+                    let t = (threshold - values[previous]) / (values[current] - values[previous]);
+                    return Some(
+                        self.frequencies[previous]
+                            + t * (self.frequencies[current] - self.frequencies[previous]),
+                    );
+                }
+                previous = current;
+            }
+            None
+        };
+
+        let low = crossing(&mut (0..index).rev())?;
+        let high = crossing(&mut (index + 1..values.len()))?;
+        Some((low, high))
+    }
+}
+
+/// Characteristics of a port around its resonance.
+#[derive(Clone, Copy, Debug)]
+pub struct Readouts {
+    /// Frequency at which |S11| is minimal
+    pub resonance: f64,
+
+    /// |S11| at resonance in dB
+    pub minimum: f64,
+
+    /// Band around the resonance in which |S11| is below
+    /// [`MATCHED_THRESHOLD`]
+    pub matched_band: Option<(f64, f64)>,
+
+    pub loaded_q: Option<f64>,
+}
+
+impl Readouts {
+    /// Matched bandwidth relative to the resonance frequency.
+    pub fn fractional_bandwidth(&self) -> Option<f64> {
+        self.matched_band
+            .map(|(low, high)| (high - low) / self.resonance)
+    }
+
+    /// Markdown list of the readouts.
+    pub fn to_markdown(&self, name: &str) -> Result<String, Error> {
+        let mut markdown = String::new();
+        writeln!(markdown, "- {name}")?;
+        writeln!(
+            markdown,
+            "  - Resonance: {} ({:.2} dB)",
+            format_frequency(self.resonance),
+            self.minimum
+        )?;
+        match (self.matched_band, self.fractional_bandwidth()) {
+            (Some((low, high)), Some(fractional_bandwidth)) => {
+                writeln!(
+                    markdown,
+                    "  - Matched ({MATCHED_THRESHOLD} dB): {} to {} ({:.2} %)",
+                    format_frequency(low),
+                    format_frequency(high),
+                    100.0 * fractional_bandwidth
+                )?;
+            }
+            _ => writeln!(markdown, "  - Matched ({MATCHED_THRESHOLD} dB): no")?,
+        }
+        if let Some(loaded_q) = self.loaded_q {
+            writeln!(markdown, "  - Loaded Q: {loaded_q:.1}")?;
+        }
+        Ok(markdown)
+    }
+}
+
+/// What is plotted of a network parameter over frequency.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NetworkQuantity {
+    #[default]
+    Magnitude,
+    Phase,
+    GroupDelay,
+}
+
+impl NetworkQuantity {
+    pub const ALL: [Self; 3] = [Self::Magnitude, Self::Phase, Self::GroupDelay];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Magnitude => "Magnitude",
+            Self::Phase => "Phase",
+            Self::GroupDelay => "Group Delay",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Self::Magnitude => "dB",
+            Self::Phase => "°",
+            Self::GroupDelay => "ns",
+        }
+    }
+
+    pub fn axis_label(&self) -> &'static str {
+        match self {
+            Self::Magnitude => "|S| [dB]",
+            Self::Phase => "Phase [°]",
+            Self::GroupDelay => "Group Delay [ns]",
+        }
+    }
+
+    /// The quantity at every sample, in [`Self::unit`]s.
+    pub fn evaluate(&self, frequencies: &[f64], values: &[Complex64]) -> Vec<f64> {
+        match self {
+            Self::Magnitude => values.iter().map(|value| to_db(value.norm())).collect(),
+            Self::Phase => unwrapped_phase(values),
+            Self::GroupDelay => {
+                group_delay(frequencies, values)
+                    .into_iter()
+                    .map(|delay| delay * 1e9)
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Phase in degrees, without the jumps at ±180°.
+pub fn unwrapped_phase(values: &[Complex64]) -> Vec<f64> {
+    let mut offset = 0.0;
+    let mut previous: Option<f64> = None;
+
+    values
+        .iter()
+        .map(|value| {
+            let phase = value.arg().to_degrees();
+            if let Some(previous) = previous {
+                // whole turns, so that the step from the previous sample is at most 180°
+                offset -= 360.0 * ((phase - previous) / 360.0).round();
+            }
+            previous = Some(phase);
+            phase + offset
+        })
+        .collect()
+}
+
+/// Group delay `-dφ/dω` in seconds at every sample, from the unwrapped phase.
+///
+/// Derivatives are central differences, and one-sided at the ends of the
+/// sweep.
+pub fn group_delay(frequencies: &[f64], values: &[Complex64]) -> Vec<f64> {
+    let phase = unwrapped_phase(values)
+        .into_iter()
+        .map(f64::to_radians)
+        .collect::<Vec<_>>();
+    let n = phase.len();
+
+    (0..n)
+        .map(|index| {
+            if n < 2 {
+                return 0.0;
+            }
+            let previous = index.saturating_sub(1);
+            let next = (index + 1).min(n - 1);
+            let d_omega = 2.0 * PI * (frequencies[next] - frequencies[previous]);
+            -(phase[next] - phase[previous]) / d_omega
+        })
+        .collect()
+}
+
+/// Formats a frequency in Hz with a unit prefix, e.g. `2.450 GHz`.
+pub fn format_frequency(frequency: f64) -> String {
+    let (scale, unit) = match frequency.abs() {
+        f if f >= 1e9 => (1e-9, "GHz"),
+        f if f >= 1e6 => (1e-6, "MHz"),
+        f if f >= 1e3 => (1e-3, "kHz"),
+        _ => (1.0, "Hz"),
+    };
+    format!("{:.3} {unit}", frequency * scale)
+}
+
+pub fn to_db(magnitude: f64) -> f64 {
+    20.0 * magnitude.max(1e-10).log10()
+}
+
+/// Shows reflection coefficients on an impedance Smith chart.
+///
+/// The resonance is marked with a filled circle, and the edges of the matched
+/// band with hollow circles. Hovering shows the impedance at the closest
+/// sample.
+pub fn smith_chart(ui: &mut egui::Ui, traces: &[(String, &Reflection)]) -> egui::Response {
+    let size = ui.available_width().min(400.0);
+    let (response, painter) = ui.allocate_painter(egui::Vec2::splat(size), egui::Sense::hover());
+    let center = response.rect.center();
+    let radius = 0.5 * size - 10.0;
+
+    let to_screen =
+        |gamma: Complex64| center + radius * egui::vec2(gamma.re as f32, -gamma.im as f32);
+    // normalized impedance to reflection coefficient
+    let to_gamma = |z: Complex64| (z - 1.0) / (z + 1.0);
+
+    let visuals = ui.visuals();
+    let grid_stroke = egui::Stroke::new(1.0, visuals.weak_text_color());
+    let text_color = visuals.text_color();
+    let font = egui::FontId::proportional(10.0);
+
+    painter.circle_stroke(center, radius, grid_stroke);
+    painter.line_segment(
+        [
+            center - egui::vec2(radius, 0.0),
+            center + egui::vec2(radius, 0.0),
+        ],
+        grid_stroke,
+    );
+
+    // lines of constant resistance and reactance, sampled in the impedance plane
+    let samples = (0..=200)
+        .map(|i| 10f64.powf(-2.0 + 5.0 * i as f64 / 200.0))
+        .collect::<Vec<_>>();
+    for value in [0.2, 0.5, 1.0, 2.0, 5.0] {
+        painter.add(egui::Shape::line(
+            samples
+                .iter()
+                .rev()
+                .map(|x| -x)
+                .chain(std::iter::once(0.0))
+                .chain(samples.iter().copied())
+                .map(|x| to_screen(to_gamma(Complex64::new(value, x))))
+                .collect(),
+            grid_stroke,
+        ));
+
+        for reactance in [-value, value] {
+            painter.add(egui::Shape::line(
+                std::iter::once(0.0)
+                    .chain(samples.iter().copied())
+                    .map(|r| to_screen(to_gamma(Complex64::new(r, reactance))))
+                    .collect(),
+                grid_stroke,
+            ));
+        }
+
+        painter.text(
+            to_screen(to_gamma(Complex64::new(value, 0.0))),
+            egui::Align2::LEFT_BOTTOM,
+            format!("{value}"),
+            font.clone(),
+            text_color,
+        );
+    }
+
+    let mut closest: Option<(f32, usize, usize)> = None;
+    let pointer = response.hover_pos();
+
+    for (trace_index, (label, reflection)) in traces.iter().enumerate() {
+        let color = color_scheme::palette().categorical(trace_index);
+        let points = reflection
+            .values
+            .iter()
+            .map(|value| to_screen(*value))
+            .collect::<Vec<_>>();
+
+        if let Some(pointer) = pointer {
+            for (index, point) in points.iter().enumerate() {
+                let distance = point.distance(pointer);
+                if closest.is_none_or(|(closest, _, _)| distance < closest) {
+                    closest = Some((distance, trace_index, index));
+                }
+            }
+        }
+
+        painter.add(egui::Shape::line(points, egui::Stroke::new(2.0, color)));
+
+        if let Some(readouts) = reflection.readouts() {
+            if let Some(value) = reflection.value_at(readouts.resonance) {
+                painter.circle_filled(to_screen(value), 4.0, color);
+            }
+            if let Some((low, high)) = readouts.matched_band {
+                for frequency in [low, high] {
+                    if let Some(value) = reflection.value_at(frequency) {
+                        painter.circle_stroke(to_screen(value), 4.0, egui::Stroke::new(1.5, color));
+                    }
+                }
+            }
+        }
+
+        painter.text(
+            response.rect.left_top() + egui::vec2(0.0, 14.0 * trace_index as f32),
+            egui::Align2::LEFT_TOP,
+            label,
+            egui::FontId::proportional(12.0),
+            color,
+        );
+    }
+
+    response.on_hover_ui_at_pointer(|ui| {
+        if let Some((distance, trace_index, index)) = closest
+            && distance < 20.0
+        {
+            let (label, reflection) = &traces[trace_index];
+            let impedance = reflection.impedance(index);
+            ui.label(label);
+            ui.label(format_frequency(reflection.frequencies[index]));
+            ui.label(format!(
+                "Z = {:.2} {} j{:.2} Ω",
+                impedance.re,
+                if impedance.im < 0.0 { '-' } else { '+' },
+                impedance.im.abs()
+            ));
+            ui.label(format!(
+                "|S| = {:.2} dB",
+                to_db(reflection.values[index].norm())
+            ));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use num::complex::Complex64;
+
+    use crate::solver::{
+        measured::Touchstone,
+        network::{
+            Reflection,
+            group_delay,
+            unwrapped_phase,
+        },
+    };
+
+    /// Series RLC resonator with Q = 10 at 1 GHz, matched to 50 Ω.
+    fn series_resonator() -> Reflection {
+        let (resistance, resonance, q) = (50.0, 1e9, 10.0);
+        let inductance = q * resistance / (2.0 * PI * resonance);
+        let capacitance = 1.0 / ((2.0 * PI * resonance).powi(2) * inductance);
+
+        let frequencies = (0..=2000)
+            .map(|i| 0.5e9 + i as f64 * 0.5e6)
+            .collect::<Vec<_>>();
+        let values = frequencies
+            .iter()
+            .map(|frequency| {
+                let omega = 2.0 * PI * frequency;
+                let impedance =
+                    Complex64::new(resistance, omega * inductance - 1.0 / (omega * capacitance));
+                (impedance - 50.0) / (impedance + 50.0)
+            })
+            .collect();
+
+        Reflection {
+            name: "S11".to_owned(),
+            reference_impedance: 50.0,
+            frequencies,
+            values,
+        }
+    }
+
+    #[test]
+    fn group_delay_of_delay_line() {
+        let delay = 2e-9;
+        let frequencies = (1..=100).map(|i| i as f64 * 1e8).collect::<Vec<_>>();
+        let values = frequencies
+            .iter()
+            .map(|frequency| Complex64::from_polar(1.0, -2.0 * PI * frequency * delay))
+            .collect::<Vec<_>>();
+
+        // the phase wraps many times over the sweep
+        let phase = unwrapped_phase(&values);
+        assert!(phase.last().unwrap() < &-7000.0);
+
+        for group_delay in group_delay(&frequencies, &values) {
+            assert!((group_delay - delay).abs() < 1e-15);
+        }
+    }
+
+    #[test]
+    fn readouts_of_series_resonator() {
+        let readouts = series_resonator().readouts().unwrap();
+
+        assert!((readouts.resonance - 1e9).abs() < 1e6);
+        // loaded with the 50 Ω port the Q halves
+        let loaded_q = readouts.loaded_q.unwrap();
+        assert!((loaded_q - 5.0).abs() < 0.1, "loaded Q = {loaded_q}");
+
+        let (low, high) = readouts.matched_band.unwrap();
+        assert!(low < 1e9 && high > 1e9);
+    }
+
+    #[test]
+    fn touchstone_round_trip() {
+        let reflection = series_resonator();
+        let text = reflection.to_touchstone(&["Port 1".to_owned()]);
+
+        let parsed = Reflection::from_touchstone(&Touchstone::parse(&text, 1).unwrap(), 0);
+        assert_eq!(parsed.frequencies, reflection.frequencies);
+        assert_eq!(parsed.values, reflection.values);
+    }
+}
@@ -0,0 +1,639 @@
+//! Near-to-far-field transformation.
+//!
+//! An [`Nf2ffBox`] is a closed surface around the radiating structure. While
+//! the solver runs, the fields on its faces are Fourier transformed at the
+//! box's frequency. The equivalent surface currents `J = n × H` and
+//! `M = -n × E` radiate the same far field as the structure inside, which is
+//! computed on demand as a [`RadiationPattern`].
+//!
+//! Fields are sampled at cell positions, ignoring the staggering of the Yee
+//! grid. The box should be a few cells away from the structure, and from the
+//! boundary of the solver volume.
+
+use std::{
+    f64::consts::PI,
+    sync::Arc,
+};
+
+use bevy_ecs::component::Component;
+use cem_probe::{
+    PropertiesUi,
+    PropertiesUiExt,
+    TrackChanges,
+    label_and_value,
+};
+use cem_scene::transform::GlobalTransform;
+use cem_solver::{
+    Field,
+    FieldComponent,
+    FieldView,
+    material::UnitSystem,
+};
+use nalgebra::{
+    Point3,
+    UnitQuaternion,
+    Vector3,
+    Vector4,
+};
+use num::complex::Complex64;
+use parking_lot::Mutex;
+
+use crate::{
+    error::ErrorHandler,
+    jobs::{
+        Cancelled,
+        JobHandle,
+        Jobs,
+    },
+    solver::{
+        pattern::{
+            PolarPlotConfig,
+            RadiationPattern,
+            pattern_3d_view,
+            polar_pattern_plot,
+        },
+        runner::CoordinateTransformations,
+    },
+};
+
+/// Records the fields on the faces of a box, to compute far-field patterns.
+///
+/// The pattern's axes are the entity's local axes.
+#[derive(Clone, Debug, Component)]
+pub struct Nf2ffBox {
+    /// Half the size of the box in the entity's local frame
+    pub half_extents: Vector3<f32>,
+
+    /// Frequency at which the far field is computed
+    pub frequency: f64,
+
+    /// Number of theta samples of the pattern
+    pub num_theta: usize,
+
+    /// Number of phi samples of the pattern
+    pub num_phi: usize,
+
+    /// Sample every N ticks
+    pub interval: usize,
+}
+
+impl Default for Nf2ffBox {
+    fn default() -> Self {
+        Self {
+            half_extents: Vector3::repeat(0.4),
+            frequency: 1.0,
+            num_theta: 37,
+            num_phi: 72,
+            interval: 1,
+        }
+    }
+}
+
+impl PropertiesUi for Nf2ffBox {
+    type Config = ();
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
+        let _ = config;
+        let mut changes = TrackChanges::default();
+
+        let response = egui::Frame::new()
+            .show(ui, |ui| {
+                label_and_value(ui, "Half Extents", &mut changes, &mut self.half_extents);
+
+                ui.horizontal(|ui| {
+                    ui.label("Frequency");
+                    changes.track(
+                        ui.add(
+                            egui::DragValue::new(&mut self.frequency)
+                                .range(0.0..=f64::INFINITY)
+                                .speed(0.01),
+                        ),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Theta Samples");
+                    changes.track(ui.add(egui::DragValue::new(&mut self.num_theta).range(2..=721)));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Phi Samples");
+                    changes.track(ui.add(egui::DragValue::new(&mut self.num_phi).range(1..=1440)));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Every N Ticks");
+                    changes.track(
+                        ui.add(egui::DragValue::new(&mut self.interval).range(1..=usize::MAX)),
+                    );
+                });
+            })
+            .response;
+
+        changes.propagated(response)
+    }
+}
+
+/// An element of the box's surface.
+#[derive(Clone, Copy, Debug)]
+pub struct SurfaceCurrent {
+    /// Position relative to the center of the box, in the pattern's frame
+    pub position: Vector3<f64>,
+
+    pub area: f64,
+
+    /// Electric surface current density
+    pub j: Vector3<Complex64>,
+
+    /// Magnetic surface current density
+    pub m: Vector3<Complex64>,
+}
+
+/// Computes the far field radiated by surface currents into `pattern`.
+pub fn radiate(
+    currents: &[SurfaceCurrent],
+    wavenumber: f64,
+    vacuum_impedance: f64,
+    pattern: &mut RadiationPattern,
+) {
+    for phi_index in 0..pattern.num_phi() {
+        let phi = pattern.phi(phi_index).to_radians();
+
+        for theta_index in 0..pattern.num_theta() {
+            let theta = pattern.theta(theta_index).to_radians();
+            let [e_theta, e_phi] = far_field(currents, wavenumber, vacuum_impedance, theta, phi);
+            pattern.set(theta_index, phi_index, e_theta, e_phi);
+        }
+    }
+}
+
+/// Far-field components `r * E_theta` and `r * E_phi` radiated by surface
+/// currents into a direction (theta, phi in radians).
+pub fn far_field(
+    currents: &[SurfaceCurrent],
+    wavenumber: f64,
+    vacuum_impedance: f64,
+    theta: f64,
+    phi: f64,
+) -> [Complex64; 2] {
+    let dot = |a: &Vector3<Complex64>, b: &Vector3<f64>| a.x * b.x + a.y * b.y + a.z * b.z;
+
+    let (sin_phi, cos_phi) = phi.sin_cos();
+    let (sin_theta, cos_theta) = theta.sin_cos();
+
+    let direction = Vector3::new(sin_theta * cos_phi, sin_theta * sin_phi, cos_theta);
+    let theta_hat = Vector3::new(cos_theta * cos_phi, cos_theta * sin_phi, -sin_theta);
+    let phi_hat = Vector3::new(-sin_phi, cos_phi, 0.0);
+
+    // radiation vectors of the electric and magnetic currents
+    let mut n = Vector3::<Complex64>::zeros();
+    let mut l = Vector3::<Complex64>::zeros();
+    for current in currents {
+        let phase =
+            Complex64::from_polar(current.area, wavenumber * direction.dot(&current.position));
+        n += current.j * phase;
+        l += current.m * phase;
+    }
+
+    let factor = Complex64::new(0.0, wavenumber / (4.0 * PI));
+    let e_theta = -factor * (dot(&l, &phi_hat) + vacuum_impedance * dot(&n, &theta_hat));
+    let e_phi = factor * (dot(&l, &theta_hat) - vacuum_impedance * dot(&n, &phi_hat));
+
+    [e_theta, e_phi]
+}
+
+/// Vacuum impedance and wavenumber at `frequency` in a unit system.
+pub(super) fn vacuum_impedance_and_wavenumber(units: UnitSystem, frequency: f64) -> (f64, f64) {
+    let constants = units.physical_constants();
+    let vacuum_impedance = (constants.vacuum_permeability / constants.vacuum_permittivity).sqrt();
+    let wavenumber = 2.0 * PI * frequency / constants.speed_of_light();
+    (vacuum_impedance, wavenumber)
+}
+
+/// A face of the box, with its cells in the solver lattice.
+#[derive(Clone, Debug)]
+struct Face {
+    min: Point3<usize>,
+    max: Point3<usize>,
+
+    /// Outward normal in the solver frame
+    normal: Vector3<f64>,
+
+    /// Area of a cell on this face
+    cell_area: f64,
+}
+
+impl Face {
+    fn points(&self) -> impl Iterator<Item = Point3<usize>> + '_ {
+        (self.min.x..=self.max.x).flat_map(move |x| {
+            (self.min.y..=self.max.y)
+                .flat_map(move |y| (self.min.z..=self.max.z).map(move |z| Point3::new(x, y, z)))
+        })
+    }
+}
+
+/// The sampled cells on the faces of an NF2FF box.
+#[derive(Debug)]
+pub(super) struct BoxSurface {
+    faces: Vec<Face>,
+
+    /// Rotation from the solver frame into the pattern's frame
+    rotation: UnitQuaternion<f64>,
+
+    /// Position of every sampled cell relative to the box's center, in the
+    /// pattern's frame. In the order of [`Face::points`].
+    positions: Vec<Vector3<f64>>,
+
+    /// Normal and cell area of every sampled cell, in the solver frame.
+    normals: Vec<(Vector3<f64>, f64)>,
+}
+
+impl BoxSurface {
+    /// Returns `None` if the box doesn't cover at least one cell along each
+    /// axis of the solver volume.
+    pub fn new(
+        half_extents: &Vector3<f32>,
+        transform: &GlobalTransform,
+        coordinate_transformations: &CoordinateTransformations,
+    ) -> Option<Self> {
+        let to_solver = &coordinate_transformations.transform_from_world_to_solver;
+        let to_world = &coordinate_transformations.transform_from_solver_to_world;
+
+        // bounding box of the box's corners in the lattice
+        let mut min = Vector3::repeat(f64::INFINITY);
+        let mut max = Vector3::repeat(f64::NEG_INFINITY);
+        for i in 0..8 {
+            let corner = Point3::from(half_extents.zip_map(
+                &Vector3::new(i & 1, (i >> 1) & 1, (i >> 2) & 1),
+                |half_extent, bit| if bit == 0 { -half_extent } else { half_extent },
+            ));
+            let corner = transform.precise_isometry() * corner.cast::<f64>();
+            let corner = Point3::from_homogeneous(to_solver * corner.to_homogeneous())?;
+            min = min.inf(&corner.coords);
+            max = max.sup(&corner.coords);
+        }
+
+        let lattice_max = coordinate_transformations
+            .lattice_size
+            .map(|size| size.saturating_sub(1) as f64);
+        let min = min.map(|c| c.round().max(0.0)).inf(&lattice_max);
+        let max = max.map(|c| c.round().max(0.0)).inf(&lattice_max);
+        if (0..3).any(|axis| min[axis] >= max[axis]) {
+            return None;
+        }
+        let min = Point3::from(min.map(|c| c as usize));
+        let max = Point3::from(max.map(|c| c as usize));
+
+        let cell_size =
+            Vector3::from_fn(|axis, _| (to_world * Vector4::ith(axis, 1.0)).xyz().norm());
+
+        let mut faces = vec![];
+        for axis in 0..3 {
+            let cell_area = cell_size[(axis + 1) % 3] * cell_size[(axis + 2) % 3];
+            for (side, sign) in [(min[axis], -1.0), (max[axis], 1.0)] {
+                let mut face_min = min;
+                let mut face_max = max;
+                face_min[axis] = side;
+                face_max[axis] = side;
+                faces.push(Face {
+                    min: face_min,
+                    max: face_max,
+                    normal: Vector3::ith(axis, sign),
+                    cell_area,
+                });
+            }
+        }
+
+        // positions relative to the box's center, rotated into the entity's frame
+        let center = transform.precise_position();
+        let to_pattern = transform.precise_isometry().rotation.inverse();
+        let mut positions = vec![];
+        let mut normals = vec![];
+        for face in &faces {
+            for point in face.points() {
+                let position =
+                    coordinate_transformations.transform_point_from_solver_to_world(&point);
+                positions.push(to_pattern * (position - center));
+                normals.push((face.normal, face.cell_area));
+            }
+        }
+
+        Some(Self {
+            faces,
+            rotation: to_pattern * coordinate_transformations.rotation_from_solver_to_world,
+            positions,
+            normals,
+        })
+    }
+
+    pub fn num_cells(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Calls `f` with the index, E and H field of every sampled cell.
+    pub fn sample<I>(
+        &self,
+        instance: &I,
+        state: &I::State,
+        mut f: impl FnMut(usize, Vector3<f64>, Vector3<f64>),
+    ) where
+        I: Field<Point3<usize>>,
+    {
+        let mut index = 0;
+
+        for face in &self.faces {
+            let e_view = instance.field(state, face.min..=face.max, FieldComponent::E);
+            let h_view = instance.field(state, face.min..=face.max, FieldComponent::H);
+
+            for point in face.points() {
+                let e = e_view.at(&point).unwrap_or_default();
+                let h = h_view.at(&point).unwrap_or_default();
+                f(index, e, h);
+                index += 1;
+            }
+        }
+    }
+
+    /// Equivalent surface currents of the Fourier transformed fields at every
+    /// sampled cell, in the pattern's frame.
+    pub fn currents(
+        &self,
+        e: &[Vector3<Complex64>],
+        h: &[Vector3<Complex64>],
+    ) -> Vec<SurfaceCurrent> {
+        let rotate = |vector: Vector3<Complex64>| {
+            let re = self.rotation * vector.map(|c| c.re);
+            let im = self.rotation * vector.map(|c| c.im);
+            re.zip_map(&im, Complex64::new)
+        };
+
+        self.positions
+            .iter()
+            .zip(&self.normals)
+            .zip(e.iter().zip(h))
+            .map(|((position, (normal, area)), (e, h))| {
+                let normal = normal.map(Complex64::from);
+                SurfaceCurrent {
+                    position: *position,
+                    area: *area,
+                    j: rotate(normal.cross(h)),
+                    m: rotate(-normal.cross(e)),
+                }
+            })
+            .collect()
+    }
+
+    /// Time-averaged power flowing out of the box, `1/2 Re ∮ (E × H*) · n dA`,
+    /// for the Fourier transformed fields at every sampled cell.
+    pub fn outgoing_power(&self, e: &[Vector3<Complex64>], h: &[Vector3<Complex64>]) -> f64 {
+        self.normals
+            .iter()
+            .zip(e.iter().zip(h))
+            .map(|((normal, area), (e, h))| {
+                let poynting = e.cross(&h.map(|c| c.conj())).map(|c| c.re);
+                0.5 * poynting.dot(normal) * area
+            })
+            .sum()
+    }
+}
+
+#[derive(Debug)]
+struct Nf2ffData {
+    frequency: f64,
+    num_theta: usize,
+    num_phi: usize,
+    units: UnitSystem,
+
+    surface: Arc<BoxSurface>,
+
+    /// Fourier transformed E and H fields of every sampled cell.
+    e: Vec<Vector3<Complex64>>,
+    h: Vec<Vector3<Complex64>>,
+
+    num_samples: usize,
+}
+
+/// Handle to the transformed fields of a running NF2FF box.
+#[derive(Clone, Debug)]
+pub struct Nf2ffOutput {
+    data: Arc<Mutex<Nf2ffData>>,
+}
+
+impl Nf2ffOutput {
+    /// Number of time samples taken so far.
+    pub fn num_samples(&self) -> usize {
+        self.data.lock().num_samples
+    }
+
+    pub fn frequency(&self) -> f64 {
+        self.data.lock().frequency
+    }
+
+    /// Computes the far-field pattern from the fields sampled so far.
+    ///
+    /// Returns `None` if nothing was sampled yet.
+    pub fn pattern(&self, label: impl Into<String>) -> Option<RadiationPattern> {
+        // copy the currents, so the solver isn't blocked while we integrate
+        let (currents, frequency, num_theta, num_phi, units) = {
+            let data = self.data.lock();
+            if data.num_samples == 0 {
+                return None;
+            }
+
+            (
+                data.surface.currents(&data.e, &data.h),
+                data.frequency,
+                data.num_theta,
+                data.num_phi,
+                data.units,
+            )
+        };
+
+        let (vacuum_impedance, wavenumber) = vacuum_impedance_and_wavenumber(units, frequency);
+
+        let mut pattern =
+            RadiationPattern::new(label, frequency, num_theta, num_phi, vacuum_impedance);
+        radiate(&currents, wavenumber, vacuum_impedance, &mut pattern);
+        Some(pattern)
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct Nf2ffInstance {
+    pub label: String,
+    surface: Arc<BoxSurface>,
+    angular_frequency: f64,
+    interval: usize,
+    output: Nf2ffOutput,
+}
+
+impl Nf2ffInstance {
+    pub fn new(
+        label: String,
+        nf2ff_box: &Nf2ffBox,
+        transform: &GlobalTransform,
+        coordinate_transformations: &CoordinateTransformations,
+        units: UnitSystem,
+    ) -> Option<Self> {
+        let surface = Arc::new(BoxSurface::new(
+            &nf2ff_box.half_extents,
+            transform,
+            coordinate_transformations,
+        )?);
+
+        let num_cells = surface.num_cells();
+        let output = Nf2ffOutput {
+            data: Arc::new(Mutex::new(Nf2ffData {
+                frequency: nf2ff_box.frequency,
+                num_theta: nf2ff_box.num_theta.max(2),
+                num_phi: nf2ff_box.num_phi.max(1),
+                units,
+                surface: surface.clone(),
+                e: vec![Vector3::zeros(); num_cells],
+                h: vec![Vector3::zeros(); num_cells],
+                num_samples: 0,
+            })),
+        };
+
+        Some(Self {
+            label,
+            surface,
+            angular_frequency: 2.0 * PI * nf2ff_box.frequency,
+            interval: nf2ff_box.interval.max(1),
+            output,
+        })
+    }
+
+    pub fn output(&self) -> Nf2ffOutput {
+        self.output.clone()
+    }
+
+    pub fn interval(&self) -> usize {
+        self.interval
+    }
+
+    /// Adds the current fields to the running Fourier transform.
+    pub fn run<I>(&mut self, instance: &I, state: &I::State, time: f64)
+    where
+        I: Field<Point3<usize>>,
+    {
+        let kernel = Complex64::from_polar(1.0, -self.angular_frequency * time);
+
+        let mut data = self.output.data.lock();
+        let data = &mut *data;
+
+        self.surface.sample(instance, state, |index, e, h| {
+            data.e[index] += e.map(|c| kernel * c);
+            data.h[index] += h.map(|c| kernel * c);
+        });
+
+        data.num_samples += 1;
+    }
+}
+
+/// Per-box view settings and the last computed pattern, kept in egui's memory.
+#[derive(Clone, Debug, Default)]
+struct Nf2ffView {
+    pattern: Option<Arc<RadiationPattern>>,
+    polar: PolarPlotConfig,
+    show_3d: bool,
+
+    /// Job computing the next pattern
+    job: Option<JobHandle<Option<RadiationPattern>>>,
+}
+
+/// Computes the pattern of a running NF2FF box on request, and plots it.
+pub fn nf2ff_pattern_ui(
+    ui: &mut egui::Ui,
+    id_salt: impl std::hash::Hash,
+    label: &str,
+    output: &Nf2ffOutput,
+    units: UnitSystem,
+) {
+    let id = ui.id().with(&id_salt);
+    let mut view = ui.data_mut(|data| data.get_temp_mut_or_default::<Nf2ffView>(id).clone());
+
+    if let Some(result) = view.job.as_ref().and_then(JobHandle::try_take_result) {
+        view.job = None;
+        match result {
+            Ok(pattern) => view.pattern = pattern.map(Arc::new),
+            Err(error) if error.is::<Cancelled>() => {
+                tracing::debug!("pattern computation cancelled");
+            }
+            Err(error) => ui.handle_error(error),
+        }
+    }
+
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "f = {} {}, {} samples",
+            output.frequency(),
+            units.frequency_unit(),
+            output.num_samples()
+        ));
+        if ui
+            .add_enabled(view.job.is_none(), egui::Button::new("Compute Pattern"))
+            .on_hover_text("Transform the fields sampled so far into the far field.")
+            .clicked()
+        {
+            let output = output.clone();
+            let label = label.to_owned();
+            view.job = Some(
+                Jobs::from_ctx(ui.ctx())
+                    .spawn("Compute pattern", move |_job| Ok(output.pattern(label))),
+            );
+        }
+        if view.job.is_some() {
+            ui.spinner();
+        }
+        ui.checkbox(&mut view.show_3d, "3D");
+    });
+
+    if let Some(pattern) = &view.pattern {
+        let (theta, phi, gain) = pattern.max_gain();
+        ui.label(format!(
+            "Peak: {:.2} dBi at θ = {theta:.0}°, φ = {phi:.0}°",
+            10.0 * gain.log10()
+        ));
+
+        ui.properties(&mut view.polar);
+        polar_pattern_plot(ui, pattern, &view.polar);
+
+        if view.show_3d {
+            pattern_3d_view(ui, id.with("3d"), pattern, view.polar.dynamic_range);
+        }
+    }
+    else {
+        ui.label("No pattern computed yet");
+    }
+
+    ui.data_mut(|data| data.insert_temp(id, view));
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+    use num::complex::Complex64;
+
+    use crate::solver::{
+        nf2ff::{
+            SurfaceCurrent,
+            radiate,
+        },
+        pattern::RadiationPattern,
+    };
+
+    #[test]
+    fn short_current_element_has_dipole_directivity() {
+        let current = SurfaceCurrent {
+            position: Vector3::zeros(),
+            area: 1e-4,
+            j: Vector3::<f64>::z().map(Complex64::from),
+            m: Vector3::zeros(),
+        };
+
+        let mut pattern = RadiationPattern::new("dipole", 1.0, 181, 72, 1.0);
+        radiate(&[current], 2.0 * std::f64::consts::PI, 1.0, &mut pattern);
+
+        let (theta, _, gain) = pattern.max_gain();
+        assert_eq!(theta, 90.0);
+        assert!((gain - 1.5).abs() < 1e-3, "gain = {gain}");
+    }
+}
@@ -2,18 +2,30 @@ use std::{
     convert::Infallible,
     ops::Index,
     path::PathBuf,
+    sync::Arc,
 };
 
-use bevy_ecs::component::Component;
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    query::Changed,
+    system::{
+        Commands,
+        Query,
+    },
+};
 use cem_probe::{
     PropertiesUi,
     TrackChanges,
     label_and_value,
     label_and_value_with_config,
 };
-use cem_render::texture::channel::{
-    ImageSender,
-    UndecidedTextureSender,
+use cem_render::{
+    components::DepthCompositing,
+    texture::channel::{
+        ImageSender,
+        UndecidedTextureSender,
+    },
 };
 use cem_solver::{
     FieldComponent,
@@ -36,8 +48,11 @@ use cem_solver::{
     project::{
         CreateProjection,
         FdtdImageTarget,
+        POST_PROCESS_SIGNATURE,
         ProjectionParameters,
         ProjectionPassAdd,
+        SetProjectionTransform,
+        validate_post_process_code,
     },
 };
 use cem_util::egui::FilePickerConfig;
@@ -45,6 +60,25 @@ use nalgebra::{
     Matrix4,
     UnitVector3,
     Vector2,
+    Vector3,
+};
+use palette::Srgb;
+use parking_lot::Mutex;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    color_scheme::Palette,
+    solver::{
+        history::{
+            DEFAULT_HISTORY_BUDGET,
+            ObserverHistory,
+        },
+        run_log::ObserverRunLog,
+        stream::StreamPublisher,
+    },
 };
 
 #[derive(Clone, Debug, Component)]
@@ -54,6 +88,24 @@ pub struct Observer {
     pub field: FieldComponent,
     pub color_map: Matrix4<f32>,
     pub half_extents: Vector2<f32>,
+
+    /// WGSL snippet that derives the displayed quantity from the field vector.
+    ///
+    /// See [`ProjectionParameters::post_process_code`].
+    pub post_process: Option<String>,
+
+    /// Show an axis-aligned slice through the domain instead of the `z = 0`
+    /// plane. Its position can be scrubbed in the solver window while the
+    /// solver runs.
+    pub slice: Option<VolumeSlice>,
+
+    /// How the observer plane is composited with the objects around it, so it
+    /// isn't hidden inside them.
+    pub depth_compositing: DepthCompositing,
+
+    /// Keep the latest images in memory, up to this many bytes, so they can be
+    /// replayed in the solver window. See [`crate::solver::history`].
+    pub history_budget: Option<usize>,
 }
 
 impl PropertiesUi for Observer {
@@ -88,6 +140,90 @@ impl PropertiesUi for Observer {
                     &FilePickerConfig::Save,
                 );
                 label_and_value(ui, "Live", &mut changes, &mut self.display_as_texture);
+
+                ui.horizontal(|ui| {
+                    let mut enabled = self.history_budget.is_some();
+                    changes.track(ui.checkbox(&mut enabled, "History"));
+                    if !enabled {
+                        self.history_budget = None;
+                    }
+                    else {
+                        // edited in MiB
+                        let budget = self.history_budget.get_or_insert(DEFAULT_HISTORY_BUDGET);
+                        let mut mebibytes = *budget >> 20;
+                        if changes
+                            .track(
+                                ui.add(
+                                    egui::DragValue::new(&mut mebibytes)
+                                        .range(1..=usize::MAX >> 20)
+                                        .suffix(" MiB"),
+                                ),
+                            )
+                            .changed()
+                        {
+                            *budget = mebibytes << 20;
+                        }
+                    }
+                })
+                .response
+                .on_hover_text("Keep the latest images, so recent ticks can be replayed");
+
+                ui.horizontal(|ui| {
+                    ui.label("Depth");
+                    egui::ComboBox::from_id_salt(ui.id().with("depth_compositing"))
+                        .selected_text(self.depth_compositing.label())
+                        .show_ui(ui, |ui| {
+                            for depth_compositing in DepthCompositing::ALL {
+                                changes.track(ui.selectable_value(
+                                    &mut self.depth_compositing,
+                                    depth_compositing,
+                                    depth_compositing.label(),
+                                ));
+                            }
+                        });
+                })
+                .response
+                .on_hover_text(
+                    "Depth Bias: Keep the plane visible where it touches objects.\nX-Ray: Draw the \
+                     plane on top of everything, partially transparent.",
+                );
+
+                let mut sliced = self.slice.is_some();
+                changes.track(ui.checkbox(&mut sliced, "Volume slice"));
+                if !sliced {
+                    self.slice = None;
+                }
+                else {
+                    let slice = self.slice.get_or_insert_default();
+                    changes.track(slice.axis_ui(ui));
+                    changes.track(slice.position_ui(ui));
+                }
+
+                let mut enabled = self.post_process.is_some();
+                changes.track(ui.checkbox(&mut enabled, "Post-processing"));
+                if !enabled {
+                    self.post_process = None;
+                }
+                else {
+                    let code = self
+                        .post_process
+                        .get_or_insert_with(|| "return value;".to_owned());
+
+                    ui.monospace(format!("{POST_PROCESS_SIGNATURE} {{"));
+                    changes.track(
+                        ui.add(
+                            egui::TextEdit::multiline(code)
+                                .code_editor()
+                                .desired_rows(4)
+                                .desired_width(f32::INFINITY),
+                        ),
+                    );
+                    ui.monospace("}");
+
+                    if let Err(error) = validate_post_process_code(code) {
+                        ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+                    }
+                }
             })
             .response;
 
@@ -95,6 +231,178 @@ impl PropertiesUi for Observer {
     }
 }
 
+/// Applies [`Observer::depth_compositing`] to the observer planes.
+pub fn update_observer_depth_compositing(
+    observers: Query<(Entity, &Observer), Changed<Observer>>,
+    mut commands: Commands,
+) {
+    for (entity, observer) in &observers {
+        commands.entity(entity).insert(observer.depth_compositing);
+    }
+}
+
+/// Lattice axis a [`VolumeSlice`] is perpendicular to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SliceAxis {
+    X,
+    Y,
+    #[default]
+    Z,
+}
+
+impl SliceAxis {
+    pub const ALL: [Self; 3] = [Self::X, Self::Y, Self::Z];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::X => "X",
+            Self::Y => "Y",
+            Self::Z => "Z",
+        }
+    }
+
+    /// Indices of the lattice axes along the image's u and v axes.
+    fn image_axes(&self) -> [usize; 2] {
+        match self {
+            Self::X => [1, 2],
+            Self::Y => [0, 2],
+            Self::Z => [0, 1],
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Self::X => 0,
+            Self::Y => 1,
+            Self::Z => 2,
+        }
+    }
+}
+
+/// An axis-aligned slice through the simulation domain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct VolumeSlice {
+    pub axis: SliceAxis,
+
+    /// Position along the axis, from 0 to 1 across the lattice.
+    pub position: f32,
+}
+
+impl VolumeSlice {
+    /// The [`ProjectionParameters::projection`] sampling this slice.
+    ///
+    /// Projections map the image plane to lattice coordinates normalized to
+    /// `[0, 1]`.
+    pub fn projection(&self) -> Matrix4<f32> {
+        let [u, v] = self.axis.image_axes();
+        let w = self.axis.index();
+
+        let mut projection = Matrix4::zeros();
+        projection[(u, 0)] = 1.0;
+        projection[(v, 1)] = 1.0;
+        projection[(w, 3)] = self.position.clamp(0.0, 1.0);
+        projection[(3, 3)] = 1.0;
+        projection
+    }
+
+    /// Size of the image showing this slice, in lattice cells.
+    pub fn image_size(&self, lattice_size: &Vector3<usize>) -> Vector2<usize> {
+        let [u, v] = self.axis.image_axes();
+        Vector2::new(lattice_size[u], lattice_size[v])
+    }
+
+    pub fn axis_ui(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        let mut changed = false;
+
+        let mut response = ui
+            .horizontal(|ui| {
+                ui.label("Axis");
+                for axis in SliceAxis::ALL {
+                    changed |= ui
+                        .selectable_value(&mut self.axis, axis, axis.label())
+                        .changed();
+                }
+            })
+            .response;
+
+        if changed {
+            response.mark_changed();
+        }
+        response
+    }
+
+    pub fn position_ui(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        ui.add(egui::Slider::new(&mut self.position, 0.0..=1.0).text(self.axis.label()))
+    }
+}
+
+/// The volume slices of a running solver's observers.
+///
+/// The UI moves them with [`Self::set_position`], and the solver thread picks
+/// up the changes with [`Self::take_changed`].
+#[derive(Clone, Debug, Default)]
+pub struct ObserverSlices {
+    inner: Arc<Mutex<Vec<ScrubbedSlice>>>,
+}
+
+#[derive(Clone, Debug)]
+struct ScrubbedSlice {
+    label: String,
+    slice: VolumeSlice,
+    changed: bool,
+}
+
+impl ObserverSlices {
+    /// Adds a slice and returns its index.
+    pub fn push(&self, label: impl Into<String>, slice: VolumeSlice) -> usize {
+        let mut slices = self.inner.lock();
+        slices.push(ScrubbedSlice {
+            label: label.into(),
+            slice,
+            changed: false,
+        });
+        slices.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().is_empty()
+    }
+
+    /// Labels of the observers and their current slices.
+    pub fn slices(&self) -> Vec<(String, VolumeSlice)> {
+        self.inner
+            .lock()
+            .iter()
+            .map(|scrubbed| (scrubbed.label.clone(), scrubbed.slice))
+            .collect()
+    }
+
+    pub fn set_position(&self, index: usize, position: f32) {
+        if let Some(scrubbed) = self.inner.lock().get_mut(index)
+            && scrubbed.slice.position != position
+        {
+            scrubbed.slice.position = position;
+            scrubbed.changed = true;
+        }
+    }
+
+    pub fn has_changes(&self) -> bool {
+        self.inner.lock().iter().any(|scrubbed| scrubbed.changed)
+    }
+
+    /// Indices and slices that moved since the last call.
+    pub fn take_changed(&self) -> Vec<(usize, VolumeSlice)> {
+        self.inner
+            .lock()
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, scrubbed)| {
+                std::mem::take(&mut scrubbed.changed).then_some((index, scrubbed.slice))
+            })
+            .collect()
+    }
+}
+
 pub fn test_color_map(scale: f32, axis: UnitVector3<f32>) -> Matrix4<f32> {
     let mut m = Matrix4::zeros();
 
@@ -113,7 +421,43 @@ pub fn test_color_map(scale: f32, axis: UnitVector3<f32>) -> Matrix4<f32> {
     m
 }
 
-struct FieldNames;
+/// WGSL for the color map observers are shown with (see
+/// [`ProjectionParameters::color_map_code`]).
+///
+/// Positive values of the field's z component are shown in the palette's
+/// positive color, negative values in its negative color. Small values are
+/// transparent.
+pub fn field_color_map_code(palette: &Palette) -> String {
+    let vec3f = |color: Srgb<u8>| {
+        let color: Srgb = color.into_format();
+        format!(
+            "vec3f({:?}, {:?}, {:?})",
+            color.red, color.green, color.blue
+        )
+    };
+
+    format!(
+        r#"
+    // color and alpha scaling
+    const s_c: f32 = 10.0;
+    const s_a: f32 = 100.0;
+
+    var color: vec3f;
+    let x = value.z;
+    if x > 0.0 {{
+        color = {positive};
+    }}
+    else {{
+        color = {negative};
+    }}
+    return vec4f(min(s_c * abs(x), 1.0) * color, min(s_a * abs(x), 1.0));
+    "#,
+        positive = vec3f(palette.field_positive),
+        negative = vec3f(palette.field_negative),
+    )
+}
+
+pub(crate) struct FieldNames;
 
 impl Index<FieldComponent> for FieldNames {
     type Output = &'static str;
@@ -126,9 +470,26 @@ impl Index<FieldComponent> for FieldNames {
     }
 }
 
-#[derive(derive_more::From, Debug)]
+#[derive(Debug)]
 pub struct TextureSenderTarget {
     pub texture_sender: UndecidedTextureSender,
+
+    /// Also send the images to remote monitors.
+    pub stream: Option<ObserverStream>,
+
+    /// Also save images into the run log.
+    pub run_log: Option<ObserverRunLog>,
+
+    /// Also keep images for replay.
+    pub history: Option<ObserverHistory>,
+}
+
+/// Where an observer publishes its images, see [`StreamPublisher`].
+#[derive(Clone, Debug)]
+pub struct ObserverStream {
+    pub publisher: StreamPublisher,
+    pub observer: usize,
+    pub label: String,
 }
 
 /// note: we could of course implement ImageTarget directly on the ImageSender,
@@ -138,6 +499,9 @@ pub struct TextureSenderTarget {
 #[derive(Debug)]
 pub struct CopyToTextureImageTarget {
     pub image_sender: ImageSender,
+    pub stream: Option<ObserverStream>,
+    pub run_log: Option<ObserverRunLog>,
+    pub history: Option<ObserverHistory>,
 }
 
 impl FdtdImageTarget for CopyToTextureImageTarget {
@@ -155,6 +519,21 @@ impl FdtdImageTarget for CopyToTextureImageTarget {
     ) -> Result<(), Infallible> {
         let mut image_buffer = self.image_sender.update_image();
         f(&mut image_buffer);
+
+        if let Some(stream) = &self.stream {
+            stream
+                .publisher
+                .publish_frame(stream.observer, &stream.label, &image_buffer);
+        }
+
+        if let Some(run_log) = &mut self.run_log {
+            run_log.save_if_requested(&image_buffer);
+        }
+
+        if let Some(history) = &self.history {
+            history.record(&image_buffer);
+        }
+
         Ok(())
     }
 }
@@ -179,13 +558,30 @@ where
         let _ = state;
 
         let image_sender = target.texture_sender.send_images();
+        if let Some(history) = &target.history {
+            history.attach(image_sender.clone());
+        }
         tracing::debug!(size = ?image_sender.size(), "creating projection with image sender");
-        let projection =
-            self.create_projection(state, CopyToTextureImageTarget { image_sender }, parameters);
+        let projection = self.create_projection(
+            state,
+            CopyToTextureImageTarget {
+                image_sender,
+                stream: target.stream,
+                run_log: target.run_log,
+                history: target.history,
+            },
+            parameters,
+        );
         FdtdCpuTextureSenderProjection { projection }
     }
 }
 
+impl SetProjectionTransform for FdtdCpuTextureSenderProjection {
+    fn set_projection_transform(&mut self, projection: Matrix4<f32>) {
+        self.projection.set_projection_transform(projection);
+    }
+}
+
 impl<'a, Threading> ProjectionPassAdd<'a, FdtdCpuTextureSenderProjection>
     for FdtdCpuProjectionPass<'a, Threading>
 {
@@ -209,12 +605,29 @@ impl CreateProjection<TextureSenderTarget> for FdtdWgpuSolverInstance {
         parameters: &ProjectionParameters,
     ) -> FdtdWgpuTextureSenderProjection {
         let texture_sender = target.texture_sender.send_texture();
+        if target.stream.is_some() {
+            // we'd need to read back the texture for this
+            tracing::warn!("observer images of the wgpu backend can't be streamed yet");
+        }
+        if target.run_log.is_some() {
+            tracing::warn!("observer images of the wgpu backend can't be logged yet");
+        }
+        if target.history.is_some() {
+            // we'd need to copy the textures on the gpu for this
+            tracing::warn!("observer images of the wgpu backend can't be replayed yet");
+        }
         tracing::debug!(size = ?texture_sender.size, format = ?texture_sender.format, "creating projection with texture sender");
         let projection = self.create_projection(state, texture_sender.texture.clone(), parameters);
         FdtdWgpuTextureSenderProjection { projection }
     }
 }
 
+impl SetProjectionTransform for FdtdWgpuTextureSenderProjection {
+    fn set_projection_transform(&mut self, projection: Matrix4<f32>) {
+        self.projection.set_projection_transform(projection);
+    }
+}
+
 impl<'a> ProjectionPassAdd<'a, FdtdWgpuTextureSenderProjection> for FdtdWgpuProjectionPass<'a> {
     fn add_projection(&mut self, projection: &'a mut FdtdWgpuTextureSenderProjection) {
         self.add_projection(&mut projection.projection);
@@ -0,0 +1,344 @@
+//! Scene parameters and material properties that depend on them.
+//!
+//! Parameters are named values set per project, e.g. a temperature. A
+//! [`MaterialDependencies`] component lets an entity's material properties
+//! follow a parameter, and solver configs can override parameter values per
+//! run (see [`SolverConfigCommon::parameter_overrides`]). This way a sweep over
+//! a parameter doesn't need to edit the materials of every entity.
+//!
+//! [`SolverConfigCommon::parameter_overrides`]: crate::solver::config::SolverConfigCommon::parameter_overrides
+
+use std::collections::BTreeMap;
+
+use bevy_ecs::{
+    component::Component,
+    reflect::ReflectComponent,
+    resource::Resource,
+    world::World,
+};
+use bevy_reflect::{
+    Reflect,
+    ReflectSerialize,
+    prelude::ReflectDefault,
+};
+use cem_probe::{
+    HasChangeValue,
+    PropertiesUi,
+    TrackChanges,
+};
+use cem_scene::probe::{
+    ComponentName,
+    ReflectComponentUi,
+};
+use cem_solver::material::Material;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// Parameter values by name.
+pub type ParameterValues = BTreeMap<String, f64>;
+
+#[derive(Clone, Debug, Default, Resource)]
+pub struct SceneParameters {
+    pub values: ParameterValues,
+}
+
+impl SceneParameters {
+    pub fn get(world: &World) -> ParameterValues {
+        world
+            .get_resource::<Self>()
+            .map(|parameters| parameters.values.clone())
+            .unwrap_or_default()
+    }
+
+    /// The parameter values a solver run uses: the scene's values, with
+    /// `overrides` taking precedence.
+    pub fn for_run(world: &World, overrides: &ParameterValues) -> ParameterValues {
+        let mut values = Self::get(world);
+        values.extend(overrides.iter().map(|(name, value)| (name.clone(), *value)));
+        values
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum MaterialProperty {
+    #[default]
+    RelativePermittivity,
+    RelativePermeability,
+    ElectricalConductivity,
+    MagneticConductivity,
+}
+
+impl MaterialProperty {
+    pub const ALL: [Self; 4] = [
+        Self::RelativePermittivity,
+        Self::RelativePermeability,
+        Self::ElectricalConductivity,
+        Self::MagneticConductivity,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::RelativePermittivity => "eps_r",
+            Self::RelativePermeability => "mu_r",
+            Self::ElectricalConductivity => "sigma",
+            Self::MagneticConductivity => "sigma_m",
+        }
+    }
+}
+
+/// A material property that varies linearly with a parameter around a
+/// reference value.
+///
+/// The property is scaled by `1 + coefficient * (x - reference)`, where `x` is
+/// the parameter's value. For the electrical conductivity the coefficient is
+/// that of the resistivity, as it's usually given for metals, so the
+/// conductivity is divided by this factor instead.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+pub struct MaterialDependence {
+    pub property: MaterialProperty,
+    pub parameter: String,
+
+    /// Parameter value at which the entity's material applies unchanged.
+    pub reference: f64,
+
+    pub coefficient: f64,
+}
+
+impl Default for MaterialDependence {
+    fn default() -> Self {
+        Self {
+            property: MaterialProperty::ElectricalConductivity,
+            parameter: "temperature".to_owned(),
+            reference: 20.0,
+            coefficient: 0.0,
+        }
+    }
+}
+
+impl MaterialDependence {
+    pub fn apply(&self, material: &mut Material, value: f64) {
+        let factor = 1.0 + self.coefficient * (value - self.reference);
+
+        match self.property {
+            MaterialProperty::RelativePermittivity => material.relative_permittivity *= factor,
+            MaterialProperty::RelativePermeability => material.relative_permeability *= factor,
+            MaterialProperty::ElectricalConductivity => material.eletrical_conductivity /= factor,
+            MaterialProperty::MagneticConductivity => material.magnetic_conductivity *= factor,
+        }
+    }
+}
+
+/// Material properties of an entity that depend on scene parameters.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Component, Reflect)]
+#[reflect(Component, ComponentUi, @ComponentName::new("Parameter Dependence"), Default, Serialize)]
+pub struct MaterialDependencies(pub Vec<MaterialDependence>);
+
+impl MaterialDependencies {
+    /// Applies the dependencies to `material`.
+    ///
+    /// Dependencies on parameters that aren't defined are skipped, i.e. the
+    /// material is used as if the parameter was at its reference value.
+    pub fn apply(&self, material: &Material, parameters: &ParameterValues) -> Material {
+        let mut material = *material;
+        for dependence in &self.0 {
+            if let Some(value) = parameters.get(&dependence.parameter) {
+                dependence.apply(&mut material, *value);
+            }
+        }
+        material
+    }
+}
+
+impl PropertiesUi for MaterialDependencies {
+    type Config = ();
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
+        let _ = config;
+        let mut changes = TrackChanges::default();
+        let mut delete = None;
+
+        let response = ui
+            .vertical(|ui| {
+                for (i, dependence) in self.0.iter_mut().enumerate() {
+                    ui.push_id(i, |ui| {
+                        ui.horizontal(|ui| {
+                            let mut changed = false;
+                            egui::ComboBox::from_id_salt("property")
+                                .selected_text(dependence.property.label())
+                                .width(70.0)
+                                .show_ui(ui, |ui| {
+                                    for property in MaterialProperty::ALL {
+                                        changed |= ui
+                                            .selectable_value(
+                                                &mut dependence.property,
+                                                property,
+                                                property.label(),
+                                            )
+                                            .changed();
+                                    }
+                                });
+                            if changed {
+                                changes.mark_changed();
+                            }
+
+                            ui.label("of");
+                            changes.track(
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut dependence.parameter)
+                                        .desired_width(80.0),
+                                ),
+                            );
+
+                            if ui.small_button("Delete").clicked() {
+                                delete = Some(i);
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("x0");
+                            changes
+                                .track(ui.add(
+                                    egui::DragValue::new(&mut dependence.reference).speed(0.1),
+                                ))
+                                .on_hover_text(
+                                    "Reference value at which the material is unchanged",
+                                );
+
+                            ui.label("α");
+                            changes
+                                .track(
+                                    ui.add(
+                                        egui::DragValue::new(&mut dependence.coefficient)
+                                            .speed(1e-4)
+                                            .max_decimals(6),
+                                    ),
+                                )
+                                .on_hover_text(
+                                    "The property is scaled by 1 + α (x - x0). For sigma, α is the \
+                                 coefficient of the resistivity.",
+                                );
+                        });
+                    });
+                }
+
+                if ui.small_button("Add").clicked() {
+                    self.0.push(Default::default());
+                    changes.mark_changed();
+                }
+            })
+            .response;
+
+        if let Some(i) = delete {
+            self.0.remove(i);
+            changes.mark_changed();
+        }
+
+        changes.propagated(response)
+    }
+}
+
+/// Window to edit the scene parameters.
+#[derive(Debug, Default)]
+pub struct ParametersWindow {
+    pub open: bool,
+
+    /// Name of the parameter that is about to be added.
+    new_name: String,
+}
+
+impl ParametersWindow {
+    pub fn show(&mut self, ctx: &egui::Context, world: &mut World) {
+        let mut parameters = world.get_resource_or_init::<SceneParameters>();
+
+        egui::Window::new("Parameters")
+            .movable(true)
+            .default_size([300.0, 200.0])
+            .open(&mut self.open)
+            .show(ctx, |ui| {
+                let mut delete = None;
+
+                if parameters.values.is_empty() {
+                    ui.weak("No parameters");
+                }
+
+                egui::Grid::new("parameters").num_columns(3).show(ui, |ui| {
+                    for (name, value) in &mut parameters.values {
+                        ui.label(name);
+                        ui.add(egui::DragValue::new(value).speed(0.1));
+                        if ui.small_button("Delete").clicked() {
+                            delete = Some(name.clone());
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                if let Some(name) = delete {
+                    parameters.values.remove(&name);
+                }
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.new_name)
+                            .hint_text("Name")
+                            .desired_width(120.0),
+                    );
+
+                    let name = self.new_name.trim();
+                    if ui
+                        .add_enabled(
+                            !name.is_empty() && !parameters.values.contains_key(name),
+                            egui::Button::new("Add"),
+                        )
+                        .clicked()
+                    {
+                        parameters.values.insert(name.to_owned(), 0.0);
+                        self.new_name.clear();
+                    }
+                });
+
+                ui.weak("Materials use these through a Parameter Dependence component.");
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cem_solver::material::Material;
+
+    use crate::solver::parameters::{
+        MaterialDependence,
+        MaterialDependencies,
+        MaterialProperty,
+        ParameterValues,
+    };
+
+    #[test]
+    fn conductivity_follows_resistivity_coefficient() {
+        // copper at 20 °C
+        let copper = Material {
+            eletrical_conductivity: 5.96e7,
+            ..Material::VACUUM
+        };
+        let dependencies = MaterialDependencies(vec![MaterialDependence {
+            property: MaterialProperty::ElectricalConductivity,
+            parameter: "temperature".to_owned(),
+            reference: 20.0,
+            coefficient: 0.00393,
+        }]);
+
+        let parameters = ParameterValues::from([("temperature".to_owned(), 120.0)]);
+        let hot = dependencies.apply(&copper, &parameters);
+        assert!((hot.eletrical_conductivity - 5.96e7 / 1.393).abs() < 1.0);
+
+        // without the parameter the material is unchanged
+        let unchanged = dependencies.apply(&copper, &ParameterValues::new());
+        assert_eq!(
+            unchanged.eletrical_conductivity,
+            copper.eletrical_conductivity
+        );
+    }
+}
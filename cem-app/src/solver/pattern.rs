@@ -0,0 +1,859 @@
+//! Far-field radiation patterns and their export.
+//!
+//! A [`RadiationPattern`] is sampled on a regular grid over the full sphere.
+//! Angles follow the usual antenna convention: theta is measured from the
+//! pattern's +Z axis, and phi from +X towards +Y.
+//!
+//! Patterns can be written as a CSV theta/phi grid, as MSI Planet `.pln` (used
+//! by coverage planning tools) and as FEKO `.ffe` far field files. They are
+//! shown with [`polar_pattern_plot`] as cuts, or in 3D with
+//! [`pattern_3d_view`].
+
+use std::{
+    f64::consts::{
+        FRAC_PI_2,
+        PI,
+    },
+    fmt::Write,
+    path::Path,
+};
+
+use cem_probe::{
+    PropertiesUi,
+    TrackChanges,
+};
+use colorgrad::Gradient;
+use nalgebra::{
+    UnitQuaternion,
+    Vector3,
+};
+use num::complex::Complex64;
+
+use crate::{
+    Error,
+    clipboard::copy_image_or_csv_context_menu,
+    color_scheme,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatternFormat {
+    Csv,
+    Planet,
+    Ffe,
+}
+
+impl PatternFormat {
+    pub const ALL: [Self; 3] = [Self::Csv, Self::Planet, Self::Ffe];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Csv => "CSV (theta/phi grid)",
+            Self::Planet => "MSI Planet",
+            Self::Ffe => "FEKO Far Field",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Planet => "pln",
+            Self::Ffe => "ffe",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RadiationPattern {
+    pub label: String,
+
+    pub frequency: f64,
+
+    /// Number of theta samples, from 0° to 180° inclusive.
+    num_theta: usize,
+
+    /// Number of phi samples, from 0° to 360° exclusive.
+    num_phi: usize,
+
+    /// Far-field components `r * E_theta` and `r * E_phi`, with theta varying
+    /// fastest.
+    e_theta: Vec<Complex64>,
+    e_phi: Vec<Complex64>,
+
+    /// Power accepted by the antenna.
+    ///
+    /// If this is known, the gains are realized gains. Otherwise they are
+    /// directivities, i.e. relative to the radiated power.
+    pub input_power: Option<f64>,
+
+    pub vacuum_impedance: f64,
+}
+
+impl RadiationPattern {
+    /// Creates a pattern with all field values set to zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are less than 2 theta samples or no phi samples.
+    pub fn new(
+        label: impl Into<String>,
+        frequency: f64,
+        num_theta: usize,
+        num_phi: usize,
+        vacuum_impedance: f64,
+    ) -> Self {
+        assert!(num_theta >= 2, "pattern needs at least 2 theta samples");
+        assert!(num_phi >= 1, "pattern needs at least 1 phi sample");
+
+        Self {
+            label: label.into(),
+            frequency,
+            num_theta,
+            num_phi,
+            e_theta: vec![Complex64::default(); num_theta * num_phi],
+            e_phi: vec![Complex64::default(); num_theta * num_phi],
+            input_power: None,
+            vacuum_impedance,
+        }
+    }
+
+    pub fn num_theta(&self) -> usize {
+        self.num_theta
+    }
+
+    pub fn num_phi(&self) -> usize {
+        self.num_phi
+    }
+
+    /// Theta of the `index`th sample in degrees.
+    pub fn theta(&self, index: usize) -> f64 {
+        index as f64 * self.theta_step()
+    }
+
+    /// Phi of the `index`th sample in degrees.
+    pub fn phi(&self, index: usize) -> f64 {
+        index as f64 * self.phi_step()
+    }
+
+    fn theta_step(&self) -> f64 {
+        180.0 / (self.num_theta - 1) as f64
+    }
+
+    fn phi_step(&self) -> f64 {
+        360.0 / self.num_phi as f64
+    }
+
+    fn index(&self, theta_index: usize, phi_index: usize) -> usize {
+        phi_index * self.num_theta + theta_index
+    }
+
+    pub fn set(
+        &mut self,
+        theta_index: usize,
+        phi_index: usize,
+        e_theta: Complex64,
+        e_phi: Complex64,
+    ) {
+        let index = self.index(theta_index, phi_index);
+        self.e_theta[index] = e_theta;
+        self.e_phi[index] = e_phi;
+    }
+
+    pub fn field(&self, theta_index: usize, phi_index: usize) -> [Complex64; 2] {
+        let index = self.index(theta_index, phi_index);
+        [self.e_theta[index], self.e_phi[index]]
+    }
+
+    /// Radiation intensity of both polarizations at a sample.
+    fn intensity(&self, index: usize) -> [f64; 2] {
+        [self.e_theta[index], self.e_phi[index]]
+            .map(|field| field.norm_sqr() / (2.0 * self.vacuum_impedance))
+    }
+
+    /// Total radiated power, integrated over the sphere.
+    pub fn radiated_power(&self) -> f64 {
+        let d_theta = self.theta_step().to_radians();
+        let d_phi = self.phi_step().to_radians();
+
+        let mut power = 0.0;
+        for phi_index in 0..self.num_phi {
+            for theta_index in 0..self.num_theta {
+                // trapezoidal rule along theta
+                let weight = if theta_index == 0 || theta_index == self.num_theta - 1 {
+                    0.5
+                }
+                else {
+                    1.0
+                };
+                let [u_theta, u_phi] = self.intensity(self.index(theta_index, phi_index));
+                power += weight
+                    * (u_theta + u_phi)
+                    * self.theta(theta_index).to_radians().sin()
+                    * d_theta
+                    * d_phi;
+            }
+        }
+        power
+    }
+
+    /// Gains for theta and phi polarization, and the total gain, at every
+    /// sample. These are linear, not in dB.
+    pub fn gains(&self) -> Vec<[f64; 3]> {
+        let power = self
+            .input_power
+            .unwrap_or_else(|| self.radiated_power())
+            .max(f64::MIN_POSITIVE);
+
+        (0..self.e_theta.len())
+            .map(|index| {
+                let [u_theta, u_phi] = self.intensity(index);
+                let gain_theta = 4.0 * PI * u_theta / power;
+                let gain_phi = 4.0 * PI * u_phi / power;
+                [gain_theta, gain_phi, gain_theta + gain_phi]
+            })
+            .collect()
+    }
+
+    /// Direction (theta, phi in degrees) and linear gain of the maximum.
+    pub fn max_gain(&self) -> (f64, f64, f64) {
+        let gains = self.gains();
+        let (index, gain) = gains
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a[2].total_cmp(&b[2]))
+            .map(|(index, gain)| (index, gain[2]))
+            .unwrap_or_default();
+        (
+            self.theta(index % self.num_theta),
+            self.phi(index / self.num_theta),
+            gain,
+        )
+    }
+
+    /// Total gain in a direction (in degrees), interpolated between samples.
+    ///
+    /// `gains` are the gains returned by [`Self::gains`], so they don't need to
+    /// be recomputed for every direction.
+    pub fn gain_at(&self, gains: &[[f64; 3]], theta: f64, phi: f64) -> f64 {
+        let theta = (theta / self.theta_step()).clamp(0.0, (self.num_theta - 1) as f64);
+        let phi = phi.rem_euclid(360.0) / self.phi_step();
+
+        let theta_0 = (theta.floor() as usize).min(self.num_theta - 2);
+        let phi_0 = phi.floor() as usize % self.num_phi;
+        let phi_1 = (phi_0 + 1) % self.num_phi;
+        let s = theta - theta_0 as f64;
+        let t = phi - phi.floor();
+
+        let gain = |theta_index, phi_index| gains[self.index(theta_index, phi_index)][2];
+
+        (1.0 - t) * ((1.0 - s) * gain(theta_0, phi_0) + s * gain(theta_0 + 1, phi_0))
+            + t * ((1.0 - s) * gain(theta_0, phi_1) + s * gain(theta_0 + 1, phi_1))
+    }
+
+    pub fn write(&self, path: &Path, format: PatternFormat) -> Result<(), Error> {
+        let contents = match format {
+            PatternFormat::Csv => self.to_csv(),
+            PatternFormat::Planet => self.to_planet()?,
+            PatternFormat::Ffe => self.to_ffe()?,
+        };
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// One row per sample, with gains in dBi.
+    pub fn to_csv(&self) -> String {
+        let gains = self.gains();
+
+        let mut csv =
+            "theta,phi,re_e_theta,im_e_theta,re_e_phi,im_e_phi,gain_theta,gain_phi,gain_total\n"
+                .to_owned();
+        for phi_index in 0..self.num_phi {
+            for theta_index in 0..self.num_theta {
+                let index = self.index(theta_index, phi_index);
+                let [e_theta, e_phi] = self.field(theta_index, phi_index);
+                let [gain_theta, gain_phi, gain_total] = gains[index].map(to_db);
+                csv.push_str(&format!(
+                    "{},{},{},{},{},{},{gain_theta},{gain_phi},{gain_total}\n",
+                    self.theta(theta_index),
+                    self.phi(phi_index),
+                    e_theta.re,
+                    e_theta.im,
+                    e_phi.re,
+                    e_phi.im,
+                ));
+            }
+        }
+        csv
+    }
+
+    /// FEKO `.ffe` far field file (format version 8).
+    pub fn to_ffe(&self) -> Result<String, Error> {
+        let gains = self.gains();
+        let quantity = if self.input_power.is_some() {
+            "Gain"
+        }
+        else {
+            "Directivity"
+        };
+
+        let mut ffe = String::new();
+        writeln!(ffe, "##File Type: Far field")?;
+        writeln!(ffe, "##File Format: 8")?;
+        writeln!(ffe, "##Source: {}", self.label)?;
+        writeln!(
+            ffe,
+            "##Date: {}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+        )?;
+        writeln!(ffe)?;
+        writeln!(ffe, "#Request Name: {}", self.label)?;
+        writeln!(ffe, "#Frequency: {:.8E}", self.frequency)?;
+        writeln!(ffe, "#Coordinate System: Spherical")?;
+        writeln!(ffe, "#No. of Theta Samples: {}", self.num_theta)?;
+        writeln!(ffe, "#No. of Phi Samples: {}", self.num_phi)?;
+        writeln!(ffe, "#Result Type: {quantity}")?;
+        writeln!(ffe, "#No. of Header Lines: 1")?;
+        writeln!(
+            ffe,
+            r#"#                 "Theta"               "Phi"       "Re(Etheta)"       "Im(Etheta)"         "Re(Ephi)"         "Im(Ephi)"  "{quantity}(Theta)"    "{quantity}(Phi)"  "{quantity}(Total)""#
+        )?;
+
+        for phi_index in 0..self.num_phi {
+            for theta_index in 0..self.num_theta {
+                let index = self.index(theta_index, phi_index);
+                let [e_theta, e_phi] = self.field(theta_index, phi_index);
+                let values = [
+                    self.theta(theta_index),
+                    self.phi(phi_index),
+                    e_theta.re,
+                    e_theta.im,
+                    e_phi.re,
+                    e_phi.im,
+                ]
+                .into_iter()
+                .chain(gains[index].map(to_db));
+
+                for value in values {
+                    write!(ffe, " {value:>18.8E}")?;
+                }
+                writeln!(ffe)?;
+            }
+        }
+
+        Ok(ffe)
+    }
+
+    /// MSI Planet `.pln` antenna file.
+    ///
+    /// Planet files describe an antenna by a horizontal and a vertical cut in
+    /// 1° steps, as attenuation relative to the peak gain. The pattern's +Z
+    /// axis is taken as up and its +X axis as boresight. Azimuth in Planet
+    /// files increases clockwise seen from above, and elevation increases
+    /// downwards.
+    pub fn to_planet(&self) -> Result<String, Error> {
+        let gains = self.gains();
+        let (_, _, max_gain) = self.max_gain();
+
+        let attenuation =
+            |theta: f64, phi: f64| to_db(max_gain) - to_db(self.gain_at(&gains, theta, phi));
+
+        // the horizontal cut is the XY plane.
+        let horizontal = (0..360)
+            .map(|azimuth| attenuation(90.0, -(azimuth as f64)))
+            .collect::<Vec<_>>();
+
+        // the vertical cut is the XZ plane, starting at the horizon towards +X.
+        let vertical = (0..360)
+            .map(|elevation| {
+                let elevation = elevation as f64;
+                if elevation <= 90.0 {
+                    attenuation(90.0 + elevation, 0.0)
+                }
+                else if elevation <= 270.0 {
+                    attenuation(270.0 - elevation, 180.0)
+                }
+                else {
+                    attenuation(elevation - 270.0, 0.0)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // polarization at boresight
+        let boresight = self.index(self.num_theta / 2, 0);
+        let [u_theta, u_phi] = self.intensity(boresight);
+        let polarization = if u_theta >= u_phi { "V" } else { "H" };
+
+        let mut pln = String::new();
+        writeln!(pln, "NAME {}", self.label)?;
+        writeln!(pln, "MAKE fdtd")?;
+        writeln!(pln, "FREQUENCY {:.3}", self.frequency * 1e-6)?;
+        writeln!(pln, "H_WIDTH {:.1}", half_power_beam_width(&horizontal))?;
+        writeln!(pln, "V_WIDTH {:.1}", half_power_beam_width(&vertical))?;
+        writeln!(pln, "FRONT_TO_BACK {:.2}", horizontal[180] - horizontal[0])?;
+        writeln!(pln, "GAIN {:.2} dBi", to_db(max_gain))?;
+        writeln!(pln, "TILT ELECTRICAL")?;
+        writeln!(pln, "POLARIZATION {polarization}")?;
+        writeln!(pln, "COMMENT Exported by fdtd")?;
+
+        for (name, cut) in [("HORIZONTAL", &horizontal), ("VERTICAL", &vertical)] {
+            writeln!(pln, "{name} 360")?;
+            for (angle, attenuation) in cut.iter().enumerate() {
+                writeln!(pln, "{angle} {attenuation:.2}")?;
+            }
+        }
+
+        Ok(pln)
+    }
+}
+
+/// A planar cut through a pattern.
+///
+/// The E- and H-plane are named for an antenna polarized along X, radiating
+/// along Z.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PatternCut {
+    /// XZ plane (φ = 0° and 180°)
+    #[default]
+    EPlane,
+
+    /// YZ plane (φ = 90° and 270°)
+    HPlane,
+
+    /// XY plane (θ = 90°)
+    Azimuth,
+}
+
+impl PatternCut {
+    pub const ALL: [Self; 3] = [Self::EPlane, Self::HPlane, Self::Azimuth];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::EPlane => "E-Plane (XZ)",
+            Self::HPlane => "H-Plane (YZ)",
+            Self::Azimuth => "Azimuth (XY)",
+        }
+    }
+
+    /// Direction (theta, phi) at `angle` degrees along the cut.
+    ///
+    /// Elevation cuts start at +Z and go over +X (or +Y) first. The azimuth
+    /// cut starts at +X.
+    pub fn direction(&self, angle: f64) -> (f64, f64) {
+        let elevation = |phi: f64| {
+            let angle = angle.rem_euclid(360.0);
+            if angle <= 180.0 {
+                (angle, phi)
+            }
+            else {
+                (360.0 - angle, phi + 180.0)
+            }
+        };
+
+        match self {
+            Self::EPlane => elevation(0.0),
+            Self::HPlane => elevation(90.0),
+            Self::Azimuth => (90.0, angle),
+        }
+    }
+
+    /// Total gain in dBi along the cut, in 1° steps.
+    pub fn gains(&self, pattern: &RadiationPattern) -> Vec<[f64; 2]> {
+        let gains = pattern.gains();
+        (0..=360)
+            .map(|angle| {
+                let (theta, phi) = self.direction(angle as f64);
+                [angle as f64, to_db(pattern.gain_at(&gains, theta, phi))]
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PolarPlotConfig {
+    pub cut: PatternCut,
+
+    /// Gain at the center of the plot in dB below the peak
+    pub dynamic_range: f64,
+
+    /// Spacing of the rings in dB
+    pub ring_step: f64,
+}
+
+impl Default for PolarPlotConfig {
+    fn default() -> Self {
+        Self {
+            cut: PatternCut::EPlane,
+            dynamic_range: 40.0,
+            ring_step: 10.0,
+        }
+    }
+}
+
+impl PropertiesUi for PolarPlotConfig {
+    type Config = ();
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
+        let _ = config;
+        let mut changes = TrackChanges::default();
+
+        let response = ui
+            .horizontal(|ui| {
+                egui::ComboBox::from_id_salt(ui.id().with("cut"))
+                    .selected_text(self.cut.label())
+                    .show_ui(ui, |ui| {
+                        for cut in PatternCut::ALL {
+                            changes.track(ui.selectable_value(&mut self.cut, cut, cut.label()));
+                        }
+                    });
+
+                changes.track(
+                    ui.add(
+                        egui::DragValue::new(&mut self.dynamic_range)
+                            .range(3.0..=120.0)
+                            .suffix(" dB")
+                            .prefix("range "),
+                    ),
+                );
+                changes.track(
+                    ui.add(
+                        egui::DragValue::new(&mut self.ring_step)
+                            .range(1.0..=60.0)
+                            .suffix(" dB")
+                            .prefix("rings "),
+                    ),
+                );
+            })
+            .response;
+
+        changes.propagated(response)
+    }
+}
+
+/// Shows a cut through a pattern as a polar plot in dB.
+///
+/// The plot is scaled to the peak of the cut, and angles increase clockwise
+/// from the top.
+pub fn polar_pattern_plot(
+    ui: &mut egui::Ui,
+    pattern: &RadiationPattern,
+    config: &PolarPlotConfig,
+) -> egui::Response {
+    let gains = config.cut.gains(pattern);
+    let peak = gains
+        .iter()
+        .map(|[_, gain]| *gain)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let dynamic_range = config.dynamic_range.max(1.0);
+
+    let size = ui.available_width().min(400.0);
+    let (response, painter) = ui.allocate_painter(egui::Vec2::splat(size), egui::Sense::hover());
+    let center = response.rect.center();
+    // leave space for the angle labels
+    let outer_radius = 0.5 * size - 20.0;
+
+    let visuals = ui.visuals();
+    let grid_stroke = egui::Stroke::new(1.0, visuals.weak_text_color());
+    let text_color = visuals.text_color();
+    let font = egui::FontId::proportional(10.0);
+
+    let to_screen = |angle: f64, gain: f64| {
+        let radius = ((gain - peak + dynamic_range) / dynamic_range).clamp(0.0, 1.0);
+        let (sin, cos) = angle.to_radians().sin_cos();
+        center + outer_radius * radius as f32 * egui::vec2(sin as f32, -cos as f32)
+    };
+
+    // rings, labelled with their gain
+    let ring_step = config.ring_step.max(1.0);
+    let mut ring = 0.0;
+    while ring < dynamic_range {
+        let radius = outer_radius * (1.0 - ring / dynamic_range) as f32;
+        painter.circle_stroke(center, radius, grid_stroke);
+        painter.text(
+            center - egui::vec2(0.0, radius),
+            egui::Align2::LEFT_BOTTOM,
+            format!("{:.0}", peak - ring),
+            font.clone(),
+            text_color,
+        );
+        ring += ring_step;
+    }
+
+    // spokes
+    for angle in (0..360).step_by(30) {
+        let (sin, cos) = (angle as f32).to_radians().sin_cos();
+        let direction = egui::vec2(sin, -cos);
+        painter.line_segment([center, center + outer_radius * direction], grid_stroke);
+        painter.text(
+            center + (outer_radius + 10.0) * direction,
+            egui::Align2::CENTER_CENTER,
+            format!("{angle}°"),
+            font.clone(),
+            text_color,
+        );
+    }
+
+    let points = gains
+        .iter()
+        .map(|[angle, gain]| to_screen(*angle, *gain))
+        .collect();
+    painter.add(egui::Shape::line(
+        points,
+        egui::Stroke::new(2.0, visuals.selection.bg_fill),
+    ));
+
+    let response = response.on_hover_ui_at_pointer(|ui| {
+        if let Some(pointer) = ui.ctx().pointer_hover_pos() {
+            let offset = pointer - center;
+            let angle = f64::from(offset.x.atan2(-offset.y).to_degrees()).rem_euclid(360.0);
+            let [angle, gain] = gains[(angle.round() as usize).min(360)];
+            let (theta, phi) = config.cut.direction(angle);
+            ui.label(format!(
+                "θ = {theta:.0}°, φ = {:.0}°: {gain:.2} dBi",
+                phi.rem_euclid(360.0)
+            ));
+        }
+    });
+
+    copy_image_or_csv_context_menu(&response, || {
+        let mut csv = "angle,theta,phi,gain\n".to_owned();
+        for [angle, gain] in &gains {
+            let (theta, phi) = config.cut.direction(*angle);
+            csv.push_str(&format!(
+                "{angle},{theta},{},{gain}\n",
+                phi.rem_euclid(360.0)
+            ));
+        }
+        csv
+    });
+
+    response
+}
+
+/// Orientation of a 3D pattern view, kept in egui's memory.
+#[derive(Clone, Copy, Debug)]
+struct PatternView3d {
+    yaw: f32,
+    pitch: f32,
+}
+
+impl Default for PatternView3d {
+    fn default() -> Self {
+        Self {
+            yaw: -0.6,
+            pitch: 0.4,
+        }
+    }
+}
+
+/// Shows the pattern as a surface, with its distance from the origin and
+/// color given by the gain in dB. The view can be rotated by dragging.
+pub fn pattern_3d_view(
+    ui: &mut egui::Ui,
+    id_salt: impl std::hash::Hash,
+    pattern: &RadiationPattern,
+    dynamic_range: f64,
+) -> egui::Response {
+    let id = ui.id().with(id_salt);
+    let mut view = ui.data_mut(|data| *data.get_temp_mut_or_default::<PatternView3d>(id));
+
+    let size = ui.available_width().min(400.0);
+    let (response, painter) = ui.allocate_painter(egui::Vec2::splat(size), egui::Sense::drag());
+    if response.dragged() {
+        let delta = response.drag_delta();
+        view.yaw += 0.01 * delta.x;
+        view.pitch = (view.pitch + 0.01 * delta.y).clamp(-FRAC_PI_2 as f32, FRAC_PI_2 as f32);
+    }
+    ui.data_mut(|data| data.insert_temp(id, view));
+
+    let center = response.rect.center();
+    let scale = 0.45 * size;
+    let rotation = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), view.pitch)
+        * UnitQuaternion::from_axis_angle(&Vector3::z_axis(), view.yaw);
+
+    // the pattern's Z axis points up, and Y into the screen
+    let project = |point: Vector3<f32>| {
+        let point = rotation * point;
+        (center + scale * egui::vec2(point.x, -point.z), point.y)
+    };
+
+    let gains = pattern.gains();
+    let peak = to_db(pattern.max_gain().2);
+    let dynamic_range = dynamic_range.max(1.0);
+    let gradient = color_scheme::palette().sequential();
+
+    let mut mesh = egui::Mesh::default();
+    let mut depths = vec![];
+    for phi_index in 0..pattern.num_phi() {
+        let (sin_phi, cos_phi) = (pattern.phi(phi_index) as f32).to_radians().sin_cos();
+        for theta_index in 0..pattern.num_theta() {
+            let (sin_theta, cos_theta) = (pattern.theta(theta_index) as f32).to_radians().sin_cos();
+            let gain = to_db(gains[pattern.index(theta_index, phi_index)][2]);
+            let radius = ((gain - peak + dynamic_range) / dynamic_range).clamp(0.0, 1.0) as f32;
+
+            let direction = Vector3::new(sin_theta * cos_phi, sin_theta * sin_phi, cos_theta);
+            let (position, depth) = project(radius * direction);
+            let [r, g, b, _] = gradient.at(radius).to_rgba8();
+            mesh.colored_vertex(position, egui::Color32::from_rgb(r, g, b));
+            depths.push(depth);
+        }
+    }
+
+    // two triangles per quad between neighbouring samples, drawn back to front
+    let mut triangles = vec![];
+    for phi_index in 0..pattern.num_phi() {
+        let next_phi = (phi_index + 1) % pattern.num_phi();
+        for theta_index in 0..pattern.num_theta() - 1 {
+            let [a, b, c, d] = [
+                (theta_index, phi_index),
+                (theta_index + 1, phi_index),
+                (theta_index + 1, next_phi),
+                (theta_index, next_phi),
+            ]
+            .map(|(theta_index, phi_index)| pattern.index(theta_index, phi_index) as u32);
+            triangles.push([a, b, c]);
+            triangles.push([a, c, d]);
+        }
+    }
+    let depth =
+        |triangle: &[u32; 3]| -> f32 { triangle.iter().map(|index| depths[*index as usize]).sum() };
+    triangles.sort_by(|a, b| depth(b).total_cmp(&depth(a)));
+    for [a, b, c] in triangles {
+        mesh.add_triangle(a, b, c);
+    }
+    painter.add(mesh);
+
+    // axes
+    let text_color = ui.visuals().text_color();
+    for (label, axis) in [
+        ("X", Vector3::<f32>::x()),
+        ("Y", Vector3::y()),
+        ("Z", Vector3::z()),
+    ] {
+        let (origin, _) = project(Vector3::zeros());
+        let (end, _) = project(1.1 * axis);
+        painter.line_segment([origin, end], egui::Stroke::new(1.0, text_color));
+        painter.text(
+            end,
+            egui::Align2::CENTER_CENTER,
+            label,
+            egui::FontId::proportional(12.0),
+            text_color,
+        );
+    }
+
+    painter.text(
+        response.rect.left_top(),
+        egui::Align2::LEFT_TOP,
+        format!("{peak:.2} dBi peak, {dynamic_range:.0} dB range"),
+        egui::FontId::proportional(10.0),
+        text_color,
+    );
+
+    response
+}
+
+fn to_db(gain: f64) -> f64 {
+    // clamp, so nulls don't turn into -inf
+    10.0 * gain.max(1e-20).log10()
+}
+
+/// Width in degrees of the main lobe of a cut given as attenuation per degree,
+/// between the interpolated -3 dB points.
+fn half_power_beam_width(cut: &[f64]) -> f64 {
+    let Some(peak) = cut
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index)
+    else {
+        return 0.0;
+    };
+    let threshold = cut[peak] + 3.0;
+    let n = cut.len();
+
+    // distance from the peak to the -3 dB point, walking in one direction
+    let crossing = |direction: isize| {
+        let at = |step: usize| {
+            cut[(peak as isize + direction * step as isize).rem_euclid(n as isize) as usize]
+        };
+        (1..n).find_map(|step| {
+            let (previous, current) = (at(step - 1), at(step));
+            (current > threshold)
+                .then(|| (step - 1) as f64 + (threshold - previous) / (current - previous))
+        })
+    };
+
+    match (crossing(1), crossing(-1)) {
+        (Some(right), Some(left)) => (right + left).min(n as f64),
+        _ => n as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num::complex::Complex64;
+
+    use crate::solver::pattern::{
+        PatternCut,
+        RadiationPattern,
+    };
+
+    /// Pattern of a short dipole along the Z axis.
+    fn short_dipole() -> RadiationPattern {
+        let mut pattern = RadiationPattern::new("dipole", 1e9, 181, 72, 376.73);
+        for phi_index in 0..pattern.num_phi() {
+            for theta_index in 0..pattern.num_theta() {
+                let e_theta = pattern.theta(theta_index).to_radians().sin();
+                pattern.set(
+                    theta_index,
+                    phi_index,
+                    Complex64::new(e_theta, 0.0),
+                    Complex64::default(),
+                );
+            }
+        }
+        pattern
+    }
+
+    #[test]
+    fn short_dipole_directivity() {
+        let (theta, _, directivity) = short_dipole().max_gain();
+        assert_eq!(theta, 90.0);
+        assert!((directivity - 1.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn planet_cuts_of_short_dipole() {
+        let pln = short_dipole().to_planet().unwrap();
+        let lines = pln.lines().collect::<Vec<_>>();
+
+        let horizontal = lines
+            .iter()
+            .position(|line| *line == "HORIZONTAL 360")
+            .unwrap();
+        let vertical = lines
+            .iter()
+            .position(|line| *line == "VERTICAL 360")
+            .unwrap();
+        assert_eq!(vertical - horizontal, 361);
+        assert_eq!(lines.len() - vertical, 361);
+
+        // omnidirectional in the horizontal plane, nulls up and down.
+        assert_eq!(lines[horizontal + 1 + 123], "123 0.00");
+        assert_eq!(lines[vertical + 1], "0 0.00");
+        assert!(lines.contains(&"H_WIDTH 360.0"));
+        assert!(lines.contains(&"V_WIDTH 89.9"));
+        assert!(lines.contains(&"POLARIZATION V"));
+    }
+
+    #[test]
+    fn e_plane_cut_of_short_dipole() {
+        let cut = PatternCut::EPlane.gains(&short_dipole());
+        assert_eq!(cut.len(), 361);
+
+        // maxima towards +X and -X, nulls along Z
+        assert!((cut[90][1] - 1.5f64.log10() * 10.0).abs() < 1e-3);
+        assert!((cut[270][1] - cut[90][1]).abs() < 1e-3);
+        assert!(cut[0][1] < -100.0);
+        assert!(cut[180][1] < -100.0);
+    }
+}
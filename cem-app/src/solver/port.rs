@@ -0,0 +1,601 @@
+//! Lumped ports.
+//!
+//! A port is a short gap along the local z axis of its entity, centered at the
+//! entity's origin. It's excited by an idealized [`Feed`] across the gap, and a
+//! [`PointProbe`] records the field in the gap.
+//!
+//! While a solver runs, the voltage across the gap and the current the feed
+//! drives through it are recorded (see [`PortOutput`]). The impedance seen by
+//! the feed gives the port's reflection coefficient.
+
+use std::{
+    f64::consts::TAU,
+    sync::Arc,
+};
+
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    name::NameOrEntity,
+    query::Changed,
+    reflect::ReflectComponent,
+    system::Query,
+    world::World,
+};
+use bevy_reflect::{
+    Reflect,
+    ReflectSerialize,
+    prelude::ReflectDefault,
+};
+use cem_probe::{
+    PropertiesUi,
+    TrackChanges,
+};
+use cem_render::{
+    material::Wireframe,
+    mesh::LoadMesh,
+};
+use cem_scene::{
+    Scene,
+    probe::{
+        ComponentName,
+        ReflectComponentUi,
+    },
+    spatial::Collider,
+    transform::LocalTransform,
+};
+use cem_solver::{
+    Field,
+    FieldComponent,
+    FieldView,
+    material::{
+        PhysicalConstants,
+        UnitSystem,
+    },
+    source::{
+        GaussianPulse,
+        SourceFunction,
+        feed::{
+            Feed,
+            FeedKind,
+        },
+    },
+};
+use nalgebra::{
+    Point3,
+    Vector3,
+};
+use num::complex::Complex64;
+use palette::{
+    Srgb,
+    Srgba,
+    WithAlpha,
+};
+use parking_lot::Mutex;
+use parry3d::shape::Cuboid;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    color_scheme,
+    composer::{
+        selection::Selectable,
+        tree::ShowInTree,
+    },
+    solver::{
+        config::SolverConfig,
+        network::Reflection,
+        probe::PointProbe,
+    },
+    util::scene::EntityBuilderExt,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Component, Reflect)]
+#[reflect(Component, ComponentUi, @ComponentName::new("Port"), Default, Serialize)]
+pub struct Port {
+    /// Number of the port, starting at 1. This orders the ports in results and
+    /// picks their color.
+    #[serde(default = "default_port_number")]
+    pub number: usize,
+
+    /// Impedance the port is calibrated to, in Ω. This is the same in all unit
+    /// systems, like in Touchstone files.
+    pub reference_impedance: f64,
+
+    /// Length of the gap
+    pub length: f32,
+}
+
+impl Default for Port {
+    fn default() -> Self {
+        Self {
+            number: default_port_number(),
+            reference_impedance: 50.0,
+            length: 0.001,
+        }
+    }
+}
+
+fn default_port_number() -> usize {
+    1
+}
+
+impl Port {
+    pub fn color(&self) -> egui::Color32 {
+        port_color(self.number)
+    }
+}
+
+/// Color of the port with the given number, in the 3D view as well as in
+/// plots.
+pub fn port_color(number: usize) -> egui::Color32 {
+    color_scheme::palette().categorical(number.saturating_sub(1))
+}
+
+fn wireframe_color(color: egui::Color32) -> Srgba {
+    Srgb::new(color.r(), color.g(), color.b())
+        .into_format()
+        .with_alpha(1.0)
+}
+
+impl PropertiesUi for Port {
+    type Config = ();
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
+        let _ = config;
+        let mut changes = TrackChanges::default();
+
+        let response = egui::Frame::new()
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Number");
+                    changes.track(
+                        ui.add(egui::DragValue::new(&mut self.number).range(1..=usize::MAX)),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Reference Impedance");
+                    changes.track(
+                        ui.add(
+                            egui::DragValue::new(&mut self.reference_impedance)
+                                .range(0.0..=f64::INFINITY)
+                                .speed(0.1)
+                                .suffix(" Ω"),
+                        ),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Gap Length");
+                    changes.track(
+                        ui.add(
+                            egui::DragValue::new(&mut self.length)
+                                .range(0.0..=f32::INFINITY)
+                                .speed(1e-4)
+                                .suffix(" m"),
+                        ),
+                    );
+                });
+            })
+            .response;
+
+        changes.propagated(response)
+    }
+}
+
+/// Adds a port with the gap along the local z axis of `transform`.
+///
+/// The port gets the next free number. It's excited with a Gaussian pulse
+/// whose spectrum falls off by about 20 dB at `max_frequency`.
+pub fn add_port(
+    scene: &mut Scene,
+    name: impl std::fmt::Display,
+    transform: LocalTransform,
+    mut port: Port,
+    max_frequency: f64,
+) {
+    port.number = scene
+        .world
+        .query::<&Port>()
+        .iter(&scene.world)
+        .map(|port| port.number)
+        .max()
+        .unwrap_or_default()
+        + 1;
+
+    let duration = 0.5 / max_frequency;
+    let feed = Feed::new(
+        FeedKind::HertzianDipole {
+            length: port.length.into(),
+        },
+        GaussianPulse::new(4.0 * duration, duration),
+    );
+
+    let marker = Cuboid::new(Vector3::new(0.25, 0.25, 0.5) * port.length);
+
+    scene
+        .world
+        .spawn((
+            port,
+            feed,
+            PointProbe::default(),
+            Collider::from(marker),
+            LoadMesh::from_shape(marker, ()),
+            Wireframe::new(wireframe_color(port.color())),
+            Selectable,
+            ShowInTree,
+        ))
+        .name(name)
+        .transform(transform);
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PortSample {
+    pub time: f64,
+    pub voltage: f64,
+    pub current: f64,
+}
+
+/// Handle to the voltage and current recorded at a port of a running solver.
+#[derive(Clone, Debug)]
+pub struct PortOutput {
+    samples: Arc<Mutex<Vec<PortSample>>>,
+    number: usize,
+    reference_impedance: f64,
+
+    /// Converts impedances from the solver's units to Ω
+    impedance_scale: f64,
+
+    /// Converts times from the solver's units to s
+    time_scale: f64,
+}
+
+impl PortOutput {
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    pub fn color(&self) -> egui::Color32 {
+        port_color(self.number)
+    }
+
+    pub fn reference_impedance(&self) -> f64 {
+        self.reference_impedance
+    }
+
+    pub fn num_samples(&self) -> usize {
+        self.samples.lock().len()
+    }
+
+    /// Highest frequency that is sampled at least 20 times per period, in Hz.
+    ///
+    /// Returns `None` if there are less than 2 samples.
+    pub fn max_frequency(&self) -> Option<f64> {
+        let samples = self.samples.lock();
+        let [first, second, ..] = samples.as_slice()
+        else {
+            return None;
+        };
+        Some(1.0 / (20.0 * (second.time - first.time) * self.time_scale))
+    }
+
+    /// Voltage and current phasors at `frequencies` (in Hz), as far as the port
+    /// has been recorded. The current flows into the port, and is scaled so
+    /// that voltage over current is in Ω.
+    ///
+    /// Returns `None` if there are no samples yet.
+    pub fn phasors(&self, frequencies: &[f64]) -> Option<Vec<(Complex64, Complex64)>> {
+        let samples = self.samples.lock();
+        if samples.is_empty() {
+            return None;
+        }
+
+        // the samples are spaced uniformly, so the time step cancels out of V/I
+        let phasors = frequencies
+            .iter()
+            .map(|frequency| {
+                let omega = TAU * frequency;
+                let (voltage, current) = samples.iter().fold(
+                    (Complex64::ZERO, Complex64::ZERO),
+                    |(voltage, current), sample| {
+                        let phasor = Complex64::cis(-omega * sample.time * self.time_scale);
+                        (
+                            voltage + phasor * sample.voltage,
+                            current + phasor * sample.current,
+                        )
+                    },
+                );
+                (voltage, current / self.impedance_scale)
+            })
+            .collect();
+
+        Some(phasors)
+    }
+
+    /// Reflection coefficient at `frequencies` (in Hz), as far as the port has
+    /// been recorded.
+    ///
+    /// Returns `None` if there are no samples yet.
+    pub fn reflection(&self, name: impl Into<String>, frequencies: Vec<f64>) -> Option<Reflection> {
+        let values = self
+            .phasors(&frequencies)?
+            .into_iter()
+            .map(|(voltage, current)| {
+                let impedance = voltage / current;
+                (impedance - self.reference_impedance) / (impedance + self.reference_impedance)
+            })
+            .collect();
+
+        Some(Reflection {
+            name: name.into(),
+            reference_impedance: self.reference_impedance,
+            frequencies,
+            values,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct PortInstance {
+    pub label: String,
+    point: Point3<usize>,
+
+    /// Direction of the gap in the solver's frame
+    direction: Vector3<f64>,
+
+    length: f64,
+
+    /// Cross section of the cells the feed drives its current through
+    cell_area: f64,
+
+    waveform: Arc<dyn SourceFunction<Output = f64>>,
+    output: PortOutput,
+}
+
+impl PortInstance {
+    pub fn new(
+        label: String,
+        point: Point3<usize>,
+        direction: Vector3<f64>,
+        cell_size: &Vector3<f64>,
+        port: &Port,
+        feed: &Feed,
+        units: UnitSystem,
+    ) -> Self {
+        let cell_length = direction.abs().dot(cell_size);
+
+        Self {
+            label,
+            point,
+            direction,
+            length: port.length.into(),
+            cell_area: cell_size.product() / cell_length,
+            waveform: feed.waveform.clone(),
+            output: PortOutput {
+                samples: Default::default(),
+                number: port.number,
+                reference_impedance: port.reference_impedance,
+                impedance_scale: PhysicalConstants::SI.vacuum_impedance()
+                    / units.physical_constants().vacuum_impedance(),
+                time_scale: units.conversion_to(&UnitSystem::Si).time,
+            },
+        }
+    }
+
+    pub fn number(&self) -> usize {
+        self.output.number
+    }
+
+    pub fn output(&self) -> PortOutput {
+        self.output.clone()
+    }
+
+    pub fn run<I>(&mut self, instance: &I, state: &I::State, time: f64)
+    where
+        I: Field<Point3<usize>>,
+    {
+        let field = instance
+            .field(state, self.point..=self.point, FieldComponent::E)
+            .at(&self.point)
+            .unwrap_or_default();
+
+        // the feed's current charges the upper end of the gap, so E points against it
+        let voltage = -field.dot(&self.direction) * self.length;
+        let current = self.waveform.evaluate(time) * self.cell_area;
+
+        self.output.samples.lock().push(PortSample {
+            time,
+            voltage,
+            current,
+        });
+    }
+}
+
+/// Keeps the wireframes of ports in their port's color.
+fn update_port_colors_system(mut ports: Query<(&Port, &mut Wireframe), Changed<Port>>) {
+    for (port, mut wireframe) in &mut ports {
+        wireframe.color = wireframe_color(port.color());
+    }
+}
+
+/// Lists the ports of a scene.
+///
+/// Ports can be renumbered, and enabled or disabled for each solver config.
+#[derive(Debug, Default)]
+pub struct PortsWindow {
+    pub open: bool,
+
+    /// Index of the solver config ports are enabled or disabled for
+    solver_config: usize,
+}
+
+impl PortsWindow {
+    /// Returns the previous solver configs if they were edited.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        world: &mut World,
+        solver_configs: &mut Vec<SolverConfig>,
+    ) -> Option<Vec<SolverConfig>> {
+        world.run_system_cached(update_port_colors_system).unwrap();
+
+        let mut previous_solver_configs = None;
+
+        egui::Window::new("Ports")
+            .movable(true)
+            .default_size([350.0, 200.0])
+            .open(&mut self.open)
+            .show(ctx, |ui| {
+                let mut ports = world
+                    .query::<(Entity, NameOrEntity, &Port)>()
+                    .iter(world)
+                    .map(|(entity, name, port)| (entity, name.to_string(), *port))
+                    .collect::<Vec<_>>();
+                ports.sort_by_key(|(_, _, port)| port.number);
+
+                if ports.is_empty() {
+                    ui.weak("No ports. Add one with Run > Add Feed.");
+                    return;
+                }
+
+                self.solver_config = self
+                    .solver_config
+                    .min(solver_configs.len().saturating_sub(1));
+                if let Some(solver_config) = solver_configs.get(self.solver_config) {
+                    ui.horizontal(|ui| {
+                        ui.label("Enabled in");
+                        egui::ComboBox::from_id_salt("solver_config")
+                            .selected_text(&solver_config.label)
+                            .show_ui(ui, |ui| {
+                                for (i, solver_config) in solver_configs.iter().enumerate() {
+                                    ui.selectable_value(
+                                        &mut self.solver_config,
+                                        i,
+                                        &solver_config.label,
+                                    );
+                                }
+                            });
+                    });
+                }
+
+                let numbers = ports
+                    .iter()
+                    .map(|(_, _, port)| port.number)
+                    .collect::<Vec<_>>();
+                let mut changed = vec![];
+                let mut toggled = None;
+
+                egui::Grid::new("ports").num_columns(4).show(ui, |ui| {
+                    for (i, (_, name, port)) in ports.iter_mut().enumerate() {
+                        let label = ui
+                            .colored_label(port.color(), format!("■ Port {}", port.number))
+                            .on_hover_text("Color of the port in the view and in plots");
+                        if numbers
+                            .iter()
+                            .filter(|number| **number == port.number)
+                            .count()
+                            > 1
+                        {
+                            label.on_hover_text("Another port has the same number");
+                        }
+                        ui.label(&*name);
+
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut port.reference_impedance)
+                                    .range(0.0..=f64::INFINITY)
+                                    .speed(0.1)
+                                    .suffix(" Ω"),
+                            )
+                            .changed()
+                        {
+                            changed.push(i);
+                        }
+
+                        if let Some(solver_config) = solver_configs.get(self.solver_config) {
+                            let mut enabled =
+                                !solver_config.common.disabled_ports.contains(&port.number);
+                            if ui.checkbox(&mut enabled, "").changed() {
+                                toggled = Some((port.number, enabled));
+                            }
+                        }
+
+                        ui.end_row();
+                    }
+                });
+
+                if ui
+                    .button("Renumber")
+                    .on_hover_text("Number the ports from 1 in their current order")
+                    .clicked()
+                {
+                    for (i, (_, _, port)) in ports.iter_mut().enumerate() {
+                        if port.number != i + 1 {
+                            port.number = i + 1;
+                            changed.push(i);
+                        }
+                    }
+                }
+
+                for i in changed {
+                    let (entity, _, port) = &ports[i];
+                    if let Some(mut component) = world.get_mut::<Port>(*entity) {
+                        *component = *port;
+                    }
+                }
+
+                if let Some((number, enabled)) = toggled {
+                    previous_solver_configs = Some(solver_configs.clone());
+                    let disabled_ports =
+                        &mut solver_configs[self.solver_config].common.disabled_ports;
+                    if enabled {
+                        disabled_ports.remove(&number);
+                    }
+                    else {
+                        disabled_ports.insert(number);
+                    }
+                }
+            });
+
+        previous_solver_configs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::solver::port::{
+        PortOutput,
+        PortSample,
+    };
+
+    #[test]
+    fn matched_resistor_doesnt_reflect() {
+        // a 50 Ω resistor across the gap: V = R I at all times
+        let samples = (0..256)
+            .map(|i| {
+                let time = i as f64 * 1e-11;
+                let current = (-((time - 5e-10) / 1e-10).powi(2)).exp();
+                PortSample {
+                    time,
+                    voltage: 50.0 * current,
+                    current,
+                }
+            })
+            .collect::<Vec<_>>();
+        let output = PortOutput {
+            samples: Arc::new(samples.into()),
+            number: 1,
+            reference_impedance: 50.0,
+            impedance_scale: 1.0,
+            time_scale: 1.0,
+        };
+
+        let reflection = output.reflection("S11", vec![1e9, 2e9]).unwrap();
+        for value in reflection.values {
+            assert!(value.norm() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,856 @@
+use std::{
+    collections::{
+        BTreeSet,
+        VecDeque,
+    },
+    fs::File,
+    io::{
+        BufWriter,
+        Write,
+    },
+    path::PathBuf,
+    sync::Arc,
+};
+
+use bevy_ecs::{
+    component::Component,
+    system::{
+        InRef,
+        Query,
+        Res,
+    },
+    world::World,
+};
+use cem_probe::{
+    PropertiesUi,
+    PropertiesUiExt,
+    TrackChanges,
+    label_and_value,
+    label_and_value_with_config,
+};
+use cem_scene::{
+    stable_id::NameOrStableId,
+    transform::GlobalTransform,
+};
+use cem_solver::{
+    Field,
+    FieldComponent,
+    FieldView,
+    material::UnitSystem,
+    source::feed::Feed,
+};
+use cem_util::egui::{
+    FilePickerConfig,
+    RepaintTrigger,
+};
+use nalgebra::{
+    Point3,
+    Vector3,
+};
+use parking_lot::Mutex;
+
+use crate::{
+    Error,
+    clipboard::copy_image_or_csv_context_menu,
+    solver::{
+        array::Excitation,
+        far_field::{
+            FarFieldProbe,
+            FarFieldProbeInstance,
+            FarFieldProbeOutput,
+        },
+        nf2ff::{
+            Nf2ffBox,
+            Nf2ffInstance,
+            Nf2ffOutput,
+        },
+        observer::FieldNames,
+        port::{
+            Port,
+            PortInstance,
+            PortOutput,
+        },
+        runner::CoordinateTransformations,
+        spectrogram::{
+            Spectrogram,
+            SpectrogramConfig,
+            spectrogram_plot,
+        },
+        units::SceneUnits,
+    },
+};
+
+/// Samples a field along a line segment.
+#[derive(Clone, Debug, Component)]
+pub struct LineProbe {
+    /// Start of the segment in the entity's local frame
+    pub start: Point3<f32>,
+
+    /// End of the segment in the entity's local frame
+    pub end: Point3<f32>,
+
+    pub num_samples: usize,
+
+    pub field: FieldComponent,
+
+    /// Sample every N ticks
+    pub interval: usize,
+
+    /// Append every sampled cut to this CSV file
+    pub write_to_csv: Option<PathBuf>,
+}
+
+impl Default for LineProbe {
+    fn default() -> Self {
+        Self {
+            start: Point3::new(-0.5, 0.0, 0.0),
+            end: Point3::new(0.5, 0.0, 0.0),
+            num_samples: 100,
+            field: FieldComponent::E,
+            interval: 10,
+            write_to_csv: None,
+        }
+    }
+}
+
+impl PropertiesUi for LineProbe {
+    type Config = ();
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
+        let _ = config;
+        let mut changes = TrackChanges::default();
+
+        let response = egui::Frame::new()
+            .show(ui, |ui| {
+                egui::ComboBox::from_id_salt(ui.id().with("field"))
+                    .selected_text(FieldNames[self.field])
+                    .show_ui(ui, |ui| {
+                        for field in [FieldComponent::E, FieldComponent::H] {
+                            changes.track(ui.selectable_value(
+                                &mut self.field,
+                                field,
+                                FieldNames[field],
+                            ));
+                        }
+                    });
+
+                label_and_value(ui, "Start", &mut changes, &mut self.start);
+                label_and_value(ui, "End", &mut changes, &mut self.end);
+
+                ui.horizontal(|ui| {
+                    ui.label("Samples");
+                    changes.track(
+                        ui.add(egui::DragValue::new(&mut self.num_samples).range(2..=10000)),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Every N Ticks");
+                    changes.track(
+                        ui.add(egui::DragValue::new(&mut self.interval).range(1..=usize::MAX)),
+                    );
+                });
+
+                label_and_value_with_config(
+                    ui,
+                    "CSV File",
+                    &mut changes,
+                    &mut self.write_to_csv,
+                    &FilePickerConfig::Save,
+                );
+            })
+            .response;
+
+        changes.propagated(response)
+    }
+}
+
+/// Records a field at the entity's position over time.
+#[derive(Clone, Debug, Component)]
+pub struct PointProbe {
+    pub field: FieldComponent,
+
+    /// Sample every N ticks
+    pub interval: usize,
+
+    /// Number of most recent samples to keep
+    pub max_samples: usize,
+}
+
+impl Default for PointProbe {
+    fn default() -> Self {
+        Self {
+            field: FieldComponent::E,
+            interval: 1,
+            max_samples: 1 << 14,
+        }
+    }
+}
+
+impl PropertiesUi for PointProbe {
+    type Config = ();
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
+        let _ = config;
+        let mut changes = TrackChanges::default();
+
+        let response = egui::Frame::new()
+            .show(ui, |ui| {
+                egui::ComboBox::from_id_salt(ui.id().with("field"))
+                    .selected_text(FieldNames[self.field])
+                    .show_ui(ui, |ui| {
+                        for field in [FieldComponent::E, FieldComponent::H] {
+                            changes.track(ui.selectable_value(
+                                &mut self.field,
+                                field,
+                                FieldNames[field],
+                            ));
+                        }
+                    });
+
+                ui.horizontal(|ui| {
+                    ui.label("Every N Ticks");
+                    changes.track(
+                        ui.add(egui::DragValue::new(&mut self.interval).range(1..=usize::MAX)),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Max. Samples");
+                    changes.track(
+                        ui.add(egui::DragValue::new(&mut self.max_samples).range(16..=1 << 20)),
+                    );
+                });
+            })
+            .response;
+
+        changes.propagated(response)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PointSample {
+    pub time: f64,
+    pub value: Vector3<f64>,
+}
+
+pub fn point_samples_to_csv(samples: &[PointSample]) -> String {
+    let mut csv = "time,x,y,z\n".to_owned();
+    for sample in samples {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            sample.time, sample.value.x, sample.value.y, sample.value.z
+        ));
+    }
+    csv
+}
+
+/// Handle to the recorded samples of a running point probe.
+#[derive(Clone, Debug, Default)]
+pub struct PointProbeOutput {
+    samples: Arc<Mutex<VecDeque<PointSample>>>,
+}
+
+impl PointProbeOutput {
+    pub fn samples(&self) -> Vec<PointSample> {
+        self.samples.lock().iter().copied().collect()
+    }
+
+    pub fn latest(&self) -> Option<PointSample> {
+        self.samples.lock().back().copied()
+    }
+
+    /// Samples taken up to `time`.
+    pub fn samples_until(&self, time: f64) -> Vec<PointSample> {
+        self.samples
+            .lock()
+            .iter()
+            .take_while(|sample| sample.time <= time)
+            .copied()
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+struct PointProbeInstance {
+    label: String,
+    point: Point3<usize>,
+    field: FieldComponent,
+    interval: usize,
+    max_samples: usize,
+    output: PointProbeOutput,
+}
+
+impl PointProbeInstance {
+    fn run<I>(&mut self, instance: &I, state: &I::State, time: f64)
+    where
+        I: Field<Point3<usize>>,
+    {
+        let value = instance
+            .field(state, self.point..=self.point, self.field)
+            .at(&self.point)
+            .unwrap_or_default();
+
+        let mut samples = self.output.samples.lock();
+        if samples.len() >= self.max_samples {
+            samples.pop_front();
+        }
+        samples.push_back(PointSample { time, value });
+    }
+}
+
+/// A single sampled line cut
+#[derive(Clone, Debug, Default)]
+pub struct LineCut {
+    pub tick: usize,
+    pub time: f64,
+
+    /// Distance of each sample from the start of the segment
+    pub distances: Vec<f64>,
+
+    pub values: Vec<Vector3<f64>>,
+}
+
+impl LineCut {
+    pub fn to_csv(&self) -> String {
+        let mut csv = "distance,x,y,z\n".to_owned();
+        for (distance, value) in self.distances.iter().zip(&self.values) {
+            csv.push_str(&format!("{distance},{},{},{}\n", value.x, value.y, value.z));
+        }
+        csv
+    }
+}
+
+/// Handle to the most recent [`LineCut`] of a running line probe.
+#[derive(Clone, Debug, Default)]
+pub struct LineProbeOutput {
+    latest: Arc<Mutex<Option<LineCut>>>,
+}
+
+impl LineProbeOutput {
+    pub fn latest(&self) -> Option<LineCut> {
+        self.latest.lock().clone()
+    }
+}
+
+#[derive(Debug)]
+struct LineProbeInstance {
+    label: String,
+    points: Vec<Point3<usize>>,
+    distances: Vec<f64>,
+    field: FieldComponent,
+    interval: usize,
+    output: LineProbeOutput,
+    csv_writer: Option<BufWriter<File>>,
+}
+
+impl LineProbeInstance {
+    fn run<I>(
+        &mut self,
+        instance: &I,
+        state: &I::State,
+        tick: usize,
+        time: f64,
+    ) -> Result<(), Error>
+    where
+        I: Field<Point3<usize>>,
+    {
+        let (Some(min), Some(max)) = (
+            self.points.iter().copied().reduce(|a, b| a.inf(&b)),
+            self.points.iter().copied().reduce(|a, b| a.sup(&b)),
+        )
+        else {
+            return Ok(());
+        };
+
+        let view = instance.field(state, min..=max, self.field);
+        let values = self
+            .points
+            .iter()
+            .map(|point| view.at(point).unwrap_or_default())
+            .collect::<Vec<_>>();
+
+        if let Some(csv_writer) = &mut self.csv_writer {
+            for (distance, value) in self.distances.iter().zip(&values) {
+                writeln!(
+                    csv_writer,
+                    "{tick},{time},{distance},{},{},{}",
+                    value.x, value.y, value.z
+                )?;
+            }
+            csv_writer.flush()?;
+        }
+
+        *self.output.latest.lock() = Some(LineCut {
+            tick,
+            time,
+            distances: self.distances.clone(),
+            values,
+        });
+
+        Ok(())
+    }
+}
+
+/// Handles to the outputs of all probes of a running solver.
+#[derive(Clone, Debug, Default)]
+pub struct ProbeOutputs {
+    pub line_probes: Vec<(String, LineProbeOutput)>,
+    pub point_probes: Vec<(String, PointProbeOutput)>,
+    pub nf2ff_boxes: Vec<(String, Nf2ffOutput)>,
+    pub far_field_probes: Vec<(String, FarFieldProbeOutput)>,
+    pub ports: Vec<(String, PortOutput)>,
+
+    /// Unit system the solver ran in
+    pub units: UnitSystem,
+}
+
+/// All probes of a solver run.
+#[derive(Debug, Default)]
+pub(super) struct Probes {
+    line_probes: Vec<LineProbeInstance>,
+    point_probes: Vec<PointProbeInstance>,
+    nf2ff_boxes: Vec<Nf2ffInstance>,
+    far_field_probes: Vec<FarFieldProbeInstance>,
+    ports: Vec<PortInstance>,
+    repaint_trigger: Option<RepaintTrigger>,
+    units: UnitSystem,
+}
+
+impl Probes {
+    pub fn from_scene(
+        world: &mut World,
+        coordinate_transformations: &CoordinateTransformations,
+        disabled_ports: &BTreeSet<usize>,
+        repaint_trigger: RepaintTrigger,
+    ) -> Result<Self, Error> {
+        let mut probes = world
+            .run_system_cached_with(
+                setup_probes_system,
+                (coordinate_transformations, disabled_ports),
+            )
+            .unwrap()?;
+
+        if !probes.line_probes.is_empty()
+            || !probes.point_probes.is_empty()
+            || !probes.ports.is_empty()
+        {
+            probes.repaint_trigger = Some(repaint_trigger);
+        }
+
+        Ok(probes)
+    }
+
+    pub fn outputs(&self) -> ProbeOutputs {
+        ProbeOutputs {
+            line_probes: self
+                .line_probes
+                .iter()
+                .map(|line_probe| (line_probe.label.clone(), line_probe.output.clone()))
+                .collect(),
+            point_probes: self
+                .point_probes
+                .iter()
+                .map(|point_probe| (point_probe.label.clone(), point_probe.output.clone()))
+                .collect(),
+            nf2ff_boxes: self
+                .nf2ff_boxes
+                .iter()
+                .map(|nf2ff_box| (nf2ff_box.label.clone(), nf2ff_box.output()))
+                .collect(),
+            far_field_probes: self
+                .far_field_probes
+                .iter()
+                .map(|probe| (probe.label.clone(), probe.output()))
+                .collect(),
+            ports: self
+                .ports
+                .iter()
+                .map(|port| (port.label.clone(), port.output()))
+                .collect(),
+            units: self.units,
+        }
+    }
+
+    pub fn run<I>(
+        &mut self,
+        instance: &I,
+        state: &I::State,
+        tick: usize,
+        time: f64,
+    ) -> Result<(), Error>
+    where
+        I: Field<Point3<usize>>,
+    {
+        let mut needs_repaint = false;
+
+        for line_probe in &mut self.line_probes {
+            if tick.is_multiple_of(line_probe.interval) {
+                line_probe.run(instance, state, tick, time)?;
+                needs_repaint = true;
+            }
+        }
+
+        for point_probe in &mut self.point_probes {
+            if tick.is_multiple_of(point_probe.interval) {
+                point_probe.run(instance, state, time);
+                needs_repaint = true;
+            }
+        }
+
+        for nf2ff_box in &mut self.nf2ff_boxes {
+            if tick.is_multiple_of(nf2ff_box.interval()) {
+                nf2ff_box.run(instance, state, time);
+            }
+        }
+
+        for far_field_probe in &mut self.far_field_probes {
+            if tick.is_multiple_of(far_field_probe.interval()) {
+                far_field_probe.run(instance, state, time);
+            }
+        }
+
+        // ports need every tick for the reflection coefficient
+        for port in &mut self.ports {
+            port.run(instance, state, time);
+        }
+        needs_repaint |= !self.ports.is_empty();
+
+        if needs_repaint && let Some(repaint_trigger) = &self.repaint_trigger {
+            repaint_trigger.repaint();
+        }
+
+        Ok(())
+    }
+}
+
+fn setup_probes_system(
+    (InRef(coordinate_transformations), InRef(disabled_ports)): (
+        InRef<CoordinateTransformations>,
+        InRef<BTreeSet<usize>>,
+    ),
+    line_probes: Query<(NameOrStableId, &GlobalTransform, &LineProbe)>,
+    point_probes: Query<(NameOrStableId, &GlobalTransform, &PointProbe)>,
+    nf2ff_boxes: Query<(NameOrStableId, &GlobalTransform, &Nf2ffBox)>,
+    far_field_probes: Query<(NameOrStableId, &GlobalTransform, &FarFieldProbe)>,
+    ports: Query<(
+        NameOrStableId,
+        &GlobalTransform,
+        &Port,
+        &Feed,
+        Option<&Excitation>,
+    )>,
+    scene_units: Option<Res<SceneUnits>>,
+) -> Result<Probes, Error> {
+    let line_probes = line_probes
+        .iter()
+        .map(|(name, transform, line_probe)| {
+            let start = transform.precise_isometry() * line_probe.start.cast::<f64>();
+            let end = transform.precise_isometry() * line_probe.end.cast::<f64>();
+            let length = nalgebra::distance(&start, &end);
+            let num_samples = line_probe.num_samples.max(2);
+
+            let (distances, points) = (0..num_samples)
+                .filter_map(|i| {
+                    let t = i as f64 / (num_samples - 1) as f64;
+                    let world_point = start + (end - start) * t;
+                    let point = coordinate_transformations
+                        .transform_point_from_world_to_solver(&world_point)?;
+                    Some((t * length, point))
+                })
+                .unzip();
+
+            let csv_writer = line_probe
+                .write_to_csv
+                .as_ref()
+                .map(|path| {
+                    let mut writer = BufWriter::new(File::create(path)?);
+                    writeln!(writer, "tick,time,distance,x,y,z")?;
+                    Ok::<_, Error>(writer)
+                })
+                .transpose()?;
+
+            tracing::debug!(%name, ?start, ?end, "creating line probe");
+
+            Ok(LineProbeInstance {
+                label: name.to_string(),
+                points,
+                distances,
+                field: line_probe.field,
+                interval: line_probe.interval.max(1),
+                output: Default::default(),
+                csv_writer,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let point_probes = point_probes
+        .iter()
+        .filter_map(|(name, transform, point_probe)| {
+            let world_point = transform.precise_position();
+            let Some(point) =
+                coordinate_transformations.transform_point_from_world_to_solver(&world_point)
+            else {
+                tracing::warn!(%name, ?world_point, "point probe outside of solver volume");
+                return None;
+            };
+
+            tracing::debug!(%name, ?world_point, ?point, "creating point probe");
+
+            Some(PointProbeInstance {
+                label: name.to_string(),
+                point,
+                field: point_probe.field,
+                interval: point_probe.interval.max(1),
+                max_samples: point_probe.max_samples.max(1),
+                output: Default::default(),
+            })
+        })
+        .collect();
+
+    let units = scene_units.map(|units| units.system).unwrap_or_default();
+
+    // far-field probes sample the surface of the closest NF2FF box
+    let far_field_probes = far_field_probes
+        .iter()
+        .filter_map(|(name, transform, far_field_probe)| {
+            let Some((box_name, box_transform, nf2ff_box)) =
+                nf2ff_boxes.iter().min_by(|&(_, a, _), &(_, b, _)| {
+                    let distance = |other: &GlobalTransform| {
+                        nalgebra::distance(&transform.position(), &other.position())
+                    };
+                    distance(a).total_cmp(&distance(b))
+                })
+            else {
+                tracing::warn!(%name, "far-field probe without NF2FF box");
+                return None;
+            };
+
+            let Some(instance) = FarFieldProbeInstance::new(
+                name.to_string(),
+                far_field_probe,
+                transform,
+                (box_name.to_string(), nf2ff_box, box_transform),
+                coordinate_transformations,
+                units,
+            )
+            else {
+                tracing::warn!(%name, %box_name, "NF2FF box outside of solver volume");
+                return None;
+            };
+
+            tracing::debug!(%name, %box_name, "creating far-field probe");
+
+            Some(instance)
+        })
+        .collect();
+
+    let nf2ff_boxes = nf2ff_boxes
+        .iter()
+        .filter_map(|(name, transform, nf2ff_box)| {
+            let Some(instance) = Nf2ffInstance::new(
+                name.to_string(),
+                nf2ff_box,
+                transform,
+                coordinate_transformations,
+                units,
+            )
+            else {
+                tracing::warn!(%name, "NF2FF box outside of solver volume");
+                return None;
+            };
+
+            tracing::debug!(%name, "creating NF2FF box");
+
+            Some(instance)
+        })
+        .collect();
+
+    let cell_size = coordinate_transformations.cell_size();
+    let mut ports = ports
+        .iter()
+        .filter(|(_, _, port, _, _)| !disabled_ports.contains(&port.number))
+        .filter_map(|(name, transform, port, feed, excitation)| {
+            let world_point = transform.precise_position();
+            let Some(point) =
+                coordinate_transformations.transform_point_from_world_to_solver(&world_point)
+            else {
+                tracing::warn!(%name, ?world_point, "port outside of solver volume");
+                return None;
+            };
+            let direction = coordinate_transformations.transform_vector_from_world_to_solver(
+                &transform.precise_isometry().transform_vector(&Vector3::z()),
+            );
+
+            tracing::debug!(%name, ?world_point, ?point, "creating port");
+
+            // the port sees the current the feed actually drives
+            let feed = excitation
+                .map_or_else(|| feed.clone(), |excitation| excitation.apply_to_feed(feed));
+
+            Some(PortInstance::new(
+                name.to_string(),
+                point,
+                direction,
+                &cell_size,
+                port,
+                &feed,
+                units,
+            ))
+        })
+        .collect::<Vec<_>>();
+    ports.sort_by_key(|port| port.number());
+
+    Ok(Probes {
+        line_probes,
+        point_probes,
+        nf2ff_boxes,
+        far_field_probes,
+        ports,
+        repaint_trigger: None,
+        units,
+    })
+}
+
+/// Plots the x, y, z components and magnitude of a line cut.
+pub fn line_cut_plot(ui: &mut egui::Ui, id_salt: impl std::hash::Hash, line_cut: &LineCut) {
+    let series = |f: fn(&Vector3<f64>) -> f64| {
+        line_cut
+            .distances
+            .iter()
+            .zip(&line_cut.values)
+            .map(|(distance, value)| [*distance, f(value)])
+            .collect::<Vec<_>>()
+    };
+
+    ui.label(format!("Tick {} (t = {:.3})", line_cut.tick, line_cut.time));
+
+    let response = egui_plot::Plot::new(id_salt)
+        .legend(egui_plot::Legend::default())
+        .height(200.0)
+        .show(ui, |plot_ui| {
+            plot_ui.line(egui_plot::Line::new("x", series(|v| v.x)));
+            plot_ui.line(egui_plot::Line::new("y", series(|v| v.y)));
+            plot_ui.line(egui_plot::Line::new("z", series(|v| v.z)));
+            plot_ui.line(egui_plot::Line::new("|v|", series(|v| v.norm())));
+        })
+        .response;
+
+    copy_image_or_csv_context_menu(&response, || line_cut.to_csv());
+}
+
+/// Scalar signal derived from a vector-valued probe sample
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SignalComponent {
+    X,
+    Y,
+    #[default]
+    Z,
+    Magnitude,
+}
+
+impl SignalComponent {
+    pub const ALL: [Self; 4] = [Self::X, Self::Y, Self::Z, Self::Magnitude];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SignalComponent::X => "x",
+            SignalComponent::Y => "y",
+            SignalComponent::Z => "z",
+            SignalComponent::Magnitude => "|v|",
+        }
+    }
+
+    pub fn of(&self, value: &Vector3<f64>) -> f64 {
+        match self {
+            SignalComponent::X => value.x,
+            SignalComponent::Y => value.y,
+            SignalComponent::Z => value.z,
+            SignalComponent::Magnitude => value.norm(),
+        }
+    }
+}
+
+/// Per-probe view settings, kept in egui's memory.
+#[derive(Clone, Copy, Debug, Default)]
+struct PointProbeView {
+    component: SignalComponent,
+    show_spectrogram: bool,
+    spectrogram: SpectrogramConfig,
+}
+
+/// Plots the signal of a point probe, and optionally its spectrogram.
+pub fn point_probe_plot(
+    ui: &mut egui::Ui,
+    id_salt: impl std::hash::Hash,
+    samples: &[PointSample],
+    units: UnitSystem,
+) {
+    let id = ui.id().with(&id_salt);
+    let mut view = ui.data_mut(|data| *data.get_temp_mut_or_default::<PointProbeView>(id));
+
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt(id.with("component"))
+            .selected_text(view.component.label())
+            .show_ui(ui, |ui| {
+                for component in SignalComponent::ALL {
+                    ui.selectable_value(&mut view.component, component, component.label());
+                }
+            });
+        ui.checkbox(&mut view.show_spectrogram, "Spectrogram");
+    });
+
+    let response = egui_plot::Plot::new(id.with("signal"))
+        .height(150.0)
+        .x_axis_label(format!("Time [{}]", units.time_unit()))
+        .show(ui, |plot_ui| {
+            plot_ui.line(egui_plot::Line::new(
+                view.component.label(),
+                samples
+                    .iter()
+                    .map(|sample| [sample.time, view.component.of(&sample.value)])
+                    .collect::<Vec<_>>(),
+            ));
+        })
+        .response;
+
+    copy_image_or_csv_context_menu(&response, || point_samples_to_csv(samples));
+
+    if view.show_spectrogram {
+        ui.properties(&mut view.spectrogram);
+
+        // samples are taken at a fixed tick interval, so they're uniformly spaced
+        let spectrogram = match samples {
+            [first, second, ..] => {
+                let signal = samples
+                    .iter()
+                    .map(|sample| view.component.of(&sample.value))
+                    .collect::<Vec<_>>();
+                Spectrogram::compute(
+                    first.time,
+                    second.time - first.time,
+                    &signal,
+                    &view.spectrogram,
+                )
+            }
+            _ => None,
+        };
+
+        if let Some(spectrogram) = spectrogram {
+            spectrogram_plot(
+                ui,
+                id.with("spectrogram"),
+                &spectrogram,
+                view.spectrogram.dynamic_range,
+                units,
+            );
+        }
+        else {
+            ui.label(format!(
+                "Not enough samples ({} / {})",
+                samples.len(),
+                view.spectrogram.window_size
+            ));
+        }
+    }
+
+    ui.data_mut(|data| data.insert_temp(id, view));
+}
@@ -0,0 +1,212 @@
+use bevy_ecs::{
+    query::{
+        Has,
+        Or,
+        With,
+    },
+    system::{
+        In,
+        Query,
+    },
+    world::World,
+};
+use cem_scene::{
+    Scene,
+    spatial::Collider,
+    transform::GlobalTransform,
+};
+use cem_solver::{
+    fdtd::mesh::{
+        GradedMesh,
+        GradedMeshConfig,
+        MeshRefinement,
+        RefinementBox,
+    },
+    material::Material,
+    source::{
+        Source,
+        feed::Feed,
+    },
+};
+use nalgebra::{
+    Isometry3,
+    UnitQuaternion,
+    Vector3,
+};
+use parry3d::{
+    bounding_volume::{
+        Aabb,
+        BoundingVolume,
+    },
+    shape::Cuboid,
+};
+
+use crate::{
+    composer::{
+        file_formats::project_file::SaveToFile,
+        selection::Selectable,
+        tree::ShowInTree,
+    },
+    util::scene::EntityBuilderExt,
+};
+
+/// Features smaller than this many base cells are considered for refinement.
+const FEATURE_THRESHOLD_CELLS: f32 = 2.0;
+
+/// Number of cells a feature should at least be resolved with after
+/// refinement.
+const CELLS_PER_FEATURE: f32 = 4.0;
+
+/// Generates the graded mesh for the domain `aabb`.
+///
+/// The `aabb` and the refinement regions are in the frame of the solver
+/// volume, which is rotated by `rotation` relative to the world.
+pub fn graded_mesh_for_scene(
+    world: &mut World,
+    aabb: &Aabb,
+    rotation: &UnitQuaternion<f32>,
+    config: &GradedMeshConfig,
+) -> GradedMesh {
+    let regions = world
+        .run_system_cached_with(
+            |In(rotation): In<UnitQuaternion<f32>>,
+             regions: Query<(&GlobalTransform, &Collider, &MeshRefinement)>| {
+                let to_volume = Isometry3::from_parts(Default::default(), rotation.inverse());
+                regions
+                    .iter()
+                    .filter_map(|(transform, collider, refinement)| {
+                        let aabb = collider.compute_aabb(&(to_volume * transform.isometry()))?;
+                        Some(RefinementBox {
+                            min: aabb.mins.cast(),
+                            max: aabb.maxs.cast(),
+                            cell_size: refinement.cell_size,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            },
+            *rotation,
+        )
+        .unwrap();
+
+    GradedMesh::generate(&aabb.mins.cast(), &aabb.maxs.cast(), config, &regions)
+}
+
+#[derive(Clone, Debug)]
+pub struct RefinementProposal {
+    pub reason: RefinementReason,
+    pub aabb: Aabb,
+    pub cell_size: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RefinementReason {
+    Source,
+    ThinFeature,
+    Gap,
+}
+
+impl RefinementReason {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RefinementReason::Source => "Source",
+            RefinementReason::ThinFeature => "Thin Feature",
+            RefinementReason::Gap => "Gap",
+        }
+    }
+}
+
+/// Looks for sources, thin features (e.g. wires) and small gaps between
+/// material objects, that wouldn't be resolved well by the base cell size.
+///
+/// This only looks at bounding boxes, so it's a heuristic. The proposals are
+/// meant to be reviewed by the user.
+pub fn propose_refinements(world: &mut World, base_cell_size: f32) -> Vec<RefinementProposal> {
+    world
+        .run_system_cached_with(propose_refinements_system, base_cell_size)
+        .unwrap()
+}
+
+fn propose_refinements_system(
+    In(base_cell_size): In<f32>,
+    sources: Query<&GlobalTransform, Or<(With<Source>, With<Feed>)>>,
+    objects: Query<(&GlobalTransform, &Collider, Has<Material>)>,
+) -> Vec<RefinementProposal> {
+    let threshold = FEATURE_THRESHOLD_CELLS * base_cell_size;
+    let mut proposals = vec![];
+
+    // refine the immediate neighborhood of sources
+    for transform in &sources {
+        proposals.push(RefinementProposal {
+            reason: RefinementReason::Source,
+            aabb: Aabb::from_half_extents(transform.position(), Vector3::repeat(threshold)),
+            cell_size: 0.5 * base_cell_size as f64,
+        });
+    }
+
+    let material_aabbs = objects
+        .iter()
+        .filter(|(_, _, has_material)| *has_material)
+        .filter_map(|(transform, collider, _)| collider.compute_aabb(transform.isometry()))
+        .collect::<Vec<_>>();
+
+    // thin features
+    for aabb in &material_aabbs {
+        let min_extent = aabb.extents().min();
+        if min_extent > 0.0 && min_extent < threshold {
+            proposals.push(RefinementProposal {
+                reason: RefinementReason::ThinFeature,
+                aabb: aabb.loosened(base_cell_size),
+                cell_size: (min_extent / CELLS_PER_FEATURE) as f64,
+            });
+        }
+    }
+
+    // gaps between objects that overlap along two axes, and are separated along
+    // the third.
+    for (i, a) in material_aabbs.iter().enumerate() {
+        for b in &material_aabbs[i + 1..] {
+            let separation = (b.mins - a.maxs).sup(&(a.mins - b.maxs));
+            let separated = separation.map(|s| s > 0.0);
+            if separated.iter().filter(|s| **s).count() != 1 {
+                continue;
+            }
+
+            let gap = separation.max();
+            if gap < threshold {
+                let mut mins = a.mins.sup(&b.mins);
+                let mut maxs = a.maxs.inf(&b.maxs);
+                for (axis, separated) in separated.iter().enumerate() {
+                    if *separated {
+                        mins[axis] = a.maxs[axis].min(b.maxs[axis]);
+                        maxs[axis] = a.mins[axis].max(b.mins[axis]);
+                    }
+                }
+
+                proposals.push(RefinementProposal {
+                    reason: RefinementReason::Gap,
+                    aabb: Aabb::new(mins, maxs),
+                    cell_size: (gap / CELLS_PER_FEATURE) as f64,
+                });
+            }
+        }
+    }
+
+    proposals
+}
+
+/// Adds refinement region entities for the proposals to the scene.
+pub fn add_refinement_regions(scene: &mut Scene, proposals: &[RefinementProposal]) {
+    for proposal in proposals {
+        scene
+            .world
+            .spawn(MeshRefinement {
+                cell_size: proposal.cell_size,
+            })
+            .name(format!("Refinement ({})", proposal.reason.label()))
+            .transform(proposal.aabb.center())
+            .collider(Cuboid::new(proposal.aabb.half_extents()))
+            .tagged::<ShowInTree>(true)
+            .tagged::<Selectable>(true)
+            .tagged::<SaveToFile>(true);
+    }
+}
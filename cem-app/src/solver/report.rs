@@ -0,0 +1,377 @@
+//! HTML reports of a solver run.
+//!
+//! A report is written next to the exported results (see
+//! [`crate::solver::export`]) and puts the plots, pattern cuts, the image of
+//! the viewport (which shows the scene and the observers' field images) and
+//! the run's metadata on one page. There is no PDF writer, but the styles are
+//! made for printing, so a browser can print the report to PDF.
+//!
+//! The page is filled into a template. Instead of one of the built-in
+//! [`ReportLayout`]s, a custom HTML file can be used, in which these
+//! placeholders are replaced:
+//!
+//! - `{{title}}`: the report's title
+//! - `{{date}}`: when the report was written
+//! - `{{metadata}}`: a table with the run's metadata
+//! - `{{figures}}`: the figures, each a `<figure>` with a caption
+//! - `{{style}}`: the styles of the built-in layouts
+
+use std::{
+    fmt::Write,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use cem_probe::{
+    PropertiesUi,
+    TrackChanges,
+    label_and_value,
+    label_and_value_with_config,
+};
+use cem_util::egui::FilePickerConfig;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    Error,
+    solver::{
+        export::{
+            VIEWPORT_IMAGE_FILE_NAME,
+            escape_xml,
+            file_stem,
+            run_metadata,
+        },
+        probe::ProbeOutputs,
+        runner::SolverState,
+    },
+};
+
+/// File name of the report in the export directory.
+pub const REPORT_FILE_NAME: &str = "report.html";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReportOptions {
+    pub title: String,
+
+    pub layout: ReportLayout,
+
+    /// HTML file that is used instead of the layout's template.
+    pub template: Option<PathBuf>,
+
+    /// Plots of line, point and far-field probes
+    pub plots: bool,
+
+    /// Cuts through the patterns of NF2FF boxes
+    pub pattern_cuts: bool,
+
+    /// Image of the viewport, with the scene and the observers
+    pub viewport: bool,
+
+    pub metadata: bool,
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        Self {
+            title: "Solver Run".to_owned(),
+            layout: ReportLayout::default(),
+            template: None,
+            plots: true,
+            pattern_cuts: true,
+            viewport: true,
+            metadata: true,
+        }
+    }
+}
+
+impl PropertiesUi for ReportOptions {
+    type Config = ();
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
+        let _ = config;
+        let mut changes = TrackChanges::default();
+
+        let response = egui::Frame::new()
+            .show(ui, |ui| {
+                label_and_value(ui, "Title", &mut changes, &mut self.title);
+
+                ui.horizontal(|ui| {
+                    ui.label("Layout");
+                    ui.add_enabled_ui(self.template.is_none(), |ui| {
+                        egui::ComboBox::from_id_salt("layout")
+                            .selected_text(self.layout.label())
+                            .show_ui(ui, |ui| {
+                                for layout in ReportLayout::ALL {
+                                    changes.track(ui.selectable_value(
+                                        &mut self.layout,
+                                        layout,
+                                        layout.label(),
+                                    ));
+                                }
+                            });
+                    });
+                });
+                label_and_value_with_config(
+                    ui,
+                    "Template",
+                    &mut changes,
+                    &mut self.template,
+                    &FilePickerConfig::Open,
+                )
+                .on_hover_text(
+                    "HTML file with {{title}}, {{date}}, {{metadata}}, {{figures}} and {{style}} \
+                     placeholders, used instead of the layout",
+                );
+
+                label_and_value(ui, "Plots", &mut changes, &mut self.plots);
+                label_and_value(ui, "Pattern cuts", &mut changes, &mut self.pattern_cuts);
+                label_and_value(ui, "Viewport", &mut changes, &mut self.viewport);
+                label_and_value(ui, "Metadata", &mut changes, &mut self.metadata);
+            })
+            .response;
+
+        changes.propagated(response)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportLayout {
+    /// One figure below the other, and one figure per page when printed.
+    #[default]
+    Article,
+
+    /// Figures side by side in two columns.
+    Grid,
+}
+
+impl ReportLayout {
+    pub const ALL: [Self; 2] = [Self::Article, Self::Grid];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Article => "Article",
+            Self::Grid => "Grid",
+        }
+    }
+
+    fn template(&self) -> &'static str {
+        match self {
+            Self::Article => ARTICLE_TEMPLATE,
+            Self::Grid => GRID_TEMPLATE,
+        }
+    }
+}
+
+/// Writes the report for files that were exported into `directory`.
+///
+/// Figures are only included if their file is in `files`. The viewport image
+/// is taken after the export, so it's always included if enabled.
+pub(crate) fn write_report(
+    directory: &Path,
+    state: &SolverState,
+    probe_outputs: &ProbeOutputs,
+    files: &[PathBuf],
+    options: &ReportOptions,
+) -> Result<PathBuf, Error> {
+    let template = match &options.template {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => options.layout.template().to_owned(),
+    };
+
+    let mut figures = vec![];
+    if options.viewport {
+        figures.push(Figure {
+            file_name: VIEWPORT_IMAGE_FILE_NAME.to_owned(),
+            caption: "Scene and field images".to_owned(),
+        });
+    }
+    let mut push_svg = |kind: &str, index: usize, label: &str, caption: String| {
+        let file_name = format!("{}.svg", file_stem(kind, index, label));
+        if files.contains(&directory.join(&file_name)) {
+            figures.push(Figure { file_name, caption });
+        }
+    };
+    if options.plots {
+        for (index, (label, _)) in probe_outputs.line_probes.iter().enumerate() {
+            push_svg("line_probe", index, label, label.clone());
+        }
+        for (index, (label, _)) in probe_outputs.point_probes.iter().enumerate() {
+            push_svg("point_probe", index, label, label.clone());
+        }
+        for (index, (label, _)) in probe_outputs.far_field_probes.iter().enumerate() {
+            push_svg("far_field_probe", index, label, label.clone());
+        }
+    }
+    if options.pattern_cuts {
+        for (index, (label, _)) in probe_outputs.nf2ff_boxes.iter().enumerate() {
+            push_svg(
+                "nf2ff_box",
+                index,
+                label,
+                format!("{label}: E- and H-plane cuts"),
+            );
+        }
+    }
+
+    let metadata = if options.metadata {
+        run_metadata(state, probe_outputs)
+    }
+    else {
+        vec![]
+    };
+
+    let html = fill_template(
+        &template,
+        &options.title,
+        &chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        &metadata,
+        &figures,
+    )?;
+
+    let path = directory.join(REPORT_FILE_NAME);
+    std::fs::write(&path, html)?;
+
+    Ok(path)
+}
+
+#[derive(Clone, Debug)]
+struct Figure {
+    /// Relative to the report
+    file_name: String,
+    caption: String,
+}
+
+fn fill_template(
+    template: &str,
+    title: &str,
+    date: &str,
+    metadata: &[(&str, String)],
+    figures: &[Figure],
+) -> Result<String, Error> {
+    let mut metadata_html = String::new();
+    if !metadata.is_empty() {
+        writeln!(metadata_html, r#"<table class="metadata">"#)?;
+        for (label, value) in metadata {
+            writeln!(
+                metadata_html,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                escape_xml(label),
+                escape_xml(value)
+            )?;
+        }
+        writeln!(metadata_html, "</table>")?;
+    }
+
+    let mut figures_html = String::new();
+    for figure in figures {
+        writeln!(
+            figures_html,
+            r#"<figure><img src="{}" alt="{caption}"><figcaption>{caption}</figcaption></figure>"#,
+            escape_xml(&figure.file_name),
+            caption = escape_xml(&figure.caption)
+        )?;
+    }
+
+    Ok(template
+        .replace("{{style}}", STYLE)
+        .replace("{{title}}", &escape_xml(title))
+        .replace("{{date}}", &escape_xml(date))
+        .replace("{{metadata}}", &metadata_html)
+        .replace("{{figures}}", &figures_html))
+}
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; color: #222222; max-width: 60em; margin: 2em auto; padding: 0 1em; }
+header .date { color: #666666; }
+table.metadata { border-collapse: collapse; margin-bottom: 2em; }
+table.metadata th, table.metadata td { text-align: left; padding: 0.2em 1.5em 0.2em 0; border-bottom: 1px solid #dddddd; }
+figure { margin: 0 0 2em 0; break-inside: avoid; }
+figure img { max-width: 100%; }
+figcaption { color: #555555; font-size: 0.9em; }
+main.grid { display: grid; grid-template-columns: 1fr 1fr; gap: 1em; }
+@media print {
+    body { max-width: none; margin: 0; }
+    main.article figure { break-after: page; }
+}
+"#;
+
+const ARTICLE_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{{title}}</title>
+<style>{{style}}</style>
+</head>
+<body>
+<header>
+<h1>{{title}}</h1>
+<p class="date">{{date}}</p>
+</header>
+{{metadata}}
+<main class="article">
+{{figures}}
+</main>
+</body>
+</html>
+"#;
+
+const GRID_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{{title}}</title>
+<style>{{style}}</style>
+</head>
+<body>
+<header>
+<h1>{{title}}</h1>
+<p class="date">{{date}}</p>
+</header>
+{{metadata}}
+<main class="grid">
+{{figures}}
+</main>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::report::{
+        Figure,
+        fill_template,
+    };
+
+    #[test]
+    fn fills_placeholders_and_escapes() {
+        let html = fill_template(
+            "<h1>{{title}}</h1>{{metadata}}{{figures}}",
+            "Dipole <test>",
+            "2025-01-01 00:00:00",
+            &[("Ticks", "100".to_owned())],
+            &[Figure {
+                file_name: "point_probe_00_Feed.svg".to_owned(),
+                caption: "Feed & Load".to_owned(),
+            }],
+        )
+        .unwrap();
+
+        assert!(html.starts_with("<h1>Dipole &lt;test&gt;</h1>"), "{html}");
+        assert!(
+            html.contains("<tr><th>Ticks</th><td>100</td></tr>"),
+            "{html}"
+        );
+        assert!(html.contains(r#"src="point_probe_00_Feed.svg""#), "{html}");
+        assert!(
+            html.contains("<figcaption>Feed &amp; Load</figcaption>"),
+            "{html}"
+        );
+        assert!(!html.contains("{{"), "{html}");
+    }
+}
@@ -0,0 +1,317 @@
+//! Periodic logging of long solver runs.
+//!
+//! Every [`RunLogConfig::interval`] of wall time a row with the field energy,
+//! the latest value of every point probe and the throughput is appended to
+//! `metrics.csv` in the run's log directory, and every observer saves its next
+//! image there as `snapshot_<entry>_<observer>_<label>.png`. Rows are flushed
+//! right away, so unattended runs leave a trail even if the app crashes.
+//!
+//! The logs of all runs are kept in [`AppFiles::run_logs_dir`], one directory
+//! per run.
+//!
+//! [`AppFiles::run_logs_dir`]: crate::files::AppFiles::run_logs_dir
+
+use std::{
+    fs::File,
+    io::{
+        BufWriter,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::{
+        Arc,
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use cem_solver::{
+    Field,
+    FieldComponent,
+    FieldView,
+    Time,
+};
+use nalgebra::Point3;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    Error,
+    solver::{
+        export::safe_file_name,
+        probe::{
+            PointProbeOutput,
+            ProbeOutputs,
+        },
+    },
+};
+
+/// Interval used when the run log is enabled in the UI.
+pub const DEFAULT_LOG_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// File name of the metrics in a run's log directory.
+pub const METRICS_FILE_NAME: &str = "metrics.csv";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunLogConfig {
+    /// Wall time between two log entries. `None` disables the run log.
+    pub interval: Option<Duration>,
+}
+
+/// Writes the log of a running solver.
+///
+/// This lives on the solver thread. The observers save their snapshots on
+/// their own thread, see [`ObserverRunLog`].
+#[derive(Debug)]
+pub struct RunLog {
+    directory: PathBuf,
+    interval: Duration,
+    metrics: BufWriter<File>,
+    point_probes: Vec<PointProbeOutput>,
+    snapshots: RunLogSnapshots,
+    num_entries: usize,
+
+    /// Wall time and tick of the last entry, or of the start of the run.
+    last_entry: (Instant, usize),
+}
+
+impl RunLog {
+    /// Creates the log directory and writes the header of the metrics.
+    pub fn create(
+        directory: PathBuf,
+        interval: Duration,
+        probe_outputs: &ProbeOutputs,
+    ) -> Result<Self, Error> {
+        std::fs::create_dir_all(&directory)?;
+
+        let mut metrics = BufWriter::new(File::create(directory.join(METRICS_FILE_NAME))?);
+        write!(
+            metrics,
+            "entry,wall_time,tick,time,field_energy,steps_per_second"
+        )?;
+        for (label, _) in &probe_outputs.point_probes {
+            for axis in ["x", "y", "z"] {
+                write!(metrics, ",{}", csv_field(&format!("{label} {axis}")))?;
+            }
+        }
+        writeln!(metrics)?;
+        metrics.flush()?;
+
+        tracing::info!(directory = %directory.display(), ?interval, "logging run");
+
+        Ok(Self {
+            snapshots: RunLogSnapshots {
+                directory: Arc::new(directory.clone()),
+                requested: Default::default(),
+            },
+            directory,
+            interval,
+            metrics,
+            point_probes: probe_outputs
+                .point_probes
+                .iter()
+                .map(|(_, output)| output.clone())
+                .collect(),
+            num_entries: 0,
+            last_entry: (Instant::now(), 0),
+        })
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Handle for the observers to save their snapshots with.
+    pub fn snapshots(&self) -> RunLogSnapshots {
+        self.snapshots.clone()
+    }
+
+    /// Writes an entry if the interval passed since the last one.
+    pub fn log<I>(&mut self, instance: &I, state: &I::State) -> Result<(), Error>
+    where
+        I: Field<Point3<usize>>,
+        I::State: Time,
+    {
+        let (last_time, last_tick) = self.last_entry;
+        let elapsed = last_time.elapsed();
+        if elapsed < self.interval {
+            return Ok(());
+        }
+
+        let tick = state.tick();
+        let entry = self.num_entries;
+        self.num_entries += 1;
+        self.last_entry = (Instant::now(), tick);
+
+        let steps_per_second = tick.saturating_sub(last_tick) as f64 / elapsed.as_secs_f64();
+
+        write!(
+            self.metrics,
+            "{entry},{},{tick},{},{},{steps_per_second}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            state.time(),
+            field_energy(instance, state),
+        )?;
+        for output in &self.point_probes {
+            let value = output
+                .latest()
+                .map_or_else(Default::default, |sample| sample.value);
+            write!(self.metrics, ",{},{},{}", value.x, value.y, value.z)?;
+        }
+        writeln!(self.metrics)?;
+        self.metrics.flush()?;
+
+        self.snapshots.requested.store(entry + 1, Ordering::Relaxed);
+
+        Ok(())
+    }
+}
+
+/// Sum of `(|E|² + |H|²) / 2` over the lattice.
+///
+/// This isn't weighted with the materials or the cell volume, but it's enough
+/// to tell if the fields are decaying, steady or blowing up.
+pub fn field_energy<I>(instance: &I, state: &I::State) -> f64
+where
+    I: Field<Point3<usize>>,
+{
+    let energy = [FieldComponent::E, FieldComponent::H]
+        .into_iter()
+        .map(|component| {
+            instance
+                .field(state, .., component)
+                .iter()
+                .map(|(_point, value)| value.norm_squared())
+                .sum::<f64>()
+        })
+        .sum::<f64>();
+    0.5 * energy
+}
+
+/// Tells the observers which log entry they should save a snapshot for.
+///
+/// This is cheap to clone.
+#[derive(Clone, Debug)]
+pub struct RunLogSnapshots {
+    directory: Arc<PathBuf>,
+
+    /// Entry number plus one of the latest entry. 0 if no entry was written
+    /// yet.
+    requested: Arc<AtomicUsize>,
+}
+
+impl RunLogSnapshots {
+    /// Observers are numbered in the order they're created.
+    pub fn for_observer(&self, observer: usize, label: impl Into<String>) -> ObserverRunLog {
+        ObserverRunLog {
+            snapshots: self.clone(),
+            observer,
+            label: label.into(),
+            saved: 0,
+        }
+    }
+}
+
+/// Saves an observer's images into the run log.
+#[derive(Clone, Debug)]
+pub struct ObserverRunLog {
+    snapshots: RunLogSnapshots,
+    observer: usize,
+    label: String,
+
+    /// Like [`RunLogSnapshots::requested`], for the latest snapshot that was
+    /// saved.
+    saved: usize,
+}
+
+impl ObserverRunLog {
+    /// Saves `image` if an entry was written since the last snapshot.
+    ///
+    /// Errors are only logged, so a full disk doesn't stop the observer.
+    pub fn save_if_requested(&mut self, image: &image::RgbaImage) {
+        let requested = self.snapshots.requested.load(Ordering::Relaxed);
+        if requested == self.saved {
+            return;
+        }
+        self.saved = requested;
+
+        let path = self.snapshots.directory.join(format!(
+            "snapshot_{:04}_{:02}_{}.png",
+            requested - 1,
+            self.observer,
+            safe_file_name(&self.label)
+        ));
+        if let Err(error) = image.save(&path) {
+            tracing::warn!(path = %path.display(), %error, "can't save run log snapshot");
+        }
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    }
+    else {
+        text.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cem_solver::{
+        FieldComponent,
+        FieldMut,
+        fdtd::{
+            cpu::FdtdCpuBackend,
+            test_util::{
+                vacuum_config,
+                vacuum_instance,
+            },
+        },
+    };
+    use nalgebra::{
+        Point3,
+        Vector3,
+    };
+
+    use crate::solver::run_log::{
+        csv_field,
+        field_energy,
+    };
+
+    #[test]
+    fn field_energy_sums_squared_fields() {
+        let config = vacuum_config(Vector3::repeat(4.0));
+        let (instance, mut state) = vacuum_instance(FdtdCpuBackend::single_threaded(), &config);
+
+        assert_eq!(field_energy(&instance, &state), 0.0);
+
+        let point = Point3::new(1, 2, 3);
+        for (_point, value) in instance.field_mut(&mut state, point..=point, FieldComponent::E) {
+            value.x = 3.0;
+        }
+        for (_point, value) in instance.field_mut(&mut state, point..=point, FieldComponent::H) {
+            value.z = 4.0;
+        }
+
+        assert_eq!(field_energy(&instance, &state), 12.5);
+    }
+
+    #[test]
+    fn csv_fields_are_quoted() {
+        assert_eq!(csv_field("Feed x"), "Feed x");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -1,5 +1,22 @@
 use std::{
-    sync::Arc,
+    cmp::Ordering,
+    collections::{
+        BTreeSet,
+        HashMap,
+    },
+    ops::ControlFlow,
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::{
+        Arc,
+        atomic::{
+            self,
+            AtomicBool,
+        },
+        mpsc,
+    },
     thread::JoinHandle,
     time::{
         Duration,
@@ -12,10 +29,8 @@ use bevy_ecs::{
     system::{
         Commands,
         In,
-        InMut,
         InRef,
         Query,
-        SystemParam,
     },
     world::World,
 };
@@ -26,27 +41,36 @@ use cem_render::{
 };
 use cem_scene::{
     Scene,
+    material_groups::MaterialGroups,
     spatial::{
         Collider,
-        queries::{
-            IntersectAabb,
-            PointQuery,
+        queries::PointQuery,
+        traits::{
+            ComputeAabb,
+            PointQuery as _,
+            RayCast,
         },
     },
-    transform::GlobalTransform,
+    stable_id::NameOrStableId,
+    transform::{
+        GlobalTransform,
+        propagate_transforms,
+    },
 };
 use cem_solver::{
+    CopyState,
     DomainDescription,
+    Field,
     SolverBackend,
     SolverInstance,
     Time,
     UpdatePass,
     UpdatePassForcing,
     fdtd::{
-        self,
         FdtdSolverConfig,
         Resolution,
         cpu::FdtdCpuBackend,
+        mesh::GradedMeshConfig,
         pml::{
             GradedPml,
             PmlCoefficients,
@@ -55,6 +79,7 @@ use cem_solver::{
     },
     material::{
         Material,
+        MaterialPriority,
         PhysicalConstants,
     },
     project::{
@@ -63,8 +88,25 @@ use cem_solver::{
         ProjectionParameters,
         ProjectionPass,
         ProjectionPassAdd,
+        SetProjectionTransform,
+    },
+    registry::{
+        CustomSolverBackend,
+        CustomSolverContext,
+        CustomSolverInstance,
+        SolverConfigCustom,
+        SolverRegistry,
+    },
+    source::{
+        Source,
+        SourceValues,
+        WithAmplitudes,
+        feed::Feed,
+    },
+    step::{
+        CancellationToken,
+        SliceEnd,
     },
-    source::Source,
 };
 use cem_util::{
     egui::{
@@ -94,13 +136,40 @@ use parry3d::{
 
 use crate::{
     Error,
-    app::CreateAppContext,
+    app::{
+        CreateAppContext,
+        WgpuContext,
+    },
+    color_scheme,
     error::{
         ErrorHandler,
         UiErrorSink,
     },
+    jobs::{
+        Cancelled,
+        JobContext,
+        JobHandle,
+        Jobs,
+    },
+    recorder::Recorder,
     solver::{
+        array::Excitation,
+        benchmark::{
+            ActiveBackend,
+            BackendChoice,
+            BenchmarkDatabase,
+            BenchmarkSample,
+            Candidate,
+            FdtdBackendKind,
+            MIN_MEASURED_TIME,
+            choose_backend,
+        },
+        clock::{
+            SimulationClock,
+            WAIT_INTERVAL,
+        },
         config::{
+            MeshGrading,
             Parallelization,
             SolverConfig,
             SolverConfigCommon,
@@ -108,14 +177,60 @@ use crate::{
             SolverConfigSpecifics,
             StopCondition,
         },
+        events::{
+            EventAction,
+            EventSchedule,
+            fields_path,
+            save_fields,
+        },
+        export::safe_file_name,
+        ground_plane::GroundPlanes,
+        hashes::{
+            CurrentHashes,
+            RunHashes,
+        },
+        history::ObserverHistory,
+        library::LibraryRun,
+        motion::apply_motions,
         observer::{
             Observer,
+            ObserverSlices,
+            ObserverStream,
             TextureSenderTarget,
+            field_color_map_code,
+        },
+        parameters::{
+            MaterialDependencies,
+            ParameterValues,
+            SceneParameters,
+        },
+        port::Port,
+        probe::{
+            ProbeOutputs,
+            Probes,
+        },
+        refinement::graded_mesh_for_scene,
+        run_log::{
+            RunLog,
+            RunLogSnapshots,
+        },
+        stream::StreamPublisher,
+        units::SceneUnits,
+        watchdog::{
+            LikelyCause,
+            Watchdog,
         },
     },
     util::spawn_thread,
 };
 
+/// How long the solver steps before it checks whether it was paused, and
+/// reports its progress.
+///
+/// Stopping the solver cancels the slice right away, see
+/// [`CancellationToken`].
+const STEP_TIME_SLICE: Duration = Duration::from_millis(20);
+
 #[derive(Debug)]
 pub struct SolverRunner {
     fdtd_wgpu: FdtdWgpuBackend,
@@ -125,55 +240,215 @@ pub struct SolverRunner {
     repaint_trigger: RepaintTrigger,
     error_sink: UiErrorSink,
 
+    /// Runs the jobs that set up FDTD solvers.
+    jobs: Jobs,
+
+    /// Observers of solvers started from now on also send their images here.
+    stream: Option<StreamPublisher>,
+
+    /// Backends for [`SolverConfigSpecifics::Custom`] configs.
+    registry: SolverRegistry,
+
+    /// Records stopping the solver, see [`crate::recorder`].
+    recorder: Recorder,
+
+    /// Throughput of past runs, for choosing a backend automatically.
+    benchmarks: BenchmarkDatabase,
+
+    /// Library that solvers started from now on are saved to, see
+    /// [`crate::solver::library`].
+    library_directory: Option<PathBuf>,
+
+    /// Solvers with a run log write it into a subdirectory of this, see
+    /// [`crate::solver::run_log`].
+    run_logs_directory: Option<PathBuf>,
+
     active_solver: Option<Solver>,
+
+    /// Job setting up the solver of the active run, see [`Self::update`].
+    pending_solver: Option<PendingSolver>,
+
+    /// The FDTD backend the active solver runs on.
+    active_backend: Option<ActiveBackend>,
+
+    /// Where the active solver is saved to when it finishes.
+    library_run: Option<LibraryRun>,
+
+    /// Where the active solver writes its run log.
+    active_run_log: Option<PathBuf>,
+
+    /// Label of the active solver's config, and what it simulates.
+    active_run_hashes: Option<(String, RunHashes)>,
+
+    /// To tell if the active solver's results are stale.
+    current_hashes: CurrentHashes,
 }
 
 impl SolverRunner {
-    pub fn from_app_context(context: &CreateAppContext) -> Self {
+    pub fn new(wgpu_context: &WgpuContext, egui_context: &egui::Context) -> Self {
         Self {
             fdtd_wgpu: FdtdWgpuBackend::new(
-                context.wgpu_context.device.clone(),
-                context.wgpu_context.queue.clone(),
-                context.wgpu_context.staging_pool.clone(),
+                wgpu_context.device.clone(),
+                wgpu_context.queue.clone(),
+                wgpu_context.staging_pool.clone(),
             ),
-            repaint_trigger: context.egui_context.repaint_trigger(),
-            error_sink: UiErrorSink::from(&context.egui_context),
+            repaint_trigger: egui_context.repaint_trigger(),
+            error_sink: UiErrorSink::from(egui_context),
+            jobs: Jobs::from_ctx(egui_context),
+            stream: None,
+            registry: Default::default(),
+            recorder: Default::default(),
+            benchmarks: Default::default(),
+            library_directory: None,
+            run_logs_directory: None,
             active_solver: None,
+            pending_solver: None,
+            active_backend: None,
+            library_run: None,
+            active_run_log: None,
+            active_run_hashes: None,
+            current_hashes: Default::default(),
+        }
+    }
+
+    pub fn from_app_context(context: &CreateAppContext) -> Self {
+        Self::new(&context.wgpu_context, &context.egui_context)
+            .with_benchmarks(BenchmarkDatabase::open(context.app_files.benchmarks_path()))
+            .with_run_logs_directory(context.app_files.run_logs_dir())
+    }
+
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = recorder;
+        self
+    }
+
+    pub fn with_benchmarks(mut self, benchmarks: BenchmarkDatabase) -> Self {
+        self.benchmarks = benchmarks;
+        self
+    }
+
+    pub fn with_run_logs_directory(mut self, directory: PathBuf) -> Self {
+        self.run_logs_directory = Some(directory);
+        self
+    }
+
+    pub fn set_stream(&mut self, stream: Option<StreamPublisher>) {
+        self.stream = stream;
+    }
+
+    pub fn set_library_directory(&mut self, directory: Option<PathBuf>) {
+        self.library_directory = directory;
+    }
+
+    /// Registers a solver backend that can then be selected by name in
+    /// [`SolverConfigSpecifics::Custom`] configs.
+    pub fn register_backend(&mut self, backend: impl CustomSolverBackend) {
+        if let Some(replaced) = self.registry.register(backend) {
+            tracing::warn!(name = replaced.name(), "replaced solver backend");
         }
     }
 
+    pub fn registry(&self) -> &SolverRegistry {
+        &self.registry
+    }
+
+    /// Starts a run.
+    ///
+    /// FDTD solvers are set up by a job, so they only become the
+    /// [active solver](Self::active_solver) in a later [`Self::update`].
+    ///
     /// TODO: We probably just want one parameter that impls some trait. That
     /// trait defines how a solver_config and scene is turned into the problem
     /// description for the runner (e.g. a `fdtd::Simulation`).
     pub fn run(&mut self, solver_config: &SolverConfig, scene: &mut Scene) -> Result<(), Error> {
-        if self.active_solver.is_some() {
+        if self.active_solver.is_some() || self.pending_solver.is_some() {
             bail!("Can't run more than one solver at once.");
         }
 
-        match &solver_config.specifics {
+        // the hashes are of the scene as it's shown, before moving geometry for this
+        // run
+        let hashes = RunHashes::new(solver_config, &mut scene.world)?;
+
+        // the solver is set up with moving geometry at the position for this run's
+        // parameters. it's moved back afterwards, so the scene isn't modified.
+        let reference_positions = apply_motions(scene, &solver_config.common.parameter_overrides);
+
+        // probes, observers, etc. might have been moved with the objects they're
+        // attached to since the last update.
+        propagate_transforms(&mut scene.world);
+
+        let result = match &solver_config.specifics {
             SolverConfigSpecifics::Fdtd(fdtd_config) => {
-                self.run_fdtd(scene, &solver_config.common, fdtd_config)?;
+                self.run_fdtd(
+                    scene,
+                    &solver_config.label,
+                    &solver_config.common,
+                    fdtd_config,
+                )
             }
-            SolverConfigSpecifics::Feec(_feec_config) => tracing::debug!("todo: feec solver"),
+            SolverConfigSpecifics::Feec(_feec_config) => {
+                tracing::debug!("todo: feec solver");
+                Ok(())
+            }
+            SolverConfigSpecifics::Custom(custom_config) => {
+                self.run_custom(scene, &solver_config.common, custom_config)
+            }
+        };
+
+        reference_positions.restore(scene);
+
+        if result.is_ok() && (self.active_solver.is_some() || self.pending_solver.is_some()) {
+            self.library_run = self.library_directory.clone().map(|directory| {
+                LibraryRun {
+                    directory,
+                    label: solver_config.label.clone(),
+                    hashes: hashes.clone(),
+                    saved: false,
+                }
+            });
+            self.active_run_hashes = Some((solver_config.label.clone(), hashes));
+            self.current_hashes = Default::default();
         }
 
-        Ok(())
+        result
     }
 
     pub fn stop(&mut self) {
+        self.library_run = None;
+        self.active_run_log = None;
+        self.active_run_hashes = None;
+
+        if let Some(pending_solver) = self.pending_solver.take() {
+            tracing::debug!("Cancelling setup of solver");
+            pending_solver.job.cancel();
+            self.active_backend = None;
+        }
+
         if let Some(solver) = self.active_solver.take() {
             tracing::debug!("Requested closing of solver");
 
             let mut state = solver.shared.state.lock();
             state.finished = true;
-            if state.paused {
-                solver.shared.condition.notify_all();
-            }
+            solver.shared.closed.store(true, atomic::Ordering::Relaxed);
+            solver.shared.cancellation.cancel();
+            solver.shared.condition.notify_all();
             drop(state);
 
             if let Err(panic) = solver.join_handle.join() {
                 tracing::error!(?panic, "Solver thread panicked");
             }
+
+            if let Some(active_backend) = self.active_backend.take() {
+                let state = *solver.shared.state.lock();
+                if state.total_running_time >= MIN_MEASURED_TIME && state.sim_tick > 0 {
+                    self.benchmarks.record(BenchmarkSample {
+                        backend: active_backend.backend,
+                        num_cells: active_backend.num_cells,
+                        cells_per_second: (state.sim_tick * active_backend.num_cells) as f64
+                            / state.total_running_time.as_secs_f64(),
+                    });
+                }
+            }
         }
     }
 
@@ -181,29 +456,204 @@ impl SolverRunner {
         self.active_solver.as_ref()
     }
 
+    /// Whether the solver of the active run is still being set up.
+    pub fn is_preparing(&self) -> bool {
+        self.pending_solver.is_some()
+    }
+
+    /// Progress of the job setting up the solver, if it reports any.
+    pub fn preparation_progress(&self) -> Option<f32> {
+        self.pending_solver
+            .as_ref()
+            .and_then(|pending_solver| pending_solver.job.progress())
+    }
+
+    /// Starts the solver of the active run, once the job setting it up is
+    /// done.
+    ///
+    /// This is called every frame.
+    pub fn update(&mut self) {
+        let Some(result) = self
+            .pending_solver
+            .as_ref()
+            .and_then(|pending_solver| pending_solver.job.try_take_result())
+        else {
+            return;
+        };
+        let pending_solver = self.pending_solver.take().unwrap();
+
+        if let Err(error) = self.start_pending_solver(pending_solver, result)
+            && !error.is::<Cancelled>()
+        {
+            self.error_sink.handle_error(error);
+        }
+    }
+
+    /// Blocks until the solver of the active run is started, e.g. for headless
+    /// runs that have no frames to [`update`](Self::update) in.
+    pub fn wait_until_started(&mut self) -> Result<(), Error> {
+        let Some(pending_solver) = self.pending_solver.take()
+        else {
+            return Ok(());
+        };
+
+        while !pending_solver.job.is_done() {
+            std::thread::sleep(WAIT_INTERVAL);
+        }
+        let result = pending_solver.job.try_take_result().expect("job is done");
+
+        self.start_pending_solver(pending_solver, result)
+    }
+
+    fn start_pending_solver(
+        &mut self,
+        pending_solver: PendingSolver,
+        result: Result<StartSolver, Error>,
+    ) -> Result<(), Error> {
+        match result {
+            Ok(start_solver) => {
+                let solver = start_solver();
+                if pending_solver.full_speed {
+                    solver.run_at_full_speed();
+                }
+                self.active_solver = Some(solver);
+                Ok(())
+            }
+            Err(error) => {
+                // the run never started, so there's no benchmark to record
+                self.active_backend = None;
+                self.stop();
+                Err(error)
+            }
+        }
+    }
+
+    /// Runs the active solver at full speed, as soon as it's started.
+    pub fn run_at_full_speed(&mut self) {
+        if let Some(pending_solver) = &mut self.pending_solver {
+            pending_solver.full_speed = true;
+        }
+        else if let Some(solver) = &self.active_solver {
+            solver.run_at_full_speed();
+        }
+    }
+
+    /// The FDTD backend the active solver runs on, and why it was chosen.
+    pub fn active_backend(&self) -> Option<&ActiveBackend> {
+        self.active_backend.as_ref()
+    }
+
+    /// Where the active solver is saved to, if its project has a library.
+    pub fn library_run(&self) -> Option<&LibraryRun> {
+        self.library_run.as_ref()
+    }
+
+    /// Compares what the active solver simulates with the current scene.
+    pub fn check_stale_results(&mut self, world: &mut World, solver_configs: &[SolverConfig]) {
+        if self.active_run_hashes.is_some() {
+            self.current_hashes.update(world, solver_configs);
+        }
+    }
+
+    /// Why the active solver's results are stale, if they are. See
+    /// [`Self::check_stale_results`].
+    pub fn stale_results(&self) -> Option<&'static str> {
+        let (label, hashes) = self.active_run_hashes.as_ref()?;
+        self.current_hashes.stale_reason(label, hashes)
+    }
+
+    /// Directory the active solver writes its run log to, if it has one.
+    pub fn active_run_log(&self) -> Option<&Path> {
+        self.active_run_log.as_deref()
+    }
+
+    /// Marks the active solver as saved to the library.
+    pub fn set_saved_to_library(&mut self) {
+        if let Some(library_run) = &mut self.library_run {
+            library_run.saved = true;
+        }
+    }
+
     fn run_fdtd(
         &mut self,
         scene: &mut Scene,
+        label: &str,
         common_config: &SolverConfigCommon,
         fdtd_config: &SolverConfigFdtd,
     ) -> Result<(), Error> {
+        let (config, aabb) = fdtd_solver_config(scene, common_config, fdtd_config)?;
+        let num_cells = config.num_cells();
+
+        let (parallelization, choice_reason) = match &common_config.parallelization {
+            Some(parallelization) => (*parallelization, None),
+            None => {
+                let choice = self.choose_fdtd_backend(&config, common_config.memory_limit)?;
+                tracing::info!(
+                    backend = choice.backend.label(),
+                    reason = choice.reason,
+                    "chose backend automatically"
+                );
+                (choice.backend.parallelization(), Some(choice.reason))
+            }
+        };
+
+        // every run gets its own log directory
+        let run_log = match (fdtd_config.run_log.interval, &self.run_logs_directory) {
+            (Some(interval), Some(run_logs_directory)) => {
+                Some((
+                    run_logs_directory.join(format!(
+                        "{}_{}",
+                        chrono::Local::now().format("%Y-%m-%d_%H-%M-%S"),
+                        safe_file_name(label)
+                    )),
+                    interval,
+                ))
+            }
+            (Some(_), None) => {
+                tracing::warn!("no directory for run logs");
+                None
+            }
+            (None, _) => None,
+        };
+        let run_log_directory = run_log.as_ref().map(|(directory, _)| directory.clone());
+
+        let sparse = fdtd_config.sparse;
         let run_fdtd = RunFdtd {
             scene,
             common_config,
             fdtd_config,
+            config,
+            aabb,
+            jobs: self.jobs.clone(),
             repaint_trigger: self.repaint_trigger.clone(),
             error_sink: self.error_sink.clone(),
+            stream: self.stream.clone(),
+            run_log,
         };
 
-        let solver = match &common_config.parallelization {
-            None => run_fdtd.run_fdtd_with_backend(&FdtdCpuBackend::single_threaded())?,
-            Some(Parallelization::MultiThreaded { num_threads }) => {
+        let (job, backend) = match parallelization {
+            Parallelization::SingleThreaded => {
+                (
+                    run_fdtd.run_fdtd_with_backend(FdtdCpuBackend {
+                        sparse,
+                        ..FdtdCpuBackend::single_threaded()
+                    })?,
+                    FdtdBackendKind::SingleThreaded,
+                )
+            }
+            Parallelization::MultiThreaded { num_threads } => {
                 if num_threads.is_some_and(|num_threads| num_threads <= 1) {
                     tracing::debug!(
                         ?num_threads,
                         "switching to single-threaded backend, because num_threads <= 1"
                     );
-                    run_fdtd.run_fdtd_with_backend(&FdtdCpuBackend::single_threaded())?
+                    (
+                        run_fdtd.run_fdtd_with_backend(FdtdCpuBackend {
+                            sparse,
+                            ..FdtdCpuBackend::single_threaded()
+                        })?,
+                        FdtdBackendKind::SingleThreaded,
+                    )
                 }
                 else {
                     #[cfg(not(feature = "multi-threading"))]
@@ -212,42 +662,209 @@ impl SolverRunner {
                         tracing::warn!(
                             "Compiled without rayon feature. Falling back to single-threaded"
                         );
-                        run_fdtd.run_fdtd_with_backend(&FdtdCpuBackend::single_threaded())?
+                        (
+                            run_fdtd.run_fdtd_with_backend(FdtdCpuBackend {
+                                sparse,
+                                ..FdtdCpuBackend::single_threaded()
+                            })?,
+                            FdtdBackendKind::SingleThreaded,
+                        )
                     }
 
                     #[cfg(feature = "multi-threading")]
                     {
                         tracing::debug!(?num_threads, "using multi-threaded cpu backend");
-                        run_fdtd
-                            .run_fdtd_with_backend(&FdtdCpuBackend::multi_threaded(*num_threads)?)?
+                        (
+                            run_fdtd.run_fdtd_with_backend(FdtdCpuBackend {
+                                sparse,
+                                ..FdtdCpuBackend::multi_threaded(num_threads)?
+                            })?,
+                            FdtdBackendKind::MultiThreaded,
+                        )
                     }
                 }
             }
-            Some(Parallelization::Wgpu) => {
+            Parallelization::Wgpu => {
                 tracing::debug!("using wgpu backend");
-                run_fdtd.run_fdtd_with_backend(&self.fdtd_wgpu)?
+                (
+                    run_fdtd.run_fdtd_with_backend(self.fdtd_wgpu.clone())?,
+                    FdtdBackendKind::Wgpu,
+                )
             }
         };
 
-        self.active_solver = Some(solver);
+        self.active_backend = Some(ActiveBackend {
+            backend,
+            num_cells,
+            choice_reason,
+        });
+        self.pending_solver = Some(PendingSolver {
+            job,
+            full_speed: false,
+        });
+        self.active_run_log = run_log_directory;
+
+        Ok(())
+    }
+
+    fn choose_fdtd_backend(
+        &self,
+        config: &FdtdSolverConfig,
+        memory_limit: Option<usize>,
+    ) -> Result<BackendChoice, Error> {
+        let cpu_memory_required = FdtdCpuBackend::single_threaded().memory_required(config);
+
+        let candidates = [
+            Candidate {
+                backend: FdtdBackendKind::SingleThreaded,
+                memory_required: cpu_memory_required,
+                unavailable: None,
+            },
+            Candidate {
+                backend: FdtdBackendKind::MultiThreaded,
+                memory_required: cpu_memory_required,
+                unavailable: (!cfg!(feature = "multi-threading"))
+                    .then_some("compiled without multi-threading"),
+            },
+            Candidate {
+                backend: FdtdBackendKind::Wgpu,
+                memory_required: self.fdtd_wgpu.memory_required(config),
+                unavailable: (!self.fdtd_wgpu.fits_device_limits(config))
+                    .then_some("grid exceeds the GPU's buffer size limit"),
+            },
+        ];
+
+        choose_backend(
+            config.num_cells(),
+            memory_limit,
+            &candidates,
+            &self.benchmarks,
+        )
+    }
+
+    fn run_custom(
+        &mut self,
+        scene: &mut Scene,
+        common_config: &SolverConfigCommon,
+        custom_config: &SolverConfigCustom,
+    ) -> Result<(), Error> {
+        let Some(backend) = self.registry.get(&custom_config.backend)
+        else {
+            bail!("Unknown solver backend: {}", custom_config.backend);
+        };
+
+        tracing::debug!(backend = backend.name(), "creating custom solver");
+        self.active_backend = None;
+
+        let volume = common_config.volume.aabb(scene);
+        let instance = backend.create_instance(CustomSolverContext {
+            scene,
+            volume,
+            default_material: common_config.default_material,
+            config: &custom_config.config,
+        })?;
+
+        self.active_solver = Some(Solver::spawn_custom(
+            instance,
+            EventSchedule::new(&common_config.events),
+            self.repaint_trigger.clone(),
+            self.error_sink.clone(),
+        ));
 
         Ok(())
     }
 }
 
+/// Creates the config for the FDTD backends, and returns it with the volume
+/// that is simulated.
+fn fdtd_solver_config(
+    scene: &mut Scene,
+    common_config: &SolverConfigCommon,
+    fdtd_config: &SolverConfigFdtd,
+) -> Result<(FdtdSolverConfig, Aabb), Error> {
+    let aabb = common_config.volume.aabb(scene);
+
+    let size = aabb.extents();
+    if !size.iter().all(|c| c.is_finite() && *c >= 0.0) {
+        bail!("invalid aabb: {aabb:?}");
+    }
+
+    let mut config = FdtdSolverConfig {
+        resolution: fdtd_config.resolution,
+        physical_constants: SceneUnits::get(&scene.world).physical_constants(),
+        size: size.cast(),
+        spatial_order: fdtd_config.spatial_order,
+    };
+
+    if let MeshGrading::Graded { max_grading_ratio } = fdtd_config.mesh {
+        let mesh = graded_mesh_for_scene(
+            &mut scene.world,
+            &aabb,
+            &common_config.volume.rotation(),
+            &GradedMeshConfig {
+                base_cell_size: fdtd_config.resolution.spatial,
+                max_grading_ratio,
+            },
+        );
+        // todo: the backends only support uniform lattices so far. until they can use
+        // the mesh lines, we use the finest cell size everywhere.
+        config.resolution.spatial =
+            mesh.min_cell_size()
+                .zip_map(&fdtd_config.resolution.spatial, |fine, base| {
+                    if fine > 0.0 { fine } else { base }
+                });
+        config.resolution.temporal = config
+            .resolution
+            .temporal
+            .min(config.max_stable_temporal_resolution());
+
+        tracing::warn!(
+            graded_num_cells = mesh.num_cells(),
+            uniform_num_cells = config.num_cells(),
+            resolution = ?config.resolution,
+            "graded meshes aren't supported by the backends yet, using the finest cell size everywhere"
+        );
+    }
+
+    // check courant condition
+    let temporal_resolution_satisfying_courant_condition = config.max_stable_temporal_resolution();
+    if config.resolution.temporal > temporal_resolution_satisfying_courant_condition {
+        tracing::warn!(resolution = ?config.resolution, "resolution doesn't satisfy courant condition");
+    }
+
+    Ok((config, aabb))
+}
+
 struct RunFdtd<'a> {
     scene: &'a mut Scene,
     common_config: &'a SolverConfigCommon,
     fdtd_config: &'a SolverConfigFdtd,
+    config: FdtdSolverConfig,
+    aabb: Aabb,
+    jobs: Jobs,
     repaint_trigger: RepaintTrigger,
     error_sink: UiErrorSink,
+    stream: Option<StreamPublisher>,
+
+    /// Directory and interval of the run log
+    run_log: Option<(PathBuf, Duration)>,
 }
 
 impl<'a> RunFdtd<'a> {
-    fn run_fdtd_with_backend<Backend>(self, backend: &Backend) -> Result<Solver, Error>
+    /// Prepares a run on the UI thread and spawns a job that rasterizes the
+    /// scene into the lattice. The job's result starts the solver.
+    fn run_fdtd_with_backend<Backend>(
+        self,
+        backend: Backend,
+    ) -> Result<JobHandle<StartSolver>, Error>
     where
-        Backend: SolverBackend<FdtdSolverConfig, Point3<usize>> + 'static,
-        Backend::Instance: CreateProjection<TextureSenderTarget> + Send + 'static,
+        Backend: SolverBackend<FdtdSolverConfig, Point3<usize>> + Send + 'static,
+        Backend::Instance: CreateProjection<TextureSenderTarget>
+            + CopyState
+            + Field<Point3<usize>>
+            + Send
+            + Sync
+            + 'static,
         <Backend::Instance as SolverInstance>::State: Time + Send + 'static,
         for<'b> <Backend::Instance as SolverInstance>::UpdatePass<'b>:
             UpdatePassForcing<Point3<usize>>,
@@ -255,40 +872,27 @@ impl<'a> RunFdtd<'a> {
                 'b,
                 <Backend::Instance as CreateProjection<TextureSenderTarget>>::Projection,
             >,
-        <Backend::Instance as CreateProjection<TextureSenderTarget>>::Projection: Send + 'static,
+        <Backend::Instance as CreateProjection<TextureSenderTarget>>::Projection:
+            SetProjectionTransform + Send + 'static,
     {
         let Self {
             scene,
             common_config,
             fdtd_config,
+            config,
+            aabb,
+            jobs,
             repaint_trigger,
             error_sink,
+            stream,
+            run_log,
         } = self;
 
         let time_start = Instant::now();
 
-        let aabb = common_config.volume.aabb(scene);
-
-        let size = aabb.extents();
-        if !size.iter().all(|c| c.is_finite() && *c >= 0.0) {
-            bail!("invalid aabb: {aabb:?}");
-        }
-
-        let config = FdtdSolverConfig {
-            resolution: fdtd_config.resolution,
-            physical_constants: common_config.physical_constants,
-            size: size.cast(),
-        };
-
-        // check courant condition
+        // the courant condition was already checked when the config was created
         let temporal_resolution_satisfying_courant_condition =
-            fdtd::estimate_temporal_from_spatial_resolution(
-                common_config.physical_constants.speed_of_light(),
-                &config.resolution.spatial,
-            );
-        if config.resolution.temporal > temporal_resolution_satisfying_courant_condition {
-            tracing::warn!(resolution = ?config.resolution, "resolution doesn't satisfy courant condition");
-        }
+            config.max_stable_temporal_resolution();
 
         // good config for debugging
         /*let config = fdtd::SimulationConfig {
@@ -309,7 +913,7 @@ impl<'a> RunFdtd<'a> {
         let lattice_size = config.size();
 
         tracing::debug!(
-            ?size,
+            size = ?config.size,
             resolution = ?config.resolution,
             memory_required = memory_required_str,
             ?lattice_size,
@@ -337,53 +941,136 @@ impl<'a> RunFdtd<'a> {
             &aabb,
         );
 
-        let instance = scene
-            .world
-            .run_system_cached_with(
-                create_solver_instance_system,
-                (
-                    backend,
-                    &config,
-                    coordinate_transformations,
-                    common_config.default_material,
-                ),
-            )
-            .unwrap()
-            .expect("fdtd solver instance creation never fails");
+        let ground_planes = GroundPlanes::from_world(
+            &mut scene.world,
+            &config.resolution,
+            &coordinate_transformations,
+        );
+
+        let parameters = SceneParameters::for_run(&scene.world, &common_config.parameter_overrides);
 
-        let mut state = instance.create_state();
+        // the job can't borrow the world, so it rasterizes a copy of the materials
+        let domain = DomainSnapshot::from_world(&mut scene.world);
 
-        let sources = Sources::from_scene(&mut scene.world, &coordinate_transformations);
+        let sources = Sources::from_scene(
+            &mut scene.world,
+            &config,
+            &coordinate_transformations,
+            &common_config.disabled_ports,
+        );
+
+        let watchdog = Watchdog::new(
+            &fdtd_config.watchdog,
+            coordinate_transformations,
+            LikelyCause::from_resolution(
+                config.resolution.temporal,
+                temporal_resolution_satisfying_courant_condition,
+            ),
+        );
 
-        // create observers
-        let observers = Observers::from_scene(
-            &instance,
-            &mut state,
+        let probes = Probes::from_scene(
+            &mut scene.world,
+            &coordinate_transformations,
+            &common_config.disabled_ports,
+            repaint_trigger.clone(),
+        )?;
+
+        let run_log = run_log
+            .map(|(directory, interval)| RunLog::create(directory, interval, &probes.outputs()))
+            .transpose()?;
+
+        // the observers' textures are created here, their projections once the
+        // instance exists.
+        let observer_targets = ObserverTargets::from_scene(
             &mut scene.world,
             &lattice_size,
             repaint_trigger,
+            stream,
+            run_log.as_ref().map(RunLog::snapshots),
         );
 
-        tracing::debug!("time to create simulation: {:?}", time_start.elapsed());
+        let stop_condition = fdtd_config.stop_condition;
+        let events = EventSchedule::new(&common_config.events);
+        let default_material = common_config.default_material;
+        let num_cells = lattice_size.product();
 
-        // run simulation
-        let solver = Solver::spawn(
-            instance,
-            state,
-            fdtd_config.stop_condition,
-            sources,
-            observers,
-            error_sink,
-        );
+        let job = jobs.spawn("Voxelize scene", move |job: &JobContext| {
+            let instance = backend.create_instance(
+                &config,
+                SceneDomainDescription {
+                    domain,
+                    coordinate_transformations,
+                    default_material,
+                    ground_planes,
+                    parameters,
+                    resolution: config.resolution,
+                    physical_constants: config.physical_constants,
+                    job,
+                    num_cells,
+                    num_done: 0,
+                    cancelled: false,
+                },
+            )?;
+            job.check_cancelled()?;
+
+            let state = instance.create_state();
+
+            // the observers project from a snapshot of the state, see `ObserverWorker`
+            let snapshot = instance.create_state();
+            let observers = observer_targets.create_projections(&instance, &snapshot);
+
+            tracing::debug!("time to create simulation: {:?}", time_start.elapsed());
+
+            // the solver thread is spawned by the runner, so a cancelled job never
+            // leaves one behind.
+            let start_solver: StartSolver = Box::new(move || {
+                Solver::spawn(
+                    instance,
+                    state,
+                    snapshot,
+                    stop_condition,
+                    events,
+                    sources,
+                    probes,
+                    observers,
+                    watchdog,
+                    run_log,
+                    error_sink,
+                )
+            });
+
+            Ok(start_solver)
+        });
 
-        Ok(solver)
+        Ok(job)
     }
 }
 
+/// Starts the solver of a run, once its job has set it up.
+type StartSolver = Box<dyn FnOnce() -> Solver + Send>;
+
+/// A run whose solver is still being set up.
+#[derive(Debug)]
+struct PendingSolver {
+    job: JobHandle<StartSolver>,
+
+    /// Run the solver at full speed once it's started, see
+    /// [`SolverRunner::run_at_full_speed`].
+    full_speed: bool,
+}
+
 #[derive(Debug)]
 struct Shared {
     state: Mutex<SolverState>,
     condition: Condvar,
+
+    /// Set when the solver is closed. Until then a finished solver keeps its
+    /// state around, so observer slices can still be scrubbed.
+    closed: AtomicBool,
+
+    /// Cancelled when the solver is stopped, so it doesn't finish its time
+    /// slice first.
+    cancellation: CancellationToken,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -398,12 +1085,20 @@ pub struct SolverState {
     pub last_step_time: Duration,
     pub step_delay: Option<Duration>,
     pub observation_delay: Option<Duration>,
+
+    /// Observations that were skipped, because the observers were still busy
+    /// with the previous one.
+    pub dropped_observations: usize,
 }
 
 #[derive(Debug)]
 pub struct Solver {
     join_handle: JoinHandle<()>,
     shared: Arc<Shared>,
+    clock: SimulationClock,
+    probe_outputs: ProbeOutputs,
+    observer_slices: ObserverSlices,
+    observer_histories: Vec<ObserverHistory>,
 }
 
 impl Solver {
@@ -419,6 +1114,7 @@ impl Solver {
     pub fn stop(&self) {
         let mut state = self.shared.state.lock();
         state.finished = true;
+        self.shared.cancellation.cancel();
         self.shared.condition.notify_all();
     }
 
@@ -427,27 +1123,75 @@ impl Solver {
         state.paused = true;
     }
 
+    /// Time base that visualizations of this solver's results show.
+    pub fn clock(&self) -> &SimulationClock {
+        &self.clock
+    }
+
+    pub fn probe_outputs(&self) -> &ProbeOutputs {
+        &self.probe_outputs
+    }
+
+    pub fn observer_slices(&self) -> &ObserverSlices {
+        &self.observer_slices
+    }
+
+    /// Histories of the observers that keep one.
+    pub fn observer_histories(&self) -> &[ObserverHistory] {
+        &self.observer_histories
+    }
+
+    /// Moves an observer's volume slice. This is projected right away, even if
+    /// the solver is paused or finished.
+    pub fn set_slice_position(&self, index: usize, position: f32) {
+        self.observer_slices.set_position(index, position);
+
+        let _state = self.shared.state.lock();
+        self.shared.condition.notify_all();
+    }
+
     pub fn resume(&self) {
         let mut state = self.shared.state.lock();
         state.paused = false;
         self.shared.condition.notify_all();
     }
 
+    /// Resumes the solver without the step delay used for watching it.
+    pub fn run_at_full_speed(&self) {
+        let mut state = self.shared.state.lock();
+        state.step_delay = None;
+        state.paused = false;
+        self.shared.condition.notify_all();
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn spawn<Instance>(
         instance: Instance,
         mut state: Instance::State,
+        snapshot: Instance::State,
         stop_condition: StopCondition,
-        sources: Sources,
-        mut observers: Observers<<Instance as CreateProjection<TextureSenderTarget>>::Projection>,
+        mut events: EventSchedule,
+        mut sources: Sources,
+        mut probes: Probes,
+        observers: Observers<<Instance as CreateProjection<TextureSenderTarget>>::Projection>,
+        mut watchdog: Option<Watchdog>,
+        mut run_log: Option<RunLog>,
         error_sink: UiErrorSink,
     ) -> Self
     where
-        Instance: SolverInstance + CreateProjection<TextureSenderTarget> + Send + 'static,
+        Instance: SolverInstance
+            + CreateProjection<TextureSenderTarget>
+            + CopyState
+            + Field<Point3<usize>>
+            + Send
+            + Sync
+            + 'static,
         Instance::State: Time + Send + 'static,
         for<'a> Instance::UpdatePass<'a>: UpdatePassForcing<Point3<usize>>,
         for<'a> <Instance as BeginProjectionPass>::ProjectionPass<'a>:
             ProjectionPassAdd<'a, <Instance as CreateProjection<TextureSenderTarget>>::Projection>,
-        <Instance as CreateProjection<TextureSenderTarget>>::Projection: Send + 'static,
+        <Instance as CreateProjection<TextureSenderTarget>>::Projection:
+            SetProjectionTransform + Send + 'static,
     {
         let start_paused = true;
 
@@ -462,25 +1206,44 @@ impl Solver {
             last_step_time: Duration::ZERO,
             step_delay: Some(Duration::from_millis(10)),
             observation_delay: Some(Duration::from_millis(1000 / 25)),
+            dropped_observations: 0,
         };
         let shared = Arc::new(Shared {
             state: Mutex::new(control_state),
             condition: Condvar::new(),
+            closed: AtomicBool::new(false),
+            cancellation: CancellationToken::new(),
         });
 
+        let clock = SimulationClock::default();
+        let probe_outputs = probes.outputs();
+        let observer_slices = observers.slices.clone();
+        let observer_histories = observers.histories.clone();
+
         let join_handle = spawn_thread("solver", {
             let shared = shared.clone();
+            let clock = clock.clone();
+            let observer_slices = observer_slices.clone();
 
             move || {
+                let instance = Arc::new(instance);
+                let observer_worker = ObserverWorker::spawn(
+                    instance.clone(),
+                    snapshot,
+                    observers,
+                    shared.clone(),
+                    error_sink.clone(),
+                );
+
                 let mut time_last_observation: Option<Instant> = None;
                 let mut stop_condition_reached = false;
                 let mut time_pass = Duration::ZERO;
                 let mut total_time = Duration::ZERO;
+                let mut dropped_observations = 0;
 
                 // if we start out paused we want to run ob observers at least once
-                if start_paused && let Err(error) = observers.run(&instance, &state) {
-                    error_sink.handle_error(error);
-                    return;
+                if start_paused {
+                    observer_worker.observe(&*instance, &state);
                 }
 
                 loop {
@@ -491,15 +1254,39 @@ impl Solver {
                     control_state.sim_time = state.time();
                     control_state.last_step_time = time_pass;
                     control_state.total_running_time = total_time;
+                    control_state.dropped_observations = dropped_observations;
+                    clock.set_solver_time(state.tick(), state.time());
 
                     control_state.finished |= stop_condition_reached;
                     if control_state.finished {
-                        control_state.stop_time = Some(Instant::now());
-                        return;
+                        control_state.stop_time.get_or_insert_with(Instant::now);
+
+                        // keep the final fields until the solver is closed, so the slices can
+                        // still be scrubbed through them
+                        if observer_slices.is_empty()
+                            || shared.closed.load(atomic::Ordering::Relaxed)
+                            || !observer_worker.is_running()
+                        {
+                            return;
+                        }
+
+                        shared.condition.wait(&mut control_state);
+                        drop(control_state);
+
+                        if observer_slices.has_changes() {
+                            observer_worker.observe(&*instance, &state);
+                        }
+                        continue;
                     }
 
                     if control_state.paused {
                         shared.condition.wait(&mut control_state);
+                        drop(control_state);
+
+                        // slices scrubbed while paused are projected right away
+                        if observer_slices.has_changes() {
+                            observer_worker.observe(&*instance, &state);
+                        }
                     }
                     else {
                         let observation_delay = control_state.observation_delay;
@@ -507,45 +1294,134 @@ impl Solver {
 
                         drop(control_state);
 
-                        // check if stop condition reached. if so, set flag and continue to next
-                        // (and last) iteration of loop
-                        if evaluate_stop_condition(&stop_condition, total_time, &state) {
-                            stop_condition_reached = true;
+                        // with a real-time factor the solver waits for the displayed time
+                        if clock.is_ahead(state.time()) {
+                            std::thread::sleep(WAIT_INTERVAL);
                             continue;
                         }
 
-                        let time_pass_start = Instant::now();
-
-                        // note: can't just put the method call into the argument because by then
-                        // the state is borrowed. we should probably give some access to the state
-                        // during an update pass.
-                        let sim_time = state.time();
-
-                        // do the update pass
-                        let mut update_pass = instance.begin_update(&mut state);
-                        sources.apply(sim_time, &mut update_pass);
-                        update_pass.finish();
-
-                        // do observations
-                        let do_observations = observation_delay.is_some_and(|observation_delay| {
-                            time_last_observation.is_none_or(|time_last_observation| {
-                                time_last_observation.elapsed() > observation_delay
-                            })
-                        });
-                        if do_observations {
-                            if let Err(error) = observers.run(&instance, &state) {
-                                error_sink.handle_error(error);
-                                stop_condition_reached = true;
-                                continue;
-                            }
-                            time_last_observation = Some(Instant::now());
+                        // with a step delay every slice is a single step
+                        let time_slice = if step_delay.is_some() {
+                            Duration::ZERO
                         }
-
-                        time_pass = time_pass_start.elapsed();
-                        total_time += time_pass;
+                        else {
+                            STEP_TIME_SLICE
+                        };
+
+                        // the slice returns to this loop to check the control state, whenever
+                        // a step can't be done right away
+                        let slice = instance.step_for(
+                            &mut state,
+                            time_slice,
+                            &shared.cancellation,
+                            |instance, state| {
+                                if clock.is_ahead(state.time()) {
+                                    return ControlFlow::Break(());
+                                }
+
+                                // check if stop condition reached. if so, set flag and return
+                                // for the next (and last) iteration of the loop
+                                if evaluate_stop_condition(&stop_condition, total_time, state) {
+                                    stop_condition_reached = true;
+                                    return ControlFlow::Break(());
+                                }
+
+                                // scheduled events run between two ticks
+                                let mut pause = false;
+                                for event in events.take_due(state.time()) {
+                                    tracing::debug!(?event, "running scheduled event");
+                                    match &event.action {
+                                        EventAction::SetSourceAmplitude { source, amplitude } => {
+                                            sources.set_amplitude(source, *amplitude);
+                                        }
+                                        EventAction::SaveFields { path } => {
+                                            let result = fields_path(
+                                                path.as_deref(),
+                                                run_log.as_ref().map(RunLog::directory),
+                                                state.tick(),
+                                            )
+                                            .and_then(|path| save_fields(instance, state, &path));
+                                            if let Err(error) = result {
+                                                error_sink.handle_error(error);
+                                            }
+                                        }
+                                        EventAction::Pause => pause = true,
+                                    }
+                                }
+                                if pause {
+                                    shared.state.lock().paused = true;
+                                    return ControlFlow::Break(());
+                                }
+
+                                let time_pass_start = Instant::now();
+
+                                // note: can't just put the method call into the argument because
+                                // by then the state is borrowed. we should probably give some
+                                // access to the state during an update pass.
+                                let sim_time = state.time();
+
+                                // do the update pass
+                                let mut update_pass = instance.begin_update(state);
+                                sources.apply(sim_time, &mut update_pass);
+                                update_pass.finish();
+
+                                // probes sample at fixed tick intervals, independent of the
+                                // observation delay
+                                if let Err(error) =
+                                    probes.run(instance, state, state.tick(), state.time())
+                                {
+                                    error_sink.handle_error(error);
+                                    stop_condition_reached = true;
+                                    return ControlFlow::Break(());
+                                }
+
+                                // halt diverged simulations instead of running them forever
+                                if let Some(watchdog) = &mut watchdog
+                                    && let Err(error) = watchdog.check(instance, state)
+                                {
+                                    tracing::warn!(%error, "simulation diverged");
+                                    error_sink.handle_error(error.into());
+                                    stop_condition_reached = true;
+                                    return ControlFlow::Break(());
+                                }
+
+                                // a failing run log shouldn't stop a long run, so it's only
+                                // reported once
+                                if let Some(log) = &mut run_log
+                                    && let Err(error) = log.log(instance, state)
+                                {
+                                    error_sink.handle_error(error);
+                                    run_log = None;
+                                }
+
+                                // do observations. the observers run on their own thread, so we
+                                // only pay for copying the fields here. while the visualization
+                                // is paused, the observers keep showing the fields from when it
+                                // was paused.
+                                let do_observations = !clock.is_visualization_paused()
+                                    && observation_delay.is_some_and(|observation_delay| {
+                                        time_last_observation.is_none_or(|time_last_observation| {
+                                            time_last_observation.elapsed() > observation_delay
+                                        })
+                                    });
+                                if do_observations {
+                                    if !observer_worker.try_observe(instance, state) {
+                                        dropped_observations += 1;
+                                    }
+                                    time_last_observation = Some(Instant::now());
+                                }
+
+                                time_pass = time_pass_start.elapsed();
+                                total_time += time_pass;
+
+                                ControlFlow::Continue(())
+                            },
+                        );
 
                         // sleep if we're ups limited
-                        if let Some(step_delay) = step_delay {
+                        if let Some(step_delay) = step_delay
+                            && slice.end == SliceEnd::Elapsed
+                        {
                             let sleep = step_delay.saturating_sub(time_pass);
                             if !sleep.is_zero() {
                                 std::thread::sleep(sleep);
@@ -559,119 +1435,567 @@ impl Solver {
         Self {
             join_handle,
             shared,
+            clock,
+            probe_outputs,
+            observer_slices,
+            observer_histories,
         }
     }
 }
 
-fn create_solver_instance_system<Backend>(
-    (InRef(backend), InRef(config), In(coordinate_transformations), In(default_material)): (
-        InRef<Backend>,
-        InRef<FdtdSolverConfig>,
-        In<CoordinateTransformations>,
-        In<Material>,
-    ),
-    world_domain_description: WorldDomainDescriptionSystemParam,
-) -> Result<Backend::Instance, Backend::Error>
+/// Runs the observers on their own thread, so that projecting the fields never
+/// slows down the solver.
+///
+/// The solver and the worker hand a single snapshot of the solver state back
+/// and forth. While the worker is still projecting the last snapshot, new
+/// observations are dropped.
+#[derive(Debug)]
+struct ObserverWorker<S> {
+    snapshots: mpsc::Receiver<S>,
+    observations: Option<mpsc::Sender<S>>,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl<S> ObserverWorker<S>
 where
-    Backend: SolverBackend<FdtdSolverConfig, Point3<usize>>,
+    S: Send + 'static,
 {
-    backend.create_instance(
-        config,
-        WorldDomainDescription {
-            system_param: world_domain_description,
-            coordinate_transformations,
-            default_material,
-            resolution: config.resolution,
-            physical_constants: config.physical_constants,
-        },
-    )
+    fn spawn<I, P>(
+        instance: Arc<I>,
+        snapshot: S,
+        mut observers: Observers<P>,
+        shared: Arc<Shared>,
+        error_sink: UiErrorSink,
+    ) -> Self
+    where
+        I: BeginProjectionPass<State = S> + Send + Sync + 'static,
+        S: Time,
+        for<'a> <I as BeginProjectionPass>::ProjectionPass<'a>: ProjectionPassAdd<'a, P>,
+        P: SetProjectionTransform + Send + 'static,
+    {
+        let (observations_sender, observations_receiver) = mpsc::channel::<S>();
+        let (snapshots_sender, snapshots_receiver) = mpsc::channel();
+        snapshots_sender
+            .send(snapshot)
+            .expect("snapshot receiver dropped");
+
+        let join_handle = spawn_thread("observers", move || {
+            for snapshot in observations_receiver {
+                if let Err(error) = observers.run(&*instance, &snapshot) {
+                    error_sink.handle_error(error);
+
+                    let mut state = shared.state.lock();
+                    state.finished = true;
+                    shared.condition.notify_all();
+                    return;
+                }
+
+                if snapshots_sender.send(snapshot).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            snapshots: snapshots_receiver,
+            observations: Some(observations_sender),
+            join_handle: Some(join_handle),
+        }
+    }
+
+    /// Hands a copy of `state` to the observers, unless they're still busy.
+    ///
+    /// Returns `false` if the observation was dropped.
+    fn try_observe<I>(&self, instance: &I, state: &S) -> bool
+    where
+        I: CopyState<State = S>,
+    {
+        match self.snapshots.try_recv() {
+            Ok(snapshot) => {
+                self.send(instance, state, snapshot);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Hands a copy of `state` to the observers, waiting for them to finish
+    /// the last observation.
+    fn observe<I>(&self, instance: &I, state: &S)
+    where
+        I: CopyState<State = S>,
+    {
+        if let Ok(snapshot) = self.snapshots.recv() {
+            self.send(instance, state, snapshot);
+        }
+    }
+
+    fn send<I>(&self, instance: &I, state: &S, mut snapshot: S)
+    where
+        I: CopyState<State = S>,
+    {
+        instance.copy_state(state, &mut snapshot);
+
+        if let Some(observations) = &self.observations {
+            // if this fails, the worker stopped because of an error, which it already
+            // reported
+            let _ = observations.send(snapshot);
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.join_handle
+            .as_ref()
+            .is_some_and(|join_handle| !join_handle.is_finished())
+    }
+}
+
+impl<S> Drop for ObserverWorker<S> {
+    fn drop(&mut self) {
+        // closing the channel stops the worker
+        self.observations = None;
+
+        if let Some(join_handle) = self.join_handle.take()
+            && let Err(panic) = join_handle.join()
+        {
+            tracing::error!(?panic, "Observer thread panicked");
+        }
+    }
 }
 
-#[derive(Debug, SystemParam)]
-struct WorldDomainDescriptionSystemParam<'w, 's> {
-    point_query: PointQuery<'w, 's>,
-    materials: Query<'w, 's, &'static Material>,
-    intersect_aabb_query: IntersectAabb<'w>,
-    pmls: Query<
-        'w,
-        's,
+impl Solver {
+    /// Runs a solver from a [`CustomSolverBackend`].
+    ///
+    /// Custom solvers bring their own outputs, so there are no probes or
+    /// observers to run here.
+    fn spawn_custom(
+        mut instance: Box<dyn CustomSolverInstance>,
+        mut events: EventSchedule,
+        repaint_trigger: RepaintTrigger,
+        error_sink: UiErrorSink,
+    ) -> Self {
+        let control_state = SolverState {
+            finished: false,
+            paused: true,
+            sim_time: 0.0,
+            sim_tick: 0,
+            start_time: Instant::now(),
+            stop_time: None,
+            total_running_time: Duration::ZERO,
+            last_step_time: Duration::ZERO,
+            step_delay: Some(Duration::from_millis(10)),
+            observation_delay: None,
+            dropped_observations: 0,
+        };
+        let shared = Arc::new(Shared {
+            state: Mutex::new(control_state),
+            condition: Condvar::new(),
+            closed: AtomicBool::new(false),
+            cancellation: CancellationToken::new(),
+        });
+
+        let clock = SimulationClock::default();
+
+        let join_handle = spawn_thread("solver", {
+            let shared = shared.clone();
+            let clock = clock.clone();
+
+            move || {
+                let mut done = false;
+                let mut time_pass = Duration::ZERO;
+                let mut total_time = Duration::ZERO;
+
+                loop {
+                    let mut control_state = shared.state.lock();
+
+                    control_state.sim_tick = instance.tick();
+                    control_state.sim_time = instance.time();
+                    control_state.last_step_time = time_pass;
+                    control_state.total_running_time = total_time;
+                    clock.set_solver_time(instance.tick(), instance.time());
+
+                    control_state.finished |= done;
+                    if control_state.finished {
+                        control_state.stop_time.get_or_insert_with(Instant::now);
+                        return;
+                    }
+
+                    if control_state.paused {
+                        shared.condition.wait(&mut control_state);
+                        continue;
+                    }
+
+                    let step_delay = control_state.step_delay;
+                    drop(control_state);
+
+                    if clock.is_ahead(instance.time()) {
+                        std::thread::sleep(WAIT_INTERVAL);
+                        continue;
+                    }
+
+                    // custom solvers bring their own sources and fields, so they can only be
+                    // paused
+                    let mut pause = false;
+                    for event in events.take_due(instance.time()) {
+                        match event.action {
+                            EventAction::Pause => pause = true,
+                            action => {
+                                tracing::warn!(?action, "custom solvers don't support this event");
+                            }
+                        }
+                    }
+                    if pause {
+                        shared.state.lock().paused = true;
+                        continue;
+                    }
+
+                    let time_pass_start = Instant::now();
+                    match instance.step() {
+                        Ok(more) => done = !more,
+                        Err(error) => {
+                            error_sink.handle_error(error.into());
+                            done = true;
+                        }
+                    }
+                    time_pass = time_pass_start.elapsed();
+                    total_time += time_pass;
+                    repaint_trigger.repaint();
+
+                    if let Some(step_delay) = step_delay {
+                        let sleep = step_delay.saturating_sub(time_pass);
+                        if !sleep.is_zero() {
+                            std::thread::sleep(sleep);
+                        }
+                    }
+                }
+            }
+        });
+
+        Self {
+            join_handle,
+            shared,
+            clock,
+            probe_outputs: Default::default(),
+            observer_slices: Default::default(),
+            observer_histories: vec![],
+        }
+    }
+}
+
+/// Query data needed to resolve overlapping materials with [`materials_at`].
+pub(super) type MaterialQueryData = (
+    &'static Material,
+    Option<&'static MaterialPriority>,
+    &'static GlobalTransform,
+    &'static Collider,
+    Option<&'static MaterialGroups<Material>>,
+    Option<&'static MaterialDependencies>,
+);
+
+/// Components of an entity in [`MaterialQueryData`].
+type MaterialItem<'a> = (
+    &'a Material,
+    Option<&'a MaterialPriority>,
+    &'a GlobalTransform,
+    &'a Collider,
+    Option<&'a MaterialGroups<Material>>,
+    Option<&'a MaterialDependencies>,
+);
+
+/// A material found at a point, see [`materials_at`].
+#[derive(Clone, Copy, Debug)]
+pub(super) struct OverlappingMaterial {
+    pub entity: Entity,
+    pub material: Material,
+    pub priority: MaterialPriority,
+
+    /// Volume of the object's AABB. This is infinite for unbounded objects.
+    pub bounding_volume: f32,
+}
+
+impl OverlappingMaterial {
+    /// Orders materials by precedence, as described in [`MaterialPriority`].
+    fn cmp_precedence(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .cmp(&self.priority)
+            .then(self.bounding_volume.total_cmp(&other.bounding_volume))
+            .then(self.entity.cmp(&other.entity))
+    }
+}
+
+/// All materials at a point in world space, in the order of precedence the
+/// solver uses. The first one is assigned to the cell.
+///
+/// Materials that depend on scene parameters are evaluated with `parameters`.
+pub(super) fn materials_at(
+    point_query: &PointQuery,
+    materials: &Query<MaterialQueryData>,
+    parameters: &ParameterValues,
+    point: Point3<f64>,
+) -> Vec<OverlappingMaterial> {
+    let mut overlapping = point_query
+        .precise_point_query(point)
+        .filter_map(|entity| {
+            let item = materials.get(entity).ok()?;
+            Some(overlapping_material(entity, item, parameters, &point))
+        })
+        .collect::<Vec<_>>();
+
+    overlapping.sort_by(OverlappingMaterial::cmp_precedence);
+
+    overlapping
+}
+
+/// The material of an entity that contains `point`.
+fn overlapping_material(
+    entity: Entity,
+    (material, priority, transform, collider, material_groups, dependencies): MaterialItem,
+    parameters: &ParameterValues,
+    point: &Point3<f64>,
+) -> OverlappingMaterial {
+    // inside a mesh with material groups the closest face decides.
+    let material = material_groups
+        .and_then(|material_groups| {
+            material_groups.group_at_point(
+                collider,
+                &Isometry3::identity(),
+                &transform.precise_point_to_local(point),
+            )
+        })
+        .map_or(material, |group| &group.material);
+    let material = dependencies.map_or(*material, |dependencies| {
+        dependencies.apply(material, parameters)
+    });
+
+    OverlappingMaterial {
+        entity,
+        material,
+        priority: priority.copied().unwrap_or_default(),
+        bounding_volume: collider
+            .compute_aabb(transform.isometry())
+            .map_or(f32::INFINITY, |aabb| aabb.volume()),
+    }
+}
+
+/// Copy of the materials and PMLs in a scene, so it can be rasterized by a
+/// job.
+///
+/// Lookups test every object whose AABB contains the point. This is the same
+/// as [`materials_at`] without the BVH.
+#[derive(Clone, Debug, Default)]
+struct DomainSnapshot {
+    materials: Vec<MaterialSnapshot>,
+    pmls: Vec<PmlSnapshot>,
+}
+
+#[derive(Clone, Debug)]
+struct MaterialSnapshot {
+    entity: Entity,
+    material: Material,
+    priority: Option<MaterialPriority>,
+    transform: GlobalTransform,
+    collider: Collider,
+    material_groups: Option<MaterialGroups<Material>>,
+    dependencies: Option<MaterialDependencies>,
+
+    /// `None` for unbounded colliders.
+    aabb: Option<Aabb>,
+}
+
+impl MaterialSnapshot {
+    fn item(&self) -> MaterialItem<'_> {
         (
-            &'static GradedPml,
-            &'static Collider,
-            &'static GlobalTransform,
-        ),
-    >,
+            &self.material,
+            self.priority.as_ref(),
+            &self.transform,
+            &self.collider,
+            self.material_groups.as_ref(),
+            self.dependencies.as_ref(),
+        )
+    }
 }
 
-struct WorldDomainDescription<'w, 's> {
-    system_param: WorldDomainDescriptionSystemParam<'w, 's>,
+#[derive(Clone, Debug)]
+struct PmlSnapshot {
+    pml: GradedPml,
+    collider: Collider,
+    transform: GlobalTransform,
+    aabb: Aabb,
+}
+
+impl DomainSnapshot {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.query::<(Entity, MaterialQueryData)>();
+        let materials = materials
+            .iter(world)
+            .map(
+                |(
+                    entity,
+                    (material, priority, transform, collider, material_groups, dependencies),
+                )| {
+                    MaterialSnapshot {
+                        entity,
+                        material: *material,
+                        priority: priority.copied(),
+                        transform: *transform,
+                        collider: collider.clone(),
+                        material_groups: material_groups.cloned(),
+                        dependencies: dependencies.cloned(),
+                        aabb: collider.compute_aabb(transform.isometry()),
+                    }
+                },
+            )
+            .collect();
+
+        // like the BVH, this skips unbounded PMLs
+        let mut pmls = world.query::<(&GradedPml, &Collider, &GlobalTransform)>();
+        let pmls = pmls
+            .iter(world)
+            .filter_map(|(pml, collider, transform)| {
+                Some(PmlSnapshot {
+                    pml: *pml,
+                    collider: collider.clone(),
+                    transform: *transform,
+                    aabb: collider.compute_aabb(transform.isometry())?,
+                })
+            })
+            .collect();
+
+        Self { materials, pmls }
+    }
+
+    /// The material with the highest precedence at `point`, see
+    /// [`materials_at`].
+    fn material_at(&self, parameters: &ParameterValues, point: &Point3<f64>) -> Option<Material> {
+        let aabb_point = point.cast();
+
+        self.materials
+            .iter()
+            .filter(|snapshot| {
+                snapshot
+                    .aabb
+                    .is_none_or(|aabb| aabb.contains_local_point(&aabb_point))
+                    && snapshot.collider.contains_point(
+                        &Isometry3::identity(),
+                        &snapshot.transform.precise_point_to_local(point),
+                    )
+            })
+            .map(|snapshot| {
+                overlapping_material(snapshot.entity, snapshot.item(), parameters, point)
+            })
+            .min_by(OverlappingMaterial::cmp_precedence)
+            .map(|overlapping| overlapping.material)
+    }
+
+    fn pml_at(
+        &self,
+        resolution: &Resolution,
+        physical_constants: &PhysicalConstants,
+        point: &Point3<f64>,
+    ) -> Option<PmlCoefficients> {
+        let aabb_point = point.cast();
+
+        let mut pmls = self
+            .pmls
+            .iter()
+            .filter(|snapshot| snapshot.aabb.contains_local_point(&aabb_point))
+            .filter_map(|snapshot| {
+                let pml = &snapshot.pml;
+                let max_depth = nalgebra::distance(&snapshot.aabb.mins, &snapshot.aabb.maxs);
+
+                // cast in the PML's frame, see `materials_at`
+                let ray = Ray::new(
+                    snapshot.transform.precise_point_to_local(point),
+                    snapshot
+                        .transform
+                        .isometry()
+                        .inverse_transform_vector(&pml.normal),
+                );
+                let ray_intersection =
+                    snapshot
+                        .collider
+                        .cast_ray(&Isometry3::identity(), &ray, max_depth, false)?;
+
+                Some(PmlCoefficients::new_graded(
+                    resolution,
+                    physical_constants,
+                    pml.m,
+                    pml.m_a,
+                    pml.sigma_max,
+                    pml.kappa_max,
+                    pml.a_max,
+                    ray_intersection.time_of_impact as f64,
+                    -pml.normal.cast(),
+                ))
+            });
+
+        // todo: merge pmls present at this point
+        pmls.next()
+    }
+}
+
+/// Number of cells between progress reports of [`SceneDomainDescription`].
+const VOXELIZE_PROGRESS_INTERVAL: usize = 0x10000;
+
+/// Rasterizes a [`DomainSnapshot`] into the lattice, reporting progress to the
+/// job it runs in.
+struct SceneDomainDescription<'a> {
+    domain: DomainSnapshot,
     coordinate_transformations: CoordinateTransformations,
     default_material: Material,
+    ground_planes: GroundPlanes,
+    parameters: ParameterValues,
     // todo: the solver knows these two so the pml parameters it takes should not need them
     resolution: Resolution,
     physical_constants: PhysicalConstants,
+    job: &'a JobContext,
+    num_cells: usize,
+    num_done: usize,
+
+    /// Once the job is cancelled the remaining cells are filled with the
+    /// default material, so the backend returns quickly.
+    cancelled: bool,
 }
 
-impl<'w, 's> DomainDescription<Point3<usize>> for WorldDomainDescription<'w, 's> {
+impl<'a> DomainDescription<Point3<usize>> for SceneDomainDescription<'a> {
     fn material(&mut self, point: &Point3<usize>) -> Material {
+        self.num_done += 1;
+        if self.num_done.is_multiple_of(VOXELIZE_PROGRESS_INTERVAL) {
+            self.job
+                .set_progress(self.num_done as f32 / self.num_cells as f32);
+            self.cancelled = self.job.is_cancelled();
+        }
+        if self.cancelled {
+            return self.default_material;
+        }
+
         let point = self
             .coordinate_transformations
             .transform_point_from_solver_to_world(point);
 
-        let mut materials = self
-            .system_param
-            .point_query
-            .point_query(point)
-            .filter_map(|entity| self.system_param.materials.get(entity).ok())
-            .cloned();
+        // ground planes are conductors that win over anything else
+        if let Some(material) = self.ground_planes.material(&point) {
+            return material;
+        }
 
-        // for now we'll just use the first material we find.
         // if nothing is found, use the default
-        materials.next().unwrap_or(self.default_material)
+        self.domain
+            .material_at(&self.parameters, &point)
+            .unwrap_or(self.default_material)
     }
 
     fn pml(&mut self, point: &Point3<usize>) -> Option<PmlCoefficients> {
+        if self.cancelled {
+            return None;
+        }
+
         let point = self
             .coordinate_transformations
             .transform_point_from_solver_to_world(point);
 
-        let mut pmls = self
-            .system_param
-            .intersect_aabb_query
-            .intersect_aabb(Aabb {
-                mins: point,
-                maxs: point,
-            })
-            .filter_map(|(entity, aabb)| {
-                self.system_param.pmls.get(entity).ok().and_then(
-                    |(pml, collider, global_transform)| {
-                        let max_depth = nalgebra::distance(&aabb.mins, &aabb.maxs);
-                        let ray = Ray::new(point, *pml.normal);
-
-                        let ray_intersection = collider.cast_ray(
-                            global_transform.isometry(),
-                            &ray,
-                            max_depth,
-                            false,
-                        )?;
-
-                        Some(PmlCoefficients::new_graded(
-                            &self.resolution,
-                            &self.physical_constants,
-                            pml.m,
-                            pml.m_a,
-                            pml.sigma_max,
-                            pml.kappa_max,
-                            pml.a_max,
-                            ray_intersection.time_of_impact as f64,
-                            -pml.normal.cast(),
-                        ))
-                    },
-                )
-            });
-
-        // todo: merge pmls present at this point
-        pmls.next()
+        self.domain
+            .pml_at(&self.resolution, &self.physical_constants, &point)
     }
 }
 
@@ -679,39 +2003,31 @@ impl<'w, 's> DomainDescription<Point3<usize>> for WorldDomainDescription<'w, 's>
 struct Observers<P> {
     projections: Vec<P>,
     repaint_trigger: Option<RepaintTrigger>,
-}
+    slices: ObserverSlices,
 
-impl<P> Observers<P> {
-    pub fn from_scene<I>(
-        instance: &I,
-        state: &mut I::State,
-        world: &mut World,
-        lattice_size: &Vector3<usize>,
-        repaint_trigger: RepaintTrigger,
-    ) -> Self
-    where
-        I: CreateProjection<TextureSenderTarget, Projection = P> + 'static,
-        I::State: 'static,
-        P: 'static,
-        for<'a> <I as BeginProjectionPass>::ProjectionPass<'a>: ProjectionPassAdd<'a, P>,
-    {
-        // todo:
-        // - derive projection from observer and transform
-        // - transform projection into simulation coordinate space
+    /// Index of the projection for each of the `slices`.
+    slice_projections: Vec<usize>,
 
-        world
-            .run_system_cached_with(
-                setup_observers_system::<I, P>,
-                (instance, state, *lattice_size, repaint_trigger),
-            )
-            .unwrap()
-    }
+    histories: Vec<ObserverHistory>,
+}
 
+impl<P> Observers<P> {
     pub fn run<I>(&mut self, instance: &I, state: &I::State) -> Result<(), Error>
     where
         I: BeginProjectionPass,
+        I::State: Time,
         for<'a> <I as BeginProjectionPass>::ProjectionPass<'a>: ProjectionPassAdd<'a, P>,
+        P: SetProjectionTransform,
     {
+        for (index, slice) in self.slices.take_changed() {
+            self.projections[self.slice_projections[index]]
+                .set_projection_transform(slice.projection());
+        }
+
+        for history in &self.histories {
+            history.begin_observation(state.tick(), state.time());
+        }
+
         let mut pass = instance.begin_projection_pass(state);
 
         for projection in &mut self.projections {
@@ -728,56 +2044,102 @@ impl<P> Observers<P> {
     }
 }
 
-#[allow(clippy::type_complexity)]
-fn setup_observers_system<I, P>(
-    (InRef(instance), InMut(state), In(lattice_size), In(repaint_trigger)): (
-        InRef<I>,
-        InMut<I::State>,
+/// Targets of the observers' projections. These are set up before the solver
+/// instance exists, which then creates the projections.
+#[derive(Debug, Default)]
+struct ObserverTargets {
+    targets: Vec<(TextureSenderTarget, ProjectionParameters)>,
+    repaint_trigger: Option<RepaintTrigger>,
+    slices: ObserverSlices,
+
+    /// Index of the target for each of the `slices`.
+    slice_projections: Vec<usize>,
+
+    histories: Vec<ObserverHistory>,
+}
+
+impl ObserverTargets {
+    pub fn from_scene(
+        world: &mut World,
+        lattice_size: &Vector3<usize>,
+        repaint_trigger: RepaintTrigger,
+        stream: Option<StreamPublisher>,
+        run_log: Option<RunLogSnapshots>,
+    ) -> Self {
+        // todo:
+        // - derive projection from observer and transform
+        // - transform projection into simulation coordinate space
+
+        world
+            .run_system_cached_with(
+                setup_observers_system,
+                (*lattice_size, repaint_trigger, stream, run_log),
+            )
+            .unwrap()
+    }
+
+    pub fn create_projections<I, P>(self, instance: &I, state: &I::State) -> Observers<P>
+    where
+        I: CreateProjection<TextureSenderTarget, Projection = P>,
+    {
+        let projections = self
+            .targets
+            .into_iter()
+            .map(|(target, parameters)| instance.create_projection(state, target, &parameters))
+            .collect();
+
+        Observers {
+            projections,
+            repaint_trigger: self.repaint_trigger,
+            slices: self.slices,
+            slice_projections: self.slice_projections,
+            histories: self.histories,
+        }
+    }
+}
+
+fn setup_observers_system(
+    (In(lattice_size), In(repaint_trigger), In(stream), In(run_log)): (
         In<Vector3<usize>>,
         In<RepaintTrigger>,
+        In<Option<StreamPublisher>>,
+        In<Option<RunLogSnapshots>>,
     ),
     mut render_resource_manager: RenderResourceManager,
-    observers: Query<(Entity, &Observer)>,
+    observers: Query<(Entity, &Observer, NameOrStableId)>,
     mut commands: Commands,
-) -> Observers<P>
-where
-    I: CreateProjection<TextureSenderTarget, Projection = P>,
-    for<'a> <I as BeginProjectionPass>::ProjectionPass<'a>: ProjectionPassAdd<'a, P>,
-{
+) -> ObserverTargets {
     let mut needs_repaint = false;
+    let mut num_streams = 0;
+    let mut num_projections = 0;
+    let slices = ObserverSlices::default();
+    let mut slice_projections = vec![];
+    let mut histories = vec![];
 
-    let projections = observers
+    let targets = observers
         .iter()
-        .filter_map(|(entity, observer)| {
+        .filter_map(|(entity, observer, name)| {
             tracing::debug!(?observer, "creating observer");
 
             observer.display_as_texture.then(|| {
                 needs_repaint = true;
 
+                let index = num_projections;
+                if let Some(slice) = observer.slice {
+                    slices.push(name.to_string(), slice);
+                    slice_projections.push(index);
+                }
+                num_projections += 1;
+
                 let parameters = ProjectionParameters {
-                    projection: Matrix4::identity(), // todo
+                    // todo: derive the plane from the observer's transform
+                    projection: observer
+                        .slice
+                        .map_or_else(Matrix4::identity, |slice| slice.projection()),
                     field: observer.field,
                     color_map: observer.color_map,
-                    color_map_code: Some(
-                        r#"
-                    // color and alpha scaling
-                    const s_c: f32 = 10.0;
-                    const s_a: f32 = 100.0;
-
-                    var color: vec4f;
-                    var x = value.z;
-                    if x > 0.0 {
-                        color.r = min(s_c * x, 1.0);
-                        color.a = min(s_a * x, 1.0);
-                    }
-                    else {
-                        color.g = min(-s_c * x, 1.0);
-                        color.a = min(-s_a * x, 1.0);
-                    }
-                    return color;
-                    "#
-                        .to_owned(),
-                    ),
+                    color_map_code: Some(field_color_map_code(color_scheme::palette())),
+                    post_process_code: observer.post_process.clone(),
                 };
 
                 // create a texture channel. the sender is still undecided whether it
@@ -788,8 +2150,11 @@ where
                 // todo: can we make so that the RENDER_ATTACHMENT usage is only applied
                 // if a texture for rendering is requested
                 // by the backend? and likewise for COPY_DST
+                let image_size = observer
+                    .slice
+                    .map_or(lattice_size.xy(), |slice| slice.image_size(&lattice_size));
                 let (sender, receiver) = render_resource_manager.create_texture_channel(
-                    &lattice_size.xy().cast(),
+                    &image_size.cast(),
                     wgpu::TextureUsages::RENDER_ATTACHMENT
                         | wgpu::TextureUsages::TEXTURE_BINDING
                         | wgpu::TextureUsages::COPY_DST,
@@ -806,66 +2171,187 @@ where
                     },
                 ));
 
-                instance.create_projection(state, TextureSenderTarget::from(sender), &parameters)
+                // observers are numbered in the order they're created, so the monitor can tell
+                // them apart.
+                let stream = stream.as_ref().map(|publisher| {
+                    let observer_stream = ObserverStream {
+                        publisher: publisher.clone(),
+                        observer: num_streams,
+                        label: name.to_string(),
+                    };
+                    num_streams += 1;
+                    observer_stream
+                });
+
+                let history = observer.history_budget.map(|budget| {
+                    let history = ObserverHistory::new(name.to_string(), budget);
+                    histories.push(history.clone());
+                    history
+                });
+
+                let target = TextureSenderTarget {
+                    texture_sender: sender,
+                    stream,
+                    run_log: run_log
+                        .as_ref()
+                        .map(|run_log| run_log.for_observer(index, name.to_string())),
+                    history,
+                };
+
+                (target, parameters)
             })
         })
         .collect();
 
-    Observers {
-        projections,
+    ObserverTargets {
+        targets,
         repaint_trigger: needs_repaint.then_some(repaint_trigger),
+        slices,
+        slice_projections,
+        histories,
     }
 }
 
 #[derive(Debug, Default)]
 struct Sources {
-    sources: Vec<(Point3<usize>, Source)>,
+    /// Lattice point, source and the name of its entity
+    sources: Vec<(Point3<usize>, Source, String)>,
+
+    /// Amplitudes set by scheduled events, by the name of the sources.
+    amplitudes: HashMap<String, f64>,
 }
 
 impl Sources {
     pub fn from_scene(
         world: &mut World,
+        config: &FdtdSolverConfig,
         coordinate_transformations: &CoordinateTransformations,
+        disabled_ports: &BTreeSet<usize>,
     ) -> Self {
         world
-            .run_system_cached_with(setup_sources_system, coordinate_transformations)
+            .run_system_cached_with(
+                setup_sources_system,
+                (config, coordinate_transformations, disabled_ports),
+            )
             .unwrap()
     }
 
-    pub fn push(&mut self, point: Point3<usize>, source: impl Into<Source>) {
+    pub fn push(&mut self, point: Point3<usize>, source: impl Into<Source>, name: String) {
         let source = source.into();
         tracing::debug!(?point, ?source, "creating source");
-        self.sources.push((point, source));
+        self.sources.push((point, source, name));
+    }
+
+    /// Scales the sources named `name`, see
+    /// [`EventAction::SetSourceAmplitude`].
+    pub fn set_amplitude(&mut self, name: &str, amplitude: f64) {
+        if !self
+            .sources
+            .iter()
+            .any(|(_, _, source_name)| source_name == name)
+        {
+            tracing::warn!(name, "no source to set the amplitude of");
+        }
+        self.amplitudes.insert(name.to_owned(), amplitude);
     }
 
     pub fn apply<UpdatePass>(&self, time: f64, update_pass: &mut UpdatePass)
     where
         UpdatePass: UpdatePassForcing<Point3<usize>>,
     {
-        for (point, source) in &self.sources {
-            let values = source.0.evaluate(time);
+        for (point, source, name) in &self.sources {
+            let mut values = source.0.evaluate(time);
+            if let Some(amplitude) = self.amplitudes.get(name) {
+                values.j *= *amplitude;
+                values.m *= *amplitude;
+            }
             update_pass.set_forcing(point, &values);
         }
     }
 }
 
 fn setup_sources_system(
-    InRef(coordinate_transformations): InRef<CoordinateTransformations>,
-    sources: Query<(&GlobalTransform, &Source)>,
+    (InRef(config), InRef(coordinate_transformations), InRef(disabled_ports)): (
+        InRef<FdtdSolverConfig>,
+        InRef<CoordinateTransformations>,
+        InRef<BTreeSet<usize>>,
+    ),
+    sources: Query<(
+        &GlobalTransform,
+        &Source,
+        Option<&Excitation>,
+        NameOrStableId,
+    )>,
+    feeds: Query<(
+        &GlobalTransform,
+        &Feed,
+        Option<&Port>,
+        Option<&Excitation>,
+        NameOrStableId,
+    )>,
 ) -> Sources {
-    let sources = sources
-        .iter()
-        .filter_map(|(global_transform, source)| {
-            let world_point = global_transform.position();
-            let sim_point =
-                coordinate_transformations.transform_point_from_world_to_solver(&world_point)?;
-            tracing::debug!(?world_point, ?sim_point, ?source, "creating source");
+    let mut sources = Sources {
+        sources: sources
+            .iter()
+            .filter_map(|(global_transform, source, excitation, name)| {
+                let world_point = global_transform.precise_position();
+                let sim_point = coordinate_transformations
+                    .transform_point_from_world_to_solver(&world_point)?;
+                tracing::debug!(?world_point, ?sim_point, ?source, "creating source");
+
+                let source = excitation.map_or_else(
+                    || source.clone(),
+                    |excitation| excitation.apply_to_source(source),
+                );
+                Some((sim_point, source, name.to_string()))
+            })
+            .collect(),
+        amplitudes: Default::default(),
+    };
+
+    for (global_transform, feed, port, excitation, name) in &feeds {
+        // feeds of disabled ports are left out
+        if port.is_some_and(|port| disabled_ports.contains(&port.number)) {
+            continue;
+        }
 
-            Some((sim_point, source.clone()))
-        })
-        .collect();
+        let feed =
+            excitation.map_or_else(|| feed.clone(), |excitation| excitation.apply_to_feed(feed));
+
+        let isometry = global_transform.precise_isometry();
+
+        for element in feed
+            .kind
+            .expand(&config.resolution.spatial, &config.physical_constants)
+        {
+            let world_point = isometry * element.position;
+            let Some(sim_point) =
+                coordinate_transformations.transform_point_from_world_to_solver(&world_point)
+            else {
+                continue;
+            };
+
+            let transform_vector = |vector: &Vector3<f64>| {
+                coordinate_transformations
+                    .transform_vector_from_world_to_solver(&isometry.transform_vector(vector))
+            };
+            let amplitude = SourceValues {
+                j: transform_vector(&element.amplitudes.j),
+                m: transform_vector(&element.amplitudes.m),
+            };
+
+            sources.push(
+                sim_point,
+                WithAmplitudes {
+                    amplitude,
+                    inner: feed.waveform.clone(),
+                },
+                name.to_string(),
+            );
+        }
+    }
 
-    Sources { sources }
+    sources
 }
 
 /// TODO: This should be created by the backend and probably be a trait
@@ -873,6 +2359,7 @@ fn setup_sources_system(
 pub struct CoordinateTransformations {
     pub transform_from_solver_to_world: Matrix4<f64>,
     pub transform_from_world_to_solver: Matrix4<f64>,
+    pub rotation_from_solver_to_world: UnitQuaternion<f64>,
     pub lattice_size: Vector3<usize>,
 }
 
@@ -902,32 +2389,56 @@ impl CoordinateTransformations {
         Self {
             transform_from_solver_to_world,
             transform_from_world_to_solver,
+            rotation_from_solver_to_world,
             lattice_size: *lattice_size,
         }
     }
 
-    pub fn transform_point_from_solver_to_world(&self, point: &Point3<usize>) -> Point3<f32> {
+    /// Position of a lattice point in the world.
+    ///
+    /// This is in double precision, like
+    /// [`transform_point_from_world_to_solver`](Self::transform_point_from_world_to_solver).
+    /// Use [`GlobalTransform::precise_point_to_local`] to test it against an
+    /// object's collider.
+    pub fn transform_point_from_solver_to_world(&self, point: &Point3<usize>) -> Point3<f64> {
         Point3::from_homogeneous(
             self.transform_from_solver_to_world * point.cast::<f64>().to_homogeneous(),
         )
         .unwrap()
-        .coords
-        .try_cast::<f32>()
-        .unwrap()
-        .into()
     }
 
+    /// Lattice point closest to a point in the world.
+    ///
+    /// This takes the point in double precision (e.g. from
+    /// [`GlobalTransform::precise_position`]), so that electrically large
+    /// scenes don't suffer from rounding errors.
     pub fn transform_point_from_world_to_solver(
         &self,
-        point: &Point3<f32>,
+        point: &Point3<f64>,
     ) -> Option<Point3<usize>> {
-        let point = Point3::from_homogeneous(
-            self.transform_from_world_to_solver * point.cast::<f64>().to_homogeneous(),
-        )
-        .unwrap();
+        let point =
+            Point3::from_homogeneous(self.transform_from_world_to_solver * point.to_homogeneous())
+                .unwrap();
         let point = Point3::from(point.coords.map(|c| c.round()).try_cast::<usize>()?);
         (point.coords < self.lattice_size).then_some(point)
     }
+
+    /// Size of a cell in world units.
+    pub fn cell_size(&self) -> Vector3<f64> {
+        Vector3::from_fn(|i, _| {
+            self.transform_from_solver_to_world
+                .fixed_view::<3, 1>(0, i)
+                .norm()
+        })
+    }
+
+    /// Rotates a direction (e.g. a current density) into the solver's frame.
+    ///
+    /// Unlike points, vectors are not scaled by the spatial resolution.
+    pub fn transform_vector_from_world_to_solver(&self, vector: &Vector3<f64>) -> Vector3<f64> {
+        self.rotation_from_solver_to_world
+            .inverse_transform_vector(vector)
+    }
 }
 
 pub fn evaluate_stop_condition<S>(
@@ -0,0 +1,595 @@
+//! Sequence runs.
+//!
+//! A sequence run steps a scene parameter through a range of values and runs
+//! the solver once for every value. Geometry with a
+//! [`ParametricMotion`](crate::solver::motion::ParametricMotion) is moved for
+//! each step, so this approximates slowly moving structures, like rotating
+//! reflectors or repositioned probes, by a series of quasi-static solves.
+//!
+//! The probe outputs of every step are kept, and summarized over the
+//! parameter: the reflection at each port at one frequency, and the peak gain
+//! of each NF2FF box.
+
+use std::collections::VecDeque;
+
+use cem_scene::Scene;
+use num::complex::Complex64;
+
+use crate::{
+    Error,
+    clipboard::copy_image_or_csv_context_menu,
+    composer::Composers,
+    error::ResultExt,
+    jobs::{
+        Cancelled,
+        JobHandle,
+        Jobs,
+    },
+    solver::{
+        config::{
+            SolverConfig,
+            SolverConfigSpecifics,
+            StopCondition,
+        },
+        network::to_db,
+        parameters::SceneParameters,
+        probe::ProbeOutputs,
+        runner::SolverRunner,
+        tuning::frequency_drag_value,
+    },
+};
+
+/// Results of one step of a sequence run.
+#[derive(Clone, Debug)]
+pub struct SequenceStep {
+    /// Parameter value of the step
+    pub value: f64,
+
+    pub outputs: ProbeOutputs,
+
+    /// Reflection at each port at the sequence's frequency
+    pub reflections: Vec<(String, egui::Color32, Option<Complex64>)>,
+
+    /// Peak gain of each NF2FF box in dBi
+    pub peak_gains: Vec<(String, Option<f64>)>,
+}
+
+impl SequenceStep {
+    /// Summarizes the outputs of a finished run. `frequency` is in Hz.
+    ///
+    /// This integrates the far field of every NF2FF box, so the sequence run
+    /// calls it from a job.
+    pub fn new(value: f64, outputs: ProbeOutputs, frequency: f64) -> Self {
+        let reflections = outputs
+            .ports
+            .iter()
+            .map(|(name, output)| {
+                let reflection = output
+                    .reflection(name.clone(), vec![frequency])
+                    .and_then(|reflection| reflection.value_at(frequency));
+                (name.clone(), output.color(), reflection)
+            })
+            .collect();
+
+        let peak_gains = outputs
+            .nf2ff_boxes
+            .iter()
+            .map(|(name, output)| {
+                let gain = output.pattern(name.clone()).map(|pattern| {
+                    let (_, _, gain) = pattern.max_gain();
+                    10.0 * gain.log10()
+                });
+                (name.clone(), gain)
+            })
+            .collect();
+
+        Self {
+            value,
+            outputs,
+            reflections,
+            peak_gains,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SequenceRun {
+    pub open: bool,
+
+    /// Index of the solver config the steps are run with
+    solver_config: usize,
+
+    parameter: Option<String>,
+    start: f64,
+    end: f64,
+    num_steps: usize,
+
+    /// Frequency the port reflections are evaluated at, in Hz
+    frequency: f64,
+
+    /// Parameter values that are still to be run
+    pending: VecDeque<f64>,
+
+    /// Parameter value of the step that is running
+    running: Option<f64>,
+
+    /// Job summarizing the running step, once its solver finished
+    summary: Option<JobHandle<SequenceStep>>,
+
+    steps: Vec<SequenceStep>,
+}
+
+impl Default for SequenceRun {
+    fn default() -> Self {
+        Self {
+            open: false,
+            solver_config: 0,
+            parameter: None,
+            start: 0.0,
+            end: 360.0,
+            num_steps: 12,
+            frequency: 2.45e9,
+            pending: VecDeque::new(),
+            running: None,
+            summary: None,
+            steps: vec![],
+        }
+    }
+}
+
+impl SequenceRun {
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Parameter values of all steps. Both ends of the range are included.
+    pub fn values(&self) -> Vec<f64> {
+        match self.num_steps {
+            0 => vec![],
+            1 => vec![self.start],
+            n => {
+                (0..n)
+                    .map(|i| self.start + (self.end - self.start) * i as f64 / (n - 1) as f64)
+                    .collect()
+            }
+        }
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        composers: &mut Composers,
+        solver_runner: &mut SolverRunner,
+    ) {
+        // the sequence continues while the window is closed
+        self.advance(ctx, composers, solver_runner)
+            .ok_or_handle(ctx);
+
+        let mut open = self.open;
+
+        egui::Window::new("Sequence Run")
+            .movable(true)
+            .default_size([400.0, 450.0])
+            .open(&mut open)
+            .show(ctx, |ui| {
+                self.setup_ui(ui, composers, solver_runner);
+
+                ui.separator();
+
+                if self.steps.is_empty() {
+                    ui.weak("No results yet");
+                }
+                else {
+                    self.results_ui(ui);
+                }
+            });
+
+        self.open = open;
+    }
+
+    fn setup_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        composers: &mut Composers,
+        solver_runner: &mut SolverRunner,
+    ) {
+        let Some((scene, solver_configs)) = composers.active_scene_mut()
+        else {
+            ui.weak("No scene open");
+            return;
+        };
+
+        if solver_configs.is_empty() {
+            ui.weak("Configure a solver first");
+            return;
+        }
+        self.solver_config = self.solver_config.min(solver_configs.len() - 1);
+
+        ui.add_enabled_ui(!self.is_running(), |ui| {
+            egui::Grid::new("sequence_setup")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Solver");
+                    egui::ComboBox::from_id_salt("solver_config")
+                        .selected_text(&solver_configs[self.solver_config].label)
+                        .show_ui(ui, |ui| {
+                            for (i, solver_config) in solver_configs.iter().enumerate() {
+                                ui.selectable_value(
+                                    &mut self.solver_config,
+                                    i,
+                                    &solver_config.label,
+                                );
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Parameter");
+                    let parameters = SceneParameters::get(&scene.world);
+                    egui::ComboBox::from_id_salt("parameter")
+                        .selected_text(self.parameter.as_deref().unwrap_or("None"))
+                        .show_ui(ui, |ui| {
+                            for name in parameters.keys() {
+                                if ui
+                                    .selectable_label(self.parameter.as_ref() == Some(name), name)
+                                    .clicked()
+                                {
+                                    self.parameter = Some(name.clone());
+                                }
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Range");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut self.start).speed(0.1));
+                        ui.label("to");
+                        ui.add(egui::DragValue::new(&mut self.end).speed(0.1));
+                    });
+                    ui.end_row();
+
+                    ui.label("Steps");
+                    ui.add(egui::DragValue::new(&mut self.num_steps).range(1..=10000));
+                    ui.end_row();
+
+                    ui.label("Frequency");
+                    frequency_drag_value(ui, &mut self.frequency)
+                        .on_hover_text("The port reflections are evaluated at this frequency");
+                    ui.end_row();
+                });
+        });
+
+        if self.parameter.is_none() {
+            ui.weak("Add a parameter in View > Parameters and select it here.");
+        }
+
+        // every step has to finish on its own
+        let runs_forever = runs_forever(&solver_configs[self.solver_config]);
+        if runs_forever {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "The solver has no stop condition, so the steps would never finish.",
+            );
+        }
+
+        let mut start = false;
+        ui.horizontal(|ui| {
+            if let Some(value) = self.running {
+                let step = self.steps.len() + 1;
+                let num_steps = step + self.pending.len();
+                ui.spinner();
+                ui.label(format!("Step {step} of {num_steps} (x = {value})"));
+                if ui.button("Cancel").clicked() {
+                    self.cancel(solver_runner);
+                }
+            }
+            else if ui
+                .add_enabled(
+                    self.parameter.is_some() && !runs_forever,
+                    egui::Button::new("Start"),
+                )
+                .on_hover_text("Run the solver once for every parameter value")
+                .clicked()
+            {
+                start = true;
+            }
+        });
+
+        if start {
+            self.steps.clear();
+            self.pending = self.values().into();
+            self.start_next(composers, solver_runner)
+                .ok_or_handle(ui.ctx());
+        }
+    }
+
+    /// Summarizes the results of the running step once it finished, and starts
+    /// the next one once that's done.
+    fn advance(
+        &mut self,
+        ctx: &egui::Context,
+        composers: &mut Composers,
+        solver_runner: &mut SolverRunner,
+    ) -> Result<(), Error> {
+        let Some(value) = self.running
+        else {
+            return Ok(());
+        };
+
+        if let Some(summary) = &self.summary {
+            let Some(result) = summary.try_take_result()
+            else {
+                return Ok(());
+            };
+            self.summary = None;
+            self.running = None;
+
+            match result {
+                Ok(step) => self.steps.push(step),
+                Err(error) => {
+                    self.pending.clear();
+                    if error.is::<Cancelled>() {
+                        return Ok(());
+                    }
+                    return Err(error);
+                }
+            }
+
+            return self.start_next(composers, solver_runner);
+        }
+
+        let outputs = match step_status(solver_runner) {
+            StepStatus::Running => return Ok(()),
+            StepStatus::Finished(outputs) => outputs,
+            StepStatus::Closed => {
+                self.pending.clear();
+                self.running = None;
+                return Ok(());
+            }
+        };
+
+        let frequency = self.frequency;
+        self.summary = Some(
+            Jobs::from_ctx(ctx).spawn("Summarize sequence step", move |_job| {
+                Ok(SequenceStep::new(value, outputs, frequency))
+            }),
+        );
+
+        Ok(())
+    }
+
+    fn start_next(
+        &mut self,
+        composers: &mut Composers,
+        solver_runner: &mut SolverRunner,
+    ) -> Result<(), Error> {
+        let (Some(parameter), Some(value)) = (&self.parameter, self.pending.pop_front())
+        else {
+            return Ok(());
+        };
+
+        let Some((scene, solver_configs)) = composers.active_scene_mut()
+        else {
+            self.pending.clear();
+            return Ok(());
+        };
+        let Some(solver_config) = solver_configs.get(self.solver_config)
+        else {
+            self.pending.clear();
+            return Ok(());
+        };
+
+        let mut solver_config = solver_config.clone();
+        solver_config
+            .common
+            .parameter_overrides
+            .insert(parameter.clone(), value);
+
+        tracing::debug!(%parameter, value, "starting sequence step");
+
+        if let Err(error) = start_step(solver_runner, scene, &solver_config) {
+            self.pending.clear();
+            return Err(error);
+        }
+        self.running = Some(value);
+
+        Ok(())
+    }
+
+    fn cancel(&mut self, solver_runner: &mut SolverRunner) {
+        self.pending.clear();
+        if let Some(summary) = self.summary.take() {
+            summary.cancel();
+        }
+        if self.running.take().is_some() {
+            solver_runner.stop();
+        }
+    }
+
+    fn results_ui(&self, ui: &mut egui::Ui) {
+        let parameter = self.parameter.as_deref().unwrap_or("x");
+
+        let reflection_lines = series(&self.steps, |step| {
+            step.reflections
+                .iter()
+                .map(|(name, color, reflection)| {
+                    (
+                        name.clone(),
+                        Some(*color),
+                        reflection.map(|reflection| to_db(reflection.norm())),
+                    )
+                })
+                .collect()
+        });
+        let gain_lines = series(&self.steps, |step| {
+            step.peak_gains
+                .iter()
+                .map(|(name, gain)| (name.clone(), None, *gain))
+                .collect()
+        });
+
+        if !reflection_lines.is_empty() {
+            ui.label(format!("Reflection at {:.3} GHz", self.frequency * 1e-9));
+            self.plot(
+                ui,
+                "sequence_reflection",
+                parameter,
+                "|S11| [dB]",
+                &reflection_lines,
+            );
+        }
+
+        if !gain_lines.is_empty() {
+            ui.label("Peak gain");
+            self.plot(ui, "sequence_gain", parameter, "Gain [dBi]", &gain_lines);
+        }
+
+        if reflection_lines.is_empty() && gain_lines.is_empty() {
+            ui.weak(format!(
+                "{} steps finished. Add ports or NF2FF boxes to the scene to see how their results change.",
+                self.steps.len()
+            ));
+        }
+    }
+
+    fn plot(&self, ui: &mut egui::Ui, id: &str, parameter: &str, y_label: &str, lines: &[Series]) {
+        let response = egui_plot::Plot::new(id)
+            .height(150.0)
+            .x_axis_label(parameter)
+            .y_axis_label(y_label)
+            .legend(egui_plot::Legend::default())
+            .show(ui, |plot_ui| {
+                for line in lines {
+                    let mut plot_line =
+                        egui_plot::Line::new(line.name.clone(), line.points.clone());
+                    if let Some(color) = line.color {
+                        plot_line = plot_line.color(color);
+                    }
+                    plot_ui.line(plot_line);
+                }
+            })
+            .response;
+
+        copy_image_or_csv_context_menu(&response, || {
+            let mut csv = parameter.to_owned();
+            for line in lines {
+                csv.push_str(&format!(",{}", line.name));
+            }
+            csv.push('\n');
+
+            for step in &self.steps {
+                csv.push_str(&step.value.to_string());
+                for line in lines {
+                    csv.push(',');
+                    if let Some([_, y]) = line.points.iter().find(|[x, _]| *x == step.value) {
+                        csv.push_str(&y.to_string());
+                    }
+                }
+                csv.push('\n');
+            }
+            csv
+        });
+    }
+}
+
+/// Whether runs with `solver_config` never finish on their own, so they can't
+/// be steps of a sequence.
+pub(super) fn runs_forever(solver_config: &SolverConfig) -> bool {
+    matches!(
+        &solver_config.specifics,
+        SolverConfigSpecifics::Fdtd(fdtd_config)
+            if matches!(fdtd_config.stop_condition, StopCondition::Never)
+    )
+}
+
+/// Starts a run as one step of a sequence. Steps run at full speed.
+pub(super) fn start_step(
+    solver_runner: &mut SolverRunner,
+    scene: &mut Scene,
+    solver_config: &SolverConfig,
+) -> Result<(), Error> {
+    solver_runner.stop();
+    solver_runner.run(solver_config, scene)?;
+    solver_runner.run_at_full_speed();
+
+    Ok(())
+}
+
+/// State of a step started with [`start_step`].
+pub(super) enum StepStatus {
+    Running,
+    Finished(ProbeOutputs),
+
+    /// The solver was closed by the user
+    Closed,
+}
+
+pub(super) fn step_status(solver_runner: &SolverRunner) -> StepStatus {
+    match solver_runner.active_solver() {
+        Some(solver) if solver.state().finished => {
+            StepStatus::Finished(solver.probe_outputs().clone())
+        }
+        Some(_) => StepStatus::Running,
+        None if solver_runner.is_preparing() => StepStatus::Running,
+        None => StepStatus::Closed,
+    }
+}
+
+/// A quantity over the parameter, e.g. the reflection at one port.
+#[derive(Clone, Debug)]
+struct Series {
+    name: String,
+    color: Option<egui::Color32>,
+    points: Vec<[f64; 2]>,
+}
+
+/// Collects the values `f` picks from every step into one series per name.
+/// Steps where a value is missing leave a gap.
+fn series<F>(steps: &[SequenceStep], mut f: F) -> Vec<Series>
+where
+    F: FnMut(&SequenceStep) -> Vec<(String, Option<egui::Color32>, Option<f64>)>,
+{
+    let mut series = Vec::<Series>::new();
+
+    for step in steps {
+        for (name, color, value) in f(step) {
+            let index = series
+                .iter()
+                .position(|series| series.name == name)
+                .unwrap_or_else(|| {
+                    series.push(Series {
+                        name,
+                        color,
+                        points: vec![],
+                    });
+                    series.len() - 1
+                });
+            if let Some(value) = value {
+                series[index].points.push([step.value, value]);
+            }
+        }
+    }
+
+    series
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::sequence::SequenceRun;
+
+    #[test]
+    fn values_include_both_ends() {
+        let sequence = SequenceRun {
+            start: 0.0,
+            end: 90.0,
+            num_steps: 4,
+            ..Default::default()
+        };
+        assert_eq!(sequence.values(), vec![0.0, 30.0, 60.0, 90.0]);
+
+        let single = SequenceRun {
+            num_steps: 1,
+            ..sequence
+        };
+        assert_eq!(single.values(), vec![0.0]);
+    }
+}
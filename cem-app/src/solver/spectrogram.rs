@@ -0,0 +1,379 @@
+//! Short-time Fourier transform of probe signals.
+
+use std::f64::consts::PI;
+
+use cem_probe::{
+    PropertiesUi,
+    TrackChanges,
+};
+use cem_solver::material::UnitSystem;
+use colorgrad::Gradient;
+use num::complex::Complex64;
+
+use crate::{
+    clipboard::copy_image_or_csv_context_menu,
+    color_scheme,
+};
+
+/// Window applied to each frame before the transform.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WindowFunction {
+    Rectangular,
+    #[default]
+    Hann,
+    Hamming,
+}
+
+impl WindowFunction {
+    pub const ALL: [Self; 3] = [Self::Rectangular, Self::Hann, Self::Hamming];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WindowFunction::Rectangular => "Rectangular",
+            WindowFunction::Hann => "Hann",
+            WindowFunction::Hamming => "Hamming",
+        }
+    }
+
+    fn coefficients(&self, size: usize) -> Vec<f64> {
+        let n = (size.max(2) - 1) as f64;
+        (0..size)
+            .map(|i| {
+                let phase = 2.0 * PI * i as f64 / n;
+                match self {
+                    WindowFunction::Rectangular => 1.0,
+                    WindowFunction::Hann => 0.5 - 0.5 * phase.cos(),
+                    WindowFunction::Hamming => 0.54 - 0.46 * phase.cos(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpectrogramConfig {
+    /// Number of samples per frame. Must be a power of two.
+    pub window_size: usize,
+
+    /// Fraction of a frame that overlaps with the next one, in `[0, 1)`.
+    pub overlap: f64,
+
+    pub window: WindowFunction,
+
+    /// Lower bound of the color scale in dB relative to the peak
+    pub dynamic_range: f64,
+}
+
+impl Default for SpectrogramConfig {
+    fn default() -> Self {
+        Self {
+            window_size: 256,
+            overlap: 0.75,
+            window: WindowFunction::Hann,
+            dynamic_range: 60.0,
+        }
+    }
+}
+
+impl SpectrogramConfig {
+    fn hop_size(&self) -> usize {
+        ((self.window_size as f64 * (1.0 - self.overlap.clamp(0.0, 0.99))) as usize).max(1)
+    }
+}
+
+impl PropertiesUi for SpectrogramConfig {
+    type Config = ();
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
+        let _ = config;
+        let mut changes = TrackChanges::default();
+
+        let response = ui
+            .horizontal(|ui| {
+                let mut exponent = self.window_size.max(1).ilog2();
+                changes.track(
+                    ui.add(
+                        egui::DragValue::new(&mut exponent)
+                            .range(4..=14)
+                            .custom_formatter(|exponent, _| {
+                                format!("{}", 1usize << exponent as u32)
+                            })
+                            .prefix("window "),
+                    ),
+                );
+                self.window_size = 1 << exponent;
+
+                changes.track(
+                    ui.add(
+                        egui::DragValue::new(&mut self.overlap)
+                            .range(0.0..=0.95)
+                            .speed(0.01)
+                            .prefix("overlap "),
+                    ),
+                );
+
+                egui::ComboBox::from_id_salt(ui.id().with("window_function"))
+                    .selected_text(self.window.label())
+                    .show_ui(ui, |ui| {
+                        for window in WindowFunction::ALL {
+                            changes.track(ui.selectable_value(
+                                &mut self.window,
+                                window,
+                                window.label(),
+                            ));
+                        }
+                    });
+
+                changes.track(
+                    ui.add(
+                        egui::DragValue::new(&mut self.dynamic_range)
+                            .range(10.0..=200.0)
+                            .suffix(" dB"),
+                    ),
+                );
+            })
+            .response;
+
+        changes.propagated(response)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Spectrogram {
+    /// Center time of each frame
+    pub times: Vec<f64>,
+
+    /// Frequency of each bin
+    pub frequencies: Vec<f64>,
+
+    /// Magnitude in dB, indexed by `[frame][bin]`
+    pub magnitudes: Vec<Vec<f64>>,
+}
+
+impl Spectrogram {
+    /// Computes the spectrogram of a uniformly sampled signal.
+    ///
+    /// Returns `None` if the signal is shorter than one window.
+    pub fn compute(
+        start_time: f64,
+        sample_interval: f64,
+        signal: &[f64],
+        config: &SpectrogramConfig,
+    ) -> Option<Self> {
+        let window_size = config.window_size;
+        assert!(
+            window_size.is_power_of_two(),
+            "window size must be a power of two: {window_size}"
+        );
+        if signal.len() < window_size || sample_interval <= 0.0 {
+            return None;
+        }
+
+        let window = config.window.coefficients(window_size);
+        let window_sum = window.iter().sum::<f64>();
+        let num_bins = window_size / 2 + 1;
+
+        let mut buffer = vec![Complex64::default(); window_size];
+        let mut spectrogram = Self {
+            frequencies: (0..num_bins)
+                .map(|k| k as f64 / (window_size as f64 * sample_interval))
+                .collect(),
+            ..Default::default()
+        };
+
+        for start in (0..=signal.len() - window_size).step_by(config.hop_size()) {
+            for ((value, sample), coefficient) in buffer
+                .iter_mut()
+                .zip(&signal[start..start + window_size])
+                .zip(&window)
+            {
+                *value = Complex64::new(sample * coefficient, 0.0);
+            }
+
+            fft(&mut buffer);
+
+            spectrogram
+                .times
+                .push(start_time + (start as f64 + 0.5 * window_size as f64) * sample_interval);
+            spectrogram.magnitudes.push(
+                buffer[..num_bins]
+                    .iter()
+                    .map(|value| 20.0 * (value.norm() / window_sum).max(1e-30).log10())
+                    .collect(),
+            );
+        }
+
+        Some(spectrogram)
+    }
+
+    /// One row per frame, with the magnitudes in dB of all frequency bins.
+    pub fn to_csv(&self) -> String {
+        let mut csv = "time".to_owned();
+        for frequency in &self.frequencies {
+            csv.push_str(&format!(",{frequency}"));
+        }
+        csv.push('\n');
+
+        for (time, frame) in self.times.iter().zip(&self.magnitudes) {
+            csv.push_str(&time.to_string());
+            for magnitude in frame {
+                csv.push_str(&format!(",{magnitude}"));
+            }
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    /// Renders the spectrogram into an image with time along the x-axis and
+    /// frequency along the y-axis (low frequencies at the bottom).
+    pub fn to_image(&self, dynamic_range: f64) -> egui::ColorImage {
+        let width = self.times.len();
+        let height = self.frequencies.len();
+
+        let peak = self
+            .magnitudes
+            .iter()
+            .flatten()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        let gradient = color_scheme::palette().sequential();
+        let mut pixels = vec![egui::Color32::BLACK; width * height];
+
+        for (x, frame) in self.magnitudes.iter().enumerate() {
+            for (bin, magnitude) in frame.iter().enumerate() {
+                let t = 1.0 - ((peak - magnitude) / dynamic_range).clamp(0.0, 1.0);
+                let [r, g, b, _] = gradient.at(t as f32).to_rgba8();
+                let y = height - 1 - bin;
+                pixels[y * width + x] = egui::Color32::from_rgb(r, g, b);
+            }
+        }
+
+        egui::ColorImage::new([width, height], pixels)
+    }
+}
+
+/// In-place iterative radix-2 FFT.
+fn fft(buffer: &mut [Complex64]) {
+    let n = buffer.len();
+    debug_assert!(n.is_power_of_two());
+
+    // bit-reversal permutation
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            buffer.swap(i, j);
+        }
+    }
+
+    let mut length = 2;
+    while length <= n {
+        let twiddle = Complex64::from_polar(1.0, -2.0 * PI / length as f64);
+        for chunk in buffer.chunks_exact_mut(length) {
+            let (even, odd) = chunk.split_at_mut(length / 2);
+            let mut w = Complex64::new(1.0, 0.0);
+            for (a, b) in even.iter_mut().zip(odd) {
+                let t = w * *b;
+                *b = *a - t;
+                *a += t;
+                w *= twiddle;
+            }
+        }
+        length *= 2;
+    }
+}
+
+/// Shows a spectrogram as an image in a plot with time and frequency axes.
+pub fn spectrogram_plot(
+    ui: &mut egui::Ui,
+    id_salt: impl std::hash::Hash,
+    spectrogram: &Spectrogram,
+    dynamic_range: f64,
+    units: UnitSystem,
+) {
+    let (Some(t0), Some(t1), Some(f1)) = (
+        spectrogram.times.first(),
+        spectrogram.times.last(),
+        spectrogram.frequencies.last(),
+    )
+    else {
+        return;
+    };
+
+    let id = ui.id().with(&id_salt).with("texture");
+    let image = spectrogram.to_image(dynamic_range);
+
+    // keep the texture alive in egui's memory, so we only upload new data
+    let texture = ui.data_mut(|data| data.get_temp::<egui::TextureHandle>(id));
+    let texture = match texture {
+        Some(mut texture) => {
+            texture.set(image, egui::TextureOptions::NEAREST);
+            texture
+        }
+        None => {
+            let texture =
+                ui.ctx()
+                    .load_texture("spectrogram", image, egui::TextureOptions::NEAREST);
+            ui.data_mut(|data| data.insert_temp(id, texture.clone()));
+            texture
+        }
+    };
+
+    let duration = (t1 - t0).max(f64::EPSILON);
+    let response = egui_plot::Plot::new(id_salt)
+        .height(200.0)
+        .x_axis_label(format!("Time [{}]", units.time_unit()))
+        .y_axis_label(format!("Frequency [{}]", units.frequency_unit()))
+        .show(ui, |plot_ui| {
+            plot_ui.image(egui_plot::PlotImage::new(
+                "spectrogram",
+                &texture,
+                egui_plot::PlotPoint::new(0.5 * (t0 + t1), 0.5 * f1),
+                [duration as f32, *f1 as f32],
+            ));
+        })
+        .response;
+
+    copy_image_or_csv_context_menu(&response, || spectrogram.to_csv());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::spectrogram::{
+        Spectrogram,
+        SpectrogramConfig,
+        WindowFunction,
+    };
+
+    #[test]
+    fn peak_at_signal_frequency() {
+        let sample_interval = 0.01;
+        let frequency = 12.5;
+        let signal = (0..1024)
+            .map(|i| (2.0 * std::f64::consts::PI * frequency * i as f64 * sample_interval).sin())
+            .collect::<Vec<_>>();
+
+        let config = SpectrogramConfig {
+            window_size: 128,
+            overlap: 0.5,
+            window: WindowFunction::Hann,
+            dynamic_range: 60.0,
+        };
+        let spectrogram = Spectrogram::compute(0.0, sample_interval, &signal, &config).unwrap();
+
+        assert_eq!(spectrogram.times.len(), 15);
+        assert_eq!(spectrogram.frequencies.len(), 65);
+
+        for frame in &spectrogram.magnitudes {
+            let peak_bin = frame
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .unwrap()
+                .0;
+            assert!((spectrogram.frequencies[peak_bin] - frequency).abs() < 1.0);
+        }
+    }
+}
@@ -0,0 +1,430 @@
+//! Streaming a solver run to a remote monitor.
+//!
+//! A headless solver process (see [`crate::headless`]) publishes its status
+//! and the images of its observers over TCP. A composer can connect to it as a
+//! monitor (Run > Monitor Remote Run), so long runs on a cluster can be watched
+//! from a workstation.
+//!
+//! Every message is a little-endian `u32` length followed by a JSON
+//! [`Header`], and another `u32` length followed by a binary payload. Observer
+//! images are sent as LZ4-compressed RGBA pixels. Other messages have an empty
+//! payload.
+
+use std::{
+    io::{
+        BufReader,
+        BufWriter,
+        Read,
+        Write,
+    },
+    net::{
+        SocketAddr,
+        TcpListener,
+        TcpStream,
+    },
+    sync::Arc,
+    time::Duration,
+};
+
+use cem_util::egui::{
+    EguiUtilContextExt,
+    RepaintTrigger,
+};
+use color_eyre::eyre::bail;
+use parking_lot::Mutex;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    Error,
+    solver::runner::SolverState,
+    util::spawn_thread,
+};
+
+/// Clients that don't accept data for this long are dropped, so a slow
+/// monitor can't stall the solver.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Upper bound for headers and payloads, so a corrupt stream doesn't make us
+/// allocate all memory.
+const MAX_MESSAGE_SIZE: usize = 256 << 20;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Header {
+    Status(RemoteStatus),
+    ObserverFrame {
+        observer: usize,
+        label: String,
+        width: u32,
+        height: u32,
+    },
+}
+
+/// What the monitor gets to know about the solver state.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct RemoteStatus {
+    pub sim_time: f64,
+    pub sim_tick: usize,
+    pub total_running_time: Duration,
+    pub last_step_time: Duration,
+    pub paused: bool,
+    pub finished: bool,
+}
+
+impl From<&SolverState> for RemoteStatus {
+    fn from(state: &SolverState) -> Self {
+        Self {
+            sim_time: state.sim_time,
+            sim_tick: state.sim_tick,
+            total_running_time: state.total_running_time,
+            last_step_time: state.last_step_time,
+            paused: state.paused,
+            finished: state.finished,
+        }
+    }
+}
+
+/// Sending side of a stream.
+///
+/// This is cheap to clone. Messages are sent to all monitors that are
+/// connected at that time.
+#[derive(Clone, Debug)]
+pub struct StreamPublisher {
+    address: SocketAddr,
+    clients: Arc<Mutex<Vec<BufWriter<TcpStream>>>>,
+}
+
+impl StreamPublisher {
+    pub fn bind(address: SocketAddr) -> Result<Self, Error> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        tracing::info!(%address, "streaming solver to monitors");
+
+        let clients = Arc::new(Mutex::new(vec![]));
+
+        spawn_thread("stream-publisher", {
+            let clients = clients.clone();
+            move || {
+                for stream in listener.incoming() {
+                    let result = stream.and_then(|stream| {
+                        stream.set_nodelay(true)?;
+                        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                        tracing::info!(peer = ?stream.peer_addr().ok(), "monitor connected");
+                        clients.lock().push(BufWriter::new(stream));
+                        Ok(())
+                    });
+                    if let Err(error) = result {
+                        tracing::warn!(%error, "failed to accept monitor");
+                    }
+                }
+            }
+        });
+
+        Ok(Self { address, clients })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    pub fn publish_status(&self, status: RemoteStatus) {
+        self.publish(&Header::Status(status), &[]);
+    }
+
+    pub fn publish_frame(&self, observer: usize, label: &str, image: &image::RgbaImage) {
+        // don't bother compressing if nobody is watching
+        if self.clients.lock().is_empty() {
+            return;
+        }
+
+        let payload = lz4_flex::compress_prepend_size(image.as_raw());
+        self.publish(
+            &Header::ObserverFrame {
+                observer,
+                label: label.to_owned(),
+                width: image.width(),
+                height: image.height(),
+            },
+            &payload,
+        );
+    }
+
+    fn publish(&self, header: &Header, payload: &[u8]) {
+        let mut clients = self.clients.lock();
+        if clients.is_empty() {
+            return;
+        }
+
+        let header = serde_json::to_vec(header).expect("header serialization failed");
+
+        clients.retain_mut(|client| {
+            match write_message(client, &header, payload) {
+                Ok(()) => true,
+                Err(error) => {
+                    tracing::info!(%error, "monitor disconnected");
+                    false
+                }
+            }
+        });
+    }
+}
+
+fn write_message(
+    writer: &mut BufWriter<TcpStream>,
+    header: &[u8],
+    payload: &[u8],
+) -> Result<(), Error> {
+    for part in [header, payload] {
+        writer.write_all(&u32::try_from(part.len())?.to_le_bytes())?;
+        writer.write_all(part)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn read_part(reader: &mut impl Read) -> Result<Vec<u8>, Error> {
+    let mut length = [0; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length) as usize;
+    if length > MAX_MESSAGE_SIZE {
+        bail!("Message too large: {length} bytes");
+    }
+
+    let mut buffer = vec![0; length];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+/// Receiving side of a stream.
+///
+/// Messages are received on a background thread. Only the latest status and
+/// the latest image of each observer are kept.
+#[derive(Debug)]
+pub struct StreamMonitor {
+    address: SocketAddr,
+    shared: Arc<Mutex<MonitorState>>,
+}
+
+#[derive(Debug, Default)]
+struct MonitorState {
+    status: Option<RemoteStatus>,
+    frames: Vec<Option<RemoteFrame>>,
+
+    /// Why the connection was closed
+    closed: Option<String>,
+}
+
+#[derive(Debug)]
+struct RemoteFrame {
+    label: String,
+    image: egui::ColorImage,
+
+    /// Counts up with every frame, so the UI knows when to update its texture.
+    generation: u64,
+}
+
+impl StreamMonitor {
+    pub fn connect(address: SocketAddr, repaint_trigger: RepaintTrigger) -> Result<Self, Error> {
+        let stream = TcpStream::connect_timeout(&address, Duration::from_secs(5))?;
+        tracing::info!(%address, "connected to remote solver");
+
+        let shared = Arc::new(Mutex::new(MonitorState::default()));
+
+        spawn_thread("stream-monitor", {
+            let shared = shared.clone();
+            move || {
+                let mut reader = BufReader::new(stream);
+                let mut generation = 0;
+
+                let error = loop {
+                    let result = receive(&mut reader, &shared, generation);
+                    repaint_trigger.repaint();
+                    if let Err(error) = result {
+                        break error;
+                    }
+                    generation += 1;
+                };
+
+                tracing::info!(%address, %error, "connection to remote solver closed");
+                shared.lock().closed = Some(error.to_string());
+                repaint_trigger.repaint();
+            }
+        });
+
+        Ok(Self { address, shared })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+fn receive(
+    reader: &mut impl Read,
+    shared: &Mutex<MonitorState>,
+    generation: u64,
+) -> Result<(), Error> {
+    let header: Header = serde_json::from_slice(&read_part(reader)?)?;
+    let payload = read_part(reader)?;
+
+    match header {
+        Header::Status(status) => {
+            shared.lock().status = Some(status);
+        }
+        Header::ObserverFrame {
+            observer,
+            label,
+            width,
+            height,
+        } => {
+            let pixels = lz4_flex::decompress_size_prepended(&payload)?;
+            let size = [width as usize, height as usize];
+            if pixels.len() != 4 * size[0] * size[1] {
+                bail!("Observer image has wrong size");
+            }
+            let image = egui::ColorImage::from_rgba_unmultiplied(size, &pixels);
+
+            let mut shared = shared.lock();
+            if shared.frames.len() <= observer {
+                shared.frames.resize_with(observer + 1, || None);
+            }
+            shared.frames[observer] = Some(RemoteFrame {
+                label,
+                image,
+                generation,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Window that shows a [`StreamMonitor`].
+#[derive(Debug)]
+pub struct RemoteMonitorWindow {
+    pub open: bool,
+    address: String,
+    monitor: Option<StreamMonitor>,
+
+    /// Textures for the observer images and the generation they show.
+    textures: Vec<Option<(u64, egui::TextureHandle)>>,
+}
+
+impl Default for RemoteMonitorWindow {
+    fn default() -> Self {
+        Self {
+            open: false,
+            address: "127.0.0.1:7879".to_owned(),
+            monitor: None,
+            textures: vec![],
+        }
+    }
+}
+
+impl RemoteMonitorWindow {
+    pub fn connect(&mut self, ctx: &egui::Context, address: SocketAddr) -> Result<(), Error> {
+        self.address = address.to_string();
+        self.textures.clear();
+        self.monitor = None;
+        self.monitor = Some(StreamMonitor::connect(address, ctx.repaint_trigger())?);
+        self.open = true;
+        Ok(())
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Result<(), Error> {
+        let mut connect = None;
+
+        egui::Window::new("Remote Run")
+            .movable(true)
+            .default_size([400.0, 400.0])
+            .open(&mut self.open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Address");
+                    ui.text_edit_singleline(&mut self.address);
+                    if ui.button("Connect").clicked() {
+                        connect = Some(self.address.parse::<SocketAddr>());
+                    }
+                });
+
+                let Some(monitor) = &self.monitor
+                else {
+                    ui.label("Not connected");
+                    return;
+                };
+
+                ui.separator();
+
+                let shared = monitor.shared.lock();
+
+                if let Some(reason) = &shared.closed {
+                    ui.label(format!("Disconnected from {}: {reason}", monitor.address));
+                }
+                else {
+                    ui.label(format!("Connected to {}", monitor.address));
+                }
+
+                if let Some(status) = &shared.status {
+                    let state = if status.finished {
+                        "Finished"
+                    }
+                    else if status.paused {
+                        "Paused"
+                    }
+                    else {
+                        "Running"
+                    };
+                    ui.label(format!("State: {state}"));
+                    ui.label(format!("Tick: {}", status.sim_tick));
+                    ui.label(format!("Time: {:.3}", status.sim_time));
+                    ui.label(format!("Running time: {:.1?}", status.total_running_time));
+                    ui.label(format!("Time per step: {:.1?}", status.last_step_time));
+                }
+
+                if self.textures.len() < shared.frames.len() {
+                    self.textures.resize_with(shared.frames.len(), || None);
+                }
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (index, frame) in shared.frames.iter().enumerate() {
+                        let Some(frame) = frame
+                        else {
+                            continue;
+                        };
+
+                        // only upload the image if it changed
+                        let slot = &mut self.textures[index];
+                        match slot {
+                            Some((generation, texture)) if *generation != frame.generation => {
+                                texture.set(frame.image.clone(), Default::default());
+                                *generation = frame.generation;
+                            }
+                            Some(_) => {}
+                            None => {
+                                let texture = ui.ctx().load_texture(
+                                    format!("remote_observer_{index}"),
+                                    frame.image.clone(),
+                                    Default::default(),
+                                );
+                                *slot = Some((frame.generation, texture));
+                            }
+                        }
+                        let (_, texture) = slot.as_ref().unwrap();
+
+                        ui.separator();
+                        ui.label(&frame.label);
+                        ui.add(egui::Image::new(texture).shrink_to_fit());
+                    }
+                });
+            });
+
+        if let Some(address) = connect {
+            self.connect(ctx, address?)?;
+        }
+
+        Ok(())
+    }
+}
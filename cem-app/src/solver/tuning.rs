@@ -0,0 +1,551 @@
+//! Antenna tuning assistant.
+//!
+//! The user picks a scene parameter and sets goals for a port's reflection,
+//! e.g. a resonance at 2.45 GHz or |S11| below -10 dB. Moving the parameter's
+//! slider starts a short preview run with the parameter overridden, and the
+//! goals are evaluated live while it runs.
+
+use cem_scene::Scene;
+
+use crate::{
+    Error,
+    clipboard::copy_image_or_csv_context_menu,
+    composer::Composers,
+    error::ResultExt,
+    solver::{
+        config::{
+            SolverConfig,
+            SolverConfigSpecifics,
+            StopCondition,
+        },
+        network::{
+            MATCHED_THRESHOLD,
+            Reflection,
+            format_frequency,
+            to_db,
+        },
+        parameters::SceneParameters,
+        port::port_color,
+        runner::SolverRunner,
+    },
+};
+
+/// Number of frequencies the reflection is evaluated at across the band.
+const NUM_FREQUENCIES: usize = 201;
+
+/// A goal for the reflection at a port. Frequencies are in Hz.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Goal {
+    /// |S11| is minimal within `tolerance` of `frequency`.
+    Resonance { frequency: f64, tolerance: f64 },
+
+    /// |S11| at `frequency` is below `threshold` dB.
+    ReflectionBelow { frequency: f64, threshold: f64 },
+}
+
+impl Goal {
+    pub fn evaluate(&self, reflection: &Reflection) -> Option<GoalStatus> {
+        match self {
+            Goal::Resonance {
+                frequency,
+                tolerance,
+            } => {
+                let readouts = reflection.readouts()?;
+                Some(GoalStatus {
+                    met: (readouts.resonance - frequency).abs() <= *tolerance,
+                    value: format!("resonance at {}", format_frequency(readouts.resonance)),
+                })
+            }
+            Goal::ReflectionBelow {
+                frequency,
+                threshold,
+            } => {
+                let value = to_db(reflection.value_at(*frequency)?.norm());
+                Some(GoalStatus {
+                    met: value <= *threshold,
+                    value: format!("{value:.1} dB"),
+                })
+            }
+        }
+    }
+
+    fn frequency(&self) -> f64 {
+        match self {
+            Goal::Resonance { frequency, .. } | Goal::ReflectionBelow { frequency, .. } => {
+                *frequency
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GoalStatus {
+    pub met: bool,
+
+    /// What was measured, for display
+    pub value: String,
+}
+
+#[derive(Debug)]
+pub struct TuningAssistant {
+    pub open: bool,
+
+    /// Index of the solver config previews are based on
+    solver_config: usize,
+
+    parameter: Option<String>,
+    min: f64,
+    max: f64,
+    value: f64,
+
+    /// Number of steps a preview runs for
+    preview_steps: usize,
+
+    /// Band the reflection is evaluated in, in Hz
+    band: (f64, f64),
+
+    /// Index of the port whose reflection the goals apply to
+    port: usize,
+
+    goals: Vec<Goal>,
+
+    /// Last evaluated reflection, with the number of samples it was computed
+    /// from
+    reflection: Option<(usize, Reflection)>,
+
+    /// Color of the port the reflection was recorded at
+    port_color: egui::Color32,
+}
+
+impl Default for TuningAssistant {
+    fn default() -> Self {
+        Self {
+            open: false,
+            solver_config: 0,
+            parameter: None,
+            min: 0.0,
+            max: 1.0,
+            value: 0.0,
+            preview_steps: 2000,
+            band: (2e9, 3e9),
+            port: 0,
+            goals: vec![
+                Goal::Resonance {
+                    frequency: 2.45e9,
+                    tolerance: 0.05e9,
+                },
+                Goal::ReflectionBelow {
+                    frequency: 2.45e9,
+                    threshold: MATCHED_THRESHOLD,
+                },
+            ],
+            reflection: None,
+            port_color: port_color(1),
+        }
+    }
+}
+
+impl TuningAssistant {
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        composers: &mut Composers,
+        solver_runner: &mut SolverRunner,
+    ) {
+        let mut open = self.open;
+
+        egui::Window::new("Tuning Assistant")
+            .movable(true)
+            .default_size([350.0, 400.0])
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let Some((scene, solver_configs)) = composers.active_scene_mut()
+                else {
+                    ui.weak("No scene open");
+                    return;
+                };
+
+                if solver_configs.is_empty() {
+                    ui.weak("Configure a solver first");
+                    return;
+                }
+                self.solver_config = self.solver_config.min(solver_configs.len() - 1);
+
+                egui::Grid::new("tuning_setup")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Solver");
+                        egui::ComboBox::from_id_salt("solver_config")
+                            .selected_text(&solver_configs[self.solver_config].label)
+                            .show_ui(ui, |ui| {
+                                for (i, solver_config) in solver_configs.iter().enumerate() {
+                                    ui.selectable_value(
+                                        &mut self.solver_config,
+                                        i,
+                                        &solver_config.label,
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Parameter");
+                        let parameters = SceneParameters::get(&scene.world);
+                        egui::ComboBox::from_id_salt("parameter")
+                            .selected_text(self.parameter.as_deref().unwrap_or("None"))
+                            .show_ui(ui, |ui| {
+                                for (name, value) in &parameters {
+                                    if ui
+                                        .selectable_label(
+                                            self.parameter.as_ref() == Some(name),
+                                            name,
+                                        )
+                                        .clicked()
+                                    {
+                                        self.select_parameter(name, *value);
+                                    }
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Range");
+                        ui.horizontal(|ui| {
+                            ui.add(egui::DragValue::new(&mut self.min).speed(0.1));
+                            ui.label("to");
+                            ui.add(egui::DragValue::new(&mut self.max).speed(0.1));
+                        });
+                        ui.end_row();
+
+                        ui.label("Preview Steps");
+                        ui.add(egui::DragValue::new(&mut self.preview_steps).range(1..=usize::MAX));
+                        ui.end_row();
+
+                        ui.label("Band");
+                        ui.horizontal(|ui| {
+                            frequency_drag_value(ui, &mut self.band.0);
+                            ui.label("to");
+                            frequency_drag_value(ui, &mut self.band.1);
+                        });
+                        ui.end_row();
+
+                        ui.label("Port");
+                        let ports = solver_runner
+                            .active_solver()
+                            .map(|solver| solver.probe_outputs().ports.as_slice())
+                            .unwrap_or_default();
+                        egui::ComboBox::from_id_salt("port")
+                            .selected_text(
+                                ports
+                                    .get(self.port)
+                                    .map_or("None", |(name, _)| name.as_str()),
+                            )
+                            .show_ui(ui, |ui| {
+                                for (i, (name, _)) in ports.iter().enumerate() {
+                                    if ui.selectable_value(&mut self.port, i, name).changed() {
+                                        self.reflection = None;
+                                    }
+                                }
+                            });
+                        ui.end_row();
+                    });
+
+                if self.parameter.is_none() {
+                    ui.weak("Add a parameter in View > Parameters and select it here.");
+                }
+
+                ui.separator();
+
+                ui.add_enabled_ui(self.parameter.is_some(), |ui| {
+                    ui.horizontal(|ui| {
+                        let response =
+                            ui.add(egui::Slider::new(&mut self.value, self.min..=self.max));
+
+                        // only start a preview when the user let go of the slider
+                        if response.drag_stopped() || (response.changed() && !response.dragged()) {
+                            self.start_preview(
+                                scene,
+                                &solver_configs[self.solver_config],
+                                solver_runner,
+                            )
+                            .ok_or_handle(ui.ctx());
+                        }
+
+                        if ui
+                            .button("Apply")
+                            .on_hover_text("Set the parameter in the scene to this value")
+                            .clicked()
+                            && let Some(parameter) = &self.parameter
+                        {
+                            scene
+                                .world
+                                .get_resource_or_init::<SceneParameters>()
+                                .values
+                                .insert(parameter.clone(), self.value);
+                        }
+                    });
+                });
+
+                ui.separator();
+
+                self.update_reflection(solver_runner);
+                self.goals_ui(ui);
+
+                if let Some((_, reflection)) = &self.reflection {
+                    ui.separator();
+                    self.reflection_plot(ui, reflection);
+                }
+            });
+
+        self.open = open;
+    }
+
+    fn select_parameter(&mut self, name: &str, value: f64) {
+        self.parameter = Some(name.to_owned());
+        self.value = value;
+
+        let span = if value == 0.0 { 1.0 } else { 0.5 * value.abs() };
+        self.min = value - span;
+        self.max = value + span;
+    }
+
+    /// Starts a short run with the parameter at the slider's value. A solver
+    /// that is still running is stopped.
+    fn start_preview(
+        &mut self,
+        scene: &mut Scene,
+        solver_config: &SolverConfig,
+        solver_runner: &mut SolverRunner,
+    ) -> Result<(), Error> {
+        let Some(parameter) = &self.parameter
+        else {
+            return Ok(());
+        };
+
+        let mut solver_config = solver_config.clone();
+        solver_config
+            .common
+            .parameter_overrides
+            .insert(parameter.clone(), self.value);
+        if let SolverConfigSpecifics::Fdtd(fdtd_config) = &mut solver_config.specifics {
+            fdtd_config.stop_condition = StopCondition::StepLimit {
+                limit: self.preview_steps,
+            };
+        }
+
+        solver_runner.stop();
+        solver_runner.run(&solver_config, scene)?;
+        self.reflection = None;
+        solver_runner.run_at_full_speed();
+
+        Ok(())
+    }
+
+    /// Recomputes the reflection from the active solver's port, if it has
+    /// recorded enough new samples or just finished.
+    fn update_reflection(&mut self, solver_runner: &SolverRunner) {
+        let Some(solver) = solver_runner.active_solver()
+        else {
+            return;
+        };
+        let Some((name, output)) = solver.probe_outputs().ports.get(self.port)
+        else {
+            return;
+        };
+
+        let num_samples = output.num_samples();
+        let computed_from = self.reflection.as_ref().map_or(0, |(n, _)| *n);
+        if num_samples == computed_from
+            || (num_samples > computed_from
+                && num_samples < computed_from + computed_from / 20
+                && !solver.state().finished)
+        {
+            return;
+        }
+
+        let (start, end) = self.band;
+        let frequencies = (0..NUM_FREQUENCIES)
+            .map(|i| start + (end - start) * i as f64 / (NUM_FREQUENCIES - 1) as f64)
+            .collect();
+
+        self.reflection = output
+            .reflection(name.clone(), frequencies)
+            .map(|reflection| (num_samples, reflection));
+        self.port_color = output.color();
+    }
+
+    fn goals_ui(&mut self, ui: &mut egui::Ui) {
+        let mut delete = None;
+
+        egui::Grid::new("goals").num_columns(3).show(ui, |ui| {
+            for (i, goal) in self.goals.iter_mut().enumerate() {
+                ui.push_id(i, |ui| {
+                    ui.horizontal(|ui| {
+                        match goal {
+                            Goal::Resonance {
+                                frequency,
+                                tolerance,
+                            } => {
+                                ui.label("Resonance at");
+                                frequency_drag_value(ui, frequency);
+                                ui.label("±");
+                                frequency_drag_value(ui, tolerance);
+                            }
+                            Goal::ReflectionBelow {
+                                frequency,
+                                threshold,
+                            } => {
+                                ui.label("|S11| at");
+                                frequency_drag_value(ui, frequency);
+                                ui.label("below");
+                                ui.add(egui::DragValue::new(threshold).speed(0.1).suffix(" dB"));
+                            }
+                        }
+                    });
+                });
+
+                let status = self
+                    .reflection
+                    .as_ref()
+                    .and_then(|(_, reflection)| goal.evaluate(reflection));
+                match status {
+                    Some(status) if status.met => {
+                        ui.colored_label(egui::Color32::GREEN, format!("✔ {}", status.value));
+                    }
+                    Some(status) => {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            format!("✘ {}", status.value),
+                        );
+                    }
+                    None => {
+                        ui.weak("-");
+                    }
+                }
+
+                if ui.small_button("Delete").clicked() {
+                    delete = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(i) = delete {
+            self.goals.remove(i);
+        }
+
+        ui.horizontal(|ui| {
+            let frequency = 0.5 * (self.band.0 + self.band.1);
+            if ui.small_button("Add Resonance").clicked() {
+                self.goals.push(Goal::Resonance {
+                    frequency,
+                    tolerance: 0.02 * frequency,
+                });
+            }
+            if ui.small_button("Add |S11| Limit").clicked() {
+                self.goals.push(Goal::ReflectionBelow {
+                    frequency,
+                    threshold: MATCHED_THRESHOLD,
+                });
+            }
+        });
+
+        if self.reflection.is_none() {
+            ui.weak("Move the slider to start a preview. The scene needs a port.");
+        }
+    }
+
+    fn reflection_plot(&self, ui: &mut egui::Ui, reflection: &Reflection) {
+        let points = reflection
+            .frequencies
+            .iter()
+            .zip(&reflection.values)
+            .map(|(frequency, value)| [frequency * 1e-9, to_db(value.norm())])
+            .collect::<Vec<_>>();
+        let goal_color = ui.visuals().warn_fg_color;
+
+        let response = egui_plot::Plot::new("tuning_reflection")
+            .height(150.0)
+            .x_axis_label("Frequency [GHz]")
+            .y_axis_label("|S11| [dB]")
+            .include_y(0.0)
+            .show(ui, |plot_ui| {
+                plot_ui.line(
+                    egui_plot::Line::new(reflection.name.clone(), points.clone())
+                        .color(self.port_color),
+                );
+
+                for goal in &self.goals {
+                    plot_ui.vline(
+                        egui_plot::VLine::new("Goal", goal.frequency() * 1e-9).color(goal_color),
+                    );
+                    if let Goal::ReflectionBelow { threshold, .. } = goal {
+                        plot_ui.hline(egui_plot::HLine::new("Goal", *threshold).color(goal_color));
+                    }
+                }
+            })
+            .response;
+
+        copy_image_or_csv_context_menu(&response, || {
+            let mut csv = "frequency,s11_db\n".to_owned();
+            for [frequency, value] in &points {
+                csv.push_str(&format!("{},{value}\n", frequency * 1e9));
+            }
+            csv
+        });
+    }
+}
+
+/// Drag value for a frequency in Hz, shown in GHz.
+pub(super) fn frequency_drag_value(ui: &mut egui::Ui, frequency: &mut f64) -> egui::Response {
+    let mut ghz = *frequency * 1e-9;
+    let response = ui.add(
+        egui::DragValue::new(&mut ghz)
+            .range(0.0..=f64::INFINITY)
+            .speed(0.001)
+            .suffix(" GHz"),
+    );
+    if response.changed() {
+        *frequency = ghz * 1e9;
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use num::complex::Complex64;
+
+    use crate::solver::{
+        network::Reflection,
+        tuning::Goal,
+    };
+
+    #[test]
+    fn goals_track_resonance() {
+        // a dip of -20 dB at 2.4 GHz
+        let frequencies = (0..=100).map(|i| 2e9 + 1e7 * i as f64).collect::<Vec<_>>();
+        let values = frequencies
+            .iter()
+            .map(|frequency| {
+                let detuning = (frequency - 2.4e9) / 1e8;
+                Complex64::from(0.1 + 0.9 * detuning.powi(2).min(1.0))
+            })
+            .collect();
+        let reflection = Reflection {
+            name: "S11".to_owned(),
+            reference_impedance: 50.0,
+            frequencies,
+            values,
+        };
+
+        let near = Goal::Resonance {
+            frequency: 2.45e9,
+            tolerance: 0.1e9,
+        };
+        assert!(near.evaluate(&reflection).unwrap().met);
+
+        let matched = Goal::ReflectionBelow {
+            frequency: 2.45e9,
+            threshold: -10.0,
+        };
+        assert!(!matched.evaluate(&reflection).unwrap().met);
+    }
+}
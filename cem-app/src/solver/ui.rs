@@ -1,4 +1,8 @@
-use std::time::Duration;
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 
 use cem_probe::{
     HasChangeValue,
@@ -9,27 +13,80 @@ use cem_probe::{
 };
 use cem_solver::{
     fdtd,
-    material::PhysicalConstants,
+    material::UnitSystem,
 };
+use cem_util::egui::file_dialog::FileDialog;
 use nalgebra::Vector3;
+use parking_lot::Mutex;
 
-use crate::solver::{
-    config::{
-        FixedVolume,
-        SceneAabbVolume,
-        SolverConfig,
-        SolverConfigCommon,
-        SolverConfigFdtd,
-        SolverConfigSpecifics,
-        StopCondition,
-        Volume,
+use crate::{
+    error::{
+        ErrorHandler,
+        ResultExt,
+    },
+    jobs::{
+        Cancelled,
+        JobHandle,
+        Jobs,
+    },
+    recorder,
+    solver::{
+        benchmark::FdtdBackendKind,
+        clock::clock_ui,
+        config::{
+            FixedVolume,
+            MeshGrading,
+            Parallelization,
+            SceneAabbVolume,
+            SolverConfig,
+            SolverConfigCommon,
+            SolverConfigFdtd,
+            SolverConfigSpecifics,
+            StopCondition,
+            Volume,
+        },
+        events::events_ui,
+        export::{
+            ExportScreenshot,
+            VIEWPORT_IMAGE_FILE_NAME,
+            export_results,
+        },
+        far_field::far_field_probe_ui,
+        hashes::stale_results_label,
+        history::observer_history_ui,
+        library::{
+            LibraryRun,
+            save_run,
+        },
+        nf2ff::nf2ff_pattern_ui,
+        parameters::ParameterValues,
+        probe::{
+            LineCut,
+            LineProbeOutput,
+            line_cut_plot,
+            point_probe_plot,
+        },
+        report::ReportOptions,
+        run_log::DEFAULT_LOG_INTERVAL,
+        runner::{
+            Solver,
+            SolverRunner,
+        },
+        watchdog::DEFAULT_CHECK_INTERVAL,
+        waveform::set_simulation_time,
     },
-    runner::SolverRunner,
 };
 
 impl SolverRunner {
     pub fn show_active_solver_ui(&mut self, ctx: &egui::Context) {
         let mut close_runner = false;
+        let mut saved_to_library = false;
+
+        set_simulation_time(
+            ctx,
+            self.active_solver()
+                .map(|solver| solver.clock().displayed_time()),
+        );
 
         if let Some(solver) = self.active_solver() {
             let state = solver.state();
@@ -57,6 +114,7 @@ impl SolverRunner {
                             }
 
                             if ui.button("⏹").clicked() {
+                                self.recorder.record(recorder::Action::StopSolver);
                                 solver.stop();
                             }
                         }
@@ -66,9 +124,38 @@ impl SolverRunner {
                         "Simulation Time: {:.3} (Tick {})",
                         state.sim_time, state.sim_tick
                     ));
+                    clock_ui(ui, solver.clock());
+
+                    if let Some(reason) = self.stale_results() {
+                        stale_results_label(ui, reason);
+                    }
+
+                    if let Some(active_backend) = self.active_backend() {
+                        let response = ui.label(format!(
+                            "Backend: {}{}",
+                            active_backend.backend.label(),
+                            if active_backend.choice_reason.is_some() {
+                                " (automatic)"
+                            }
+                            else {
+                                ""
+                            }
+                        ));
+                        if let Some(reason) = &active_backend.choice_reason {
+                            response.on_hover_text(reason);
+                        }
+                    }
 
                     ui.label(format!("Running Time: {:.3?}", state.total_running_time));
                     ui.label(format!("Update Time: {:.3?}", state.last_step_time));
+                    ui.label(format!("Dropped Frames: {}", state.dropped_observations));
+
+                    if let Some(directory) = self.active_run_log() {
+                        ui.label("Logging run").on_hover_text(format!(
+                            "Metrics and observer images are saved to {}",
+                            directory.display()
+                        ));
+                    }
 
                     let mut ups_slider = |label: &str, delay: Option<Duration>, max: u64| {
                         // returns Option<Option<Duration>>: the outer Option indicates if the
@@ -112,17 +199,297 @@ impl SolverRunner {
                         let mut state = solver.state_mut();
                         state.observation_delay = delay;
                     }
+
+                    ui.add_enabled_ui(state.finished || state.paused, |ui| {
+                        export_results_button(ui, solver);
+                    });
+
+                    if let Some(library_run) = self.library_run() {
+                        saved_to_library = save_to_library_ui(ui, solver, library_run);
+                    }
+
+                    for (i, (label, mut slice)) in
+                        solver.observer_slices().slices().into_iter().enumerate()
+                    {
+                        ui.horizontal(|ui| {
+                            ui.label(label);
+                            if slice.position_ui(ui).changed() {
+                                solver.set_slice_position(i, slice.position);
+                            }
+                        });
+                    }
+
+                    for history in solver.observer_histories() {
+                        observer_history_ui(ui, history, solver.clock());
+                    }
+
+                    let probe_outputs = solver.probe_outputs();
+                    let displayed_time = solver.clock().displayed_time();
+
+                    for (i, (label, output)) in probe_outputs.line_probes.iter().enumerate() {
+                        egui::CollapsingHeader::new(label)
+                            .id_salt(("line_probe", i))
+                            .show(ui, |ui| {
+                                let id = ui.id().with(("shown_line_cut", i));
+                                if let Some(line_cut) =
+                                    shown_line_cut(ui, id, output, displayed_time)
+                                {
+                                    line_cut_plot(ui, ("line_probe_plot", i), &line_cut);
+                                }
+                                else {
+                                    ui.label("No samples yet");
+                                }
+                            });
+                    }
+
+                    for (i, (label, output)) in probe_outputs.point_probes.iter().enumerate() {
+                        egui::CollapsingHeader::new(label)
+                            .id_salt(("point_probe", i))
+                            .show(ui, |ui| {
+                                point_probe_plot(
+                                    ui,
+                                    ("point_probe_plot", i),
+                                    &output.samples_until(displayed_time),
+                                    probe_outputs.units,
+                                );
+                            });
+                    }
+
+                    for (i, (label, output)) in probe_outputs.nf2ff_boxes.iter().enumerate() {
+                        egui::CollapsingHeader::new(label)
+                            .id_salt(("nf2ff_box", i))
+                            .show(ui, |ui| {
+                                nf2ff_pattern_ui(
+                                    ui,
+                                    ("nf2ff_pattern", i),
+                                    label,
+                                    output,
+                                    probe_outputs.units,
+                                );
+                            });
+                    }
+
+                    for (i, (label, output)) in probe_outputs.far_field_probes.iter().enumerate() {
+                        egui::CollapsingHeader::new(label)
+                            .id_salt(("far_field_probe", i))
+                            .show(ui, |ui| {
+                                far_field_probe_ui(
+                                    ui,
+                                    ("far_field_probe_plot", i),
+                                    output,
+                                    probe_outputs.units,
+                                );
+                            });
+                    }
                 });
 
             close_runner = !window_open;
         }
 
+        if saved_to_library {
+            self.set_saved_to_library();
+        }
+
         if close_runner {
+            self.recorder.record(recorder::Action::StopSolver);
             self.stop();
         }
     }
 }
 
+/// The line cut shown for a line probe. The probe only keeps its latest cut, so
+/// the last one up to the displayed time is remembered, e.g. while the
+/// visualization is paused.
+fn shown_line_cut(
+    ui: &egui::Ui,
+    id: egui::Id,
+    output: &LineProbeOutput,
+    displayed_time: f64,
+) -> Option<LineCut> {
+    if let Some(line_cut) = output.latest()
+        && line_cut.time <= displayed_time
+    {
+        ui.data_mut(|data| data.insert_temp(id, line_cut.clone()));
+        Some(line_cut)
+    }
+    else {
+        ui.data(|data| data.get_temp::<LineCut>(id))
+            .or_else(|| output.latest())
+    }
+}
+
+/// Lets the user pick a directory and exports all results of the run into it.
+fn export_results_button(ui: &mut egui::Ui, solver: &Solver) {
+    let id = ui.id().with("export_results");
+    let file_dialog = ui.data_mut(|data| {
+        data.get_temp_mut_or_insert_with(id, || {
+            Arc::new(Mutex::new(
+                FileDialog::new().anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0]),
+            ))
+        })
+        .clone()
+    });
+    let mut file_dialog = file_dialog.lock();
+
+    let job_id = id.with("job");
+    let job = ui.data(|data| data.get_temp::<JobHandle<ExportedResults>>(job_id));
+
+    let report_id = id.with("report");
+    let (mut write_report, mut report_options) = ui.data_mut(|data| {
+        data.get_persisted_mut_or_default::<(bool, ReportOptions)>(report_id).clone()
+    });
+
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(job.is_none(), egui::Button::new("Export Results"))
+            .on_hover_text(
+                "Write probe data, plots, a summary and an image of the viewport into a directory.",
+            )
+            .clicked()
+        {
+            file_dialog.pick_directory();
+        }
+
+        let mut changed = ui
+            .checkbox(&mut write_report, "Report")
+            .on_hover_text("Also write an HTML report with the plots and the viewport image")
+            .changed();
+        ui.add_enabled_ui(write_report, |ui| {
+            ui.menu_button("Options", |ui| {
+                changed |= ui.properties(&mut report_options).changed();
+            });
+        });
+        if changed {
+            ui.data_mut(|data| {
+                data.insert_persisted(report_id, (write_report, report_options.clone()));
+            });
+        }
+
+        if let Some(job) = &job {
+            match job.progress() {
+                Some(progress) => {
+                    ui.add(egui::ProgressBar::new(progress).desired_width(100.0));
+                }
+                None => {
+                    ui.spinner();
+                }
+            }
+        }
+    });
+
+    file_dialog.update(ui.ctx());
+
+    if let Some(directory) = file_dialog.take_picked() {
+        let state = solver.state();
+        let probe_outputs = solver.probe_outputs().clone();
+        let report = write_report.then_some(report_options);
+        let handle = Jobs::from_ctx(ui.ctx()).spawn("Export results", move |job| {
+            let files = export_results(&directory, &state, &probe_outputs, report.as_ref(), job)?;
+            Ok(ExportedResults { directory, files })
+        });
+        ui.data_mut(|data| data.insert_temp(job_id, handle));
+    }
+
+    if let Some(result) = job.and_then(|job| job.try_take_result()) {
+        ui.data_mut(|data| data.remove::<JobHandle<ExportedResults>>(job_id));
+
+        match result {
+            Ok(ExportedResults { directory, files }) => {
+                tracing::info!(directory = %directory.display(), num_files = files.len(), "exported results");
+
+                // the observers are only shown in the viewport, so we take a screenshot of it.
+                ui.ctx()
+                    .send_viewport_cmd(egui::ViewportCommand::Screenshot(egui::UserData::new(
+                        ExportScreenshot {
+                            path: directory.join(VIEWPORT_IMAGE_FILE_NAME),
+                        },
+                    )));
+            }
+            Err(error) if error.is::<Cancelled>() => {
+                tracing::debug!("export cancelled");
+            }
+            Err(error) => ui.handle_error(error),
+        }
+    }
+}
+
+/// Saves the run to the project's library once it finished, or when the user
+/// asks for it. Returns whether saving was started.
+fn save_to_library_ui(ui: &mut egui::Ui, solver: &Solver, library_run: &LibraryRun) -> bool {
+    let job_id = ui.id().with("save_to_library");
+    let job = ui.data(|data| data.get_temp::<JobHandle<PathBuf>>(job_id));
+    let state = solver.state();
+
+    let mut save = state.finished && !library_run.saved;
+
+    ui.horizontal(|ui| {
+        let label = if library_run.saved {
+            "Saved to Library"
+        }
+        else {
+            "Save to Library"
+        };
+        save |= ui
+            .add_enabled(
+                job.is_none() && !library_run.saved,
+                egui::Button::new(label),
+            )
+            .on_hover_text("Keep the results of this run in the project's results library. Finished runs are saved automatically.")
+            .clicked();
+
+        if let Some(job) = &job {
+            match job.progress() {
+                Some(progress) => {
+                    ui.add(egui::ProgressBar::new(progress).desired_width(100.0));
+                }
+                None => {
+                    ui.spinner();
+                }
+            }
+        }
+    });
+
+    let started = save && job.is_none();
+    if started {
+        let library_run = library_run.clone();
+        let probe_outputs = solver.probe_outputs().clone();
+        let handle = Jobs::from_ctx(ui.ctx()).spawn("Save run to library", move |job| {
+            save_run(&library_run, &state, &probe_outputs, job)
+        });
+        ui.data_mut(|data| data.insert_temp(job_id, handle));
+    }
+
+    if let Some(result) = job.and_then(|job| job.try_take_result()) {
+        ui.data_mut(|data| data.remove::<JobHandle<PathBuf>>(job_id));
+
+        match result {
+            Ok(directory) => {
+                tracing::info!(directory = %directory.display(), "saved run to library");
+
+                // the viewport image is the run's thumbnail
+                ui.ctx()
+                    .send_viewport_cmd(egui::ViewportCommand::Screenshot(egui::UserData::new(
+                        ExportScreenshot {
+                            path: directory.join(VIEWPORT_IMAGE_FILE_NAME),
+                        },
+                    )));
+            }
+            Err(error) if error.is::<Cancelled>() => {
+                tracing::debug!("saving run cancelled");
+            }
+            Err(error) => ui.handle_error(error),
+        }
+    }
+
+    started
+}
+
+#[derive(Debug)]
+struct ExportedResults {
+    directory: PathBuf,
+    files: Vec<PathBuf>,
+}
+
 impl PropertiesUi for SolverConfig {
     type Config = ();
 
@@ -138,15 +505,134 @@ impl PropertiesUi for SolverConfig {
                     ui.properties(&mut self.common.volume);
                 });
 
-                ui.label("Physical Constants");
-                ui.indent("volume_ui", |ui| {
-                    ui.properties(&mut self.common.physical_constants);
+                // the physical constants are set per project
+                let units = UnitSystem::display_units(ui.ctx());
+                ui.label(format!("Units: {}", units.label()));
+
+                ui.label("Parameter Overrides");
+                ui.indent("parameter_overrides_ui", |ui| {
+                    parameter_overrides_ui(ui, &mut changes, &mut self.common.parameter_overrides);
+                });
+
+                ui.label("Events");
+                ui.indent("events_ui", |ui| {
+                    events_ui(ui, &mut changes, &mut self.common.events);
                 });
 
                 // todo
                 match &mut self.specifics {
-                    SolverConfigSpecifics::Fdtd(_fdtd_config) => {}
+                    SolverConfigSpecifics::Fdtd(fdtd_config) => {
+                        parallelization_ui(ui, &mut changes, &mut self.common.parallelization);
+
+                        label_and_value(
+                            ui,
+                            "Spatial Order",
+                            &mut changes,
+                            &mut fdtd_config.spatial_order,
+                        );
+
+                        ui.horizontal(|ui| {
+                            ui.label("Mesh");
+                            // the backends only support uniform lattices so far, so grading can
+                            // only be turned off for configs that still have it.
+                            let mut graded =
+                                matches!(fdtd_config.mesh, MeshGrading::Graded { .. });
+                            if changes
+                                .track(ui.add_enabled(
+                                    graded,
+                                    egui::Checkbox::new(&mut graded, "Graded"),
+                                ))
+                                .on_disabled_hover_text(
+                                    "Not supported yet: the backends only use uniform lattices",
+                                )
+                                .changed()
+                                && !graded
+                            {
+                                fdtd_config.mesh = MeshGrading::Uniform;
+                            }
+                            if graded {
+                                ui.colored_label(
+                                    ui.visuals().warn_fg_color,
+                                    "Uses the finest cell size everywhere",
+                                );
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Watchdog");
+                            let watchdog = &mut fdtd_config.watchdog;
+                            let mut enabled = watchdog.check_interval.is_some();
+                            if changes
+                                .track(ui.checkbox(&mut enabled, "Enabled"))
+                                .changed()
+                            {
+                                watchdog.check_interval =
+                                    enabled.then_some(DEFAULT_CHECK_INTERVAL);
+                            }
+                            if let Some(check_interval) = &mut watchdog.check_interval {
+                                changes.track(ui.add(
+                                    egui::DragValue::new(check_interval)
+                                        .range(1..=100_000)
+                                        .prefix("every ")
+                                        .suffix(" ticks"),
+                                ));
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Sparse");
+                            let mut enabled = fdtd_config.sparse.is_some();
+                            if changes
+                                .track(ui.checkbox(&mut enabled, "Skip quiet regions"))
+                                .on_hover_text("Only supported by the CPU backends")
+                                .changed()
+                            {
+                                fdtd_config.sparse = enabled.then(Default::default);
+                            }
+                            if let Some(sparse) = &mut fdtd_config.sparse {
+                                changes.track(ui.add(
+                                    egui::DragValue::new(&mut sparse.chunk_size)
+                                        .range(fdtd::cpu::MIN_CHUNK_SIZE..=256)
+                                        .prefix("chunks of ")
+                                        .suffix(" cells"),
+                                ));
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Run Log");
+                            let run_log = &mut fdtd_config.run_log;
+                            let mut enabled = run_log.interval.is_some();
+                            if changes
+                                .track(ui.checkbox(&mut enabled, "Enabled"))
+                                .on_hover_text(
+                                    "Periodically save metrics and observer images, to keep a \
+                                     trail of long runs",
+                                )
+                                .changed()
+                            {
+                                run_log.interval = enabled.then_some(DEFAULT_LOG_INTERVAL);
+                            }
+                            if let Some(interval) = &mut run_log.interval {
+                                let mut minutes = interval.as_secs() / 60;
+                                if changes
+                                    .track(ui.add(
+                                        egui::DragValue::new(&mut minutes)
+                                            .range(1..=24 * 60)
+                                            .prefix("every ")
+                                            .suffix(" min"),
+                                    ))
+                                    .changed()
+                                {
+                                    *interval = Duration::from_secs(minutes * 60);
+                                }
+                            }
+                        });
+                    }
                     SolverConfigSpecifics::Feec(_feec_config) => {}
+                    SolverConfigSpecifics::Custom(custom_config) => {
+                        ui.label(format!("Backend: {}", custom_config.backend));
+                    }
                 }
             })
             .response;
@@ -155,6 +641,95 @@ impl PropertiesUi for SolverConfig {
     }
 }
 
+/// Selects the backend an FDTD solver runs on.
+fn parallelization_ui(
+    ui: &mut egui::Ui,
+    changes: &mut TrackChanges,
+    parallelization: &mut Option<Parallelization>,
+) {
+    let selected = parallelization
+        .as_ref()
+        .map(FdtdBackendKind::from_parallelization);
+
+    ui.horizontal(|ui| {
+        ui.label("Backend");
+        egui::ComboBox::from_id_salt("backend")
+            .selected_text(selected.map_or("Automatic", |backend| backend.label()))
+            .show_ui(ui, |ui| {
+                if ui
+                    .selectable_label(selected.is_none(), "Automatic")
+                    .on_hover_text(
+                        "Chosen when the solver is run, by grid size, memory limit and past runs",
+                    )
+                    .clicked()
+                    && selected.is_some()
+                {
+                    *parallelization = None;
+                    changes.mark_changed();
+                }
+                for backend in FdtdBackendKind::ALL {
+                    if ui
+                        .selectable_label(selected == Some(backend), backend.label())
+                        .clicked()
+                        && selected != Some(backend)
+                    {
+                        *parallelization = Some(backend.parallelization());
+                        changes.mark_changed();
+                    }
+                }
+            });
+    });
+}
+
+/// Edits the scene parameters a solver config overrides. Parameters that
+/// aren't listed keep the scene's value.
+fn parameter_overrides_ui(
+    ui: &mut egui::Ui,
+    changes: &mut TrackChanges,
+    overrides: &mut ParameterValues,
+) {
+    let mut delete = None;
+
+    for (name, value) in overrides.iter_mut() {
+        ui.horizontal(|ui| {
+            ui.label(name);
+            changes.track(ui.add(egui::DragValue::new(value).speed(0.1)));
+            if ui.small_button("Delete").clicked() {
+                delete = Some(name.clone());
+            }
+        });
+    }
+
+    if let Some(name) = delete {
+        overrides.remove(&name);
+        changes.mark_changed();
+    }
+
+    let id = ui.id().with("new_override");
+    let mut new_name = ui.data(|data| data.get_temp::<String>(id).unwrap_or_default());
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut new_name)
+                .hint_text("Parameter")
+                .desired_width(100.0),
+        );
+
+        let name = new_name.trim();
+        if ui
+            .add_enabled(
+                !name.is_empty() && !overrides.contains_key(name),
+                egui::Button::new("Override"),
+            )
+            .clicked()
+        {
+            overrides.insert(name.to_owned(), 0.0);
+            changes.mark_changed();
+            new_name.clear();
+        }
+    });
+    ui.data_mut(|data| data.insert_temp(id, new_name));
+}
+
 impl PropertiesUi for Volume {
     type Config = ();
 
@@ -262,6 +837,13 @@ pub struct SolverConfigUiWindow {
     pub selection: Option<usize>,
     pub is_open: bool,
     pub default_solver_config: SolverConfig,
+
+    /// The solver configs before the user started interacting with the
+    /// window.
+    edit_in_progress: Option<Vec<SolverConfig>>,
+
+    /// Whether the configs changed since [`Self::edit_in_progress`] was taken.
+    edit_changed: bool,
 }
 
 impl Default for SolverConfigUiWindow {
@@ -274,10 +856,12 @@ impl Default for SolverConfigUiWindow {
                 label: "New solver".to_owned(),
                 common: SolverConfigCommon {
                     volume: Default::default(),
-                    physical_constants: PhysicalConstants::REDUCED,
                     default_material: Default::default(),
                     parallelization: None,
                     memory_limit: None,
+                    parameter_overrides: Default::default(),
+                    disabled_ports: Default::default(),
+                    events: vec![],
                 },
                 specifics: SolverConfigSpecifics::Fdtd(SolverConfigFdtd {
                     resolution: fdtd::Resolution {
@@ -285,8 +869,15 @@ impl Default for SolverConfigUiWindow {
                         temporal: 0.25,
                     },
                     stop_condition: StopCondition::StepLimit { limit: 1000 },
+                    spatial_order: Default::default(),
+                    mesh: Default::default(),
+                    watchdog: Default::default(),
+                    sparse: None,
+                    run_log: Default::default(),
                 }),
             },
+            edit_in_progress: None,
+            edit_changed: false,
         }
     }
 }
@@ -301,9 +892,47 @@ impl SolverConfigUiWindow {
         self.is_open = true;
     }
 
-    pub fn show(&mut self, ctx: &egui::Context, solver_configs: &mut Vec<SolverConfig>) {
+    /// Shows the window.
+    ///
+    /// When the user finished an edit, this returns the solver configs as they
+    /// were before, so the edit can be undone.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        solver_configs: &mut Vec<SolverConfig>,
+    ) -> Option<Vec<SolverConfig>> {
         let id = egui::Id::new("solver_config_ui_window");
 
+        // dragging a value or typing into a text field changes the config every
+        // frame. we only want a single undo step for this, so the configs are
+        // saved once when the user starts interacting, and the edit is finished
+        // when they let go. only widgets in this window count, so that clicking
+        // the viewport or another window doesn't save the configs.
+        let window_layer = egui::LayerId::new(egui::Order::Middle, id);
+        let in_window = |widget: Option<egui::Id>| {
+            widget
+                .and_then(|widget| ctx.read_response(widget))
+                .is_some_and(|response| response.layer_id == window_layer)
+        };
+        let is_editing =
+            || in_window(ctx.dragged_id()) || in_window(ctx.memory(|memory| memory.focused()));
+        let pressed_in_window = ctx
+            .input(|input| {
+                input
+                    .pointer
+                    .any_pressed()
+                    .then(|| input.pointer.interact_pos())
+                    .flatten()
+            })
+            .and_then(|position| ctx.layer_id_at(position))
+            == Some(window_layer);
+        if self.is_open && self.edit_in_progress.is_none() && (is_editing() || pressed_in_window) {
+            self.edit_in_progress = Some(solver_configs.clone());
+            self.edit_changed = false;
+        }
+
+        let mut changed = false;
+
         egui::Window::new("Configure Solver")
             .id(id)
             .movable(true)
@@ -345,6 +974,7 @@ impl SolverConfigUiWindow {
                     if ui.add(egui::Button::new("+")).clicked() {
                         self.selection = Some(solver_configs.len());
                         solver_configs.push(self.default_solver_config.clone());
+                        changed = true;
                     }
 
                     if ui
@@ -357,11 +987,28 @@ impl SolverConfigUiWindow {
 
                 // property ui for selected solver
                 if let Some(selection) = self.selection {
-                    ui.properties(&mut solver_configs[selection]);
+                    changed |= ui.properties(&mut solver_configs[selection]).changed();
                 }
                 else {
                     ui.label("No solver selected");
                 }
             });
+
+        self.edit_changed |= changed;
+
+        // popups (e.g. the backend selection) are in their own layer, so an
+        // edit started in this window stays open while they are
+        if is_editing() || (self.edit_in_progress.is_some() && egui::Popup::is_any_open(ctx)) {
+            None
+        }
+        else {
+            let before = self.edit_in_progress.take();
+            if std::mem::take(&mut self.edit_changed) {
+                before
+            }
+            else {
+                None
+            }
+        }
     }
 }
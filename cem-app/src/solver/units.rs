@@ -0,0 +1,97 @@
+//! The unit system a scene is modelled in.
+//!
+//! This is a project-level setting: the solver takes its physical constants
+//! from it, and materials, probe plots and exported results are labelled with
+//! its units.
+
+use bevy_ecs::{
+    resource::Resource,
+    system::{
+        In,
+        Query,
+    },
+    world::World,
+};
+use cem_scene::Scene;
+use cem_solver::{
+    material::{
+        Material,
+        UnitConversion,
+        UnitSystem,
+    },
+    source::{
+        Source,
+        feed::Feed,
+    },
+};
+
+use crate::solver::{
+    array::Excitation,
+    config::SolverConfig,
+};
+
+#[derive(Clone, Copy, Debug, Default, Resource)]
+pub struct SceneUnits {
+    pub system: UnitSystem,
+}
+
+impl SceneUnits {
+    pub fn get(world: &World) -> UnitSystem {
+        world
+            .get_resource::<Self>()
+            .map(|units| units.system)
+            .unwrap_or_default()
+    }
+}
+
+/// Switches the scene to another unit system.
+///
+/// Materials, sources, their excitations and the solver configs are converted,
+/// so that the simulation stays physically the same.
+pub fn switch_unit_system(
+    scene: &mut Scene,
+    solver_configs: &mut [SolverConfig],
+    system: UnitSystem,
+) {
+    let current = SceneUnits::get(&scene.world);
+    if current == system {
+        return;
+    }
+
+    tracing::debug!(from = ?current, to = ?system, "switching unit system");
+    let conversion = current.conversion_to(&system);
+
+    scene
+        .world
+        .run_system_cached_with(convert_scene_system, conversion)
+        .unwrap();
+    scene.world.insert_resource(SceneUnits { system });
+
+    for solver_config in solver_configs {
+        solver_config.convert_units(&conversion);
+    }
+}
+
+fn convert_scene_system(
+    In(conversion): In<UnitConversion>,
+    mut materials: Query<&mut Material>,
+    mut sources: Query<&mut Source>,
+    mut feeds: Query<&mut Feed>,
+    mut excitations: Query<&mut Excitation>,
+) {
+    for mut material in &mut materials {
+        *material = material.convert_units(&conversion);
+    }
+
+    for mut source in &mut sources {
+        *source = source.convert_units(&conversion);
+    }
+
+    for mut feed in &mut feeds {
+        *feed = feed.convert_units(&conversion);
+    }
+
+    for mut excitation in &mut excitations {
+        *excitation = excitation.convert_units(&conversion);
+    }
+}
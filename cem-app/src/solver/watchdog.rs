@@ -0,0 +1,206 @@
+use std::fmt::Display;
+
+use cem_solver::{
+    Field,
+    FieldComponent,
+    FieldView,
+    Time,
+};
+use nalgebra::Point3;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::solver::runner::CoordinateTransformations;
+
+/// Default number of ticks between two checks of the fields.
+pub const DEFAULT_CHECK_INTERVAL: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchdogConfig {
+    /// Number of ticks between two checks of the fields. `None` disables the
+    /// watchdog.
+    pub check_interval: Option<usize>,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            check_interval: Some(DEFAULT_CHECK_INTERVAL),
+        }
+    }
+}
+
+/// Halts a simulation once it diverged.
+///
+/// Every few ticks this scans the fields for values that aren't finite. Once
+/// the fields blew up they never recover, so there's no point in letting the
+/// solver continue.
+#[derive(Clone, Copy, Debug)]
+pub struct Watchdog {
+    check_interval: usize,
+    last_check: Option<usize>,
+    coordinate_transformations: CoordinateTransformations,
+    likely_cause: LikelyCause,
+}
+
+impl Watchdog {
+    /// Returns `None` if the watchdog is disabled.
+    pub fn new(
+        config: &WatchdogConfig,
+        coordinate_transformations: CoordinateTransformations,
+        likely_cause: LikelyCause,
+    ) -> Option<Self> {
+        let check_interval = config.check_interval.filter(|interval| *interval > 0)?;
+
+        Some(Self {
+            check_interval,
+            last_check: None,
+            coordinate_transformations,
+            likely_cause,
+        })
+    }
+
+    pub fn check<I>(&mut self, instance: &I, state: &I::State) -> Result<(), DivergedError>
+    where
+        I: Field<Point3<usize>>,
+    {
+        let tick = state.tick();
+        if self
+            .last_check
+            .is_some_and(|last_check| tick < last_check + self.check_interval)
+        {
+            return Ok(());
+        }
+        self.last_check = Some(tick);
+
+        if let Some((lattice_point, component)) = find_non_finite(instance, state) {
+            return Err(DivergedError {
+                tick,
+                time: state.time(),
+                component,
+                lattice_point,
+                point: self
+                    .coordinate_transformations
+                    .transform_point_from_solver_to_world(&lattice_point),
+                likely_cause: self.likely_cause,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds the first lattice point with a field value that isn't finite.
+pub fn find_non_finite<I>(instance: &I, state: &I::State) -> Option<(Point3<usize>, FieldComponent)>
+where
+    I: Field<Point3<usize>>,
+{
+    [FieldComponent::E, FieldComponent::H]
+        .into_iter()
+        .find_map(|component| {
+            let view = instance.field(state, .., component);
+            view.iter()
+                .find(|(_point, value)| !value.iter().all(|x| x.is_finite()))
+                .map(|(point, _value)| (point, component))
+        })
+}
+
+/// What most likely made a simulation diverge.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LikelyCause {
+    /// The time step is larger than the stability limit.
+    CflViolation { temporal: f64, max_stable: f64 },
+
+    /// The time step is fine, so it's probably something in the scene.
+    Unknown,
+}
+
+impl LikelyCause {
+    pub fn from_resolution(temporal: f64, max_stable: f64) -> Self {
+        if temporal > max_stable {
+            Self::CflViolation {
+                temporal,
+                max_stable,
+            }
+        }
+        else {
+            Self::Unknown
+        }
+    }
+}
+
+impl Display for LikelyCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CflViolation {
+                temporal,
+                max_stable,
+            } => {
+                write!(
+                    f,
+                    "The time step {temporal} violates the CFL condition. Use a time step of at most {max_stable}."
+                )
+            }
+            Self::Unknown => {
+                write!(
+                    f,
+                    "Check for materials with negative or extreme values and for sources with very large amplitudes."
+                )
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, thiserror::Error)]
+#[error(
+    "Simulation diverged at tick {tick} (t = {time}): {component:?} field is not finite at {point} (lattice point {lattice_point}). {likely_cause}"
+)]
+pub struct DivergedError {
+    pub tick: usize,
+    pub time: f64,
+    pub component: FieldComponent,
+    pub lattice_point: Point3<usize>,
+    pub point: Point3<f64>,
+    pub likely_cause: LikelyCause,
+}
+
+#[cfg(test)]
+mod tests {
+    use cem_solver::{
+        FieldComponent,
+        FieldMut,
+        fdtd::{
+            cpu::FdtdCpuBackend,
+            test_util::{
+                vacuum_config,
+                vacuum_instance,
+            },
+        },
+    };
+    use nalgebra::{
+        Point3,
+        Vector3,
+    };
+
+    use crate::solver::watchdog::find_non_finite;
+
+    #[test]
+    fn finds_non_finite_field_value() {
+        let config = vacuum_config(Vector3::repeat(4.0));
+        let (instance, mut state) = vacuum_instance(FdtdCpuBackend::single_threaded(), &config);
+
+        assert_eq!(find_non_finite(&instance, &state), None);
+
+        let point = Point3::new(1, 2, 3);
+        for (_point, value) in instance.field_mut(&mut state, point..=point, FieldComponent::H) {
+            value.y = f64::NAN;
+        }
+
+        assert_eq!(
+            find_non_finite(&instance, &state),
+            Some((point, FieldComponent::H))
+        );
+    }
+}
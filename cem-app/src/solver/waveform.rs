@@ -0,0 +1,239 @@
+//! Preview of a source's waveform and spectrum.
+//!
+//! The preview is shown when a source is selected. While a solver runs, a
+//! cursor marks the displayed time of its [`SimulationClock`], which the solver
+//! window sets with [`set_simulation_time`].
+//!
+//! [`SimulationClock`]: crate::solver::clock::SimulationClock
+
+use cem_solver::{
+    material::UnitSystem,
+    source::{
+        Source,
+        SourceValues,
+    },
+};
+use nalgebra::Vector3;
+
+use crate::{
+    clipboard::copy_image_or_csv_context_menu,
+    solver::spectrogram::{
+        Spectrogram,
+        SpectrogramConfig,
+        WindowFunction,
+    },
+};
+
+/// Number of samples the waveform is evaluated at. Must be a power of two for
+/// the spectrum.
+const NUM_SAMPLES: usize = 1024;
+
+/// Makes `time` the simulation time marked in waveform previews.
+pub fn set_simulation_time(ctx: &egui::Context, time: Option<f64>) {
+    ctx.data_mut(|data| data.insert_temp(simulation_time_id(), time));
+}
+
+fn simulation_time(ctx: &egui::Context) -> Option<f64> {
+    ctx.data(|data| data.get_temp(simulation_time_id()))
+        .flatten()
+}
+
+fn simulation_time_id() -> egui::Id {
+    egui::Id::new("simulation_time")
+}
+
+/// A source evaluated at uniformly spaced times from 0.
+#[derive(Clone, Debug)]
+pub struct Waveform {
+    pub sample_interval: f64,
+
+    /// Current density projected onto the direction of its peak. Sources that
+    /// only have magnetic currents use those instead.
+    pub values: Vec<f64>,
+}
+
+impl Waveform {
+    pub fn sample(source: &Source, duration: f64, num_samples: usize) -> Self {
+        let sample_interval = duration / num_samples as f64;
+        let samples = (0..num_samples)
+            .map(|i| source.0.evaluate(i as f64 * sample_interval))
+            .collect::<Vec<_>>();
+
+        let peak = |current: fn(&SourceValues) -> Vector3<f64>| {
+            samples
+                .iter()
+                .map(current)
+                .max_by(|a, b| a.norm().total_cmp(&b.norm()))
+                .and_then(|peak| peak.try_normalize(0.0))
+        };
+
+        let values = if let Some(direction) = peak(|sample| sample.j) {
+            samples
+                .iter()
+                .map(|sample| sample.j.dot(&direction))
+                .collect()
+        }
+        else if let Some(direction) = peak(|sample| sample.m) {
+            samples
+                .iter()
+                .map(|sample| sample.m.dot(&direction))
+                .collect()
+        }
+        else {
+            vec![0.0; num_samples]
+        };
+
+        Self {
+            sample_interval,
+            values,
+        }
+    }
+
+    pub fn points(&self) -> Vec<[f64; 2]> {
+        self.values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| [i as f64 * self.sample_interval, *value])
+            .collect()
+    }
+
+    /// Magnitude spectrum in dB relative to the peak.
+    pub fn spectrum(&self) -> Option<Vec<[f64; 2]>> {
+        let spectrogram = Spectrogram::compute(
+            0.0,
+            self.sample_interval,
+            &self.values,
+            &SpectrogramConfig {
+                window_size: self.values.len(),
+                window: WindowFunction::Hann,
+                ..Default::default()
+            },
+        )?;
+
+        let magnitudes = spectrogram.magnitudes.first()?;
+        let peak = magnitudes.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        Some(
+            spectrogram
+                .frequencies
+                .iter()
+                .zip(magnitudes)
+                .map(|(frequency, magnitude)| [*frequency, magnitude - peak])
+                .collect(),
+        )
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = "time,value\n".to_owned();
+        for [time, value] in self.points() {
+            csv.push_str(&format!("{time},{value}\n"));
+        }
+        csv
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct WaveformView {
+    /// Time span the waveform is shown for
+    duration: f64,
+
+    show_spectrum: bool,
+}
+
+impl Default for WaveformView {
+    fn default() -> Self {
+        Self {
+            duration: 1.0,
+            show_spectrum: true,
+        }
+    }
+}
+
+/// Small plots of a source's waveform and spectrum, with a cursor at the
+/// current simulation time.
+pub fn waveform_preview_ui(
+    ui: &mut egui::Ui,
+    id_salt: impl std::hash::Hash,
+    source: &Source,
+    units: UnitSystem,
+) {
+    let id = ui.id().with(&id_salt);
+    let mut view = ui.data_mut(|data| *data.get_temp_mut_or_default::<WaveformView>(id));
+
+    ui.horizontal(|ui| {
+        ui.label("Duration");
+        let speed = view.duration * 0.01;
+        ui.add(
+            egui::DragValue::new(&mut view.duration)
+                .range(1e-15..=f64::INFINITY)
+                .speed(speed)
+                .suffix(format!(" {}", units.time_unit())),
+        );
+        ui.checkbox(&mut view.show_spectrum, "Spectrum");
+    });
+
+    let waveform = Waveform::sample(source, view.duration, NUM_SAMPLES);
+    let time = simulation_time(ui.ctx());
+    let cursor_color = ui.visuals().warn_fg_color;
+
+    let response = egui_plot::Plot::new(id.with("waveform"))
+        .height(100.0)
+        .width(250.0)
+        .x_axis_label(format!("Time [{}]", units.time_unit()))
+        .show(ui, |plot_ui| {
+            plot_ui.line(egui_plot::Line::new("Waveform", waveform.points()));
+
+            if let Some(time) = time {
+                plot_ui.vline(egui_plot::VLine::new("Simulation Time", time).color(cursor_color));
+            }
+        })
+        .response;
+
+    copy_image_or_csv_context_menu(&response, || waveform.to_csv());
+
+    if view.show_spectrum
+        && let Some(spectrum) = waveform.spectrum()
+    {
+        egui_plot::Plot::new(id.with("spectrum"))
+            .height(100.0)
+            .width(250.0)
+            .x_axis_label(format!("Frequency [{}]", units.frequency_unit()))
+            .y_axis_label("dB")
+            .include_y(-60.0)
+            .show(ui, |plot_ui| {
+                plot_ui.line(egui_plot::Line::new("Spectrum", spectrum));
+            });
+    }
+
+    ui.data_mut(|data| data.insert_temp(id, view));
+}
+
+#[cfg(test)]
+mod tests {
+    use cem_solver::source::{
+        ContinousWave,
+        ScalarSourceFunctionExt,
+        Source,
+    };
+    use nalgebra::Vector3;
+
+    use crate::solver::waveform::Waveform;
+
+    #[test]
+    fn spectrum_peaks_at_carrier_frequency() {
+        let source = Source::from(
+            ContinousWave::new(0.0, 50.0).with_amplitudes(-Vector3::y(), Vector3::zeros()),
+        );
+        let waveform = Waveform::sample(&source, 1.0, 1024);
+
+        // projected onto the peak direction, the waveform starts at a peak
+        assert!((waveform.values[0].abs() - 1.0).abs() < 1e-12);
+
+        let spectrum = waveform.spectrum().unwrap();
+        let [frequency, _] = spectrum
+            .iter()
+            .max_by(|a, b| a[1].total_cmp(&b[1]))
+            .unwrap();
+        assert!((frequency - 50.0).abs() < 1e-9);
+    }
+}
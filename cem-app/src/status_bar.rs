@@ -0,0 +1,184 @@
+//! Status bar at the bottom of the app.
+//!
+//! It shows what's under the pointer (the point in the scene and the solver
+//! cell with its material), the active tool, the selection with its
+//! dimensions, and whether a solver is running.
+
+use cem_scene::spatial::Aabb;
+use cem_solver::material::UnitSystem;
+use nalgebra::Point3;
+
+use crate::{
+    composer::{
+        ComposerState,
+        Composers,
+        dimensions::{
+            format_size,
+            format_size_in_wavelengths,
+            vacuum_wavelength,
+        },
+    },
+    solver::{
+        inspector::CellMaterial,
+        network::format_frequency,
+        runner::SolverRunner,
+    },
+};
+
+/// What the status bar shows about the active composer.
+#[derive(Clone, Debug)]
+pub struct ComposerStatus {
+    pub units: UnitSystem,
+    pub tool: &'static str,
+    pub num_selected: usize,
+
+    /// Bounding box of the selection
+    pub selection_aabb: Option<Aabb>,
+
+    /// Point in the scene under the pointer
+    pub pointer: Option<Point3<f32>>,
+
+    /// Solver cell under the pointer, in the grid of the first FDTD solver
+    /// config.
+    pub cell: Option<CellMaterial>,
+}
+
+#[derive(Debug, Default)]
+pub struct StatusBar {
+    /// Frequency the wavelengths are computed at. This is set to a default for
+    /// the scene's units when the status bar is shown first.
+    frequency: Option<f64>,
+}
+
+impl StatusBar {
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        composers: &mut Composers,
+        solver_runner: &SolverRunner,
+    ) {
+        let status = composers.with_active_mut(ComposerState::status);
+
+        egui::Panel::bottom("status_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                run_status_ui(ui, solver_runner);
+
+                if let Some(status) = &status {
+                    ui.separator();
+                    ui.label(status.tool);
+                    ui.separator();
+                    self.pointer_ui(ui, status);
+                    ui.separator();
+                    self.selection_ui(ui, status);
+                }
+            });
+        });
+    }
+
+    fn pointer_ui(&self, ui: &mut egui::Ui, status: &ComposerStatus) {
+        let Some(pointer) = status.pointer
+        else {
+            ui.weak("No object under pointer");
+            return;
+        };
+
+        ui.label(format!(
+            "({:.4}, {:.4}, {:.4})",
+            pointer.x, pointer.y, pointer.z
+        ));
+
+        if let Some(cell) = &status.cell {
+            ui.label(format!(
+                "Cell ({}, {}, {})",
+                cell.cell.x, cell.cell.y, cell.cell.z
+            ));
+            ui.label(format!(
+                "eps_r = {}, mu_r = {}",
+                cell.material.relative_permittivity, cell.material.relative_permeability
+            ))
+            .on_hover_ui(|ui| cell.show(ui));
+        }
+    }
+
+    fn selection_ui(&mut self, ui: &mut egui::Ui, status: &ComposerStatus) {
+        if status.num_selected == 0 {
+            ui.weak("Nothing selected");
+            return;
+        }
+        ui.label(format!("{} selected", status.num_selected));
+
+        let Some(aabb) = &status.selection_aabb
+        else {
+            return;
+        };
+        let units = status.units;
+        let frequency = *self.frequency.get_or_insert(match units {
+            UnitSystem::Si => 1e9,
+            UnitSystem::Normalized => 1.0,
+        });
+
+        ui.label(format_size(aabb));
+        ui.label(format_size_in_wavelengths(
+            aabb,
+            vacuum_wavelength(units, frequency),
+        ));
+        ui.label("at");
+
+        let mut frequency = frequency;
+        let drag_value = match units {
+            UnitSystem::Si => {
+                egui::DragValue::new(&mut frequency)
+                    .speed(1e6)
+                    .custom_formatter(|frequency, _| format_frequency(frequency))
+            }
+            UnitSystem::Normalized => {
+                egui::DragValue::new(&mut frequency)
+                    .speed(0.01)
+                    .suffix(format!(" {}", units.frequency_unit()))
+            }
+        };
+        if ui
+            .add(drag_value.range(f64::MIN_POSITIVE..=f64::INFINITY))
+            .on_hover_text("Excitation frequency")
+            .changed()
+        {
+            self.frequency = Some(frequency);
+        }
+    }
+}
+
+fn run_status_ui(ui: &mut egui::Ui, solver_runner: &SolverRunner) {
+    let Some(solver) = solver_runner.active_solver()
+    else {
+        if solver_runner.is_preparing() {
+            ui.spinner();
+            ui.label("Preparing solver");
+        }
+        else {
+            ui.weak("Solver idle");
+        }
+        return;
+    };
+    let state = solver.state();
+    let units = UnitSystem::display_units(ui.ctx());
+    let time = format!(
+        "tick {}, t = {:.3e} {}",
+        state.sim_tick,
+        state.sim_time,
+        units.time_unit()
+    );
+
+    if state.finished {
+        ui.label(format!("Solver finished ({time})"));
+    }
+    else if state.paused {
+        ui.colored_label(
+            ui.visuals().warn_fg_color,
+            format!("Solver paused ({time})"),
+        );
+    }
+    else {
+        ui.add(egui::Spinner::new());
+        ui.label(format!("Solver running ({time})"));
+    }
+}
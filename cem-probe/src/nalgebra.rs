@@ -20,36 +20,11 @@ pub struct Isometry3UiConfig {
     pub rotation: UnitQuaternionUiConfig,
 }
 
-impl PropertiesUi for Isometry3<f32> {
-    type Config = Isometry3UiConfig;
-
-    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
-        let mut changed = TrackChanges::default();
-
-        let response = egui::Frame::new()
-            .show(ui, |ui| {
-                changed.track(self.translation.properties_ui(ui, &config.translation));
-                changed.track(self.rotation.properties_ui(ui, &config.rotation));
-            })
-            .response;
-
-        changed.propagated(response)
-    }
-}
-
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Translation3UiConfig {
     pub vector: Vector3UiConfig,
 }
 
-impl PropertiesUi for Translation3<f32> {
-    type Config = Translation3UiConfig;
-
-    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
-        self.vector.properties_ui(ui, &config.vector)
-    }
-}
-
 #[derive(Clone, Copy, Debug)]
 pub struct Vector3UiConfig {
     pub speed: Vector3<f32>,
@@ -63,27 +38,6 @@ impl Default for Vector3UiConfig {
     }
 }
 
-impl PropertiesUi for Vector3<f32> {
-    type Config = Vector3UiConfig;
-
-    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
-        let mut changed = TrackChanges::default();
-
-        let response = ui
-            .horizontal(|ui| {
-                ui.label("X");
-                changed.track(ui.add(egui::DragValue::new(&mut self.x).speed(config.speed.x)));
-                ui.label("Y");
-                changed.track(ui.add(egui::DragValue::new(&mut self.y).speed(config.speed.y)));
-                ui.label("Z");
-                changed.track(ui.add(egui::DragValue::new(&mut self.z).speed(config.speed.z)));
-            })
-            .response;
-
-        changed.propagated(response)
-    }
-}
-
 #[derive(Clone, Copy, Debug)]
 pub struct UnitQuaternionUiConfig {
     pub speed: EulerAngles,
@@ -107,32 +61,6 @@ impl Default for UnitQuaternionUiConfig {
     }
 }
 
-impl PropertiesUi for UnitQuaternion<f32> {
-    type Config = UnitQuaternionUiConfig;
-
-    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
-        let mut euler = EulerAngles::from(*self);
-        let mut changed = TrackChanges::default();
-
-        let response = ui
-            .horizontal(|ui| {
-                ui.label("Roll");
-                changed.track(ui.add(DragAngle::new(&mut euler.roll).speed(config.speed.roll)));
-
-                ui.label("Pitch");
-                changed.track(ui.add(DragAngle::new(&mut euler.pitch).speed(config.speed.pitch)));
-
-                ui.label("Yaw");
-                changed.track(ui.add(DragAngle::new(&mut euler.yaw).speed(config.speed.yaw)));
-            })
-            .response;
-
-        changed.propagated_and(response, || {
-            *self = euler.into();
-        })
-    }
-}
-
 #[derive(Clone, Copy, Debug, Default)]
 pub struct EulerAngles {
     pub roll: f32,
@@ -153,10 +81,117 @@ impl From<EulerAngles> for UnitQuaternion<f32> {
     }
 }
 
-impl PropertiesUi for Point3<f32> {
-    type Config = Vector3UiConfig;
+macro_rules! impl_nalgebra_properties_ui {
+    ($ty:ty) => {
+        impl PropertiesUi for Isometry3<$ty> {
+            type Config = Isometry3UiConfig;
+
+            fn properties_ui(
+                &mut self,
+                ui: &mut egui::Ui,
+                config: &Self::Config,
+            ) -> egui::Response {
+                let mut changed = TrackChanges::default();
+
+                let response = egui::Frame::new()
+                    .show(ui, |ui| {
+                        changed.track(self.translation.properties_ui(ui, &config.translation));
+                        changed.track(self.rotation.properties_ui(ui, &config.rotation));
+                    })
+                    .response;
+
+                changed.propagated(response)
+            }
+        }
+
+        impl PropertiesUi for Translation3<$ty> {
+            type Config = Translation3UiConfig;
 
-    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
-        self.coords.properties_ui(ui, config)
-    }
+            fn properties_ui(
+                &mut self,
+                ui: &mut egui::Ui,
+                config: &Self::Config,
+            ) -> egui::Response {
+                self.vector.properties_ui(ui, &config.vector)
+            }
+        }
+
+        impl PropertiesUi for Vector3<$ty> {
+            type Config = Vector3UiConfig;
+
+            fn properties_ui(
+                &mut self,
+                ui: &mut egui::Ui,
+                config: &Self::Config,
+            ) -> egui::Response {
+                let mut changed = TrackChanges::default();
+
+                let response = ui
+                    .horizontal(|ui| {
+                        ui.label("X");
+                        changed
+                            .track(ui.add(egui::DragValue::new(&mut self.x).speed(config.speed.x)));
+                        ui.label("Y");
+                        changed
+                            .track(ui.add(egui::DragValue::new(&mut self.y).speed(config.speed.y)));
+                        ui.label("Z");
+                        changed
+                            .track(ui.add(egui::DragValue::new(&mut self.z).speed(config.speed.z)));
+                    })
+                    .response;
+
+                changed.propagated(response)
+            }
+        }
+
+        impl PropertiesUi for UnitQuaternion<$ty> {
+            type Config = UnitQuaternionUiConfig;
+
+            fn properties_ui(
+                &mut self,
+                ui: &mut egui::Ui,
+                config: &Self::Config,
+            ) -> egui::Response {
+                let mut euler = EulerAngles::from(self.cast::<f32>());
+                let mut changed = TrackChanges::default();
+
+                let response = ui
+                    .horizontal(|ui| {
+                        ui.label("Roll");
+                        changed.track(
+                            ui.add(DragAngle::new(&mut euler.roll).speed(config.speed.roll)),
+                        );
+
+                        ui.label("Pitch");
+                        changed.track(
+                            ui.add(DragAngle::new(&mut euler.pitch).speed(config.speed.pitch)),
+                        );
+
+                        ui.label("Yaw");
+                        changed
+                            .track(ui.add(DragAngle::new(&mut euler.yaw).speed(config.speed.yaw)));
+                    })
+                    .response;
+
+                changed.propagated_and(response, || {
+                    *self = UnitQuaternion::<f32>::from(euler).cast();
+                })
+            }
+        }
+
+        impl PropertiesUi for Point3<$ty> {
+            type Config = Vector3UiConfig;
+
+            fn properties_ui(
+                &mut self,
+                ui: &mut egui::Ui,
+                config: &Self::Config,
+            ) -> egui::Response {
+                self.coords.properties_ui(ui, config)
+            }
+        }
+    };
 }
+
+impl_nalgebra_properties_ui!(f32);
+impl_nalgebra_properties_ui!(f64);
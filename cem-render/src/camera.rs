@@ -54,11 +54,17 @@ use serde::{
 use wgpu::util::DeviceExt;
 
 use crate::{
+    components::{
+        OVERLAY_FLAGS_SHIFT,
+        OverlayFlags,
+        ViewOverlays,
+    },
     draw_commands::DrawCommandFlags,
     light::{
         AmbientLight,
         PointLight,
     },
+    state::relative_to_render_origin,
 };
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, Component, Reflect)]
@@ -68,6 +74,16 @@ pub struct CameraProjection {
     // coordinate systems
     #[reflect(ignore)]
     projection: Perspective3<f32>,
+
+    /// Size of the viewport in points. This is used to give lines a width in
+    /// screen space.
+    #[serde(skip, default = "default_viewport_size")]
+    #[reflect(ignore)]
+    viewport_size: Vector2<f32>,
+}
+
+fn default_viewport_size() -> Vector2<f32> {
+    Vector2::repeat(1.0)
 }
 
 impl CameraProjection {
@@ -76,12 +92,17 @@ impl CameraProjection {
     /// - `fovy`: Field of view along (camera-local) Y-axis (vertical angle).
     pub fn new(fovy: f32) -> Self {
         let projection = Perspective3::new(1.0, fovy, 0.1, 100.0);
-        Self { projection }
+        Self {
+            projection,
+            viewport_size: default_viewport_size(),
+        }
     }
 
     pub(super) fn set_viewport(&mut self, viewport: &Viewport) {
         if let Some(aspect_ratio) = viewport.aspect_ratio() {
             self.set_aspect_ratio(aspect_ratio);
+            self.viewport_size =
+                Vector2::new(viewport.viewport.width(), viewport.viewport.height());
         }
     }
 
@@ -97,6 +118,14 @@ impl CameraProjection {
         point
     }
 
+    /// Projects a point in the camera's frame to normalized device
+    /// coordinates. This is the inverse of [`Self::unproject`].
+    pub fn project(&self, point: &Point3<f32>) -> Point3<f32> {
+        let mut point = *point;
+        point.z *= -1.0;
+        self.projection.project_point(&point)
+    }
+
     /// Returns angles (horizontal, vertical) that a point makes with the focal
     /// point of the camera.
     pub fn unproject_screen(&self, point: &Point2<f32>) -> Vector2<f32> {
@@ -123,6 +152,11 @@ impl CameraProjection {
         self.projection.aspect()
     }
 
+    /// Distance of the near clipping plane from the camera.
+    pub fn znear(&self) -> f32 {
+        self.projection.znear()
+    }
+
     /// Distance needed to move back from center of AABB to fit the AABB into
     /// FOV, assuming the camera is looking straight onto its XY plane
     ///
@@ -163,6 +197,13 @@ impl Default for CameraProjection {
     }
 }
 
+/// Marks the camera the render origin follows, e.g. the camera of the main
+/// view.
+///
+/// See [`update_render_origin`](crate::systems::update_render_origin).
+#[derive(Clone, Copy, Debug, Default, Component)]
+pub struct ActiveCamera;
+
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Component)]
 pub struct Viewport {
     pub viewport: egui::Rect,
@@ -262,20 +303,24 @@ pub struct CameraData {
     point_light_color: LinSrgba,
     flags: CameraFlags,
     gamma: f32,
-    _padding: [u32; 2],
+    viewport_size: Vector2<f32>,
 }
 
 impl CameraData {
     pub fn new(
         camera_projection: &CameraProjection,
         camera_transform: &GlobalTransform,
+        render_origin: &Point3<f64>,
         clear_color: Option<&ClearColor>,
         ambient_light: Option<&AmbientLight>,
         point_light: Option<&PointLight>,
         camera_config: Option<&CameraConfig>,
+        view_overlays: Option<&ViewOverlays>,
     ) -> Self {
+        let camera_transform = relative_to_render_origin(camera_transform, render_origin);
+
         let mut data = Self {
-            transform: camera_transform.isometry().inverse().to_homogeneous(),
+            transform: camera_transform.inverse().to_homogeneous(),
             projection: {
                 let mut projection = camera_projection.projection.to_homogeneous();
                 // nalgebra assumes we're using a right-handed world coordinate system and a
@@ -284,8 +329,9 @@ impl CameraData {
                 projection[(3, 2)] = 1.0;
                 projection
             },
-            world_position: camera_transform.position().to_homogeneous(),
+            world_position: Point3::from(camera_transform.translation.vector).to_homogeneous(),
             gamma: 1.0,
+            viewport_size: camera_projection.viewport_size,
             ..Self::zeroed()
         };
 
@@ -310,9 +356,17 @@ impl CameraData {
             if camera_config.tone_map {
                 data.flags.insert(CameraFlags::TONE_MAP)
             }
+            if camera_config.see_through {
+                data.flags.insert(CameraFlags::SEE_THROUGH)
+            }
             data.gamma = camera_config.gamma;
         }
 
+        // the shader hides overlay instances if their overlays aren't shown
+        let overlays =
+            view_overlays.map_or_else(OverlayFlags::default, |view_overlays| view_overlays.flags);
+        data.flags |= CameraFlags::from_bits_retain(overlays.bits() << OVERLAY_FLAGS_SHIFT);
+
         data
     }
 }
@@ -324,6 +378,7 @@ bitflags! {
         const AMBIENT_LIGHT = 0b0000_0001;
         const POINT_LIGHT   = 0b0000_0010;
         const TONE_MAP      = 0b0000_0100;
+        const SEE_THROUGH   = 0b0000_1000;
     }
 }
 
@@ -336,6 +391,17 @@ pub struct CameraConfig {
     pub show_wireframe: bool,
     pub show_debug_wireframe: bool,
     pub show_outline: bool,
+
+    // scenes saved before point clouds existed don't have this.
+    #[serde(default = "default_show_points")]
+    pub show_points: bool,
+
+    /// Draw entities tagged with [`SeeThrough`] semi-transparent.
+    ///
+    /// [`SeeThrough`]: crate::components::SeeThrough
+    #[serde(default)]
+    pub see_through: bool,
+
     pub tone_map: bool,
     pub gamma: f32,
 }
@@ -350,9 +416,15 @@ impl CameraConfig {
         flags.set(DrawCommandFlags::WIREFRAME, self.show_wireframe);
         flags.set(DrawCommandFlags::DEBUG_WIREFRAME, self.show_debug_wireframe);
         flags.set(DrawCommandFlags::OUTLINE, self.show_outline);
+        flags.set(DrawCommandFlags::POINTS, self.show_points);
+        flags.set(DrawCommandFlags::SEE_THROUGH, self.see_through);
     }
 }
 
+fn default_show_points() -> bool {
+    true
+}
+
 impl Default for CameraConfig {
     fn default() -> Self {
         Self {
@@ -361,6 +433,8 @@ impl Default for CameraConfig {
             show_wireframe: true,
             show_debug_wireframe: false,
             show_outline: true,
+            show_points: default_show_points(),
+            see_through: false,
             tone_map: true,
             gamma: 2.4,
         }
@@ -395,6 +469,8 @@ impl PropertiesUi for CameraConfig {
                     &mut self.show_debug_wireframe,
                 );
                 label_and_value(ui, "Outline", &mut changes, &mut self.show_outline);
+                label_and_value(ui, "Points", &mut changes, &mut self.show_points);
+                label_and_value(ui, "See-through", &mut changes, &mut self.see_through);
                 label_and_value(ui, "Tone Map", &mut changes, &mut self.tone_map);
                 label_and_value_with_config(
                     ui,
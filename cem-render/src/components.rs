@@ -7,6 +7,7 @@ use bevy_reflect::{
     ReflectSerialize,
     prelude::ReflectDefault,
 };
+use bitflags::bitflags;
 use cem_probe::PropertiesUi;
 use cem_scene::probe::{
     ComponentName,
@@ -30,3 +31,111 @@ impl PropertiesUi for Hidden {
         ui.noop()
     }
 }
+
+/// Tag for entities that are drawn see-through by cameras with
+/// [`CameraConfig::see_through`] enabled.
+///
+/// The rendered alpha of these entities is scaled down, so that anything
+/// inside or behind them stays visible.
+///
+/// [`CameraConfig::see_through`]: crate::camera::CameraConfig::see_through
+#[derive(Copy, Clone, Debug, Default, Component)]
+pub struct SeeThrough;
+
+/// How an entity is depth-composited with the rest of the scene.
+///
+/// Entities without this are depth-tested like [`Self::Normal`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Component)]
+pub enum DepthCompositing {
+    #[default]
+    Normal,
+
+    /// Depth-tested, but pulled slightly towards the camera, so it isn't
+    /// hidden by surfaces it's coplanar with.
+    Biased,
+
+    /// Drawn on top of everything with reduced alpha.
+    XRay,
+}
+
+impl DepthCompositing {
+    pub const ALL: [Self; 3] = [Self::Normal, Self::Biased, Self::XRay];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Normal => "Normal",
+            Self::Biased => "Depth Bias",
+            Self::XRay => "X-Ray",
+        }
+    }
+}
+
+bitflags! {
+    /// Overlays a view can show on top of the scene.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub struct OverlayFlags: u32 {
+        const GRID                = 0b0000_0001;
+        const AXES                = 0b0000_0010;
+        const BOUNDING_BOXES      = 0b0000_0100;
+        const COLLIDER_WIREFRAMES = 0b0000_1000;
+        const OBSERVERS           = 0b0001_0000;
+        const PML                 = 0b0010_0000;
+        const STATISTICS          = 0b0100_0000;
+        const NAVIGATION_CUBE     = 0b1000_0000;
+    }
+}
+
+impl OverlayFlags {
+    pub const ALL: [Self; 8] = [
+        Self::GRID,
+        Self::AXES,
+        Self::BOUNDING_BOXES,
+        Self::COLLIDER_WIREFRAMES,
+        Self::OBSERVERS,
+        Self::PML,
+        Self::STATISTICS,
+        Self::NAVIGATION_CUBE,
+    ];
+
+    /// Label of a single overlay.
+    pub fn label(&self) -> &'static str {
+        match *self {
+            Self::GRID => "Grid",
+            Self::AXES => "Axes",
+            Self::BOUNDING_BOXES => "Bounding Boxes",
+            Self::COLLIDER_WIREFRAMES => "Collider Wireframes",
+            Self::OBSERVERS => "Observer Planes",
+            Self::PML => "PML Regions",
+            Self::STATISTICS => "Statistics",
+            Self::NAVIGATION_CUBE => "Navigation Cube",
+            _ => "Overlays",
+        }
+    }
+}
+
+impl Default for OverlayFlags {
+    fn default() -> Self {
+        Self::GRID | Self::AXES | Self::OBSERVERS | Self::PML | Self::NAVIGATION_CUBE
+    }
+}
+
+/// Bit position of the overlay flags in the instance and camera flags passed
+/// to the shader.
+pub(crate) const OVERLAY_FLAGS_SHIFT: u32 = 16;
+
+/// Tag for entities that are part of an overlay.
+///
+/// They're only drawn by cameras that show all of the entity's overlays in
+/// their [`ViewOverlays`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Component)]
+pub struct Overlay {
+    pub flags: OverlayFlags,
+}
+
+/// Overlays shown by a camera.
+///
+/// Cameras without this show the [default overlays](OverlayFlags::default).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Component)]
+pub struct ViewOverlays {
+    pub flags: OverlayFlags,
+}
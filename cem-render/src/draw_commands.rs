@@ -14,7 +14,11 @@ use std::{
 
 use bevy_ecs::{
     component::Component,
-    entity::Entity,
+    entity::{
+        Entity,
+        EntityHashMap,
+    },
+    resource::Resource,
 };
 use bitflags::bitflags;
 use cem_util::{
@@ -29,44 +33,78 @@ use crate::{
     mesh::{
         Mesh,
         MeshBindGroup,
+        MeshFlags,
     },
     pipeline::Stencil,
     renderer::Renderer,
 };
 
-#[derive(Debug, Default)]
+/// Stores all draw commands that are generated in
+/// [`update_instance_buffer_and_draw_command`](crate::systems::update_instance_buffer_and_draw_command).
+/// Its `finish` method returns the finalized draw command (aggregate) for a
+/// specific camera.
+///
+/// Every scene has its own.
+#[derive(Debug, Default, Resource)]
 pub struct DrawCommandBuffer {
     buffer: ReusableSharedBuffer<DrawCommandBuilderBuffer>,
+
+    /// Drawing order of the transparent meshes for each camera.
+    transparent_orders: EntityHashMap<TransparentOrder>,
 }
 
 impl DrawCommandBuffer {
     pub fn builder(&mut self) -> DrawCommandBuilder<'_> {
+        // cameras that weren't drawn with the current contents are probably gone
+        let generation = self.buffer.generation();
+        self.transparent_orders
+            .retain(|_, order| order.generation == Some(generation));
+
         let mut buffer = self.buffer.write(Default::default);
 
         // very important lol
         buffer.clear();
 
         if buffer.reallocated() {
-            tracing::warn!("draw command buffer reallocated");
+            // this happens until there are enough buffers for the draw commands still
+            // held by views.
+            tracing::debug!("draw command buffer reallocated");
         }
 
         DrawCommandBuilder { buffer }
     }
 
     pub fn finish(
-        &self,
+        &mut self,
         renderer: &Renderer,
         camera_bind_group: wgpu::BindGroup,
         camera_position: Point3<f32>,
         flags: DrawCommandFlags,
         draw_command_info_sink: DrawCommandInfoSink,
     ) -> DrawCommand {
+        let buffer = self.buffer.get();
+
+        let see_through = flags.contains(DrawCommandFlags::SEE_THROUGH);
+        let transparent_order = (flags.contains(DrawCommandFlags::MESH_TRANSPARENT)
+            && buffer.num_sorted(see_through) > 0)
+            .then(|| {
+                self.transparent_orders
+                    .entry(draw_command_info_sink.camera_entity)
+                    .or_default()
+                    .update(
+                        self.buffer.generation(),
+                        camera_position,
+                        see_through,
+                        &buffer,
+                    )
+            });
+
         DrawCommand {
             camera_bind_group,
+            shared_texture_bind_group: renderer.shared_texture_bind_group(),
             clear_pipeline: flags
                 .contains(DrawCommandFlags::CLEAR)
                 .then(|| renderer.clear_pipeline.pipeline.clone()),
-            camera_position,
             flags,
             mesh_opaque_pipeline: flags
                 .contains(DrawCommandFlags::MESH_OPAQUE)
@@ -74,18 +112,77 @@ impl DrawCommandBuffer {
             mesh_transparent_pipeline: flags
                 .contains(DrawCommandFlags::MESH_TRANSPARENT)
                 .then(|| renderer.mesh_transparent_pipeline.pipeline.clone()),
+            mesh_x_ray_pipeline: flags
+                .contains(DrawCommandFlags::MESH_TRANSPARENT)
+                .then(|| renderer.mesh_x_ray_pipeline.pipeline.clone()),
             wireframe_pipeline: flags
                 .intersects(DrawCommandFlags::WIREFRAME | DrawCommandFlags::DEBUG_WIREFRAME)
                 .then(|| renderer.wireframe_pipeline.pipeline.clone()),
             outline_pipeline: flags
                 .contains(DrawCommandFlags::OUTLINE)
                 .then(|| renderer.outline_pipeline.pipeline.clone()),
-            buffer: self.buffer.get(),
+            point_pipeline: flags
+                .contains(DrawCommandFlags::POINTS)
+                .then(|| renderer.point_pipeline.pipeline.clone()),
+            buffer,
+            transparent_order,
             draw_command_info_sink,
         }
     }
 }
 
+/// Order in which transparent meshes are drawn for a camera: furthest first.
+///
+/// This is only sorted again when the camera moves or the draw commands change.
+#[derive(Debug, Default)]
+struct TransparentOrder {
+    /// Generation of the draw command buffer this order was sorted for.
+    generation: Option<u64>,
+
+    camera_position: Point3<f32>,
+
+    /// Whether the see-through meshes were sorted too.
+    see_through: bool,
+
+    /// Indices into the sorted draw meshes, see
+    /// [`DrawCommandBuilderBuffer::sorted_draw_mesh`].
+    order: ReusableSharedBuffer<Vec<u32>>,
+}
+
+impl TransparentOrder {
+    fn update(
+        &mut self,
+        generation: u64,
+        camera_position: Point3<f32>,
+        see_through: bool,
+        buffer: &DrawCommandBuilderBuffer,
+    ) -> Arc<Vec<u32>> {
+        if self.generation != Some(generation)
+            || self.camera_position != camera_position
+            || self.see_through != see_through
+        {
+            let distance_to_camera = |index: &u32| {
+                (buffer.sorted_draw_mesh(*index).depth_reference - camera_position).norm_squared()
+            };
+
+            let mut order = self.order.write(Vec::new);
+            order.clear();
+            order.extend(0..buffer.num_sorted(see_through) as u32);
+            order.sort_unstable_by(|a, b| {
+                distance_to_camera(b)
+                    .partial_cmp(&distance_to_camera(a))
+                    .expect("invalid distance to camera")
+            });
+
+            self.generation = Some(generation);
+            self.camera_position = camera_position;
+            self.see_through = see_through;
+        }
+
+        self.order.get()
+    }
+}
+
 bitflags! {
     #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub struct DrawCommandFlags: u32 {
@@ -95,9 +192,32 @@ bitflags! {
         const WIREFRAME        = 0x0000_0008;
         const OUTLINE          = 0x0000_0010;
         const DEBUG_WIREFRAME  = 0x0000_0020;
+        const POINTS           = 0x0000_0040;
+        const SEE_THROUGH      = 0x0000_0080;
     }
 }
 
+/// How a mesh is blended with what's behind it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MeshBlending {
+    Opaque,
+
+    /// The mesh is drawn opaque, unless the camera has
+    /// [`DrawCommandFlags::SEE_THROUGH`] set. Then it's drawn with the
+    /// transparent meshes.
+    SeeThrough {
+        depth_reference: Point3<f32>,
+    },
+
+    Transparent {
+        depth_reference: Point3<f32>,
+    },
+
+    /// The mesh is drawn on top of everything else, after the transparent
+    /// meshes.
+    XRay,
+}
+
 #[derive(Debug)]
 pub struct DrawCommandBuilder<'a> {
     buffer: ReusableSharedBufferGuard<'a, DrawCommandBuilderBuffer>,
@@ -109,7 +229,31 @@ impl<'a> DrawCommandBuilder<'a> {
         instances: Range<u32>,
         mesh: &Mesh,
         mesh_bind_group: &MeshBindGroup,
-        transparent: Option<Point3<f32>>,
+        texture_bind_group: Option<&wgpu::BindGroup>,
+        blending: MeshBlending,
+        outlined: bool,
+    ) {
+        self.draw_mesh_faces(
+            instances,
+            mesh,
+            0..mesh.num_faces(),
+            mesh_bind_group,
+            texture_bind_group,
+            blending,
+            outlined,
+        );
+    }
+
+    /// Draws only some faces of a mesh, e.g. a material group.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_mesh_faces(
+        &mut self,
+        instances: Range<u32>,
+        mesh: &Mesh,
+        faces: Range<u32>,
+        mesh_bind_group: &MeshBindGroup,
+        texture_bind_group: Option<&wgpu::BindGroup>,
+        blending: MeshBlending,
         outlined: bool,
     ) {
         let mut stencil_reference = Stencil::empty();
@@ -118,19 +262,30 @@ impl<'a> DrawCommandBuilder<'a> {
             stencil_reference.insert(Stencil::OUTLINE);
         }
 
-        let draw_mesh = DrawMesh {
+        let mut draw_mesh = DrawMesh {
             instances,
-            indices: mesh.indices.clone(),
+            indices: mesh.face_indices(faces),
             mesh_bind_group: mesh_bind_group.bind_group.clone(),
+            texture_bind_group: texture_bind_group.cloned(),
             stencil_reference,
-            depth_reference: transparent.unwrap_or_default(),
+            depth_reference: Default::default(),
         };
 
-        if transparent.is_some() {
-            self.buffer.draw_meshes_transparent.push(draw_mesh);
-        }
-        else {
-            self.buffer.draw_meshes_opaque.push(draw_mesh);
+        match blending {
+            MeshBlending::Opaque => {
+                self.buffer.draw_meshes_opaque.push(draw_mesh);
+            }
+            MeshBlending::SeeThrough { depth_reference } => {
+                draw_mesh.depth_reference = depth_reference;
+                self.buffer.draw_meshes_see_through.push(draw_mesh);
+            }
+            MeshBlending::Transparent { depth_reference } => {
+                draw_mesh.depth_reference = depth_reference;
+                self.buffer.draw_meshes_transparent.push(draw_mesh);
+            }
+            MeshBlending::XRay => {
+                self.buffer.draw_meshes_x_ray.push(draw_mesh);
+            }
         }
     }
 
@@ -144,6 +299,7 @@ impl<'a> DrawCommandBuilder<'a> {
             instances,
             indices: mesh.indices.clone(),
             mesh_bind_group: mesh_bind_group.bind_group.clone(),
+            texture_bind_group: None,
             stencil_reference: Stencil::OUTLINE,
             depth_reference: Default::default(),
         })
@@ -155,10 +311,36 @@ impl<'a> DrawCommandBuilder<'a> {
         mesh: &Mesh,
         mesh_bind_group: &MeshBindGroup,
     ) {
+        // the line vertices are already computed here, since this depends on whether
+        // it's a line mesh.
+        let indices = if mesh.flags.contains(MeshFlags::LINES) {
+            line_vertices_for_lines(mesh.indices.clone())
+        }
+        else {
+            line_vertices_for_faces(mesh.indices.clone())
+        };
+
         self.buffer.draw_wireframes.push(DrawMesh {
             instances,
-            indices: mesh.indices.clone(),
+            indices,
             mesh_bind_group: mesh_bind_group.bind_group.clone(),
+            texture_bind_group: None,
+            stencil_reference: Stencil::empty(),
+            depth_reference: Default::default(),
+        })
+    }
+
+    pub fn draw_points(
+        &mut self,
+        instances: Range<u32>,
+        mesh: &Mesh,
+        mesh_bind_group: &MeshBindGroup,
+    ) {
+        self.buffer.draw_points.push(DrawMesh {
+            instances,
+            indices: point_vertices(mesh.indices.clone()),
+            mesh_bind_group: mesh_bind_group.bind_group.clone(),
+            texture_bind_group: None,
             stencil_reference: Stencil::empty(),
             depth_reference: Default::default(),
         })
@@ -169,8 +351,11 @@ impl<'a> DrawCommandBuilder<'a> {
 struct DrawCommandBuilderBuffer {
     draw_meshes_opaque: Vec<DrawMesh>,
     draw_meshes_transparent: Vec<DrawMesh>,
+    draw_meshes_see_through: Vec<DrawMesh>,
+    draw_meshes_x_ray: Vec<DrawMesh>,
     draw_outlines: Vec<DrawMesh>,
     draw_wireframes: Vec<DrawMesh>,
+    draw_points: Vec<DrawMesh>,
 }
 
 impl DrawCommandBuilderBuffer {
@@ -181,14 +366,45 @@ impl DrawCommandBuilderBuffer {
         let Self {
             draw_meshes_opaque,
             draw_meshes_transparent,
+            draw_meshes_see_through,
+            draw_meshes_x_ray,
             draw_outlines,
             draw_wireframes,
+            draw_points,
         } = self;
 
         draw_meshes_opaque.clear();
         draw_meshes_transparent.clear();
+        draw_meshes_see_through.clear();
+        draw_meshes_x_ray.clear();
         draw_outlines.clear();
         draw_wireframes.clear();
+        draw_points.clear();
+    }
+
+    /// Number of meshes that are sorted by distance to the camera.
+    ///
+    /// These are the transparent meshes, followed by the see-through meshes if
+    /// `see_through` is set.
+    fn num_sorted(&self, see_through: bool) -> usize {
+        let mut n = self.draw_meshes_transparent.len();
+        if see_through {
+            n += self.draw_meshes_see_through.len();
+        }
+        n
+    }
+
+    /// Mesh with the given index into the meshes counted by
+    /// [`Self::num_sorted`].
+    fn sorted_draw_mesh(&self, index: u32) -> &DrawMesh {
+        let index = index as usize;
+        let num_transparent = self.draw_meshes_transparent.len();
+        if index < num_transparent {
+            &self.draw_meshes_transparent[index]
+        }
+        else {
+            &self.draw_meshes_see_through[index - num_transparent]
+        }
     }
 }
 
@@ -203,6 +419,10 @@ struct DrawMesh {
     /// the bind group containing the index and vertex buffer for the mesh.
     mesh_bind_group: wgpu::BindGroup,
 
+    /// the entity's own texture bind group. if this is `None`, the shared one
+    /// is used (i.e. the texture table).
+    texture_bind_group: Option<wgpu::BindGroup>,
+
     /// the stencil reference to set before the draw call is issued.
     stencil_reference: Stencil,
 
@@ -213,18 +433,24 @@ struct DrawMesh {
 #[derive(Debug)]
 pub struct DrawCommand {
     camera_bind_group: wgpu::BindGroup,
-    camera_position: Point3<f32>,
+    shared_texture_bind_group: wgpu::BindGroup,
     flags: DrawCommandFlags,
 
     // pipelines
     clear_pipeline: Option<wgpu::RenderPipeline>,
     mesh_opaque_pipeline: Option<wgpu::RenderPipeline>,
     mesh_transparent_pipeline: Option<wgpu::RenderPipeline>,
+    mesh_x_ray_pipeline: Option<wgpu::RenderPipeline>,
     wireframe_pipeline: Option<wgpu::RenderPipeline>,
     outline_pipeline: Option<wgpu::RenderPipeline>,
+    point_pipeline: Option<wgpu::RenderPipeline>,
 
     buffer: Arc<DrawCommandBuilderBuffer>,
 
+    /// See [`TransparentOrder`]. This is `None` if no transparent meshes are
+    /// drawn.
+    transparent_order: Option<Arc<Vec<u32>>>,
+
     draw_command_info_sink: DrawCommandInfoSink,
 }
 
@@ -232,7 +458,7 @@ impl DrawCommand {
     pub fn render(&self, render_pass: &mut wgpu::RenderPass<'static>) {
         let time_start = Instant::now();
 
-        let mut render_pass = RenderPass::from(render_pass);
+        let mut render_pass = RenderPass::new(render_pass, &self.shared_texture_bind_group);
 
         // set camera
         render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
@@ -244,67 +470,92 @@ impl DrawCommand {
         }
 
         // solid opaque mesh
-        if let Some(solid_pipeline) = &self.mesh_opaque_pipeline
-            && !self.buffer.draw_meshes_opaque.is_empty()
+        if let Some(solid_pipeline) = &self.mesh_opaque_pipeline {
+            if !self.buffer.draw_meshes_opaque.is_empty() {
+                render_pass.draw_meshes_with_pipeline(
+                    solid_pipeline,
+                    &self.buffer.draw_meshes_opaque,
+                    identity,
+                );
+            }
+
+            // see-through meshes are only sorted with the transparent ones if the
+            // camera wants to see through them.
+            if !self.flags.contains(DrawCommandFlags::SEE_THROUGH)
+                && !self.buffer.draw_meshes_see_through.is_empty()
+            {
+                render_pass.draw_meshes_with_pipeline(
+                    solid_pipeline,
+                    &self.buffer.draw_meshes_see_through,
+                    identity,
+                );
+            }
+        }
+
+        // point clouds
+        if let Some(point_pipeline) = &self.point_pipeline
+            && !self.buffer.draw_points.is_empty()
         {
             render_pass.draw_meshes_with_pipeline(
-                solid_pipeline,
-                &self.buffer.draw_meshes_opaque,
+                point_pipeline,
+                &self.buffer.draw_points,
                 identity,
             );
         }
 
-        // solid transparent mesh
+        // solid transparent mesh, sorted by distance to camera (furthest first)
         if let Some(solid_pipeline) = &self.mesh_transparent_pipeline
-            && !self.buffer.draw_meshes_transparent.is_empty()
+            && let Some(transparent_order) = &self.transparent_order
         {
-            // sort transparent mesh draw commands by distance to camera (furthest first).
-            // for now we'll allocate here :sobbing:
-            let mut draw_meshes_transparent_sorted = self
-                .buffer
-                .draw_meshes_transparent
-                .iter()
-                .map(|draw_mesh| {
-                    let distance_to_camera =
-                        (draw_mesh.depth_reference - self.camera_position).norm_squared();
-                    (draw_mesh, distance_to_camera)
-                })
-                .collect::<Vec<_>>();
-            draw_meshes_transparent_sorted.sort_unstable_by(|(_, a), (_, b)| {
-                b.partial_cmp(a).expect("invalid distance to camera")
-            });
-
             render_pass.draw_meshes_with_pipeline(
                 solid_pipeline,
-                draw_meshes_transparent_sorted
-                    .into_iter()
-                    .map(|(draw_mesh, _)| draw_mesh),
+                transparent_order
+                    .iter()
+                    .map(|index| self.buffer.sorted_draw_mesh(*index)),
+                identity,
+            );
+        }
+
+        // x-ray mesh, on top of everything drawn so far
+        if let Some(x_ray_pipeline) = &self.mesh_x_ray_pipeline
+            && !self.buffer.draw_meshes_x_ray.is_empty()
+        {
+            render_pass.draw_meshes_with_pipeline(
+                x_ray_pipeline,
+                &self.buffer.draw_meshes_x_ray,
                 identity,
             );
         }
 
         // wireframe mesh
         if let Some(wireframe_pipeline) = &self.wireframe_pipeline {
-            let map_indices = |Range { start, end }| {
-                Range {
-                    start: 2 * start,
-                    end: 2 * end,
-                }
-            };
-
             if self.flags.contains(DrawCommandFlags::DEBUG_WIREFRAME) {
                 if !self.buffer.draw_meshes_opaque.is_empty() {
                     render_pass.draw_meshes_with_pipeline(
                         wireframe_pipeline,
                         &self.buffer.draw_meshes_opaque,
-                        map_indices,
+                        line_vertices_for_faces,
                     );
                 }
                 if !self.buffer.draw_meshes_transparent.is_empty() {
                     render_pass.draw_meshes_with_pipeline(
                         wireframe_pipeline,
                         &self.buffer.draw_meshes_transparent,
-                        map_indices,
+                        line_vertices_for_faces,
+                    );
+                }
+                if !self.buffer.draw_meshes_see_through.is_empty() {
+                    render_pass.draw_meshes_with_pipeline(
+                        wireframe_pipeline,
+                        &self.buffer.draw_meshes_see_through,
+                        line_vertices_for_faces,
+                    );
+                }
+                if !self.buffer.draw_meshes_x_ray.is_empty() {
+                    render_pass.draw_meshes_with_pipeline(
+                        wireframe_pipeline,
+                        &self.buffer.draw_meshes_x_ray,
+                        line_vertices_for_faces,
                     );
                 }
             }
@@ -317,7 +568,7 @@ impl DrawCommand {
                 render_pass.draw_meshes_with_pipeline(
                     wireframe_pipeline,
                     &self.buffer.draw_wireframes,
-                    map_indices,
+                    identity,
                 );
             }
         }
@@ -338,17 +589,46 @@ impl DrawCommand {
             total,
             num_opaque: self.buffer.draw_meshes_opaque.len(),
             num_transparent: self.buffer.draw_meshes_transparent.len(),
+            num_see_through: self.buffer.draw_meshes_see_through.len(),
+            num_x_ray: self.buffer.draw_meshes_x_ray.len(),
             num_outlines: self.buffer.draw_outlines.len(),
         };
         self.draw_command_info_sink.send(draw_command_info);
     }
 }
 
+/// Vertices to draw to render the edges of the faces with the given indices.
+///
+/// Every edge is expanded to a quad (6 vertices) in the vertex shader, and a
+/// face has 3 edges.
+fn line_vertices_for_faces(indices: Range<u32>) -> Range<u32> {
+    6 * indices.start..6 * indices.end
+}
+
+/// Vertices to draw to render the lines of a line mesh with the given indices.
+///
+/// Every line is stored as a face `[a, b, b]`, and expanded to a quad (6
+/// vertices) in the vertex shader.
+fn line_vertices_for_lines(indices: Range<u32>) -> Range<u32> {
+    2 * indices.start..2 * indices.end
+}
+
+/// Vertices to draw to render the points of a point mesh with the given
+/// indices.
+///
+/// Every point is stored as a face `[a, a, a]`, and expanded to a quad (6
+/// vertices) in the vertex shader.
+fn point_vertices(indices: Range<u32>) -> Range<u32> {
+    2 * indices.start..2 * indices.end
+}
+
 #[derive(Clone, Copy, Debug, Component)]
 pub struct DrawCommandInfo {
     pub total: Duration,
     pub num_opaque: usize,
     pub num_transparent: usize,
+    pub num_see_through: usize,
+    pub num_x_ray: usize,
     pub num_outlines: usize,
 }
 
@@ -377,9 +657,34 @@ struct RenderPass<'a> {
     /// We keep track of this, so we only set this if we actually change the
     /// value.
     stencil_reference: Stencil,
+
+    /// Currently set mesh bind group.
+    ///
+    /// Meshes in the same arena page can share a bind group, so this saves us
+    /// some bind group switches.
+    mesh_bind_group: Option<wgpu::BindGroup>,
+
+    /// Bind group used for draws without their own texture bind group.
+    shared_texture_bind_group: &'a wgpu::BindGroup,
+
+    /// Currently set texture bind group.
+    texture_bind_group: Option<wgpu::BindGroup>,
 }
 
 impl<'a> RenderPass<'a> {
+    fn new(
+        inner: &'a mut wgpu::RenderPass<'static>,
+        shared_texture_bind_group: &'a wgpu::BindGroup,
+    ) -> Self {
+        Self {
+            inner,
+            stencil_reference: Default::default(),
+            mesh_bind_group: None,
+            shared_texture_bind_group,
+            texture_bind_group: None,
+        }
+    }
+
     pub fn set_stencil_reference(&mut self, stencil_reference: Stencil) {
         if self.stencil_reference != stencil_reference {
             self.inner.set_stencil_reference(stencil_reference.into());
@@ -387,6 +692,21 @@ impl<'a> RenderPass<'a> {
         }
     }
 
+    pub fn set_mesh_bind_group(&mut self, mesh_bind_group: &wgpu::BindGroup) {
+        if self.mesh_bind_group.as_ref() != Some(mesh_bind_group) {
+            self.inner.set_bind_group(1, mesh_bind_group, &[]);
+            self.mesh_bind_group = Some(mesh_bind_group.clone());
+        }
+    }
+
+    pub fn set_texture_bind_group(&mut self, texture_bind_group: Option<&wgpu::BindGroup>) {
+        let texture_bind_group = texture_bind_group.unwrap_or(self.shared_texture_bind_group);
+        if self.texture_bind_group.as_ref() != Some(texture_bind_group) {
+            self.inner.set_bind_group(2, texture_bind_group, &[]);
+            self.texture_bind_group = Some(texture_bind_group.clone());
+        }
+    }
+
     /// Helper function to render objects with a given pipeline.
     ///
     /// Obviously the pipeline must be compatible. This works
@@ -402,8 +722,8 @@ impl<'a> RenderPass<'a> {
 
         // issue draw commands
         for draw_command in draw_meshes {
-            self.inner
-                .set_bind_group(1, &draw_command.mesh_bind_group, &[]);
+            self.set_mesh_bind_group(&draw_command.mesh_bind_group);
+            self.set_texture_bind_group(draw_command.texture_bind_group.as_ref());
 
             self.set_stencil_reference(draw_command.stencil_reference);
 
@@ -414,15 +734,6 @@ impl<'a> RenderPass<'a> {
     }
 }
 
-impl<'a> From<&'a mut wgpu::RenderPass<'static>> for RenderPass<'a> {
-    fn from(value: &'a mut wgpu::RenderPass<'static>) -> Self {
-        Self {
-            inner: value,
-            stencil_reference: Default::default(),
-        }
-    }
-}
-
 impl<'a> Deref for RenderPass<'a> {
     type Target = wgpu::RenderPass<'static>;
 
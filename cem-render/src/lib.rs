@@ -25,6 +25,10 @@ pub mod texture;
 use std::time::Duration;
 
 use bevy_ecs::resource::Resource;
+use cem_util::wgpu::buffer::{
+    StagingPoolInfo,
+    WriteStagingBeltInfo,
+};
 pub use draw_commands::{
     DrawCommand,
     DrawCommandInfo,
@@ -41,6 +45,25 @@ use crate::{
 pub struct RendererInfo {
     pub prepare_world_staged: StagingInfo,
     pub prepare_world_time: Duration,
+
+    /// Number of instances rendered in the last frame.
+    pub num_instances: usize,
+
+    /// Number of instances that were uploaded to the GPU in the last frame.
+    ///
+    /// Only instances that changed are uploaded.
+    pub num_instances_updated: usize,
+
+    /// Whether the draw list of the previous frame was reused, because nothing
+    /// changed.
+    pub draw_list_reused: bool,
+
+    /// What was staged for the last frame.
+    pub staging_belt: WriteStagingBeltInfo,
+
+    /// The staging pool after the last frame was committed. The pool is shared
+    /// with the solver, so this includes its uploads.
+    pub staging_pool: StagingPoolInfo,
 }
 
 #[derive(Clone, Copy, Debug, Default)]
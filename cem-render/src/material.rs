@@ -4,7 +4,10 @@ use bevy_ecs::{
     component::Component,
     lifecycle::HookContext,
     reflect::ReflectComponent,
-    system::EntityCommands,
+    system::{
+        EntityCommands,
+        Res,
+    },
     world::DeferredWorld,
 };
 use bevy_reflect::{
@@ -26,7 +29,10 @@ use cem_probe::{
     std::NumericPropertyUiConfig,
 };
 use cem_scene::{
-    assets::LoadAsset,
+    assets::{
+        AssetStore,
+        LoadAsset,
+    },
     async_commands::SpawnAsync,
     probe::{
         ComponentName,
@@ -46,12 +52,19 @@ use serde::{
 };
 
 use crate::{
+    renderer::{
+        Fallbacks,
+        MaterialTextures,
+        Renderer,
+    },
     resource::RenderResourceManager,
-    systems::UpdateMeshBindGroupMessage,
+    systems::UpdateTextureBindingMessage,
     texture::{
+        LoadedTexture,
         Sampler,
         TextureLoadError,
         TextureSource,
+        table::TextureSlot,
     },
 };
 
@@ -298,6 +311,12 @@ impl PropertiesUi for Material {
 pub struct Wireframe {
     #[reflect(ignore)]
     pub color: Srgba,
+
+    /// Line width in points.
+    ///
+    /// Lines are expanded into quads in the vertex shader, so this works on all
+    /// backends (line primitives are usually limited to 1 pixel).
+    pub width: f32,
 }
 
 impl Wireframe {
@@ -307,8 +326,14 @@ impl Wireframe {
     {
         Self {
             color: color.into(),
+            width: 1.0,
         }
     }
+
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
 }
 
 impl Default for Wireframe {
@@ -321,51 +346,187 @@ impl PropertiesUi for Wireframe {
     type Config = ();
 
     fn properties_ui(&mut self, ui: &mut egui::Ui, _config: &Self::Config) -> egui::Response {
-        self.color.properties_ui(ui, &Default::default())
+        let mut changes = TrackChanges::default();
+
+        let response = egui::Frame::new()
+            .show(ui, |ui| {
+                label_and_value(ui, "Color", &mut changes, &mut self.color);
+                label_and_value_with_config(
+                    ui,
+                    "Width",
+                    &mut changes,
+                    &mut self.width,
+                    &NumericPropertyUiConfig::Slider { range: 0.5..=10.0 },
+                );
+            })
+            .response;
+
+        changes.propagated(response)
     }
 }
 
 #[derive(Clone, Debug, Component)]
-#[component(on_add = albedo_texture_added, on_insert = albedo_texture_added, on_remove = albedo_texture_removed)]
+#[component(on_add = texture_changed, on_insert = texture_changed, on_remove = texture_changed)]
 pub struct AlbedoTexture {
     pub texture: Arc<wgpu::Texture>,
     pub texture_view: wgpu::TextureView,
     pub transparent: bool,
     pub sampler: Sampler,
-}
 
-fn albedo_texture_added(mut world: DeferredWorld, context: HookContext) {
-    world.write_message(UpdateMeshBindGroupMessage::AlbedoTextureAdded {
-        entity: context.entity,
-    });
-}
-
-fn albedo_texture_removed(mut world: DeferredWorld, context: HookContext) {
-    world.write_message(UpdateMeshBindGroupMessage::AlbedoTextureRemoved {
-        entity: context.entity,
-    });
+    /// Where the texture was loaded from.
+    pub source: TextureSource,
 }
 
 /// Combined ambient occlusion, roughness, metalness map
 #[derive(Clone, Debug, Component)]
-#[component(on_add = material_texture_added, on_insert = material_texture_added, on_remove = material_texture_removed)]
+#[component(on_add = texture_changed, on_insert = texture_changed, on_remove = texture_changed)]
 pub struct MaterialTexture {
     pub texture: Arc<wgpu::Texture>,
     pub texture_view: wgpu::TextureView,
     pub flags: MaterialTextureFlags,
     pub sampler: Sampler,
+
+    /// Where the texture was loaded from.
+    pub source: TextureSource,
 }
 
-fn material_texture_added(mut world: DeferredWorld, context: HookContext) {
-    world.write_message(UpdateMeshBindGroupMessage::MaterialTextureAdded {
+fn texture_changed(mut world: DeferredWorld, context: HookContext) {
+    world.write_message(UpdateTextureBindingMessage {
         entity: context.entity,
     });
 }
 
-fn material_texture_removed(mut world: DeferredWorld, context: HookContext) {
-    world.write_message(UpdateMeshBindGroupMessage::MaterialTextureRemoved {
-        entity: context.entity,
-    });
+/// How the textures of an entity are bound for rendering.
+///
+/// This is inserted by the renderer for entities with an [`AlbedoTexture`] or
+/// [`MaterialTexture`].
+#[derive(Debug, Component)]
+pub enum TextureBinding {
+    /// The textures are in the renderer's texture table, and the instance data
+    /// contains their indices.
+    Table {
+        albedo: Option<TextureSlot>,
+        material: Option<TextureSlot>,
+    },
+
+    /// Binding arrays aren't supported, so the entity has its own bind group
+    /// with its textures.
+    BindGroup {
+        bind_group: wgpu::BindGroup,
+        albedo: bool,
+        material: bool,
+    },
+}
+
+impl TextureBinding {
+    pub(crate) fn new(
+        renderer: &Renderer,
+        albedo_texture: Option<&AlbedoTexture>,
+        material_texture: Option<&MaterialTexture>,
+    ) -> Self {
+        match &renderer.material_textures {
+            MaterialTextures::Table(texture_table) => {
+                let insert = |texture_view, sampler: &Sampler| {
+                    let slot =
+                        texture_table.insert(texture_view, sampler.pick(&renderer.fallbacks));
+                    if slot.is_none() {
+                        tracing::warn!("Texture table is full");
+                    }
+                    slot
+                };
+
+                Self::Table {
+                    albedo: albedo_texture
+                        .and_then(|texture| insert(&texture.texture_view, &texture.sampler)),
+                    material: material_texture
+                        .and_then(|texture| insert(&texture.texture_view, &texture.sampler)),
+                }
+            }
+            MaterialTextures::PerEntity { .. } => {
+                Self::BindGroup {
+                    bind_group: Self::create_bind_group(
+                        &renderer.device,
+                        &renderer.texture_bind_group_layout,
+                        albedo_texture,
+                        material_texture,
+                        &renderer.fallbacks,
+                    ),
+                    albedo: albedo_texture.is_some(),
+                    material: material_texture.is_some(),
+                }
+            }
+        }
+    }
+
+    /// Creates a bind group with the textures of a single entity.
+    ///
+    /// This is only used if there's no texture table.
+    pub(crate) fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        albedo_texture: Option<&AlbedoTexture>,
+        material_texture: Option<&MaterialTexture>,
+        fallbacks: &Fallbacks,
+    ) -> wgpu::BindGroup {
+        let (albedo_sampler, albedo_texture) = albedo_texture.map_or(
+            (&fallbacks.sampler_nearest_clamp, &fallbacks.white),
+            |texture| (texture.sampler.pick(fallbacks), &texture.texture_view),
+        );
+
+        let (material_sampler, material_texture) = material_texture.map_or(
+            (&fallbacks.sampler_nearest_clamp, &fallbacks.white),
+            |texture| (texture.sampler.pick(fallbacks), &texture.texture_view),
+        );
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("texture bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Sampler(albedo_sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(albedo_texture),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(material_sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(material_texture),
+                },
+            ],
+        })
+    }
+
+    /// The entity's own bind group, if it has one.
+    pub fn bind_group(&self) -> Option<&wgpu::BindGroup> {
+        match self {
+            Self::Table { .. } => None,
+            Self::BindGroup { bind_group, .. } => Some(bind_group),
+        }
+    }
+
+    /// Index of the albedo texture in the texture table, or `None` if it
+    /// isn't bound.
+    fn albedo_index(&self) -> Option<u32> {
+        match self {
+            Self::Table { albedo, .. } => albedo.as_ref().map(TextureSlot::index),
+            Self::BindGroup { albedo, .. } => albedo.then_some(0),
+        }
+    }
+
+    /// Index of the material texture in the texture table, or `None` if it
+    /// isn't bound.
+    fn material_index(&self) -> Option<u32> {
+        match self {
+            Self::Table { material, .. } => material.as_ref().map(TextureSlot::index),
+            Self::BindGroup { material, .. } => material.then_some(0),
+        }
+    }
 }
 
 bitflags! {
@@ -400,7 +561,10 @@ pub(super) struct MaterialData {
     // combined from MaterialFlags and MaterialTextureFlags
     flags: u32,
     alpha_threshold: f32,
-    _padding: [u32; 3],
+    // index into the texture table
+    albedo_texture: u32,
+    material_texture: u32,
+    wireframe_width: f32,
 }
 
 impl MaterialData {
@@ -409,12 +573,23 @@ impl MaterialData {
         wireframe: Option<&Wireframe>,
         albedo_texture: Option<&AlbedoTexture>,
         material_texture: Option<&MaterialTexture>,
+        texture_binding: Option<&TextureBinding>,
     ) -> Self {
         let mut data = Self {
             wireframe: LinSrgba::BLACK,
             ..Default::default()
         };
 
+        // textures are only used once they're bound
+        let albedo_texture = albedo_texture
+            .filter(|_| texture_binding.is_some_and(|binding| binding.albedo_index().is_some()));
+        let material_texture = material_texture
+            .filter(|_| texture_binding.is_some_and(|binding| binding.material_index().is_some()));
+        if let Some(texture_binding) = texture_binding {
+            data.albedo_texture = texture_binding.albedo_index().unwrap_or_default();
+            data.material_texture = texture_binding.material_index().unwrap_or_default();
+        }
+
         if let Some(albedo_texture) = albedo_texture {
             // if a texture is present, a non-exitent material will yield white,
             // such that it doesn't affect the color output
@@ -455,6 +630,11 @@ impl MaterialData {
 
         if let Some(wireframe) = wireframe {
             data.wireframe = wireframe.color.into_linear();
+            data.wireframe_width = wireframe.width;
+        }
+        else {
+            // used for debug wireframes
+            data.wireframe_width = 1.0;
         }
 
         // default values
@@ -514,37 +694,62 @@ impl From<TextureSource> for LoadAlbedoTexture {
 }
 
 impl LoadAsset for LoadAlbedoTexture {
-    type Context = (RenderResourceManager<'static>, SpawnAsync<'static>);
+    type Context = (
+        RenderResourceManager<'static>,
+        SpawnAsync<'static>,
+        Res<'static, AssetStore>,
+    );
     type Error = TextureLoadError;
 
     fn load(
         &self,
-        entity: EntityCommands,
-        (render_resource_manager, spawn_async): &mut (RenderResourceManager, SpawnAsync),
+        mut entity: EntityCommands,
+        (render_resource_manager, spawn_async, asset_store): &mut (
+            RenderResourceManager,
+            SpawnAsync,
+            Res<AssetStore>,
+        ),
     ) -> Result<(), TextureLoadError> {
+        let placeholder = render_resource_manager.missing_texture();
+
+        let resolved_source = match self.source.resolve(asset_store) {
+            Ok(resolved_source) => resolved_source,
+            Err(error) => {
+                entity.insert(self.missing(placeholder, error.into()));
+                return Ok(());
+            }
+        };
+
         let entity = entity.id();
         let render_resource_manager = render_resource_manager.as_async();
-        let source = self.source.clone();
-        let transparent = self.transparent;
-        let sampler = self.sampler.clone();
+        let loader = self.clone();
 
         spawn_async.spawn(async move |world| {
-            let loaded_texture = source.load(render_resource_manager).await?;
-
-            let transparent = transparent
-                .or_else(|| {
-                    loaded_texture
-                        .image_info
-                        .map(|info| info.original_color_type.has_alpha())
-                })
-                .unwrap_or_default();
-
-            world.entity(entity).insert(AlbedoTexture {
-                texture: loaded_texture.texture,
-                texture_view: loaded_texture.texture_view,
-                transparent,
-                sampler,
-            });
+            match resolved_source.load(render_resource_manager).await {
+                Ok(loaded_texture) => {
+                    let transparent = loader
+                        .transparent
+                        .or_else(|| {
+                            loaded_texture
+                                .image_info
+                                .map(|info| info.original_color_type.has_alpha())
+                        })
+                        .unwrap_or_default();
+
+                    world.entity(entity).insert(AlbedoTexture {
+                        texture: loaded_texture.texture,
+                        texture_view: loaded_texture.texture_view,
+                        transparent,
+                        sampler: loader.sampler,
+                        source: loader.source,
+                    });
+                }
+                Err(error) => {
+                    world
+                        .entity(entity)
+                        .insert(loader.missing(placeholder, error));
+                }
+            }
 
             Ok::<(), TextureLoadError>(())
         });
@@ -553,6 +758,31 @@ impl LoadAsset for LoadAlbedoTexture {
     }
 }
 
+impl LoadAlbedoTexture {
+    /// The placeholder texture and the problem to show if loading failed.
+    fn missing(
+        &self,
+        placeholder: LoadedTexture,
+        error: TextureLoadError,
+    ) -> (AlbedoTexture, MissingTexture<Self>) {
+        tracing::warn!(source = ?self.source, %error, "albedo texture missing");
+
+        (
+            AlbedoTexture {
+                texture: placeholder.texture,
+                texture_view: placeholder.texture_view,
+                transparent: false,
+                sampler: self.sampler.clone(),
+                source: self.source.clone(),
+            },
+            MissingTexture {
+                loader: self.clone(),
+                error: error.to_string(),
+            },
+        )
+    }
+}
+
 #[derive(Clone, Debug, Component)]
 pub struct LoadMaterialTexture {
     pub source: TextureSource,
@@ -576,29 +806,49 @@ impl LoadMaterialTexture {
 }
 
 impl LoadAsset for LoadMaterialTexture {
-    type Context = (RenderResourceManager<'static>, SpawnAsync<'static>);
+    type Context = (
+        RenderResourceManager<'static>,
+        SpawnAsync<'static>,
+        Res<'static, AssetStore>,
+    );
     type Error = TextureLoadError;
 
     fn load(
         &self,
-        entity: EntityCommands,
-        (render_resource_manager, spawn_async): &mut (RenderResourceManager, SpawnAsync),
+        mut entity: EntityCommands,
+        (render_resource_manager, spawn_async, asset_store): &mut (
+            RenderResourceManager,
+            SpawnAsync,
+            Res<AssetStore>,
+        ),
     ) -> Result<(), TextureLoadError> {
+        let resolved_source = match self.source.resolve(asset_store) {
+            Ok(resolved_source) => resolved_source,
+            Err(error) => {
+                entity.insert(self.missing(error.into()));
+                return Ok(());
+            }
+        };
+
         let entity = entity.id();
         let render_resource_manager = render_resource_manager.as_async();
-        let source = self.source.clone();
-        let flags = self.flags;
-        let sampler = self.sampler.clone();
+        let loader = self.clone();
 
         spawn_async.spawn(async move |world| {
-            let loaded_texture = source.load(render_resource_manager).await?;
-
-            world.entity(entity).insert(MaterialTexture {
-                texture: loaded_texture.texture,
-                texture_view: loaded_texture.texture_view,
-                flags,
-                sampler,
-            });
+            match resolved_source.load(render_resource_manager).await {
+                Ok(loaded_texture) => {
+                    world.entity(entity).insert(MaterialTexture {
+                        texture: loaded_texture.texture,
+                        texture_view: loaded_texture.texture_view,
+                        flags: loader.flags,
+                        sampler: loader.sampler,
+                        source: loader.source,
+                    });
+                }
+                Err(error) => {
+                    world.entity(entity).insert(loader.missing(error));
+                }
+            }
 
             Ok::<(), TextureLoadError>(())
         });
@@ -607,6 +857,31 @@ impl LoadAsset for LoadMaterialTexture {
     }
 }
 
+impl LoadMaterialTexture {
+    /// The problem to show if loading failed.
+    ///
+    /// A checkerboard doesn't make sense for metalness and roughness, so
+    /// without a texture the material's own values are used.
+    fn missing(&self, error: TextureLoadError) -> MissingTexture<Self> {
+        tracing::warn!(source = ?self.source, %error, "material texture missing");
+
+        MissingTexture {
+            loader: self.clone(),
+            error: error.to_string(),
+        }
+    }
+}
+
+/// Added to entities whose texture failed to load.
+///
+/// `L` is the loader that failed. To fix the problem, change its source and
+/// insert it again.
+#[derive(Clone, Debug, Component)]
+pub struct MissingTexture<L: Send + Sync + 'static> {
+    pub loader: L,
+    pub error: String,
+}
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, Component, Reflect)]
 #[reflect(Component, ComponentUi, @ComponentName::new("Outline"), Default, Serialize)]
 pub struct Outline {
@@ -0,0 +1,330 @@
+//! Mega-buffers for mesh data.
+//!
+//! Instead of every mesh owning its own index and vertex buffers, meshes are
+//! suballocated from a few large buffers ("pages"). Meshes in the same page
+//! share their buffers and their bind group. This
+//! means fewer bind group switches when drawing, and lets us batch draws of
+//! meshes in the same page later.
+//!
+//! Pages are never reallocated, so bind groups referencing them stay valid.
+//! Freed ranges are reused by later meshes.
+
+use std::{
+    ops::Range,
+    sync::{
+        Arc,
+        OnceLock,
+    },
+};
+
+use bytemuck::Pod;
+use cem_util::{
+    format_size,
+    wgpu::buffer::WriteStaging,
+};
+use parking_lot::Mutex;
+
+use crate::mesh::Vertex;
+
+/// Number of faces the index buffer of a page can hold.
+const PAGE_FACES: u32 = 1 << 20;
+
+/// Number of vertices the vertex buffer of a page can hold.
+const PAGE_VERTICES: u32 = 1 << 18;
+
+#[derive(Clone, Debug)]
+pub struct MeshArena {
+    device: wgpu::Device,
+    pages: Arc<Mutex<Vec<Arc<MeshArenaPage>>>>,
+}
+
+impl MeshArena {
+    pub fn new(device: wgpu::Device) -> Self {
+        Self {
+            device,
+            pages: Default::default(),
+        }
+    }
+
+    /// Allocates space for a mesh and writes its faces and vertices.
+    ///
+    /// Face indices are relative to the first vertex of the mesh, i.e. the
+    /// shader needs to add [`MeshAllocation::base_vertex`].
+    pub(super) fn allocate(
+        &self,
+        faces: &[[u32; 3]],
+        vertices: &[Vertex],
+        mut write_staging: impl WriteStaging,
+    ) -> MeshAllocation {
+        let num_faces = u32::try_from(faces.len()).expect("too many faces");
+        let num_vertices = u32::try_from(vertices.len()).expect("too many vertices");
+
+        let (page, face_range, vertex_range) = {
+            let mut pages = self.pages.lock();
+
+            let allocated = pages.iter().find_map(|page| {
+                let mut allocator = page.allocator.lock();
+                let face_range = allocator.faces.allocate(num_faces)?;
+                let Some(vertex_range) = allocator.vertices.allocate(num_vertices)
+                else {
+                    allocator.faces.free(face_range);
+                    return None;
+                };
+                Some((page.clone(), face_range, vertex_range))
+            });
+
+            allocated.unwrap_or_else(|| {
+                // meshes that don't fit into a regular page get their own, larger page.
+                let page = Arc::new(MeshArenaPage::new(
+                    &self.device,
+                    pages.len(),
+                    num_faces.max(PAGE_FACES),
+                    num_vertices.max(PAGE_VERTICES),
+                ));
+                pages.push(page.clone());
+
+                let mut allocator = page.allocator.lock();
+                let face_range = allocator.faces.allocate(num_faces).unwrap();
+                let vertex_range = allocator.vertices.allocate(num_vertices).unwrap();
+                drop(allocator);
+
+                (page, face_range, vertex_range)
+            })
+        };
+
+        write_range(&mut write_staging, &page.index_buffer, &face_range, faces);
+        write_range(
+            &mut write_staging,
+            &page.vertex_buffer,
+            &vertex_range,
+            vertices,
+        );
+
+        MeshAllocation {
+            page,
+            faces: face_range,
+            vertices: vertex_range,
+        }
+    }
+
+    /// Number of pages and bytes allocated on the GPU.
+    pub fn allocated_size(&self) -> (usize, u64) {
+        let pages = self.pages.lock();
+        let size = pages
+            .iter()
+            .map(|page| page.index_buffer.size() + page.vertex_buffer.size())
+            .sum();
+        (pages.len(), size)
+    }
+}
+
+fn write_range<T: Pod>(
+    write_staging: &mut impl WriteStaging,
+    buffer: &wgpu::Buffer,
+    range: &Range<u32>,
+    data: &[T],
+) {
+    if data.is_empty() {
+        return;
+    }
+    let element_size = size_of::<T>() as wgpu::BufferAddress;
+    let start = wgpu::BufferAddress::from(range.start) * element_size;
+    let end = wgpu::BufferAddress::from(range.end) * element_size;
+    write_staging.write_buffer_from_slice(buffer.slice(start..end), bytemuck::cast_slice(data));
+}
+
+#[derive(Debug)]
+struct MeshArenaPage {
+    id: usize,
+    index_buffer: wgpu::Buffer,
+    vertex_buffer: wgpu::Buffer,
+    allocator: Mutex<PageAllocator>,
+
+    /// Bind group shared by all meshes in this page.
+    bind_group: OnceLock<wgpu::BindGroup>,
+}
+
+#[derive(Debug)]
+struct PageAllocator {
+    faces: FreeList,
+    vertices: FreeList,
+}
+
+impl MeshArenaPage {
+    fn new(device: &wgpu::Device, id: usize, num_faces: u32, num_vertices: u32) -> Self {
+        let index_buffer_size =
+            wgpu::BufferAddress::from(num_faces) * size_of::<[u32; 3]>() as wgpu::BufferAddress;
+        let vertex_buffer_size =
+            wgpu::BufferAddress::from(num_vertices) * size_of::<Vertex>() as wgpu::BufferAddress;
+
+        tracing::debug!(
+            id,
+            index_buffer_size = %format_size(index_buffer_size),
+            vertex_buffer_size = %format_size(vertex_buffer_size),
+            "allocating mesh arena page"
+        );
+
+        let create_buffer = |label: &str, size| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("render/mesh_arena/{id}/{label}")),
+                size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+
+        Self {
+            id,
+            index_buffer: create_buffer("index", index_buffer_size),
+            vertex_buffer: create_buffer("vertex", vertex_buffer_size),
+            allocator: Mutex::new(PageAllocator {
+                faces: FreeList::new(num_faces),
+                vertices: FreeList::new(num_vertices),
+            }),
+            bind_group: OnceLock::new(),
+        }
+    }
+}
+
+/// Space allocated for a mesh in a [`MeshArena`].
+///
+/// The space is freed when this is dropped.
+#[derive(Debug)]
+pub struct MeshAllocation {
+    page: Arc<MeshArenaPage>,
+    faces: Range<u32>,
+    vertices: Range<u32>,
+}
+
+impl MeshAllocation {
+    pub fn index_buffer(&self) -> &wgpu::Buffer {
+        &self.page.index_buffer
+    }
+
+    pub fn vertex_buffer(&self) -> &wgpu::Buffer {
+        &self.page.vertex_buffer
+    }
+
+    /// Range in the (flat) index buffer.
+    pub fn indices(&self) -> Range<u32> {
+        3 * self.faces.start..3 * self.faces.end
+    }
+
+    pub fn base_vertex(&self) -> u32 {
+        self.vertices.start
+    }
+
+    /// Identifies the page, i.e. the buffers, this allocation is in.
+    pub fn page_id(&self) -> usize {
+        self.page.id
+    }
+
+    /// Bind group shared by all meshes in the same page.
+    pub(crate) fn bind_group(&self, create: impl FnOnce() -> wgpu::BindGroup) -> wgpu::BindGroup {
+        self.page.bind_group.get_or_init(create).clone()
+    }
+}
+
+impl Drop for MeshAllocation {
+    fn drop(&mut self) {
+        let mut allocator = self.page.allocator.lock();
+        allocator.faces.free(self.faces.clone());
+        allocator.vertices.free(self.vertices.clone());
+    }
+}
+
+/// First-fit allocator for ranges of elements in a buffer.
+#[derive(Debug)]
+struct FreeList {
+    /// Free ranges, sorted and non-adjacent.
+    free: Vec<Range<u32>>,
+}
+
+impl FreeList {
+    fn new(capacity: u32) -> Self {
+        Self {
+            free: vec![0..capacity],
+        }
+    }
+
+    fn allocate(&mut self, size: u32) -> Option<Range<u32>> {
+        // empty meshes fit anywhere, even into a full page
+        if size == 0 {
+            return Some(0..0);
+        }
+
+        let index = self
+            .free
+            .iter()
+            .position(|free| free.end - free.start >= size)?;
+
+        let free = &mut self.free[index];
+        let allocated = free.start..free.start + size;
+        free.start += size;
+        if free.is_empty() {
+            self.free.remove(index);
+        }
+
+        Some(allocated)
+    }
+
+    fn free(&mut self, range: Range<u32>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let index = self.free.partition_point(|free| free.start < range.start);
+
+        let merge_previous = index > 0 && self.free[index - 1].end == range.start;
+        let merge_next = self
+            .free
+            .get(index)
+            .is_some_and(|next| next.start == range.end);
+
+        match (merge_previous, merge_next) {
+            (true, true) => {
+                self.free[index - 1].end = self.free[index].end;
+                self.free.remove(index);
+            }
+            (true, false) => self.free[index - 1].end = range.end,
+            (false, true) => self.free[index].start = range.start,
+            (false, false) => self.free.insert(index, range),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FreeList;
+
+    #[test]
+    fn it_reuses_and_merges_freed_ranges() {
+        let mut free_list = FreeList::new(10);
+
+        let a = free_list.allocate(4).unwrap();
+        let b = free_list.allocate(4).unwrap();
+        assert_eq!(a, 0..4);
+        assert_eq!(b, 4..8);
+        assert_eq!(free_list.allocate(3), None);
+
+        free_list.free(a);
+        assert_eq!(free_list.allocate(2), Some(0..2));
+
+        free_list.free(0..2);
+        free_list.free(b);
+        assert_eq!(free_list.free, vec![0..10]);
+    }
+
+    #[test]
+    fn it_allocates_empty_ranges_from_full_lists() {
+        let mut free_list = FreeList::new(4);
+        assert_eq!(free_list.allocate(4), Some(0..4));
+        assert_eq!(free_list.allocate(1), None);
+
+        let empty = free_list.allocate(0).unwrap();
+        assert!(empty.is_empty());
+
+        free_list.free(empty);
+        assert!(free_list.free.is_empty());
+    }
+}
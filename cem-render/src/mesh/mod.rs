@@ -1,5 +1,8 @@
+pub mod arena;
 #[cfg(feature = "parry-mesh")]
 pub mod parry;
+pub mod point_cloud;
+pub mod polyline;
 
 use std::{
     convert::Infallible,
@@ -20,24 +23,25 @@ use bytemuck::{
     Zeroable,
 };
 use cem_scene::assets::LoadAsset;
-use cem_util::format_size;
+use cem_util::{
+    format_size,
+    wgpu::buffer::WriteStaging,
+};
 use nalgebra::{
+    Isometry3,
     Point2,
     Point3,
     Vector3,
     Vector4,
 };
-use wgpu::util::DeviceExt;
+use palette::LinSrgba;
 
 use crate::{
-    material::{
-        AlbedoTexture,
-        MaterialTexture,
-    },
-    renderer::{
-        Fallbacks,
-        Renderer,
+    mesh::arena::{
+        MeshAllocation,
+        MeshArena,
     },
+    renderer::Renderer,
     resource::RenderResourceManager,
     systems::UpdateMeshBindGroupMessage,
 };
@@ -45,14 +49,37 @@ use crate::{
 #[derive(Debug, Component)]
 #[component(on_add = mesh_added, on_insert = mesh_added, on_remove = mesh_removed)]
 pub struct Mesh {
-    pub index_buffer: wgpu::Buffer,
-    pub vertex_buffer: wgpu::Buffer,
+    /// Where the mesh data is stored in the [`MeshArena`].
+    pub allocation: MeshAllocation,
     pub indices: Range<u32>,
     pub base_vertex: u32,
     pub winding_order: WindingOrder,
     pub flags: MeshFlags,
 }
 
+impl Mesh {
+    pub fn num_faces(&self) -> u32 {
+        self.indices.len() as u32 / 3
+    }
+
+    /// Range in the index buffer for the given faces of this mesh.
+    pub fn face_indices(&self, faces: Range<u32>) -> Range<u32> {
+        debug_assert!(faces.end <= self.num_faces(), "face out of range");
+        self.indices.start + 3 * faces.start..self.indices.start + 3 * faces.end
+    }
+}
+
+/// Transforms a mesh from its own frame to the entity's frame.
+///
+/// This is used when the entity's origin is moved, but its geometry should
+/// stay in place. See also [`Collider::offset`].
+///
+/// [`Collider::offset`]: cem_scene::spatial::Collider::offset
+#[derive(Clone, Copy, Debug, Default, Component)]
+pub struct MeshOffset {
+    pub isometry: Isometry3<f32>,
+}
+
 fn mesh_added(mut world: DeferredWorld, context: HookContext) {
     world.write_message(UpdateMeshBindGroupMessage::MeshAdded {
         entity: context.entity,
@@ -71,6 +98,15 @@ bitflags! {
     pub struct MeshFlags: u32 {
         const UVS       = 0x0000_0001;
         const NORMALS   = 0x0000_0002;
+
+        /// The mesh consists of line segments instead of faces. Each "face" in
+        /// the index buffer is a line `[a, b, b]`.
+        const LINES     = 0x0000_0004;
+
+        /// The mesh consists of points. Each "face" in the index buffer is a
+        /// point `[a, a, a]`. The vertex normal holds the (linear) color of the
+        /// point, and the first UV coordinate its size.
+        const POINTS    = 0x0000_0008;
     }
 }
 
@@ -93,49 +129,24 @@ impl MeshBindGroup {
         device: &wgpu::Device,
         mesh_bind_group_layout: &wgpu::BindGroupLayout,
         mesh: &Mesh,
-        albedo_texture: Option<&AlbedoTexture>,
-        material_texture: Option<&MaterialTexture>,
-        fallbacks: &Fallbacks,
     ) -> Self {
-        let (albedo_sampler, albedo_texture) = albedo_texture.map_or(
-            (&fallbacks.sampler_nearest_clamp, &fallbacks.white),
-            |texture| (texture.sampler.pick(fallbacks), &texture.texture_view),
-        );
-
-        let (material_sampler, material_texture) = material_texture.map_or(
-            (&fallbacks.sampler_nearest_clamp, &fallbacks.white),
-            |texture| (texture.sampler.pick(fallbacks), &texture.texture_view),
-        );
-
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("mesh bind group"),
-            layout: mesh_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: mesh.index_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: mesh.vertex_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::Sampler(albedo_sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: wgpu::BindingResource::TextureView(albedo_texture),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 4,
-                    resource: wgpu::BindingResource::Sampler(material_sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 5,
-                    resource: wgpu::BindingResource::TextureView(material_texture),
-                },
-            ],
+        // textures are bound separately, so all meshes in the same arena page can share
+        // their bind group.
+        let bind_group = mesh.allocation.bind_group(|| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("mesh bind group"),
+                layout: mesh_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: mesh.allocation.index_buffer().as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: mesh.allocation.vertex_buffer().as_entire_binding(),
+                    },
+                ],
+            })
         });
 
         Self { bind_group }
@@ -160,15 +171,32 @@ impl MeshBufferBuilder {
         }
     }
 
-    pub fn finish(self, device: &wgpu::Device, label: &str) -> Mesh {
-        let num_indices = self.index_buffer.len();
+    /// Makes sure faces, lines and points aren't mixed in one mesh.
+    fn set_primitive(&mut self, primitive: MeshFlags) {
+        let current = self.flags & (MeshFlags::LINES | MeshFlags::POINTS);
+        assert!(
+            self.index_buffer.is_empty() || current == primitive,
+            "Can't mix faces, lines and points in one mesh"
+        );
+        self.flags.insert(primitive);
+    }
+
+    /// Writes the mesh into the arena.
+    pub fn finish(self, arena: &MeshArena, write_staging: impl WriteStaging, label: &str) -> Mesh {
+        let num_faces = self.index_buffer.len();
         let num_vertices = self.vertex_buffer.len();
 
-        assert_ne!(num_indices, 0, "Mesh with no indices");
+        assert_ne!(num_faces, 0, "Mesh with no indices");
         assert_ne!(num_vertices, 0, "Mesh with no vertices");
-        let winding_order = self
-            .preferred_winding_order
-            .expect("once we get a face, we must have a winding order");
+        let winding_order = if self.flags.intersects(MeshFlags::LINES | MeshFlags::POINTS) {
+            // lines and points don't have a winding order, but the renderer still checks
+            // it.
+            Renderer::WINDING_ORDER
+        }
+        else {
+            self.preferred_winding_order
+                .expect("once we get a face, we must have a winding order")
+        };
 
         #[cfg(debug_assertions)]
         {
@@ -180,37 +208,23 @@ impl MeshBufferBuilder {
             }
         }
 
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("{label}/mesh/index")),
-            contents: bytemuck::cast_slice(&self.index_buffer),
-            usage: wgpu::BufferUsages::STORAGE,
-        });
-
-        let vertex_data = bytemuck::cast_slice(&self.vertex_buffer);
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("{label}/mesh/vertex")),
-            contents: vertex_data,
-            usage: wgpu::BufferUsages::STORAGE,
-        });
-
-        // the indices array in surface_mesh is **not** flat (i.e. it consists of `[u32;
-        // 3]`, one index per face), thus we need to multiply by 3.
-        let num_indices = (self.index_buffer.len() * 3) as u32;
+        let allocation = arena.allocate(&self.index_buffer, &self.vertex_buffer, write_staging);
 
         tracing::debug!(
             ?label,
-            ?num_indices,
+            num_faces,
+            num_vertices,
             flags = ?self.flags,
-            index_buffer_size = %format_size(num_indices * 3),
-            vertex_buffer_size = %format_size(vertex_data.len()),
+            page = allocation.page_id(),
+            index_buffer_size = %format_size(size_of_val(self.index_buffer.as_slice())),
+            vertex_buffer_size = %format_size(size_of_val(self.vertex_buffer.as_slice())),
             "created mesh"
         );
 
         Mesh {
-            index_buffer,
-            vertex_buffer,
-            indices: 0..num_indices,
-            base_vertex: 0,
+            indices: allocation.indices(),
+            base_vertex: allocation.base_vertex(),
+            allocation,
             winding_order,
             flags: self.flags,
         }
@@ -236,9 +250,28 @@ impl MeshBuilder for MeshBufferBuilder {
             face.reverse();
         }
 
+        self.set_primitive(MeshFlags::empty());
         self.index_buffer.push(face);
     }
 
+    fn push_line(&mut self, [a, b]: [u32; 2]) {
+        self.set_primitive(MeshFlags::LINES);
+        self.index_buffer.push([a, b, b]);
+    }
+
+    fn push_point(&mut self, position: Point3<f32>, color: LinSrgba, size: f32) {
+        self.set_primitive(MeshFlags::POINTS);
+
+        let index = u32::try_from(self.vertex_buffer.len()).expect("too many vertices");
+        self.vertex_buffer.push(Vertex {
+            position: position.to_homogeneous(),
+            normal: Vector4::new(color.red, color.green, color.blue, color.alpha),
+            uv: Vector3::new(size, 0.0, 0.0),
+            _padding: [0; _],
+        });
+        self.index_buffer.push([index; 3]);
+    }
+
     fn push_vertex_homogeneous(
         &mut self,
         position: Vector4<f32>,
@@ -303,18 +336,27 @@ impl LoadMesh {
         }
     }
 
-    // fixme: we don't return a Result here because it's just too easy to
-    // accidentally just stick the whole Result into an entity as component. You
-    // won't even get a warning because, well, the Result is used, but the mesh will
-    // not work.
-    // It's probably a better idea to defer the error until we start loading where
-    // we can emit it anyway.
+    /// Mesh of a shape whose mesh generation can't fail.
+    ///
+    /// Use [`try_from_shape`](Self::try_from_shape) for other shapes.
     pub fn from_shape<S>(shape: S, config: S::Config) -> Self
+    where
+        S: IntoGenerateMesh<Error = Infallible>,
+        S::GenerateMesh: Debug + Send + Sync + 'static,
+    {
+        let Ok(generator) = shape.into_generate_mesh(config);
+        Self::from_generator(generator)
+    }
+
+    // note: it's easy to accidentally stick the whole Result into an entity as
+    // component. You won't even get a warning because, well, the Result is used,
+    // but the mesh will not work. So don't forget the `?`.
+    pub fn try_from_shape<S>(shape: S, config: S::Config) -> Result<Self, S::Error>
     where
         S: IntoGenerateMesh,
         S::GenerateMesh: Debug + Send + Sync + 'static,
     {
-        Self::from_generator(shape.into_generate_mesh(config).unwrap())
+        Ok(Self::from_generator(shape.into_generate_mesh(config)?))
     }
 }
 
@@ -331,7 +373,7 @@ impl LoadAsset for LoadMesh {
             LoadMesh::Generator { generator } => {
                 let mut mesh_builder = MeshBufferBuilder::new(Some(Renderer::WINDING_ORDER));
                 generator.generate(&mut mesh_builder, true, true);
-                mesh_builder.finish(context.device(), &format!("{generator:?}"))
+                context.create_mesh(mesh_builder, &format!("{generator:?}"))
             } //LoadMesh::File { path: _ } => todo!("load mesh from file"),
         };
 
@@ -354,6 +396,18 @@ pub trait MeshBuilder {
 
     fn push_face(&mut self, face: [u32; 3], winding_order: WindingOrder);
 
+    /// Adds a line segment.
+    ///
+    /// A mesh can either consist of faces or lines. Line meshes are only
+    /// rendered with a [`Wireframe`](crate::material::Wireframe).
+    fn push_line(&mut self, line: [u32; 2]);
+
+    /// Adds a point with its own vertex.
+    ///
+    /// Point meshes are rendered as round splats of `size` points (the UI
+    /// unit) if the entity has a [`Material`](crate::material::Material).
+    fn push_point(&mut self, position: Point3<f32>, color: LinSrgba, size: f32);
+
     fn push_vertex(
         &mut self,
         position: Point3<f32>,
@@ -378,7 +432,7 @@ pub trait MeshBuilder {
 pub trait IntoGenerateMesh {
     type Config;
     type GenerateMesh: GenerateMesh;
-    type Error: std::error::Error;
+    type Error: std::error::Error + Send + Sync + 'static;
 
     fn into_generate_mesh(self, config: Self::Config) -> Result<Self::GenerateMesh, Self::Error>;
 }
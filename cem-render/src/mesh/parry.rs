@@ -5,6 +5,7 @@ use parry3d::shape::{
     Ball,
     Cuboid,
     Cylinder,
+    TriMesh,
 };
 
 use crate::mesh::{
@@ -150,3 +151,29 @@ impl IntoGenerateMesh for Cylinder {
         })
     }
 }
+
+/// Face `i` of the generated mesh is triangle `i` of the [`TriMesh`], so
+/// [`MaterialGroups`](cem_scene::material_groups::MaterialGroups) apply to
+/// both the rendered mesh and the collider.
+impl GenerateMesh for TriMesh {
+    fn generate(&self, mesh_builder: &mut dyn MeshBuilder, normals: bool, uvs: bool) {
+        let _ = (normals, uvs);
+        mesh_builder.reserve(self.indices().len(), self.vertices().len());
+        write_parry_to_trimesh_output_into_mesh_builder(
+            mesh_builder,
+            (self.vertices().to_vec(), self.indices().to_vec()),
+        );
+    }
+}
+
+impl IntoGenerateMesh for TriMesh {
+    type Config = ();
+    type GenerateMesh = Self;
+    type Error = Infallible;
+
+    fn into_generate_mesh(self, config: Self::Config) -> Result<Self::GenerateMesh, Self::Error> {
+        #[allow(clippy::let_unit_value)]
+        let _ = config;
+        Ok(self)
+    }
+}
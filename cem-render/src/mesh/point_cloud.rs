@@ -0,0 +1,63 @@
+use nalgebra::Point3;
+use palette::Srgba;
+
+use crate::mesh::{
+    GenerateMesh,
+    MeshBuilder,
+};
+
+/// A set of colored points.
+///
+/// This generates a point mesh, which is rendered as round splats. Use it to
+/// visualize sampled data, e.g. field values at probe locations.
+#[derive(Clone, Debug, Default)]
+pub struct PointCloud {
+    pub points: Vec<Point>,
+}
+
+impl PointCloud {
+    pub fn new(points: impl IntoIterator<Item = Point>) -> Self {
+        Self {
+            points: points.into_iter().collect(),
+        }
+    }
+
+    pub fn push(&mut self, point: Point) {
+        self.points.push(point);
+    }
+}
+
+impl FromIterator<Point> for PointCloud {
+    fn from_iter<T: IntoIterator<Item = Point>>(iter: T) -> Self {
+        Self::new(iter)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Point {
+    pub position: Point3<f32>,
+    pub color: Srgba,
+
+    /// Diameter of the point on screen in points (the UI unit).
+    pub size: f32,
+}
+
+impl Point {
+    pub fn new(position: Point3<f32>, color: Srgba, size: f32) -> Self {
+        Self {
+            position,
+            color,
+            size,
+        }
+    }
+}
+
+impl GenerateMesh for PointCloud {
+    fn generate(&self, mesh_builder: &mut dyn MeshBuilder, _normals: bool, _uvs: bool) {
+        mesh_builder.reserve(self.points.len(), self.points.len());
+
+        for point in &self.points {
+            mesh_builder.push_point(point.position, point.color.into_linear(), point.size);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use nalgebra::Point3;
+
+use crate::mesh::{
+    GenerateMesh,
+    MeshBuilder,
+};
+
+/// A line through a sequence of points.
+///
+/// This generates a line mesh, which is rendered with the entity's
+/// [`Wireframe`](crate::material::Wireframe). Use it for wires, field lines,
+/// annotations, etc.
+#[derive(Clone, Debug, Default)]
+pub struct Polyline {
+    pub points: Vec<Point3<f32>>,
+
+    /// Connect the last point with the first.
+    pub closed: bool,
+}
+
+impl Polyline {
+    pub fn new(points: impl IntoIterator<Item = Point3<f32>>) -> Self {
+        Self {
+            points: points.into_iter().collect(),
+            closed: false,
+        }
+    }
+
+    pub fn closed(mut self) -> Self {
+        self.closed = true;
+        self
+    }
+
+    fn num_segments(&self) -> usize {
+        match self.points.len() {
+            0 | 1 => 0,
+            2 => 1,
+            n if self.closed => n,
+            n => n - 1,
+        }
+    }
+}
+
+impl GenerateMesh for Polyline {
+    fn generate(&self, mesh_builder: &mut dyn MeshBuilder, _normals: bool, _uvs: bool) {
+        let num_segments = self.num_segments();
+        mesh_builder.reserve(num_segments, self.points.len());
+
+        for point in &self.points {
+            mesh_builder.push_vertex(*point, None, None);
+        }
+
+        let num_points = self.points.len() as u32;
+        for i in 0..num_segments as u32 {
+            mesh_builder.push_line([i, (i + 1) % num_points]);
+        }
+    }
+}
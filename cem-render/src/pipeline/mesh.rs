@@ -15,10 +15,16 @@ pub struct MeshPipelineDescriptor<'a> {
     pub renderer_config: &'a RendererConfig,
     pub camera_bind_group_layout: &'a wgpu::BindGroupLayout,
     pub mesh_bind_group_layout: &'a wgpu::BindGroupLayout,
+    pub texture_bind_group_layout: &'a wgpu::BindGroupLayout,
     pub shader_module: &'a wgpu::ShaderModule,
     pub depth_state: DepthState,
     pub stencil_state: wgpu::StencilState,
     pub topology: wgpu::PrimitiveTopology,
+
+    /// Disables back-face culling, e.g. for geometry generated in the vertex
+    /// shader.
+    pub double_sided: bool,
+
     pub vertex_shader_entry_point: &'a str,
     pub fragment_shader_entry_point: &'a str,
     pub alpha_blending: bool,
@@ -41,7 +47,7 @@ impl MeshPipeline {
             | wgpu::PrimitiveTopology::LineList
             | wgpu::PrimitiveTopology::LineStrip => false,
             wgpu::PrimitiveTopology::TriangleList | wgpu::PrimitiveTopology::TriangleStrip => {
-                CULL_BACK_FACES
+                CULL_BACK_FACES && !descriptor.double_sided
             }
         };
         let cull_mode = cull_back_faces.then_some(wgpu::Face::Back);
@@ -51,6 +57,7 @@ impl MeshPipeline {
             bind_group_layouts: &[
                 descriptor.camera_bind_group_layout,
                 descriptor.mesh_bind_group_layout,
+                descriptor.texture_bind_group_layout,
             ],
             push_constant_ranges: &[],
         });
@@ -1,5 +1,3 @@
-use std::sync::Arc;
-
 use bevy_ecs::schedule::{
     IntoScheduleConfigs,
     SystemSet,
@@ -13,7 +11,9 @@ use cem_scene::{
 use cem_util::wgpu::buffer::StagingPool;
 
 use crate::{
+    RendererInfo,
     command,
+    draw_commands::DrawCommandBuffer,
     material::{
         LoadAlbedoTexture,
         LoadMaterialTexture,
@@ -28,10 +28,16 @@ use crate::{
         RenderResourceTransactionState,
         SharedMipMapCache,
     },
-    state::RendererState,
+    state::{
+        FrameStaging,
+        InstanceBuffer,
+        InstanceBufferReallocated,
+        RenderOrigin,
+    },
     systems::{
         self,
         UpdateMeshBindGroupMessage,
+        UpdateTextureBindingMessage,
         handle_command_queue,
     },
     texture::{
@@ -50,9 +56,16 @@ pub enum RenderSystems {
     HandleCommandQueue,
 }
 
+/// Sets up rendering for a scene.
+///
+/// The plugin can be registered with multiple scenes (e.g. one per open
+/// file). They will all share the same pipelines, mesh arena and texture cache.
+/// Only state that depends on the scene's contents (instances, draw commands,
+/// camera bind groups) is created per scene.
 #[derive(Clone, Debug)]
 pub struct RenderPlugin {
     renderer: SharedRenderer,
+    texture_cache: TextureCache,
     mipmap_cache: Option<SharedMipMapCache>,
 }
 
@@ -65,7 +78,8 @@ impl RenderPlugin {
     ) -> Self {
         let renderer = Renderer::new(device, queue, staging_pool, config);
         Self {
-            renderer: SharedRenderer(Arc::new(renderer)),
+            renderer: SharedRenderer::new(renderer),
+            texture_cache: TextureCache::default(),
             mipmap_cache: None,
         }
     }
@@ -87,16 +101,22 @@ impl Plugin for RenderPlugin {
         }
 
         builder
-            // todo: share the texture cache between worlds
-            .insert_resource(TextureCache::default())
-            // insert the shared renderer as resource
+            // shared between all scenes
+            .insert_resource(self.texture_cache.clone())
             .insert_resource(self.renderer.clone())
-            .insert_resource(RendererState::new(&self.renderer.device))
+            // per-scene state
+            .insert_resource(InstanceBuffer::new(&self.renderer.device))
+            .insert_resource(DrawCommandBuffer::default())
+            .insert_resource(FrameStaging::default())
+            .insert_resource(RenderOrigin::default())
+            .insert_resource(RendererInfo::default())
             .insert_resource(RenderResourceTransactionState::default())
             .insert_resource(command_sender)
             .insert_resource(command_receiver)
             // register messages
             .register_message::<UpdateMeshBindGroupMessage>()
+            .register_message::<UpdateTextureBindingMessage>()
+            .register_message::<InstanceBufferReallocated>()
             // add various rendering systems
             .add_systems(
                 schedule::PostUpdate,
@@ -113,7 +133,11 @@ impl Plugin for RenderPlugin {
                             systems::destroy_camera_bind_groups,
                         )
                             .in_set(RenderSystems::UpdateCameras),
-                        systems::update_mesh_bind_groups.in_set(RenderSystems::UpdateMeshes),
+                        (
+                            systems::update_mesh_bind_groups,
+                            systems::update_texture_bindings,
+                        )
+                            .in_set(RenderSystems::UpdateMeshes),
                     )
                         .before(RenderSystems::EmitDrawList),
                     // the actual rendering
@@ -121,6 +145,7 @@ impl Plugin for RenderPlugin {
                         (systems::begin_frame, systems::commit_resource_transaction)
                             .in_set(RenderSystems::Begin),
                         (
+                            systems::update_render_origin,
                             systems::update_instance_buffer_and_draw_command,
                             // note: `create_camera_bind_groups` is run here after
                             // `update_instance_buffer_and_draw_command` because it needs to
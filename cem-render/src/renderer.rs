@@ -13,11 +13,19 @@ use cem_util::wgpu::{
         WriteStagingTransaction,
     },
     create_texture_from_linsrgba,
+    image::{
+        ImageTextureExt,
+        MipLevels,
+    },
 };
 use palette::LinSrgba;
 
 use crate::{
-    mesh::WindingOrder,
+    material::TextureBinding,
+    mesh::{
+        WindingOrder,
+        arena::MeshArena,
+    },
     pipeline::{
         DepthState,
         Stencil,
@@ -32,6 +40,7 @@ use crate::{
             StencilStateExt,
         },
     },
+    texture::table::TextureTable,
 };
 
 #[derive(Clone, Copy, Debug)]
@@ -41,6 +50,9 @@ pub struct RendererConfig {
     pub multisample_count: NonZero<u32>,
 }
 
+/// Renderer state that doesn't depend on the scene.
+///
+/// This is shared between all scenes (see [`SharedRenderer`]).
 #[derive(Debug)]
 pub struct Renderer {
     pub device: wgpu::Device,
@@ -51,14 +63,26 @@ pub struct Renderer {
     pub camera_bind_group_layout: wgpu::BindGroupLayout,
     pub mesh_bind_group_layout: wgpu::BindGroupLayout,
 
+    /// Layout of the texture bind group. This is either the layout of the
+    /// texture table, or of the bind group of a single entity.
+    pub texture_bind_group_layout: wgpu::BindGroupLayout,
+
     pub clear_pipeline: ClearPipeline,
     pub mesh_opaque_pipeline: MeshPipeline,
     pub mesh_transparent_pipeline: MeshPipeline,
+    pub mesh_x_ray_pipeline: MeshPipeline,
     pub wireframe_pipeline: MeshPipeline,
     pub outline_pipeline: MeshPipeline,
+    pub point_pipeline: MeshPipeline,
 
     /// Fallbacks for textures and sampler
     pub fallbacks: Fallbacks,
+
+    /// Index and vertex buffers that meshes are allocated from
+    pub mesh_arena: MeshArena,
+
+    /// How material textures are bound
+    pub material_textures: MaterialTextures,
 }
 
 impl Renderer {
@@ -71,8 +95,17 @@ impl Renderer {
     /// (the ones generated by parry clockwise apparently)
     pub const WINDING_ORDER: WindingOrder = WindingOrder::CounterClockwise;
 
-    pub const MESH_SHADER_MODULE: wgpu::ShaderModuleDescriptor<'static> =
-        wgpu::include_wgsl!("shader.wgsl");
+    /// Shader source if the texture table is used.
+    pub const MESH_SHADER_SOURCE_TEXTURE_TABLE: &str = concat!(
+        include_str!("shader.wgsl"),
+        include_str!("shader_texture_table.wgsl")
+    );
+
+    /// Shader source if every entity has its own texture bind group.
+    pub const MESH_SHADER_SOURCE_TEXTURE_SINGLE: &str = concat!(
+        include_str!("shader.wgsl"),
+        include_str!("shader_texture_single.wgsl")
+    );
 
     // We need to flip the interpretation of the winding order here, because this
     // actually depends on the orientation of our Z axis.
@@ -125,6 +158,24 @@ impl Renderer {
                 }
             };
 
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("mesh_bind_group_layout"),
+                entries: &[
+                    // index buffer
+                    vertex_buffer(0),
+                    // vertex buffer
+                    vertex_buffer(1),
+                ],
+            })
+        };
+
+        let use_texture_table = TextureTable::is_supported(&device);
+        tracing::debug!(use_texture_table, "creating renderer");
+
+        let texture_bind_group_layout = if use_texture_table {
+            TextureTable::bind_group_layout(&device)
+        }
+        else {
             let texture = |binding| {
                 wgpu::BindGroupLayoutEntry {
                     binding,
@@ -148,27 +199,34 @@ impl Renderer {
             };
 
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("mesh_bind_group_layout"),
+                label: Some("texture_bind_group_layout"),
                 entries: &[
-                    // index buffer
-                    vertex_buffer(0),
-                    // vertex buffer
-                    vertex_buffer(1),
                     // sampler - albedo
-                    sampler(2),
+                    sampler(0),
                     // texture - albedo
-                    texture(3),
-                    // sampler - albedo
-                    sampler(4),
+                    texture(1),
+                    // sampler - material
+                    sampler(2),
                     // texture - material
-                    texture(5),
+                    texture(3),
                 ],
             })
         };
 
         // this is actually used for everything, not just meshes. but we might split it
         // into clear, mesh, etc.
-        let mesh_shader_module = device.create_shader_module(Self::MESH_SHADER_MODULE);
+        let mesh_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("shader.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(
+                if use_texture_table {
+                    Self::MESH_SHADER_SOURCE_TEXTURE_TABLE
+                }
+                else {
+                    Self::MESH_SHADER_SOURCE_TEXTURE_SINGLE
+                }
+                .into(),
+            ),
+        });
 
         let clear_pipeline = ClearPipeline::new(
             &device,
@@ -186,10 +244,12 @@ impl Renderer {
                 renderer_config: &config,
                 camera_bind_group_layout: &camera_bind_group_layout,
                 mesh_bind_group_layout: &mesh_bind_group_layout,
+                texture_bind_group_layout: &texture_bind_group_layout,
                 shader_module: &mesh_shader_module,
                 depth_state: DepthState::new(true, wgpu::CompareFunction::Less),
                 stencil_state: wgpu::StencilState::new(Some(Stencil::OUTLINE), None),
                 topology: wgpu::PrimitiveTopology::TriangleList,
+                double_sided: false,
                 vertex_shader_entry_point: "vs_main_solid",
                 fragment_shader_entry_point: "fs_main_solid",
                 alpha_blending: false,
@@ -203,10 +263,33 @@ impl Renderer {
                 renderer_config: &config,
                 camera_bind_group_layout: &camera_bind_group_layout,
                 mesh_bind_group_layout: &mesh_bind_group_layout,
+                texture_bind_group_layout: &texture_bind_group_layout,
                 shader_module: &mesh_shader_module,
                 depth_state: DepthState::new(false, wgpu::CompareFunction::Less),
                 stencil_state: wgpu::StencilState::new(Some(Stencil::OUTLINE), None),
                 topology: wgpu::PrimitiveTopology::TriangleList,
+                double_sided: false,
+                vertex_shader_entry_point: "vs_main_solid",
+                fragment_shader_entry_point: "fs_main_solid",
+                alpha_blending: true,
+            },
+        );
+
+        // like the transparent pipeline, but ignores the depth buffer, so the meshes
+        // are drawn on top.
+        let mesh_x_ray_pipeline = MeshPipeline::new(
+            &device,
+            &MeshPipelineDescriptor {
+                label: "render/mesh/x_ray",
+                renderer_config: &config,
+                camera_bind_group_layout: &camera_bind_group_layout,
+                mesh_bind_group_layout: &mesh_bind_group_layout,
+                texture_bind_group_layout: &texture_bind_group_layout,
+                shader_module: &mesh_shader_module,
+                depth_state: DepthState::new(false, wgpu::CompareFunction::Always),
+                stencil_state: wgpu::StencilState::new(Some(Stencil::OUTLINE), None),
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                double_sided: false,
                 vertex_shader_entry_point: "vs_main_solid",
                 fragment_shader_entry_point: "fs_main_solid",
                 alpha_blending: true,
@@ -220,10 +303,14 @@ impl Renderer {
                 renderer_config: &config,
                 camera_bind_group_layout: &camera_bind_group_layout,
                 mesh_bind_group_layout: &mesh_bind_group_layout,
+                texture_bind_group_layout: &texture_bind_group_layout,
                 shader_module: &mesh_shader_module,
                 depth_state: DepthState::new(true, wgpu::CompareFunction::LessEqual),
                 stencil_state: Default::default(),
-                topology: wgpu::PrimitiveTopology::LineList,
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                // lines are expanded to quads facing the camera, but we don't know which way
+                // they're wound.
+                double_sided: true,
                 vertex_shader_entry_point: "vs_main_wireframe",
                 fragment_shader_entry_point: "fs_main_flat",
                 alpha_blending: true,
@@ -240,6 +327,7 @@ impl Renderer {
                 renderer_config: &config,
                 camera_bind_group_layout: &camera_bind_group_layout,
                 mesh_bind_group_layout: &mesh_bind_group_layout,
+                texture_bind_group_layout: &texture_bind_group_layout,
                 shader_module: &mesh_shader_module,
                 depth_state: DepthState::new(false, wgpu::CompareFunction::Always),
                 stencil_state: wgpu::StencilState::new(
@@ -250,12 +338,33 @@ impl Renderer {
                     }),
                 ),
                 topology: wgpu::PrimitiveTopology::TriangleList,
+                double_sided: false,
                 vertex_shader_entry_point: "vs_main_outline",
                 fragment_shader_entry_point: "fs_main_flat",
                 alpha_blending: true,
             },
         );
 
+        let point_pipeline = MeshPipeline::new(
+            &device,
+            &MeshPipelineDescriptor {
+                label: "render/mesh/point",
+                renderer_config: &config,
+                camera_bind_group_layout: &camera_bind_group_layout,
+                mesh_bind_group_layout: &mesh_bind_group_layout,
+                texture_bind_group_layout: &texture_bind_group_layout,
+                shader_module: &mesh_shader_module,
+                depth_state: DepthState::new(true, wgpu::CompareFunction::Less),
+                stencil_state: Default::default(),
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                // same as the wireframe: points are expanded to quads in the vertex shader.
+                double_sided: true,
+                vertex_shader_entry_point: "vs_main_point",
+                fragment_shader_entry_point: "fs_main_point",
+                alpha_blending: true,
+            },
+        );
+
         let mut command_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("render/init"),
         });
@@ -267,6 +376,23 @@ impl Renderer {
         write_staging.commit();
         queue.submit([command_encoder.finish()]);
 
+        let mesh_arena = MeshArena::new(device.clone());
+
+        let material_textures = if use_texture_table {
+            MaterialTextures::Table(TextureTable::new())
+        }
+        else {
+            MaterialTextures::PerEntity {
+                untextured_bind_group: TextureBinding::create_bind_group(
+                    &device,
+                    &texture_bind_group_layout,
+                    None,
+                    None,
+                    &fallbacks,
+                ),
+            }
+        };
+
         Self {
             device,
             queue,
@@ -274,20 +400,70 @@ impl Renderer {
             config,
             camera_bind_group_layout,
             mesh_bind_group_layout,
+            texture_bind_group_layout,
             clear_pipeline,
             mesh_opaque_pipeline,
             mesh_transparent_pipeline,
+            mesh_x_ray_pipeline,
             wireframe_pipeline,
             outline_pipeline,
+            point_pipeline,
             fallbacks,
+            mesh_arena,
+            material_textures,
+        }
+    }
+
+    /// Texture bind group for draws that don't have their own.
+    ///
+    /// With the texture table this is used for all draws.
+    pub fn shared_texture_bind_group(&self) -> wgpu::BindGroup {
+        match &self.material_textures {
+            MaterialTextures::Table(texture_table) => {
+                texture_table.bind_group(
+                    &self.device,
+                    &self.texture_bind_group_layout,
+                    &self.fallbacks,
+                )
+            }
+            MaterialTextures::PerEntity {
+                untextured_bind_group,
+            } => untextured_bind_group.clone(),
         }
     }
 }
 
-// todo: render-refactor: this should be as hidden as possible. but it needs to
-// be pub for grab_draw_list. although the whole module is not pub.
+/// How material textures are bound.
+#[derive(Debug)]
+pub enum MaterialTextures {
+    /// All textures are in a [`TextureTable`] (bindless).
+    Table(TextureTable),
+
+    /// Every textured entity has its own bind group. Untextured entities use
+    /// the bind group stored here.
+    PerEntity {
+        untextured_bind_group: wgpu::BindGroup,
+    },
+}
+
+/// The [`Renderer`] as a resource.
+///
+/// All scenes created from the same
+/// [`RenderPlugin`](crate::plugin::RenderPlugin) share one renderer, so
+/// pipelines, layouts and fallbacks only exist once. Per-scene state lives in
+/// scene resources, see [`state`](crate::state).
+///
+/// This is only `pub` because it's in the signature of
+/// [`grab_draw_list_for_camera`](crate::grab_draw_list_for_camera). The
+/// renderer itself can't be accessed outside of this crate.
 #[derive(Clone, Debug, Resource)]
-pub struct SharedRenderer(pub Arc<Renderer>);
+pub struct SharedRenderer(Arc<Renderer>);
+
+impl SharedRenderer {
+    pub(crate) fn new(renderer: Renderer) -> Self {
+        Self(Arc::new(renderer))
+    }
+}
 
 impl Deref for SharedRenderer {
     type Target = Renderer;
@@ -301,6 +477,11 @@ impl Deref for SharedRenderer {
 pub struct Fallbacks {
     pub white: wgpu::TextureView,
     pub black: wgpu::TextureView,
+
+    /// Checkerboard shown in place of textures that failed to load.
+    pub missing: Arc<wgpu::Texture>,
+    pub missing_view: wgpu::TextureView,
+
     pub sampler_nearest_clamp: wgpu::Sampler,
     pub sampler_linear_clamp: wgpu::Sampler,
     pub sampler_linear_repeat: wgpu::Sampler,
@@ -327,6 +508,28 @@ impl Fallbacks {
         let white = color_texture(LinSrgba::new(255, 255, 255, 255), "white");
         let black = color_texture(LinSrgba::new(0, 0, 0, 255), "black");
 
+        // magenta and black, so it's hard to miss
+        let missing = image::RgbaImage::from_fn(64, 64, |x, y| {
+            if (x / 8 + y / 8) % 2 == 0 {
+                image::Rgba([255, 0, 255, 255])
+            }
+            else {
+                image::Rgba([0, 0, 0, 255])
+            }
+        })
+        .create_texture(
+            "missing",
+            wgpu::TextureUsages::TEXTURE_BINDING,
+            MipLevels::One,
+            device,
+            &mut write_staging,
+        )
+        .expect("missing texture has unsupported color space");
+        let missing_view = missing.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("missing"),
+            ..Default::default()
+        });
+
         let sampler_neatest_clamp = device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("default texture sampler (nearest, clamp)"),
             address_mode_u: wgpu::AddressMode::ClampToEdge,
@@ -360,6 +563,8 @@ impl Fallbacks {
         Self {
             white,
             black,
+            missing: Arc::new(missing),
+            missing_view,
             sampler_nearest_clamp: sampler_neatest_clamp,
             sampler_linear_clamp,
             sampler_linear_repeat,
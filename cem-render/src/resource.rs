@@ -34,6 +34,11 @@ use parking_lot::Mutex;
 
 use crate::{
     command::CommandSender,
+    mesh::{
+        Mesh,
+        MeshBufferBuilder,
+        arena::MeshArena,
+    },
     renderer::{
         Renderer,
         SharedRenderer,
@@ -103,6 +108,16 @@ impl<'w> RenderResourceManager<'w> {
         })
     }
 
+    /// Placeholder for textures that failed to load.
+    pub fn missing_texture(&self) -> LoadedTexture {
+        let fallbacks = &self.renderer.fallbacks;
+        LoadedTexture {
+            texture: fallbacks.missing.clone(),
+            texture_view: fallbacks.missing_view.clone(),
+            image_info: None,
+        }
+    }
+
     pub fn create_texture_channel(
         &mut self,
         size: &Vector2<u32>,
@@ -128,6 +143,17 @@ impl<'w> RenderResourceManager<'w> {
         });
     }
 
+    /// Writes a mesh into the renderer's [`MeshArena`].
+    pub fn create_mesh(&mut self, mesh_builder: MeshBufferBuilder, label: &str) -> Mesh {
+        self.transaction.with(&self.renderer, |transaction| {
+            mesh_builder.finish(
+                &self.renderer.mesh_arena,
+                &mut transaction.write_staging,
+                label,
+            )
+        })
+    }
+
     pub fn as_async(&self) -> AsyncRenderResourceManager {
         AsyncRenderResourceManager {
             renderer: self.renderer.clone(),
@@ -225,79 +251,91 @@ impl AsyncRenderResourceManager {
         P: AsRef<Path>,
     {
         let path = path.as_ref();
-        let (texture, image_info) = self
-            .texture_cache
+        let texture_cache = self.texture_cache.clone();
+        let (texture, image_info) = texture_cache
             .get_or_insert(path, async || {
                 tracing::debug!(path = %path.display(), ?mip_levels, "loading texture from file");
-
-                let label = path.display().to_string();
-
                 let image = image::ImageReader::open(path)?.decode()?;
-                let original_color_type = image.color();
-                let image = image.into_rgba8();
-
-                let texture = self
-                    .transaction
-                    .with_async(&self.renderer, async |transaction| {
-                        // pretend this is async lol
-                        let texture = if let Some(mipmap_cache) = &self.mipmap_cache {
-                            let mut mipmap_cache = mipmap_cache.0.lock();
-
-                            create_texture_from_mipmap_cache(
-                                &label,
-                                &image,
-                                &self.renderer.device,
-                                &mut transaction.write_staging,
-                                &mut mipmap_cache,
-                            )?
-                        }
-                        else {
-                            image.create_texture(
-                                &label,
-                                wgpu::TextureUsages::TEXTURE_BINDING,
-                                mip_levels,
-                                &self.renderer.device,
-                                &mut transaction.write_staging,
-                            )?
-                        };
-
-                        Ok::<_, TextureLoadError>(texture)
-                    })
-                    .await?;
-
-                Ok::<_, TextureLoadError>((
-                    texture,
-                    ImageInfo {
-                        original_color_type,
-                    },
-                ))
+                self.create_texture_from_decoded_image(
+                    &path.display().to_string(),
+                    image,
+                    mip_levels,
+                )
+                .await
             })
             .await?;
 
-        // if a fixed mip level count is specified, we use that. otherwise we use all
-        // available mip levels
-        let mut mip_level_count = mip_levels.fixed_mip_level_count();
-
-        // check if the cached texture actually has enough mip levels
-        // todo: if not we need to make more.
-        if let Some(requested_mip_level_count) = mip_level_count
-            && requested_mip_level_count.get() > texture.mip_level_count()
-        {
-            tracing::warn!(?requested_mip_level_count, cached_mip_level_count = ?texture.mip_level_count(), "todo: Cached texture's mip level count too low");
-            mip_level_count = None;
-        }
+        Ok(loaded_texture_with_mip_levels(
+            texture, image_info, mip_levels,
+        ))
+    }
 
-        // todo: label
-        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor {
-            mip_level_count: mip_level_count.map(|mip_level_count| mip_level_count.get()),
-            ..Default::default()
-        });
+    /// Loads a texture from an encoded image, e.g. the contents of a PNG file.
+    ///
+    /// Unlike textures loaded from files, these are not cached.
+    pub async fn load_texture_from_memory(
+        &mut self,
+        label: &str,
+        data: &[u8],
+        mip_levels: MipLevels,
+    ) -> Result<LoadedTexture, TextureLoadError> {
+        tracing::debug!(label, ?mip_levels, "loading texture from memory");
+        let image = image::load_from_memory(data)?;
+        let (texture, image_info) = self
+            .create_texture_from_decoded_image(label, image, mip_levels)
+            .await?;
+
+        Ok(loaded_texture_with_mip_levels(
+            Arc::new(texture),
+            image_info,
+            mip_levels,
+        ))
+    }
 
-        Ok(LoadedTexture {
+    async fn create_texture_from_decoded_image(
+        &mut self,
+        label: &str,
+        image: image::DynamicImage,
+        mip_levels: MipLevels,
+    ) -> Result<(wgpu::Texture, ImageInfo), TextureLoadError> {
+        let original_color_type = image.color();
+        let image = image.into_rgba8();
+
+        let texture = self
+            .transaction
+            .with_async(&self.renderer, async |transaction| {
+                // pretend this is async lol
+                let texture = if let Some(mipmap_cache) = &self.mipmap_cache {
+                    let mut mipmap_cache = mipmap_cache.0.lock();
+
+                    create_texture_from_mipmap_cache(
+                        label,
+                        &image,
+                        &self.renderer.device,
+                        &mut transaction.write_staging,
+                        &mut mipmap_cache,
+                    )?
+                }
+                else {
+                    image.create_texture(
+                        label,
+                        wgpu::TextureUsages::TEXTURE_BINDING,
+                        mip_levels,
+                        &self.renderer.device,
+                        &mut transaction.write_staging,
+                    )?
+                };
+
+                Ok::<_, TextureLoadError>(texture)
+            })
+            .await?;
+
+        Ok((
             texture,
-            texture_view,
-            image_info: Some(image_info),
-        })
+            ImageInfo {
+                original_color_type,
+            },
+        ))
     }
 
     pub async fn create_texture_from_image(
@@ -322,6 +360,37 @@ impl AsyncRenderResourceManager {
     }
 }
 
+fn loaded_texture_with_mip_levels(
+    texture: Arc<wgpu::Texture>,
+    image_info: ImageInfo,
+    mip_levels: MipLevels,
+) -> LoadedTexture {
+    // if a fixed mip level count is specified, we use that. otherwise we use all
+    // available mip levels
+    let mut mip_level_count = mip_levels.fixed_mip_level_count();
+
+    // check if the cached texture actually has enough mip levels
+    // todo: if not we need to make more.
+    if let Some(requested_mip_level_count) = mip_level_count
+        && requested_mip_level_count.get() > texture.mip_level_count()
+    {
+        tracing::warn!(?requested_mip_level_count, cached_mip_level_count = ?texture.mip_level_count(), "todo: Cached texture's mip level count too low");
+        mip_level_count = None;
+    }
+
+    // todo: label
+    let texture_view = texture.create_view(&wgpu::TextureViewDescriptor {
+        mip_level_count: mip_level_count.map(|mip_level_count| mip_level_count.get()),
+        ..Default::default()
+    });
+
+    LoadedTexture {
+        texture,
+        texture_view,
+        image_info: Some(image_info),
+    }
+}
+
 fn create_texture_from_mipmap_cache<S>(
     label: &str,
     base_image: &image::RgbaImage,
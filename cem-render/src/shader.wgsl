@@ -9,7 +9,7 @@ struct Camera {
     point_light_color: vec4f,
     flags: u32,
     gamma: f32,
-    // 8 bytes padding
+    viewport_size: vec2f,
 };
 
 struct Instance {
@@ -31,7 +31,9 @@ struct Material {
     ambient_occlusion: f32,
     flags: u32,
     alpha_threshold: f32,
-    // 12 bytes padding
+    albedo_texture: u32,
+    material_texture: u32,
+    wireframe_width: f32,
 }
 
 struct PointLight {
@@ -42,6 +44,8 @@ struct PointLight {
 
 const FLAG_MESH_UVS: u32                    = 0x00000001;
 const FLAG_MESH_NORMALS: u32                = 0x00000002;
+const FLAG_MESH_LINES: u32                  = 0x00000004;
+const FLAG_MESH_POINTS: u32                 = 0x00000008;
 const FLAG_MESH_NORMALS_GENERATOR_MASK: u32 = 0xff000000;
 const FLAG_MESH_NORMALS_FROM_FACE: u32      = 0x01000000;
 const FLAG_MESH_NORMALS_FROM_VERTEX: u32    = 0x02000000;
@@ -59,6 +63,27 @@ const FLAG_MATERIAL_GAMMA: u32                     = 0x00000080;
 const FLAG_CAMERA_AMBIENT_LIGHT: u32 = 0x01;
 const FLAG_CAMERA_POINT_LIGHT: u32   = 0x02;
 const FLAG_CAMERA_TONE_MAP: u32      = 0x04;
+const FLAG_CAMERA_SEE_THROUGH: u32   = 0x08;
+
+const FLAG_INSTANCE_SEE_THROUGH: u32 = 0x01;
+const FLAG_INSTANCE_DEPTH_BIAS: u32  = 0x02;
+const FLAG_INSTANCE_X_RAY: u32       = 0x04;
+
+// bit position of the overlay flags in the instance and camera flags (see `OverlayFlags`)
+const OVERLAY_FLAGS_SHIFT: u32 = 16;
+
+// clip position for vertices of hidden instances. it's outside of the clip volume, so their
+// primitives are discarded.
+const HIDDEN_POSITION: vec4f = vec4f(0.0, 0.0, -1.0, 1.0);
+
+// alpha of see-through instances, if the camera wants to see through them
+const SEE_THROUGH_ALPHA: f32 = 0.3;
+
+// fraction of the distance to the camera by which depth-biased instances are pulled towards it
+const DEPTH_BIAS: f32 = 0.001;
+
+// alpha of x-ray instances, which are drawn on top of everything
+const X_RAY_ALPHA: f32 = 0.6;
 
 
 // camera
@@ -85,17 +110,9 @@ var<storage, read> index_buffer: array<u32>;
 @group(1) @binding(1)
 var<storage, read> vertex_buffer: array<VertexData>;
 
-@group(1) @binding(2)
-var sampler_albedo: sampler;
-
-@group(1) @binding(3)
-var texture_albedo: texture_2d<f32>;
-
-@group(1) @binding(4)
-var sampler_material: sampler;
-
-@group(1) @binding(5)
-var texture_material: texture_2d<f32>;
+// material textures (group 2) are declared in either `shader_texture_table.wgsl` or
+// `shader_texture_single.wgsl`, depending on whether binding arrays are supported. both
+// provide `sample_albedo` and `sample_material`.
 
 struct VertexData {
     position: vec4f,
@@ -124,6 +141,13 @@ struct VertexOutputFlat {
     @location(0) color: vec4f,
 }
 
+struct VertexOutputPoint {
+    @builtin(position) fragment_position: vec4f,
+    @location(0) color: vec4f,
+    @location(1) corner: vec2f,
+    @location(2) @interpolate(flat, either) instance_index: u32,
+}
+
 struct FragmentOutput {
     @location(0) color: vec4f,
 }
@@ -136,15 +160,27 @@ fn vs_main_solid(input: VertexInput) -> VertexOutputSolid {
     let instance = instance_buffer[input.instance_index];
     output.instance_index = input.instance_index;
 
+    if is_hidden_overlay(instance) {
+        output.fragment_position = HIDDEN_POSITION;
+        return output;
+    }
+
     let vertex_data = get_vertex_data(input.vertex_index, instance.base_vertex);
     let vertex_position = vertex_data.position;
 
     // we only actually use the combined transform, so we could pass the combined to the shader directly
     let combined_camera_matrix = camera.projection * camera.transform;
 
-    // transform vertex position to world and view coordinates
+    // transform vertex position to world and view coordinates. world coordinates are relative to
+    // the render origin (see `RendererState::render_origin`).
     output.world_position = instance.transform * vertex_position;
-    output.fragment_position = combined_camera_matrix * output.world_position;
+    var depth_position = output.world_position;
+    if (instance.instance_flags & FLAG_INSTANCE_DEPTH_BIAS) != 0 {
+        // moving the vertex along the ray to the camera doesn't change where it is on screen, but
+        // lets it win the depth test against surfaces it's coplanar with.
+        depth_position = vec4f(mix(depth_position.xyz, camera.world_position.xyz, DEPTH_BIAS), 1.0);
+    }
+    output.fragment_position = combined_camera_matrix * depth_position;
     // vertex uv
     output.texture_position = vertex_data.uv;
 
@@ -166,6 +202,13 @@ fn vs_main_solid(input: VertexInput) -> VertexOutputSolid {
     return output;
 }
 
+// whether the instance is part of an overlay the camera doesn't show
+fn is_hidden_overlay(instance: Instance) -> bool {
+    let overlays = instance.instance_flags >> OVERLAY_FLAGS_SHIFT;
+    let shown = camera.flags >> OVERLAY_FLAGS_SHIFT;
+    return (overlays & ~shown) != 0;
+}
+
 fn calculate_normal(v1: vec3f, v2: vec3f, v3: vec3f,) -> vec3f {
     return cross(v2 - v1, v3 - v1);
 }
@@ -212,12 +255,12 @@ fn pbr_shader(input: VertexOutputSolid, instance: Instance) -> vec4f {
     // sample material textures
     let texture_position = input.texture_position.xy / input.texture_position.z;
     if (instance.material.flags & FLAG_MATERIAL_ALBEDO_TEXTURE) != 0 {
-        let albedo_and_alpha = textureSample(texture_albedo, sampler_albedo, texture_position);
+        let albedo_and_alpha = sample_albedo(instance.material, texture_position);
         albedo *= albedo_and_alpha.rgb;
         alpha *= albedo_and_alpha.a;
     }
     if (instance.material.flags & FLAG_MATERIAL_ANY_ORM) != 0 {
-        let material = textureSample(texture_material, sampler_material, texture_position);
+        let material = sample_material(instance.material, texture_position);
         if (instance.material.flags & FLAG_MATERIAL_METALLIC_TEXTURE) != 0 {
             metalness *= material.r;
         }
@@ -234,6 +277,13 @@ fn pbr_shader(input: VertexOutputSolid, instance: Instance) -> vec4f {
         discard;
     }
 
+    if (camera.flags & FLAG_CAMERA_SEE_THROUGH) != 0 && (instance.instance_flags & FLAG_INSTANCE_SEE_THROUGH) != 0 {
+        alpha *= SEE_THROUGH_ALPHA;
+    }
+    if (instance.instance_flags & FLAG_INSTANCE_X_RAY) != 0 {
+        alpha *= X_RAY_ALPHA;
+    }
+
     // note: i think we can remove this, since alpha writes are disabled on the opaque pipeline
     // optionally disable transparency
     //if (instance.material.flags & FLAG_MATERIAL_TRANSPARENT) == 0 {
@@ -385,33 +435,81 @@ fn gamma_correct(color: vec3f) -> vec3f {
 fn vs_main_wireframe(input: VertexInput) -> VertexOutputFlat {
     let instance = instance_buffer[input.instance_index];
 
+    if is_hidden_overlay(instance) {
+        var hidden: VertexOutputFlat;
+        hidden.fragment_position = HIDDEN_POSITION;
+        return hidden;
+    }
+
     /*
+        Every line segment is expanded to a quad (2 triangles, 6 vertices) facing the camera,
+        so that lines can be wider than 1 pixel.
+
+        For triangle meshes every face has 3 line segments, so the shader is called with 18
+        vertices per face (6 * number of indices). The line segments are the edges:
+
         0----2
         |   /
         | /
         1
 
-        shader will be called with vertex_index = [0, 1, 2, 3, 4, 5] (2 * number of vertices)
-
-        vertex_index | draw vertex
-                   0 | 0
-                   1 | 1
-                   2 | 1
-                   3 | 2
-                   4 | 2
-                   5 | 0
+        edge | line
+           0 | 0 - 1
+           1 | 1 - 2
+           2 | 2 - 0
 
-        `(i + 1) % 6 / 2` gives the vertex indices for lines of a single triangle.
-        `(i / 6) * 3` gives the vertex index for the first index of a triangle.
+        Line meshes store a line segment `[a, b, b]` per face, so the shader is called with 6
+        vertices per face (2 * number of indices).
     */
 
-    var vertex_index = ((input.vertex_index + 1) % 6) / 2 + (input.vertex_index / 6) * 3;
-    vertex_index = index_buffer[vertex_index];
-    let vertex_position = vertex_buffer[vertex_index].position.xyz;
+    var face: u32;
+    var edge: u32;
+    if (instance.mesh_flags & FLAG_MESH_LINES) != 0 {
+        face = input.vertex_index / 6;
+        edge = 0;
+    }
+    else {
+        face = input.vertex_index / 18;
+        edge = (input.vertex_index % 18) / 6;
+    }
+
+    let start = get_vertex_data(3 * face + edge, instance.base_vertex).position.xyz;
+    let end = get_vertex_data(3 * face + (edge + 1) % 3, instance.base_vertex).position.xyz;
+
+    let combined_matrix = camera.projection * camera.transform * instance.transform;
+    let clip_start = combined_matrix * vec4f(start, 1.0);
+    let clip_end = combined_matrix * vec4f(end, 1.0);
+
+    // quad corners: which end of the line, and which side of it
+    //
+    //   5----4/2
+    //   |   / |
+    //   | /   |
+    //  0/3----1
+    let corner = input.vertex_index % 6;
+    let at_end = corner == 1 || corner == 2 || corner == 4;
+    let side = select(-1.0, 1.0, corner == 2 || corner == 4 || corner == 5);
+
+    // direction of the line on screen
+    let viewport_size = max(camera.viewport_size, vec2f(1.0));
+    let screen_start = clip_start.xy / clip_start.w * viewport_size;
+    let screen_end = clip_end.xy / clip_end.w * viewport_size;
+    var direction = screen_end - screen_start;
+    if dot(direction, direction) < 1e-12 {
+        direction = vec2f(1.0, 0.0);
+    }
+    direction = normalize(direction);
+    let normal = vec2f(-direction.y, direction.x);
+
+    // offset by half the width to each side. normalized device coordinates span 2 units over
+    // the viewport, so the half-width in NDC is `width / viewport_size`.
+    var clip_position = select(clip_start, clip_end, at_end);
+    let offset = side * normal * instance.material.wireframe_width / viewport_size;
+    clip_position += vec4f(offset * clip_position.w, 0.0, 0.0);
 
     var output: VertexOutputFlat;
     output.color = instance.material.wireframe;
-    output.fragment_position = camera.projection * camera.transform * instance.transform * vec4f(vertex_position, 1.0);
+    output.fragment_position = clip_position;
 
     return output;
 }
@@ -420,8 +518,13 @@ fn vs_main_wireframe(input: VertexInput) -> VertexOutputFlat {
 fn vs_main_outline(input: VertexInput) -> VertexOutputFlat {
     let instance = instance_buffer[input.instance_index];
 
-    let vertex_index = index_buffer[input.vertex_index];
-    let vertex_position = vertex_buffer[vertex_index].position.xyz;
+    if is_hidden_overlay(instance) {
+        var hidden: VertexOutputFlat;
+        hidden.fragment_position = HIDDEN_POSITION;
+        return hidden;
+    }
+
+    let vertex_position = get_vertex_data(input.vertex_index, instance.base_vertex).position.xyz;
     let scaling = 1.0 + instance.outline_thickness;
 
     var output: VertexOutputFlat;
@@ -439,6 +542,66 @@ fn fs_main_flat(input: VertexOutputFlat) -> FragmentOutput {
     return output;
 }
 
+@vertex
+fn vs_main_point(input: VertexInput) -> VertexOutputPoint {
+    let instance = instance_buffer[input.instance_index];
+
+    if is_hidden_overlay(instance) {
+        var hidden: VertexOutputPoint;
+        hidden.fragment_position = HIDDEN_POSITION;
+        return hidden;
+    }
+
+    /*
+        Point meshes store a point `[a, a, a]` per face, so the shader is called with 6 vertices
+        per face (2 * number of indices). Every point is expanded to a quad facing the camera,
+        with the same corners as the line quads in `vs_main_wireframe`.
+
+        The vertex normal holds the point's color, and the first UV coordinate its size.
+    */
+
+    let point = input.vertex_index / 6;
+    let data = get_vertex_data(3 * point, instance.base_vertex);
+
+    let corner_index = input.vertex_index % 6;
+    let corner = vec2f(
+        select(-1.0, 1.0, corner_index == 1 || corner_index == 2 || corner_index == 4),
+        select(-1.0, 1.0, corner_index == 2 || corner_index == 4 || corner_index == 5),
+    );
+
+    // see `vs_main_wireframe` for how sizes translate to normalized device coordinates.
+    let viewport_size = max(camera.viewport_size, vec2f(1.0));
+    var clip_position = camera.projection * camera.transform * instance.transform * vec4f(data.position.xyz, 1.0);
+    let offset = corner * data.uv.x / viewport_size;
+    clip_position += vec4f(offset * clip_position.w, 0.0, 0.0);
+
+    var output: VertexOutputPoint;
+    output.fragment_position = clip_position;
+    output.color = data.normal * instance.material.albedo;
+    output.corner = corner;
+    output.instance_index = input.instance_index;
+    return output;
+}
+
+@fragment
+fn fs_main_point(input: VertexOutputPoint) -> FragmentOutput {
+    let instance = instance_buffer[input.instance_index];
+
+    // make the splat round
+    if dot(input.corner, input.corner) > 1.0 {
+        discard;
+    }
+
+    var color = input.color;
+    if (instance.material.flags & FLAG_MATERIAL_GAMMA) != 0 {
+        color = vec4f(gamma_correct(color.rgb), color.a);
+    }
+
+    var output: FragmentOutput;
+    output.color = color;
+    return output;
+}
+
 @vertex
 fn vs_main_clear(input: VertexInput) -> VertexOutputFlat {
     var output: VertexOutputFlat;
@@ -0,0 +1,22 @@
+
+// material textures - one bind group per object
+
+@group(2) @binding(0)
+var sampler_albedo: sampler;
+
+@group(2) @binding(1)
+var texture_albedo: texture_2d<f32>;
+
+@group(2) @binding(2)
+var sampler_material: sampler;
+
+@group(2) @binding(3)
+var texture_material: texture_2d<f32>;
+
+fn sample_albedo(material: Material, texture_position: vec2f) -> vec4f {
+    return textureSample(texture_albedo, sampler_albedo, texture_position);
+}
+
+fn sample_material(material: Material, texture_position: vec2f) -> vec4f {
+    return textureSample(texture_material, sampler_material, texture_position);
+}
@@ -0,0 +1,16 @@
+
+// material textures - texture table (bindless)
+
+@group(2) @binding(0)
+var texture_table_samplers: binding_array<sampler, 256>;
+
+@group(2) @binding(1)
+var texture_table: binding_array<texture_2d<f32>, 256>;
+
+fn sample_albedo(material: Material, texture_position: vec2f) -> vec4f {
+    return textureSample(texture_table[material.albedo_texture], texture_table_samplers[material.albedo_texture], texture_position);
+}
+
+fn sample_material(material: Material, texture_position: vec2f) -> vec4f {
+    return textureSample(texture_table[material.material_texture], texture_table_samplers[material.material_texture], texture_position);
+}
@@ -1,4 +1,14 @@
-use bevy_ecs::resource::Resource;
+//! Per-scene renderer state.
+//!
+//! Every scene has its own instances, draw commands and render origin, which
+//! are stored as resources in the scene. The pipelines, layouts and fallbacks
+//! are in the [`Renderer`], which is shared between scenes.
+
+use bevy_ecs::{
+    entity::Entity,
+    message::Message,
+    resource::Resource,
+};
 use bitflags::bitflags;
 use bytemuck::{
     Pod,
@@ -6,69 +16,146 @@ use bytemuck::{
 };
 use cem_scene::transform::GlobalTransform;
 use cem_util::wgpu::buffer::{
-    StagedTypedArrayBuffer,
+    TypedArrayBuffer,
     WriteStagingBelt,
     WriteStagingTransaction,
 };
-use nalgebra::Matrix4;
+use nalgebra::{
+    Isometry3,
+    Matrix4,
+    Point3,
+    Translation3,
+};
 use palette::LinSrgba;
 
 use crate::{
     MaterialData,
-    draw_commands::DrawCommandBuffer,
+    components::{
+        DepthCompositing,
+        OVERLAY_FLAGS_SHIFT,
+        Overlay,
+    },
     material::{
         AlbedoTexture,
         Material,
         MaterialTexture,
         Outline,
+        TextureBinding,
         Wireframe,
     },
     mesh::{
         Mesh,
         MeshFlags,
+        MeshOffset,
     },
     renderer::Renderer,
 };
 
+/// The instances of a scene's meshes.
 #[derive(Debug, Resource)]
-pub struct RendererState {
-    /// The instance buffer.
-    ///
-    /// This holds the handle to the GPU buffer for the instance data, a
-    /// host staging buffer for the instance data, and the bind group for the
-    /// GPU buffer.
-    pub instance_buffer: StagedTypedArrayBuffer<InstanceData>,
-
-    /// This stores all draw commands that are generated during `prepare_world`.
-    /// Its `finish` method returns the finalized draw command (aggregate) for a
-    /// specific camera.
-    pub draw_command_buffer: DrawCommandBuffer,
+pub struct InstanceBuffer {
+    /// The instance buffer on the GPU.
+    pub buffer: TypedArrayBuffer<InstanceData>,
 
-    pub write_staging:
-        Option<WriteStagingTransaction<WriteStagingBelt, wgpu::Device, wgpu::CommandEncoder>>,
+    /// Host copy of the instance data uploaded to [`Self::buffer`].
+    ///
+    /// This is kept between frames, so that we only need to upload instances
+    /// that changed.
+    pub instances: Vec<InstanceData>,
 
-    pub instance_buffer_reallocated: bool,
+    /// The entity for each instance in [`Self::instances`].
+    ///
+    /// Entities with material groups have multiple consecutive instances. If
+    /// the entities are the same as in the last frame, we can update the
+    /// instances in-place.
+    pub entities: Vec<Entity>,
 }
 
-impl RendererState {
+impl InstanceBuffer {
     pub fn new(device: &wgpu::Device) -> Self {
-        let instance_buffer = StagedTypedArrayBuffer::with_capacity(
+        let buffer = TypedArrayBuffer::with_capacity(
             device.clone(),
             "render/instance_buffer",
-            wgpu::BufferUsages::STORAGE,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
             128,
         );
-        assert!(instance_buffer.buffer.is_allocated());
+        assert!(buffer.is_allocated());
+
+        Self {
+            buffer,
+            instances: vec![],
+            entities: vec![],
+        }
+    }
+}
+
+/// Sent when the [`InstanceBuffer`] was reallocated, so the camera bind groups
+/// need to be recreated.
+#[derive(Clone, Copy, Debug, Message)]
+pub struct InstanceBufferReallocated;
+
+/// Staged writes of the frame that is being prepared.
+///
+/// This is opened in [`begin_frame`](crate::systems::begin_frame) and committed
+/// in [`end_frame`](crate::systems::end_frame).
+#[derive(Debug, Default, Resource)]
+pub struct FrameStaging(
+    pub Option<WriteStagingTransaction<WriteStagingBelt, wgpu::Device, wgpu::CommandEncoder>>,
+);
 
+impl FrameStaging {
+    /// The transaction of the current frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a frame.
+    pub fn get_mut(
+        &mut self,
+    ) -> &mut WriteStagingTransaction<WriteStagingBelt, wgpu::Device, wgpu::CommandEncoder> {
+        self.0.as_mut().expect("no frame in progress")
+    }
+}
+
+/// Origin of the coordinates passed to the GPU.
+///
+/// Instance and camera transforms are made relative to this in double
+/// precision before they're rounded to `f32`. Otherwise scenes far away
+/// from the world origin (e.g. at geographic coordinates) jitter, because
+/// `f32` can't resolve small movements there. See
+/// [`update_render_origin`](crate::systems::update_render_origin).
+#[derive(Clone, Copy, Debug, Resource)]
+pub struct RenderOrigin {
+    pub origin: Point3<f64>,
+
+    /// Whether the origin moved this frame, meaning all instances need to be
+    /// updated.
+    pub moved: bool,
+}
+
+impl Default for RenderOrigin {
+    fn default() -> Self {
         Self {
-            instance_buffer,
-            draw_command_buffer: Default::default(),
-            write_staging: None,
-            instance_buffer_reallocated: false,
+            origin: Point3::origin(),
+            moved: false,
         }
     }
 }
 
+/// Transform relative to the render origin in single precision.
+///
+/// The offset is taken in double precision, so objects close to the camera stay
+/// exact, no matter how far from the world origin they are.
+pub fn relative_to_render_origin(
+    transform: &GlobalTransform,
+    render_origin: &Point3<f64>,
+) -> Isometry3<f32> {
+    let translation = transform.precise_position() - render_origin;
+    Isometry3::from_parts(
+        Translation3::from(translation.cast::<f32>()),
+        transform.isometry().rotation,
+    )
+}
+
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 #[repr(C)]
 pub struct InstanceData {
@@ -83,14 +170,21 @@ pub struct InstanceData {
 
 impl InstanceData {
     /// Creates instance data for mesh rendering
+    #[allow(clippy::too_many_arguments)]
     pub fn new_mesh(
         transform: &GlobalTransform,
+        render_origin: &Point3<f64>,
         mesh: &Mesh,
         material: Option<&Material>,
         wireframe: Option<&Wireframe>,
         albedo_texture: Option<&AlbedoTexture>,
         material_texture: Option<&MaterialTexture>,
+        texture_binding: Option<&TextureBinding>,
         outline: Option<&Outline>,
+        see_through: bool,
+        depth_compositing: Option<&DepthCompositing>,
+        mesh_offset: Option<&MeshOffset>,
+        overlay: Option<&Overlay>,
     ) -> Self {
         // note: this should be fixed by the mesh builder (e.g. `MeshBufferBuilder` does
         // this)
@@ -106,14 +200,38 @@ impl InstanceData {
             (outline.thickness, outline.color.into_linear())
         });
 
+        let mut instance_flags = InstanceFlags::empty();
+        instance_flags.set(InstanceFlags::SEE_THROUGH, see_through);
+        match depth_compositing {
+            None | Some(DepthCompositing::Normal) => {}
+            Some(DepthCompositing::Biased) => instance_flags.insert(InstanceFlags::DEPTH_BIAS),
+            Some(DepthCompositing::XRay) => instance_flags.insert(InstanceFlags::X_RAY),
+        }
+        if let Some(overlay) = overlay {
+            // the shader hides the instance if the camera doesn't show all of these
+            instance_flags |=
+                InstanceFlags::from_bits_retain(overlay.flags.bits() << OVERLAY_FLAGS_SHIFT);
+        }
+
+        let mut transform = relative_to_render_origin(transform, render_origin);
+        if let Some(mesh_offset) = mesh_offset {
+            transform *= mesh_offset.isometry;
+        }
+
         Self {
-            transform: transform.isometry().to_homogeneous(),
-            instance_flags: InstanceFlags::empty(),
+            transform: transform.to_homogeneous(),
+            instance_flags,
             mesh_flags: mesh.flags,
             base_vertex: mesh.base_vertex,
             outline_thickness,
             outline_color,
-            material: MaterialData::new(material, wireframe, albedo_texture, material_texture),
+            material: MaterialData::new(
+                material,
+                wireframe,
+                albedo_texture,
+                material_texture,
+                texture_binding,
+            ),
         }
     }
 }
@@ -122,6 +240,8 @@ bitflags! {
     #[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
     #[repr(C)]
     struct InstanceFlags: u32 {
-        // unused currently, but surely will be useful in the future again.
+        const SEE_THROUGH = 0x01;
+        const DEPTH_BIAS  = 0x02;
+        const X_RAY       = 0x04;
     }
 }
@@ -1,18 +1,22 @@
 #![allow(clippy::type_complexity)]
 
+use std::{
+    iter,
+    ops::Range,
+};
+
 use bevy_ecs::{
     entity::{
         Entity,
         EntityHashSet,
     },
+    lifecycle::RemovedComponents,
     message::{
         Message,
         MessageReader,
+        MessageWriter,
     },
-    name::{
-        NameOrEntity,
-        NameOrEntityItem,
-    },
+    name::NameOrEntity,
     query::{
         Changed,
         Has,
@@ -29,9 +33,13 @@ use bevy_ecs::{
         Query,
         Res,
         ResMut,
+        SystemParam,
     },
 };
-use cem_scene::transform::GlobalTransform;
+use cem_scene::{
+    material_groups::MaterialGroups,
+    transform::GlobalTransform,
+};
 use cem_util::wgpu::{
     buffer::{
         WriteStagingCommit,
@@ -39,10 +47,13 @@ use cem_util::wgpu::{
     },
     image::ImageTextureExt,
 };
+use nalgebra::Point3;
 
 use crate::{
     Command,
+    RendererInfo,
     camera::{
+        ActiveCamera,
         CameraBindGroup,
         CameraConfig,
         CameraData,
@@ -54,11 +65,20 @@ use crate::{
         CommandReceiver,
         CommandSender,
     },
-    components::Hidden,
+    components::{
+        DepthCompositing,
+        Hidden,
+        Overlay,
+        OverlayFlags,
+        SeeThrough,
+        ViewOverlays,
+    },
     draw_commands::{
         DrawCommand,
+        DrawCommandBuffer,
         DrawCommandFlags,
         DrawCommandInfoSink,
+        MeshBlending,
     },
     light::{
         AmbientLight,
@@ -69,12 +89,14 @@ use crate::{
         Material,
         MaterialTexture,
         Outline,
+        TextureBinding,
         Wireframe,
     },
     mesh::{
         Mesh,
         MeshBindGroup,
         MeshFlags,
+        MeshOffset,
     },
     renderer::{
         Renderer,
@@ -82,13 +104,16 @@ use crate::{
     },
     resource::RenderResourceTransactionState,
     state::{
+        FrameStaging,
+        InstanceBuffer,
+        InstanceBufferReallocated,
         InstanceData,
-        RendererState,
+        RenderOrigin,
     },
 };
 
-pub fn begin_frame(renderer: Res<SharedRenderer>, mut state: ResMut<RendererState>) {
-    assert!(state.write_staging.is_none());
+pub fn begin_frame(renderer: Res<SharedRenderer>, mut frame_staging: ResMut<FrameStaging>) {
+    assert!(frame_staging.0.is_none());
 
     let command_encoder = renderer
         .device
@@ -102,17 +127,26 @@ pub fn begin_frame(renderer: Res<SharedRenderer>, mut state: ResMut<RendererStat
         command_encoder,
     );
 
-    state.write_staging = Some(write_staging);
+    frame_staging.0 = Some(write_staging);
 }
 
-pub fn end_frame(renderer: Res<SharedRenderer>, mut state: ResMut<RendererState>) {
+pub fn end_frame(
+    renderer: Res<SharedRenderer>,
+    mut frame_staging: ResMut<FrameStaging>,
+    mut info: ResMut<RendererInfo>,
+) {
+    let write_staging = frame_staging.0.take().unwrap();
+    info.staging_belt = write_staging.provider().info();
+
     // finish all staged writes
-    let command_encoder = state.write_staging.take().unwrap().commit();
+    let command_encoder = write_staging.commit();
+    info.staging_pool = renderer.staging_pool.info();
     renderer.queue.submit([command_encoder.finish()]);
 }
 
 #[derive(QueryData)]
 pub struct UpdateInstanceBufferAndDrawCommandQueryData {
+    entity: Entity,
     global_transform: &'static GlobalTransform,
     mesh: &'static Mesh,
     mesh_bind_group: &'static MeshBindGroup,
@@ -120,114 +154,390 @@ pub struct UpdateInstanceBufferAndDrawCommandQueryData {
     wireframe: Option<&'static Wireframe>,
     albedo_texture: Option<&'static AlbedoTexture>,
     material_texture: Option<&'static MaterialTexture>,
+    texture_binding: Option<&'static TextureBinding>,
     outline: Option<&'static Outline>,
+    material_groups: Option<&'static MaterialGroups<Material>>,
+    see_through: Has<SeeThrough>,
+    depth_compositing: Option<&'static DepthCompositing>,
+    mesh_offset: Option<&'static MeshOffset>,
+    overlay: Option<&'static Overlay>,
+}
+
+impl UpdateInstanceBufferAndDrawCommandQueryDataItem<'_, '_> {
+    /// Number of instances this entity uses: One for the entity itself and one
+    /// for each material group.
+    fn num_instances(&self) -> usize {
+        1 + self.material_groups.map_or(0, |groups| groups.len())
+    }
+
+    fn instance_data(&self, render_origin: &Point3<f64>) -> impl Iterator<Item = InstanceData> {
+        let instance_data = |material| {
+            InstanceData::new_mesh(
+                self.global_transform,
+                render_origin,
+                self.mesh,
+                material,
+                self.wireframe,
+                self.albedo_texture,
+                self.material_texture,
+                self.texture_binding,
+                self.outline,
+                self.see_through,
+                self.depth_compositing,
+                self.mesh_offset,
+                self.overlay,
+            )
+        };
+
+        iter::once(instance_data(self.material)).chain(
+            self.material_groups
+                .into_iter()
+                .flat_map(|groups| groups.iter())
+                .map(move |group| instance_data(Some(&group.material))),
+        )
+    }
+}
+
+/// Entities that are rendered as instances.
+type InstanceFilter = (
+    Or<(
+        With<Material>,
+        With<Wireframe>,
+        With<AlbedoTexture>,
+        With<MaterialTexture>,
+        With<MaterialGroups<Material>>,
+    )>,
+    Without<Hidden>,
+);
+
+/// Instances whose instance data or draw commands need to be updated.
+type InstanceChangedFilter = Or<(
+    Changed<GlobalTransform>,
+    Changed<Mesh>,
+    Changed<MeshBindGroup>,
+    Changed<Material>,
+    Changed<Wireframe>,
+    Changed<AlbedoTexture>,
+    Changed<MaterialTexture>,
+    Changed<TextureBinding>,
+    Changed<Outline>,
+    Changed<MaterialGroups<Material>>,
+    Changed<SeeThrough>,
+    Changed<DepthCompositing>,
+    Changed<MeshOffset>,
+    Changed<Overlay>,
+)>;
+
+/// Components that change the instance data when they're removed from an
+/// entity that is still rendered.
+#[derive(SystemParam)]
+pub struct RemovedInstanceComponents<'w, 's> {
+    material: RemovedComponents<'w, 's, Material>,
+    wireframe: RemovedComponents<'w, 's, Wireframe>,
+    albedo_texture: RemovedComponents<'w, 's, AlbedoTexture>,
+    material_texture: RemovedComponents<'w, 's, MaterialTexture>,
+    texture_binding: RemovedComponents<'w, 's, TextureBinding>,
+    outline: RemovedComponents<'w, 's, Outline>,
+    material_groups: RemovedComponents<'w, 's, MaterialGroups<Material>>,
+    see_through: RemovedComponents<'w, 's, SeeThrough>,
+    depth_compositing: RemovedComponents<'w, 's, DepthCompositing>,
+    mesh_offset: RemovedComponents<'w, 's, MeshOffset>,
+    overlay: RemovedComponents<'w, 's, Overlay>,
+}
+
+impl RemovedInstanceComponents<'_, '_> {
+    fn read(&mut self) -> impl Iterator<Item = Entity> {
+        self.material
+            .read()
+            .chain(self.wireframe.read())
+            .chain(self.albedo_texture.read())
+            .chain(self.material_texture.read())
+            .chain(self.texture_binding.read())
+            .chain(self.outline.read())
+            .chain(self.material_groups.read())
+            .chain(self.see_through.read())
+            .chain(self.depth_compositing.read())
+            .chain(self.mesh_offset.read())
+            .chain(self.overlay.read())
+    }
+}
+
+/// How far from the render origin a camera can be before the origin is moved.
+///
+/// Moving the origin means rewriting all instances, so this shouldn't happen
+/// every frame. `f32` still has sub-millimeter precision at this distance.
+const RENDER_ORIGIN_MAX_DISTANCE: f64 = 1000.0;
+
+/// Moves the render origin to the [`ActiveCamera`], if it's too far away from
+/// it.
+///
+/// All cameras of a scene share the instance buffer and thus the origin. If no
+/// camera is active, the origin follows the camera nearest to it. Ties go to
+/// the lower entity, so the choice doesn't depend on the query's order.
+pub fn update_render_origin(
+    mut render_origin: ResMut<RenderOrigin>,
+    cameras: Query<(Entity, &GlobalTransform, Has<ActiveCamera>), With<CameraProjection>>,
+) {
+    let origin = render_origin.origin;
+    let moved = cameras
+        .iter()
+        .map(|(entity, transform, active)| {
+            let position = transform.precise_position();
+            let distance = nalgebra::distance(&position, &origin);
+            (!active, distance, entity, position)
+        })
+        .min_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)).then(a.2.cmp(&b.2)))
+        .filter(|(_, distance, _, _)| *distance > RENDER_ORIGIN_MAX_DISTANCE)
+        .map(|(_, _, _, position)| position);
+
+    if let Some(position) = moved {
+        tracing::debug!(from = ?origin, to = ?position, "moving render origin");
+        render_origin.origin = position;
+    }
+    render_origin.moved = moved.is_some();
 }
 
 pub fn update_instance_buffer_and_draw_command(
-    query: Query<
-        UpdateInstanceBufferAndDrawCommandQueryData,
-        (
-            Or<(
-                With<Material>,
-                With<Wireframe>,
-                With<AlbedoTexture>,
-                With<MaterialTexture>,
-            )>,
-            Without<Hidden>,
-        ),
-    >,
-    mut state: ResMut<RendererState>,
+    query: Query<UpdateInstanceBufferAndDrawCommandQueryData, InstanceFilter>,
+    changed: Query<(), (InstanceFilter, InstanceChangedFilter)>,
+    mut removed: RemovedInstanceComponents,
+    mut instance_buffer: ResMut<InstanceBuffer>,
+    mut draw_command_buffer: ResMut<DrawCommandBuffer>,
+    mut frame_staging: ResMut<FrameStaging>,
+    render_origin: Res<RenderOrigin>,
+    mut reallocated: MessageWriter<InstanceBufferReallocated>,
+    mut info: ResMut<RendererInfo>,
+    mut dirty: Local<EntityHashSet>,
+) {
+    let instance_buffer = &mut *instance_buffer;
+    let write_staging = frame_staging.get_mut();
+
+    dirty.clear();
+    dirty.extend(removed.read());
+
+    // if the same entities are rendered in the same order (and with the same number
+    // of instances) as in the last frame, we only need to update the instances that
+    // changed. if the render origin moved, all instances changed.
+    let same_instances = !render_origin.moved
+        && query
+            .iter()
+            .flat_map(|item| iter::repeat_n(item.entity, item.num_instances()))
+            .eq(instance_buffer.entities.iter().copied());
+
+    let mut num_updated = 0;
+
+    if same_instances {
+        let mut dirty_range: Option<Range<usize>> = None;
+        let mut index = 0;
+
+        for item in query.iter() {
+            let num_instances = item.num_instances();
+            let instances = index..index + num_instances;
+            index += num_instances;
+
+            if changed.contains(item.entity) || dirty.contains(&item.entity) {
+                for (instance, instance_data) in instance_buffer.instances[instances.clone()]
+                    .iter_mut()
+                    .zip(item.instance_data(&render_origin.origin))
+                {
+                    *instance = instance_data;
+                }
+                num_updated += num_instances;
+
+                // merge adjacent instances into one write
+                match &mut dirty_range {
+                    Some(range) if range.end == instances.start => range.end = instances.end,
+                    _ => {
+                        if let Some(range) = dirty_range.replace(instances) {
+                            instance_buffer
+                                .buffer
+                                .write_view(range.clone(), &mut *write_staging)
+                                .copy_from_slice(&instance_buffer.instances[range]);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(range) = dirty_range {
+            instance_buffer
+                .buffer
+                .write_view(range.clone(), &mut *write_staging)
+                .copy_from_slice(&instance_buffer.instances[range]);
+        }
+    }
+    else {
+        instance_buffer.instances.clear();
+        instance_buffer.entities.clear();
+        for item in query.iter() {
+            instance_buffer
+                .instances
+                .extend(item.instance_data(&render_origin.origin));
+            instance_buffer
+                .entities
+                .extend(iter::repeat_n(item.entity, item.num_instances()));
+        }
+        num_updated = instance_buffer.instances.len();
+
+        if !instance_buffer.instances.is_empty()
+            && instance_buffer.buffer.write_all(
+                &instance_buffer.instances,
+                |_buffer| {},
+                write_staging,
+            )
+        {
+            reallocated.write(InstanceBufferReallocated);
+        }
+    }
+
+    // the draw commands only change if an instance changed. otherwise we can just
+    // reuse the ones from the last frame.
+    let reuse_draw_list = same_instances && num_updated == 0;
+    if !reuse_draw_list {
+        emit_draw_commands(&query, &mut draw_command_buffer);
+    }
+
+    info.num_instances = instance_buffer.instances.len();
+    info.num_instances_updated = num_updated;
+    info.draw_list_reused = reuse_draw_list;
+    info.prepare_world_staged.instance_buffer = (num_updated * size_of::<InstanceData>()) as u64;
+    info.prepare_world_staged.total = info.prepare_world_staged.command_queue
+        + info.prepare_world_staged.instance_buffer
+        + info.prepare_world_staged.camera_buffers;
+}
+
+fn emit_draw_commands(
+    query: &Query<UpdateInstanceBufferAndDrawCommandQueryData, InstanceFilter>,
+    draw_command_buffer: &mut DrawCommandBuffer,
 ) {
+    let mut draw_command_builder = draw_command_buffer.builder();
+
     // for now every draw call will only draw one instance, but we could do
     // instancing for real later.
-    let mut first_instance = 0;
-    let mut next_instances = || {
-        let instances = first_instance..(first_instance + 1);
-        first_instance += 1;
-        instances
-    };
-
-    let state = &mut *state;
-    let write_staging = state.write_staging.as_mut().unwrap();
-
-    // this is the second time I have a bug with this. keep this assert!
-    assert!(
-        state.instance_buffer.host_staging.is_empty(),
-        "instance scratch buffer hasn't been cleared yet"
-    );
-
-    // prepare the actual draw commands
-    let mut draw_command_builder = state.draw_command_buffer.builder();
+    let mut index = 0;
+    for item in query.iter() {
+        let first_instance = index as u32;
+        let instances = first_instance..first_instance + 1;
+        index += item.num_instances();
+
+        // point meshes are only drawn as points. the material tints the points.
+        if item.mesh.flags.contains(MeshFlags::POINTS) {
+            if item.material.is_some() {
+                draw_command_builder.draw_points(instances, item.mesh, item.mesh_bind_group);
+            }
+            continue;
+        }
 
-    query.iter().for_each(|item| {
+        // line meshes don't have faces, so they can only be drawn as wireframe.
+        let is_lines = item.mesh.flags.contains(MeshFlags::LINES);
         let has_material = item.material.is_some()
             || item.albedo_texture.is_some()
             || item.material_texture.is_some();
         let has_wireframe = item.wireframe.is_some();
 
-        // write per-instance data into a buffer
-        state.instance_buffer.push(InstanceData::new_mesh(
-            item.global_transform,
-            item.mesh,
-            item.material,
-            item.wireframe,
-            item.albedo_texture,
-            item.material_texture,
-            item.outline,
-        ));
-
-        let instances = next_instances();
+        if !is_lines {
+            let texture_bind_group = item.texture_binding.and_then(TextureBinding::bind_group);
+            let outlined = item.outline.is_some();
 
-        if has_material {
             // if it is transparent we need to remember its position to later sort by
-            // distance from camera.
-            let transparent = item
-                .material
-                .is_some_and(|material| material.transparent)
-                .then(|| item.global_transform.position());
-
-            draw_command_builder.draw_mesh(
-                instances.clone(),
-                item.mesh,
-                item.mesh_bind_group,
-                transparent,
-                item.outline.is_some(),
-            );
+            // distance from camera. see-through meshes might be drawn transparent too.
+            let blending = |material: Option<&Material>| {
+                let depth_reference = item.global_transform.position();
+                if item.depth_compositing == Some(&DepthCompositing::XRay) {
+                    MeshBlending::XRay
+                }
+                else if material.is_some_and(|material| material.transparent) {
+                    MeshBlending::Transparent { depth_reference }
+                }
+                else if item.see_through {
+                    MeshBlending::SeeThrough { depth_reference }
+                }
+                else {
+                    MeshBlending::Opaque
+                }
+            };
+
+            match item.material_groups {
+                Some(material_groups) if !material_groups.is_empty() => {
+                    // every group is drawn with its own instance. faces between the groups use
+                    // the entity's material.
+                    let num_faces = item.mesh.num_faces();
+                    let mut next_face = 0;
+                    for (group_instance, group) in
+                        (first_instance + 1..).zip(material_groups.iter())
+                    {
+                        let faces =
+                            group.faces.start.min(num_faces)..group.faces.end.min(num_faces);
+
+                        if has_material && next_face < faces.start {
+                            draw_command_builder.draw_mesh_faces(
+                                instances.clone(),
+                                item.mesh,
+                                next_face..faces.start,
+                                item.mesh_bind_group,
+                                texture_bind_group,
+                                blending(item.material),
+                                outlined,
+                            );
+                        }
+
+                        if !faces.is_empty() {
+                            draw_command_builder.draw_mesh_faces(
+                                group_instance..group_instance + 1,
+                                item.mesh,
+                                faces,
+                                item.mesh_bind_group,
+                                texture_bind_group,
+                                blending(Some(&group.material)),
+                                outlined,
+                            );
+                        }
+                        next_face = next_face.max(faces.end);
+                    }
+
+                    if has_material && next_face < num_faces {
+                        draw_command_builder.draw_mesh_faces(
+                            instances.clone(),
+                            item.mesh,
+                            next_face..num_faces,
+                            item.mesh_bind_group,
+                            texture_bind_group,
+                            blending(item.material),
+                            outlined,
+                        );
+                    }
+                }
+                _ if has_material => {
+                    draw_command_builder.draw_mesh(
+                        instances.clone(),
+                        item.mesh,
+                        item.mesh_bind_group,
+                        texture_bind_group,
+                        blending(item.material),
+                        outlined,
+                    );
+                }
+                _ => {}
+            }
         }
-        if item.outline.is_some() {
+        if item.outline.is_some() && !is_lines {
             draw_command_builder.draw_outline(instances.clone(), item.mesh, item.mesh_bind_group);
         }
         if has_wireframe {
             draw_command_builder.draw_wireframe(instances.clone(), item.mesh, item.mesh_bind_group);
         }
-    });
-
-    // send instance data to gpu
-    // todo: pass `instance_buffer_reallocated` outside of renderer state.
-    state.instance_buffer_reallocated = state.instance_buffer.flush(|_buffer| {}, write_staging);
+    }
 }
 
 #[derive(Debug, Message)]
 pub enum UpdateMeshBindGroupMessage {
     MeshAdded { entity: Entity },
     MeshRemoved { entity: Entity },
-    AlbedoTextureAdded { entity: Entity },
-    AlbedoTextureRemoved { entity: Entity },
-    MaterialTextureAdded { entity: Entity },
-    MaterialTextureRemoved { entity: Entity },
-}
-
-#[derive(QueryData)]
-pub struct UpdateMeshBindGroupsQueryData {
-    name: NameOrEntity,
-    mesh: &'static Mesh,
-    albedo_texture: Option<&'static AlbedoTexture>,
-    material_texture: Option<&'static MaterialTexture>,
 }
 
 pub fn update_mesh_bind_groups(
     renderer: Res<SharedRenderer>,
-    query: Query<UpdateMeshBindGroupsQueryData>,
+    query: Query<(NameOrEntity, &Mesh)>,
     mut messages: MessageReader<UpdateMeshBindGroupMessage>,
     mut commands: Commands,
     mut updated: Local<EntityHashSet>,
@@ -236,25 +546,17 @@ pub fn update_mesh_bind_groups(
 
     messages.read().for_each(|message| {
         match message {
-            UpdateMeshBindGroupMessage::MeshAdded { entity }
-            | UpdateMeshBindGroupMessage::AlbedoTextureAdded { entity }
-            | UpdateMeshBindGroupMessage::MaterialTextureAdded { entity }
-            | UpdateMeshBindGroupMessage::AlbedoTextureRemoved { entity }
-            | UpdateMeshBindGroupMessage::MaterialTextureRemoved { entity } => {
+            UpdateMeshBindGroupMessage::MeshAdded { entity } => {
                 if updated.insert(*entity) {
-                    let item = query.get(*entity).unwrap();
-                    tracing::debug!(?message, name = %item.name, "update mesh bind group");
-
-                    let entity_commands = commands.entity(*entity);
+                    let (name, mesh) = query.get(*entity).unwrap();
+                    tracing::debug!(?message, %name, "update mesh bind group");
 
-                    update_mesh_bind_group(
-                        &renderer,
-                        entity_commands,
-                        item.mesh,
-                        item.albedo_texture,
-                        item.material_texture,
-                        item.name,
+                    let mesh_bind_group = MeshBindGroup::new(
+                        &renderer.device,
+                        &renderer.mesh_bind_group_layout,
+                        mesh,
                     );
+                    commands.entity(*entity).insert(mesh_bind_group);
                 }
             }
             UpdateMeshBindGroupMessage::MeshRemoved { entity } => {
@@ -269,30 +571,71 @@ pub fn update_mesh_bind_groups(
     updated.clear();
 }
 
-pub fn update_mesh_bind_group(
+/// Sent when an [`AlbedoTexture`] or [`MaterialTexture`] is added, replaced or
+/// removed.
+#[derive(Debug, Message)]
+pub struct UpdateTextureBindingMessage {
+    pub entity: Entity,
+}
+
+#[derive(QueryData)]
+pub struct UpdateTextureBindingsQueryData {
+    name: NameOrEntity,
+    mesh: Option<&'static Mesh>,
+    albedo_texture: Option<&'static AlbedoTexture>,
+    material_texture: Option<&'static MaterialTexture>,
+}
+
+pub fn update_texture_bindings(
+    renderer: Res<SharedRenderer>,
+    query: Query<UpdateTextureBindingsQueryData>,
+    mut messages: MessageReader<UpdateTextureBindingMessage>,
+    mut commands: Commands,
+    mut updated: Local<EntityHashSet>,
+) {
+    assert!(updated.is_empty());
+
+    messages.read().for_each(|message| {
+        if !updated.insert(message.entity) {
+            return;
+        }
+
+        // the entity might have been despawned already
+        let Ok(item) = query.get(message.entity)
+        else {
+            return;
+        };
+        tracing::debug!(?message, name = %item.name, "update texture binding");
+
+        update_texture_binding(&renderer, commands.entity(message.entity), item);
+    });
+
+    updated.clear();
+}
+
+fn update_texture_binding(
     renderer: &Renderer,
     mut entity_commands: EntityCommands,
-    mesh: &Mesh,
-    albedo_texture: Option<&AlbedoTexture>,
-    material_texture: Option<&MaterialTexture>,
-    name: NameOrEntityItem,
+    item: UpdateTextureBindingsQueryDataItem,
 ) {
-    if !mesh.flags.contains(MeshFlags::UVS)
-        && (albedo_texture.is_some() || material_texture.is_some())
-    {
-        tracing::warn!(%name, "Mesh with textures, but no UV buffer");
+    // this is also sent when a texture is removed, so there might be none left.
+    if item.albedo_texture.is_none() && item.material_texture.is_none() {
+        entity_commands.try_remove::<TextureBinding>();
+        return;
     }
 
-    let mesh_bind_group = MeshBindGroup::new(
-        &renderer.device,
-        &renderer.mesh_bind_group_layout,
-        mesh,
-        albedo_texture,
-        material_texture,
-        &renderer.fallbacks,
-    );
+    if item
+        .mesh
+        .is_some_and(|mesh| !mesh.flags.contains(MeshFlags::UVS))
+    {
+        tracing::warn!(name = %item.name, "Mesh with textures, but no UV buffer");
+    }
 
-    entity_commands.insert(mesh_bind_group);
+    entity_commands.insert(TextureBinding::new(
+        renderer,
+        item.albedo_texture,
+        item.material_texture,
+    ));
 }
 
 pub fn update_camera_viewports(
@@ -314,11 +657,13 @@ pub struct CreateCameraBindGroupsQueryData {
     ambient_light: Option<&'static AmbientLight>,
     point_light: Option<&'static PointLight>,
     camera_config: Option<&'static CameraConfig>,
+    view_overlays: Option<&'static ViewOverlays>,
 }
 
 pub fn create_camera_bind_groups(
     renderer: Res<SharedRenderer>,
-    state: Res<RendererState>,
+    instance_buffer: Res<InstanceBuffer>,
+    render_origin: Res<RenderOrigin>,
     query: Query<CreateCameraBindGroupsQueryData, Without<CameraBindGroup>>,
     mut commands: Commands,
 ) {
@@ -331,6 +676,7 @@ pub fn create_camera_bind_groups(
              ambient_light,
              point_light,
              camera_config,
+             view_overlays,
          }| {
             tracing::debug!(
                 ?entity,
@@ -344,16 +690,18 @@ pub fn create_camera_bind_groups(
             let camera_data = CameraData::new(
                 camera_projection,
                 global_transform,
+                &render_origin.origin,
                 clear_color,
                 ambient_light,
                 point_light,
                 camera_config,
+                view_overlays,
             );
             let camera_bind_group = CameraBindGroup::new(
                 &renderer.camera_bind_group_layout,
                 &renderer.device,
                 &camera_data,
-                state.instance_buffer.buffer.buffer().unwrap(),
+                instance_buffer.buffer.buffer().unwrap(),
             );
             commands.entity(entity).insert(camera_bind_group);
         },
@@ -385,22 +733,23 @@ pub struct UpdateCameraBindGroupsQueryData {
     ambient_light: Option<&'static AmbientLight>,
     point_light: Option<&'static PointLight>,
     camera_config: Option<&'static CameraConfig>,
+    view_overlays: Option<&'static ViewOverlays>,
 }
 
 pub fn update_camera_bind_groups(
     renderer: Res<SharedRenderer>,
-    mut state: ResMut<RendererState>,
+    instance_buffer: Res<InstanceBuffer>,
+    render_origin: Res<RenderOrigin>,
+    mut frame_staging: ResMut<FrameStaging>,
+    mut reallocated: MessageReader<InstanceBufferReallocated>,
     mut query: Query<UpdateCameraBindGroupsQueryData>,
 ) {
-    let state = &mut *state;
-
-    // todo: changed filter
-    let updated_instance_buffer = state.instance_buffer_reallocated.then_some((
+    let updated_instance_buffer = (reallocated.read().count() > 0).then_some((
         &renderer.camera_bind_group_layout,
-        state.instance_buffer.buffer.buffer().unwrap(),
+        instance_buffer.buffer.buffer().unwrap(),
     ));
 
-    let mut write_staging = state.write_staging.as_mut().unwrap();
+    let mut write_staging = frame_staging.get_mut();
 
     query.iter_mut().for_each(
         |UpdateCameraBindGroupsQueryDataItem {
@@ -411,14 +760,17 @@ pub fn update_camera_bind_groups(
              ambient_light,
              point_light,
              camera_config,
+             view_overlays,
          }| {
             let camera_data = CameraData::new(
                 camera_projection,
                 global_transform,
+                &render_origin.origin,
                 clear_color,
                 ambient_light,
                 point_light,
                 camera_config,
+                view_overlays,
             );
             camera_bind_group.update(
                 &renderer.device,
@@ -432,7 +784,9 @@ pub fn update_camera_bind_groups(
 
 /// Prepares rendering a frame for a specific view.
 ///
-/// This just fetches camera information and the prepared draw commands
+/// This just fetches camera information and the prepared draw commands, and
+/// sorts the transparent meshes if the camera moved or the draw commands
+/// changed.
 ///
 /// The [`DrawCommand`] can be cloned and passed via [`egui::PaintCallback`]
 /// to do the actual rendering with a [`wgpu::RenderPass`].
@@ -443,17 +797,18 @@ pub fn update_camera_bind_groups(
 pub fn grab_draw_list_for_camera(
     In(camera_entity): In<Entity>,
     renderer: Res<SharedRenderer>,
-    state: Res<RendererState>,
+    mut draw_command_buffer: ResMut<DrawCommandBuffer>,
     command_sender: Res<CommandSender>,
     cameras: Query<(
         &CameraBindGroup,
         Option<&CameraConfig>,
+        Option<&ViewOverlays>,
         Has<ClearColor>,
         &GlobalTransform,
     )>,
 ) -> Option<DrawCommand> {
     // get bind group and config for our camera
-    let (camera_resources, camera_config, has_clear_color, camera_transform) =
+    let (camera_resources, camera_config, view_overlays, has_clear_color, camera_transform) =
         cameras.get(camera_entity).unwrap();
 
     // default to all, then apply configuration, so by default stuff will render and
@@ -461,11 +816,21 @@ pub fn grab_draw_list_for_camera(
     // pipeline
     let mut draw_command_flags = DrawCommandFlags::all();
     draw_command_flags.set(DrawCommandFlags::CLEAR, has_clear_color);
+    // this changes how meshes look, so it must be enabled explicitly
+    draw_command_flags.remove(DrawCommandFlags::SEE_THROUGH);
     if let Some(camera_config) = camera_config {
         camera_config.apply_to_draw_command_flags(&mut draw_command_flags);
     }
+    // the collider wireframes overlay draws the wireframes of all meshes
+    if view_overlays.is_some_and(|view_overlays| {
+        view_overlays
+            .flags
+            .contains(OverlayFlags::COLLIDER_WIREFRAMES)
+    }) {
+        draw_command_flags.insert(DrawCommandFlags::DEBUG_WIREFRAME);
+    }
 
-    Some(state.draw_command_buffer.finish(
+    Some(draw_command_buffer.finish(
         &renderer,
         camera_resources.bind_group.clone(),
         camera_transform.position(),
@@ -95,7 +95,7 @@ pub struct TextureSender {
     pub format: wgpu::TextureFormat,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ImageSender {
     shared: Arc<Shared>,
 }
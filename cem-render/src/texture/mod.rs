@@ -1,5 +1,6 @@
 pub mod cache;
 pub mod channel;
+pub mod table;
 
 #[cfg(feature = "mipmap-cache")]
 pub mod mipmap_cache;
@@ -12,6 +13,12 @@ use std::{
     sync::Arc,
 };
 
+use cem_scene::assets::{
+    AssetPath,
+    AssetStore,
+    AssetStoreError,
+    ResolvedAsset,
+};
 use cem_util::wgpu::image::{
     MipLevels,
     UnsupportedColorSpace,
@@ -36,6 +43,12 @@ pub enum TextureLoadError {
 
     #[error(transparent)]
     UnsupportedColorspace(#[from] UnsupportedColorSpace),
+
+    #[error(transparent)]
+    Asset(#[from] AssetStoreError),
+
+    #[error("Project asset wasn't resolved before loading: {path:?}")]
+    Unresolved { path: AssetPath },
 }
 
 #[derive(Clone, Debug)]
@@ -44,6 +57,17 @@ pub enum TextureSource {
         path: PathBuf,
         mip_levels: MipLevels,
     },
+    /// An asset of the project, see [`AssetStore`].
+    Asset {
+        path: AssetPath,
+        mip_levels: MipLevels,
+    },
+    /// An encoded image, e.g. the contents of a PNG file.
+    Memory {
+        label: String,
+        data: Arc<[u8]>,
+        mip_levels: MipLevels,
+    },
     Channel {
         receiver: TextureReceiver,
     },
@@ -57,6 +81,46 @@ impl TextureSource {
         }
     }
 
+    /// Path of the file this is loaded from, if any.
+    pub fn file_path(&self) -> Option<&Path> {
+        match self {
+            Self::File { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    pub fn mip_levels(&self) -> MipLevels {
+        match self {
+            Self::File { mip_levels, .. }
+            | Self::Asset { mip_levels, .. }
+            | Self::Memory { mip_levels, .. } => *mip_levels,
+            Self::Channel { .. } => MipLevels::One,
+        }
+    }
+
+    /// Looks up project assets in the `asset_store`.
+    ///
+    /// This is done before loading, because loading happens asynchronously
+    /// without access to the scene.
+    pub fn resolve(&self, asset_store: &AssetStore) -> Result<Self, AssetStoreError> {
+        match self {
+            Self::Asset { path, mip_levels } => {
+                let mip_levels = *mip_levels;
+                match asset_store.resolve(path)? {
+                    ResolvedAsset::File(path) => Ok(Self::File { path, mip_levels }),
+                    ResolvedAsset::Memory { name, data } => {
+                        Ok(Self::Memory {
+                            label: name,
+                            data,
+                            mip_levels,
+                        })
+                    }
+                }
+            }
+            _ => Ok(self.clone()),
+        }
+    }
+
     pub async fn load(
         &self,
         mut render_resource_manager: AsyncRenderResourceManager,
@@ -67,6 +131,18 @@ impl TextureSource {
                     .load_texture_from_file(path, *mip_levels)
                     .await
             }
+            TextureSource::Asset { path, .. } => {
+                Err(TextureLoadError::Unresolved { path: path.clone() })
+            }
+            TextureSource::Memory {
+                label,
+                data,
+                mip_levels,
+            } => {
+                render_resource_manager
+                    .load_texture_from_memory(label, data, *mip_levels)
+                    .await
+            }
             TextureSource::Channel { receiver } => {
                 let texture = Arc::new(receiver.inner.clone());
                 let texture_view = texture.create_view(&Default::default());
@@ -108,6 +184,15 @@ impl From<&str> for TextureSource {
     }
 }
 
+impl From<AssetPath> for TextureSource {
+    fn from(value: AssetPath) -> Self {
+        Self::Asset {
+            path: value,
+            mip_levels: MipLevels::One,
+        }
+    }
+}
+
 impl From<TextureReceiver> for TextureSource {
     fn from(value: TextureReceiver) -> Self {
         Self::Channel { receiver: value }
@@ -0,0 +1,245 @@
+//! Bindless textures.
+//!
+//! If the device supports binding arrays, all textures used by materials are
+//! put into a [`TextureTable`], which is bound once for all draw calls.
+//! Instances refer to their textures by their index into the table. This way
+//! adding or removing a texture doesn't require a new bind group for the
+//! object, and objects with different textures can be drawn without switching
+//! bind groups.
+//!
+//! Without binding arrays, each textured object gets its own material bind
+//! group (see [`TextureBinding`](crate::material::TextureBinding)).
+
+use std::{
+    num::NonZero,
+    sync::Arc,
+};
+
+use parking_lot::Mutex;
+
+use crate::renderer::Fallbacks;
+
+/// Number of textures that fit into the table.
+///
+/// This must match the size of the binding arrays in
+/// `shader_texture_table.wgsl`.
+pub const TEXTURE_TABLE_SIZE: u32 = 256;
+
+#[derive(Clone, Debug)]
+pub struct TextureTable {
+    state: Arc<Mutex<TableState>>,
+}
+
+#[derive(Debug)]
+struct TableState {
+    slots: Vec<Option<TableEntry>>,
+    free: Vec<u32>,
+
+    /// Bind group for the current contents of the table. This is reset when a
+    /// slot is inserted or freed.
+    bind_group: Option<wgpu::BindGroup>,
+}
+
+#[derive(Debug)]
+struct TableEntry {
+    texture_view: wgpu::TextureView,
+    sampler: wgpu::Sampler,
+}
+
+impl TextureTable {
+    /// Features a device needs for the texture table.
+    pub const REQUIRED_FEATURES: wgpu::Features = wgpu::Features::TEXTURE_BINDING_ARRAY
+        .union(wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING);
+
+    /// Adds the features and limits the texture table needs, if the adapter
+    /// supports them.
+    pub fn request_features(
+        adapter: &wgpu::Adapter,
+        features: &mut wgpu::Features,
+        limits: &mut wgpu::Limits,
+    ) {
+        let adapter_limits = adapter.limits();
+        if adapter.features().contains(Self::REQUIRED_FEATURES)
+            && adapter_limits.max_binding_array_elements_per_shader_stage >= TEXTURE_TABLE_SIZE
+            && adapter_limits.max_binding_array_sampler_elements_per_shader_stage
+                >= TEXTURE_TABLE_SIZE
+        {
+            features.insert(Self::REQUIRED_FEATURES);
+            limits.max_binding_array_elements_per_shader_stage = limits
+                .max_binding_array_elements_per_shader_stage
+                .max(TEXTURE_TABLE_SIZE);
+            limits.max_binding_array_sampler_elements_per_shader_stage = limits
+                .max_binding_array_sampler_elements_per_shader_stage
+                .max(TEXTURE_TABLE_SIZE);
+        }
+    }
+
+    /// Returns whether the device was created with what the texture table
+    /// needs.
+    pub fn is_supported(device: &wgpu::Device) -> bool {
+        let limits = device.limits();
+        device.features().contains(Self::REQUIRED_FEATURES)
+            && limits.max_binding_array_elements_per_shader_stage >= TEXTURE_TABLE_SIZE
+            && limits.max_binding_array_sampler_elements_per_shader_stage >= TEXTURE_TABLE_SIZE
+    }
+
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(TableState {
+                slots: vec![],
+                free: vec![],
+                bind_group: None,
+            })),
+        }
+    }
+
+    pub(crate) fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        let count = NonZero::new(TEXTURE_TABLE_SIZE);
+
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("texture_table_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count,
+                },
+            ],
+        })
+    }
+
+    /// Puts a texture into the table.
+    ///
+    /// Returns `None` if the table is full. The slot is freed when the
+    /// returned [`TextureSlot`] is dropped.
+    pub fn insert(
+        &self,
+        texture_view: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+    ) -> Option<TextureSlot> {
+        let mut state = self.state.lock();
+
+        let index = if let Some(index) = state.free.pop() {
+            index
+        }
+        else if state.slots.len() < TEXTURE_TABLE_SIZE as usize {
+            state.slots.push(None);
+            (state.slots.len() - 1) as u32
+        }
+        else {
+            return None;
+        };
+
+        state.slots[index as usize] = Some(TableEntry {
+            texture_view: texture_view.clone(),
+            sampler: sampler.clone(),
+        });
+        state.bind_group = None;
+
+        Some(TextureSlot {
+            index,
+            state: self.state.clone(),
+        })
+    }
+
+    /// Number of occupied slots.
+    pub fn len(&self) -> usize {
+        let state = self.state.lock();
+        state.slots.len() - state.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the bind group for the current contents of the table.
+    ///
+    /// The bind group is only recreated if the table changed. Unused slots are
+    /// filled with the fallback texture.
+    pub(crate) fn bind_group(
+        &self,
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        fallbacks: &Fallbacks,
+    ) -> wgpu::BindGroup {
+        let mut state = self.state.lock();
+
+        if let Some(bind_group) = &state.bind_group {
+            return bind_group.clone();
+        }
+
+        tracing::debug!(
+            num_textures = state.slots.len(),
+            "update texture table bind group"
+        );
+
+        let entry = |index: usize| state.slots.get(index).and_then(Option::as_ref);
+        let samplers = (0..TEXTURE_TABLE_SIZE as usize)
+            .map(|index| {
+                entry(index).map_or(&fallbacks.sampler_nearest_clamp, |entry| &entry.sampler)
+            })
+            .collect::<Vec<_>>();
+        let texture_views = (0..TEXTURE_TABLE_SIZE as usize)
+            .map(|index| entry(index).map_or(&fallbacks.white, |entry| &entry.texture_view))
+            .collect::<Vec<_>>();
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("texture table"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::SamplerArray(&samplers),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureViewArray(&texture_views),
+                },
+            ],
+        });
+
+        state.bind_group = Some(bind_group.clone());
+        bind_group
+    }
+}
+
+impl Default for TextureTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A texture's slot in the [`TextureTable`].
+///
+/// The slot is freed when this is dropped.
+#[derive(Debug)]
+pub struct TextureSlot {
+    index: u32,
+    state: Arc<Mutex<TableState>>,
+}
+
+impl TextureSlot {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+impl Drop for TextureSlot {
+    fn drop(&mut self) {
+        let mut state = self.state.lock();
+        state.slots[self.index as usize] = None;
+        state.free.push(self.index);
+        state.bind_group = None;
+    }
+}
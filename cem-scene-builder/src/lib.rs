@@ -0,0 +1,213 @@
+#![warn(clippy::todo, unused_qualifications)]
+
+//! Builder API for generating scenes programmatically.
+//!
+//! This is the same API the editor uses to populate presets and import
+//! files, so scenes built with it can be opened in the editor, handed to the
+//! solver, or saved as project files.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use cem_scene::{
+//!     SceneBuilder,
+//!     builtin_plugins,
+//! };
+//! use cem_scene_builder::{
+//!     EntityBuilderExt,
+//!     SceneBuilderExt,
+//!     SceneBuilderPlugin,
+//! };
+//! use nalgebra::Point3;
+//! use parry3d::shape::Ball;
+//!
+//! let mut builder = SceneBuilder::default();
+//! builder.register_plugins(builtin_plugins());
+//! builder.register_plugin(SceneBuilderPlugin);
+//! let mut scene = builder.build();
+//!
+//! scene
+//!     .spawn_object(Point3::new(0.0, 0.0, 0.0), Ball::new(0.5))
+//!     .name("sphere");
+//! ```
+
+use std::{
+    convert::Infallible,
+    fmt::{
+        Debug,
+        Display,
+    },
+};
+
+use bevy_ecs::{
+    component::Component,
+    name::Name,
+    reflect::ReflectComponent,
+    world::EntityWorldMut,
+};
+use bevy_reflect::{
+    Reflect,
+    prelude::ReflectDefault,
+};
+use cem_render::{
+    material::Material,
+    mesh::{
+        IntoGenerateMesh,
+        LoadMesh,
+    },
+};
+use cem_scene::{
+    Scene,
+    SceneBuilder,
+    plugin::Plugin,
+    spatial::Collider,
+    stable_id::StableId,
+    transform::LocalTransform,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// Registers the components this crate adds to entities.
+///
+/// Serializing the scene relies on [`SaveToFile`] being registered, so this
+/// should be registered before any objects are spawned.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SceneBuilderPlugin;
+
+impl Plugin for SceneBuilderPlugin {
+    fn setup(&self, builder: &mut SceneBuilder) {
+        builder.world.register_component::<SaveToFile>();
+    }
+}
+
+/// Marks entities that are written to project files.
+///
+/// These entities get a [`StableId`], so that they can be referred to across
+/// save and load.
+#[derive(Debug, Default, Serialize, Deserialize, Component, Reflect)]
+#[reflect(Component, Default)]
+#[require(StableId)]
+pub struct SaveToFile;
+
+pub trait SceneBuilderExt {
+    /// Spawns an object with the given shape.
+    ///
+    /// The object gets a name derived from the shape, a collider, a mesh
+    /// generated from the shape, and is tagged with [`SaveToFile`]. Use
+    /// [`EntityBuilderExt`] on the returned entity to add a material or
+    /// override any of these.
+    fn spawn_object<S>(
+        &mut self,
+        transform: impl Into<LocalTransform>,
+        shape: S,
+    ) -> EntityWorldMut<'_>
+    where
+        S: ShapeName + Clone + IntoGenerateMesh<Error = Infallible>,
+        Collider: From<S>,
+        S::Config: Default,
+        S::GenerateMesh: Debug + Send + Sync + 'static;
+}
+
+impl SceneBuilderExt for Scene {
+    fn spawn_object<S>(
+        &mut self,
+        transform: impl Into<LocalTransform>,
+        shape: S,
+    ) -> EntityWorldMut<'_>
+    where
+        S: ShapeName + Clone + IntoGenerateMesh<Error = Infallible>,
+        Collider: From<S>,
+        S::Config: Default,
+        S::GenerateMesh: Debug + Send + Sync + 'static,
+    {
+        let name = shape.shape_name().to_owned();
+        let collider = Collider::from(shape.clone());
+        let mesh = LoadMesh::from_shape(shape, Default::default());
+
+        self.world
+            .spawn_empty()
+            .name(name)
+            .transform(transform)
+            .collider(collider)
+            .mesh(mesh)
+            .tagged::<SaveToFile>(true)
+    }
+}
+
+/// Chainable helpers for inserting the common components of scene objects.
+pub trait EntityBuilderExt {
+    fn transform(self, transform: impl Into<LocalTransform>) -> Self;
+    fn material(self, material: impl Into<Material>) -> Self;
+    fn mesh(self, mesh: impl Into<LoadMesh>) -> Self;
+    fn collider(self, collider: impl Into<Collider>) -> Self;
+    fn name(self, label: impl Display) -> Self;
+    fn tagged<T>(self, on: bool) -> Self
+    where
+        T: Default + Component;
+}
+
+impl<'a> EntityBuilderExt for EntityWorldMut<'a> {
+    fn transform(mut self, transform: impl Into<LocalTransform>) -> Self {
+        self.insert(transform.into());
+        self
+    }
+
+    fn material(mut self, material: impl Into<Material>) -> Self {
+        self.insert(material.into());
+        self
+    }
+
+    fn mesh(mut self, mesh: impl Into<LoadMesh>) -> Self {
+        self.insert(mesh.into());
+        self
+    }
+
+    fn collider(mut self, collider: impl Into<Collider>) -> Self {
+        self.insert(collider.into());
+        self
+    }
+
+    fn name(mut self, label: impl Display) -> Self {
+        self.insert(Name::new(label.to_string()));
+        self
+    }
+
+    fn tagged<T>(mut self, on: bool) -> Self
+    where
+        T: Default + Component,
+    {
+        if on {
+            self.insert(T::default());
+        }
+        self
+    }
+}
+
+// todo: implement a proper way of naming things and remove this
+pub trait ShapeName {
+    fn shape_name(&self) -> &str;
+}
+
+mod shape_names {
+    use parry3d::shape::*;
+
+    macro_rules! shape_name {
+        {$($ty:ty,)*} => {
+            $(
+                impl super::ShapeName for $ty {
+                    fn shape_name(&self) -> &str {
+                        stringify!($ty)
+                    }
+                }
+            )*
+        };
+    }
+
+    shape_name! {
+        Ball,
+        Cuboid,
+        Cylinder,
+    }
+}
@@ -1,4 +1,5 @@
 mod plugin;
+mod store;
 mod systems;
 
 use bevy_ecs::{
@@ -13,10 +14,16 @@ pub use plugin::{
     AssetLoaderSystems,
     AssetPlugin,
 };
+pub use store::{
+    AssetPath,
+    AssetStore,
+    AssetStoreError,
+    ResolvedAsset,
+};
 
 pub trait LoadAsset: Component {
     type Context: SystemParam + 'static;
-    type Error: std::error::Error;
+    type Error: std::error::Error + Send + Sync + 'static;
 
     fn load(
         &self,
@@ -6,6 +6,7 @@ use bevy_ecs::schedule::{
 use crate::{
     SceneBuilder,
     assets::{
+        AssetStore,
         LoadAsset,
         systems::start_loading,
     },
@@ -18,9 +19,7 @@ pub struct AssetPlugin;
 
 impl Plugin for AssetPlugin {
     fn setup(&self, builder: &mut SceneBuilder) {
-        // note: this doesn't do anything but we'll keep it since we will probably add
-        // stuff (e.g. resources) at some point
-        let _ = builder;
+        builder.insert_resource(AssetStore::default());
     }
 }
 
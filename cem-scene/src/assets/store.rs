@@ -0,0 +1,180 @@
+use std::{
+    collections::BTreeMap,
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::Arc,
+};
+
+use bevy_ecs::resource::Resource;
+
+/// Reference to an asset that belongs to a project.
+///
+/// Unlike plain paths these stay valid when the project is moved or shared,
+/// as long as its assets are moved with it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AssetPath {
+    /// Path relative to the project directory.
+    Project(PathBuf),
+
+    /// Asset that is stored in the project file itself.
+    Embedded(String),
+}
+
+impl AssetPath {
+    /// A name for the asset, e.g. for labels.
+    pub fn name(&self) -> String {
+        match self {
+            AssetPath::Project(path) => path.display().to_string(),
+            AssetPath::Embedded(name) => name.clone(),
+        }
+    }
+}
+
+/// Where an [`AssetPath`] can actually be loaded from.
+#[derive(Clone, Debug)]
+pub enum ResolvedAsset {
+    File(PathBuf),
+    Memory { name: String, data: Arc<[u8]> },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AssetStoreError {
+    #[error("Asset {path:?} is relative to the project, but the project has no directory yet")]
+    NoProjectDirectory { path: PathBuf },
+
+    #[error("No embedded asset named {name:?}")]
+    NotEmbedded { name: String },
+}
+
+/// Assets of the project the scene belongs to.
+///
+/// This knows the project directory to resolve [`AssetPath::Project`], and
+/// holds the data of embedded assets.
+#[derive(Clone, Debug, Default, Resource)]
+pub struct AssetStore {
+    project_directory: Option<PathBuf>,
+    embedded: BTreeMap<String, Arc<[u8]>>,
+}
+
+impl AssetStore {
+    pub fn project_directory(&self) -> Option<&Path> {
+        self.project_directory.as_deref()
+    }
+
+    pub fn set_project_directory(&mut self, directory: Option<PathBuf>) {
+        self.project_directory = directory;
+    }
+
+    pub fn resolve(&self, path: &AssetPath) -> Result<ResolvedAsset, AssetStoreError> {
+        match path {
+            AssetPath::Project(path) => {
+                let directory = self
+                    .project_directory
+                    .as_ref()
+                    .ok_or_else(|| AssetStoreError::NoProjectDirectory { path: path.clone() })?;
+                Ok(ResolvedAsset::File(directory.join(path)))
+            }
+            AssetPath::Embedded(name) => {
+                let data = self
+                    .embedded
+                    .get(name)
+                    .ok_or_else(|| AssetStoreError::NotEmbedded { name: name.clone() })?;
+                Ok(ResolvedAsset::Memory {
+                    name: name.clone(),
+                    data: data.clone(),
+                })
+            }
+        }
+    }
+
+    /// Turns `path` into a project-relative path, if it's inside the project
+    /// directory.
+    pub fn relative_path(&self, path: &Path) -> Option<AssetPath> {
+        let directory = self.project_directory.as_ref()?;
+        let path = path.strip_prefix(directory).ok()?;
+        Some(AssetPath::Project(path.to_owned()))
+    }
+
+    /// Embeds `data` into the project.
+    ///
+    /// If there already is an asset named `name` with different data, a
+    /// suffix is added to the name. The name that was actually used is
+    /// returned.
+    pub fn embed(&mut self, name: &str, data: impl Into<Arc<[u8]>>) -> AssetPath {
+        let data = data.into();
+
+        let mut unique_name = name.to_owned();
+        let mut counter = 1;
+        while let Some(existing) = self.embedded.get(&unique_name) {
+            if *existing == data {
+                return AssetPath::Embedded(unique_name);
+            }
+            counter += 1;
+            unique_name = format!("{name}.{counter}");
+        }
+
+        self.embedded.insert(unique_name.clone(), data);
+        AssetPath::Embedded(unique_name)
+    }
+
+    pub fn embedded(&self) -> impl Iterator<Item = (&str, &Arc<[u8]>)> {
+        self.embedded
+            .iter()
+            .map(|(name, data)| (name.as_str(), data))
+    }
+
+    pub fn remove_embedded(&mut self, name: &str) -> Option<Arc<[u8]>> {
+        self.embedded.remove(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::assets::{
+        AssetPath,
+        AssetStore,
+        ResolvedAsset,
+    };
+
+    #[test]
+    fn resolves_project_paths() {
+        let mut store = AssetStore::default();
+        let path = AssetPath::Project("textures/ground.png".into());
+        assert!(store.resolve(&path).is_err());
+
+        store.set_project_directory(Some("/projects/antenna".into()));
+        match store.resolve(&path).unwrap() {
+            ResolvedAsset::File(resolved) => {
+                assert_eq!(
+                    resolved,
+                    PathBuf::from("/projects/antenna/textures/ground.png")
+                );
+            }
+            resolved => panic!("unexpected resolved asset: {resolved:?}"),
+        }
+
+        assert_eq!(
+            store.relative_path("/projects/antenna/textures/ground.png".as_ref()),
+            Some(path)
+        );
+        assert_eq!(store.relative_path("/elsewhere/ground.png".as_ref()), None);
+    }
+
+    #[test]
+    fn deduplicates_embedded_names() {
+        let mut store = AssetStore::default();
+        let a = store.embed("texture.png", vec![1, 2, 3]);
+        let b = store.embed("texture.png", vec![1, 2, 3]);
+        let c = store.embed("texture.png", vec![4, 5, 6]);
+
+        assert_eq!(a, AssetPath::Embedded("texture.png".to_owned()));
+        assert_eq!(a, b);
+        assert_eq!(c, AssetPath::Embedded("texture.png.2".to_owned()));
+        assert_eq!(store.embedded().count(), 2);
+    }
+}
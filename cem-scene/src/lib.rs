@@ -2,6 +2,7 @@
 
 pub mod assets;
 pub mod async_commands;
+pub mod material_groups;
 pub mod plugin;
 #[cfg(feature = "probe")]
 pub mod probe;
@@ -9,9 +10,13 @@ pub mod schedule;
 #[cfg(feature = "serde")]
 pub mod serde;
 pub mod spatial;
+pub mod stable_id;
 pub mod transform;
 
-use std::sync::OnceLock;
+use std::{
+    sync::OnceLock,
+    time::Instant,
+};
 
 use bevy_ecs::{
     message::{
@@ -39,7 +44,9 @@ use crate::{
         Plugin,
         PluginRegistry,
     },
+    schedule::ScheduleTimings,
     spatial::SpatialQueryPlugin,
+    stable_id::StableIdPlugin,
     transform::TransformHierarchyPlugin,
 };
 
@@ -50,13 +57,25 @@ pub struct Scene {
 
 impl Scene {
     pub fn update(&mut self) {
-        self.world.run_schedule(schedule::PreUpdate);
-        self.world.run_schedule(schedule::Update);
-        self.world.run_schedule(schedule::PostUpdate);
+        self.run_schedule(schedule::PreUpdate);
+        self.run_schedule(schedule::Update);
+        self.run_schedule(schedule::PostUpdate);
     }
 
     pub fn render(&mut self) {
-        self.world.run_schedule(schedule::Render);
+        self.run_schedule(schedule::Render);
+    }
+
+    /// Runs a schedule and records how long it took in [`ScheduleTimings`].
+    fn run_schedule(&mut self, label: impl ScheduleLabel) {
+        let label = label.intern();
+        let start = Instant::now();
+        self.world.run_schedule(label);
+        let duration = start.elapsed();
+
+        self.world
+            .get_resource_or_init::<ScheduleTimings>()
+            .record(label, duration);
     }
 }
 
@@ -145,12 +164,13 @@ pub fn builtin_plugins() -> &'static PluginRegistry {
         builtin.register(TransformHierarchyPlugin);
         builtin.register(SpatialQueryPlugin);
         builtin.register(AsyncPlugin);
+        builtin.register(StableIdPlugin);
         builtin
     })
 }
 
 pub trait PopulateScene {
-    type Error;
+    type Error: std::error::Error + Send + Sync + 'static;
 
     fn populate_scene(&self, scene: &mut Scene) -> Result<(), Self::Error>;
 }
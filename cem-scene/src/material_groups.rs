@@ -0,0 +1,155 @@
+//! Different materials for parts of one mesh.
+//!
+//! Imported meshes often come with material groups, e.g. a PCB with copper
+//! traces on a substrate. Instead of splitting those into separate objects,
+//! the mesh entity gets a [`MaterialGroups`] component that assigns a material
+//! to ranges of its faces.
+//!
+//! The component is generic over the material, so the renderer and the solver
+//! can each use their own material type for the same mesh.
+
+use std::ops::Range;
+
+use bevy_ecs::component::Component;
+use nalgebra::{
+    Isometry3,
+    Point3,
+};
+
+use crate::spatial::traits::PointQuery;
+
+/// A material for a range of faces of a mesh.
+#[derive(Clone, Debug)]
+pub struct MaterialGroup<M> {
+    /// Name of the group, e.g. the material name from the file it was imported
+    /// from.
+    pub name: String,
+
+    /// Range of faces (not indices) this group covers.
+    pub faces: Range<u32>,
+
+    pub material: M,
+}
+
+/// Materials for sub-ranges of the faces of an entity's mesh.
+///
+/// Faces that aren't covered by any group use the entity's own material.
+#[derive(Clone, Debug, Component)]
+pub struct MaterialGroups<M: Send + Sync + 'static> {
+    groups: Vec<MaterialGroup<M>>,
+}
+
+impl<M: Send + Sync + 'static> Default for MaterialGroups<M> {
+    fn default() -> Self {
+        Self { groups: vec![] }
+    }
+}
+
+impl<M: Send + Sync + 'static> MaterialGroups<M> {
+    /// Adds a group.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the faces overlap with an existing group.
+    pub fn push(&mut self, name: impl Into<String>, faces: Range<u32>, material: M) {
+        assert!(
+            self.groups
+                .iter()
+                .all(|group| group.faces.end <= faces.start || faces.end <= group.faces.start),
+            "Material group {faces:?} overlaps with an existing group"
+        );
+
+        let index = self
+            .groups
+            .partition_point(|group| group.faces.start < faces.start);
+        self.groups.insert(
+            index,
+            MaterialGroup {
+                name: name.into(),
+                faces,
+                material,
+            },
+        );
+    }
+
+    pub fn with_group(mut self, name: impl Into<String>, faces: Range<u32>, material: M) -> Self {
+        self.push(name, faces, material);
+        self
+    }
+
+    /// The groups, ordered by their faces.
+    pub fn iter(&self) -> impl Iterator<Item = &MaterialGroup<M>> {
+        self.groups.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut MaterialGroup<M>> {
+        self.groups.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// The group the face belongs to.
+    pub fn group_for_face(&self, face: u32) -> Option<&MaterialGroup<M>> {
+        let index = self.groups.partition_point(|group| group.faces.end <= face);
+        self.groups
+            .get(index)
+            .filter(|group| group.faces.contains(&face))
+    }
+
+    /// The group of the face closest to `point`.
+    ///
+    /// This is used to decide which material a point inside a solid belongs
+    /// to. Returns `None` if the shape doesn't report faces, or the face is
+    /// not in any group.
+    pub fn group_at_point(
+        &self,
+        shape: &(impl PointQuery + ?Sized),
+        transform: &Isometry3<f32>,
+        point: &Point3<f32>,
+    ) -> Option<&MaterialGroup<M>> {
+        self.group_for_face(shape.closest_face(transform, point)?)
+    }
+}
+
+impl<M: Send + Sync + 'static> FromIterator<MaterialGroup<M>> for MaterialGroups<M> {
+    fn from_iter<T: IntoIterator<Item = MaterialGroup<M>>>(iter: T) -> Self {
+        let mut groups = Self::default();
+        for group in iter {
+            groups.push(group.name, group.faces, group.material);
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material_groups::MaterialGroups;
+
+    #[test]
+    fn finds_group_for_face() {
+        let groups = MaterialGroups::default()
+            .with_group("b", 10..20, 'b')
+            .with_group("a", 0..4, 'a');
+
+        assert_eq!(groups.group_for_face(0).unwrap().material, 'a');
+        assert_eq!(groups.group_for_face(3).unwrap().material, 'a');
+        assert!(groups.group_for_face(4).is_none());
+        assert_eq!(groups.group_for_face(10).unwrap().material, 'b');
+        assert_eq!(groups.group_for_face(19).unwrap().material, 'b');
+        assert!(groups.group_for_face(20).is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_overlapping_groups() {
+        let _ = MaterialGroups::default()
+            .with_group("a", 0..10, ())
+            .with_group("b", 5..15, ());
+    }
+}
@@ -1,4 +1,14 @@
-use bevy_ecs::schedule::ScheduleLabel;
+use std::time::Duration;
+
+use bevy_ecs::{
+    resource::Resource,
+    schedule::{
+        InternedScheduleLabel,
+        ScheduleLabel,
+        Schedules,
+    },
+    world::World,
+};
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, ScheduleLabel)]
 pub struct Startup;
@@ -17,3 +27,47 @@ pub struct PostUpdate;
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, ScheduleLabel)]
 pub struct Render;
+
+/// How long the schedules took the last time they were run by
+/// [`Scene::update`](crate::Scene::update) and
+/// [`Scene::render`](crate::Scene::render).
+#[derive(Clone, Debug, Default, Resource)]
+pub struct ScheduleTimings {
+    timings: Vec<(InternedScheduleLabel, Duration)>,
+}
+
+impl ScheduleTimings {
+    pub fn get(&self, label: impl ScheduleLabel) -> Option<Duration> {
+        let label = label.intern();
+        self.timings
+            .iter()
+            .find_map(|(other, duration)| (*other == label).then_some(*duration))
+    }
+
+    /// Timings in the order the schedules were first run.
+    pub fn iter(&self) -> impl Iterator<Item = (InternedScheduleLabel, Duration)> {
+        self.timings.iter().copied()
+    }
+
+    pub(crate) fn record(&mut self, label: InternedScheduleLabel, duration: Duration) {
+        if let Some((_, timing)) = self.timings.iter_mut().find(|(other, _)| *other == label) {
+            *timing = duration;
+        }
+        else {
+            self.timings.push((label, duration));
+        }
+    }
+}
+
+/// Names of the systems in a schedule.
+///
+/// Returns `None` if the schedule doesn't exist or hasn't been run yet.
+pub fn schedule_systems(world: &World, label: impl ScheduleLabel) -> Option<Vec<String>> {
+    let schedule = world.get_resource::<Schedules>()?.get(label)?;
+    let systems = schedule.systems().ok()?;
+    Some(
+        systems
+            .map(|(_, system)| system.name().to_string())
+            .collect(),
+    )
+}
@@ -1,7 +1,16 @@
-use std::marker::PhantomData;
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    fmt::{
+        self,
+        Display,
+    },
+    marker::PhantomData,
+};
 
 use bevy_ecs::{
     entity::Entity,
+    hierarchy::ChildOf,
     query::QueryFilter,
     reflect::{
         AppTypeRegistry,
@@ -11,19 +20,36 @@ use bevy_ecs::{
 };
 use bevy_reflect::{
     ReflectSerialize,
+    TypePath,
     TypeRegistry,
-    serde::ReflectSerializer,
+    serde::{
+        ReflectSerializer,
+        TypedReflectDeserializer,
+    },
 };
 use cem_util::serde::FlattenMapSerializer;
 use serde::{
+    Deserializer,
     Serialize,
     Serializer,
+    de::{
+        self,
+        DeserializeSeed,
+        MapAccess,
+        SeqAccess,
+        Visitor,
+    },
     ser::{
         SerializeMap,
         SerializeSeq,
     },
 };
 
+use crate::stable_id::{
+    StableId,
+    StableIds,
+};
+
 pub struct WorldSerialize<'world, F> {
     pub world: &'world World,
     pub _filter: PhantomData<F>,
@@ -80,10 +106,20 @@ impl<'world> Serialize for EntitySerialize<'world> {
     {
         let mut components_map = serializer.serialize_map(None)?;
 
-        components_map.serialize_entry("id", &self.entity)?;
-
         let entity = self.world.entity(self.entity);
 
+        // entity IDs aren't stable across save and load, so references to entities
+        // use the stable ID.
+        if let Some(stable_id) = entity.get::<StableId>() {
+            components_map.serialize_entry("id", stable_id)?;
+        }
+        if let Some(parent_id) = entity
+            .get::<ChildOf>()
+            .and_then(|child_of| self.world.get::<StableId>(child_of.parent()))
+        {
+            components_map.serialize_entry("parent", parent_id)?;
+        }
+
         let reflect_components =
             entity
                 .archetype()
@@ -92,6 +128,12 @@ impl<'world> Serialize for EntitySerialize<'world> {
                 .copied()
                 .filter_map(|component_id| {
                     let type_id = self.world.components().get_info(component_id)?.type_id()?;
+
+                    // these are written above
+                    if type_id == TypeId::of::<StableId>() || type_id == TypeId::of::<ChildOf>() {
+                        return None;
+                    }
+
                     let type_registration = self.type_registry.get(type_id)?;
 
                     if type_registration.contains::<ReflectSerialize>() {
@@ -114,3 +156,349 @@ impl<'world> Serialize for EntitySerialize<'world> {
         components_map.end()
     }
 }
+
+/// Spawns the entities written by [`WorldSerialize`] into a world.
+///
+/// Parents are restored once all entities are spawned. Returns the spawned
+/// entities.
+pub struct WorldDeserialize<'world> {
+    pub world: &'world mut World,
+    pub ids: SavedIds,
+}
+
+impl<'world> WorldDeserialize<'world> {
+    /// Deserializes entities that keep their [`StableId`]s.
+    pub fn new(world: &'world mut World) -> Self {
+        Self {
+            world,
+            ids: SavedIds::Keep,
+        }
+    }
+
+    pub fn with_ids(mut self, ids: SavedIds) -> Self {
+        self.ids = ids;
+        self
+    }
+}
+
+/// How the deserialized entities were identified when they were saved, and
+/// which [`StableId`]s they get.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SavedIds {
+    /// By their [`StableId`], which they keep.
+    #[default]
+    Keep,
+
+    /// By their [`StableId`], but they get new ones. This is used for copies
+    /// of entities that are still in the world.
+    Renew,
+
+    /// By the [`Entity`] they had when they were saved, with their parent as a
+    /// `ChildOf` component. They get new [`StableId`]s.
+    ///
+    /// This is how [`WorldSerialize`] wrote entities before there were
+    /// [`StableId`]s.
+    Entity,
+}
+
+/// ID of an entity in serialized data, see [`SavedIds`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum SavedId {
+    Stable(StableId),
+    Entity(Entity),
+}
+
+impl Display for SavedId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stable(id) => id.fmt(f),
+            Self::Entity(entity) => entity.fmt(f),
+        }
+    }
+}
+
+impl<'de, 'world> DeserializeSeed<'de> for WorldDeserialize<'world> {
+    type Value = Vec<Entity>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'world> Visitor<'de> for WorldDeserialize<'world> {
+    type Value = Vec<Entity>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of entities")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let type_registry = self.world.resource::<AppTypeRegistry>().clone();
+        let type_registry = type_registry.read();
+
+        let mut entities = vec![];
+        let mut parents = vec![];
+        let mut spawned = HashMap::new();
+
+        while let Some(SpawnedEntity { entity, id, parent }) =
+            seq.next_element_seed(EntityDeserialize {
+                world: &mut *self.world,
+                type_registry: &type_registry,
+                ids: self.ids,
+            })?
+        {
+            entities.push(entity);
+            if let Some(id) = id {
+                spawned.insert(id, entity);
+            }
+            if let Some(parent) = parent {
+                parents.push((entity, parent));
+            }
+        }
+
+        // parents can come after their children, so they're only looked up once
+        // everything is spawned. a copied entity whose parent wasn't copied stays
+        // with the original parent.
+        for (entity, parent) in parents {
+            let parent = spawned
+                .get(&parent)
+                .copied()
+                .or_else(|| {
+                    match parent {
+                        SavedId::Stable(parent) => self.world.resource::<StableIds>().get(&parent),
+                        SavedId::Entity(_) => None,
+                    }
+                })
+                .ok_or_else(|| de::Error::custom(format_args!("Unknown parent: {parent}")))?;
+            self.world.entity_mut(entity).insert(ChildOf(parent));
+        }
+
+        Ok(entities)
+    }
+}
+
+struct EntityDeserialize<'world, 'registry> {
+    world: &'world mut World,
+    type_registry: &'registry TypeRegistry,
+    ids: SavedIds,
+}
+
+struct SpawnedEntity {
+    entity: Entity,
+    id: Option<SavedId>,
+    parent: Option<SavedId>,
+}
+
+impl<'de, 'world, 'registry> DeserializeSeed<'de> for EntityDeserialize<'world, 'registry> {
+    type Value = SpawnedEntity;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, 'world, 'registry> Visitor<'de> for EntityDeserialize<'world, 'registry> {
+    type Value = SpawnedEntity;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of components")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut entity = self.world.spawn_empty();
+        let mut id = None;
+        let mut parent = None;
+
+        // only kept IDs are read from the data, everything else gets a new one
+        if self.ids != SavedIds::Keep {
+            entity.insert(StableId::new());
+        }
+
+        while let Some(key) = map.next_key::<String>()? {
+            match (self.ids, key.as_str()) {
+                (SavedIds::Keep, "id") => {
+                    let stable_id = map.next_value::<StableId>()?;
+                    entity.insert(stable_id);
+                    id = Some(SavedId::Stable(stable_id));
+                }
+                (SavedIds::Renew, "id") => {
+                    id = Some(SavedId::Stable(map.next_value()?));
+                }
+                (SavedIds::Keep | SavedIds::Renew, "parent") => {
+                    parent = Some(SavedId::Stable(map.next_value()?));
+                }
+                (SavedIds::Entity, "id") => {
+                    id = Some(SavedId::Entity(map.next_value()?));
+                }
+                (SavedIds::Entity, type_path) if type_path == ChildOf::type_path() => {
+                    parent = Some(SavedId::Entity(map.next_value::<ChildOf>()?.parent()));
+                }
+                (_, type_path) => {
+                    let type_registration = self
+                        .type_registry
+                        .get_with_type_path(type_path)
+                        .ok_or_else(|| {
+                            de::Error::custom(format_args!("Unknown component: {type_path}"))
+                        })?;
+                    let reflect_component = type_registration
+                        .data::<ReflectComponent>()
+                        .ok_or_else(|| {
+                            de::Error::custom(format_args!("Not a component: {type_path}"))
+                        })?;
+                    let component = map.next_value_seed(TypedReflectDeserializer::new(
+                        type_registration,
+                        self.type_registry,
+                    ))?;
+                    reflect_component.insert(&mut entity, &*component, self.type_registry);
+                }
+            }
+        }
+
+        Ok(SpawnedEntity {
+            entity: entity.id(),
+            id,
+            parent,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        component::Component,
+        hierarchy::ChildOf,
+        query::With,
+        reflect::{
+            AppTypeRegistry,
+            ReflectComponent,
+        },
+        world::World,
+    };
+    use bevy_reflect::{
+        Reflect,
+        ReflectDeserialize,
+        ReflectSerialize,
+    };
+    use serde::{
+        Deserialize,
+        Serialize,
+        de::DeserializeSeed,
+    };
+
+    use crate::{
+        serde::{
+            SavedIds,
+            WorldDeserialize,
+            WorldSerialize,
+        },
+        stable_id::{
+            StableId,
+            StableIds,
+        },
+    };
+
+    #[derive(Clone, Debug, PartialEq, Component, Reflect, Serialize, Deserialize)]
+    #[reflect(Component, Serialize, Deserialize)]
+    struct Label(String);
+
+    fn empty_world() -> World {
+        let mut world = World::new();
+        world.insert_resource(StableIds::default());
+        let type_registry = AppTypeRegistry::default();
+        type_registry.write().register::<Label>();
+        world.insert_resource(type_registry);
+        world
+    }
+
+    #[test]
+    fn stable_ids_and_parents_survive_round_trip() {
+        let mut world = empty_world();
+        let parent_id = StableId::new();
+        let child_id = StableId::new();
+        // spawn the child first, so that its parent comes after it
+        let child = world.spawn((child_id, Label("child".to_owned()))).id();
+        let parent = world.spawn((parent_id, Label("parent".to_owned()))).id();
+        world.entity_mut(child).insert(ChildOf(parent));
+
+        let json = serde_json::to_string(&WorldSerialize::<With<Label>>::new(&world)).unwrap();
+
+        let mut loaded = empty_world();
+        let entities = WorldDeserialize::new(&mut loaded)
+            .deserialize(&mut serde_json::Deserializer::from_str(&json))
+            .unwrap();
+        assert_eq!(entities.len(), 2);
+
+        let stable_ids = loaded.resource::<StableIds>();
+        let parent = stable_ids.get(&parent_id).unwrap();
+        let child = stable_ids.get(&child_id).unwrap();
+        assert_eq!(
+            loaded.get::<Label>(parent),
+            Some(&Label("parent".to_owned()))
+        );
+        assert_eq!(loaded.get::<Label>(child), Some(&Label("child".to_owned())));
+        assert_eq!(
+            loaded.get::<ChildOf>(child).map(ChildOf::parent),
+            Some(parent)
+        );
+    }
+
+    #[test]
+    fn pasted_copies_get_new_stable_ids() {
+        let mut world = empty_world();
+        let root = world
+            .spawn((StableId::new(), Label("root".to_owned())))
+            .id();
+        let group = world
+            .spawn((StableId::new(), Label("group".to_owned()), ChildOf(root)))
+            .id();
+        world.spawn((StableId::new(), Label("member".to_owned()), ChildOf(group)));
+
+        // the root isn't copied, so the copy of the group stays in it
+        let json = serde_json::to_string(&WorldSerialize::<With<ChildOf>>::new(&world)).unwrap();
+        let copies = WorldDeserialize::new(&mut world)
+            .with_ids(SavedIds::Renew)
+            .deserialize(&mut serde_json::Deserializer::from_str(&json))
+            .unwrap();
+        assert_eq!(copies.len(), 2);
+
+        let find_copy = |label: &str| {
+            copies
+                .iter()
+                .copied()
+                .find(|copy| {
+                    world
+                        .get::<Label>(*copy)
+                        .is_some_and(|copy| copy.0 == label)
+                })
+                .unwrap()
+        };
+        let group_copy = find_copy("group");
+        let member_copy = find_copy("member");
+
+        assert_ne!(
+            world.get::<StableId>(group_copy),
+            world.get::<StableId>(group)
+        );
+        assert_eq!(
+            world.get::<ChildOf>(group_copy).map(ChildOf::parent),
+            Some(root)
+        );
+        assert_eq!(
+            world.get::<ChildOf>(member_copy).map(ChildOf::parent),
+            Some(group_copy)
+        );
+        assert_eq!(world.resource::<StableIds>().len(), 5);
+    }
+}
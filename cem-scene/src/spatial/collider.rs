@@ -35,6 +35,12 @@ use crate::spatial::{
 #[component(on_add = collider_added, on_remove = collider_removed)]
 pub struct Collider {
     inner: Arc<dyn AnyCollider>,
+
+    /// Transforms from the shape's frame to the entity's frame.
+    ///
+    /// This is the identity, unless the entity's origin was moved without
+    /// moving its geometry.
+    offset: Isometry3<f32>,
 }
 
 fn collider_added(mut world: DeferredWorld, context: HookContext) {
@@ -51,7 +57,14 @@ fn collider_removed(mut world: DeferredWorld, context: HookContext) {
 
 impl Debug for Collider {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("Collider").field(&*self.inner).finish()
+        // the offset is left out if it's the identity, so the geometry hashes of
+        // existing results don't change.
+        let mut f = f.debug_tuple("Collider");
+        f.field(&*self.inner);
+        if self.offset != Isometry3::identity() {
+            f.field(&self.offset);
+        }
+        f.finish()
     }
 }
 
@@ -65,13 +78,26 @@ impl Deref for Collider {
 
 impl Collider {
     pub fn new(value: Arc<dyn AnyCollider>) -> Self {
-        Self { inner: value }
+        Self {
+            inner: value,
+            offset: Isometry3::identity(),
+        }
+    }
+
+    pub fn offset(&self) -> &Isometry3<f32> {
+        &self.offset
+    }
+
+    /// Sets the transform from the shape's frame to the entity's frame.
+    pub fn with_offset(mut self, offset: Isometry3<f32>) -> Self {
+        self.offset = offset;
+        self
     }
 }
 
 impl ComputeAabb for Collider {
     fn compute_aabb(&self, transform: &Isometry3<f32>) -> Option<Aabb> {
-        self.inner.compute_aabb(transform)
+        self.inner.compute_aabb(&(transform * self.offset))
     }
 }
 
@@ -84,7 +110,7 @@ impl RayCast for Collider {
         solid: bool,
     ) -> Option<RayIntersection> {
         self.inner
-            .cast_ray(transform, ray, max_time_of_impact, solid)
+            .cast_ray(&(transform * self.offset), ray, max_time_of_impact, solid)
     }
 
     fn supported(&self) -> bool {
@@ -94,7 +120,11 @@ impl RayCast for Collider {
 
 impl PointQuery for Collider {
     fn contains_point(&self, transform: &Isometry3<f32>, point: &Point3<f32>) -> bool {
-        self.inner.contains_point(transform, point)
+        self.inner.contains_point(&(transform * self.offset), point)
+    }
+
+    fn closest_face(&self, transform: &Isometry3<f32>, point: &Point3<f32>) -> Option<u32> {
+        self.inner.closest_face(&(transform * self.offset), point)
     }
 
     fn supported(&self) -> bool {
@@ -100,6 +100,25 @@ impl<'w, 's> PointQuery<'w, 's> {
                 .then_some(entity)
         })
     }
+
+    /// Like [`point_query`](Self::point_query), but the point is tested in the
+    /// frame of each object, see [`GlobalTransform::precise_point_to_local`].
+    pub fn precise_point_query<'a>(
+        &'a self,
+        point: Point3<f64>,
+    ) -> impl Iterator<Item = Entity> + 'a {
+        self.bvh
+            .point_query(point.cast())
+            .filter_map(move |entity| {
+                let (transform, collider) = self.query.get(entity).ok()?;
+                collider
+                    .contains_point(
+                        &Isometry3::identity(),
+                        &transform.precise_point_to_local(&point),
+                    )
+                    .then_some(entity)
+            })
+    }
 }
 
 /* todo: need a trait for things that can maybe do this
@@ -152,6 +171,21 @@ impl<'w, 's> WorldAabb<'w, 's> {
         self.bvh.root_aabb()
     }
 
+    /// Merged AABB of some entities, e.g. the selection.
+    ///
+    /// This uses the AABBs stored in the BVH. Entities without a collider or
+    /// with an unbounded one are skipped.
+    pub fn of_entities<I>(&self, entities: I) -> Option<Aabb>
+    where
+        I: IntoIterator<Item = Entity>,
+    {
+        merge_aabbs(
+            entities
+                .into_iter()
+                .filter_map(|entity| self.bvh_leaves.get(entity).ok()?.aabb()),
+        )
+    }
+
     /// Computes the scene's AABB relative to an observer.
     ///
     /// # Arguments
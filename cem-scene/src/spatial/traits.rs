@@ -10,6 +10,7 @@ use parry3d::{
         Ray,
         RayIntersection,
     },
+    shape::FeatureId,
 };
 
 pub trait AnyCollider: ComputeAabb + RayCast + PointQuery + Debug + Send + Sync + 'static {}
@@ -74,6 +75,15 @@ pub trait PointQuery {
     }
 
     fn contains_point(&self, transform: &Isometry3<f32>, point: &Point3<f32>) -> bool;
+
+    /// Index of the face closest to `point`.
+    ///
+    /// For triangle meshes this is the index of the triangle. Returns `None`
+    /// if the shape doesn't have faces.
+    fn closest_face(&self, transform: &Isometry3<f32>, point: &Point3<f32>) -> Option<u32> {
+        let _ = (transform, point);
+        None
+    }
 }
 
 impl<T> PointQuery for T
@@ -83,4 +93,13 @@ where
     fn contains_point(&self, transform: &Isometry3<f32>, point: &Point3<f32>) -> bool {
         parry3d::query::PointQuery::contains_point(self, transform, point)
     }
+
+    fn closest_face(&self, transform: &Isometry3<f32>, point: &Point3<f32>) -> Option<u32> {
+        let (_, feature) =
+            parry3d::query::PointQuery::project_point_and_get_feature(self, transform, point);
+        match feature {
+            FeatureId::Face(face) => Some(face),
+            _ => None,
+        }
+    }
 }
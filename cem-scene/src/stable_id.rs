@@ -0,0 +1,209 @@
+//! Entity IDs that stay the same across save and load.
+//!
+//! An [`Entity`] is only valid in the world it was spawned in, and only until
+//! it's despawned. Anything that refers to an entity beyond that, e.g. a
+//! project file, the clipboard or the undo buffer, uses its [`StableId`]
+//! instead. [`StableIds`] maps them back to entities.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+};
+
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    lifecycle::HookContext,
+    name::Name,
+    query::QueryData,
+    reflect::ReflectComponent,
+    resource::Resource,
+    world::DeferredWorld,
+};
+use bevy_reflect::Reflect;
+#[cfg(feature = "serde")]
+use bevy_reflect::{
+    ReflectDeserialize,
+    ReflectSerialize,
+};
+use uuid::Uuid;
+
+use crate::{
+    SceneBuilder,
+    plugin::Plugin,
+};
+
+/// Namespace of the IDs made by [`StableId::from_index`].
+const FILE_NAMESPACE: Uuid = Uuid::from_u128(0x3b1e_9f4c_7a2d_4e08_b6c5_d1f0_8a93_2e47);
+
+/// Random ID of an entity that is kept when the entity is saved and loaded
+/// again.
+///
+/// The default is a new random ID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Component, Reflect)]
+#[component(on_insert = stable_id_inserted, on_replace = stable_id_replaced)]
+#[reflect(opaque)]
+#[reflect(Component, Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent),
+    reflect(Serialize, Deserialize)
+)]
+pub struct StableId(Uuid);
+
+impl StableId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+
+    /// ID of the `index`-th entity of a file.
+    ///
+    /// Unlike [`StableId::new`] this is the same every time the file is opened.
+    pub fn from_index(index: usize) -> Self {
+        Self(Uuid::new_v5(&FILE_NAMESPACE, &(index as u64).to_le_bytes()))
+    }
+
+    pub fn from_uuid(uuid: Uuid) -> Self {
+        Self(uuid)
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        self.0
+    }
+}
+
+impl Default for StableId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for StableId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+fn stable_id_inserted(mut world: DeferredWorld, context: HookContext) {
+    let id = *world.get::<StableId>(context.entity).unwrap();
+    if let Some(mut stable_ids) = world.get_resource_mut::<StableIds>()
+        && let Some(other) = stable_ids.entities.insert(id, context.entity)
+        && other != context.entity
+    {
+        tracing::warn!(%id, ?other, entity = ?context.entity, "duplicate stable ID");
+    }
+}
+
+fn stable_id_replaced(mut world: DeferredWorld, context: HookContext) {
+    let id = *world.get::<StableId>(context.entity).unwrap();
+    if let Some(mut stable_ids) = world.get_resource_mut::<StableIds>()
+        && stable_ids.entities.get(&id) == Some(&context.entity)
+    {
+        stable_ids.entities.remove(&id);
+    }
+}
+
+/// Looks up entities by their [`StableId`].
+#[derive(Debug, Default, Resource)]
+pub struct StableIds {
+    entities: HashMap<StableId, Entity>,
+}
+
+impl StableIds {
+    pub fn get(&self, id: &StableId) -> Option<Entity> {
+        self.entities.get(id).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+/// Query data for an entity's name, or its [`StableId`] if it has no name.
+///
+/// Like [`NameOrEntity`](bevy_ecs::name::NameOrEntity), but this stays the same
+/// across save and load, e.g. for labels of run results.
+#[derive(QueryData)]
+pub struct NameOrStableId {
+    pub name: Option<&'static Name>,
+    pub id: &'static StableId,
+}
+
+impl Display for NameOrStableIdItem<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name {
+            Some(name) => name.fmt(f),
+            None => self.id.fmt(f),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StableIdPlugin;
+
+impl Plugin for StableIdPlugin {
+    fn setup(&self, builder: &mut SceneBuilder) {
+        builder.insert_resource(StableIds::default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        name::Name,
+        world::World,
+    };
+
+    use crate::{
+        stable_id::{
+            NameOrStableId,
+            StableId,
+            StableIds,
+        },
+        transform::LocalTransform,
+    };
+
+    #[test]
+    fn entities_are_found_by_stable_id() {
+        let mut world = World::new();
+        world.insert_resource(StableIds::default());
+
+        let id = StableId::new();
+        let entity = world.spawn(id).id();
+        assert_eq!(world.resource::<StableIds>().get(&id), Some(entity));
+
+        // replacing the ID forgets the old one
+        let new_id = StableId::new();
+        world.entity_mut(entity).insert(new_id);
+        assert_eq!(world.resource::<StableIds>().get(&id), None);
+        assert_eq!(world.resource::<StableIds>().get(&new_id), Some(entity));
+
+        world.despawn(entity);
+        assert!(world.resource::<StableIds>().is_empty());
+    }
+
+    #[test]
+    fn objects_get_a_stable_id_when_spawned() {
+        let mut world = World::new();
+        world.insert_resource(StableIds::default());
+
+        let named = world
+            .spawn((LocalTransform::identity(), Name::new("dipole")))
+            .id();
+        let unnamed = world.spawn(LocalTransform::identity()).id();
+        let id = *world.get::<StableId>(unnamed).unwrap();
+        assert_eq!(world.resource::<StableIds>().get(&id), Some(unnamed));
+
+        let mut query = world.query::<NameOrStableId>();
+        assert_eq!(query.get(&world, named).unwrap().to_string(), "dipole");
+        assert_eq!(
+            query.get(&world, unnamed).unwrap().to_string(),
+            id.to_string()
+        );
+    }
+}
@@ -17,28 +17,38 @@ use crate::transform::LocalTransform;
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "probe", reflect(ComponentUi, @crate::probe::ComponentName::new("Global Transform")))]
 pub struct GlobalTransform {
+    /// The transform composed from the local transforms in double precision.
+    #[reflect(ignore)]
+    precise: Isometry3<f64>,
+
+    /// [`Self::precise`] rounded to single precision.
     #[reflect(ignore)]
     isometry: Isometry3<f32>,
 }
 
 impl GlobalTransform {
     pub(crate) fn from_local(local: LocalTransform) -> Self {
-        Self {
-            isometry: local.isometry,
-        }
+        Self::from_precise(local.precise_isometry())
     }
 
     pub(crate) fn with_local(self, local: &LocalTransform) -> Self {
+        Self::from_precise(self.precise * local.precise_isometry())
+    }
+
+    fn from_precise(precise: Isometry3<f64>) -> Self {
         Self {
-            isometry: self.isometry * local.isometry,
+            precise,
+            isometry: precise.cast(),
         }
     }
 
     #[cfg(test)]
     pub fn new_test(isometry: Isometry3<f32>) -> Self {
-        Self { isometry }
+        Self::from_precise(isometry.cast())
     }
 
+    /// The transform in single precision, e.g. for rendering and spatial
+    /// queries.
     pub fn isometry(&self) -> &Isometry3<f32> {
         &self.isometry
     }
@@ -46,6 +56,27 @@ impl GlobalTransform {
     pub fn position(&self) -> Point3<f32> {
         self.isometry.translation.vector.into()
     }
+
+    /// The transform in double precision.
+    ///
+    /// Use this when placing things in the solver's lattice, see the
+    /// [module-level documentation](crate::transform#precision).
+    pub fn precise_isometry(&self) -> &Isometry3<f64> {
+        &self.precise
+    }
+
+    pub fn precise_position(&self) -> Point3<f64> {
+        self.precise.translation.vector.into()
+    }
+
+    /// Transforms a point in world space into the object's frame.
+    ///
+    /// Only the result is rounded to single precision. Points near the object
+    /// are small in its frame, so they can be tested against its collider
+    /// without the rounding error of large world coordinates.
+    pub fn precise_point_to_local(&self, point: &Point3<f64>) -> Point3<f32> {
+        self.precise.inverse_transform_point(point).cast()
+    }
 }
 
 #[cfg(feature = "probe")]
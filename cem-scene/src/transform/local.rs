@@ -17,9 +17,15 @@ use nalgebra::{
 
 #[cfg(feature = "probe")]
 use crate::probe::ReflectComponentUi;
+use crate::stable_id::StableId;
 
+/// Transform of an object relative to its parent.
+///
+/// Every object in a scene has one, so this is also what gives objects their
+/// [`StableId`] when they're spawned.
 #[derive(Clone, Copy, Debug, Default, Component, Reflect)]
 #[reflect(Component)]
+#[require(StableId)]
 #[cfg_attr(
     feature = "serde",
     derive(serde::Serialize, serde::Deserialize),
@@ -30,7 +36,7 @@ pub struct LocalTransform {
     /// Rotation followed by translation that transforms points from the
     /// object's local frame to the global frame.
     #[reflect(ignore)]
-    pub isometry: Isometry3<f32>,
+    pub isometry: Isometry3<f64>,
 }
 
 impl LocalTransform {
@@ -44,37 +50,42 @@ impl LocalTransform {
         translation: impl Into<Translation3<f32>>,
         rotation: impl Into<UnitQuaternion<f32>>,
     ) -> Self {
-        Self {
-            isometry: Isometry3::from_parts(translation.into(), rotation.into()),
-        }
+        Self::from(Isometry3::<f32>::from_parts(
+            translation.into(),
+            rotation.into(),
+        ))
+    }
+
+    pub fn from_precise(isometry: Isometry3<f64>) -> Self {
+        Self { isometry }
     }
 
     pub fn translate_local(&mut self, translation: &Translation3<f32>) {
-        self.isometry.translation.vector +=
-            self.isometry.rotation.transform_vector(&translation.vector);
+        self.isometry.translation.vector += self
+            .isometry
+            .rotation
+            .transform_vector(&translation.vector.cast());
     }
 
     pub fn translate_global(&mut self, translation: &Translation3<f32>) {
-        self.isometry.translation.vector += &translation.vector;
+        self.isometry.translation.vector += translation.vector.cast();
     }
 
     pub fn rotate_local(&mut self, rotation: &UnitQuaternion<f32>) {
-        self.isometry.rotation *= rotation;
+        self.isometry.rotation *= rotation.cast();
     }
 
     pub fn rotate_global(&mut self, rotation: &UnitQuaternion<f32>) {
-        self.isometry.append_rotation_mut(rotation);
+        self.isometry.append_rotation_mut(&rotation.cast());
     }
 
     pub fn rotate_around(&mut self, anchor: &Point3<f32>, rotation: &UnitQuaternion<f32>) {
         self.isometry
-            .append_rotation_wrt_point_mut(rotation, anchor);
+            .append_rotation_wrt_point_mut(&rotation.cast(), &anchor.cast());
     }
 
     pub fn look_at(eye: &Point3<f32>, target: &Point3<f32>, up: &Vector3<f32>) -> Self {
-        Self {
-            isometry: Isometry3::face_towards(eye, target, up),
-        }
+        Self::from(Isometry3::face_towards(eye, target, up))
     }
 
     /// Pan and tilt object (e.g. a camera) with a given `up` vector.
@@ -82,23 +93,31 @@ impl LocalTransform {
     /// Pan is the horizontal turning. Tilt is the vertical turning.
     pub fn pan_tilt(&mut self, pan: f32, tilt: f32, up: &Vector3<f32>) {
         let local_up =
-            UnitVector3::new_normalize(self.isometry.rotation.inverse_transform_vector(up));
+            UnitVector3::new_normalize(self.isometry.rotation.inverse_transform_vector(&up.cast()));
         let local_right = Vector3::x_axis();
 
-        let rotation = UnitQuaternion::from_axis_angle(&local_up, -pan)
-            * UnitQuaternion::from_axis_angle(&local_right, tilt);
+        let rotation = UnitQuaternion::from_axis_angle(&local_up, -f64::from(pan))
+            * UnitQuaternion::from_axis_angle(&local_right, f64::from(tilt));
 
         self.isometry.rotation *= rotation;
     }
 
     pub fn position(&self) -> Point3<f32> {
+        self.precise_position().cast()
+    }
+
+    pub fn precise_isometry(&self) -> Isometry3<f64> {
+        self.isometry
+    }
+
+    pub fn precise_position(&self) -> Point3<f64> {
         self.isometry.translation.vector.into()
     }
 }
 
 impl From<Isometry3<f32>> for LocalTransform {
     fn from(value: Isometry3<f32>) -> Self {
-        Self { isometry: value }
+        Self::from_precise(value.cast())
     }
 }
 
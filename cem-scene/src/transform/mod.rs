@@ -1,10 +1,34 @@
+//! Transform hierarchy.
+//!
+//! # Precision
+//!
+//! [`LocalTransform`]s are stored in double precision, so objects far from
+//! the origin (or a camera flying around them) don't snap to the `f32` grid.
+//! [`GlobalTransform`]s are composed from them in double precision too, so
+//! that the rounding errors of a deep hierarchy don't add up. The result is
+//! available in both precisions:
+//!
+//! - [`GlobalTransform::isometry`] for picking and collisions.
+//! - [`GlobalTransform::precise_position`] for rendering, which subtracts a
+//!   render origin near the camera before rounding.
+//! - [`GlobalTransform::precise_isometry`] for mapping into the solver's
+//!   lattice. For electrically large scenes a cell is small compared to the
+//!   distance from the origin, so rounding to `f32` first could move things
+//!   into a neighbouring cell.
+//! - [`GlobalTransform::precise_point_to_local`] for testing lattice points
+//!   against colliders, which work in single precision. The point is moved into
+//!   the object's frame before it's rounded.
+
 mod global;
 mod local;
 mod systems;
 
-use bevy_ecs::schedule::{
-    IntoScheduleConfigs,
-    SystemSet,
+use bevy_ecs::{
+    schedule::{
+        IntoScheduleConfigs,
+        SystemSet,
+    },
+    world::World,
 };
 
 pub use crate::transform::{
@@ -66,3 +90,20 @@ impl Plugin for TransformHierarchyPlugin {
             );
     }
 }
+
+/// Propagates [`LocalTransform`]s to [`GlobalTransform`]s right away.
+///
+/// This normally happens in [`PostUpdate`]. Call this before reading the
+/// global transforms of entities that might have been moved or reparented
+/// since the last update.
+pub fn propagate_transforms(world: &mut World) {
+    world
+        .run_system_cached(mark_dirty_trees)
+        .expect("mark_dirty_trees failed");
+    world
+        .run_system_cached(propagate_parent_transforms)
+        .expect("propagate_parent_transforms failed");
+    world
+        .run_system_cached(sync_simple_transforms)
+        .expect("sync_simple_transforms failed");
+}
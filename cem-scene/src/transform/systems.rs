@@ -466,7 +466,10 @@ mod test {
         ComputeTaskPool,
         TaskPool,
     };
-    use nalgebra::Point3;
+    use nalgebra::{
+        Isometry3,
+        Point3,
+    };
 
     use crate::transform::{
         LocalTransform,
@@ -514,7 +517,7 @@ mod test {
 
         assert_eq!(
             world.get::<GlobalTransform>(parent).unwrap(),
-            &offset_global_transform(4.4 + 3.3),
+            &offset_global_transform(3.3).with_local(&offset_transform(4.4)),
             "The transform systems didn't run, ie: `GlobalTransform` wasn't updated",
         );
 
@@ -704,4 +707,53 @@ mod test {
             vec![children[1]]
         );
     }
+
+    #[test]
+    fn propagates_in_double_precision() {
+        ComputeTaskPool::get_or_init(TaskPool::default);
+        let mut world = World::default();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(
+            (
+                mark_dirty_trees,
+                sync_simple_transforms,
+                propagate_parent_transforms,
+            )
+                .chain(),
+        );
+
+        // far away from the origin a small offset is lost when adding in single
+        // precision
+        let parent = world.spawn(local_transform_from_xyz(1.0e4, 0.0, 0.0)).id();
+        let child = world
+            .spawn((local_transform_from_xyz(1.0e-4, 0.0, 0.0), ChildOf(parent)))
+            .id();
+        schedule.run(&mut world);
+
+        let global_transform = world.get::<GlobalTransform>(child).unwrap();
+        let x = global_transform.precise_position().x;
+        assert!((x - (1.0e4 + 1.0e-4f32 as f64)).abs() < 1e-9, "{x}");
+
+        // and so are points near the child in its frame
+        let local = global_transform.precise_point_to_local(&Point3::new(x + 5.0e-5, 0.0, 0.0));
+        assert!((local.x - 5.0e-5).abs() < 1e-9, "{local}");
+
+        // local transforms keep positions that single precision can't represent
+        let far = world
+            .spawn(LocalTransform::from_precise(Isometry3::translation(
+                1.0e8 + 0.5,
+                0.0,
+                0.0,
+            )))
+            .id();
+        schedule.run(&mut world);
+
+        let x = world
+            .get::<GlobalTransform>(far)
+            .unwrap()
+            .precise_position()
+            .x;
+        assert_eq!(x, 1.0e8 + 0.5);
+    }
 }
@@ -0,0 +1,175 @@
+use std::{
+    error::Error,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use cem_solver::{
+    DomainDescription,
+    Field,
+    FieldComponent,
+    FieldView,
+    SolverBackend,
+    SolverInstance,
+    UpdatePass,
+    UpdatePassForcing,
+    fdtd::{
+        FdtdSolverConfig,
+        Resolution,
+        SpatialOrder,
+        wgpu::{
+            FdtdWgpuBackend,
+            FieldStorage,
+            UpdateKernel,
+        },
+    },
+    material::{
+        Material,
+        PhysicalConstants,
+    },
+    source::SourceValues,
+};
+use cem_util::wgpu::buffer::StagingPool;
+use clap::Parser;
+use nalgebra::{
+    Point3,
+    Vector3,
+};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default().with_env());
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))?;
+    println!("adapter: {}", adapter.get_info().name);
+    let (device, queue) =
+        pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default()))?;
+    let staging_pool = StagingPool::new(
+        wgpu::BufferSize::new(0x10000).unwrap(),
+        "fdtd benchmark staging",
+    );
+
+    let config = FdtdSolverConfig {
+        resolution: Resolution {
+            spatial: Vector3::repeat(1.0),
+            temporal: 0.25,
+        },
+        physical_constants: PhysicalConstants::REDUCED,
+        size: Vector3::new(
+            args.size as f64,
+            args.size as f64,
+            if args.flat { 0.0 } else { args.size as f64 },
+        ),
+        spatial_order: if args.fourth_order {
+            SpatialOrder::Fourth
+        }
+        else {
+            SpatialOrder::Second
+        },
+    };
+    let num_cells = config.num_cells();
+    let center = Point3::from(config.size() / 2);
+    println!(
+        "lattice: {:?}, {num_cells} cells, {:?}",
+        config.size(),
+        config.spatial_order
+    );
+
+    let mut fields = vec![];
+
+    let variants = [
+        (FieldStorage::Buffer, UpdateKernel::Direct),
+        (FieldStorage::Buffer, UpdateKernel::Tiled),
+        (FieldStorage::Texture, UpdateKernel::Direct),
+    ];
+
+    for (field_storage, update_kernel) in variants {
+        let backend = FdtdWgpuBackend::new(device.clone(), queue.clone(), staging_pool.clone())
+            .with_field_storage(field_storage)
+            .with_update_kernel(update_kernel);
+        let instance = backend.create_instance(&config, Vacuum)?;
+        let mut state = instance.create_state();
+
+        // excite the lattice, so that the fields of all variants can be compared.
+        // this also warms up the pipelines.
+        for _ in 0..args.warmup {
+            let mut update_pass = instance.begin_update(&mut state);
+            update_pass.set_forcing(
+                &center,
+                &SourceValues {
+                    j: Vector3::z(),
+                    m: Vector3::zeros(),
+                },
+            );
+            update_pass.finish();
+        }
+
+        let start = Instant::now();
+        for _ in 0..args.steps {
+            instance.begin_update(&mut state).finish();
+        }
+        let elapsed = start.elapsed();
+
+        println!(
+            "{field_storage:?}, {update_kernel:?}: {:?} per step, {:.3e} cells/s",
+            elapsed / args.steps.max(1),
+            (num_cells * args.steps as usize) as f64
+                / elapsed.max(Duration::from_nanos(1)).as_secs_f64()
+        );
+
+        fields.push(
+            instance
+                .field(&state, .., FieldComponent::E)
+                .iter()
+                .map(|(_point, value)| value)
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    for ((field_storage, update_kernel), field) in variants.iter().zip(&fields).skip(1) {
+        let max_difference = fields[0]
+            .iter()
+            .zip(field)
+            .map(|(reference, value)| (reference - value).norm())
+            .fold(0.0, f64::max);
+        println!("{field_storage:?}, {update_kernel:?}: max difference of E: {max_difference:.3e}");
+    }
+
+    Ok(())
+}
+
+/// Benchmark the FDTD update kernels and field storages of the GPU backend
+/// against each other
+#[derive(Debug, Parser)]
+struct Args {
+    /// Number of cells along each axis
+    #[clap(long, default_value = "128")]
+    size: usize,
+
+    /// Use a 2D lattice
+    #[clap(long)]
+    flat: bool,
+
+    /// Use 4th order spatial derivatives
+    #[clap(long)]
+    fourth_order: bool,
+
+    /// Number of steps to measure
+    #[clap(long, default_value = "200")]
+    steps: u32,
+
+    /// Number of steps before measuring
+    #[clap(long, default_value = "20")]
+    warmup: u32,
+}
+
+struct Vacuum;
+
+impl DomainDescription<Point3<usize>> for Vacuum {
+    fn material(&mut self, _point: &Point3<usize>) -> Material {
+        Material::VACUUM
+    }
+}
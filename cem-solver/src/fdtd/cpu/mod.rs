@@ -1,13 +1,11 @@
 mod lattice;
 pub mod project;
+mod sparse;
 mod util;
 
-use std::{
-    convert::Infallible,
-    ops::{
-        Range,
-        RangeBounds,
-    },
+use std::ops::{
+    Range,
+    RangeBounds,
 };
 
 use nalgebra::{
@@ -15,7 +13,13 @@ use nalgebra::{
     Vector3,
 };
 
+pub use self::sparse::{
+    MIN_CHUNK_SIZE,
+    SparseConfig,
+};
 use crate::{
+    CopyState,
+    CreateInstanceError,
     DomainDescription,
     Field,
     FieldComponent,
@@ -29,6 +33,7 @@ use crate::{
     fdtd::{
         FdtdSolverConfig,
         Resolution,
+        SpatialOrder,
         boundary_condition::{
             AnyBoundaryCondition,
             default_boundary_conditions,
@@ -39,6 +44,10 @@ use crate::{
                 LatticeIter,
                 LatticeIterMut,
             },
+            sparse::{
+                SparseChunks,
+                SparseState,
+            },
             util::jacobian,
         },
         pml::PmlCoefficients,
@@ -50,6 +59,7 @@ use crate::{
             normalize_point_bounds,
         },
     },
+    material::Material,
     source::SourceValues,
 };
 
@@ -153,7 +163,10 @@ impl FdtdCpuBackend<MultiThreaded> {
             MultiThreaded::from_default_thread_pool()
         };
 
-        Ok(Self { threading })
+        Ok(Self {
+            threading,
+            sparse: None,
+        })
     }
 
     pub fn num_threads(&self) -> usize {
@@ -170,6 +183,9 @@ impl FdtdCpuBackend<MultiThreaded> {
 pub struct FdtdCpuBackend<Threading = SingleThreaded> {
     /// Whether to use single-threading or multi-threading
     pub threading: Threading,
+
+    /// Skip quiet regions of the lattice, see [`SparseConfig`].
+    pub sparse: Option<SparseConfig>,
 }
 
 impl Default for FdtdCpuBackend<SingleThreaded> {
@@ -180,7 +196,15 @@ impl Default for FdtdCpuBackend<SingleThreaded> {
 
 impl<Threading> FdtdCpuBackend<Threading> {
     pub fn new(threading: Threading) -> Self {
-        Self { threading }
+        Self {
+            threading,
+            sparse: None,
+        }
+    }
+
+    pub fn with_sparse(mut self, sparse: SparseConfig) -> Self {
+        self.sparse = Some(sparse);
+        self
     }
 }
 
@@ -188,6 +212,7 @@ impl FdtdCpuBackend<SingleThreaded> {
     pub fn single_threaded() -> Self {
         Self {
             threading: SingleThreaded,
+            sparse: None,
         }
     }
 }
@@ -197,7 +222,7 @@ where
     Threading: LatticeForEach + Clone,
 {
     type Instance = FdtdCpuSolverInstance<Threading>;
-    type Error = Infallible;
+    type Error = CreateInstanceError;
 
     fn create_instance<D>(
         &self,
@@ -207,10 +232,13 @@ where
     where
         D: DomainDescription<Point3<usize>>,
     {
+        config.validate()?;
+
         Ok(FdtdCpuSolverInstance::new(
             config,
             domain_description,
             self.threading.clone(),
+            self.sparse.as_ref(),
         ))
     }
 
@@ -225,9 +253,11 @@ where
 pub struct FdtdCpuSolverInstance<Threading = SingleThreaded> {
     strider: Strider,
     resolution: Resolution,
+    spatial_order: SpatialOrder,
     update_coefficients: Lattice<UpdateCoefficients>,
     boundary_conditions: [AnyBoundaryCondition; 3],
     pml: Option<PmlInstance>,
+    sparse: Option<SparseChunks>,
     threading: Threading,
 }
 
@@ -236,15 +266,23 @@ impl<Threading> FdtdCpuSolverInstance<Threading> {
         config: &FdtdSolverConfig,
         mut domain_description: impl DomainDescription<Point3<usize>>,
         threading: Threading,
+        sparse_config: Option<&SparseConfig>,
     ) -> Self {
         let strider = config.strider();
 
         let mut pml = None;
+        let mut sparse =
+            sparse_config.map(|sparse_config| SparseChunks::new(sparse_config, &strider));
+        let vacuum = UpdateCoefficients::new(
+            &config.resolution,
+            &config.physical_constants,
+            &Material::VACUUM,
+        );
 
         let update_coefficients = Lattice::from_fn(&strider, |_index, point| {
             point
                 .map(|point| {
-                    if let Some(pml_coefficients) = domain_description.pml(&point) {
+                    let is_pml = if let Some(pml_coefficients) = domain_description.pml(&point) {
                         let pml_instance = pml.get_or_insert_with(|| PmlInstance::new(&strider));
                         let pml_index = pml_instance.coefficients.len();
                         pml_instance.coefficients.push(pml_coefficients);
@@ -252,13 +290,25 @@ impl<Threading> FdtdCpuSolverInstance<Threading> {
                             .indirection
                             .get_point_mut(&strider, &point)
                             .unwrap() = pml_index;
+                        true
                     }
+                    else {
+                        false
+                    };
 
-                    UpdateCoefficients::new(
+                    let update_coefficients = UpdateCoefficients::new(
                         &config.resolution,
                         &config.physical_constants,
                         &domain_description.material(&point),
-                    )
+                    );
+
+                    if let Some(sparse) = &mut sparse
+                        && (is_pml || update_coefficients != vacuum)
+                    {
+                        sparse.set_always_active(&point);
+                    }
+
+                    update_coefficients
                 })
                 .unwrap_or_default()
         });
@@ -268,9 +318,11 @@ impl<Threading> FdtdCpuSolverInstance<Threading> {
         Self {
             strider,
             resolution: config.resolution,
+            spatial_order: config.spatial_order,
             update_coefficients,
             boundary_conditions,
             pml,
+            sparse,
             threading,
         }
     }
@@ -287,7 +339,7 @@ where
         Self: 'a;
 
     fn create_state(&self) -> Self::State {
-        FdtdCpuSolverState::new(&self.strider, self.pml.as_ref())
+        FdtdCpuSolverState::new(&self.strider, self.pml.as_ref(), self.sparse.as_ref())
     }
 
     fn begin_update<'a>(&'a self, state: &'a mut Self::State) -> FdtdCpuUpdatePass<'a, Threading> {
@@ -302,12 +354,30 @@ pub struct FdtdCpuSolverState {
     source_field: Lattice<usize>,
     source_buffer: Vec<(usize, SourceValues)>,
     pml: PmlState,
+    sparse: Option<SparseState>,
     tick: usize,
     time: f64,
 }
 
+impl<Threading> CopyState for FdtdCpuSolverInstance<Threading>
+where
+    Threading: LatticeForEach,
+{
+    fn copy_state(&self, source: &FdtdCpuSolverState, target: &mut FdtdCpuSolverState) {
+        target.h_field.clone_from(&source.h_field);
+        target.e_field.clone_from(&source.e_field);
+        target.sparse.clone_from(&source.sparse);
+        target.tick = source.tick;
+        target.time = source.time;
+    }
+}
+
 impl FdtdCpuSolverState {
-    fn new(strider: &Strider, pml_instance: Option<&PmlInstance>) -> Self {
+    fn new(
+        strider: &Strider,
+        pml_instance: Option<&PmlInstance>,
+        sparse_chunks: Option<&SparseChunks>,
+    ) -> Self {
         let pml = pml_instance.map_or_else(Default::default, PmlState::new);
 
         Self {
@@ -316,6 +386,7 @@ impl FdtdCpuSolverState {
             source_field: Lattice::from_default(strider),
             source_buffer: vec![],
             pml,
+            sparse: sparse_chunks.map(SparseState::new),
             tick: 0,
             time: 0.0,
         }
@@ -424,6 +495,34 @@ where
         // todo: use pml
         let _ = (&self.state.pml.psi_e, &self.state.pml.psi_h);
 
+        // decide which chunks to update
+        if let (Some(sparse_chunks), Some(sparse_state)) =
+            (&self.instance.sparse, &mut self.state.sparse)
+        {
+            if sparse_state.rescan {
+                sparse_state.rescan(
+                    sparse_chunks,
+                    &self.instance.strider,
+                    &self.state.e_field[previous],
+                    &self.state.h_field[previous],
+                );
+            }
+
+            sparse_state.begin_step(
+                sparse_chunks,
+                self.state
+                    .source_buffer
+                    .iter()
+                    .skip(1)
+                    .map(|(index, _)| self.instance.strider.point_unchecked(*index)),
+            );
+        }
+        let sparse = self
+            .instance
+            .sparse
+            .as_ref()
+            .zip(self.state.sparse.as_ref());
+
         //let mut energy = 0.0;
 
         // update magnetic field
@@ -432,6 +531,18 @@ where
             &self.instance.strider,
             h_field_next,
             |index, point, h_field_next| {
+                let chunk_index = sparse.map(|(sparse_chunks, sparse_state)| {
+                    (sparse_chunks.chunk_index(&point), sparse_state)
+                });
+                if let Some((chunk_index, sparse_state)) = chunk_index
+                    && !sparse_state.is_updated(chunk_index)
+                {
+                    if sparse_state.needs_copy(chunk_index) {
+                        *h_field_next = h_field_previous[index];
+                    }
+                    return;
+                }
+
                 let e_jacobian = jacobian(
                     &point,
                     &Vector3::repeat(1),
@@ -439,6 +550,7 @@ where
                     &self.instance.strider,
                     &self.state.e_field[previous],
                     &self.instance.resolution.spatial,
+                    self.instance.spatial_order,
                     &self.instance.boundary_conditions,
                 );
                 let e_curl = e_jacobian.curl();
@@ -460,6 +572,10 @@ where
                 *h_field_next = update_coefficients.d_a * h_field_previous[index]
                     + update_coefficients.d_b * (-e_curl - m_source + psi);
 
+                if let Some((chunk_index, sparse_state)) = chunk_index {
+                    sparse_state.record_h(chunk_index, h_field_next.norm_squared());
+                }
+
                 // note: this is just for debugging
                 //energy += cell[current].h.norm_squared()
                 //    / (cell.material.relative_permeability
@@ -474,6 +590,18 @@ where
             &self.instance.strider,
             e_field_next,
             |index, point, e_field_next| {
+                let chunk_index = sparse.map(|(sparse_chunks, sparse_state)| {
+                    (sparse_chunks.chunk_index(&point), sparse_state)
+                });
+                if let Some((chunk_index, sparse_state)) = chunk_index
+                    && !sparse_state.is_updated(chunk_index)
+                {
+                    if sparse_state.needs_copy(chunk_index) {
+                        *e_field_next = e_field_previous[index];
+                    }
+                    return;
+                }
+
                 let h_jacobian = jacobian(
                     &point,
                     &Vector3::zeros(),
@@ -483,6 +611,7 @@ where
                     // H field with the new values in `current`.
                     &self.state.h_field[next],
                     &self.instance.resolution.spatial,
+                    self.instance.spatial_order,
                     &self.instance.boundary_conditions,
                 );
                 let h_curl = h_jacobian.curl();
@@ -502,6 +631,10 @@ where
                 *e_field_next = update_coefficients.c_a * e_field_previous[index]
                     + update_coefficients.c_b * (h_curl - j_source + psi);
 
+                if let Some((chunk_index, sparse_state)) = chunk_index {
+                    sparse_state.record_e(chunk_index, e_field_next.norm_squared());
+                }
+
                 // note: this is just for debugging
                 //energy += cell[current].e.norm_squared()
                 //    * cell.material.relative_permittivity
@@ -509,6 +642,10 @@ where
             },
         );
 
+        if let Some(sparse_state) = &mut self.state.sparse {
+            sparse_state.end_step();
+        }
+
         self.state.tick += 1;
         self.state.time += self.instance.resolution.temporal;
         //self.total_energy = 0.5 * energy * self.resolution.spatial.product();
@@ -615,6 +752,9 @@ impl FieldMut<Point3<usize>> for FdtdCpuSolverInstance {
         R: RangeBounds<Point3<usize>>,
     {
         let swap_buffer_index = SwapBufferIndex::from_tick(state.tick);
+        if let Some(sparse_state) = &mut state.sparse {
+            sparse_state.rescan = true;
+        }
         let lattice = &mut state.field_mut(field_component)[swap_buffer_index];
 
         CpuFieldRegionIterMut {
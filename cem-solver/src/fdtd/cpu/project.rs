@@ -4,6 +4,7 @@ use std::ops::{
 };
 
 use nalgebra::{
+    Matrix4,
     Point3,
     Vector2,
     Vector4,
@@ -30,6 +31,7 @@ use crate::{
         ProjectionParameters,
         ProjectionPass,
         ProjectionPassAdd,
+        SetProjectionTransform,
     },
 };
 
@@ -56,6 +58,13 @@ where
         parameters: &ProjectionParameters,
     ) -> FdtdCpuImageProjection<Target> {
         let _ = state;
+
+        // todo: we could interpret a small subset of WGSL, or let users provide a
+        // closure instead.
+        if parameters.post_process_code.is_some() {
+            tracing::warn!("Post-processing code is not supported by the CPU backend");
+        }
+
         FdtdCpuImageProjection {
             target,
             parameters: parameters.clone(),
@@ -63,6 +72,15 @@ where
     }
 }
 
+impl<Target> SetProjectionTransform for FdtdCpuImageProjection<Target>
+where
+    Target: FdtdImageTarget,
+{
+    fn set_projection_transform(&mut self, projection: Matrix4<f32>) {
+        self.parameters.projection = projection;
+    }
+}
+
 impl<'a, Threading, Target> ProjectionPassAdd<'a, FdtdCpuImageProjection<Target>>
     for FdtdCpuProjectionPass<'a, Threading>
 where
@@ -0,0 +1,383 @@
+//! Skipping quiet regions of the lattice, see [`SparseConfig`].
+
+use std::sync::atomic::{
+    AtomicU64,
+    Ordering,
+};
+
+use nalgebra::{
+    Point3,
+    Vector3,
+};
+
+use crate::fdtd::{
+    cpu::lattice::Lattice,
+    strider::Strider,
+};
+
+/// Chunks are at least this many cells wide.
+///
+/// With the 4th order stencil the H update reads the E field up to 2 cells
+/// away, and the E update reads the H field up to 2 cells away on the other
+/// side, so a field spreads up to 3 cells per step. A chunk that is updated
+/// because its neighbor is active can therefore pick up fields, but never
+/// passes them on to a chunk that is skipped in the same step.
+pub const MIN_CHUNK_SIZE: usize = 4;
+
+/// Options for skipping regions of the lattice where nothing happens.
+///
+/// The lattice is divided into cubic chunks. A chunk is *active* if it
+/// contains a material other than vacuum, a PML, a source that is set in this
+/// step, or fields above the threshold. Only active chunks and their
+/// neighbors are updated. Since fields can't cross a neighbor in a single
+/// step, a wave reaching into a skipped region always activates the chunks
+/// in its way first.
+///
+/// Skipped chunks keep their fields. With a `relative_threshold` of 0 only
+/// chunks without any field are skipped, which doesn't change the result.
+/// Larger thresholds also freeze fields that are negligible compared to the
+/// rest of the lattice.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SparseConfig {
+    /// Edge length of the chunks in cells. Smaller values are raised to
+    /// [`MIN_CHUNK_SIZE`], which is wider than a field spreads in one step.
+    pub chunk_size: usize,
+
+    /// Field magnitudes below this fraction of the largest magnitude in the
+    /// lattice are negligible. E and H fields are compared separately.
+    pub relative_threshold: f64,
+}
+
+impl Default for SparseConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size: 16,
+            relative_threshold: 1e-6,
+        }
+    }
+}
+
+/// How an instance divides its lattice into chunks.
+#[derive(Clone, Debug)]
+pub(super) struct SparseChunks {
+    chunk_size: usize,
+    num_chunks: Vector3<usize>,
+    relative_threshold: f64,
+
+    /// Chunks that contain something other than vacuum.
+    always_active: Vec<bool>,
+}
+
+impl SparseChunks {
+    pub fn new(config: &SparseConfig, strider: &Strider) -> Self {
+        let chunk_size = config.chunk_size.max(MIN_CHUNK_SIZE);
+        let num_chunks = strider.size().map(|n| n.div_ceil(chunk_size));
+
+        Self {
+            chunk_size,
+            num_chunks,
+            relative_threshold: config.relative_threshold,
+            always_active: vec![false; num_chunks.product()],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.always_active.len()
+    }
+
+    pub fn chunk_index(&self, point: &Point3<usize>) -> usize {
+        let chunk = point.coords / self.chunk_size;
+        chunk.x + self.num_chunks.x * (chunk.y + self.num_chunks.y * chunk.z)
+    }
+
+    fn chunk_point(&self, mut chunk_index: usize) -> Vector3<usize> {
+        let x = chunk_index % self.num_chunks.x;
+        chunk_index /= self.num_chunks.x;
+        let y = chunk_index % self.num_chunks.y;
+        let z = chunk_index / self.num_chunks.y;
+        Vector3::new(x, y, z)
+    }
+
+    /// Marks the chunk containing `point` as always active.
+    pub fn set_always_active(&mut self, point: &Point3<usize>) {
+        let chunk_index = self.chunk_index(point);
+        self.always_active[chunk_index] = true;
+    }
+
+    /// Calls `f` with the chunk and all chunks sharing a face, edge or corner
+    /// with it.
+    fn for_each_neighbor(&self, chunk_index: usize, mut f: impl FnMut(usize)) {
+        let chunk = self.chunk_point(chunk_index);
+        let start = chunk.map(|x| x.saturating_sub(1));
+        let end = chunk.zip_map(&self.num_chunks, |x, n| (x + 2).min(n));
+
+        for z in start.z..end.z {
+            for y in start.y..end.y {
+                for x in start.x..end.x {
+                    f(x + self.num_chunks.x * (y + self.num_chunks.y * z));
+                }
+            }
+        }
+    }
+}
+
+/// Per-state bookkeeping of which chunks are updated.
+#[derive(Debug)]
+pub(super) struct SparseState {
+    /// Largest squared magnitude of the E and H field per chunk, as `f64`
+    /// bits. For non-negative floats the order of the bits is the order of the
+    /// values, so they can be maxed atomically from multiple threads.
+    e_peaks: Vec<AtomicU64>,
+    h_peaks: Vec<AtomicU64>,
+
+    /// Chunks updated in the current step.
+    updated: Vec<bool>,
+
+    /// Chunks that have the same fields in both swap buffers. Skipping them
+    /// doesn't need a copy.
+    settled: Vec<bool>,
+
+    /// Set when the fields were modified from outside, so that the peaks have
+    /// to be recomputed.
+    pub rescan: bool,
+}
+
+impl SparseState {
+    pub fn new(chunks: &SparseChunks) -> Self {
+        let num_chunks = chunks.len();
+        Self {
+            e_peaks: (0..num_chunks).map(|_| AtomicU64::new(0)).collect(),
+            h_peaks: (0..num_chunks).map(|_| AtomicU64::new(0)).collect(),
+            updated: vec![false; num_chunks],
+            // both swap buffers start out zeroed
+            settled: vec![true; num_chunks],
+            rescan: false,
+        }
+    }
+
+    /// Recomputes the peaks from the current fields.
+    pub fn rescan(
+        &mut self,
+        chunks: &SparseChunks,
+        strider: &Strider,
+        e_field: &Lattice<Vector3<f64>>,
+        h_field: &Lattice<Vector3<f64>>,
+    ) {
+        for peak in self.e_peaks.iter_mut().chain(&mut self.h_peaks) {
+            *peak.get_mut() = 0;
+        }
+
+        for (index, point, e) in e_field.iter(strider, ..) {
+            let chunk_index = chunks.chunk_index(&point);
+            self.record_e(chunk_index, e.norm_squared());
+            self.record_h(chunk_index, h_field[index].norm_squared());
+        }
+
+        // only the current swap buffer was modified
+        self.settled.fill(false);
+        self.rescan = false;
+    }
+
+    /// Decides which chunks are updated in this step.
+    ///
+    /// `sources` are the points at which sources are set in this step.
+    pub fn begin_step(
+        &mut self,
+        chunks: &SparseChunks,
+        sources: impl IntoIterator<Item = Point3<usize>>,
+    ) {
+        let load = |peak: &AtomicU64| f64::from_bits(peak.load(Ordering::Relaxed));
+        let max_peak = |peaks: &[AtomicU64]| peaks.iter().map(load).fold(0.0, f64::max);
+
+        // the threshold is for magnitudes, but the peaks are squared
+        let relative_threshold = chunks.relative_threshold.powi(2);
+        let e_threshold = relative_threshold * max_peak(&self.e_peaks);
+        let h_threshold = relative_threshold * max_peak(&self.h_peaks);
+
+        let mut active = (0..chunks.len())
+            .map(|chunk_index| {
+                chunks.always_active[chunk_index]
+                    || load(&self.e_peaks[chunk_index]) > e_threshold
+                    || load(&self.h_peaks[chunk_index]) > h_threshold
+            })
+            .collect::<Vec<_>>();
+        for point in sources {
+            active[chunks.chunk_index(&point)] = true;
+        }
+
+        self.updated.fill(false);
+        for (chunk_index, active) in active.into_iter().enumerate() {
+            if active {
+                chunks.for_each_neighbor(chunk_index, |neighbor| {
+                    self.updated[neighbor] = true;
+                });
+            }
+        }
+
+        // peaks of updated chunks are recorded again during the update
+        for (chunk_index, updated) in self.updated.iter().enumerate() {
+            if *updated {
+                *self.e_peaks[chunk_index].get_mut() = 0;
+                *self.h_peaks[chunk_index].get_mut() = 0;
+            }
+        }
+    }
+
+    pub fn end_step(&mut self) {
+        // skipped chunks were copied into the next swap buffer if necessary
+        for (settled, updated) in self.settled.iter_mut().zip(&self.updated) {
+            *settled = !*updated;
+        }
+    }
+
+    pub fn is_updated(&self, chunk_index: usize) -> bool {
+        self.updated[chunk_index]
+    }
+
+    /// Whether a skipped chunk has to be copied into the next swap buffer.
+    pub fn needs_copy(&self, chunk_index: usize) -> bool {
+        !self.settled[chunk_index]
+    }
+
+    pub fn record_e(&self, chunk_index: usize, norm_squared: f64) {
+        record_peak(&self.e_peaks[chunk_index], norm_squared);
+    }
+
+    pub fn record_h(&self, chunk_index: usize, norm_squared: f64) {
+        record_peak(&self.h_peaks[chunk_index], norm_squared);
+    }
+
+    #[cfg(test)]
+    pub fn num_updated(&self) -> usize {
+        self.updated.iter().filter(|updated| **updated).count()
+    }
+}
+
+impl Clone for SparseState {
+    fn clone(&self) -> Self {
+        let clone_peaks = |peaks: &[AtomicU64]| {
+            peaks
+                .iter()
+                .map(|peak| AtomicU64::new(peak.load(Ordering::Relaxed)))
+                .collect()
+        };
+
+        Self {
+            e_peaks: clone_peaks(&self.e_peaks),
+            h_peaks: clone_peaks(&self.h_peaks),
+            updated: self.updated.clone(),
+            settled: self.settled.clone(),
+            rescan: self.rescan,
+        }
+    }
+}
+
+fn record_peak(peak: &AtomicU64, norm_squared: f64) {
+    let bits = norm_squared.to_bits();
+    // most cells don't raise the peak, so check before contending for the cache
+    // line
+    if bits > peak.load(Ordering::Relaxed) {
+        peak.fetch_max(bits, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{
+        Point3,
+        Vector3,
+    };
+
+    use crate::{
+        Field,
+        FieldComponent,
+        FieldView,
+        fdtd::{
+            SpatialOrder,
+            cpu::{
+                FdtdCpuBackend,
+                sparse::SparseConfig,
+            },
+            test_util::{
+                run_source,
+                vacuum_config,
+                vacuum_instance,
+            },
+        },
+    };
+
+    fn run(
+        backend: FdtdCpuBackend,
+        spatial_order: SpatialOrder,
+        num_steps: usize,
+    ) -> (Vec<Vector3<f64>>, Option<usize>) {
+        let config =
+            vacuum_config(Vector3::new(48.0, 48.0, 0.0)).with_spatial_order(spatial_order);
+        let (instance, mut state) = vacuum_instance(backend, &config);
+        run_source(
+            &instance,
+            &mut state,
+            Point3::new(4, 4, 0),
+            Vector3::z(),
+            5,
+            num_steps,
+        );
+
+        let num_updated = state.sparse.as_ref().map(|sparse| sparse.num_updated());
+        let field = instance
+            .field(&state, .., FieldComponent::E)
+            .iter()
+            .map(|(_point, value)| value)
+            .collect();
+        (field, num_updated)
+    }
+
+    #[test]
+    fn skipping_empty_chunks_is_exact() {
+        let sparse_config = SparseConfig {
+            chunk_size: 4,
+            relative_threshold: 0.0,
+        };
+
+        for spatial_order in [SpatialOrder::Second, SpatialOrder::Fourth] {
+            let (dense, _) = run(FdtdCpuBackend::single_threaded(), spatial_order, 10);
+            let (sparse, num_updated) = run(
+                FdtdCpuBackend::single_threaded().with_sparse(sparse_config),
+                spatial_order,
+                10,
+            );
+
+            assert_eq!(dense, sparse, "{spatial_order:?}");
+
+            // the wave hasn't reached the far corner yet
+            let num_updated = num_updated.unwrap();
+            assert!(num_updated < 12 * 12, "{spatial_order:?}: {num_updated}");
+        }
+    }
+
+    #[test]
+    fn quiet_chunks_are_skipped() {
+        // with a large threshold only the chunks around the source are updated,
+        // so the wave front freezes once it leaves them
+        let sparse_config = SparseConfig {
+            chunk_size: 4,
+            relative_threshold: 0.5,
+        };
+
+        for spatial_order in [SpatialOrder::Second, SpatialOrder::Fourth] {
+            let (dense, _) = run(FdtdCpuBackend::single_threaded(), spatial_order, 40);
+            let (sparse, _) = run(
+                FdtdCpuBackend::single_threaded().with_sparse(sparse_config),
+                spatial_order,
+                40,
+            );
+
+            assert_ne!(dense, sparse, "{spatial_order:?}");
+            assert!(
+                sparse.iter().all(|value| value.iter().all(|x| x.is_finite())),
+                "{spatial_order:?}"
+            );
+        }
+    }
+}
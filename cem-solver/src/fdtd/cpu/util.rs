@@ -13,6 +13,7 @@ use num::{
 };
 
 use crate::fdtd::{
+    SpatialOrder,
     boundary_condition::{
         AnyBoundaryCondition,
         BoundaryCondition,
@@ -79,6 +80,7 @@ pub(super) fn jacobian(
     strider: &Strider,
     lattice: &Lattice<Vector3<f64>>,
     spatial_resolution: &Vector3<f64>,
+    spatial_order: SpatialOrder,
     boundary_conditions: &[AnyBoundaryCondition; 3],
 ) -> Jacobian {
     Jacobian {
@@ -91,6 +93,7 @@ pub(super) fn jacobian(
                 strider,
                 lattice,
                 spatial_resolution,
+                spatial_order,
                 boundary_conditions,
             ),
             partial_derivative(
@@ -101,6 +104,7 @@ pub(super) fn jacobian(
                 strider,
                 lattice,
                 spatial_resolution,
+                spatial_order,
                 boundary_conditions,
             ),
             partial_derivative(
@@ -111,6 +115,7 @@ pub(super) fn jacobian(
                 strider,
                 lattice,
                 spatial_resolution,
+                spatial_order,
                 boundary_conditions,
             ),
         ]),
@@ -164,6 +169,13 @@ impl Jacobian {
 /// To compute the spatial partial derivatives adjacent field values are needed.
 /// Since these are not available outside of the lattice, all derivatives along
 /// a boundary default to 0. This is effectively a Neumann boundary condition.
+///
+/// # Spatial order
+///
+/// With [`SpatialOrder::Fourth`] the two next-outer points `x - dx0 - 1` and
+/// `x + dx1 + 1` are used as well. If either of them lies outside of the
+/// lattice we fall back to the 2nd order difference, which is then handled by
+/// the boundary condition as usual.
 #[allow(clippy::too_many_arguments)]
 fn partial_derivative(
     axis: Axis,
@@ -173,6 +185,7 @@ fn partial_derivative(
     strider: &Strider,
     lattice: &Lattice<Vector3<f64>>,
     spatial_resolution: &Vector3<f64>,
+    spatial_order: SpatialOrder,
     boundary_conditions: &[AnyBoundaryCondition; 3],
 ) -> Vector3<f64> {
     let i = axis.vector_index();
@@ -190,5 +203,106 @@ fn partial_derivative(
     let f1 = lattice.get_point(strider, &(x + e * dx1)).copied();
 
     // fixme: the boundary conditions should be invariant under dx
-    boundary_conditions[i].apply_df(f0, f1) / dx
+    let df = boundary_conditions[i].apply_df(f0, f1);
+
+    match spatial_order {
+        SpatialOrder::Second => df / dx,
+        SpatialOrder::Fourth => {
+            let outer = (x.coords[i] > dx0)
+                .then(|| lattice.get_point(strider, &(x - e * (dx0 + 1))))
+                .flatten()
+                .zip(lattice.get_point(strider, &(x + e * (dx1 + 1))));
+
+            if let Some((f00, f11)) = outer {
+                (9.0 / 8.0 * df - 1.0 / 24.0 * (f11 - f00)) / dx
+            }
+            else {
+                df / dx
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{
+        Point3,
+        Vector3,
+    };
+
+    use crate::fdtd::{
+        SpatialOrder,
+        boundary_condition::default_boundary_conditions,
+        cpu::{
+            lattice::Lattice,
+            util::{
+                Axis,
+                partial_derivative,
+            },
+        },
+        strider::Strider,
+    };
+
+    const CELL_SIZE: f64 = 0.5;
+    const NUM_CELLS: usize = 12;
+
+    fn cubic(s: f64) -> f64 {
+        2.0 * s.powi(3) - 3.0 * s.powi(2) + s - 4.0
+    }
+
+    fn cubic_derivative(s: f64) -> f64 {
+        6.0 * s.powi(2) - 6.0 * s + 1.0
+    }
+
+    /// Derivative along x between the cells `x - 1` and `x` of a 1D lattice
+    /// holding the cubic, as it's done for the curl of the E-field.
+    fn derivative(x: usize, spatial_order: SpatialOrder) -> f64 {
+        let strider = Strider::new(&Vector3::new(NUM_CELLS, 1, 1));
+        // the E-field is staggered by half a cell
+        let lattice = Lattice::from_fn(&strider, |_index, point| {
+            let s = (point.unwrap().x as f64 + 0.5) * CELL_SIZE;
+            Vector3::x() * cubic(s)
+        });
+
+        partial_derivative(
+            Axis::X,
+            &Point3::new(x, 0, 0),
+            &Vector3::repeat(1),
+            &Vector3::zeros(),
+            &strider,
+            &lattice,
+            &Vector3::repeat(CELL_SIZE),
+            spatial_order,
+            &default_boundary_conditions(strider.size()),
+        )
+        .x
+    }
+
+    #[test]
+    fn fourth_order_derivative_is_exact_for_cubic() {
+        for x in 2..NUM_CELLS - 1 {
+            let expected = cubic_derivative(x as f64 * CELL_SIZE);
+            let actual = derivative(x, SpatialOrder::Fourth);
+            assert!(
+                (actual - expected).abs() < 1e-9,
+                "{x}: {actual} != {expected}"
+            );
+
+            // the 2-point difference isn't exact for a cubic
+            let second_order = derivative(x, SpatialOrder::Second);
+            assert!((second_order - expected).abs() > 1e-3, "{x}");
+        }
+    }
+
+    #[test]
+    fn fourth_order_falls_back_to_second_order_at_boundary() {
+        // the wide stencil would reach outside of the lattice on the left resp. right
+        for x in [1, NUM_CELLS - 1] {
+            assert_eq!(
+                derivative(x, SpatialOrder::Fourth),
+                derivative(x, SpatialOrder::Second),
+                "{x}"
+            );
+        }
+    }
 }
@@ -0,0 +1,226 @@
+//! Graded (non-uniform, tensor-product) mesh generation.
+//!
+//! The mesh lines along each axis are generated independently. Refinement
+//! regions are therefore given as axis-aligned boxes; any other shape (e.g. a
+//! sphere) is refined over its bounding box.
+
+#[cfg(feature = "probe")]
+use cem_probe::{
+    PropertiesUi,
+    TrackChanges,
+    label_and_value,
+};
+#[cfg(all(feature = "probe", feature = "bevy_ecs"))]
+use cem_scene::probe::{
+    ComponentName,
+    ReflectComponentUi,
+};
+use nalgebra::{
+    Point3,
+    Vector3,
+};
+
+/// Requests a smaller cell size for the region occupied by an entity.
+///
+/// The region is the entity's bounding box.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "bevy_ecs",
+    derive(bevy_ecs::component::Component, bevy_reflect::Reflect),
+    reflect(Component)
+)]
+#[cfg_attr(all(feature = "probe", feature = "bevy_ecs"), reflect(ComponentUi, @ComponentName::new("Mesh Refinement")))]
+#[cfg_attr(all(feature = "serde", feature = "bevy_ecs"), reflect(Serialize))]
+pub struct MeshRefinement {
+    /// Target cell size inside the region
+    pub cell_size: f64,
+}
+
+#[cfg(feature = "probe")]
+impl PropertiesUi for MeshRefinement {
+    type Config = ();
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
+        let _ = config;
+        let mut changes = TrackChanges::default();
+
+        let response = egui::Frame::new()
+            .show(ui, |ui| {
+                label_and_value(ui, "Cell Size", &mut changes, &mut self.cell_size);
+            })
+            .response;
+
+        changes.propagated(response)
+    }
+}
+
+/// A [`MeshRefinement`] resolved to an axis-aligned box.
+#[derive(Clone, Copy, Debug)]
+pub struct RefinementBox {
+    pub min: Point3<f64>,
+    pub max: Point3<f64>,
+    pub cell_size: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GradedMeshConfig {
+    /// Cell size outside of any refinement region
+    pub base_cell_size: Vector3<f64>,
+
+    /// Maximum ratio between the sizes of two adjacent cells. Values around
+    /// 1.2 - 1.5 are common.
+    pub max_grading_ratio: f64,
+}
+
+/// The mesh lines (cell boundaries) along each axis.
+#[derive(Clone, Debug)]
+pub struct GradedMesh {
+    pub lines: [Vec<f64>; 3],
+}
+
+impl GradedMesh {
+    pub fn generate(
+        min: &Point3<f64>,
+        max: &Point3<f64>,
+        config: &GradedMeshConfig,
+        regions: &[RefinementBox],
+    ) -> Self {
+        let lines = std::array::from_fn(|axis| {
+            let intervals = regions
+                .iter()
+                .map(|region| (region.min[axis], region.max[axis], region.cell_size))
+                .collect::<Vec<_>>();
+            generate_lines(
+                min[axis],
+                max[axis],
+                config.base_cell_size[axis],
+                config.max_grading_ratio,
+                &intervals,
+            )
+        });
+
+        Self { lines }
+    }
+
+    /// Number of cells along each axis
+    pub fn size(&self) -> Vector3<usize> {
+        Vector3::from_fn(|axis, _| self.lines[axis].len().saturating_sub(1).max(1))
+    }
+
+    pub fn num_cells(&self) -> usize {
+        self.size().product()
+    }
+
+    /// Smallest cell size along each axis. This determines the Courant limit.
+    pub fn min_cell_size(&self) -> Vector3<f64> {
+        Vector3::from_fn(|axis, _| {
+            self.lines[axis]
+                .windows(2)
+                .map(|pair| pair[1] - pair[0])
+                .reduce(f64::min)
+                .unwrap_or_default()
+        })
+    }
+}
+
+/// Generates mesh lines from `start` to `end`.
+///
+/// The desired cell size at `x` is the base size, or the target size of any
+/// interval containing `x`. Outside of an interval the size is allowed to grow
+/// linearly with the distance to it, which approximates a geometric grading
+/// with `max_grading_ratio`.
+fn generate_lines(
+    start: f64,
+    end: f64,
+    base_cell_size: f64,
+    max_grading_ratio: f64,
+    intervals: &[(f64, f64, f64)],
+) -> Vec<f64> {
+    let length = end - start;
+    if length <= 0.0 || base_cell_size <= 0.0 {
+        return vec![start, end];
+    }
+
+    let growth = (max_grading_ratio - 1.0).max(0.0);
+
+    let cell_size_at = |x: f64| {
+        intervals
+            .iter()
+            .filter(|(_, _, cell_size)| *cell_size > 0.0)
+            .map(|(min, max, cell_size)| {
+                let distance = (min - x).max(x - max).max(0.0);
+                cell_size + growth * distance
+            })
+            .fold(base_cell_size, f64::min)
+    };
+
+    let mut lines = vec![start];
+    let mut x = start;
+    while x < end {
+        // the cell must not be larger than the desired size at its far end, otherwise
+        // we'd step over the start of a refinement region. this fixed-point iteration
+        // converges, since the cell size grows slower than the distance.
+        let mut step = cell_size_at(x);
+        for _ in 0..16 {
+            step = cell_size_at(x).min(cell_size_at(x + step));
+        }
+        x += step;
+        lines.push(x);
+    }
+
+    // the last cell overshoots. either drop it if it's mostly outside, or keep it.
+    // then stretch all lines to fit exactly.
+    let n = lines.len();
+    if n > 2 && (lines[n - 1] - end) > 0.5 * (lines[n - 1] - lines[n - 2]) {
+        lines.pop();
+    }
+    let scale = length / (lines.last().unwrap() - start);
+    for line in &mut lines {
+        *line = start + (*line - start) * scale;
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fdtd::mesh::generate_lines;
+
+    #[test]
+    fn uniform_without_refinement() {
+        let lines = generate_lines(0.0, 1.0, 0.1, 1.3, &[]);
+        assert_eq!(lines.len(), 11);
+        for pair in lines.windows(2) {
+            assert!((pair[1] - pair[0] - 0.1).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn refinement_is_respected_and_graded() {
+        let ratio = 1.3;
+        let lines = generate_lines(0.0, 1.0, 0.1, ratio, &[(0.4, 0.5, 0.01)]);
+
+        assert_eq!(*lines.first().unwrap(), 0.0);
+        assert!((lines.last().unwrap() - 1.0).abs() < 1e-9);
+
+        let sizes = lines
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .collect::<Vec<_>>();
+
+        // cells inside the region are (approximately, because of the final stretch)
+        // the target size
+        for (pair, size) in lines.windows(2).zip(&sizes) {
+            if pair[0] >= 0.4 && pair[1] <= 0.5 {
+                assert!(*size < 0.0125, "cell {pair:?} too large: {size}");
+            }
+        }
+
+        // adjacent cells don't grow too fast
+        for pair in sizes.windows(2) {
+            let r = pair[1].max(pair[0]) / pair[1].min(pair[0]);
+            assert!(r <= ratio + 0.05, "grading ratio too large: {r}");
+        }
+    }
+}
@@ -1,15 +1,26 @@
 mod boundary_condition;
 pub mod cpu;
+pub mod mesh;
 pub mod pml;
 mod strider;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
 mod util;
+#[cfg(feature = "wgpu")]
 pub mod wgpu;
 
 use std::fmt::Debug;
 
 use nalgebra::Vector3;
 
+#[cfg(feature = "probe")]
+use cem_probe::{
+    PropertiesUi,
+    TrackChanges,
+};
+
 use crate::{
+    CreateInstanceError,
     fdtd::strider::Strider,
     material::PhysicalConstants,
 };
@@ -19,9 +30,57 @@ pub struct FdtdSolverConfig {
     pub resolution: Resolution,
     pub physical_constants: PhysicalConstants,
     pub size: Vector3<f64>,
+    pub spatial_order: SpatialOrder,
 }
 
 impl FdtdSolverConfig {
+    /// Config for a domain of `size` (in units of length) with SI constants
+    /// and 2nd order spatial derivatives.
+    pub fn new(size: Vector3<f64>, resolution: Resolution) -> Self {
+        Self {
+            resolution,
+            physical_constants: PhysicalConstants::default(),
+            size,
+            spatial_order: SpatialOrder::default(),
+        }
+    }
+
+    pub fn with_physical_constants(mut self, physical_constants: PhysicalConstants) -> Self {
+        self.physical_constants = physical_constants;
+        self
+    }
+
+    pub fn with_spatial_order(mut self, spatial_order: SpatialOrder) -> Self {
+        self.spatial_order = spatial_order;
+        self
+    }
+
+    /// Checks that a lattice can be created from this config.
+    ///
+    /// This doesn't check the Courant condition (see
+    /// [`max_stable_temporal_resolution`](Self::max_stable_temporal_resolution)),
+    /// as an unstable run might still be useful to look at.
+    pub fn validate(&self) -> Result<(), CreateInstanceError> {
+        let Resolution { spatial, temporal } = &self.resolution;
+        if !spatial.iter().all(|x| x.is_finite() && *x > 0.0) {
+            return Err(CreateInstanceError::InvalidResolution(format!(
+                "cell size must be positive: {spatial:?}"
+            )));
+        }
+        if !(temporal.is_finite() && *temporal > 0.0) {
+            return Err(CreateInstanceError::InvalidResolution(format!(
+                "time step must be positive: {temporal}"
+            )));
+        }
+        if !self.size.iter().all(|x| x.is_finite() && *x >= 0.0) {
+            return Err(CreateInstanceError::InvalidSize(format!(
+                "size must not be negative: {:?}",
+                self.size
+            )));
+        }
+        Ok(())
+    }
+
     pub fn size(&self) -> Vector3<usize> {
         self.size
             .component_div(&self.resolution.spatial)
@@ -35,6 +94,70 @@ impl FdtdSolverConfig {
     pub fn num_cells(&self) -> usize {
         self.size().product()
     }
+
+    /// Largest temporal resolution for which the update is still stable.
+    pub fn max_stable_temporal_resolution(&self) -> f64 {
+        estimate_temporal_from_spatial_resolution(
+            self.physical_constants.speed_of_light(),
+            &self.resolution.spatial,
+        ) / self.spatial_order.courant_reduction()
+    }
+}
+
+/// Order of accuracy of the spatial derivatives in the update equations.
+///
+/// The temporal update is always second order (leapfrog).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpatialOrder {
+    /// The standard Yee scheme, i.e. a 2-point central difference.
+    #[default]
+    Second,
+
+    /// The (2,4) scheme using a 4-point central difference with coefficients
+    /// `9/8` and `-1/24`.
+    ///
+    /// This has much lower numerical dispersion, so fewer cells per wavelength
+    /// are needed for the same phase error. Where the wider stencil would
+    /// reach outside of the lattice, the 2-point difference is used instead.
+    Fourth,
+}
+
+impl SpatialOrder {
+    pub fn order(&self) -> u32 {
+        match self {
+            Self::Second => 2,
+            Self::Fourth => 4,
+        }
+    }
+
+    /// Factor by which the Courant limit of the Yee scheme must be reduced.
+    ///
+    /// This is the sum of the absolute values of the stencil's coefficients.
+    pub fn courant_reduction(&self) -> f64 {
+        match self {
+            Self::Second => 1.0,
+            Self::Fourth => 9.0 / 8.0 + 1.0 / 24.0,
+        }
+    }
+}
+
+#[cfg(feature = "probe")]
+impl PropertiesUi for SpatialOrder {
+    type Config = ();
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, _config: &Self::Config) -> egui::Response {
+        let mut changes = TrackChanges::default();
+
+        let response = ui
+            .horizontal(|ui| {
+                changes.track(ui.selectable_value(self, Self::Second, "2nd order"));
+                changes.track(ui.selectable_value(self, Self::Fourth, "4th order"));
+            })
+            .response;
+
+        changes.propagated(response)
+    }
 }
 
 pub fn estimate_temporal_from_spatial_resolution(
@@ -91,3 +214,108 @@ impl Resolution {
         Self { spatial, temporal }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{
+        Point3,
+        Vector3,
+    };
+
+    use crate::{
+        CreateInstanceError,
+        Field,
+        FieldComponent,
+        FieldView,
+        fdtd::{
+            FdtdSolverConfig,
+            Resolution,
+            SpatialOrder,
+            cpu::FdtdCpuBackend,
+            test_util::{
+                run_source,
+                vacuum_config,
+                vacuum_instance,
+            },
+        },
+    };
+
+    #[test]
+    fn config_needs_positive_resolution() {
+        let resolution = Resolution {
+            spatial: Vector3::repeat(1.0),
+            temporal: 0.25,
+        };
+        let config = FdtdSolverConfig::new(Vector3::new(10.0, 10.0, 0.0), resolution);
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(config.size(), Vector3::new(10, 10, 1));
+
+        let config = FdtdSolverConfig::new(
+            Vector3::repeat(10.0),
+            Resolution {
+                spatial: Vector3::new(1.0, 0.0, 1.0),
+                ..resolution
+            },
+        );
+        assert!(matches!(
+            config.validate(),
+            Err(CreateInstanceError::InvalidResolution(_))
+        ));
+
+        let config = FdtdSolverConfig::new(Vector3::new(-1.0, 10.0, 10.0), resolution);
+        assert!(matches!(
+            config.validate(),
+            Err(CreateInstanceError::InvalidSize(_))
+        ));
+    }
+
+    fn courant_limit(spatial_order: SpatialOrder) -> f64 {
+        vacuum_config(Vector3::repeat(1.0))
+            .with_spatial_order(spatial_order)
+            .max_stable_temporal_resolution()
+    }
+
+    /// Largest E-field value after kicking a 3D lattice with a current pulse
+    /// and running it for `num_steps`.
+    fn max_field_after_pulse(spatial_order: SpatialOrder, temporal: f64, num_steps: usize) -> f64 {
+        let mut config = vacuum_config(Vector3::repeat(8.0)).with_spatial_order(spatial_order);
+        config.resolution.temporal = temporal;
+
+        let (instance, mut state) = vacuum_instance(FdtdCpuBackend::single_threaded(), &config);
+        run_source(
+            &instance,
+            &mut state,
+            Point3::new(4, 4, 4),
+            Vector3::repeat(1.0),
+            1,
+            num_steps,
+        );
+
+        instance
+            .field(&state, .., FieldComponent::E)
+            .iter()
+            .map(|(_point, value)| value.norm())
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn fourth_order_reduces_courant_limit() {
+        let second = courant_limit(SpatialOrder::Second);
+        let fourth = courant_limit(SpatialOrder::Fourth);
+        assert!((second - 1.0 / 3.0f64.sqrt()).abs() < 1e-12);
+        assert!((second / fourth - 7.0 / 6.0).abs() < 1e-12);
+
+        // just below the limit the (2,4) scheme is stable, but it diverges at the
+        // limit of the Yee scheme
+        assert!(max_field_after_pulse(SpatialOrder::Fourth, 0.99 * fourth, 100) < 1.0);
+        assert!(max_field_after_pulse(SpatialOrder::Second, 0.99 * second, 100) < 1.0);
+        assert!(max_field_after_pulse(SpatialOrder::Fourth, 0.99 * second, 100) > 1e3);
+    }
+
+    #[test]
+    fn fourth_order_is_stable_at_courant_limit() {
+        let fourth = courant_limit(SpatialOrder::Fourth);
+        let max_field = max_field_after_pulse(SpatialOrder::Fourth, fourth, 500);
+        assert!(max_field.is_finite() && max_field < 1.0, "{max_field}");
+    }
+}
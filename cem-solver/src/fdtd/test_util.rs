@@ -0,0 +1,85 @@
+//! Fixtures for tests of the FDTD solver and of crates using it.
+//!
+//! Other crates get this module with the `test-util` feature.
+
+use nalgebra::{
+    Point3,
+    Vector3,
+};
+
+use crate::{
+    SolverBackend,
+    SolverInstance,
+    UpdatePass,
+    UpdatePassForcing,
+    fdtd::{
+        FdtdSolverConfig,
+        Resolution,
+        cpu::{
+            FdtdCpuBackend,
+            FdtdCpuSolverInstance,
+            FdtdCpuSolverState,
+            LatticeForEach,
+        },
+    },
+    material::{
+        Material,
+        PhysicalConstants,
+    },
+    source::SourceValues,
+};
+
+/// Config for a lattice of `size` cells with a cell size of 1, a time step of
+/// 0.25 and [`PhysicalConstants::REDUCED`].
+pub fn vacuum_config(size: Vector3<f64>) -> FdtdSolverConfig {
+    FdtdSolverConfig::new(
+        size,
+        Resolution {
+            spatial: Vector3::repeat(1.0),
+            temporal: 0.25,
+        },
+    )
+    .with_physical_constants(PhysicalConstants::REDUCED)
+}
+
+/// Creates an instance filled with vacuum and its initial state.
+pub fn vacuum_instance<Threading>(
+    backend: FdtdCpuBackend<Threading>,
+    config: &FdtdSolverConfig,
+) -> (FdtdCpuSolverInstance<Threading>, FdtdCpuSolverState)
+where
+    Threading: LatticeForEach + Clone,
+{
+    let instance = backend
+        .create_instance(config, |_: &Point3<usize>| Material::VACUUM)
+        .unwrap();
+    let state = instance.create_state();
+    (instance, state)
+}
+
+/// Does `num_steps` steps, setting the current density `j` at `point` in the
+/// first `num_forced` of them.
+pub fn run_source<Threading>(
+    instance: &FdtdCpuSolverInstance<Threading>,
+    state: &mut FdtdCpuSolverState,
+    point: Point3<usize>,
+    j: Vector3<f64>,
+    num_forced: usize,
+    num_steps: usize,
+) where
+    Threading: LatticeForEach,
+{
+    for tick in 0..num_steps {
+        let mut update_pass = instance.begin_update(state);
+        if tick < num_forced {
+            update_pass.set_forcing(
+                &point,
+                &SourceValues {
+                    j,
+                    m: Vector3::zeros(),
+                },
+            );
+        }
+        update_pass.finish();
+    }
+}
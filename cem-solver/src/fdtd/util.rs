@@ -87,7 +87,7 @@ impl SwapBufferIndex {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct UpdateCoefficients {
     pub c_a: f64,
     pub c_b: f64,
@@ -101,7 +101,12 @@ impl UpdateCoefficients {
         physical_constants: &PhysicalConstants,
         material: &Material,
     ) -> Self {
-        let c_or_d = |perm, sigma| {
+        let c_or_d = |perm, sigma: f64| {
+            if sigma.is_infinite() {
+                // perfect conductor: the field is forced to zero
+                return (0.0, 0.0);
+            }
+
             let half_sigmal_delta_t_over_perm = 0.5 * sigma * resolution.temporal / perm;
 
             let a: f64 =
@@ -210,9 +215,24 @@ impl ExactSizeIterator for PointIter {}
 
 #[cfg(test)]
 mod tests {
-    use nalgebra::Point3;
+    use nalgebra::{
+        Point3,
+        Vector3,
+    };
 
-    use crate::fdtd::util::iter_points;
+    use crate::{
+        fdtd::{
+            Resolution,
+            util::{
+                UpdateCoefficients,
+                iter_points,
+            },
+        },
+        material::{
+            Material,
+            PhysicalConstants,
+        },
+    };
 
     #[test]
     fn it_iters_inclusive() {
@@ -254,4 +274,19 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn it_zeroes_e_field_in_pec() {
+        let coefficients = UpdateCoefficients::new(
+            &Resolution {
+                spatial: Vector3::repeat(1.0),
+                temporal: 0.25,
+            },
+            &PhysicalConstants::REDUCED,
+            &Material::PEC,
+        );
+        assert_eq!(coefficients.c_a, 0.0);
+        assert_eq!(coefficients.c_b, 0.0);
+        assert_eq!(coefficients.d_a, 1.0);
+    }
 }
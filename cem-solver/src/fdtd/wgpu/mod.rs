@@ -1,7 +1,57 @@
+//! FDTD solver on the GPU.
+//!
+//! The backend is created from a device, and is then used like the
+//! [CPU backend](crate::fdtd::cpu):
+//!
+//! ```no_run
+//! use cem_solver::{
+//!     SolverBackend,
+//!     SolverInstance,
+//!     UpdatePass,
+//!     fdtd::{
+//!         FdtdSolverConfig,
+//!         Resolution,
+//!         wgpu::FdtdWgpuBackend,
+//!     },
+//!     material::{
+//!         Material,
+//!         PhysicalConstants,
+//!     },
+//! };
+//! use cem_util::wgpu::buffer::StagingPool;
+//! use nalgebra::{
+//!     Point3,
+//!     Vector3,
+//! };
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let instance = wgpu::Instance::default();
+//! let adapter =
+//!     pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))?;
+//! let (device, queue) =
+//!     pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default()))?;
+//!
+//! let config = FdtdSolverConfig::new(
+//!     Vector3::repeat(64.0),
+//!     Resolution {
+//!         spatial: Vector3::repeat(1.0),
+//!         temporal: 0.25,
+//!     },
+//! )
+//! .with_physical_constants(PhysicalConstants::REDUCED);
+//!
+//! let backend = FdtdWgpuBackend::new(device, queue, StagingPool::default());
+//! let instance = backend.create_instance(&config, |_: &Point3<usize>| Material::VACUUM)?;
+//! let mut state = instance.create_state();
+//! instance.begin_update(&mut state).finish();
+//! # Ok(())
+//! # }
+//! ```
+
 pub mod project;
+mod texture;
 
 use std::{
-    convert::Infallible,
     ops::{
         Index,
         Range,
@@ -30,7 +80,16 @@ use nalgebra::{
 use wgpu::util::DeviceExt;
 
 pub use self::project::FdtdWgpuTextureProjection;
+use self::texture::{
+    FIELD_TEXTURE_CELL_SIZE,
+    FieldTextures,
+    TexturePipelines,
+    TextureStorageLayout,
+    texture_workgroup_size,
+};
 use crate::{
+    CopyState,
+    CreateInstanceError,
     DomainDescription,
     Field,
     FieldComponent,
@@ -43,6 +102,7 @@ use crate::{
     fdtd::{
         FdtdSolverConfig,
         Resolution,
+        SpatialOrder,
         strider::Strider,
         util::{
             SwapBuffer,
@@ -55,14 +115,59 @@ use crate::{
     source::SourceValues,
 };
 
+/// Which compute kernel updates the fields.
+///
+/// None of the kernels implement the PML yet. Like the CPU backend, they
+/// ignore it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UpdateKernel {
+    /// Every invocation reads the neighbors of its cell from global memory
+    /// (`update.wgsl`).
+    #[default]
+    Direct,
+
+    /// Every workgroup first copies a block of cells and their neighbors into
+    /// workgroup memory, and computes the derivatives from there
+    /// (`update_tiled.wgsl`).
+    ///
+    /// Falls back to [`Direct`](Self::Direct) if the device's limits don't
+    /// allow a suitable tile.
+    Tiled,
+}
+
+/// Where the fields are stored on the GPU.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FieldStorage {
+    /// Flat storage buffers.
+    #[default]
+    Buffer,
+
+    /// 3D storage textures (`update_texture.wgsl`).
+    ///
+    /// The texture cache covers neighbors along all axes, which suits the
+    /// stencil better than a flat buffer. The fields are copied into buffers
+    /// when they're read back or projected, so this needs more memory.
+    ///
+    /// The [`UpdateKernel`] is ignored with this storage. Falls back to
+    /// [`Buffer`](Self::Buffer) if the lattice is larger than the device's
+    /// maximum texture size.
+    Texture,
+}
+
 #[derive(Clone, Debug)]
 pub struct FdtdWgpuBackend {
     device: wgpu::Device,
     queue: wgpu::Queue,
     limits: ComputeLimits,
+    update_kernel: UpdateKernel,
+    field_storage: FieldStorage,
     shader_module: wgpu::ShaderModule,
+    tiled_shader_module: wgpu::ShaderModule,
     bind_group_layout: wgpu::BindGroupLayout,
     pipeline_layout: wgpu::PipelineLayout,
+    texture_storage: TextureStorageLayout,
     projection: ProjectionPipeline,
     staging_pool: StagingPool,
 }
@@ -72,6 +177,8 @@ impl FdtdWgpuBackend {
         let limits = ComputeLimits::from_limits(&device.limits());
 
         let shader_module = device.create_shader_module(wgpu::include_wgsl!("update.wgsl"));
+        let tiled_shader_module =
+            device.create_shader_module(wgpu::include_wgsl!("update_tiled.wgsl"));
 
         let bind_group_layout = BINDINGS.bind_group_layout(&device);
 
@@ -81,20 +188,65 @@ impl FdtdWgpuBackend {
             push_constant_ranges: &[],
         });
 
+        let texture_storage = TextureStorageLayout::new(&device, &bind_group_layout);
+
         let projection = ProjectionPipeline::new(&device);
 
         Self {
             device,
             queue,
             limits,
+            update_kernel: UpdateKernel::default(),
+            field_storage: FieldStorage::default(),
             shader_module,
+            tiled_shader_module,
             bind_group_layout,
             pipeline_layout,
+            texture_storage,
             projection,
             staging_pool,
         }
     }
 
+    /// Uses `update_kernel` for instances created from now on.
+    pub fn with_update_kernel(mut self, update_kernel: UpdateKernel) -> Self {
+        self.update_kernel = update_kernel;
+        self
+    }
+
+    pub fn update_kernel(&self) -> UpdateKernel {
+        self.update_kernel
+    }
+
+    /// Uses `field_storage` for instances created from now on.
+    pub fn with_field_storage(mut self, field_storage: FieldStorage) -> Self {
+        self.field_storage = field_storage;
+        self
+    }
+
+    pub fn field_storage(&self) -> FieldStorage {
+        self.field_storage
+    }
+
+    /// Whether the buffers of a simulation with this config fit into the
+    /// device's limits.
+    ///
+    /// The GPU's memory might still run out, but it doesn't tell us how much
+    /// it has.
+    pub fn fits_device_limits(&self, config: &FdtdSolverConfig) -> bool {
+        let limits = self.device.limits();
+        let max_buffer_size = limits
+            .max_buffer_size
+            .min(limits.max_storage_buffer_binding_size.into());
+
+        let largest_element = size_of::<UpdateCoefficientsData>()
+            .max(size_of::<Cell>())
+            .max(size_of::<SourceData>());
+
+        u64::try_from(config.size().product() * largest_element)
+            .is_ok_and(|buffer_size| buffer_size <= max_buffer_size)
+    }
+
     fn submit_and_poll(&self, command_buffers: impl IntoIterator<Item = wgpu::CommandBuffer>) {
         let submission_index = self.queue.submit(command_buffers);
 
@@ -109,7 +261,7 @@ impl FdtdWgpuBackend {
 
 impl SolverBackend<FdtdSolverConfig, Point3<usize>> for FdtdWgpuBackend {
     type Instance = FdtdWgpuSolverInstance;
-    type Error = Infallible;
+    type Error = CreateInstanceError;
 
     fn create_instance<D>(
         &self,
@@ -119,6 +271,11 @@ impl SolverBackend<FdtdSolverConfig, Point3<usize>> for FdtdWgpuBackend {
     where
         D: DomainDescription<Point3<usize>>,
     {
+        config.validate()?;
+        if !self.fits_device_limits(config) {
+            return Err(CreateInstanceError::ExceedsDeviceLimits);
+        }
+
         Ok(FdtdWgpuSolverInstance::new(
             self,
             config,
@@ -127,8 +284,11 @@ impl SolverBackend<FdtdSolverConfig, Point3<usize>> for FdtdWgpuBackend {
     }
 
     fn memory_required(&self, config: &FdtdSolverConfig) -> Option<usize> {
-        let per_cell =
+        let mut per_cell =
             size_of::<UpdateCoefficientsData>() + 2 * size_of::<Cell>() + size_of::<SourceData>();
+        if self.field_storage == FieldStorage::Texture {
+            per_cell += 2 * FIELD_TEXTURE_CELL_SIZE;
+        }
         Some(config.size().product() * per_cell)
     }
 }
@@ -144,6 +304,10 @@ pub struct FdtdWgpuSolverInstance {
     update_sources_pipeline: wgpu::ComputePipeline,
     update_e_pipeline: wgpu::ComputePipeline,
     update_h_pipeline: wgpu::ComputePipeline,
+
+    /// Set if the fields are stored in textures.
+    texture_pipelines: Option<TexturePipelines>,
+
     workgroup_size: Vector3<u32>,
     dispatches: Vec<Vector3<u32>>,
 }
@@ -190,37 +354,138 @@ impl FdtdWgpuSolverInstance {
 
         let workgroup_size = backend.limits.work_group_size_for(num_cells);
 
-        let dispatches = backend
+        let mut dispatches = backend
             .limits
             .divide_work_into_dispatches(num_cells, &workgroup_size)
             .collect();
 
-        tracing::debug!(?workgroup_size, ?dispatches);
-
-        let shader_constants = [
+        let direct_constants = [
             ("workgroup_size_x", workgroup_size.x.into()),
             ("workgroup_size_y", workgroup_size.y.into()),
             ("workgroup_size_z", workgroup_size.z.into()),
+            ("spatial_order", config.spatial_order.order().into()),
         ];
-        let create_pipeline = |label, entrypoint| {
+        let create_pipeline = |label, module, entrypoint, constants: &[(&str, f64)]| {
             backend
                 .device
                 .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
                     label: Some(label),
                     layout: Some(&backend.pipeline_layout),
-                    module: &backend.shader_module,
+                    module,
                     entry_point: Some(entrypoint),
                     compilation_options: wgpu::PipelineCompilationOptions {
-                        constants: &shader_constants,
+                        constants,
                         zero_initialize_workgroup_memory: true,
                     },
                     cache: None,
                 })
         };
 
-        let update_sources_pipeline = create_pipeline("fdtd/update/sources", "update_sources");
-        let update_e_pipeline = create_pipeline("fdtd/update/e", "update_e");
-        let update_h_pipeline = create_pipeline("fdtd/update/h", "update_h");
+        // the tiled kernel has no entry point for the sources, so they're always
+        // updated by the direct kernel
+        let update_sources_pipeline = create_pipeline(
+            "fdtd/update/sources",
+            &backend.shader_module,
+            "update_sources",
+            &direct_constants,
+        );
+
+        let texture_pipelines = match backend.field_storage {
+            FieldStorage::Buffer => None,
+            FieldStorage::Texture => {
+                if let Some(texture_workgroup_size) = texture_workgroup_size(
+                    strider.size(),
+                    &backend.limits,
+                    &backend.device.limits(),
+                ) {
+                    dispatches = vec![num_workgroups_for_lattice(
+                        strider.size(),
+                        &texture_workgroup_size,
+                    )];
+                    Some(TexturePipelines::new(
+                        backend,
+                        &texture_workgroup_size,
+                        &workgroup_size,
+                        config.spatial_order,
+                    ))
+                }
+                else {
+                    tracing::warn!("lattice doesn't fit into a texture, storing fields in buffers");
+                    None
+                }
+            }
+        };
+
+        let tile_layout = match backend.update_kernel {
+            _ if texture_pipelines.is_some() => None,
+            UpdateKernel::Direct => None,
+            UpdateKernel::Tiled => {
+                let tile_layout =
+                    TileLayout::new(strider.size(), config.spatial_order, &backend.limits);
+                if tile_layout.is_none() {
+                    tracing::warn!("no tile fits into the device limits, using direct kernel");
+                }
+                tile_layout
+            }
+        };
+
+        let (update_e_pipeline, update_h_pipeline) =
+            if let Some(texture_pipelines) = &texture_pipelines {
+                (
+                    texture_pipelines.update_e.clone(),
+                    texture_pipelines.update_h.clone(),
+                )
+            }
+            else if let Some(tile_layout) = &tile_layout {
+                let tiled_constants = [
+                    ("tile_x", tile_layout.tile.x.into()),
+                    ("tile_y", tile_layout.tile.y.into()),
+                    ("tile_z", tile_layout.tile.z.into()),
+                    ("halo_x", tile_layout.halo.x.into()),
+                    ("halo_y", tile_layout.halo.y.into()),
+                    ("halo_z", tile_layout.halo.z.into()),
+                    ("spatial_order", config.spatial_order.order().into()),
+                ];
+                dispatches = vec![tile_layout.num_workgroups(strider.size())];
+
+                (
+                    create_pipeline(
+                        "fdtd/update/e",
+                        &backend.tiled_shader_module,
+                        "update_e",
+                        &tiled_constants,
+                    ),
+                    create_pipeline(
+                        "fdtd/update/h",
+                        &backend.tiled_shader_module,
+                        "update_h",
+                        &tiled_constants,
+                    ),
+                )
+            }
+            else {
+                (
+                    create_pipeline(
+                        "fdtd/update/e",
+                        &backend.shader_module,
+                        "update_e",
+                        &direct_constants,
+                    ),
+                    create_pipeline(
+                        "fdtd/update/h",
+                        &backend.shader_module,
+                        "update_h",
+                        &direct_constants,
+                    ),
+                )
+            };
+
+        tracing::debug!(
+            ?workgroup_size,
+            ?tile_layout,
+            textures = texture_pipelines.is_some(),
+            ?dispatches
+        );
 
         Self {
             backend: backend.clone(),
@@ -232,6 +497,7 @@ impl FdtdWgpuSolverInstance {
             update_sources_pipeline,
             update_e_pipeline,
             update_h_pipeline,
+            texture_pipelines,
             workgroup_size,
             dispatches,
         }
@@ -257,6 +523,7 @@ impl SolverInstance for FdtdWgpuSolverInstance {
 #[derive(Debug)]
 pub struct FdtdWgpuSolverState {
     field_buffers: SwapBuffer<FieldBuffers>,
+    field_textures: Option<FieldTextures>,
     source_buffer: StagedTypedArrayBuffer<SourceData>,
     update_bind_groups: SwapBuffer<wgpu::BindGroup>,
     tick: usize,
@@ -273,7 +540,9 @@ impl FdtdWgpuSolverState {
                         instance.backend.device.clone(),
                         label,
                         instance.num_cells,
-                        wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                        wgpu::BufferUsages::STORAGE
+                            | wgpu::BufferUsages::COPY_SRC
+                            | wgpu::BufferUsages::COPY_DST,
                         |_index| default_value,
                     )
                 };
@@ -293,8 +562,14 @@ impl FdtdWgpuSolverState {
         let update_bind_groups =
             BINDINGS.bind_group(instance, &field_buffers, source_buffer.buffer.buffer().expect("source buffer should have a gpu buffer allocated because it is initialized with an non-zero initial capacity"));
 
+        let field_textures = instance
+            .texture_pipelines
+            .is_some()
+            .then(|| FieldTextures::new(instance));
+
         Self {
             field_buffers,
+            field_textures,
             source_buffer,
             update_bind_groups,
             tick: 0,
@@ -303,6 +578,40 @@ impl FdtdWgpuSolverState {
     }
 }
 
+impl CopyState for FdtdWgpuSolverInstance {
+    fn copy_state(&self, source: &FdtdWgpuSolverState, target: &mut FdtdWgpuSolverState) {
+        let mut command_encoder =
+            self.backend
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("fdtd/copy_state"),
+                });
+
+        for swap_buffer_index in [SwapBufferIndex::from_tick(0), SwapBufferIndex::from_tick(1)] {
+            let source = &source.field_buffers[swap_buffer_index];
+            let target = &target.field_buffers[swap_buffer_index];
+
+            for (source, target) in [(&source.e, &target.e), (&source.h, &target.h)] {
+                // both are allocated with `num_cells` elements
+                let source = source.buffer().expect("field buffer not allocated");
+                let target = target.buffer().expect("field buffer not allocated");
+                command_encoder.copy_buffer_to_buffer(source, 0, target, 0, source.size());
+            }
+        }
+
+        if let (Some(source), Some(target)) = (&source.field_textures, &target.field_textures) {
+            source.copy_to(target, &mut command_encoder);
+        }
+
+        // no need to wait. anything that reads the target is submitted to the same
+        // queue later.
+        self.backend.queue.submit([command_encoder.finish()]);
+
+        target.tick = source.tick;
+        target.time = source.time;
+    }
+}
+
 impl Time for FdtdWgpuSolverState {
     fn tick(&self) -> usize {
         self.tick
@@ -423,7 +732,7 @@ impl<'a> UpdatePass for FdtdWgpuUpdatePass<'a> {
                 );
             }
 
-            let mut dispatch_update = |pipeline| {
+            let dispatch_update = |compute_pass: &mut wgpu::ComputePass, pipeline| {
                 compute_pass.set_pipeline(pipeline);
 
                 for num_workgroups in &self.instance.dispatches {
@@ -435,8 +744,44 @@ impl<'a> UpdatePass for FdtdWgpuUpdatePass<'a> {
                 }
             };
 
-            dispatch_update(&self.instance.update_h_pipeline);
-            dispatch_update(&self.instance.update_e_pipeline);
+            if let (Some(texture_pipelines), Some(field_textures)) =
+                (&self.instance.texture_pipelines, &self.state.field_textures)
+            {
+                compute_pass.set_bind_group(
+                    1,
+                    &field_textures.update_h_bind_groups[self.swap_buffer_index],
+                    &[],
+                );
+                dispatch_update(&mut compute_pass, &self.instance.update_h_pipeline);
+
+                compute_pass.set_bind_group(
+                    1,
+                    &field_textures.update_e_bind_groups[self.swap_buffer_index],
+                    &[],
+                );
+                dispatch_update(&mut compute_pass, &self.instance.update_e_pipeline);
+
+                // the texture kernels don't reset the source ids in the field buffers
+                compute_pass.set_pipeline(&texture_pipelines.clear_sources);
+                for num_workgroups in self
+                    .instance
+                    .backend
+                    .limits
+                    .divide_work_into_dispatches(num_sources, &self.instance.workgroup_size)
+                {
+                    compute_pass.dispatch_workgroups(
+                        num_workgroups.x,
+                        num_workgroups.y,
+                        num_workgroups.z,
+                    );
+                }
+
+                field_textures.updated(self.swap_buffer_index);
+            }
+            else {
+                dispatch_update(&mut compute_pass, &self.instance.update_h_pipeline);
+                dispatch_update(&mut compute_pass, &self.instance.update_e_pipeline);
+            }
         }
 
         self.instance
@@ -469,6 +814,7 @@ impl Field<Point3<usize>> for FdtdWgpuSolverInstance {
             let start_index = index_range.start;
 
             let swap_buffer_index = SwapBufferIndex::from_tick(state.tick);
+            self.sync_field_buffers(state, swap_buffer_index);
 
             let field_buffers = &state.field_buffers[swap_buffer_index];
             let buffer = &field_buffers[field_component];
@@ -715,6 +1061,19 @@ impl ComputeLimits {
         )
     }
 
+    /// Whether a workgroup of this size can be dispatched over the whole
+    /// lattice at once, with one invocation per cell.
+    pub fn fits_lattice_dispatch(
+        &self,
+        workgroup_size: &Vector3<u32>,
+        lattice_size: &Vector3<usize>,
+    ) -> bool {
+        workgroup_size.product() <= self.max_invocations_per_workgroup
+            && *workgroup_size <= self.max_workgroup_size
+            && num_workgroups_for_lattice(lattice_size, workgroup_size)
+                <= self.max_workgroups_per_dispatch
+    }
+
     pub fn divide_work_into_dispatches(
         &self,
         work_size: usize,
@@ -776,6 +1135,98 @@ pub fn divide_work_into_dispatches(
     })
 }
 
+/// Upper bound for the number of cells in a tile including its halo.
+///
+/// This must match `max_tile_cells` in `update_tiled.wgsl`, which sizes the
+/// workgroup memory with it.
+const MAX_TILE_CELLS: u32 = 768;
+
+/// Block of cells a workgroup of the tiled kernel updates, see
+/// [`UpdateKernel::Tiled`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct TileLayout {
+    /// Cells updated per workgroup. This is also the workgroup size.
+    tile: Vector3<u32>,
+
+    /// Cells staged on each side of the tile for the spatial derivatives.
+    halo: Vector3<u32>,
+}
+
+impl TileLayout {
+    /// Chooses a tile for the lattice, or `None` if no tile fits into the
+    /// device limits.
+    fn new(
+        lattice_size: &Vector3<usize>,
+        spatial_order: SpatialOrder,
+        limits: &ComputeLimits,
+    ) -> Option<Self> {
+        if (MAX_TILE_CELLS as usize) * size_of::<[f32; 4]>()
+            > limits.max_workgroup_storage_size as usize
+        {
+            return None;
+        }
+
+        // no halo along axes the lattice is flat in
+        let halo = lattice_size.map(|n| if n <= 1 { 0 } else { spatial_order.order() / 2 });
+
+        let tile = lattice_workgroup_size(lattice_size, |tile| {
+            Self { tile: *tile, halo }.fits(lattice_size, limits)
+        })?;
+
+        Some(Self { tile, halo })
+    }
+
+    fn fits(&self, lattice_size: &Vector3<usize>, limits: &ComputeLimits) -> bool {
+        let extent = self.tile + 2 * self.halo;
+
+        limits.fits_lattice_dispatch(&self.tile, lattice_size) && extent.product() <= MAX_TILE_CELLS
+    }
+
+    fn num_workgroups(&self, lattice_size: &Vector3<usize>) -> Vector3<u32> {
+        num_workgroups_for_lattice(lattice_size, &self.tile)
+    }
+}
+
+/// Workgroup size for kernels that run one invocation per cell, dispatched
+/// over the lattice in 3D (see [`TileLayout`] and [`FieldStorage::Texture`]).
+///
+/// The workgroup only extends along the axes the lattice extends along, so 2D
+/// and 1D lattices don't waste invocations. It's halved until `fits` accepts
+/// it, or `None` is returned if not even a single invocation does.
+fn lattice_workgroup_size(
+    lattice_size: &Vector3<usize>,
+    mut fits: impl FnMut(&Vector3<u32>) -> bool,
+) -> Option<Vector3<u32>> {
+    let flat = lattice_size.map(|n| n <= 1);
+    let mut sides = match flat.iter().filter(|flat| !**flat).count() {
+        0 | 1 => &[256][..],
+        2 => &[16, 16][..],
+        _ => &[8, 4, 4][..],
+    }
+    .iter()
+    .copied();
+    let mut workgroup_size = flat.map(|flat| if flat { 1 } else { sides.next().unwrap_or(1) });
+
+    loop {
+        if fits(&workgroup_size) {
+            return Some(workgroup_size);
+        }
+
+        let (axis, side) = workgroup_size.argmax();
+        if side <= 1 {
+            return None;
+        }
+        workgroup_size[axis] = side / 2;
+    }
+}
+
+fn num_workgroups_for_lattice(
+    lattice_size: &Vector3<usize>,
+    workgroup_size: &Vector3<u32>,
+) -> Vector3<u32> {
+    lattice_size.zip_map(workgroup_size, |n, size| n.div_ceil(size as usize) as u32)
+}
+
 #[derive(Clone, Copy, Debug)]
 struct Bindings {
     config: u32,
@@ -901,3 +1352,81 @@ const BINDINGS: Bindings = Bindings {
     h_field_previous: 5,
     e_field_previous: 6,
 };
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::fdtd::{
+        SpatialOrder,
+        wgpu::{
+            ComputeLimits,
+            MAX_TILE_CELLS,
+            TileLayout,
+            texture::texture_workgroup_size,
+        },
+    };
+
+    fn limits() -> ComputeLimits {
+        ComputeLimits {
+            max_workgroup_storage_size: 16384,
+            max_invocations_per_workgroup: 256,
+            max_workgroup_size: Vector3::new(256, 256, 64),
+            max_workgroups_per_dispatch: Vector3::repeat(65535),
+        }
+    }
+
+    #[test]
+    fn tile_follows_lattice_dimensions() {
+        let tile_layout = TileLayout::new(
+            &Vector3::new(100, 100, 100),
+            SpatialOrder::Fourth,
+            &limits(),
+        )
+        .unwrap();
+        assert_eq!(tile_layout.tile, Vector3::new(8, 4, 4));
+        assert_eq!(tile_layout.halo, Vector3::repeat(2));
+
+        let tile_layout =
+            TileLayout::new(&Vector3::new(100, 100, 1), SpatialOrder::Second, &limits()).unwrap();
+        assert_eq!(tile_layout.tile, Vector3::new(16, 16, 1));
+        assert_eq!(tile_layout.halo, Vector3::new(1, 1, 0));
+        assert_eq!(
+            tile_layout.num_workgroups(&Vector3::new(100, 100, 1)),
+            Vector3::new(7, 7, 1)
+        );
+    }
+
+    #[test]
+    fn tile_shrinks_to_fit_limits() {
+        let mut limits = limits();
+        limits.max_invocations_per_workgroup = 64;
+
+        let tile_layout =
+            TileLayout::new(&Vector3::new(100, 100, 100), SpatialOrder::Second, &limits).unwrap();
+        assert!(tile_layout.tile.product() <= 64, "{tile_layout:?}");
+        assert!(
+            (tile_layout.tile + 2 * tile_layout.halo).product() <= MAX_TILE_CELLS,
+            "{tile_layout:?}"
+        );
+
+        limits.max_workgroup_storage_size = 4096;
+        assert!(
+            TileLayout::new(&Vector3::new(100, 100, 100), SpatialOrder::Second, &limits).is_none()
+        );
+    }
+
+    #[test]
+    fn texture_must_fit_lattice() {
+        let device_limits = wgpu::Limits::downlevel_defaults();
+        let limits = ComputeLimits::from_limits(&device_limits);
+
+        let workgroup_size =
+            texture_workgroup_size(&Vector3::new(100, 100, 1), &limits, &device_limits);
+        assert_eq!(workgroup_size, Some(Vector3::new(16, 16, 1)));
+
+        let workgroup_size =
+            texture_workgroup_size(&Vector3::new(1000, 100, 100), &limits, &device_limits);
+        assert_eq!(workgroup_size, None);
+    }
+}
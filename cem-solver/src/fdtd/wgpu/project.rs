@@ -1,6 +1,11 @@
 use std::{
     hash::Hash,
     sync::Arc,
+    task::{
+        Context,
+        Poll,
+        Waker,
+    },
 };
 
 use bytemuck::{
@@ -27,9 +32,12 @@ use crate::{
         BeginProjectionPass,
         CreateProjection,
         FdtdImageTarget,
+        POST_PROCESS_SIGNATURE,
         ProjectionParameters,
         ProjectionPass,
         ProjectionPassAdd,
+        SetProjectionTransform,
+        validate_post_process_code,
     },
 };
 
@@ -107,6 +115,12 @@ impl ProjectionPipeline {
 struct TextureProjectionInner {
     pipeline: Arc<wgpu::RenderPipeline>,
     bind_groups: SwapBuffer<wgpu::BindGroup>,
+    projection_buffer: wgpu::Buffer,
+    projection_data: ProjectionData,
+
+    /// Set when the projection data changed and needs to be written to
+    /// `projection_buffer` before the next projection.
+    projection_data_changed: bool,
 }
 
 impl TextureProjectionInner {
@@ -127,11 +141,20 @@ impl TextureProjectionInner {
                     .to_owned()
             });
 
+            let post_process = parameters.post_process_code.clone().filter(|code| {
+                validate_post_process_code(code)
+                    .inspect_err(|error| {
+                        tracing::error!(%error, "Ignoring invalid post-processing code");
+                    })
+                    .is_ok()
+            });
+
             cache.get_pipeline(
                 &instance.backend.device,
                 &instance.backend.projection.pipeline_layout,
                 target_texture_format,
                 color_map,
+                post_process,
             )
         };
 
@@ -144,7 +167,7 @@ impl TextureProjectionInner {
                 .create_buffer_init(&wgpu::util::BufferInitDescriptor {
                     label: Some("fdtd/project/projection"),
                     contents: bytemuck::bytes_of(&projection_data),
-                    usage: wgpu::BufferUsages::UNIFORM,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
                 });
 
         let field_component_buffer = |swap_buffer_index| {
@@ -182,15 +205,33 @@ impl TextureProjectionInner {
         Self {
             pipeline,
             bind_groups,
+            projection_buffer,
+            projection_data,
+            projection_data_changed: false,
         }
     }
 
+    fn set_projection_transform(&mut self, projection: Matrix4<f32>) {
+        self.projection_data.projection = projection;
+        self.projection_data_changed = true;
+    }
+
     fn project(
-        &self,
+        &mut self,
+        queue: &wgpu::Queue,
         command_encoder: &mut wgpu::CommandEncoder,
         swap_buffer_index: SwapBufferIndex,
         target_texture_view: &wgpu::TextureView,
     ) {
+        // the write is scheduled before the command buffer of the pass is submitted
+        if std::mem::take(&mut self.projection_data_changed) {
+            queue.write_buffer(
+                &self.projection_buffer,
+                0,
+                bytemuck::bytes_of(&self.projection_data),
+            );
+        }
+
         let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("fdtd/project"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -219,6 +260,12 @@ pub struct FdtdWgpuTextureProjection {
     texture_view: wgpu::TextureView,
 }
 
+impl SetProjectionTransform for FdtdWgpuTextureProjection {
+    fn set_projection_transform(&mut self, projection: Matrix4<f32>) {
+        self.inner.set_projection_transform(projection);
+    }
+}
+
 impl CreateProjection<wgpu::Texture> for FdtdWgpuSolverInstance {
     type Projection = FdtdWgpuTextureProjection;
 
@@ -342,6 +389,15 @@ where
     }
 }
 
+impl<Target> SetProjectionTransform for ImageProjection<Target>
+where
+    Target: FdtdImageTarget,
+{
+    fn set_projection_transform(&mut self, projection: Matrix4<f32>) {
+        self.inner.set_projection_transform(projection);
+    }
+}
+
 #[derive(Debug)]
 struct Staging {
     bytes_per_row_padded: u32,
@@ -354,6 +410,7 @@ where
 {
     fn add_projection(&mut self, projection: &'a mut ImageProjection<Target>) {
         projection.inner.project(
+            &self.instance.backend.queue,
             &mut self.command_encoder,
             self.swap_buffer_index,
             &projection.buffer_texture_view,
@@ -466,11 +523,6 @@ type CopyToImageBufferCallback<'a> =
 
 impl<'a> FdtdWgpuProjectionPass<'a> {
     fn new(instance: &'a FdtdWgpuSolverInstance, state: &'a FdtdWgpuSolverState) -> Self {
-        // we don't need the state here because the bind groups already reference the
-        // buffers. all we could do here is that it's actually the correct state
-        // object
-        let _ = state;
-
         let command_encoder =
             instance
                 .backend
@@ -481,6 +533,10 @@ impl<'a> FdtdWgpuProjectionPass<'a> {
 
         let swap_buffer_index = SwapBufferIndex::from_tick(state.tick + 1);
 
+        // the bind groups already reference the field buffers, but if the fields are
+        // stored in textures, the buffers have to be brought up to date first.
+        instance.sync_field_buffers(state, swap_buffer_index);
+
         Self {
             instance,
             command_encoder,
@@ -493,6 +549,7 @@ impl<'a> FdtdWgpuProjectionPass<'a> {
 impl<'a> ProjectionPassAdd<'a, FdtdWgpuTextureProjection> for FdtdWgpuProjectionPass<'a> {
     fn add_projection(&mut self, projection: &mut FdtdWgpuTextureProjection) {
         projection.inner.project(
+            &self.instance.backend.queue,
             &mut self.command_encoder,
             self.swap_buffer_index,
             &projection.texture_view,
@@ -580,9 +637,11 @@ impl Cache {
         pipeline_layout: &wgpu::PipelineLayout,
         target_texture_format: wgpu::TextureFormat,
         color_map: String,
+        post_process: Option<String>,
     ) -> Arc<wgpu::RenderPipeline> {
         let shader_key = ShaderKey {
             color_map: color_map.clone(),
+            post_process: post_process.clone(),
         };
 
         let pipeline_key = PipelineKey {
@@ -598,10 +657,37 @@ impl Cache {
                 &format!("fn color_map(value: vec3f) -> vec4f {{{color_map}}}"),
             );
 
-            let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: Some("fdtd/project"),
-                source: wgpu::ShaderSource::Wgsl(source.into()),
-            });
+            let shader_module = post_process
+                .and_then(|post_process| {
+                    tracing::debug!("Using post-processing code:\n{post_process}");
+
+                    let source = source.replace(
+                        &format!("{POST_PROCESS_SIGNATURE} {{return value;}}"),
+                        &format!("{POST_PROCESS_SIGNATURE} {{{post_process}}}"),
+                    );
+
+                    // the user code might not compile, in which case we fall back to the shader
+                    // without post-processing.
+                    device.push_error_scope(wgpu::ErrorFilter::Validation);
+                    let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                        label: Some("fdtd/project/post_process"),
+                        source: wgpu::ShaderSource::Wgsl(source.into()),
+                    });
+                    match pop_error_scope(device) {
+                        Some(error) => {
+                            tracing::error!(%error, "Post-processing code failed to compile");
+                            None
+                        }
+                        None => Some(shader_module),
+                    }
+                })
+                .unwrap_or_else(|| {
+                    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                        label: Some("fdtd/project"),
+                        source: wgpu::ShaderSource::Wgsl(source.into()),
+                    })
+                });
+
             Arc::new(shader_module)
         };
 
@@ -646,9 +732,22 @@ impl Cache {
     }
 }
 
+/// Pops an error scope without an async runtime.
+///
+/// On native backends validation is synchronous, so the future is ready right
+/// away. If it isn't, we assume there was no error.
+fn pop_error_scope(device: &wgpu::Device) -> Option<wgpu::Error> {
+    let future = std::pin::pin!(device.pop_error_scope());
+    match future.poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(error) => error,
+        Poll::Pending => None,
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct ShaderKey {
     color_map: String,
+    post_process: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -60,7 +60,7 @@ fn fs_main(input: VertexOutput) -> FragmentOutput {
     let point = vec3u(round(input.field_position));
     let index = point_to_index(point);
 
-    let value = field[index].value;
+    let value = post_process(field[index].value, point);
 
 
     //var color = clamp(projection.color_map * vec4f(value, 1.0), vec4f(0.0), vec4f(1.0));
@@ -78,6 +78,10 @@ fn fs_main(input: VertexOutput) -> FragmentOutput {
 // This is hard-coded into project.rs to be replaced with the actual colormap
 fn color_map(value: vec3f) -> vec4f {return vec4f(0.0);}
 
+// DO NOT MODIFY THIS LINE
+// This is hard-coded into project.rs to be replaced with the user's post-processing code
+fn post_process(value: vec3f, point: vec3u) -> vec3f {return value;}
+
 
 const quad_vertices: array<vec2f, 6> = array<vec2f, 6>(
     // first tri
@@ -0,0 +1,50 @@
+// Copies the fields from the textures used by `update_texture.wgsl` into the
+// field buffers, from where they're read back and projected.
+
+struct Config {
+    size: vec4u,
+    strides: vec4u,
+    resolution: vec4f,
+    time: f32,
+    num_sources: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> config: Config;
+
+struct Cell {
+    value: vec3f,
+    source_id: u32,
+}
+
+@group(0) @binding(3)
+var<storage, read_write> h_field_next: array<Cell>;
+
+@group(0) @binding(4)
+var<storage, read_write> e_field_next: array<Cell>;
+
+@group(1) @binding(0)
+var h_field: texture_3d<f32>;
+
+@group(1) @binding(1)
+var e_field: texture_3d<f32>;
+
+
+// override constants for the workgroup size being used
+override workgroup_size_x: u32 = 0;
+override workgroup_size_y: u32 = 0;
+override workgroup_size_z: u32 = 0;
+
+
+@compute @workgroup_size(workgroup_size_x, workgroup_size_y, workgroup_size_z)
+fn store_fields(@builtin(global_invocation_id) x: vec3u) {
+    // check if our worker is outside of lattice
+    if any(x >= config.size.xyz) {
+        return;
+    }
+
+    // only the values are copied, the source ids belong to the buffers
+    let index = dot(x, config.strides.xyz);
+    h_field_next[index].value = textureLoad(h_field, x, 0).xyz;
+    e_field_next[index].value = textureLoad(e_field, x, 0).xyz;
+}
@@ -0,0 +1,444 @@
+//! Field storage in 3D textures, see [`FieldStorage::Texture`].
+//!
+//! [`FieldStorage::Texture`]: super::FieldStorage::Texture
+
+use std::sync::atomic::{
+    AtomicBool,
+    Ordering,
+};
+
+use nalgebra::Vector3;
+
+use crate::fdtd::{
+    SpatialOrder,
+    util::{
+        SwapBuffer,
+        SwapBufferIndex,
+    },
+    wgpu::{
+        ComputeLimits,
+        FdtdWgpuBackend,
+        FdtdWgpuSolverInstance,
+        FdtdWgpuSolverState,
+        lattice_workgroup_size,
+    },
+};
+
+/// Format of the field textures. The 4th component is unused.
+const FIELD_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+
+/// Bytes per cell in a field texture.
+pub(super) const FIELD_TEXTURE_CELL_SIZE: usize = size_of::<[f32; 4]>();
+
+/// Layouts and shaders for texture storage, created once per backend.
+#[derive(Clone, Debug)]
+pub(super) struct TextureStorageLayout {
+    update_shader_module: wgpu::ShaderModule,
+    store_shader_module: wgpu::ShaderModule,
+    update_bind_group_layout: wgpu::BindGroupLayout,
+    store_bind_group_layout: wgpu::BindGroupLayout,
+    update_pipeline_layout: wgpu::PipelineLayout,
+    store_pipeline_layout: wgpu::PipelineLayout,
+}
+
+impl TextureStorageLayout {
+    /// `bind_group_layout` is the layout of the buffer bindings, which are
+    /// bound to group 0 of all texture kernels.
+    pub(super) fn new(device: &wgpu::Device, bind_group_layout: &wgpu::BindGroupLayout) -> Self {
+        let update_shader_module =
+            device.create_shader_module(wgpu::include_wgsl!("update_texture.wgsl"));
+        let store_shader_module =
+            device.create_shader_module(wgpu::include_wgsl!("store_fields.wgsl"));
+
+        let field_texture_entry = |binding| {
+            wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D3,
+                    multisampled: false,
+                },
+                count: None,
+            }
+        };
+
+        let update_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("fdtd/texture/update"),
+                entries: &[
+                    // field the curl is taken of
+                    field_texture_entry(0),
+                    // previous value of the updated field
+                    field_texture_entry(1),
+                    // next value of the updated field
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::StorageTexture {
+                            access: wgpu::StorageTextureAccess::WriteOnly,
+                            format: FIELD_TEXTURE_FORMAT,
+                            view_dimension: wgpu::TextureViewDimension::D3,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let store_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("fdtd/texture/store"),
+                entries: &[
+                    // h field
+                    field_texture_entry(0),
+                    // e field
+                    field_texture_entry(1),
+                ],
+            });
+
+        let update_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("fdtd/texture/update"),
+                bind_group_layouts: &[bind_group_layout, &update_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let store_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("fdtd/texture/store"),
+                bind_group_layouts: &[bind_group_layout, &store_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        Self {
+            update_shader_module,
+            store_shader_module,
+            update_bind_group_layout,
+            store_bind_group_layout,
+            update_pipeline_layout,
+            store_pipeline_layout,
+        }
+    }
+}
+
+/// Workgroup size for the texture kernels, or `None` if the lattice doesn't
+/// fit into a 3D texture or a single dispatch.
+pub(super) fn texture_workgroup_size(
+    lattice_size: &Vector3<usize>,
+    limits: &ComputeLimits,
+    device_limits: &wgpu::Limits,
+) -> Option<Vector3<u32>> {
+    let max_texture_size = device_limits.max_texture_dimension_3d as usize;
+    if lattice_size.iter().any(|n| *n > max_texture_size) {
+        return None;
+    }
+
+    lattice_workgroup_size(lattice_size, |workgroup_size| {
+        limits.fits_lattice_dispatch(workgroup_size, lattice_size)
+    })
+}
+
+/// Pipelines of an instance that stores its fields in textures.
+#[derive(Clone, Debug)]
+pub(super) struct TexturePipelines {
+    pub update_h: wgpu::ComputePipeline,
+    pub update_e: wgpu::ComputePipeline,
+    pub clear_sources: wgpu::ComputePipeline,
+    pub store_fields: wgpu::ComputePipeline,
+}
+
+impl TexturePipelines {
+    /// `workgroup_size` is used for the kernels that run over the lattice,
+    /// `source_workgroup_size` for the one that runs over the sources.
+    pub(super) fn new(
+        backend: &FdtdWgpuBackend,
+        workgroup_size: &Vector3<u32>,
+        source_workgroup_size: &Vector3<u32>,
+        spatial_order: SpatialOrder,
+    ) -> Self {
+        let layout = &backend.texture_storage;
+
+        let shader_constants = |workgroup_size: &Vector3<u32>| {
+            [
+                ("workgroup_size_x", workgroup_size.x.into()),
+                ("workgroup_size_y", workgroup_size.y.into()),
+                ("workgroup_size_z", workgroup_size.z.into()),
+                ("spatial_order", spatial_order.order().into()),
+            ]
+        };
+        let lattice_constants = shader_constants(workgroup_size);
+        let source_constants = shader_constants(source_workgroup_size);
+
+        let create_pipeline =
+            |label, pipeline_layout, module, entrypoint, constants: &[(&str, f64)]| {
+                backend
+                    .device
+                    .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                        label: Some(label),
+                        layout: Some(pipeline_layout),
+                        module,
+                        entry_point: Some(entrypoint),
+                        compilation_options: wgpu::PipelineCompilationOptions {
+                            constants,
+                            zero_initialize_workgroup_memory: true,
+                        },
+                        cache: None,
+                    })
+            };
+
+        Self {
+            update_h: create_pipeline(
+                "fdtd/texture/update/h",
+                &layout.update_pipeline_layout,
+                &layout.update_shader_module,
+                "update_h",
+                &lattice_constants,
+            ),
+            update_e: create_pipeline(
+                "fdtd/texture/update/e",
+                &layout.update_pipeline_layout,
+                &layout.update_shader_module,
+                "update_e",
+                &lattice_constants,
+            ),
+            clear_sources: create_pipeline(
+                "fdtd/texture/clear_sources",
+                &layout.update_pipeline_layout,
+                &layout.update_shader_module,
+                "clear_sources",
+                &source_constants,
+            ),
+            // the store shader has no spatial order
+            store_fields: create_pipeline(
+                "fdtd/texture/store",
+                &layout.store_pipeline_layout,
+                &layout.store_shader_module,
+                "store_fields",
+                &lattice_constants[..3],
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FieldTextureSet {
+    e: wgpu::Texture,
+    h: wgpu::Texture,
+}
+
+#[derive(Debug)]
+struct FieldTextureViews {
+    e: wgpu::TextureView,
+    h: wgpu::TextureView,
+}
+
+/// Field textures of a state.
+#[derive(Debug)]
+pub(super) struct FieldTextures {
+    textures: SwapBuffer<FieldTextureSet>,
+    pub update_h_bind_groups: SwapBuffer<wgpu::BindGroup>,
+    pub update_e_bind_groups: SwapBuffer<wgpu::BindGroup>,
+    store_bind_groups: SwapBuffer<wgpu::BindGroup>,
+
+    /// Whether the field buffers hold older values than the textures.
+    buffers_stale: SwapBuffer<AtomicBool>,
+}
+
+impl FieldTextures {
+    pub(super) fn new(instance: &FdtdWgpuSolverInstance) -> Self {
+        let device = &instance.backend.device;
+        let layout = &instance.backend.texture_storage;
+        let size = instance.strider.size().cast::<u32>();
+
+        // textures are zero-initialized, like the field buffers
+        let textures = SwapBuffer::from_fn(|_| {
+            let texture = |label| {
+                device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width: size.x,
+                        height: size.y,
+                        depth_or_array_layers: size.z,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D3,
+                    format: FIELD_TEXTURE_FORMAT,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING
+                        | wgpu::TextureUsages::STORAGE_BINDING
+                        | wgpu::TextureUsages::COPY_SRC
+                        | wgpu::TextureUsages::COPY_DST,
+                    view_formats: &[],
+                })
+            };
+            FieldTextureSet {
+                e: texture("fdtd/texture/field/e"),
+                h: texture("fdtd/texture/field/h"),
+            }
+        });
+
+        let views = SwapBuffer::from_fn(|index| {
+            let view = |texture: &wgpu::Texture| texture.create_view(&Default::default());
+            FieldTextureViews {
+                e: view(&textures[index].e),
+                h: view(&textures[index].h),
+            }
+        });
+
+        let update_bind_group = |label,
+                                 curl_field: &wgpu::TextureView,
+                                 prev_field: &wgpu::TextureView,
+                                 next_field: &wgpu::TextureView| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &layout.update_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(curl_field),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(prev_field),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(next_field),
+                    },
+                ],
+            })
+        };
+
+        let update_h_bind_groups = SwapBuffer::from_fn(|current| {
+            let previous = current.other();
+            update_bind_group(
+                "fdtd/texture/update/h",
+                &views[previous].e,
+                &views[previous].h,
+                &views[current].h,
+            )
+        });
+
+        let update_e_bind_groups = SwapBuffer::from_fn(|current| {
+            let previous = current.other();
+            update_bind_group(
+                "fdtd/texture/update/e",
+                &views[current].h,
+                &views[previous].e,
+                &views[current].e,
+            )
+        });
+
+        let store_bind_groups = SwapBuffer::from_fn(|current| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("fdtd/texture/store"),
+                layout: &layout.store_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&views[current].h),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&views[current].e),
+                    },
+                ],
+            })
+        });
+
+        Self {
+            textures,
+            update_h_bind_groups,
+            update_e_bind_groups,
+            store_bind_groups,
+            buffers_stale: SwapBuffer::from_fn(|_| AtomicBool::new(false)),
+        }
+    }
+
+    /// Marks the field buffers as stale after the textures were updated.
+    pub(super) fn updated(&self, swap_buffer_index: SwapBufferIndex) {
+        self.buffers_stale[swap_buffer_index].store(true, Ordering::Relaxed);
+    }
+
+    /// Copies the textures and stale flags of `self` into `target`.
+    pub(super) fn copy_to(&self, target: &Self, command_encoder: &mut wgpu::CommandEncoder) {
+        for swap_buffer_index in [SwapBufferIndex::from_tick(0), SwapBufferIndex::from_tick(1)] {
+            let source_textures = &self.textures[swap_buffer_index];
+            let target_textures = &target.textures[swap_buffer_index];
+
+            for (source, target) in [
+                (&source_textures.e, &target_textures.e),
+                (&source_textures.h, &target_textures.h),
+            ] {
+                command_encoder.copy_texture_to_texture(
+                    source.as_image_copy(),
+                    target.as_image_copy(),
+                    source.size(),
+                );
+            }
+
+            target.buffers_stale[swap_buffer_index].store(
+                self.buffers_stale[swap_buffer_index].load(Ordering::Relaxed),
+                Ordering::Relaxed,
+            );
+        }
+    }
+}
+
+impl FdtdWgpuSolverInstance {
+    /// Copies the fields from the textures into the field buffers at
+    /// `swap_buffer_index`, if they're stale.
+    ///
+    /// This is submitted to the queue, so anything that reads the buffers
+    /// afterwards sees the current fields.
+    pub(super) fn sync_field_buffers(
+        &self,
+        state: &FdtdWgpuSolverState,
+        swap_buffer_index: SwapBufferIndex,
+    ) {
+        let (Some(pipelines), Some(field_textures)) =
+            (&self.texture_pipelines, &state.field_textures)
+        else {
+            return;
+        };
+
+        if !field_textures.buffers_stale[swap_buffer_index].swap(false, Ordering::Relaxed) {
+            return;
+        }
+
+        let mut command_encoder =
+            self.backend
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("fdtd/texture/store"),
+                });
+
+        {
+            let mut compute_pass =
+                command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("fdtd/texture/store"),
+                    timestamp_writes: None,
+                });
+
+            // the bind group for this index has the field buffers at this index as next
+            // fields
+            compute_pass.set_bind_group(0, &state.update_bind_groups[swap_buffer_index], &[]);
+            compute_pass.set_bind_group(
+                1,
+                &field_textures.store_bind_groups[swap_buffer_index],
+                &[],
+            );
+            compute_pass.set_pipeline(&pipelines.store_fields);
+
+            for num_workgroups in &self.dispatches {
+                compute_pass.dispatch_workgroups(
+                    num_workgroups.x,
+                    num_workgroups.y,
+                    num_workgroups.z,
+                );
+            }
+        }
+
+        self.backend.queue.submit([command_encoder.finish()]);
+    }
+}
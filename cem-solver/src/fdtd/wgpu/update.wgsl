@@ -46,6 +46,13 @@ override workgroup_size_x: u32 = 0;
 override workgroup_size_y: u32 = 0;
 override workgroup_size_z: u32 = 0;
 
+// order of the spatial derivatives: 2 (Yee) or 4 for the (2,4) scheme
+override spatial_order: u32 = 2;
+
+// coefficients for the 4th order central difference
+const c_inner: f32 = 9.0 / 8.0;
+const c_outer: f32 = -1.0 / 24.0;
+
 // compute shader input
 struct Input {
     @builtin(global_invocation_id) worker_id: vec3u,
@@ -154,8 +161,17 @@ fn curl(dfdx: vec3f, dfdy: vec3f, dfdz: vec3f) -> vec3f {
 
 fn dedi(index: u32, x: vec3u, axis: u32) -> vec3f {
     if x[axis] > 0 {
-        let e1 = e_field_prev[index - config.strides[axis]].value;
+        let stride = config.strides[axis];
+        let e1 = e_field_prev[index - stride].value;
         let e2 = e_field_prev[index].value;
+
+        // the wide stencil falls back to 2nd order next to the boundary
+        if spatial_order == 4 && x[axis] > 1 && x[axis] + 1 < config.size[axis] {
+            let e0 = e_field_prev[index - 2 * stride].value;
+            let e3 = e_field_prev[index + stride].value;
+            return (c_inner * (e2 - e1) + c_outer * (e3 - e0)) / config.resolution[axis];
+        }
+
         return (e2 - e1) / config.resolution[axis];
     }
     else {
@@ -166,8 +182,17 @@ fn dedi(index: u32, x: vec3u, axis: u32) -> vec3f {
 
 fn dhdi(index: u32, x: vec3u, axis: u32) -> vec3f {
     if x[axis] + 1 < config.size[axis] {
+        let stride = config.strides[axis];
         let h1 = h_field_next[index].value;
-        let h2 = h_field_next[index + config.strides[axis]].value;
+        let h2 = h_field_next[index + stride].value;
+
+        // the wide stencil falls back to 2nd order next to the boundary
+        if spatial_order == 4 && x[axis] > 0 && x[axis] + 2 < config.size[axis] {
+            let h0 = h_field_next[index - stride].value;
+            let h3 = h_field_next[index + 2 * stride].value;
+            return (c_inner * (h2 - h1) + c_outer * (h3 - h0)) / config.resolution[axis];
+        }
+
         return (h2 - h1) / config.resolution[axis];
     }
     else {
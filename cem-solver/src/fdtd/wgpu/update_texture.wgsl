@@ -0,0 +1,224 @@
+// Variant of `update.wgsl` that keeps the fields in 3D textures.
+//
+// The stencil reads neighbors along all 3 axes. In a flat buffer only the
+// neighbors along x are close in memory, but textures are laid out such that
+// the texture cache also covers the neighbors along y and z.
+//
+// The field buffers are still bound, but only the `source_id`s in them are
+// used. `store_fields.wgsl` copies the field values into them when they're
+// read back or projected.
+
+struct Config {
+    size: vec4u,
+    strides: vec4u,
+    resolution: vec4f,
+    time: f32,
+    num_sources: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> config: Config;
+
+@group(0) @binding(1)
+var<storage, read> materials: array<vec4f>;
+
+struct Source {
+    j_source: vec3f,
+    index: u32,
+    m_source: vec3f,
+}
+
+@group(0) @binding(2)
+var<storage, read> sources: array<Source>;
+
+struct Cell {
+    value: vec3f,
+    source_id: u32,
+}
+
+@group(0) @binding(3)
+var<storage, read_write> h_field_next: array<Cell>;
+
+@group(0) @binding(4)
+var<storage, read_write> e_field_next: array<Cell>;
+
+// field the curl is taken of: the previous E field when updating H, and the H
+// field that was just updated when updating E.
+@group(1) @binding(0)
+var curl_field: texture_3d<f32>;
+
+// previous value of the field that is updated
+@group(1) @binding(1)
+var prev_field: texture_3d<f32>;
+
+// next value of the field that is updated
+@group(1) @binding(2)
+var next_field: texture_storage_3d<rgba32float, write>;
+
+
+// override constants for the workgroup size being used
+override workgroup_size_x: u32 = 0;
+override workgroup_size_y: u32 = 0;
+override workgroup_size_z: u32 = 0;
+
+// order of the spatial derivatives: 2 (Yee) or 4 for the (2,4) scheme
+override spatial_order: u32 = 2;
+
+// coefficients for the 4th order central difference
+const c_inner: f32 = 9.0 / 8.0;
+const c_outer: f32 = -1.0 / 24.0;
+
+// compute shader input for `clear_sources`
+struct Input {
+    @builtin(global_invocation_id) worker_id: vec3u,
+    @builtin(num_workgroups) num_workgroups: vec3u,
+}
+
+
+@compute @workgroup_size(workgroup_size_x, workgroup_size_y, workgroup_size_z)
+fn update_h(@builtin(global_invocation_id) x: vec3u) {
+    // check if our worker is outside of lattice
+    if any(x >= config.size.xyz) {
+        return;
+    }
+
+    let index = point_to_index(x);
+
+    // calculate curl
+    let dedx = backward_difference(x, 0);
+    let dedy = backward_difference(x, 1);
+    let dedz = backward_difference(x, 2);
+    let e_curl = curl(dedx, dedy, dedz);
+
+    // material coefficients: D_a, D_b
+    let coeff = materials[index].zw;
+
+    // source
+    var m_source: vec3f;
+    let source_id = h_field_next[index].source_id;
+    if source_id != 0 {
+        m_source = sources[source_id].m_source;
+    }
+
+    // todo: pml
+    let psi = vec3f(0.0);
+
+    // update rule
+    let h = coeff.x * textureLoad(prev_field, x, 0).xyz + coeff.y * (-e_curl - m_source + psi);
+    textureStore(next_field, x, vec4f(h, 0.0));
+}
+
+
+@compute @workgroup_size(workgroup_size_x, workgroup_size_y, workgroup_size_z)
+fn update_e(@builtin(global_invocation_id) x: vec3u) {
+    // check if our worker is outside of lattice
+    if any(x >= config.size.xyz) {
+        return;
+    }
+
+    let index = point_to_index(x);
+
+    // calculate curl
+    let dhdx = forward_difference(x, 0);
+    let dhdy = forward_difference(x, 1);
+    let dhdz = forward_difference(x, 2);
+    let h_curl = curl(dhdx, dhdy, dhdz);
+
+    // material coefficients: C_a, C_b
+    let coeff = materials[index].xy;
+
+    // source
+    var j_source: vec3f;
+    let source_id = e_field_next[index].source_id;
+    if source_id != 0 {
+        j_source = sources[source_id].j_source;
+    }
+
+    // todo: pml
+    let psi = vec3f(0.0);
+
+    // update rule
+    let e = coeff.x * textureLoad(prev_field, x, 0).xyz + coeff.y * (h_curl - j_source + psi);
+    textureStore(next_field, x, vec4f(e, 0.0));
+}
+
+
+// resets the source ids that `update_sources` in `update.wgsl` put into the
+// field buffers. the buffer kernels do this when they store the updated cells.
+@compute @workgroup_size(workgroup_size_x, workgroup_size_y, workgroup_size_z)
+fn clear_sources(input: Input) {
+    let source_id = input_to_index(input);
+
+    if source_id >= config.num_sources {
+        return;
+    }
+
+    let index = sources[source_id].index;
+    e_field_next[index].source_id = 0;
+    h_field_next[index].source_id = 0;
+}
+
+fn curl(dfdx: vec3f, dfdy: vec3f, dfdz: vec3f) -> vec3f {
+    return vec3f(
+        dfdy.z - dfdz.y,
+        dfdz.x - dfdx.z,
+        dfdx.y - dfdy.x,
+    );
+}
+
+// derivative of the E field, see `dedi` in `update.wgsl`
+fn backward_difference(x: vec3u, axis: u32) -> vec3f {
+    if x[axis] > 0 {
+        let e1 = load_curl_field(x, axis, -1);
+        let e2 = load_curl_field(x, axis, 0);
+
+        // the wide stencil falls back to 2nd order next to the boundary
+        if spatial_order == 4 && x[axis] > 1 && x[axis] + 1 < config.size[axis] {
+            let e0 = load_curl_field(x, axis, -2);
+            let e3 = load_curl_field(x, axis, 1);
+            return (c_inner * (e2 - e1) + c_outer * (e3 - e0)) / config.resolution[axis];
+        }
+
+        return (e2 - e1) / config.resolution[axis];
+    }
+    else {
+        // boundary condition
+        return vec3f(0.0);
+    }
+}
+
+// derivative of the H field, see `dhdi` in `update.wgsl`
+fn forward_difference(x: vec3u, axis: u32) -> vec3f {
+    if x[axis] + 1 < config.size[axis] {
+        let h1 = load_curl_field(x, axis, 0);
+        let h2 = load_curl_field(x, axis, 1);
+
+        // the wide stencil falls back to 2nd order next to the boundary
+        if spatial_order == 4 && x[axis] > 0 && x[axis] + 2 < config.size[axis] {
+            let h0 = load_curl_field(x, axis, -1);
+            let h3 = load_curl_field(x, axis, 2);
+            return (c_inner * (h2 - h1) + c_outer * (h3 - h0)) / config.resolution[axis];
+        }
+
+        return (h2 - h1) / config.resolution[axis];
+    }
+    else {
+        // boundary condition
+        return vec3f(0.0);
+    }
+}
+
+// loads the cell `offset` cells away from `x` along `axis`
+fn load_curl_field(x: vec3u, axis: u32, offset: i32) -> vec3f {
+    var point = vec3i(x);
+    point[axis] += offset;
+    return textureLoad(curl_field, point, 0).xyz;
+}
+
+fn point_to_index(point: vec3u) -> u32 {
+    return dot(point, config.strides.xyz);
+}
+
+fn input_to_index(input: Input) -> u32 {
+    return input.worker_id.x + input.num_workgroups.x * workgroup_size_x * (input.worker_id.y + input.num_workgroups.y * workgroup_size_y * input.worker_id.z);
+}
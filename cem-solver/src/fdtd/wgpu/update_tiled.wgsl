@@ -0,0 +1,284 @@
+// Variant of `update.wgsl` that stages the field the curl is taken of in
+// workgroup memory first.
+//
+// Every workgroup updates a block of `tile_x * tile_y * tile_z` cells. The
+// block is loaded together with a halo of `halo_*` cells on each side, which
+// the spatial derivatives at the edges of the block read. This way every cell
+// is read from global memory about once per workgroup instead of once per
+// neighbor.
+
+struct Config {
+    size: vec4u,
+    strides: vec4u,
+    resolution: vec4f,
+    time: f32,
+    num_sources: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> config: Config;
+
+@group(0) @binding(1)
+var<storage, read> materials: array<vec4f>;
+
+struct Source {
+    j_source: vec3f,
+    index: u32,
+    m_source: vec3f,
+}
+
+@group(0) @binding(2)
+var<storage, read> sources: array<Source>;
+
+struct Cell {
+    value: vec3f,
+    source_id: u32,
+}
+
+@group(0) @binding(3)
+var<storage, read_write> h_field_next: array<Cell>;
+
+@group(0) @binding(4)
+var<storage, read_write> e_field_next: array<Cell>;
+
+@group(0) @binding(5)
+var<storage, read> h_field_prev: array<Cell>;
+
+@group(0) @binding(6)
+var<storage, read> e_field_prev: array<Cell>;
+
+
+// size of the block of cells a workgroup updates. this is also the workgroup
+// size.
+override tile_x: u32 = 8;
+override tile_y: u32 = 4;
+override tile_z: u32 = 4;
+
+// cells loaded on each side of the block. this is half the spatial order,
+// or 0 along axes the lattice is flat in.
+override halo_x: u32 = 1;
+override halo_y: u32 = 1;
+override halo_z: u32 = 1;
+
+// order of the spatial derivatives: 2 (Yee) or 4 for the (2,4) scheme
+override spatial_order: u32 = 2;
+
+// coefficients for the 4th order central difference
+const c_inner: f32 = 9.0 / 8.0;
+const c_outer: f32 = -1.0 / 24.0;
+
+// upper bound for the number of cells in a tile including its halo. this must
+// match `MAX_TILE_CELLS` in `mod.rs`.
+const max_tile_cells: u32 = 768;
+
+var<workgroup> tile: array<vec3f, max_tile_cells>;
+
+// compute shader input
+struct Input {
+    @builtin(workgroup_id) workgroup_id: vec3u,
+    @builtin(local_invocation_id) local_id: vec3u,
+    @builtin(local_invocation_index) local_index: u32,
+}
+
+
+@compute @workgroup_size(tile_x, tile_y, tile_z)
+fn update_h(input: Input) {
+    let extent = tile_extent();
+    let origin = vec3i(input.workgroup_id * tile_size()) - vec3i(halo());
+
+    // stage E field. the number of iterations is the same for all invocations, so
+    // the barrier is in uniform control flow.
+    let num_tile_cells = extent.x * extent.y * extent.z;
+    let num_invocations = tile_x * tile_y * tile_z;
+    for (var i = 0u; i < num_tile_cells; i += num_invocations) {
+        let tile_index = i + input.local_index;
+        if tile_index < num_tile_cells {
+            let point = origin + vec3i(tile_index_to_point(tile_index, extent));
+            var value = vec3f(0.0);
+            if in_lattice(point) {
+                value = e_field_prev[point_to_index(vec3u(point))].value;
+            }
+            tile[tile_index] = value;
+        }
+    }
+    workgroupBarrier();
+
+    // calculate point we're operating on
+    let x = input.workgroup_id * tile_size() + input.local_id;
+
+    // check if our worker is outside of lattice
+    if any(x >= config.size.xyz) {
+        return;
+    }
+
+    let index = point_to_index(x);
+    let tile_index = tile_point_to_index(input.local_id + halo(), extent);
+
+    // calculate curl
+    let dedx = backward_difference(tile_index, x, extent, 0);
+    let dedy = backward_difference(tile_index, x, extent, 1);
+    let dedz = backward_difference(tile_index, x, extent, 2);
+    let e_curl = curl(dedx, dedy, dedz);
+
+    // material coefficients: D_a, D_b
+    let coeff = materials[index].zw;
+
+    // source
+    var m_source: vec3f;
+    let source_id = h_field_next[index].source_id;
+    if source_id != 0 {
+        m_source = sources[source_id].m_source;
+    }
+
+    // todo: pml
+    let psi = vec3f(0.0);
+
+    // update rule
+    let h = coeff.x * h_field_prev[index].value + coeff.y * (-e_curl - m_source + psi);
+    h_field_next[index] = Cell(h, 0);
+}
+
+
+@compute @workgroup_size(tile_x, tile_y, tile_z)
+fn update_e(input: Input) {
+    let extent = tile_extent();
+    let origin = vec3i(input.workgroup_id * tile_size()) - vec3i(halo());
+
+    // stage the H field that was just updated
+    let num_tile_cells = extent.x * extent.y * extent.z;
+    let num_invocations = tile_x * tile_y * tile_z;
+    for (var i = 0u; i < num_tile_cells; i += num_invocations) {
+        let tile_index = i + input.local_index;
+        if tile_index < num_tile_cells {
+            let point = origin + vec3i(tile_index_to_point(tile_index, extent));
+            var value = vec3f(0.0);
+            if in_lattice(point) {
+                value = h_field_next[point_to_index(vec3u(point))].value;
+            }
+            tile[tile_index] = value;
+        }
+    }
+    workgroupBarrier();
+
+    // calculate point we're operating on
+    let x = input.workgroup_id * tile_size() + input.local_id;
+
+    // check if our worker is outside of lattice
+    if any(x >= config.size.xyz) {
+        return;
+    }
+
+    let index = point_to_index(x);
+    let tile_index = tile_point_to_index(input.local_id + halo(), extent);
+
+    // calculate curl
+    let dhdx = forward_difference(tile_index, x, extent, 0);
+    let dhdy = forward_difference(tile_index, x, extent, 1);
+    let dhdz = forward_difference(tile_index, x, extent, 2);
+    let h_curl = curl(dhdx, dhdy, dhdz);
+
+    // material coefficients: C_a, C_b
+    let coeff = materials[index].xy;
+
+    // source
+    var j_source: vec3f;
+    let source_id = e_field_next[index].source_id;
+    if source_id != 0 {
+        j_source = sources[source_id].j_source;
+    }
+
+    // todo: pml
+    let psi = vec3f(0.0);
+
+    // update rule
+    let e = coeff.x * e_field_prev[index].value + coeff.y * (h_curl - j_source + psi);
+    e_field_next[index] = Cell(e, 0);
+}
+
+fn curl(dfdx: vec3f, dfdy: vec3f, dfdz: vec3f) -> vec3f {
+    return vec3f(
+        dfdy.z - dfdz.y,
+        dfdz.x - dfdx.z,
+        dfdx.y - dfdy.x,
+    );
+}
+
+// derivative of the E field in the tile, see `dedi` in `update.wgsl`
+fn backward_difference(tile_index: u32, x: vec3u, extent: vec3u, axis: u32) -> vec3f {
+    if x[axis] > 0 {
+        let stride = tile_strides(extent)[axis];
+        let e1 = tile[tile_index - stride];
+        let e2 = tile[tile_index];
+
+        // the wide stencil falls back to 2nd order next to the boundary
+        if spatial_order == 4 && x[axis] > 1 && x[axis] + 1 < config.size[axis] {
+            let e0 = tile[tile_index - 2 * stride];
+            let e3 = tile[tile_index + stride];
+            return (c_inner * (e2 - e1) + c_outer * (e3 - e0)) / config.resolution[axis];
+        }
+
+        return (e2 - e1) / config.resolution[axis];
+    }
+    else {
+        // boundary condition
+        return vec3f(0.0);
+    }
+}
+
+// derivative of the H field in the tile, see `dhdi` in `update.wgsl`
+fn forward_difference(tile_index: u32, x: vec3u, extent: vec3u, axis: u32) -> vec3f {
+    if x[axis] + 1 < config.size[axis] {
+        let stride = tile_strides(extent)[axis];
+        let h1 = tile[tile_index];
+        let h2 = tile[tile_index + stride];
+
+        // the wide stencil falls back to 2nd order next to the boundary
+        if spatial_order == 4 && x[axis] > 0 && x[axis] + 2 < config.size[axis] {
+            let h0 = tile[tile_index - stride];
+            let h3 = tile[tile_index + 2 * stride];
+            return (c_inner * (h2 - h1) + c_outer * (h3 - h0)) / config.resolution[axis];
+        }
+
+        return (h2 - h1) / config.resolution[axis];
+    }
+    else {
+        // boundary condition
+        return vec3f(0.0);
+    }
+}
+
+fn tile_size() -> vec3u {
+    return vec3u(tile_x, tile_y, tile_z);
+}
+
+fn halo() -> vec3u {
+    return vec3u(halo_x, halo_y, halo_z);
+}
+
+fn tile_extent() -> vec3u {
+    return tile_size() + 2 * halo();
+}
+
+fn tile_strides(extent: vec3u) -> vec3u {
+    return vec3u(1, extent.x, extent.x * extent.y);
+}
+
+fn tile_index_to_point(tile_index: u32, extent: vec3u) -> vec3u {
+    return vec3u(
+        tile_index % extent.x,
+        (tile_index / extent.x) % extent.y,
+        tile_index / (extent.x * extent.y),
+    );
+}
+
+fn tile_point_to_index(point: vec3u, extent: vec3u) -> u32 {
+    return dot(point, tile_strides(extent));
+}
+
+fn in_lattice(point: vec3i) -> bool {
+    return all(point >= vec3i(0)) && all(vec3u(point) < config.size.xyz);
+}
+
+fn point_to_index(point: vec3u) -> u32 {
+    return dot(point, config.strides.xyz);
+}
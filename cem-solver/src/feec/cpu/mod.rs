@@ -128,28 +128,3 @@ fn generate_test_mesh() -> HalfEdgeMesh<Vertex, Edge, Face> {
         },
     )
 }
-
-/*
-fn load_obj_file(path: impl AsRef<Path>) -> Result<Simulation, tobj::Error> {
-    let (models, _) = tobj::load_obj(path.as_ref(), &Default::default())?;
-    let model = models.into_iter().next().ok_or_eyre("no models")?;
-    tracing::debug!("loading mesh: {}", model.name);
-    assert!(model.mesh.face_arities.is_empty());
-
-    Ok(Simulation::from_tri_mesh(
-        model
-            .mesh
-            .indices
-            .chunks_exact(3)
-            .map(|indices| indices.try_into().unwrap()),
-        |index| {
-            Point3::new(
-                model.mesh.positions[index as usize * 3],
-                model.mesh.positions[index as usize * 3 + 1],
-                model.mesh.positions[index as usize * 3 + 2],
-            )
-            .cast()
-        },
-    ))
-}
-*/
@@ -1,14 +1,94 @@
+//! Electromagnetic field solvers.
+//!
+//! A solver is set up with a [`SolverBackend`], which creates a
+//! [`SolverInstance`] for a config and a [`DomainDescription`] that assigns the
+//! materials. The instance creates states, and steps them with
+//! [`UpdatePass`]es, to which sources are added. The fields are read with
+//! [`Field`], or projected into images (see [`project`]).
+//!
+//! The FDTD solver has a CPU backend (see [`fdtd::cpu`]) and, with the `wgpu`
+//! feature, a GPU backend (see `fdtd::wgpu`). Both are driven the same way:
+//!
+//! ```
+//! use cem_solver::{
+//!     Field,
+//!     FieldComponent,
+//!     FieldView,
+//!     SolverBackend,
+//!     SolverInstance,
+//!     Time,
+//!     UpdatePass,
+//!     UpdatePassForcing,
+//!     fdtd::{
+//!         FdtdSolverConfig,
+//!         Resolution,
+//!         cpu::FdtdCpuBackend,
+//!     },
+//!     material::{
+//!         Material,
+//!         PhysicalConstants,
+//!     },
+//!     source::SourceValues,
+//! };
+//! use nalgebra::{
+//!     Point3,
+//!     Vector3,
+//! };
+//!
+//! let config = FdtdSolverConfig::new(
+//!     Vector3::new(16.0, 16.0, 16.0),
+//!     Resolution {
+//!         spatial: Vector3::repeat(1.0),
+//!         temporal: 0.25,
+//!     },
+//! )
+//! .with_physical_constants(PhysicalConstants::REDUCED);
+//!
+//! let backend = FdtdCpuBackend::single_threaded();
+//! let instance = backend.create_instance(&config, |_: &Point3<usize>| Material::VACUUM)?;
+//! let mut state = instance.create_state();
+//!
+//! // drive a current at the center for a few steps
+//! let center = Point3::new(8, 8, 8);
+//! for _ in 0..10 {
+//!     let mut update_pass = instance.begin_update(&mut state);
+//!     update_pass.set_forcing(
+//!         &center,
+//!         &SourceValues {
+//!             j: Vector3::z(),
+//!             m: Vector3::zeros(),
+//!         },
+//!     );
+//!     update_pass.finish();
+//! }
+//! assert_eq!(state.tick(), 10);
+//!
+//! let e = instance.field(&state, .., FieldComponent::E);
+//! assert!(e.at(&center).unwrap().norm() > 0.0);
+//! # Ok::<(), cem_solver::CreateInstanceError>(())
+//! ```
+
 #![warn(clippy::todo, unused_qualifications)]
 
 pub mod fdtd;
 pub mod feec;
 pub mod material;
 pub mod project;
+#[cfg(feature = "registry")]
+pub mod registry;
 pub mod source;
+pub mod step;
 
 use std::{
     fmt::Debug,
-    ops::RangeBounds,
+    ops::{
+        ControlFlow,
+        RangeBounds,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
 use nalgebra::Vector3;
@@ -19,6 +99,11 @@ use crate::{
     fdtd::pml::PmlCoefficients,
     material::Material,
     source::SourceValues,
+    step::{
+        CancellationToken,
+        SliceEnd,
+        StepSlice,
+    },
 };
 
 /// TODO: Reconcile the use of a config and domain description. Should they be
@@ -28,7 +113,7 @@ use crate::{
 pub trait SolverBackend<Config, Point> {
     type Instance: SolverInstance;
 
-    type Error: std::error::Error;
+    type Error: std::error::Error + Send + Sync + 'static;
 
     fn create_instance<D>(
         &self,
@@ -66,6 +151,29 @@ pub trait DomainDescription<P> {
     }
 }
 
+/// Closures assign the materials, without PML.
+impl<P, F> DomainDescription<P> for F
+where
+    F: FnMut(&P) -> Material,
+{
+    fn material(&mut self, point: &P) -> Material {
+        self(point)
+    }
+}
+
+/// Errors from [`SolverBackend::create_instance`].
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum CreateInstanceError {
+    #[error("Invalid resolution: {0}")]
+    InvalidResolution(String),
+
+    #[error("Invalid domain size: {0}")]
+    InvalidSize(String),
+
+    #[error("The simulation doesn't fit into the device's limits")]
+    ExceedsDeviceLimits,
+}
+
 /// todo: needs methods for converting from/to solver coordinates
 pub trait SolverInstance {
     type State: Time;
@@ -76,6 +184,59 @@ pub trait SolverInstance {
     fn create_state(&self) -> Self::State;
 
     fn begin_update<'a>(&'a self, state: &'a mut Self::State) -> Self::UpdatePass<'a>;
+
+    /// Calls `step` until `time_slice` has elapsed, `cancellation` is
+    /// cancelled, or `step` returns [`ControlFlow::Break`].
+    ///
+    /// `step` does a single step, e.g. it sets the sources in an update pass
+    /// and reads probes afterwards. It's called at least once, unless
+    /// `cancellation` was already cancelled. See [`step`](crate::step).
+    fn step_for<F>(
+        &self,
+        state: &mut Self::State,
+        time_slice: Duration,
+        cancellation: &CancellationToken,
+        mut step: F,
+    ) -> StepSlice
+    where
+        F: FnMut(&Self, &mut Self::State) -> ControlFlow<()>,
+    {
+        let start = Instant::now();
+        let mut steps = 0;
+
+        let end = loop {
+            if cancellation.is_cancelled() {
+                break SliceEnd::Cancelled;
+            }
+            if steps > 0 && start.elapsed() >= time_slice {
+                break SliceEnd::Elapsed;
+            }
+
+            steps += 1;
+            if step(self, state).is_break() {
+                break SliceEnd::Stopped;
+            }
+        };
+
+        StepSlice {
+            steps,
+            elapsed: start.elapsed(),
+            end,
+        }
+    }
+}
+
+/// Trait for [`SolverInstance`]s that can copy their state into another state
+/// created by the same instance.
+///
+/// This is used to take snapshots of the fields, so that they can be
+/// projected while the solver keeps stepping.
+pub trait CopyState: SolverInstance {
+    /// Copies the fields and time of `source` into `target`.
+    ///
+    /// This doesn't have to copy anything that is only needed to step the
+    /// solver (e.g. PML auxiliary fields).
+    fn copy_state(&self, source: &Self::State, target: &mut Self::State);
 }
 
 pub trait UpdatePass
@@ -136,6 +297,7 @@ pub trait FieldView<P> {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FieldComponent {
     E,
     H,
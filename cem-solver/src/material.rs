@@ -1,9 +1,14 @@
-use std::fmt::Debug;
+use std::{
+    f64::consts::TAU,
+    fmt::Debug,
+};
 
 #[cfg(feature = "bevy_ecs")]
 use bevy_ecs::reflect::ReflectComponent;
 #[cfg(all(feature = "serde", feature = "bevy_ecs"))]
 use bevy_reflect::ReflectSerialize;
+#[cfg(feature = "bevy_ecs")]
+use bevy_reflect::prelude::ReflectDefault;
 #[cfg(feature = "probe")]
 use cem_probe::{
     PropertiesUi,
@@ -61,6 +66,166 @@ impl PhysicalConstants {
     pub fn wavelength_to_frequency(&self, wavelength: f64) -> f64 {
         self.speed_of_light() / wavelength
     }
+
+    /// Wave impedance of free space
+    pub fn vacuum_impedance(&self) -> f64 {
+        (self.vacuum_permeability / self.vacuum_permittivity).sqrt()
+    }
+}
+
+/// Named presets for the physical constants a scene is modelled in.
+///
+/// Lengths are always in meters. [`UnitSystem::Normalized`] sets
+/// `eps_0 = mu_0 = 1`, so time is measured in the time light takes to travel
+/// one meter, and the electric and magnetic fields have the same unit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnitSystem {
+    #[default]
+    Si,
+    Normalized,
+}
+
+impl UnitSystem {
+    pub const ALL: [Self; 2] = [Self::Si, Self::Normalized];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            UnitSystem::Si => "SI",
+            UnitSystem::Normalized => "Normalized (c0 = 1)",
+        }
+    }
+
+    pub fn physical_constants(&self) -> PhysicalConstants {
+        match self {
+            UnitSystem::Si => PhysicalConstants::SI,
+            UnitSystem::Normalized => PhysicalConstants::REDUCED,
+        }
+    }
+
+    pub fn time_unit(&self) -> &'static str {
+        match self {
+            UnitSystem::Si => "s",
+            UnitSystem::Normalized => "m/c0",
+        }
+    }
+
+    pub fn frequency_unit(&self) -> &'static str {
+        match self {
+            UnitSystem::Si => "Hz",
+            UnitSystem::Normalized => "c0/m",
+        }
+    }
+
+    pub fn electrical_conductivity_unit(&self) -> &'static str {
+        match self {
+            UnitSystem::Si => "S/m",
+            UnitSystem::Normalized => "1/m",
+        }
+    }
+
+    pub fn magnetic_conductivity_unit(&self) -> &'static str {
+        match self {
+            UnitSystem::Si => "Ω/m",
+            UnitSystem::Normalized => "1/m",
+        }
+    }
+
+    /// Factors to convert quantities from this unit system into `other`.
+    pub fn conversion_to(&self, other: &Self) -> UnitConversion {
+        self.to_si().then(&other.to_si().inverse())
+    }
+
+    fn to_si(self) -> UnitConversion {
+        match self {
+            UnitSystem::Si => UnitConversion::IDENTITY,
+            UnitSystem::Normalized => {
+                let si = PhysicalConstants::SI;
+                let c = si.speed_of_light();
+                let z = si.vacuum_impedance();
+                UnitConversion {
+                    time: 1.0 / c,
+                    magnetic_field: 1.0 / z,
+                    electric_current_density: 1.0 / z,
+                    magnetic_current_density: 1.0,
+                    electrical_conductivity: 1.0 / z,
+                    magnetic_conductivity: z,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "probe")]
+impl UnitSystem {
+    /// Makes this the unit system used to display quantities in the UI.
+    pub fn set_display_units(&self, ctx: &egui::Context) {
+        ctx.data_mut(|data| data.insert_temp(Self::display_units_id(), *self));
+    }
+
+    /// The unit system set with [`set_display_units`][Self::set_display_units].
+    pub fn display_units(ctx: &egui::Context) -> Self {
+        ctx.data(|data| data.get_temp(Self::display_units_id()))
+            .unwrap_or_default()
+    }
+
+    fn display_units_id() -> egui::Id {
+        egui::Id::new("display_unit_system")
+    }
+}
+
+/// Multiplicative factors to convert quantities between [`UnitSystem`]s.
+///
+/// Lengths, electric fields and the relative material parameters are the same
+/// in all unit systems. Frequencies scale with the inverse of `time`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UnitConversion {
+    pub time: f64,
+    pub magnetic_field: f64,
+    pub electric_current_density: f64,
+    pub magnetic_current_density: f64,
+    pub electrical_conductivity: f64,
+    pub magnetic_conductivity: f64,
+}
+
+impl UnitConversion {
+    pub const IDENTITY: Self = Self {
+        time: 1.0,
+        magnetic_field: 1.0,
+        electric_current_density: 1.0,
+        magnetic_current_density: 1.0,
+        electrical_conductivity: 1.0,
+        magnetic_conductivity: 1.0,
+    };
+
+    pub fn frequency(&self) -> f64 {
+        1.0 / self.time
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            time: 1.0 / self.time,
+            magnetic_field: 1.0 / self.magnetic_field,
+            electric_current_density: 1.0 / self.electric_current_density,
+            magnetic_current_density: 1.0 / self.magnetic_current_density,
+            electrical_conductivity: 1.0 / self.electrical_conductivity,
+            magnetic_conductivity: 1.0 / self.magnetic_conductivity,
+        }
+    }
+
+    /// Conversion that first applies `self` and then `other`.
+    pub fn then(&self, other: &Self) -> Self {
+        Self {
+            time: self.time * other.time,
+            magnetic_field: self.magnetic_field * other.magnetic_field,
+            electric_current_density: self.electric_current_density
+                * other.electric_current_density,
+            magnetic_current_density: self.magnetic_current_density
+                * other.magnetic_current_density,
+            electrical_conductivity: self.electrical_conductivity * other.electrical_conductivity,
+            magnetic_conductivity: self.magnetic_conductivity * other.magnetic_conductivity,
+        }
+    }
 }
 
 #[cfg(feature = "probe")]
@@ -112,6 +277,12 @@ pub struct Material {
     pub relative_permittivity: f64,
     /// sigma
     pub eletrical_conductivity: f64,
+
+    /// Set if the dielectric loss was entered as a loss tangent at this
+    /// frequency. The solver only uses the conductivity, see
+    /// [`Material::loss_tangent`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub loss_tangent_frequency: Option<f64>,
 }
 
 impl Material {
@@ -120,7 +291,61 @@ impl Material {
         magnetic_conductivity: 0.0,
         relative_permittivity: 1.0,
         eletrical_conductivity: 0.0,
+        loss_tangent_frequency: None,
     };
+
+    /// Perfect electric conductor.
+    ///
+    /// The electric field is held at zero in cells with this material.
+    pub const PEC: Self = Self {
+        eletrical_conductivity: f64::INFINITY,
+        ..Self::VACUUM
+    };
+
+    pub fn is_perfect_electric_conductor(&self) -> bool {
+        self.eletrical_conductivity == f64::INFINITY
+    }
+
+    /// Loss tangent `tan δ = σ / (ω ε)` at `frequency`.
+    ///
+    /// Dielectric loss is modelled by the electrical conductivity, so the loss
+    /// tangent falls off with `1/f` and is only exact at the frequency it was
+    /// specified at.
+    pub fn loss_tangent(&self, frequency: f64, physical_constants: &PhysicalConstants) -> f64 {
+        self.eletrical_conductivity / (TAU * frequency * self.permittivity(physical_constants))
+    }
+
+    /// Sets the electrical conductivity, such that the material has
+    /// `loss_tangent` at `frequency`.
+    pub fn set_loss_tangent(
+        &mut self,
+        loss_tangent: f64,
+        frequency: f64,
+        physical_constants: &PhysicalConstants,
+    ) {
+        self.eletrical_conductivity =
+            loss_tangent * TAU * frequency * self.permittivity(physical_constants);
+        self.loss_tangent_frequency = Some(frequency);
+    }
+
+    fn permittivity(&self, physical_constants: &PhysicalConstants) -> f64 {
+        self.relative_permittivity * physical_constants.vacuum_permittivity
+    }
+}
+
+impl Material {
+    pub fn convert_units(&self, conversion: &UnitConversion) -> Self {
+        Self {
+            relative_permeability: self.relative_permeability,
+            magnetic_conductivity: self.magnetic_conductivity * conversion.magnetic_conductivity,
+            relative_permittivity: self.relative_permittivity,
+            eletrical_conductivity: self.eletrical_conductivity
+                * conversion.electrical_conductivity,
+            loss_tangent_frequency: self
+                .loss_tangent_frequency
+                .map(|frequency| frequency / conversion.time),
+        }
+    }
 }
 
 impl Default for Material {
@@ -137,6 +362,14 @@ impl PropertiesUi for Material {
         let _ = config;
         let mut changes = TrackChanges::default();
 
+        let units = UnitSystem::display_units(ui.ctx());
+        let physical_constants = units.physical_constants();
+
+        // a loss tangent is kept when the permittivity or its frequency change
+        let loss_tangent = self
+            .loss_tangent_frequency
+            .map(|frequency| self.loss_tangent(frequency, &physical_constants));
+
         let response = egui::Frame::new()
             .show(ui, |ui| {
                 label_and_value(
@@ -147,25 +380,177 @@ impl PropertiesUi for Material {
                 );
                 label_and_value(
                     ui,
-                    "Magnetic Conductivity",
+                    &format!(
+                        "Magnetic Conductivity [{}]",
+                        units.magnetic_conductivity_unit()
+                    ),
                     &mut changes,
                     &mut self.magnetic_conductivity,
                 );
-                label_and_value(
+                let permittivity_changed = label_and_value(
                     ui,
                     "Relative Permittivity",
                     &mut changes,
                     &mut self.relative_permittivity,
-                );
-                label_and_value(
+                )
+                .changed();
+                let conductivity_changed = label_and_value(
                     ui,
-                    "Electrical Conductivity",
+                    &format!(
+                        "Electrical Conductivity [{}]",
+                        units.electrical_conductivity_unit()
+                    ),
                     &mut changes,
                     &mut self.eletrical_conductivity,
-                );
+                )
+                .changed();
+
+                let mut specify_loss_tangent = self.loss_tangent_frequency.is_some();
+                changes.track(ui.checkbox(&mut specify_loss_tangent, "Loss Tangent"));
+
+                if !specify_loss_tangent {
+                    self.loss_tangent_frequency = None;
+                }
+                else {
+                    // 1 GHz, unless the frequency was set before
+                    let mut frequency = self
+                        .loss_tangent_frequency
+                        .unwrap_or_else(|| 1e9 / UnitSystem::Si.conversion_to(&units).time);
+                    let mut loss_tangent = loss_tangent
+                        .filter(|_| !conductivity_changed)
+                        .unwrap_or_else(|| self.loss_tangent(frequency, &physical_constants));
+
+                    let loss_tangent_changed = ui
+                        .horizontal(|ui| {
+                            ui.label("tan δ");
+                            changes.track(
+                                ui.add(
+                                    egui::DragValue::new(&mut loss_tangent)
+                                        .range(0.0..=f64::INFINITY)
+                                        .speed(1e-4),
+                                ),
+                            )
+                        })
+                        .inner
+                        .changed();
+                    let speed = frequency * 0.01;
+                    let frequency_changed = ui
+                        .horizontal(|ui| {
+                            ui.label("at");
+                            changes.track(
+                                ui.add(
+                                    egui::DragValue::new(&mut frequency)
+                                        .range(f64::MIN_POSITIVE..=f64::INFINITY)
+                                        .speed(speed)
+                                        .suffix(format!(" {}", units.frequency_unit())),
+                                ),
+                            )
+                        })
+                        .inner
+                        .changed();
+
+                    if conductivity_changed {
+                        self.loss_tangent_frequency = Some(frequency);
+                    }
+                    else if permittivity_changed
+                        || loss_tangent_changed
+                        || frequency_changed
+                        || self.loss_tangent_frequency.is_none()
+                    {
+                        self.set_loss_tangent(loss_tangent, frequency, &physical_constants);
+                    }
+                }
             })
             .response;
 
         changes.propagated(response)
     }
 }
+
+/// Precedence of an object's [`Material`] where it overlaps other objects.
+///
+/// Overlaps are resolved by the highest priority first, then by the smallest
+/// bounding box (so objects embedded in others win), and finally by entity
+/// order. Objects without this component have priority 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "bevy_ecs",
+    derive(bevy_ecs::component::Component, bevy_reflect::Reflect),
+    reflect(Component, Default)
+)]
+#[cfg_attr(all(feature = "probe", feature = "bevy_ecs"), reflect(ComponentUi, @ComponentName::new("Material Priority")))]
+#[cfg_attr(all(feature = "serde", feature = "bevy_ecs"), reflect(Serialize))]
+pub struct MaterialPriority(pub i32);
+
+#[cfg(feature = "probe")]
+impl PropertiesUi for MaterialPriority {
+    type Config = ();
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, config: &Self::Config) -> egui::Response {
+        let _ = config;
+        let mut changes = TrackChanges::default();
+
+        let response = egui::Frame::new()
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Priority");
+                    changes.track(ui.add(egui::DragValue::new(&mut self.0)));
+                })
+                .response
+                .on_hover_text("Higher priorities win where objects overlap.");
+            })
+            .response;
+
+        changes.propagated(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::{
+        Material,
+        PhysicalConstants,
+        UnitSystem,
+    };
+
+    #[test]
+    fn it_converts_between_unit_systems() {
+        let conversion = UnitSystem::Si.conversion_to(&UnitSystem::Normalized);
+        let c = PhysicalConstants::SI.speed_of_light();
+        assert!((conversion.time / c - 1.0).abs() < 1e-12);
+
+        let material = Material {
+            eletrical_conductivity: 5.8e7,
+            magnetic_conductivity: 1.0,
+            ..Material::VACUUM
+        };
+        let back = material
+            .convert_units(&conversion)
+            .convert_units(&UnitSystem::Normalized.conversion_to(&UnitSystem::Si));
+        assert!(
+            (back.eletrical_conductivity / material.eletrical_conductivity - 1.0).abs() < 1e-12
+        );
+        assert!((back.magnetic_conductivity / material.magnetic_conductivity - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_keeps_the_loss_tangent_in_other_unit_systems() {
+        let mut material = Material {
+            relative_permittivity: 4.4,
+            ..Material::VACUUM
+        };
+        material.set_loss_tangent(0.02, 1e9, &PhysicalConstants::SI);
+        assert!((material.loss_tangent(1e9, &PhysicalConstants::SI) - 0.02).abs() < 1e-15);
+
+        // FR-4 at 1 GHz
+        assert!((material.eletrical_conductivity - 4.895e-3).abs() < 1e-6);
+
+        let normalized =
+            material.convert_units(&UnitSystem::Si.conversion_to(&UnitSystem::Normalized));
+        let frequency = normalized.loss_tangent_frequency.unwrap();
+        assert!(
+            (normalized.loss_tangent(frequency, &PhysicalConstants::REDUCED) - 0.02).abs() < 1e-12
+        );
+    }
+}
@@ -3,15 +3,15 @@
 //! For now this handles only projections from the 3D volume of a simulation
 //! domain into a 2D planar surface. The projection is mainly defined by
 //! [`ProjectionParameters`], but additional machinery is required to allow
-//! projections into different targets (such as [`wgpu::Texture`] and
+//! projections into different targets (such as `wgpu::Texture` and
 //! [`image::ImageBuffer`]) using different backends (e.g. a wgpu backend
 //! requires a lot of preparation).
 //!
 //! A rough outline on how to project an image:
 //!
 //! 1. Choose a target:
-//!  - [`wgpu::Texture`] wrapped in a [`TextureTarget`].
-//!  - [`ImageTarget`]: trait implemented for anything that provide a
+//!  - `wgpu::Texture` or `wgpu::TextureView`, with the `wgpu` feature.
+//!  - [`FdtdImageTarget`]: trait implemented for anything that provide a
 //!    [`image::ImageBuffer`] to render to.
 //! 2. Create projection with one of the implementations of
 //!    [`CreateProjection::create_projection`] on a [`SolverInstance`].
@@ -21,7 +21,62 @@
 //! 4. Put all your projections into the pass using
 //!    [`ProjectionPassAdd::add_projection`]. This is implemented on the
 //!    projection pass you've created, if the backend supports the target.
-//! 5. Finish projections using [`ProjectionPassFinish::finish`]
+//! 5. Finish projections using [`ProjectionPass::finish`]
+//!
+//! E.g. to project a slice through the middle of a CPU simulation into an
+//! image:
+//!
+//! ```
+//! use cem_solver::{
+//!     FieldComponent,
+//!     SolverBackend,
+//!     SolverInstance,
+//!     fdtd::{
+//!         FdtdSolverConfig,
+//!         Resolution,
+//!         cpu::FdtdCpuBackend,
+//!     },
+//!     material::Material,
+//!     project::{
+//!         BeginProjectionPass,
+//!         CreateProjection,
+//!         ProjectionParameters,
+//!         ProjectionPass,
+//!         ProjectionPassAdd,
+//!     },
+//! };
+//! use nalgebra::{
+//!     Matrix4,
+//!     Point3,
+//!     Vector3,
+//! };
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let config = FdtdSolverConfig::new(
+//!     Vector3::repeat(1e-2),
+//!     Resolution {
+//!         spatial: Vector3::repeat(1e-3),
+//!         temporal: 1e-12,
+//!     },
+//! );
+//! let instance = FdtdCpuBackend::single_threaded()
+//!     .create_instance(&config, |_: &Point3<usize>| Material::VACUUM)?;
+//! let state = instance.create_state();
+//!
+//! // the image plane is mapped onto the lattice, which spans [0, 1]^3
+//! let parameters = ProjectionParameters::new(
+//!     Matrix4::new_translation(&Vector3::new(0.0, 0.0, 0.5)),
+//!     FieldComponent::E,
+//! );
+//! let mut image = image::RgbaImage::new(32, 32);
+//! let mut projection = instance.create_projection(&state, &mut image, &parameters);
+//!
+//! let mut projection_pass = instance.begin_projection_pass(&state);
+//! projection_pass.add_projection(&mut projection);
+//! projection_pass.finish()?;
+//! # Ok(())
+//! # }
+//! ```
 
 use std::{
     convert::Infallible,
@@ -65,6 +120,83 @@ pub struct ProjectionParameters {
     // at the moment this is just wgsl source code
     // todo: this should be some proper type
     pub color_map_code: Option<String>,
+
+    /// User-provided WGSL that transforms the sampled field vector before it is
+    /// color-mapped.
+    ///
+    /// This is the body of the function
+    /// `fn post_process(value: vec3f, point: vec3u) -> vec3f`, where `point`
+    /// is the lattice point that was sampled. Check snippets with
+    /// [`validate_post_process_code`] before passing them here. Backends that
+    /// can't compile WGSL ignore it.
+    pub post_process_code: Option<String>,
+}
+
+impl ProjectionParameters {
+    /// Projects the `field` through the image plane transformed by
+    /// `projection`, with the field vector as color.
+    pub fn new(projection: Matrix4<f32>, field: FieldComponent) -> Self {
+        Self {
+            projection,
+            field,
+            color_map: Matrix4::identity(),
+            color_map_code: None,
+            post_process_code: None,
+        }
+    }
+
+    pub fn with_color_map(mut self, color_map: Matrix4<f32>) -> Self {
+        self.color_map = color_map;
+        self
+    }
+}
+
+/// Signature of the function that [`ProjectionParameters::post_process_code`]
+/// is templated into.
+pub const POST_PROCESS_SIGNATURE: &str = "fn post_process(value: vec3f, point: vec3u) -> vec3f";
+
+/// Checks that a post-processing snippet can be templated into the projection
+/// shader as a function body.
+///
+/// This only catches snippets that would escape the function body or declare
+/// their own bindings. Anything else is reported by the shader compiler when
+/// the projection is created.
+pub fn validate_post_process_code(code: &str) -> Result<(), PostProcessCodeError> {
+    if code.contains('@') {
+        return Err(PostProcessCodeError::Attribute);
+    }
+
+    let mut depth = 0usize;
+    for c in code.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or(PostProcessCodeError::UnbalancedBraces)?
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err(PostProcessCodeError::UnbalancedBraces);
+    }
+
+    if !code.contains("return") {
+        return Err(PostProcessCodeError::MissingReturn);
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum PostProcessCodeError {
+    #[error("Post-processing code must not contain attributes")]
+    Attribute,
+    #[error("Post-processing code has unbalanced braces")]
+    UnbalancedBraces,
+    #[error("Post-processing code must return a vec3f")]
+    MissingReturn,
 }
 
 /// Trait for [`SolverInstance`]s that can create projections to a specific
@@ -98,7 +230,7 @@ pub trait BeginProjectionPass: SolverInstance {
     /// The projection pass which can be used to add projections to it.
     ///
     /// After all projections have been added, call
-    /// [`ProjectionPassFinish::finish`].
+    /// [`ProjectionPass::finish`].
     type ProjectionPass<'a>: ProjectionPass
     where
         Self: 'a;
@@ -124,6 +256,14 @@ pub trait ProjectionPass {
     fn finish(self) -> Result<(), Self::Error>;
 }
 
+/// Trait for projections whose transform can be changed after they've been
+/// created, e.g. to move a slice through the simulation domain.
+pub trait SetProjectionTransform {
+    /// Replaces [`ProjectionParameters::projection`]. The new transform is used
+    /// from the next projection pass on.
+    fn set_projection_transform(&mut self, projection: Matrix4<f32>);
+}
+
 /// Trait for projection passes that can accept `Projection`s to be added.
 ///
 /// If a [`SolverInstance`] allows for a projection to be created, it must also
@@ -0,0 +1,181 @@
+//! Solver backends that are registered at runtime.
+//!
+//! Applications have their own config types for the built-in solvers. Other
+//! backends (e.g. FDFD or ray-tracing) implement [`CustomSolverBackend`] and
+//! are registered in a [`SolverRegistry`]. Their configs are stored as
+//! [`SolverConfigCustom`], which refers to the backend by name and keeps the
+//! backend-specific part as an opaque value that the backend parses when it's
+//! run.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    sync::Arc,
+};
+
+use cem_scene::{
+    Scene,
+    spatial::Aabb,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+    de::DeserializeOwned,
+};
+
+use crate::material::Material;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SolverConfigCustom {
+    /// Name of the backend, see [`CustomSolverBackend::name`].
+    pub backend: String,
+
+    /// Backend-specific config, parsed by the backend with
+    /// [`CustomSolverContext::parse_config`].
+    #[serde(default)]
+    pub config: serde_json::Value,
+}
+
+pub trait CustomSolverBackend: Debug + Send + Sync + 'static {
+    /// Name the backend is registered with and referred to by configs.
+    fn name(&self) -> &str;
+
+    /// Backend-specific config for newly created solver configs.
+    fn default_config(&self) -> serde_json::Value {
+        serde_json::Value::Null
+    }
+
+    /// Sets up a solver for the scene.
+    ///
+    /// This is called on the UI thread. The returned instance is then stepped
+    /// on a solver thread.
+    fn create_instance(
+        &self,
+        context: CustomSolverContext<'_>,
+    ) -> Result<Box<dyn CustomSolverInstance>, CustomSolverError>;
+}
+
+/// Everything a [`CustomSolverBackend`] gets to set up a solver.
+#[derive(Debug)]
+pub struct CustomSolverContext<'a> {
+    pub scene: &'a mut Scene,
+
+    /// Part of the scene that is simulated.
+    pub volume: Aabb,
+
+    /// Material wherever the scene doesn't specify one.
+    pub default_material: Material,
+
+    pub config: &'a serde_json::Value,
+}
+
+impl<'a> CustomSolverContext<'a> {
+    pub fn parse_config<T>(&self) -> Result<T, CustomSolverError>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(serde_json::from_value(self.config.clone())?)
+    }
+}
+
+pub trait CustomSolverInstance: Send + 'static {
+    /// Advances the solver by one step.
+    ///
+    /// Returns `false` once the solver is done.
+    fn step(&mut self) -> Result<bool, CustomSolverError>;
+
+    fn tick(&self) -> usize;
+
+    fn time(&self) -> f64;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CustomSolverError {
+    #[error("Invalid solver config")]
+    InvalidConfig(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Backend(#[from] Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+/// Solver backends by name.
+#[derive(Clone, Debug, Default)]
+pub struct SolverRegistry {
+    backends: BTreeMap<String, Arc<dyn CustomSolverBackend>>,
+}
+
+impl SolverRegistry {
+    /// Registers a backend, replacing any backend that was registered with the
+    /// same name.
+    pub fn register<B>(&mut self, backend: B) -> Option<Arc<dyn CustomSolverBackend>>
+    where
+        B: CustomSolverBackend,
+    {
+        self.backends
+            .insert(backend.name().to_owned(), Arc::new(backend))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn CustomSolverBackend>> {
+        self.backends.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn CustomSolverBackend>> {
+        self.backends.values()
+    }
+
+    /// Creates a config for the named backend with its default settings.
+    pub fn default_config(&self, name: &str) -> Option<SolverConfigCustom> {
+        self.get(name).map(|backend| {
+            SolverConfigCustom {
+                backend: backend.name().to_owned(),
+                config: backend.default_config(),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::registry::{
+        CustomSolverBackend,
+        CustomSolverContext,
+        CustomSolverError,
+        CustomSolverInstance,
+        SolverRegistry,
+    };
+
+    #[derive(Debug)]
+    struct Backend {
+        default_steps: u64,
+    }
+
+    impl CustomSolverBackend for Backend {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        fn default_config(&self) -> serde_json::Value {
+            self.default_steps.into()
+        }
+
+        fn create_instance(
+            &self,
+            _context: CustomSolverContext<'_>,
+        ) -> Result<Box<dyn CustomSolverInstance>, CustomSolverError> {
+            Err(CustomSolverError::Backend("not runnable".into()))
+        }
+    }
+
+    #[test]
+    fn backends_are_replaced_by_name() {
+        let mut registry = SolverRegistry::default();
+        assert!(registry.register(Backend { default_steps: 1 }).is_none());
+        assert!(registry.register(Backend { default_steps: 2 }).is_some());
+        assert_eq!(registry.iter().count(), 1);
+
+        let config = registry.default_config("test").unwrap();
+        assert_eq!(config.backend, "test");
+        assert_eq!(config.config, 2);
+        assert!(registry.default_config("other").is_none());
+    }
+}
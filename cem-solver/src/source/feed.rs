@@ -0,0 +1,279 @@
+//! Idealized antenna feeds.
+//!
+//! A [`Feed`] expands into a set of point sources with fixed J/M amplitudes
+//! that are all driven by the same scalar waveform. This way common
+//! excitations can be placed into a scene without modelling the actual feed
+//! geometry.
+
+use std::{
+    f64::consts::{
+        PI,
+        TAU,
+    },
+    sync::Arc,
+};
+
+use nalgebra::{
+    Point3,
+    Vector3,
+};
+
+use crate::{
+    material::{
+        PhysicalConstants,
+        UnitConversion,
+    },
+    source::{
+        SourceFunction,
+        SourceValues,
+        TimeScaled,
+    },
+};
+
+/// Loops whose circumference resolves into fewer cells than this are replaced
+/// by their equivalent magnetic dipole.
+pub const MIN_LOOP_SAMPLES: usize = 8;
+
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FeedKind {
+    /// Electric current element along the local z-axis, centered at the
+    /// origin.
+    ///
+    /// The current is uniform along the element, so this only behaves like a
+    /// Hertzian dipole if `length` is small compared to the wavelength.
+    HertzianDipole { length: f64 },
+
+    /// Current loop in the local xy-plane, centered at the origin.
+    ///
+    /// If the circumference can't be resolved into at least
+    /// [`MIN_LOOP_SAMPLES`] cells, the loop is replaced by a magnetic current
+    /// element along the local z-axis. This is the loop's equivalent magnetic
+    /// dipole, up to a time derivative.
+    MagneticLoop { radius: f64 },
+
+    /// Rectangular aperture in the local xy-plane radiating towards +z.
+    ///
+    /// The aperture field is polarized along y and has a cosine taper along x,
+    /// like the TE10 mode of a rectangular waveguide. It's excited as a
+    /// Huygens source (J and M currents), so it radiates only into the
+    /// forward half-space.
+    CosineAperture { width: f64, height: f64 },
+}
+
+/// A single point source of an expanded [`FeedKind`].
+#[derive(Clone, Copy, Debug)]
+pub struct FeedElement {
+    /// Position in the feed's local frame
+    pub position: Point3<f64>,
+
+    /// Current densities in the feed's local frame
+    pub amplitudes: SourceValues,
+}
+
+impl FeedKind {
+    /// Expands the feed into point sources spaced according to `cell_size`.
+    pub fn expand(
+        &self,
+        cell_size: &Vector3<f64>,
+        physical_constants: &PhysicalConstants,
+    ) -> Vec<FeedElement> {
+        match self {
+            FeedKind::HertzianDipole { length } => {
+                samples(*length, cell_size.z)
+                    .map(|z| {
+                        FeedElement {
+                            position: Point3::new(0.0, 0.0, z),
+                            amplitudes: SourceValues {
+                                j: Vector3::z(),
+                                m: Vector3::zeros(),
+                            },
+                        }
+                    })
+                    .collect()
+            }
+            FeedKind::MagneticLoop { radius } => {
+                let step = cell_size.x.min(cell_size.y);
+                let num_samples = (TAU * radius / step).round() as usize;
+
+                if num_samples >= MIN_LOOP_SAMPLES {
+                    (0..num_samples)
+                        .map(|i| {
+                            let phi = TAU * i as f64 / num_samples as f64;
+                            let (sin, cos) = phi.sin_cos();
+                            FeedElement {
+                                position: Point3::new(radius * cos, radius * sin, 0.0),
+                                amplitudes: SourceValues {
+                                    j: Vector3::new(-sin, cos, 0.0),
+                                    m: Vector3::zeros(),
+                                },
+                            }
+                        })
+                        .collect()
+                }
+                else {
+                    vec![FeedElement {
+                        position: Point3::origin(),
+                        amplitudes: SourceValues {
+                            j: Vector3::zeros(),
+                            m: Vector3::z(),
+                        },
+                    }]
+                }
+            }
+            FeedKind::CosineAperture { width, height } => {
+                let impedance = physical_constants.vacuum_impedance();
+
+                // the equivalent currents are surface currents, but we force volume current
+                // densities, so we spread them over one cell.
+                let thickness = cell_size.z;
+
+                let mut elements = vec![];
+                for x in samples(*width, cell_size.x) {
+                    let taper = (PI * x / width).cos();
+
+                    // with the aperture normal n = z, E = y * taper and H = -x * taper / Z_0:
+                    // J = n x H and M = -n x E
+                    let amplitudes = SourceValues {
+                        j: -Vector3::y() * taper / (impedance * thickness),
+                        m: Vector3::x() * taper / thickness,
+                    };
+
+                    for y in samples(*height, cell_size.y) {
+                        elements.push(FeedElement {
+                            position: Point3::new(x, y, 0.0),
+                            amplitudes,
+                        });
+                    }
+                }
+                elements
+            }
+        }
+    }
+}
+
+/// Cell-centered sample positions along an interval of `length` centered at 0.
+///
+/// This yields at least one sample.
+fn samples(length: f64, step: f64) -> impl Iterator<Item = f64> {
+    let n = ((length / step).round() as usize).max(1);
+    (0..n).map(move |i| ((i as f64 + 0.5) / n as f64 - 0.5) * length)
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "bevy_ecs", derive(bevy_ecs::component::Component))]
+pub struct Feed {
+    pub kind: FeedKind,
+    pub waveform: Arc<dyn SourceFunction<Output = f64>>,
+}
+
+impl Feed {
+    pub fn new(kind: FeedKind, waveform: impl SourceFunction<Output = f64>) -> Self {
+        Self {
+            kind,
+            waveform: Arc::new(waveform),
+        }
+    }
+
+    /// Rescales the waveform in time for another unit system.
+    ///
+    /// The current densities of the expanded [`FeedElement`]s are normalized,
+    /// so only the waveform needs to change.
+    pub fn convert_units(&self, conversion: &UnitConversion) -> Self {
+        Self::new(
+            self.kind,
+            TimeScaled {
+                time_scale: conversion.time,
+                inner: self.waveform.clone(),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{
+        Point3,
+        Vector3,
+    };
+
+    use crate::{
+        material::PhysicalConstants,
+        source::feed::FeedKind,
+    };
+
+    const CELL_SIZE: Vector3<f64> = Vector3::new(1.0, 1.0, 1.0);
+
+    #[test]
+    fn short_dipole_is_single_current_element_along_axis() {
+        let elements = FeedKind::HertzianDipole { length: 0.5 }
+            .expand(&CELL_SIZE, &PhysicalConstants::REDUCED);
+        assert_eq!(elements.len(), 1);
+        assert_eq!(elements[0].position, Point3::origin());
+        assert_eq!(elements[0].amplitudes.j, Vector3::z());
+        assert_eq!(elements[0].amplitudes.m, Vector3::zeros());
+
+        // longer dipoles are a line of current elements on the axis
+        let elements = FeedKind::HertzianDipole { length: 5.0 }
+            .expand(&CELL_SIZE, &PhysicalConstants::REDUCED);
+        assert_eq!(elements.len(), 5);
+        for element in elements {
+            assert_eq!(element.position.xy(), Point3::origin().xy());
+            assert_eq!(element.amplitudes.j, Vector3::z());
+        }
+    }
+
+    #[test]
+    fn loop_current_circulates() {
+        let radius = 4.0;
+        let elements =
+            FeedKind::MagneticLoop { radius }.expand(&CELL_SIZE, &PhysicalConstants::REDUCED);
+        assert_eq!(elements.len(), 25);
+
+        let mut net_current = Vector3::zeros();
+        for element in &elements {
+            let radial = element.position.coords;
+            let j = element.amplitudes.j;
+            assert!((radial.norm() - radius).abs() < 1e-12);
+            assert!(radial.dot(&j).abs() < 1e-12, "not tangential");
+            assert!(radial.cross(&j).z > 0.0, "not circulating around +z");
+            assert_eq!(element.amplitudes.m, Vector3::zeros());
+            net_current += j;
+        }
+        assert!(net_current.norm() < 1e-12);
+
+        // a loop that can't be resolved is its magnetic dipole
+        let elements =
+            FeedKind::MagneticLoop { radius: 0.5 }.expand(&CELL_SIZE, &PhysicalConstants::REDUCED);
+        assert_eq!(elements.len(), 1);
+        assert_eq!(elements[0].amplitudes.j, Vector3::zeros());
+        assert_eq!(elements[0].amplitudes.m, Vector3::z());
+    }
+
+    #[test]
+    fn aperture_radiates_forward() {
+        let physical_constants = PhysicalConstants::default();
+        let impedance = physical_constants.vacuum_impedance();
+        let elements = FeedKind::CosineAperture {
+            width: 4.0,
+            height: 2.0,
+        }
+        .expand(&CELL_SIZE, &physical_constants);
+        assert_eq!(elements.len(), 8);
+
+        let normal = Vector3::z();
+        for element in elements {
+            // J = n x H and M = -n x E, so these are the aperture's fields
+            let e = normal.cross(&element.amplitudes.m);
+            let h = element.amplitudes.j.cross(&normal);
+
+            // the Huygens source is a plane wave towards +z, so its backward
+            // radiation cancels
+            let poynting = e.cross(&h);
+            assert!(poynting.z > 0.0);
+            assert!(poynting.xy().norm() < 1e-12 * poynting.z);
+            assert!((e.norm() - impedance * h.norm()).abs() < 1e-9 * e.norm());
+            assert!(e.y > 0.0, "not polarized along +y");
+        }
+    }
+}
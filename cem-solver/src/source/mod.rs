@@ -0,0 +1,222 @@
+pub mod feed;
+
+use std::{
+    f64::consts::TAU,
+    fmt::Debug,
+    sync::Arc,
+};
+
+use nalgebra::Vector3;
+
+use crate::material::UnitConversion;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SourceValues {
+    pub j: Vector3<f64>,
+    pub m: Vector3<f64>,
+}
+
+pub trait SourceFunction: Debug + Send + Sync + 'static {
+    type Output;
+
+    fn evaluate(&self, time: f64) -> Self::Output;
+}
+
+pub trait ScalarSourceFunctionExt: SourceFunction<Output = f64> {
+    fn with_amplitudes(self, j: Vector3<f64>, m: Vector3<f64>) -> WithAmplitudes<Self>
+    where
+        Self: Sized,
+    {
+        WithAmplitudes {
+            amplitude: SourceValues { j, m },
+            inner: self,
+        }
+    }
+}
+
+impl<T> ScalarSourceFunctionExt for T where T: SourceFunction<Output = f64> {}
+
+impl<F> SourceFunction for Arc<F>
+where
+    F: SourceFunction + ?Sized,
+{
+    type Output = F::Output;
+
+    fn evaluate(&self, time: f64) -> Self::Output {
+        (**self).evaluate(time)
+    }
+}
+
+//pub trait SourceFunctionExt: SourceFunction {}
+//impl<T> SourceFunctionExt for T where T: SourceFunction {}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GaussianPulse {
+    pub time: f64,
+    pub duration: f64,
+}
+
+impl GaussianPulse {
+    pub fn new(time: f64, duration: f64) -> Self {
+        Self { time, duration }
+    }
+}
+
+impl SourceFunction for GaussianPulse {
+    type Output = f64;
+
+    fn evaluate(&self, time: f64) -> f64 {
+        (-((time - self.time) / self.duration).powi(2)).exp()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ContinousWave {
+    pub phase: f64,
+    pub frequency: f64,
+}
+
+impl ContinousWave {
+    pub fn new(phase: f64, frequency: f64) -> Self {
+        Self { phase, frequency }
+    }
+}
+
+impl SourceFunction for ContinousWave {
+    type Output = f64;
+
+    fn evaluate(&self, time: f64) -> f64 {
+        (TAU * self.frequency * time + self.phase).cos()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct WithAmplitudes<F> {
+    pub amplitude: SourceValues,
+    pub inner: F,
+}
+
+impl<F> SourceFunction for WithAmplitudes<F>
+where
+    F: SourceFunction<Output = f64>,
+{
+    type Output = SourceValues;
+
+    fn evaluate(&self, time: f64) -> Self::Output {
+        let value = self.inner.evaluate(time);
+        SourceValues {
+            j: self.amplitude.j * value,
+            m: self.amplitude.m * value,
+        }
+    }
+}
+
+/// Stretches a source function in time, e.g. when converting between unit
+/// systems.
+///
+/// The result is `inner` evaluated at `time / time_scale`.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeScaled<F> {
+    pub time_scale: f64,
+    pub inner: F,
+}
+
+impl<F> SourceFunction for TimeScaled<F>
+where
+    F: SourceFunction,
+{
+    type Output = F::Output;
+
+    fn evaluate(&self, time: f64) -> Self::Output {
+        self.inner.evaluate(time / self.time_scale)
+    }
+}
+
+/// Delays a source function.
+///
+/// The result is `inner` evaluated at `time - delay`.
+#[derive(Clone, Copy, Debug)]
+pub struct Delayed<F> {
+    pub delay: f64,
+    pub inner: F,
+}
+
+impl<F> SourceFunction for Delayed<F>
+where
+    F: SourceFunction,
+{
+    type Output = F::Output;
+
+    fn evaluate(&self, time: f64) -> Self::Output {
+        self.inner.evaluate(time - self.delay)
+    }
+}
+
+/// Scales a scalar source function, e.g. the waveform of a feed.
+#[derive(Clone, Copy, Debug)]
+pub struct Scaled<F> {
+    pub scale: f64,
+    pub inner: F,
+}
+
+impl<F> SourceFunction for Scaled<F>
+where
+    F: SourceFunction<Output = f64>,
+{
+    type Output = f64;
+
+    fn evaluate(&self, time: f64) -> f64 {
+        self.scale * self.inner.evaluate(time)
+    }
+}
+
+/// Scales the current densities of a source function.
+#[derive(Clone, Copy, Debug)]
+pub struct ScaledAmplitudes<F> {
+    pub j: f64,
+    pub m: f64,
+    pub inner: F,
+}
+
+impl<F> SourceFunction for ScaledAmplitudes<F>
+where
+    F: SourceFunction<Output = SourceValues>,
+{
+    type Output = SourceValues;
+
+    fn evaluate(&self, time: f64) -> Self::Output {
+        let value = self.inner.evaluate(time);
+        SourceValues {
+            j: value.j * self.j,
+            m: value.m * self.m,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "bevy_ecs", derive(bevy_ecs::component::Component))]
+pub struct Source(pub Arc<dyn SourceFunction<Output = SourceValues>>);
+
+impl Source {
+    /// Wraps the source function, so that it produces the same fields in
+    /// another unit system.
+    pub fn convert_units(&self, conversion: &UnitConversion) -> Self {
+        Self::from(TimeScaled {
+            time_scale: conversion.time,
+            inner: ScaledAmplitudes {
+                j: conversion.electric_current_density,
+                m: conversion.magnetic_current_density,
+                inner: self.0.clone(),
+            },
+        })
+    }
+}
+
+impl<F> From<F> for Source
+where
+    F: SourceFunction<Output = SourceValues>,
+{
+    fn from(value: F) -> Self {
+        Source(Arc::new(value))
+    }
+}
@@ -0,0 +1,136 @@
+//! Stepping in time slices.
+//!
+//! [`SolverInstance::step_for`] steps a state until a time slice has elapsed,
+//! and then returns control to the caller, e.g. to report progress or do
+//! other work. A [`CancellationToken`] can end the slice early from another
+//! thread. Both are only checked between two steps, so a state is never left
+//! in the middle of an update.
+//!
+//! [`SolverInstance::step_for`]: crate::SolverInstance::step_for
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+    },
+    time::Duration,
+};
+
+/// Cancels stepping from another thread.
+///
+/// Clones share the same flag. Once cancelled, a token stays cancelled.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// What happened during a time slice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StepSlice {
+    /// Number of times the step function was called
+    pub steps: usize,
+
+    /// Time spent stepping
+    pub elapsed: Duration,
+
+    pub end: SliceEnd,
+}
+
+/// Why a time slice ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SliceEnd {
+    /// The time slice has elapsed.
+    Elapsed,
+
+    /// The [`CancellationToken`] was cancelled.
+    Cancelled,
+
+    /// The step function returned [`ControlFlow::Break`].
+    ///
+    /// [`ControlFlow::Break`]: std::ops::ControlFlow::Break
+    Stopped,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ops::ControlFlow,
+        time::Duration,
+    };
+
+    use nalgebra::Vector3;
+
+    use crate::{
+        SolverInstance,
+        UpdatePass,
+        fdtd::{
+            cpu::FdtdCpuBackend,
+            test_util::{
+                vacuum_config,
+                vacuum_instance,
+            },
+        },
+        step::{
+            CancellationToken,
+            SliceEnd,
+        },
+    };
+
+    #[test]
+    fn slices_end_between_steps() {
+        let config = vacuum_config(Vector3::repeat(4.0));
+        let (instance, mut state) = vacuum_instance(FdtdCpuBackend::single_threaded(), &config);
+        let cancellation = CancellationToken::new();
+
+        // an empty slice still does one step
+        let slice = instance.step_for(
+            &mut state,
+            Duration::ZERO,
+            &cancellation,
+            |instance, state| {
+                instance.begin_update(state).finish();
+                ControlFlow::Continue(())
+            },
+        );
+        assert_eq!((slice.steps, slice.end), (1, SliceEnd::Elapsed));
+        assert_eq!(state.tick(), 1);
+
+        let slice = instance.step_for(
+            &mut state,
+            Duration::from_secs(60),
+            &cancellation,
+            |instance, state| {
+                instance.begin_update(state).finish();
+                if state.tick() == 5 {
+                    cancellation.cancel();
+                }
+                ControlFlow::Continue(())
+            },
+        );
+        assert_eq!((slice.steps, slice.end), (4, SliceEnd::Cancelled));
+        assert_eq!(state.tick(), 5);
+
+        // a cancelled token doesn't step at all
+        let slice = instance.step_for(&mut state, Duration::ZERO, &cancellation, |_, _| {
+            ControlFlow::Continue(())
+        });
+        assert_eq!((slice.steps, slice.end), (0, SliceEnd::Cancelled));
+    }
+}
@@ -0,0 +1,372 @@
+//! Golden tests for the WGSL kernels of the GPU backend.
+//!
+//! Each test runs a tiny lattice on the CPU backend and on every kernel variant
+//! of the GPU backend, checks that the GPU fields match the CPU fields, and
+//! checks the fields against an analytic solution where there is one.
+//!
+//! These need a GPU adapter (a software adapter like lavapipe works too), so
+//! they only run with `cargo test -p cem-solver --features gpu-tests`.
+
+use cem_solver::{
+    DomainDescription,
+    Field,
+    FieldComponent,
+    FieldView,
+    SolverBackend,
+    SolverInstance,
+    Time,
+    UpdatePass,
+    UpdatePassForcing,
+    fdtd::{
+        FdtdSolverConfig,
+        Resolution,
+        SpatialOrder,
+        cpu::FdtdCpuBackend,
+        pml::PmlCoefficients,
+        wgpu::{
+            FdtdWgpuBackend,
+            FieldStorage,
+            UpdateKernel,
+        },
+    },
+    material::{
+        Material,
+        PhysicalConstants,
+    },
+    source::SourceValues,
+};
+use cem_util::wgpu::buffer::StagingPool;
+use nalgebra::{
+    Point3,
+    Vector3,
+};
+
+/// Largest difference to the reference, relative to the reference's largest
+/// value. The GPU kernels compute in single precision.
+const TOLERANCE: f64 = 1e-4;
+
+const VARIANTS: [(FieldStorage, UpdateKernel); 3] = [
+    (FieldStorage::Buffer, UpdateKernel::Direct),
+    (FieldStorage::Buffer, UpdateKernel::Tiled),
+    (FieldStorage::Texture, UpdateKernel::Direct),
+];
+
+/// Current source along z with a differentiated gaussian pulse.
+///
+/// The pulse has no DC component, so it doesn't leave a static field at the
+/// source.
+#[derive(Clone, Copy, Debug)]
+struct Pulse {
+    point: Point3<usize>,
+    delay: f64,
+    width: f64,
+}
+
+impl Pulse {
+    fn current(&self, time: f64) -> f64 {
+        let t = (time - self.delay) / self.width;
+        -t * (-0.5 * t * t).exp()
+    }
+}
+
+struct Scenario<D> {
+    config: FdtdSolverConfig,
+    domain: D,
+    pulse: Pulse,
+
+    /// Ticks at which the fields are read, in ascending order
+    snapshots: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct Fields {
+    points: Vec<Point3<usize>>,
+    e: Vec<Vector3<f64>>,
+    h: Vec<Vector3<f64>>,
+}
+
+impl Fields {
+    fn new<I>(instance: &I, state: &I::State) -> Self
+    where
+        I: Field<Point3<usize>>,
+    {
+        let e = instance.field(state, .., FieldComponent::E);
+        let h = instance.field(state, .., FieldComponent::H);
+        Self {
+            points: e.iter().map(|(point, _)| point).collect(),
+            e: e.iter().map(|(_, value)| value).collect(),
+            h: h.iter().map(|(_, value)| value).collect(),
+        }
+    }
+}
+
+/// 1D lattice along x with `c dt = dx`.
+///
+/// With this time step the 1D update has no numerical dispersion, so a pulse
+/// moves exactly one cell per step.
+fn line(cells: usize) -> FdtdSolverConfig {
+    FdtdSolverConfig::new(
+        Vector3::new(cells as f64, 0.0, 0.0),
+        Resolution {
+            spatial: Vector3::repeat(1.0),
+            temporal: 1.0,
+        },
+    )
+    .with_physical_constants(PhysicalConstants::REDUCED)
+}
+
+fn line_pulse(x: usize) -> Pulse {
+    Pulse {
+        point: Point3::new(x, 0, 0),
+        delay: 15.0,
+        width: 3.0,
+    }
+}
+
+fn wgpu_backends() -> Vec<(String, FdtdWgpuBackend)> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default().with_env());
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .expect("the gpu tests need a GPU adapter");
+    let (device, queue) =
+        pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default())).unwrap();
+    let staging_pool = StagingPool::new(
+        wgpu::BufferSize::new(0x10000).unwrap(),
+        "fdtd gpu tests staging",
+    );
+
+    VARIANTS
+        .into_iter()
+        .map(|(field_storage, update_kernel)| {
+            let backend = FdtdWgpuBackend::new(device.clone(), queue.clone(), staging_pool.clone())
+                .with_field_storage(field_storage)
+                .with_update_kernel(update_kernel);
+            (format!("{field_storage:?}, {update_kernel:?}"), backend)
+        })
+        .collect()
+}
+
+fn run<B, D>(backend: &B, scenario: &Scenario<D>) -> Vec<Fields>
+where
+    B: SolverBackend<FdtdSolverConfig, Point3<usize>>,
+    B::Instance: Field<Point3<usize>> + 'static,
+    for<'a> <B::Instance as SolverInstance>::UpdatePass<'a>: UpdatePassForcing<Point3<usize>>,
+    D: DomainDescription<Point3<usize>> + Clone,
+{
+    let instance = backend
+        .create_instance(&scenario.config, scenario.domain.clone())
+        .unwrap();
+    let mut state = instance.create_state();
+
+    scenario
+        .snapshots
+        .iter()
+        .map(|&tick| {
+            while state.tick() < tick {
+                let current = scenario.pulse.current(state.time());
+                let mut update_pass = instance.begin_update(&mut state);
+                update_pass.set_forcing(
+                    &scenario.pulse.point,
+                    &SourceValues {
+                        j: Vector3::z() * current,
+                        m: Vector3::zeros(),
+                    },
+                );
+                update_pass.finish();
+            }
+            Fields::new(&instance, &state)
+        })
+        .collect()
+}
+
+/// Runs a scenario on the CPU backend and all GPU kernel variants, and checks
+/// that the GPU fields match the CPU fields.
+///
+/// Returns the snapshots of all backends, the CPU backend first.
+fn run_all<D>(scenario: &Scenario<D>) -> Vec<(String, Vec<Fields>)>
+where
+    D: DomainDescription<Point3<usize>> + Clone,
+{
+    let reference = run(&FdtdCpuBackend::single_threaded(), scenario);
+
+    let mut results = vec![];
+    for (label, backend) in wgpu_backends() {
+        let snapshots = run(&backend, scenario);
+        for ((tick, reference), snapshot) in
+            scenario.snapshots.iter().zip(&reference).zip(&snapshots)
+        {
+            assert_eq!(reference.points, snapshot.points, "{label}");
+            assert_close(
+                &format!("{label}, tick {tick}, E"),
+                &reference.e,
+                &snapshot.e,
+            );
+            assert_close(
+                &format!("{label}, tick {tick}, H"),
+                &reference.h,
+                &snapshot.h,
+            );
+        }
+        results.push((label, snapshots));
+    }
+
+    results.insert(0, ("cpu".to_owned(), reference));
+    results
+}
+
+fn max_norm(values: &[Vector3<f64>]) -> f64 {
+    values.iter().map(|value| value.norm()).fold(0.0, f64::max)
+}
+
+fn assert_close(label: &str, expected: &[Vector3<f64>], actual: &[Vector3<f64>]) {
+    assert_eq!(expected.len(), actual.len(), "{label}");
+    let scale = max_norm(expected);
+    let max_difference = expected
+        .iter()
+        .zip(actual)
+        .map(|(expected, actual)| (expected - actual).norm())
+        .fold(0.0, f64::max);
+    assert!(
+        max_difference <= TOLERANCE * scale,
+        "{label}: max difference {max_difference:.3e}, largest value {scale:.3e}"
+    );
+}
+
+#[test]
+fn plane_wave_moves_one_cell_per_step() {
+    let source = 32;
+    let shift = 20;
+    let scenario = Scenario {
+        config: line(128),
+        domain: |_: &Point3<usize>| Material::VACUUM,
+        pulse: line_pulse(source),
+        snapshots: vec![40, 40 + shift],
+    };
+
+    for (label, snapshots) in run_all(&scenario) {
+        let [before, after] = &snapshots[..]
+        else {
+            unreachable!()
+        };
+
+        // right of the source there's only the pulse moving to the right
+        let moved = &after.e[source + 1 + shift..];
+        assert_close(&label, &before.e[source + 1..128 - shift], moved);
+        assert!(max_norm(moved) > 0.1, "{label}: no pulse");
+    }
+}
+
+#[test]
+fn pec_wall_mirrors_inverted_pulse() {
+    // a PEC wall is equivalent to an inverted image source mirrored at the wall,
+    // so in front of the wall the field is the free field minus the mirrored free
+    // field.
+    let wall = 64;
+    let free = Scenario {
+        config: line(128),
+        domain: |_: &Point3<usize>| Material::VACUUM,
+        pulse: line_pulse(48),
+        snapshots: vec![40],
+    };
+    let pec = Scenario {
+        config: line(128),
+        domain: |point: &Point3<usize>| {
+            if point.x >= wall {
+                Material::PEC
+            }
+            else {
+                Material::VACUUM
+            }
+        },
+        pulse: line_pulse(48),
+        snapshots: vec![40],
+    };
+
+    for ((label, free), (_, pec)) in run_all(&free).iter().zip(&run_all(&pec)) {
+        let (free, pec) = (&free[0], &pec[0]);
+        let expected = (1..128)
+            .map(|x| {
+                if x < wall {
+                    free.e[x] - free.e[2 * wall - x]
+                }
+                else {
+                    Vector3::zeros()
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_close(label, &expected, &pec.e[1..]);
+        assert!(
+            max_norm(&pec.e[wall - 24..wall]) > 0.1,
+            "{label}: no reflected pulse"
+        );
+    }
+}
+
+#[test]
+fn kernels_match_cpu_in_3d() {
+    let center = Point3::new(6, 6, 6);
+    for spatial_order in [SpatialOrder::Second, SpatialOrder::Fourth] {
+        let scenario = Scenario {
+            config: FdtdSolverConfig::new(
+                Vector3::repeat(12.0),
+                Resolution {
+                    spatial: Vector3::repeat(1.0),
+                    temporal: 0.25,
+                },
+            )
+            .with_physical_constants(PhysicalConstants::REDUCED)
+            .with_spatial_order(spatial_order),
+            // a lossy dielectric half-space, so that the material coefficients are
+            // tested too
+            domain: |point: &Point3<usize>| {
+                if point.x < center.x {
+                    Material {
+                        relative_permittivity: 4.0,
+                        eletrical_conductivity: 0.1,
+                        ..Material::VACUUM
+                    }
+                }
+                else {
+                    Material::VACUUM
+                }
+            },
+            pulse: Pulse {
+                point: center,
+                delay: 3.0,
+                width: 1.0,
+            },
+            snapshots: vec![20, 40],
+        };
+        run_all(&scenario);
+    }
+}
+
+/// Vacuum with a PML in the last cell.
+#[derive(Clone, Copy, Debug)]
+struct PmlAtEnd;
+
+impl DomainDescription<Point3<usize>> for PmlAtEnd {
+    fn material(&mut self, _point: &Point3<usize>) -> Material {
+        Material::VACUUM
+    }
+
+    fn pml(&mut self, point: &Point3<usize>) -> Option<PmlCoefficients> {
+        (point.x == 15).then(|| PmlCoefficients {
+            b: Vector3::x(),
+            c: Vector3::x(),
+        })
+    }
+}
+
+#[test]
+fn pml_is_ignored_like_on_the_cpu() {
+    // neither backend implements the PML yet, so the GPU kernels must use the
+    // same boundaries as the CPU backend
+    let scenario = Scenario {
+        config: line(16),
+        domain: PmlAtEnd,
+        pulse: line_pulse(4),
+        snapshots: vec![30],
+    };
+    run_all(&scenario);
+}
@@ -58,15 +58,30 @@ pub fn normalize_index_bounds(range: impl RangeBounds<usize>, len: usize) -> Ran
     Range { start, end }
 }
 
+/// Number of buffers that were still shared when writing, that are kept to be
+/// reused later.
+const MAX_SPARE_SHARED_BUFFERS: usize = 4;
+
+/// A value that is shared with readers through an [`Arc`], and overwritten in
+/// place when possible.
+///
+/// If readers still hold the value when it's written, another buffer is used,
+/// and the shared one is kept as a spare until the readers drop it. Every write
+/// increments a generation counter, so that derived data can be cached until
+/// the contents change.
 #[derive(Debug, Default)]
 pub struct ReusableSharedBuffer<T> {
     value: Arc<T>,
+    spares: Vec<Arc<T>>,
+    generation: u64,
 }
 
 impl<T> ReusableSharedBuffer<T> {
     pub fn new(value: T) -> Self {
         Self {
             value: Arc::new(value),
+            spares: vec![],
+            generation: 0,
         }
     }
 
@@ -74,13 +89,36 @@ impl<T> ReusableSharedBuffer<T> {
         self.value.clone()
     }
 
+    /// Number of writes so far.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns the value for writing.
+    ///
+    /// The value might contain the contents of an older write.
     pub fn write(&mut self, allocate: impl FnOnce() -> T) -> ReusableSharedBufferGuard<'_, T> {
         let mut reallocated = false;
         if Arc::get_mut(&mut self.value).is_none() {
-            self.value = Arc::new(allocate());
-            reallocated = true;
+            let value = if let Some(index) = self
+                .spares
+                .iter_mut()
+                .position(|spare| Arc::get_mut(spare).is_some())
+            {
+                self.spares.swap_remove(index)
+            }
+            else {
+                reallocated = true;
+                Arc::new(allocate())
+            };
+
+            let shared = std::mem::replace(&mut self.value, value);
+            if self.spares.len() < MAX_SPARE_SHARED_BUFFERS {
+                self.spares.push(shared);
+            }
         }
 
+        self.generation += 1;
         let value = Arc::get_mut(&mut self.value).unwrap();
 
         ReusableSharedBufferGuard { value, reallocated }
@@ -112,3 +150,40 @@ impl<'a, T> DerefMut for ReusableSharedBufferGuard<'a, T> {
         self.value
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::ReusableSharedBuffer;
+
+    #[test]
+    fn shared_buffers_are_reused_once_dropped() {
+        let mut buffer = ReusableSharedBuffer::new(vec![1]);
+
+        let reader = buffer.get();
+        {
+            let mut guard = buffer.write(Vec::new);
+            assert!(guard.reallocated());
+            guard.push(2);
+        }
+        assert_eq!(*reader, vec![1]);
+
+        // the first buffer is still read, so the second one is written in place
+        {
+            let guard = buffer.write(Vec::new);
+            assert!(!guard.reallocated());
+            assert_eq!(*guard, vec![2]);
+        }
+
+        // once the reader is done, its buffer is reused instead of allocating
+        let reader_2 = buffer.get();
+        drop(reader);
+        {
+            let guard = buffer.write(Vec::new);
+            assert!(!guard.reallocated());
+            assert_eq!(*guard, vec![1]);
+        }
+        drop(reader_2);
+
+        assert_eq!(buffer.generation(), 3);
+    }
+}
@@ -182,6 +182,10 @@ where
             command_encoder,
         }
     }
+
+    pub fn provider(&self) -> &Provider {
+        &self.provider
+    }
 }
 
 impl<Provider, Device, Encoder> WriteStaging for WriteStagingTransaction<Provider, Device, Encoder>
@@ -434,10 +438,17 @@ pub struct StagingPool {
     inner: Arc<ChunkPoolInner>,
 }
 
+/// Chunks grow up to this multiple of the pool's minimum chunk size.
+const MAX_CHUNK_SIZE_FACTOR: u64 = 64;
+
+/// Number of consecutive belts that staged at most a quarter of the chunk size,
+/// after which the chunk size is halved.
+const SHRINK_AFTER_QUIET_BELTS: usize = 120;
+
 #[derive(Debug)]
 struct ChunkPoolInner {
     /// Minimum size of an individual chunk
-    chunk_size: wgpu::BufferSize,
+    min_chunk_size: wgpu::BufferSize,
     chunk_label: Cow<'static, str>,
     state: RwLock<ChunkPoolState>,
 }
@@ -451,6 +462,56 @@ struct ChunkPoolState {
     total_allocated_count: usize,
     total_allocated_bytes: u64,
     total_staged_bytes: u64,
+    total_reused_count: usize,
+    total_stall_count: usize,
+
+    /// Size of newly allocated chunks. This adapts to the bytes staged per
+    /// belt, see [`ChunkPoolState::adapt_chunk_size`].
+    chunk_size: u64,
+
+    /// Number of consecutive belts that staged little compared to the chunk
+    /// size.
+    quiet_belts: usize,
+}
+
+impl ChunkPoolState {
+    /// Adapts the chunk size to the bytes a belt staged.
+    ///
+    /// If a belt staged more than fits into one chunk, the chunk size grows
+    /// right away, so that heavy upload frames need fewer allocations. It only
+    /// shrinks after a while of belts staging much less, so that occasional
+    /// heavy frames don't cause allocations every time.
+    fn adapt_chunk_size(&mut self, staged_bytes: u64, min_chunk_size: u64) {
+        let max_chunk_size = min_chunk_size * MAX_CHUNK_SIZE_FACTOR;
+        let target = staged_bytes
+            .next_power_of_two()
+            .clamp(min_chunk_size, max_chunk_size);
+
+        if target > self.chunk_size {
+            tracing::debug!(
+                from = self.chunk_size,
+                to = target,
+                "growing staging chunk size"
+            );
+            self.chunk_size = target;
+            self.quiet_belts = 0;
+        }
+        else if staged_bytes <= self.chunk_size / 4 && self.chunk_size > min_chunk_size {
+            self.quiet_belts += 1;
+            if self.quiet_belts >= SHRINK_AFTER_QUIET_BELTS {
+                self.chunk_size = (self.chunk_size / 2).max(min_chunk_size);
+                self.quiet_belts = 0;
+
+                // give back the memory of chunks that are bigger than we need now
+                let chunk_size = self.chunk_size;
+                self.free_chunks
+                    .retain(|chunk| chunk.buffer.size() <= chunk_size);
+            }
+        }
+        else {
+            self.quiet_belts = 0;
+        }
+    }
 }
 
 impl Default for StagingPool {
@@ -460,12 +521,19 @@ impl Default for StagingPool {
 }
 
 impl StagingPool {
+    /// Creates a pool with chunks of at least `chunk_size` bytes.
+    ///
+    /// The size of new chunks adapts to how much is staged per belt, between
+    /// `chunk_size` and a multiple of it.
     pub fn new(chunk_size: wgpu::BufferSize, chunk_label: impl Into<Cow<'static, str>>) -> Self {
         Self {
             inner: Arc::new(ChunkPoolInner {
-                chunk_size,
+                min_chunk_size: chunk_size,
                 chunk_label: chunk_label.into(),
-                state: RwLock::new(Default::default()),
+                state: RwLock::new(ChunkPoolState {
+                    chunk_size: chunk_size.get(),
+                    ..Default::default()
+                }),
             }),
         }
     }
@@ -483,6 +551,9 @@ impl StagingPool {
             total_allocation_count: state.total_allocated_count,
             total_allocation_bytes: state.total_allocated_bytes,
             total_staged_bytes: state.total_staged_bytes,
+            total_reuse_count: state.total_reused_count,
+            total_stall_count: state.total_stall_count,
+            chunk_size: state.chunk_size,
         }
     }
 }
@@ -496,6 +567,8 @@ pub struct WriteStagingBelt {
     /// Note: if the WriteStagingBelt is dropped while it has active chunks
     /// (i.e. finish wasn't called), the chunks will not be reused.
     active_chunks: Vec<Chunk>,
+
+    info: WriteStagingBeltInfo,
 }
 
 impl WriteStagingBelt {
@@ -507,9 +580,15 @@ impl WriteStagingBelt {
         Self {
             pool,
             active_chunks: vec![],
+            info: Default::default(),
         }
     }
 
+    /// What was staged with this belt since it was created or last committed.
+    pub fn info(&self) -> WriteStagingBeltInfo {
+        self.info
+    }
+
     fn discard_impl(&mut self) {
         let mut state = self.pool.inner.state.write();
         state.in_flight_count -= self.active_chunks.len();
@@ -536,19 +615,30 @@ impl StagingBufferProvider for WriteStagingBelt {
             .position(|chunk| chunk.can_allocate(size, alignment.get()))
             .unwrap_or_else(|| {
                 let mut state = self.pool.inner.state.write();
-                state.in_flight_count += 1;
 
                 let chunk = if let Some(index) = state
                     .free_chunks
                     .iter()
                     .position(|chunk| chunk.can_allocate(size, alignment.get()))
                 {
+                    state.in_flight_count += 1;
+                    state.total_reused_count += 1;
+                    self.info.reused_chunks += 1;
                     state.free_chunks.swap_remove(index)
                 }
                 else {
-                    let size = self.pool.inner.chunk_size.get().max(size.get());
+                    // chunks that are still in flight could have been reused, if the GPU
+                    // was done with them.
+                    if state.in_flight_count > 0 {
+                        state.total_stall_count += 1;
+                        self.info.stalls += 1;
+                    }
+
+                    let size = state.chunk_size.max(size.get());
+                    state.in_flight_count += 1;
                     state.total_allocated_count += 1;
                     state.total_allocated_bytes += size;
+                    self.info.allocated_chunks += 1;
                     drop(state);
 
                     Chunk {
@@ -569,6 +659,7 @@ impl StagingBufferProvider for WriteStagingBelt {
 
         let chunk = &mut self.active_chunks[chunk_index];
         let allocation_offset = chunk.allocate(size, alignment.get());
+        self.info.staged_bytes += size.get();
 
         let staging_buffer_slice = chunk
             .buffer
@@ -582,6 +673,13 @@ impl StagingBufferProvider for WriteStagingBelt {
             chunk.buffer.unmap();
         }
 
+        let info = std::mem::take(&mut self.info);
+        self.pool
+            .inner
+            .state
+            .write()
+            .adapt_chunk_size(info.staged_bytes, self.pool.inner.min_chunk_size.get());
+
         let inflight_chunks =
             InflightChunks::new(self.pool.clone(), std::mem::take(&mut self.active_chunks));
 
@@ -592,6 +690,7 @@ impl StagingBufferProvider for WriteStagingBelt {
     }
 
     fn discard(&mut self) {
+        self.info = Default::default();
         if !self.active_chunks.is_empty() {
             self.discard_impl()
         }
@@ -723,6 +822,45 @@ pub struct StagingPoolInfo {
     pub total_allocation_count: usize,
     pub total_allocation_bytes: u64,
     pub total_staged_bytes: u64,
+
+    /// Number of chunks that were taken from the free list instead of being
+    /// allocated.
+    pub total_reuse_count: usize,
+
+    /// Number of chunks that were allocated while other chunks were in flight.
+    pub total_stall_count: usize,
+
+    /// Current size of newly allocated chunks.
+    pub chunk_size: u64,
+}
+
+/// What was staged with a [`WriteStagingBelt`], usually in one frame.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WriteStagingBeltInfo {
+    /// Bytes staged, without alignment padding.
+    pub staged_bytes: u64,
+
+    /// Chunks taken from the pool's free list.
+    pub reused_chunks: usize,
+
+    /// Chunks that had to be allocated.
+    pub allocated_chunks: usize,
+
+    /// Allocations of new chunks while the pool had chunks in flight.
+    ///
+    /// The belt never waits for the GPU, but these indicate backpressure: the
+    /// chunks are returned slower than new ones are needed.
+    pub stalls: usize,
+}
+
+impl WriteStagingBeltInfo {
+    /// Fraction of the belt's chunks that were reused.
+    ///
+    /// Returns `None` if the belt didn't need any chunks.
+    pub fn reuse_rate(&self) -> Option<f64> {
+        let total = self.reused_chunks + self.allocated_chunks;
+        (total > 0).then(|| self.reused_chunks as f64 / total as f64)
+    }
 }
 
 #[derive(Debug)]
@@ -756,3 +894,52 @@ impl Chunk {
         self.offset = 0;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::wgpu::buffer::staging::write::{
+        ChunkPoolState,
+        MAX_CHUNK_SIZE_FACTOR,
+        SHRINK_AFTER_QUIET_BELTS,
+    };
+
+    const MIN_CHUNK_SIZE: u64 = 0x1000;
+
+    fn state() -> ChunkPoolState {
+        ChunkPoolState {
+            chunk_size: MIN_CHUNK_SIZE,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn chunk_size_grows_with_heavy_belts() {
+        let mut state = state();
+
+        state.adapt_chunk_size(3 * MIN_CHUNK_SIZE, MIN_CHUNK_SIZE);
+        assert_eq!(state.chunk_size, 4 * MIN_CHUNK_SIZE);
+
+        state.adapt_chunk_size(u64::MAX / 4, MIN_CHUNK_SIZE);
+        assert_eq!(state.chunk_size, MAX_CHUNK_SIZE_FACTOR * MIN_CHUNK_SIZE);
+    }
+
+    #[test]
+    fn chunk_size_shrinks_after_quiet_belts() {
+        let mut state = state();
+        state.adapt_chunk_size(8 * MIN_CHUNK_SIZE, MIN_CHUNK_SIZE);
+
+        for _ in 1..SHRINK_AFTER_QUIET_BELTS {
+            state.adapt_chunk_size(MIN_CHUNK_SIZE, MIN_CHUNK_SIZE);
+        }
+        assert_eq!(state.chunk_size, 8 * MIN_CHUNK_SIZE);
+
+        state.adapt_chunk_size(MIN_CHUNK_SIZE, MIN_CHUNK_SIZE);
+        assert_eq!(state.chunk_size, 4 * MIN_CHUNK_SIZE);
+
+        // never below the minimum
+        for _ in 0..10 * SHRINK_AFTER_QUIET_BELTS {
+            state.adapt_chunk_size(0, MIN_CHUNK_SIZE);
+        }
+        assert_eq!(state.chunk_size, MIN_CHUNK_SIZE);
+    }
+}
@@ -1,16 +1,14 @@
 use std::{
+    error::Error,
     fs::File,
     io::BufReader,
     path::PathBuf,
 };
 
 use clap::Parser;
-use color_eyre::eyre::Error;
 use nec_file::NecFile;
 
-fn main() -> Result<(), Error> {
-    color_eyre::install()?;
-
+fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
     let reader = BufReader::new(File::open(&args.file)?);
     let nec = NecFile::from_reader(reader)?;
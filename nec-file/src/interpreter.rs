@@ -28,9 +28,8 @@ use crate::{
 };
 
 #[derive(Debug, thiserror::Error)]
-#[error("NEC error")]
 pub enum Error {
-    #[error("NEC parser error")]
+    #[error(transparent)]
     Parser(#[from] super::parser::Error),
     #[error("Invalid card type {card_type} in {section:?} section")]
     InvalidCardType { section: Section, card_type: String },
@@ -16,8 +16,8 @@ use crate::card::{
 };
 
 #[derive(Debug, thiserror::Error)]
-#[error("NEC error")]
 pub enum Error {
+    #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error("Unexpected end of file")]
     UnexpectedEnd {
@@ -34,9 +34,10 @@ pub enum Error {
         section: Section,
     },
 
-    #[error("Invalid parameters")]
+    #[error("Invalid parameter {value:?} in {section:?} section")]
     InvalidParameter {
-        // todo some info?
+        section: Section,
+        value: String,
     },
     #[error("Invalid patch shape: {value}")]
     InvalidPatchShape {
@@ -344,16 +345,15 @@ impl<'a> TokenReader<'a> {
     where
         T: FromStr,
     {
-        self.read_token()
-            .ok_or(Error::UnexpectedEnd {
+        let token = self.read_token().ok_or(Error::UnexpectedEnd {
+            section: self.section,
+        })?;
+        token.parse::<T>().map_err(|_error| {
+            Error::InvalidParameter {
                 section: self.section,
-            })?
-            .parse::<T>()
-            .map_err(|_error| {
-                Error::InvalidParameter {
-                    // todo
-                }
-            })
+                value: token.to_owned(),
+            }
+        })
     }
 
     fn read_array<const N: usize, T>(&mut self) -> Result<[T; N], Error>